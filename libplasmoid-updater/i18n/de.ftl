@@ -0,0 +1,223 @@
+# German translations of plasmoid-updater's terminal output.
+#
+# Message IDs match the tr!() calls in the sources; the English text there is
+# used for anything missing here. Keep the IDs in the same order as es.ftl.
+
+## Checking
+
+fetching = Komponentendaten werden abgerufen
+no-updates = keine Aktualisierungen verfügbar
+nothing-to-update = nichts zu aktualisieren
+no-components = keine Komponenten installiert
+count-updates =
+    { $count ->
+        [one] { $count } Aktualisierung verfügbar.
+       *[other] { $count } Aktualisierungen verfügbar.
+    }
+count-installed =
+    { $count ->
+        [one] { $count } installierte Komponente.
+       *[other] { $count } installierte Komponenten.
+    }
+held = zurückgehalten (bekannt fehlerhaft): { $name } { $version } — { $reason }
+shadowed = Warnung: { $name } in { $path } verdeckt die Systemkopie in { $system }
+author-changed = Warnung: der Store-Autor von { $name } hat von { $previous } zu { $author } gewechselt
+companions-missing = Hinweis: { $name } verwendet auch { $names }, die nicht installiert sind
+companion-installed = { $name } { $version } für { $theme } installiert
+file-conflict = Warnung: { $components } schreiben nach { $path }; das zuletzt installierte gewinnt
+skip-distro-owned = { $name } wird übersprungen: von einem Distributionspaket installiert
+skip-severity = zur Prüfung zurückgestellt: { $name } { $version } ({ $severity ->
+        [patch] Patch-Update
+        [minor] Minor-Update
+       *[major] Major-Update
+    })
+skip-author = zur Prüfung zurückgestellt: { $name } (Store-Autor von { $previous } zu { $author } gewechselt)
+catalog-incomplete = Warnung: Store-Ergebnisse unvollständig, { $failed }/{ $pages } Seiten fehlgeschlagen, Ergebnisse können fehlen
+store-usage = Store-Nutzung: { $usage }
+changes-more = … und { $more } weitere
+
+## Tables
+
+column-name = NAME
+column-current = AKTUELL
+column-available = VERFÜGBAR
+column-id = ID
+column-size = GRÖSSE
+column-type = TYP
+column-version = VERSION
+column-count = ANZAHL
+column-from = VON
+column-to = AUF
+column-time = ZEIT
+column-step = SCHRITT
+column-error = FEHLER
+column-registry = REGISTRIERUNG
+column-issue = PROBLEM
+column-severity = STUFE
+detail-author = Autor
+detail-description = Beschreibung
+detail-license = Lizenz
+detail-website = Website
+detail-icon = Symbol
+detail-path = Pfad
+detail-size = Größe
+detail-not-updated = nicht aktualisiert
+usage-installed = installiert
+usage-backups = Sicherungen
+usage-versions = Versionen
+usage-cache = Cache
+
+## Prompts
+
+prompt-select =
+    { $count ->
+        [one] { $count } Aktualisierung verfügbar, zum Anwenden auswählen:
+       *[other] { $count } Aktualisierungen verfügbar, zum Anwenden auswählen:
+    }
+prompt-variant = { $name } bietet mehrere Downloads an, bitte einen wählen:
+prompt-restart = { $actions } jetzt?
+prompt-restart-and = und
+prompt-in-use =
+    { $count ->
+        [one] { $names } ist auf dem Desktop oder in einer Leiste und kann bis zum Neustart von plasmashell fehlerhaft dargestellt werden. Trotzdem aktualisieren?
+       *[other] { $names } sind auf dem Desktop oder in Leisten und können bis zum Neustart von plasmashell fehlerhaft dargestellt werden. Trotzdem aktualisieren?
+    }
+prompt-author = Der Store-Autor von { $name } hat von { $previous } zu { $author } gewechselt. { $version } trotzdem installieren?
+prompt-companion-updates = { $theme } verwendet { $names }, für die es ebenfalls Updates gibt. Auch aktualisieren?
+prompt-companion-install = { $theme } verwendet { $names }, die nicht installiert sind. Aus dem KDE Store installieren?
+prompt-recover = Ein früherer Lauf wurde bei der Installation von { $names } unterbrochen. Wiederherstellen?
+prompt-crash-rollback = plasmashell ist nach dem Neustart abgestürzt ({ $reason }). { $names } zurücksetzen?
+
+## Restart actions
+
+restart-none = nichts tun
+restart-font-cache = den Schriftarten-Cache aktualisieren
+restart-kwin = KWin neu konfigurieren
+restart-look-and-feel = die aktualisierten Designs erneut anwenden
+restart-plasmashell = plasmashell neu starten
+watching-plasmashell = plasmashell wird { $seconds } s lang beobachtet …
+
+## Updating
+
+stage-backup = Sichern
+stage-download = Herunterladen
+stage-extract = Entpacken
+stage-install = Installieren
+task-done = Fertig
+task-failed = Fehlgeschlagen
+task-updated = { $name } (aktualisiert)
+task-failed-line = { $name } (fehlgeschlagen)
+task-retrying = { $name } (neuer Versuch)
+summary = Zusammenfassung: { $succeeded } erfolgreich, { $failed } fehlgeschlagen, { $skipped } übersprungen ({ $total } insgesamt)
+summary-unverified = { $count } nicht bestätigt
+summary-unhealthy = { $count } fehlerhaft
+summary-rolled-back = { $count } zurückgesetzt
+summary-retried = { $retried } nach erneutem Versuch
+summary-unreadable = (nicht lesbar)
+summary-unverified-line = nicht bestätigt: { $name } — erwartet { $expected }, gefunden { $actual }
+summary-unhealthy-line = fehlerhaft: { $name } — { $problems }
+summary-rolled-back-line = nach Absturz von plasmashell zurückgesetzt: { $name }
+restored = { $name } wiederhergestellt
+
+## Run reports
+
+run-header =
+    { $scope ->
+        [system] Systemlauf gestartet { $started }, Dauer { $took }
+       *[user] Benutzerlauf gestartet { $started }, Dauer { $took }
+    }
+run-failed = fehlgeschlagen:
+run-rolled-back = zurückgesetzt
+run-skipped = übersprungen
+run-then = dann
+run-afterwards = danach: { $actions }
+run-totals = { $updated } aktualisiert, { $failed } fehlgeschlagen, { $downloaded } heruntergeladen; Store-Nutzung: { $usage }
+no-runs = noch keine Aktualisierungsläufe aufgezeichnet
+
+## Commands
+
+skipped-outage = übersprungen: { $error }
+skip-immutable = Systemkomponenten werden übersprungen: sie sind auf { $system } schreibgeschützt
+interrupted =
+    { $count ->
+        [one] Warnung: eine Installation wurde unterbrochen, `plasmoid-updater recover` stellt sie wieder her
+       *[other] Warnung: { $count } Installationen wurden unterbrochen, `plasmoid-updater recover` stellt sie wieder her
+    }
+nothing-to-recover = nichts wiederherzustellen
+no-orphans = keine verwaisten Komponenten gefunden
+no-duplicates = keine doppelten Installationen gefunden
+duplicate = { $name }: { $version } in { $path } verdeckt { $system_version } in { $system_path }
+duplicate-removed = Benutzerkopie entfernt
+duplicate-redundant = die Benutzerkopie ist überflüssig; mit --apply entfernen
+duplicate-system-older = die Systemkopie ist älter; mit dem Paketmanager deinstallieren oder { $path } entfernen
+cache-empty = der Download-Cache ist leer
+cache-freed = { $mib } MiB freigegeben
+timer-installs = installiert Aktualisierungen
+timer-checks = sucht nach Aktualisierungen
+timer-every = alle { $interval }
+timer-installed = plasmoid-updater-check.timer in { $dir } installiert; er { $action } { $when }
+timer-hint = siehe `systemctl --user list-timers` und `journalctl --user -u plasmoid-updater-check`
+timer-none = kein Timer installiert
+timer-removed = plasmoid-updater-check.timer entfernt
+rated = { $component } mit { $score }/10 bewertet (https://store.kde.org/p/{ $content_id })
+apply-installed = installiert
+apply-updated = aktualisiert
+apply-removed = entfernt
+apply-unchanged = aktuell
+installed-file = { $name }{ $version } nach { $path } installiert
+installed-matched = dem KDE-Store-Eintrag { $id } zugeordnet; wird aktuell gehalten
+installed-unmatched = kein KDE-Store-Eintrag gefunden; erhält keine Aktualisierungen
+no-unregistered = keine unregistrierten Komponenten gefunden
+adopt-failed = { $name } konnte nicht übernommen werden: { $reason }
+no-kept-versions = von { $component } werden keine Versionen aufbewahrt (siehe keep_versions)
+downgraded = { $component } ist jetzt auf { $version }; plasmashell neu starten, um sie zu laden
+reinstalled = { $component } { $version } neu installiert (sha256 { $sha256 }); plasmashell neu starten, um es zu laden
+verify-no-record = { $name }: kein Installationsprotokoll zum Vergleichen
+verify-ok = { $name } { $version }: in Ordnung
+verify-changed = { $name } { $version }: { $modified } geändert, { $missing } fehlend, { $extra } zusätzlich
+verify-modified = geändert
+verify-missing = fehlt
+verify-extra = zusätzlich
+verify-unrecorded = { $count } Komponenten haben kein Installationsprotokoll zum Vergleichen
+info-type = Typ
+info-version = Version
+info-path = Pfad
+info-author = Autor
+info-license = Lizenz
+info-website = Webseite
+info-summary = Beschreibung
+info-registry = Registrierung
+info-content-id = Inhalts-ID
+info-released = erschienen
+info-not-registered = nicht registriert
+info-store = Store
+info-update-available = Update verfügbar
+info-up-to-date = aktuell
+info-latest = neueste
+info-download = Download
+info-changelog = Änderungen
+info-no-store-entry = kein passender Store-Eintrag
+info-backups = Sicherungen
+info-kept-versions = aufbewahrte Versionen
+info-kept-version = { $version }  (aufbewahrt { $date })
+info-package = installiertes Paket
+info-installed = installiert
+info-config = Konfiguration
+info-yes = ja
+info-no = nein
+info-excluded = ausgeschlossen
+info-groups = Gruppen
+explain-resolution = Zuordnung
+explain-held-back = zurückgehalten
+snapshot-saved = { $count } Store-Einträge in { $file } gespeichert
+registry-clean = die Registrierung ist konsistent
+registry-fixed =
+    { $count ->
+        [one] { $count } Registrierungsproblem behoben
+       *[other] { $count } Registrierungsprobleme behoben
+    }
+registry-issues =
+    { $count ->
+        [one] { $count } Registrierungsproblem gefunden, mit --fix beheben
+       *[other] { $count } Registrierungsprobleme gefunden, mit --fix beheben
+    }
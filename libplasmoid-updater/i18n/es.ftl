@@ -0,0 +1,223 @@
+# Spanish translations of plasmoid-updater's terminal output.
+#
+# Message IDs match the tr!() calls in the sources; the English text there is
+# used for anything missing here. Keep the IDs in the same order as de.ftl.
+
+## Checking
+
+fetching = Obteniendo datos de los componentes
+no-updates = no hay actualizaciones disponibles
+nothing-to-update = nada que actualizar
+no-components = no hay componentes instalados
+count-updates =
+    { $count ->
+        [one] { $count } actualización disponible.
+       *[other] { $count } actualizaciones disponibles.
+    }
+count-installed =
+    { $count ->
+        [one] { $count } componente instalado.
+       *[other] { $count } componentes instalados.
+    }
+held = retenida (con fallos conocidos): { $name } { $version } — { $reason }
+shadowed = aviso: { $name } en { $path } oculta la copia del sistema en { $system }
+author-changed = aviso: el autor de { $name } en la tienda cambió de { $previous } a { $author }
+companions-missing = nota: { $name } también usa { $names }, que no están instalados
+companion-installed = instalado { $name } { $version } para { $theme }
+file-conflict = aviso: { $components } escriben en { $path }; prevalece el último instalado
+skip-distro-owned = se omite { $name }: instalado por un paquete de la distribución
+skip-severity = pendiente de revisión: { $name } { $version } ({ $severity ->
+        [patch] actualización de parche
+        [minor] actualización menor
+       *[major] actualización mayor
+    })
+skip-author = pendiente de revisión: { $name } (el autor en la tienda cambió de { $previous } a { $author })
+catalog-incomplete = aviso: resultados de la tienda incompletos, fallaron { $failed }/{ $pages } páginas, pueden faltar resultados
+store-usage = uso de la tienda: { $usage }
+changes-more = … y { $more } más
+
+## Tables
+
+column-name = NOMBRE
+column-current = ACTUAL
+column-available = DISPONIBLE
+column-id = ID
+column-size = TAMAÑO
+column-type = TIPO
+column-version = VERSIÓN
+column-count = CANTIDAD
+column-from = DE
+column-to = A
+column-time = TIEMPO
+column-step = PASO
+column-error = ERROR
+column-registry = REGISTRO
+column-issue = PROBLEMA
+column-severity = NIVEL
+detail-author = autor
+detail-description = descripción
+detail-license = licencia
+detail-website = sitio web
+detail-icon = icono
+detail-path = ruta
+detail-size = tamaño
+detail-not-updated = no se actualiza
+usage-installed = instalado
+usage-backups = copias
+usage-versions = versiones
+usage-cache = caché
+
+## Prompts
+
+prompt-select =
+    { $count ->
+        [one] { $count } actualización disponible, elija cuáles aplicar:
+       *[other] { $count } actualizaciones disponibles, elija cuáles aplicar:
+    }
+prompt-variant = { $name } ofrece varias descargas, elija una:
+prompt-restart = ¿{ $actions } ahora?
+prompt-restart-and = y
+prompt-in-use =
+    { $count ->
+        [one] { $names } está en el escritorio o en un panel y puede verse mal hasta que plasmashell se reinicie. ¿Actualizar de todos modos?
+       *[other] { $names } están en el escritorio o en paneles y pueden verse mal hasta que plasmashell se reinicie. ¿Actualizar de todos modos?
+    }
+prompt-author = El autor de { $name } en la tienda cambió de { $previous } a { $author }. ¿Instalar { $version } de todos modos?
+prompt-companion-updates = { $theme } usa { $names }, que también tienen actualizaciones. ¿Actualizarlos también?
+prompt-companion-install = { $theme } usa { $names }, que no están instalados. ¿Instalarlos desde la KDE Store?
+prompt-recover = Una ejecución anterior se interrumpió al instalar { $names }. ¿Restaurar?
+prompt-crash-rollback = plasmashell falló tras el reinicio ({ $reason }). ¿Revertir { $names }?
+
+## Restart actions
+
+restart-none = no hacer nada
+restart-font-cache = actualizar la caché de fuentes
+restart-kwin = reconfigurar KWin
+restart-look-and-feel = volver a aplicar los temas actualizados
+restart-plasmashell = reiniciar plasmashell
+watching-plasmashell = vigilando plasmashell durante { $seconds } s…
+
+## Updating
+
+stage-backup = Respaldando
+stage-download = Descargando
+stage-extract = Extrayendo
+stage-install = Instalando
+task-done = Hecho
+task-failed = Falló
+task-updated = { $name } (actualizado)
+task-failed-line = { $name } (falló)
+task-retrying = { $name } (reintentando)
+summary = Resumen: { $succeeded } correctas, { $failed } fallidas, { $skipped } omitidas ({ $total } en total)
+summary-unverified = { $count } sin verificar
+summary-unhealthy = { $count } defectuosas
+summary-rolled-back = { $count } revertidas
+summary-retried = { $retried } tras reintentar
+summary-unreadable = (ilegible)
+summary-unverified-line = sin verificar: { $name } — se esperaba { $expected }, se encontró { $actual }
+summary-unhealthy-line = defectuosa: { $name } — { $problems }
+summary-rolled-back-line = revertido tras fallar plasmashell: { $name }
+restored = { $name } restaurado
+
+## Run reports
+
+run-header =
+    { $scope ->
+        [system] ejecución de sistema iniciada { $started }, duró { $took }
+       *[user] ejecución de usuario iniciada { $started }, duró { $took }
+    }
+run-failed = fallidas:
+run-rolled-back = revertidas
+run-skipped = omitidas
+run-then = luego
+run-afterwards = después: { $actions }
+run-totals = { $updated } actualizadas, { $failed } fallidas, { $downloaded } descargados; uso de la tienda: { $usage }
+no-runs = aún no hay ejecuciones de actualización registradas
+
+## Commands
+
+skipped-outage = omitido: { $error }
+skip-immutable = se omiten los componentes del sistema: son de solo lectura en { $system }
+interrupted =
+    { $count ->
+        [one] aviso: se interrumpió una instalación, ejecute `plasmoid-updater recover` para restaurarla
+       *[other] aviso: se interrumpieron { $count } instalaciones, ejecute `plasmoid-updater recover` para restaurarlas
+    }
+nothing-to-recover = nada que recuperar
+no-orphans = no se encontraron componentes huérfanos
+no-duplicates = no se encontraron instalaciones duplicadas
+duplicate = { $name }: { $version } en { $path } oculta { $system_version } en { $system_path }
+duplicate-removed = se eliminó la copia del usuario
+duplicate-redundant = la copia del usuario sobra; elimínela con --apply
+duplicate-system-older = la copia del sistema es más antigua; desinstálela con el gestor de paquetes o elimine { $path }
+cache-empty = la caché de descargas está vacía
+cache-freed = se liberaron { $mib } MiB
+timer-installs = instala actualizaciones
+timer-checks = busca actualizaciones
+timer-every = cada { $interval }
+timer-installed = plasmoid-updater-check.timer instalado en { $dir }; { $action } { $when }
+timer-hint = consulte `systemctl --user list-timers` y `journalctl --user -u plasmoid-updater-check`
+timer-none = no hay ningún temporizador instalado
+timer-removed = se eliminó plasmoid-updater-check.timer
+rated = { $component } valorado con { $score }/10 (https://store.kde.org/p/{ $content_id })
+apply-installed = instalado
+apply-updated = actualizado
+apply-removed = eliminado
+apply-unchanged = al día
+installed-file = { $name }{ $version } instalado en { $path }
+installed-matched = asociado a la entrada { $id } de la KDE Store; se mantendrá actualizado
+installed-unmatched = no se encontró ninguna entrada en la KDE Store; no recibirá actualizaciones
+no-unregistered = no se encontraron componentes sin registrar
+adopt-failed = no se pudo adoptar { $name }: { $reason }
+no-kept-versions = no se conservan versiones de { $component } (vea keep_versions)
+downgraded = { $component } está ahora en { $version }; reinicie plasmashell para cargarlo
+reinstalled = { $component } { $version } reinstalado (sha256 { $sha256 }); reinicie plasmashell para cargarlo
+verify-no-record = { $name }: no hay registro de instalación con el que comparar
+verify-ok = { $name } { $version }: correcto
+verify-changed = { $name } { $version }: { $modified } modificados, { $missing } ausentes, { $extra } adicionales
+verify-modified = modificado
+verify-missing = ausente
+verify-extra = adicional
+verify-unrecorded = { $count } componentes no tienen registro de instalación con el que comparar
+info-type = tipo
+info-version = versión
+info-path = ruta
+info-author = autor
+info-license = licencia
+info-website = sitio web
+info-summary = resumen
+info-registry = registro
+info-content-id = id de contenido
+info-released = publicado
+info-not-registered = no registrado
+info-store = tienda
+info-update-available = actualización disponible
+info-up-to-date = al día
+info-latest = última
+info-download = descarga
+info-changelog = cambios
+info-no-store-entry = ninguna entrada de la tienda coincide
+info-backups = copias de seguridad
+info-kept-versions = versiones conservadas
+info-kept-version = { $version }  (conservada { $date })
+info-package = paquete instalado
+info-installed = instalado
+info-config = configuración
+info-yes = sí
+info-no = no
+info-excluded = excluido
+info-groups = grupos
+explain-resolution = resolución
+explain-held-back = retenido
+snapshot-saved = se guardaron { $count } entradas de la tienda en { $file }
+registry-clean = el registro es coherente
+registry-fixed =
+    { $count ->
+        [one] se corrigió { $count } problema del registro
+       *[other] se corrigieron { $count } problemas del registro
+    }
+registry-issues =
+    { $count ->
+        [one] se encontró { $count } problema en el registro, ejecute con --fix para repararlo
+       *[other] se encontraron { $count } problemas en el registro, ejecute con --fix para repararlos
+    }
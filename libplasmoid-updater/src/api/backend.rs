@@ -0,0 +1,272 @@
+// SPDX-License-Identifier: GPL-3.0-or-later
+
+use std::{collections::HashMap, sync::Arc};
+
+use parking_lot::Mutex;
+use serde::{Deserialize, Serialize};
+
+use crate::{Error, Result};
+
+use super::config::DEFAULT_BASE_URL;
+use super::credentials::Authorization;
+
+/// A response to a KDE Store API request.
+#[derive(Debug, Clone)]
+pub struct HttpResponse {
+    /// HTTP status code.
+    pub status: u16,
+    /// Value of the `Retry-After` header in seconds, if present.
+    pub retry_after: Option<u64>,
+    /// Response body (OCS XML).
+    pub body: String,
+    /// `ETag` and `Last-Modified` of the response, if the server sent them.
+    pub validators: CacheValidators,
+}
+
+impl HttpResponse {
+    /// A `200 OK` response with the given body.
+    pub fn ok(body: impl Into<String>) -> Self {
+        Self {
+            status: 200,
+            retry_after: None,
+            body: body.into(),
+            validators: CacheValidators::default(),
+        }
+    }
+}
+
+/// Cache validators of an earlier response to the same URL.
+///
+/// Sent as `If-None-Match` and `If-Modified-Since`, letting the server answer
+/// `304 Not Modified` instead of repeating the page.
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct CacheValidators {
+    pub etag: Option<String>,
+    pub last_modified: Option<String>,
+}
+
+impl CacheValidators {
+    /// Returns `true` if neither validator is set.
+    pub fn is_empty(&self) -> bool {
+        self.etag.is_none() && self.last_modified.is_none()
+    }
+}
+
+/// Transport used for KDE Store API requests.
+///
+/// The default backend talks to the store over HTTPS. Tests and downstream
+/// consumers can substitute their own, e.g. [`FixtureBackend`], via
+/// [`Config::with_http_backend`](crate::Config::with_http_backend). Component
+/// downloads during updates always go over the network.
+pub trait HttpBackend: Send + Sync + std::fmt::Debug {
+    /// Performs a GET request. Transport failures are errors; HTTP error
+    /// statuses are returned as responses.
+    fn get(&self, url: &str) -> Result<HttpResponse>;
+
+    /// Performs a conditional GET request, which may be answered with
+    /// `304 Not Modified` if `validators` still match.
+    ///
+    /// The default implementation ignores the validators and calls
+    /// [`get`](Self::get).
+    fn get_conditional(&self, url: &str, validators: &CacheValidators) -> Result<HttpResponse> {
+        let _ = validators;
+        self.get(url)
+    }
+
+    /// Performs a POST request with a form-encoded body, used for
+    /// authenticated store actions such as rating content.
+    ///
+    /// The default implementation fails: backends that only serve recorded
+    /// catalog pages do not need it.
+    fn post_form(&self, url: &str, form: &[(&str, &str)]) -> Result<HttpResponse> {
+        let _ = form;
+        Err(Error::other(format!(
+            "POST {url} is not supported by this HTTP backend"
+        )))
+    }
+}
+
+/// The network backend, built on the shared reqwest client.
+#[derive(Debug, Clone)]
+pub(crate) struct ReqwestBackend {
+    pub(crate) client: reqwest::blocking::Client,
+    /// Sent with every store request, see [`StoreCredentials`](super::StoreCredentials).
+    pub(crate) authorization: Option<Authorization>,
+}
+
+impl ReqwestBackend {
+    fn authorize(
+        &self,
+        request: reqwest::blocking::RequestBuilder,
+    ) -> reqwest::blocking::RequestBuilder {
+        match &self.authorization {
+            Some(authorization) => authorization.apply(request),
+            None => request,
+        }
+    }
+}
+
+impl HttpBackend for ReqwestBackend {
+    fn get(&self, url: &str) -> Result<HttpResponse> {
+        self.get_conditional(url, &CacheValidators::default())
+    }
+
+    fn get_conditional(&self, url: &str, validators: &CacheValidators) -> Result<HttpResponse> {
+        let mut request = self.authorize(self.client.get(url));
+        if let Some(etag) = &validators.etag {
+            request = request.header(reqwest::header::IF_NONE_MATCH, etag);
+        }
+        if let Some(last_modified) = &validators.last_modified {
+            request = request.header(reqwest::header::IF_MODIFIED_SINCE, last_modified);
+        }
+
+        read_response(request.send()?)
+    }
+
+    fn post_form(&self, url: &str, form: &[(&str, &str)]) -> Result<HttpResponse> {
+        let body = form_urlencoded::Serializer::new(String::new())
+            .extend_pairs(form)
+            .finish();
+        let request = self
+            .authorize(self.client.post(url))
+            .header(
+                reqwest::header::CONTENT_TYPE,
+                "application/x-www-form-urlencoded",
+            )
+            .body(body);
+        read_response(request.send()?)
+    }
+}
+
+fn read_response(response: reqwest::blocking::Response) -> Result<HttpResponse> {
+    let status = response.status().as_u16();
+    let retry_after = parse_retry_after(&response);
+    let validators = CacheValidators {
+        etag: header_value(&response, reqwest::header::ETAG),
+        last_modified: header_value(&response, reqwest::header::LAST_MODIFIED),
+    };
+    let body = response.text()?;
+
+    Ok(HttpResponse {
+        status,
+        retry_after,
+        body,
+        validators,
+    })
+}
+
+fn parse_retry_after(response: &reqwest::blocking::Response) -> Option<u64> {
+    header_value(response, reqwest::header::RETRY_AFTER)?
+        .parse()
+        .ok()
+}
+
+fn header_value(
+    response: &reqwest::blocking::Response,
+    name: reqwest::header::HeaderName,
+) -> Option<String> {
+    Some(response.headers().get(name)?.to_str().ok()?.to_string())
+}
+
+/// Serves recorded responses instead of contacting the store.
+///
+/// Responses are keyed by the request path and query relative to the OCS
+/// base URL, e.g. `/content/data/1234`. Requests without a recorded response
+/// fail with an error. Every request is recorded and can be inspected with
+/// [`requests`](Self::requests).
+///
+/// # Example
+///
+/// ```rust
+/// use libplasmoid_updater::{Config, FixtureBackend};
+///
+/// let backend = FixtureBackend::new()
+///     .with_response("/content/data/1000001", FixtureBackend::SAMPLE_CONTENT);
+/// let config = Config::new().with_http_backend(backend);
+/// ```
+#[derive(Debug, Clone, Default)]
+pub struct FixtureBackend {
+    responses: HashMap<String, HttpResponse>,
+    requests: Arc<Mutex<Vec<String>>>,
+}
+
+impl FixtureBackend {
+    /// A catalog page listing two plasma widgets (content IDs `1000001` and
+    /// `1000002`), served for `/content/data?categories=705&page=0&pagesize=100&sort=new`.
+    pub const SAMPLE_CATALOG: &str = include_str!("../../testdata/ocs/catalog-plasma-widgets.xml");
+
+    /// Details of content ID `1000001`, served for `/content/data/1000001`.
+    pub const SAMPLE_CONTENT: &str = include_str!("../../testdata/ocs/content-1000001.xml");
+
+    /// An OCS rate-limit response.
+    pub const SAMPLE_RATE_LIMITED: &str = include_str!("../../testdata/ocs/rate-limited.xml");
+
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// A backend serving the bundled sample catalog and content details.
+    pub fn sample() -> Self {
+        Self::new()
+            .with_response(
+                "/content/data?categories=705&page=0&pagesize=100&sort=new",
+                Self::SAMPLE_CATALOG,
+            )
+            .with_response("/content/data/1000001", Self::SAMPLE_CONTENT)
+    }
+
+    /// Serves `body` with status 200 for `path`.
+    pub fn with_response(self, path: impl Into<String>, body: impl Into<String>) -> Self {
+        self.with_http_response(path, HttpResponse::ok(body))
+    }
+
+    /// Serves an arbitrary response, e.g. an HTTP 429, for `path`.
+    pub fn with_http_response(mut self, path: impl Into<String>, response: HttpResponse) -> Self {
+        self.responses.insert(path.into(), response);
+        self
+    }
+
+    /// Paths requested so far, in order.
+    pub fn requests(&self) -> Vec<String> {
+        self.requests.lock().clone()
+    }
+}
+
+impl HttpBackend for FixtureBackend {
+    fn get(&self, url: &str) -> Result<HttpResponse> {
+        let path = url.strip_prefix(DEFAULT_BASE_URL).unwrap_or(url);
+        self.requests.lock().push(path.to_string());
+
+        self.responses
+            .get(path)
+            .cloned()
+            .ok_or_else(|| Error::other(format!("no fixture recorded for {path}")))
+    }
+
+    /// Serves POST requests like GET requests; the form is ignored.
+    fn post_form(&self, url: &str, _form: &[(&str, &str)]) -> Result<HttpResponse> {
+        self.get(url)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn store_requests_carry_sensitive_authorization() {
+        let backend = ReqwestBackend {
+            client: reqwest::blocking::Client::new(),
+            authorization: Some(Authorization::Bearer("s3cret".to_string())),
+        };
+
+        let request = backend
+            .authorize(backend.client.get(DEFAULT_BASE_URL))
+            .build()
+            .unwrap();
+        let header = &request.headers()[reqwest::header::AUTHORIZATION];
+        assert_eq!(header, "Bearer s3cret");
+        assert!(header.is_sensitive());
+        assert!(!format!("{header:?}").contains("s3cret"));
+    }
+}
@@ -4,32 +4,37 @@
 // and KDE Discover (https://invent.kde.org/plasma/discover) -
 // GPL-2.0-only OR GPL-3.0-only OR LicenseRef-KDE-Accepted-GPL
 
-use std::{
-    sync::{
-        Arc,
-        atomic::{AtomicUsize, Ordering},
-    },
-    thread,
-    time::Duration,
-};
+use std::{io::Read as _, path::PathBuf, sync::Arc, thread, time::Duration};
 
+use parking_lot::Mutex;
 use rayon::iter::{IntoParallelRefIterator, ParallelIterator};
 
 use crate::{
-    types::{ComponentType, StoreEntry},
+    Config,
+    types::{CatalogFetchReport, ComponentType, StoreEntry},
     {Error, Result},
 };
 
-use super::config::{ApiConfig, CONNECT_TIMEOUT, DEFAULT_API_CONFIG, REQUEST_TIMEOUT, USER_AGENT};
+use super::backend::{HttpBackend, HttpResponse, ReqwestBackend};
+use super::config::{ApiConfig, DEFAULT_BASE_URL, DEFAULT_PAGE_SIZE};
+use super::credentials::StoreCredentials;
 use super::ocs_parser::Meta;
 use super::ocs_parser::{build_category_string, parse_ocs_response};
+use super::page_cache::PageCache;
+use super::previews::{self, MAX_PREVIEW_SIZE, PreviewSize};
+use super::snapshot::StoreSnapshot;
+use super::stats::{ApiCounters, ApiStats};
 
 /// Thread-safe API client for KDE Store interactions.
 #[derive(Clone)]
 pub(crate) struct ApiClient {
     client: reqwest::blocking::Client,
-    config: &'static ApiConfig,
-    request_count: Arc<AtomicUsize>,
+    backend: Arc<dyn HttpBackend>,
+    config: ApiConfig,
+    stats: Arc<ApiCounters>,
+    page_cache: Option<PageCache>,
+    snapshot: Option<Arc<StoreSnapshot>>,
+    recorded: Option<Arc<Mutex<Vec<StoreEntry>>>>,
 }
 
 impl Default for ApiClient {
@@ -45,46 +50,158 @@ impl ApiClient {
     ///
     /// Panics if the HTTP client cannot be created (e.g., TLS backend unavailable).
     pub fn new() -> Self {
-        Self::with_config(&DEFAULT_API_CONFIG)
+        Self::with_config(ApiConfig::default())
             .unwrap_or_else(|e| panic!("failed to create API client: {e}"))
     }
 
-    /// Creates a new API client with the given configuration.
-    pub(super) fn with_config(config: &'static ApiConfig) -> Result<Self> {
-        let client = reqwest::blocking::Client::builder()
-            .connect_timeout(CONNECT_TIMEOUT)
-            .timeout(REQUEST_TIMEOUT)
-            .user_agent(USER_AGENT)
-            .build()?;
+    /// Creates a new API client with the given timeouts and retry policy.
+    pub(crate) fn with_config(config: ApiConfig) -> Result<Self> {
+        let user_agent = config.user_agent();
+        log::debug!(target: "api", "user agent: {user_agent}");
+        let builder = reqwest::blocking::Client::builder()
+            .connect_timeout(config.connect_timeout)
+            .timeout(config.request_timeout)
+            .user_agent(user_agent);
+
+        // rustls unless native-tls is the only TLS feature enabled.
+        #[cfg(any(feature = "rustls", not(feature = "native-tls")))]
+        let builder = builder.tls_backend_rustls();
+        #[cfg(all(feature = "native-tls", not(feature = "rustls")))]
+        let builder = builder.tls_backend_native();
+
+        let client = builder.build()?;
 
         Ok(Self {
+            backend: Arc::new(ReqwestBackend {
+                client: client.clone(),
+                authorization: None,
+            }),
             client,
             config,
-            request_count: Arc::new(AtomicUsize::new(0)),
+            stats: Arc::default(),
+            page_cache: None,
+            snapshot: None,
+            recorded: None,
         })
     }
 
+    /// Creates an API client using [`Config::http_backend`] for store requests, if set.
+    ///
+    /// Pages are cached for conditional requests when
+    /// [`Config::conditional_requests`] is set. With
+    /// [`Config::store_snapshot`], entries are served from the snapshot and
+    /// no store requests are made.
+    pub(crate) fn from_config(config: &Config) -> Result<Self> {
+        let mut client = Self::with_config(config.api.clone())?.with_page_cache(
+            config
+                .conditional_requests
+                .then(PageCache::default_location),
+        );
+        client.snapshot = config.store_snapshot.clone();
+        if let Some(credentials) = &config.store_credentials {
+            client = client.with_credentials(credentials);
+        }
+        Ok(match &config.http_backend {
+            Some(backend) => client.with_backend(Arc::clone(backend)),
+            None => client,
+        })
+    }
+
+    pub(crate) fn with_backend(mut self, backend: Arc<dyn HttpBackend>) -> Self {
+        self.backend = backend;
+        self
+    }
+
+    /// Authenticates store requests with `credentials`. If they cannot be
+    /// resolved, e.g. the keyring is locked, requests stay anonymous.
+    pub(crate) fn with_credentials(mut self, credentials: &StoreCredentials) -> Self {
+        let authorization = match credentials.authorization() {
+            Ok(authorization) => authorization,
+            Err(e) => {
+                log::warn!(target: "api", "store credentials unavailable, continuing anonymously: {e}");
+                return self;
+            }
+        };
+        log::debug!(target: "api", "authenticating store requests as {}", authorization.describe());
+        self.backend = Arc::new(ReqwestBackend {
+            client: self.client.clone(),
+            authorization: Some(authorization),
+        });
+        self
+    }
+
+    pub(crate) fn with_page_cache(mut self, cache: Option<PageCache>) -> Self {
+        self.page_cache = cache;
+        self
+    }
+
+    /// Keeps a copy of every store entry fetched from now on, see
+    /// [`recorded_entries`](Self::recorded_entries).
+    pub(crate) fn with_recording(mut self) -> Self {
+        self.recorded = Some(Arc::default());
+        self
+    }
+
+    /// The store entries fetched since [`with_recording`](Self::with_recording).
+    pub(crate) fn recorded_entries(&self) -> Vec<StoreEntry> {
+        self.recorded
+            .as_ref()
+            .map(|recorded| recorded.lock().clone())
+            .unwrap_or_default()
+    }
+
+    fn record(&self, entries: &[StoreEntry]) {
+        if let Some(recorded) = &self.recorded {
+            recorded.lock().extend_from_slice(entries);
+        }
+    }
+
     /// Returns a reference to the underlying HTTP client for reuse.
     pub fn http_client(&self) -> &reqwest::blocking::Client {
         &self.client
     }
 
-    /// Total number of HTTP requests sent since this client was created.
-    #[cfg(feature = "debug")]
-    pub fn request_count(&self) -> usize {
-        self.request_count.load(Ordering::Relaxed)
+    /// Store and download usage since this client was created, downloads
+    /// made with [`Self::counters`] included.
+    pub fn stats(&self) -> ApiStats {
+        self.stats.snapshot()
     }
 
-    /// A shared handle to the request counter, suitable for passing to the installer.
-    pub(crate) fn request_counter(&self) -> Arc<AtomicUsize> {
-        Arc::clone(&self.request_count)
+    /// A shared handle to the usage counters, suitable for passing to the installer.
+    pub(crate) fn counters(&self) -> Arc<ApiCounters> {
+        Arc::clone(&self.stats)
     }
 
     /// Fetches all content from specified categories with parallel page fetching.
     pub fn fetch_all(&self, categories: &[ComponentType]) -> Result<Vec<StoreEntry>> {
+        self.fetch_all_with_report(categories)
+            .map(|(entries, _)| entries)
+    }
+
+    /// Like [`fetch_all`](Self::fetch_all), also reporting how many catalog
+    /// pages failed. Failed pages past the first are skipped, not errors.
+    pub(crate) fn fetch_all_with_report(
+        &self,
+        categories: &[ComponentType],
+    ) -> Result<(Vec<StoreEntry>, CatalogFetchReport)> {
+        let (entries, report) = match &self.snapshot {
+            Some(snapshot) => (
+                snapshot.entries_in(categories),
+                CatalogFetchReport::default(),
+            ),
+            None => self.fetch_catalog(categories)?,
+        };
+        self.record(&entries);
+        Ok((entries, report))
+    }
+
+    fn fetch_catalog(
+        &self,
+        categories: &[ComponentType],
+    ) -> Result<(Vec<StoreEntry>, CatalogFetchReport)> {
         let category_str = build_category_string(categories);
-        let base_url = self.config.base_url;
-        let page_size = self.config.page_size;
+        let base_url = DEFAULT_BASE_URL;
+        let page_size = DEFAULT_PAGE_SIZE;
 
         let first_url = format!(
             "{base_url}/content/data?categories={category_str}&page=0&pagesize={page_size}&sort=new"
@@ -94,7 +211,13 @@ impl ApiClient {
         let total_items = meta.total_items;
 
         if total_items <= u32::from(page_size) {
-            return Ok(first_entries);
+            return Ok((
+                first_entries,
+                CatalogFetchReport {
+                    pages: 1,
+                    failed_pages: 0,
+                },
+            ));
         }
 
         let total_pages = total_items.div_ceil(u32::from(page_size));
@@ -119,7 +242,7 @@ impl ApiClient {
             .collect();
 
         let mut all_entries = first_entries;
-        let mut error_count = 0usize;
+        let mut error_count = 0u32;
         for result in results {
             match result {
                 Ok((entries, _)) => all_entries.extend(entries),
@@ -131,56 +254,169 @@ impl ApiClient {
             log::warn!(target: "api", "{error_count} page{} failed to fetch", if error_count == 1 { "" } else { "s" });
         }
 
-        Ok(all_entries)
+        Ok((
+            all_entries,
+            CatalogFetchReport {
+                pages: total_pages,
+                failed_pages: error_count,
+            },
+        ))
     }
 
     /// Fetches content details of multiple components.
+    ///
+    /// IDs the store does not know, e.g. deleted entries, fail with
+    /// [`Error::ComponentNotFound`].
     pub fn fetch_details(&self, content_ids: &[u64]) -> Vec<Result<StoreEntry>> {
         content_ids
             .par_iter()
             .map(|&id| {
-                let base_url = self.config.base_url;
-                let url = format!("{base_url}/content/data/{id}");
-                let (entries, _) = self.fetch_page(&url)?;
-                entries
-                    .into_iter()
-                    .next()
-                    .ok_or_else(|| Error::ComponentNotFound(format!("store content id {id}")))
+                let entry = match &self.snapshot {
+                    Some(snapshot) => snapshot.entry(id).cloned(),
+                    None => {
+                        let url = format!("{DEFAULT_BASE_URL}/content/data/{id}");
+                        match self.fetch_page(&url) {
+                            Ok((entries, _)) => entries.into_iter().next(),
+                            Err(Error::ApiError(OCS_CONTENT_NOT_FOUND)) => None,
+                            Err(e) => return Err(e),
+                        }
+                    }
+                }
+                .ok_or_else(|| Error::ComponentNotFound(format!("store content id {id}")))?;
+                self.record(std::slice::from_ref(&entry));
+                Ok(entry)
             })
             .collect()
     }
 
+    /// Returns a local copy of the `size` preview image of `entry`,
+    /// downloading it into `$XDG_CACHE_HOME/plasmoid-updater/previews` unless
+    /// it is cached already.
+    ///
+    /// The cache is kept under 64 MiB by removing the least recently shown
+    /// previews. Fails if the entry has no preview or the download fails.
+    pub fn download_preview(&self, entry: &StoreEntry, size: PreviewSize) -> Result<PathBuf> {
+        let url = previews::preview_url(entry, size)?;
+        previews::fetch(entry.id, url, || {
+            if !url.starts_with("https://") && !url.starts_with("http://") {
+                return Err(Error::download(format!("unsupported preview url {url}")));
+            }
+            self.stats.request();
+            let response = self.client.get(url).send()?;
+            if !response.status().is_success() {
+                return Err(Error::download(format!(
+                    "HTTP {} for {url}",
+                    response.status().as_u16()
+                )));
+            }
+            let mut image = Vec::new();
+            response
+                .take(MAX_PREVIEW_SIZE + 1)
+                .read_to_end(&mut image)?;
+            self.stats.received(image.len() as u64);
+            if image.len() as u64 > MAX_PREVIEW_SIZE {
+                return Err(Error::download(format!("preview {url} is too large")));
+            }
+            Ok(image)
+        })
+    }
+
+    /// Rates content `id` with `score` from 1 (bad) to 10 (excellent), like
+    /// the rating control in Discover. The request needs
+    /// [`StoreCredentials`].
+    pub(crate) fn rate_content(&self, id: u64, score: u8) -> Result<()> {
+        if !(1..=10).contains(&score) {
+            return Err(Error::other(format!(
+                "score must be between 1 and 10, got {score}"
+            )));
+        }
+
+        // OCS votes are percentages; KNewStuff sends the score times ten.
+        let vote = (u16::from(score) * 10).to_string();
+        let url = format!("{DEFAULT_BASE_URL}/content/vote/{id}");
+        self.stats.request();
+        let response = self.backend.post_form(&url, &[("vote", &vote)])?;
+        self.count_response(&response);
+
+        match response.status {
+            TOO_MANY_REQUESTS => return Err(Error::RateLimited),
+            OK => {}
+            status => return Err(Error::ApiError(status)),
+        }
+        parse_ocs_response(&response.body)?;
+        log::info!(target: "api", "rated content {id} with {score}/10");
+        Ok(())
+    }
+
+    /// Sends one small catalog request, bypassing retries and the page
+    /// cache, to see whether the store answers. Used by
+    /// [`doctor()`](crate::doctor).
+    pub(crate) fn probe(&self) -> Result<()> {
+        let url = format!("{DEFAULT_BASE_URL}/content/data?page=0&pagesize=1");
+        self.stats.request();
+        let response = self.backend.get(&url)?;
+        self.count_response(&response);
+
+        match response.status {
+            TOO_MANY_REQUESTS => Err(Error::RateLimited),
+            OK => check_available(&response)
+                .and_then(|()| parse_ocs_response(&response.body))
+                .map(|_| ()),
+            status => Err(Error::ApiError(status)),
+        }
+    }
+
+    /// Fetches a plain document from outside the store API, e.g. the
+    /// advisory feed.
+    pub(crate) fn fetch_text(&self, url: &str) -> Result<String> {
+        let response = self.get(url)?;
+        if response.status != OK {
+            return Err(Error::other(format!("HTTP {} for {url}", response.status)));
+        }
+        self.remember(url, &response);
+        Ok(response.body)
+    }
+
     fn fetch_page(&self, url: &str) -> Result<(Vec<StoreEntry>, Meta)> {
-        let mut backoff_ms = self.config.initial_backoff_ms;
+        let attempts = self.config.attempts();
+        let mut backoff = self.config.backoff;
 
-        for attempt in 0..self.config.max_retries {
-            self.request_count.fetch_add(1, Ordering::Relaxed);
-            let r = self.client.get(url).send()?;
-            let retry_after_secs = parse_retry_after(&r);
+        for attempt in 0..attempts {
+            let r = self.get(url)?;
+            let retry_after_secs = r.retry_after;
 
             // HTTP 429: respect Retry-After with a single retry.
-            if r.status() == reqwest::StatusCode::TOO_MANY_REQUESTS {
+            if r.status == TOO_MANY_REQUESTS {
                 return match retry_after_secs {
                     Some(secs) => self.send_after(url, secs),
                     None => Err(Error::RateLimited),
                 };
             }
 
-            let xml = r.text()?;
-            match parse_ocs_response(&xml) {
-                Ok(result) => return Ok(result),
+            if r.status == NOT_FOUND {
+                return Err(Error::ComponentNotFound(url.to_string()));
+            }
+
+            let parsed = check_available(&r).and_then(|()| parse_ocs_response(&r.body));
+            if matches!(parsed, Err(Error::RateLimited)) {
+                self.stats.rate_limited();
+            }
+            match parsed {
+                Ok(result) => {
+                    self.remember(url, &r);
+                    return Ok(result);
+                }
                 // OCS rate limit with Retry-After: respect it with a single retry.
                 Err(Error::RateLimited) if retry_after_secs.is_some() => {
                     return self.send_after(url, retry_after_secs.unwrap());
                 }
-                // Retry transient errors (including OCS rate limit without Retry-After).
+                // Retry transient errors (including OCS rate limit without
+                // Retry-After and maintenance pages).
                 // ApiError is a deterministic OCS status — retrying wastes a request.
-                Err(ref e)
-                    if !matches!(e, Error::ApiError(_))
-                        && attempt + 1 < self.config.max_retries =>
-                {
-                    thread::sleep(Duration::from_millis(backoff_ms.into()));
-                    backoff_ms = backoff_ms.saturating_mul(2);
+                Err(ref e) if !matches!(e, Error::ApiError(_)) && attempt + 1 < attempts => {
+                    self.stats.retry();
+                    thread::sleep(backoff);
+                    backoff = backoff.saturating_mul(2);
                 }
                 Err(e) => return Err(e),
             }
@@ -193,24 +429,321 @@ impl ApiClient {
     fn send_after(&self, url: &str, secs: u64) -> Result<(Vec<StoreEntry>, Meta)> {
         log::info!(target: "api", "rate limited, retrying after {secs}s");
         thread::sleep(Duration::from_secs(secs));
-        self.request_count.fetch_add(1, Ordering::Relaxed);
-        let r = self.client.get(url).send()?;
+        self.stats.retry();
+        let r = self.get(url)?;
 
-        if r.status() == reqwest::StatusCode::TOO_MANY_REQUESTS {
+        if r.status == TOO_MANY_REQUESTS {
             return Err(Error::RateLimited);
         }
 
-        let xml = r.text()?;
-        parse_ocs_response(&xml)
+        check_available(&r)?;
+        let result = parse_ocs_response(&r.body)?;
+        self.remember(url, &r);
+        Ok(result)
+    }
+
+    /// Sends a GET request, conditional on the cached page for `url` if
+    /// there is one. A `304 Not Modified` answer is returned as the cached
+    /// page with status 200.
+    fn get(&self, url: &str) -> Result<HttpResponse> {
+        self.stats.request();
+
+        let cached = self.page_cache.as_ref().and_then(|cache| cache.load(url));
+        let Some(cached) = cached else {
+            return self.backend.get(url).inspect(|r| self.count_response(r));
+        };
+
+        let response = self.backend.get_conditional(url, &cached.validators)?;
+        if response.status != NOT_MODIFIED {
+            self.count_response(&response);
+            return Ok(response);
+        }
+
+        log::debug!(target: "api", "not modified, reusing cached page for {url}");
+        Ok(HttpResponse {
+            retry_after: response.retry_after,
+            ..HttpResponse::ok(cached.body)
+        })
+    }
+
+    /// Adds a fresh response to the usage counters.
+    fn count_response(&self, response: &HttpResponse) {
+        self.stats.received(response.body.len() as u64);
+        if response.status == TOO_MANY_REQUESTS {
+            self.stats.rate_limited();
+        }
+    }
+
+    /// Caches a successfully parsed response for later conditional requests.
+    fn remember(&self, url: &str, response: &HttpResponse) {
+        if let Some(cache) = &self.page_cache {
+            cache.store(url, response);
+        }
+    }
+}
+
+/// Fails with [`Error::StoreUnavailable`] for server errors and for answers
+/// that cannot be OCS XML, like the HTML page shown during maintenance, which
+/// would otherwise surface as a cryptic XML parse error.
+fn check_available(response: &HttpResponse) -> Result<()> {
+    let body = response.body.trim_start();
+    let head = body.get(..14).unwrap_or(body).to_ascii_lowercase();
+    let is_html = head.starts_with("<!doctype html") || head.starts_with("<html");
+    if response.status >= 500 || is_html || !body.starts_with('<') {
+        return Err(Error::StoreUnavailable {
+            status: response.status,
+            retry_hint: response.retry_after,
+        });
     }
+    Ok(())
 }
 
-fn parse_retry_after(response: &reqwest::blocking::Response) -> Option<u64> {
-    response
-        .headers()
-        .get(reqwest::header::RETRY_AFTER)?
-        .to_str()
-        .ok()?
-        .parse()
-        .ok()
+const OK: u16 = 200;
+const NOT_MODIFIED: u16 = 304;
+const NOT_FOUND: u16 = 404;
+const TOO_MANY_REQUESTS: u16 = 429;
+/// OCS status of a content request for an ID the store does not know.
+const OCS_CONTENT_NOT_FOUND: u16 = 101;
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::api::{CacheValidators, FixtureBackend};
+
+    fn fixture_client(backend: &FixtureBackend) -> ApiClient {
+        ApiClient::new().with_backend(Arc::new(backend.clone()))
+    }
+
+    #[test]
+    fn fetch_all_reads_catalog_from_fixture() {
+        let backend = FixtureBackend::sample();
+        let entries = fixture_client(&backend)
+            .fetch_all(&[ComponentType::PlasmaWidget])
+            .unwrap();
+
+        let names: Vec<&str> = entries.iter().map(|e| e.name.as_str()).collect();
+        assert_eq!(names, ["Sample Clock", "Sample Monitor"]);
+        assert_eq!(entries[0].download_links[0].version, "2.0");
+        assert_eq!(
+            backend.requests(),
+            ["/content/data?categories=705&page=0&pagesize=100&sort=new"]
+        );
+    }
+
+    #[test]
+    fn failed_catalog_pages_are_reported() {
+        let first_page = FixtureBackend::SAMPLE_CATALOG
+            .replace("<totalitems>2</totalitems>", "<totalitems>250</totalitems>");
+        let backend = FixtureBackend::new()
+            .with_response(
+                "/content/data?categories=705&page=0&pagesize=100&sort=new",
+                first_page,
+            )
+            .with_response(
+                "/content/data?categories=705&page=1&pagesize=100&sort=new",
+                FixtureBackend::SAMPLE_CATALOG,
+            );
+
+        let (entries, report) = fixture_client(&backend)
+            .fetch_all_with_report(&[ComponentType::PlasmaWidget])
+            .unwrap();
+
+        assert_eq!(entries.len(), 4);
+        assert_eq!(
+            report,
+            CatalogFetchReport {
+                pages: 3,
+                failed_pages: 1,
+            }
+        );
+        assert!(!report.is_complete());
+    }
+
+    #[test]
+    fn rate_content_posts_vote() {
+        let backend = FixtureBackend::new().with_response(
+            "/content/vote/1000001",
+            "<ocs><meta><status>ok</status><statuscode>100</statuscode></meta></ocs>",
+        );
+        let client = fixture_client(&backend);
+
+        client.rate_content(1000001, 8).unwrap();
+        assert_eq!(backend.requests(), ["/content/vote/1000001"]);
+        assert!(client.rate_content(1000001, 11).is_err());
+        assert_eq!(backend.requests().len(), 1);
+    }
+
+    #[test]
+    fn fetch_details_reports_missing_fixture_per_id() {
+        let backend = FixtureBackend::sample();
+        let results = fixture_client(&backend).fetch_details(&[1000001, 42]);
+
+        assert_eq!(results[0].as_ref().unwrap().version, "2.0");
+        assert!(results[1].is_err());
+    }
+
+    #[test]
+    fn http_429_without_retry_after_is_rate_limited() {
+        let backend = FixtureBackend::new().with_http_response(
+            "/content/data/1",
+            HttpResponse {
+                status: TOO_MANY_REQUESTS,
+                retry_after: None,
+                body: String::new(),
+                validators: Default::default(),
+            },
+        );
+        let results = fixture_client(&backend).fetch_details(&[1]);
+
+        assert!(matches!(results[0], Err(Error::RateLimited)));
+    }
+
+    #[test]
+    fn ocs_rate_limit_is_retried_then_reported() {
+        let backend = FixtureBackend::new()
+            .with_response("/content/data/1", FixtureBackend::SAMPLE_RATE_LIMITED);
+        let results = fixture_client(&backend).fetch_details(&[1]);
+
+        assert!(matches!(results[0], Err(Error::RateLimited)));
+        assert_eq!(
+            backend.requests().len(),
+            usize::from(ApiConfig::DEFAULT_MAX_ATTEMPTS)
+        );
+    }
+
+    #[test]
+    fn retry_policy_comes_from_api_config() {
+        let backend = FixtureBackend::new()
+            .with_response("/content/data/1", FixtureBackend::SAMPLE_RATE_LIMITED);
+        let api = ApiConfig::new()
+            .with_max_attempts(5)
+            .with_backoff(Duration::ZERO);
+        let client = ApiClient::with_config(api)
+            .unwrap()
+            .with_backend(Arc::new(backend.clone()));
+
+        assert!(matches!(
+            client.fetch_details(&[1])[0],
+            Err(Error::RateLimited)
+        ));
+        assert_eq!(backend.requests().len(), 5);
+    }
+
+    #[test]
+    fn maintenance_pages_are_retried_then_reported_as_unavailable() {
+        let backend = FixtureBackend::new()
+            .with_response(
+                "/content/data/1",
+                "<!DOCTYPE html>\n<html><body>Down for maintenance</body></html>",
+            )
+            .with_http_response(
+                "/content/data/2",
+                HttpResponse {
+                    status: 503,
+                    retry_after: Some(300),
+                    ..HttpResponse::ok("")
+                },
+            );
+        let api = ApiConfig::new().with_backoff(Duration::ZERO);
+        let client = ApiClient::with_config(api)
+            .unwrap()
+            .with_backend(Arc::new(backend.clone()));
+
+        let results = client.fetch_details(&[1, 2]);
+
+        assert!(matches!(
+            results[0],
+            Err(Error::StoreUnavailable {
+                status: 200,
+                retry_hint: None
+            })
+        ));
+        assert!(matches!(
+            results[1],
+            Err(Error::StoreUnavailable {
+                status: 503,
+                retry_hint: Some(300)
+            })
+        ));
+        assert_eq!(client.stats().retries, 4);
+    }
+
+    /// Serves the sample content page with an `ETag`, and `304 Not Modified`
+    /// once the request carries it.
+    #[derive(Debug, Default)]
+    struct RevalidatingBackend {
+        validators: Mutex<Vec<CacheValidators>>,
+    }
+
+    impl HttpBackend for RevalidatingBackend {
+        fn get(&self, url: &str) -> Result<HttpResponse> {
+            self.get_conditional(url, &CacheValidators::default())
+        }
+
+        fn get_conditional(
+            &self,
+            _url: &str,
+            validators: &CacheValidators,
+        ) -> Result<HttpResponse> {
+            self.validators.lock().push(validators.clone());
+            let etag = "\"v1\"".to_string();
+            if validators.etag.as_ref() == Some(&etag) {
+                return Ok(HttpResponse {
+                    status: NOT_MODIFIED,
+                    ..HttpResponse::ok("")
+                });
+            }
+            Ok(HttpResponse {
+                validators: CacheValidators {
+                    etag: Some(etag),
+                    last_modified: None,
+                },
+                ..HttpResponse::ok(FixtureBackend::SAMPLE_CONTENT)
+            })
+        }
+    }
+
+    #[test]
+    fn not_modified_reuses_cached_page() {
+        let dir = tempfile::tempdir().unwrap();
+        let backend = Arc::new(RevalidatingBackend::default());
+        let client = ApiClient::new()
+            .with_backend(backend.clone())
+            .with_page_cache(Some(PageCache::new(dir.path())));
+
+        for _ in 0..2 {
+            let results = client.fetch_details(&[1000001]);
+            assert_eq!(results[0].as_ref().unwrap().version, "2.0");
+        }
+
+        let sent = backend.validators.lock();
+        assert!(sent[0].is_empty());
+        assert_eq!(sent[1].etag.as_deref(), Some("\"v1\""));
+    }
+
+    #[test]
+    fn snapshot_serves_entries_without_requests() {
+        let backend = FixtureBackend::sample();
+        let recording = fixture_client(&backend).with_recording();
+        recording.fetch_all(&[ComponentType::PlasmaWidget]).unwrap();
+        let snapshot = StoreSnapshot::from_entries(recording.recorded_entries());
+        assert_eq!(snapshot.len(), 2);
+
+        let offline = FixtureBackend::new();
+        let mut client = fixture_client(&offline);
+        client.snapshot = Some(Arc::new(snapshot));
+
+        let entries = client.fetch_all(&[ComponentType::PlasmaWidget]).unwrap();
+        assert_eq!(entries.len(), 2);
+        assert!(
+            client
+                .fetch_all(&[ComponentType::IconTheme])
+                .unwrap()
+                .is_empty()
+        );
+        let results = client.fetch_details(&[entries[0].id, 42]);
+        assert_eq!(results[0].as_ref().unwrap().id, entries[0].id);
+        assert!(results[1].is_err());
+        assert!(offline.requests().is_empty());
+    }
 }
@@ -4,20 +4,47 @@ use std::time::Duration;
 
 pub(crate) const DEFAULT_BASE_URL: &str = "https://api.kde-look.org/ocs/v1";
 pub(crate) const DEFAULT_PAGE_SIZE: u8 = 100;
-pub(crate) const DEFAULT_MAX_RETRIES: u8 = 3;
-pub(crate) const DEFAULT_INITIAL_BACKOFF_MS: u32 = 100;
-pub(crate) const CONNECT_TIMEOUT: Duration = Duration::from_secs(10);
-pub(crate) const REQUEST_TIMEOUT: Duration = Duration::from_secs(60);
 pub(crate) const MAX_DOWNLOAD_LINKS: usize = 64;
 
 pub(crate) const USER_AGENT: &str = concat!("plasmoid-updater/", env!("CARGO_PKG_VERSION"));
 
-/// Configuration for KDE Store API interactions.
-pub(super) struct ApiConfig {
-    pub(super) base_url: &'static str,
-    pub(super) page_size: u8,
-    pub(super) max_retries: u8,
-    pub(super) initial_backoff_ms: u32,
+/// Timeouts and retry policy for HTTP requests.
+///
+/// Used for both KDE Store API requests and package downloads. Set it with
+/// [`Config::with_api`](crate::Config::with_api).
+///
+/// # Example
+///
+/// ```rust
+/// use std::time::Duration;
+/// use libplasmoid_updater::{ApiConfig, Config};
+///
+/// let api = ApiConfig::new()
+///     .with_connect_timeout(Duration::from_secs(30))
+///     .with_download_timeout(Duration::from_secs(600));
+/// let config = Config::new().with_api(api);
+/// ```
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ApiConfig {
+    /// Time allowed to establish a connection. Defaults to
+    /// [`Self::DEFAULT_CONNECT_TIMEOUT`].
+    pub connect_timeout: Duration,
+    /// Time allowed for a whole store API request. Defaults to
+    /// [`Self::DEFAULT_REQUEST_TIMEOUT`].
+    pub request_timeout: Duration,
+    /// Time allowed for a whole package download. Defaults to
+    /// [`Self::DEFAULT_DOWNLOAD_TIMEOUT`].
+    pub download_timeout: Duration,
+    /// How many times a store request is attempted before a transient error
+    /// is returned, at least once. Defaults to [`Self::DEFAULT_MAX_ATTEMPTS`].
+    pub max_attempts: u8,
+    /// Delay before the second attempt; it doubles for each further one.
+    /// Defaults to [`Self::DEFAULT_BACKOFF`].
+    pub backoff: Duration,
+    /// Identifies the application embedding the library, e.g.
+    /// `topgrade/16.0`. Appended to the user agent of store requests and
+    /// downloads, see [`Self::user_agent`].
+    pub user_agent_suffix: Option<String>,
 }
 
 impl Default for ApiConfig {
@@ -27,17 +54,141 @@ impl Default for ApiConfig {
 }
 
 impl ApiConfig {
-    pub(super) const fn new() -> Self {
+    pub const DEFAULT_CONNECT_TIMEOUT: Duration = Duration::from_secs(10);
+    pub const DEFAULT_REQUEST_TIMEOUT: Duration = Duration::from_secs(60);
+    pub const DEFAULT_DOWNLOAD_TIMEOUT: Duration = Duration::from_secs(60);
+    pub const DEFAULT_MAX_ATTEMPTS: u8 = 3;
+    pub const DEFAULT_BACKOFF: Duration = Duration::from_millis(100);
+
+    /// Creates the default timeouts and retry policy.
+    pub const fn new() -> Self {
         Self {
-            base_url: DEFAULT_BASE_URL,
-            page_size: DEFAULT_PAGE_SIZE,
-            max_retries: DEFAULT_MAX_RETRIES,
-            initial_backoff_ms: DEFAULT_INITIAL_BACKOFF_MS,
+            connect_timeout: Self::DEFAULT_CONNECT_TIMEOUT,
+            request_timeout: Self::DEFAULT_REQUEST_TIMEOUT,
+            download_timeout: Self::DEFAULT_DOWNLOAD_TIMEOUT,
+            max_attempts: Self::DEFAULT_MAX_ATTEMPTS,
+            backoff: Self::DEFAULT_BACKOFF,
+            user_agent_suffix: None,
         }
     }
-}
 
-pub(crate) static DEFAULT_API_CONFIG: ApiConfig = ApiConfig::new();
+    /// Sets the time allowed to establish a connection.
+    ///
+    /// # Example
+    ///
+    /// ```rust
+    /// use std::time::Duration;
+    /// use libplasmoid_updater::ApiConfig;
+    ///
+    /// let api = ApiConfig::new().with_connect_timeout(Duration::from_secs(30));
+    /// assert_eq!(api.connect_timeout, Duration::from_secs(30));
+    /// ```
+    pub fn with_connect_timeout(mut self, timeout: Duration) -> Self {
+        self.connect_timeout = timeout;
+        self
+    }
+
+    /// Sets the time allowed for a whole store API request.
+    ///
+    /// # Example
+    ///
+    /// ```rust
+    /// use std::time::Duration;
+    /// use libplasmoid_updater::ApiConfig;
+    ///
+    /// let api = ApiConfig::new().with_request_timeout(Duration::from_secs(120));
+    /// assert_eq!(api.request_timeout, Duration::from_secs(120));
+    /// ```
+    pub fn with_request_timeout(mut self, timeout: Duration) -> Self {
+        self.request_timeout = timeout;
+        self
+    }
+
+    /// Sets the time allowed for a whole package download.
+    ///
+    /// # Example
+    ///
+    /// ```rust
+    /// use std::time::Duration;
+    /// use libplasmoid_updater::ApiConfig;
+    ///
+    /// let api = ApiConfig::new().with_download_timeout(Duration::from_secs(600));
+    /// assert_eq!(api.download_timeout, Duration::from_secs(600));
+    /// ```
+    pub fn with_download_timeout(mut self, timeout: Duration) -> Self {
+        self.download_timeout = timeout;
+        self
+    }
+
+    /// Sets how many times a store request is attempted.
+    ///
+    /// # Example
+    ///
+    /// ```rust
+    /// use libplasmoid_updater::ApiConfig;
+    ///
+    /// let api = ApiConfig::new().with_max_attempts(5);
+    /// assert_eq!(api.max_attempts, 5);
+    /// ```
+    pub fn with_max_attempts(mut self, attempts: u8) -> Self {
+        self.max_attempts = attempts;
+        self
+    }
+
+    /// Sets the delay before the second attempt of a store request.
+    ///
+    /// # Example
+    ///
+    /// ```rust
+    /// use std::time::Duration;
+    /// use libplasmoid_updater::ApiConfig;
+    ///
+    /// let api = ApiConfig::new().with_backoff(Duration::from_secs(1));
+    /// assert_eq!(api.backoff, Duration::from_secs(1));
+    /// ```
+    pub fn with_backoff(mut self, backoff: Duration) -> Self {
+        self.backoff = backoff;
+        self
+    }
+
+    /// Sets the product token appended to the user agent.
+    ///
+    /// # Example
+    ///
+    /// ```rust
+    /// use libplasmoid_updater::ApiConfig;
+    ///
+    /// let api = ApiConfig::new().with_user_agent_suffix("topgrade/16.0");
+    /// assert!(api.user_agent().ends_with(" topgrade/16.0"));
+    /// ```
+    pub fn with_user_agent_suffix(mut self, suffix: impl Into<String>) -> Self {
+        self.user_agent_suffix = Some(suffix.into());
+        self
+    }
+
+    /// Returns the user agent sent with store requests and downloads:
+    /// `plasmoid-updater/<version>`, followed by
+    /// [`Self::user_agent_suffix`] if it is set. Control characters, which
+    /// cannot go in a header, are dropped from the suffix.
+    pub fn user_agent(&self) -> String {
+        let suffix: String = self
+            .user_agent_suffix
+            .as_deref()
+            .unwrap_or_default()
+            .chars()
+            .filter(|c| !c.is_control())
+            .collect();
+        match suffix.trim() {
+            "" => USER_AGENT.to_string(),
+            suffix => format!("{USER_AGENT} {suffix}"),
+        }
+    }
+
+    /// Attempts per store request, never fewer than one.
+    pub(crate) fn attempts(&self) -> u8 {
+        self.max_attempts.max(1)
+    }
+}
 
 #[cfg(test)]
 mod tests {
@@ -45,12 +196,28 @@ mod tests {
 
     #[test]
     fn connect_timeout_is_10_seconds() {
-        assert_eq!(CONNECT_TIMEOUT, Duration::from_secs(10));
+        assert_eq!(ApiConfig::new().connect_timeout, Duration::from_secs(10));
+    }
+
+    #[test]
+    fn default_max_attempts_is_3() {
+        assert_eq!(ApiConfig::DEFAULT_MAX_ATTEMPTS, 3);
+        assert_eq!(ApiConfig::default().attempts(), 3);
+        assert_eq!(ApiConfig::new().with_max_attempts(0).attempts(), 1);
     }
 
     #[test]
-    fn default_max_retries_is_3() {
-        assert_eq!(DEFAULT_MAX_RETRIES, 3);
-        assert_eq!(DEFAULT_API_CONFIG.max_retries, 3);
+    fn user_agent_suffix_follows_crate_token() {
+        assert_eq!(ApiConfig::new().user_agent(), USER_AGENT);
+        assert_eq!(
+            ApiConfig::new()
+                .with_user_agent_suffix("topgrade/16.0\r\n")
+                .user_agent(),
+            format!("{USER_AGENT} topgrade/16.0")
+        );
+        assert_eq!(
+            ApiConfig::new().with_user_agent_suffix(" ").user_agent(),
+            USER_AGENT
+        );
     }
 }
@@ -0,0 +1,190 @@
+// SPDX-License-Identifier: GPL-3.0-or-later
+
+use std::{
+    fmt,
+    process::{Command, Stdio},
+};
+
+use serde::Deserialize;
+
+use crate::{Error, Result};
+
+/// Keyring attribute `service` that [`StoreCredentials::Keyring`] looks up.
+const KEYRING_SERVICE: &str = "plasmoid-updater";
+
+/// Credentials sent with KDE Store API requests, see
+/// [`Config::store_credentials`](crate::Config::store_credentials).
+///
+/// Authenticated OCS requests get higher rate limits. Credentials are only
+/// sent to the store API, never to package download hosts, and are redacted
+/// from `Debug` output and logs.
+///
+/// Deserializes from a table tagged by `kind`, e.g.
+/// `{ kind = "keyring", username = "me" }`.
+///
+/// # Example
+///
+/// ```rust
+/// use libplasmoid_updater::{Config, StoreCredentials};
+///
+/// let config = Config::new().with_store_credentials(StoreCredentials::Token {
+///     token: "0123456789abcdef".to_string(),
+/// });
+/// assert!(!format!("{config:?}").contains("0123456789abcdef"));
+/// ```
+#[derive(Clone, PartialEq, Eq, Deserialize)]
+#[serde(tag = "kind", rename_all = "lowercase")]
+pub enum StoreCredentials {
+    /// HTTP Basic authentication with a store username and password.
+    Basic { username: String, password: String },
+    /// An API token, sent as a bearer token.
+    Token { token: String },
+    /// HTTP Basic authentication with the password kept in the Secret
+    /// Service keyring (KWallet or GNOME Keyring), looked up with
+    /// `secret-tool lookup service plasmoid-updater username <username>`.
+    ///
+    /// Store it once with
+    /// `secret-tool store --label="KDE Store" service plasmoid-updater username <username>`.
+    Keyring { username: String },
+}
+
+impl fmt::Debug for StoreCredentials {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::Basic { username, .. } => f
+                .debug_struct("Basic")
+                .field("username", username)
+                .field("password", &"<redacted>")
+                .finish(),
+            Self::Token { .. } => f
+                .debug_struct("Token")
+                .field("token", &"<redacted>")
+                .finish(),
+            Self::Keyring { username } => f
+                .debug_struct("Keyring")
+                .field("username", username)
+                .finish(),
+        }
+    }
+}
+
+/// Credentials ready to be attached to a request, with any keyring lookup
+/// done.
+#[derive(Clone, PartialEq, Eq)]
+pub(crate) enum Authorization {
+    Basic { username: String, password: String },
+    Bearer(String),
+}
+
+impl fmt::Debug for Authorization {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::Basic { username, .. } => write!(f, "Basic({username}, <redacted>)"),
+            Self::Bearer(_) => f.write_str("Bearer(<redacted>)"),
+        }
+    }
+}
+
+impl StoreCredentials {
+    /// Resolves the credentials, reading the password from the keyring for
+    /// [`Self::Keyring`].
+    pub(crate) fn authorization(&self) -> Result<Authorization> {
+        match self {
+            Self::Basic { username, password } => Ok(Authorization::Basic {
+                username: username.clone(),
+                password: password.clone(),
+            }),
+            Self::Token { token } => Ok(Authorization::Bearer(token.clone())),
+            Self::Keyring { username } => Ok(Authorization::Basic {
+                username: username.clone(),
+                password: keyring_lookup(username)?,
+            }),
+        }
+    }
+}
+
+impl Authorization {
+    /// Adds the `Authorization` header to `request`. reqwest marks the header
+    /// sensitive, so it is never logged.
+    pub(crate) fn apply(
+        &self,
+        request: reqwest::blocking::RequestBuilder,
+    ) -> reqwest::blocking::RequestBuilder {
+        match self {
+            Self::Basic { username, password } => request.basic_auth(username, Some(password)),
+            Self::Bearer(token) => request.bearer_auth(token),
+        }
+    }
+
+    /// Who the requests are made as, for logs.
+    pub(crate) fn describe(&self) -> String {
+        match self {
+            Self::Basic { username, .. } => format!("user {username}"),
+            Self::Bearer(_) => "an API token".to_string(),
+        }
+    }
+}
+
+fn keyring_lookup(username: &str) -> Result<String> {
+    let output = Command::new("secret-tool")
+        .args(["lookup", "service", KEYRING_SERVICE, "username", username])
+        .stdin(Stdio::null())
+        .output()
+        .map_err(|e| Error::other(format!("failed to run secret-tool: {e}")))?;
+    let password = String::from_utf8_lossy(&output.stdout)
+        .trim_end_matches('\n')
+        .to_string();
+    if !output.status.success() || password.is_empty() {
+        return Err(Error::other(format!(
+            "no store password for {username} in the keyring"
+        )));
+    }
+    Ok(password)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn debug_output_redacts_secrets() {
+        let basic = StoreCredentials::Basic {
+            username: "me".to_string(),
+            password: "hunter2".to_string(),
+        };
+        let token = StoreCredentials::Token {
+            token: "s3cret".to_string(),
+        };
+
+        for (credentials, secret) in [(&basic, "hunter2"), (&token, "s3cret")] {
+            let debug = format!("{credentials:?}");
+            assert!(!debug.contains(secret), "{debug}");
+            let debug = format!("{:?}", credentials.authorization().unwrap());
+            assert!(!debug.contains(secret), "{debug}");
+        }
+        assert!(format!("{basic:?}").contains("me"));
+    }
+
+    #[test]
+    fn credentials_deserialize_tagged_by_kind() {
+        let credentials: Vec<StoreCredentials> = serde_json::from_str(
+            r#"[
+                {"kind": "token", "token": "abc"},
+                {"kind": "keyring", "username": "me"}
+            ]"#,
+        )
+        .unwrap();
+
+        assert_eq!(
+            credentials,
+            [
+                StoreCredentials::Token {
+                    token: "abc".to_string()
+                },
+                StoreCredentials::Keyring {
+                    username: "me".to_string()
+                },
+            ]
+        );
+    }
+}
@@ -1,7 +1,23 @@
 // SPDX-License-Identifier: GPL-3.0-or-later
 
+mod backend;
 mod client;
 mod config;
+mod credentials;
 mod ocs_parser;
+mod page_cache;
+mod previews;
+mod snapshot;
+mod stats;
 
+pub use backend::{CacheValidators, FixtureBackend, HttpBackend, HttpResponse};
 pub(crate) use client::ApiClient;
+pub use config::ApiConfig;
+#[cfg(feature = "test-support")]
+pub(crate) use config::DEFAULT_BASE_URL;
+pub use credentials::StoreCredentials;
+pub use previews::PreviewSize;
+pub(crate) use previews::clear as clear_previews;
+pub use snapshot::StoreSnapshot;
+pub(crate) use stats::ApiCounters;
+pub use stats::ApiStats;
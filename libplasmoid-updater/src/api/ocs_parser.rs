@@ -58,6 +58,10 @@ pub(super) struct ContentXml {
     version: String,
     typeid: u16,
     changed: String,
+    changelog: String,
+    personid: String,
+    previewpic1: String,
+    smallpreviewpic1: String,
     download_links: Vec<DownloadLink>,
 }
 
@@ -78,7 +82,7 @@ impl<'de> Deserialize<'de> for StatusCode {
     }
 }
 
-#[derive(Debug, Deserialize)]
+#[derive(Debug, Default, Deserialize)]
 pub(super) struct Data {
     #[serde(default)]
     pub content: Vec<ContentXml>,
@@ -87,12 +91,15 @@ pub(super) struct Data {
 #[derive(Debug, Deserialize)]
 pub(super) struct Response {
     pub meta: Meta,
+    /// Absent in answers to actions such as votes.
+    #[serde(default)]
     pub data: Data,
 }
 
 #[derive(Default)]
 struct DownloadParts {
     url: Option<String>,
+    name: Option<String>,
     version: Option<String>,
     checksum: Option<String>,
     size_kb: Option<u64>,
@@ -103,6 +110,7 @@ impl DownloadParts {
         let url = self.url.filter(|u| !u.is_empty())?;
         Some(DownloadLink {
             url,
+            name: self.name.unwrap_or_default(),
             version: self.version.unwrap_or_default(),
             checksum: self.checksum.filter(|s| !s.is_empty()),
             size_kb: self.size_kb,
@@ -122,6 +130,10 @@ where
         downloads[i].url = map.next_value()?;
         return Ok(true);
     }
+    if let Some(i) = parse_download_index(key, "downloadname") {
+        downloads[i].name = map.next_value()?;
+        return Ok(true);
+    }
     if let Some(i) = parse_download_index(key, "download_version") {
         downloads[i].version = map.next_value()?;
         return Ok(true);
@@ -160,6 +172,10 @@ impl<'de> Deserialize<'de> for ContentXml {
                 let mut version = String::new();
                 let mut typeid: u16 = 0;
                 let mut changed = String::new();
+                let mut changelog = String::new();
+                let mut personid = String::new();
+                let mut previewpic1 = String::new();
+                let mut smallpreviewpic1 = String::new();
                 let mut downloads: [DownloadParts; MAX_DOWNLOAD_LINKS] =
                     std::array::from_fn(|_| DownloadParts::default());
 
@@ -170,6 +186,10 @@ impl<'de> Deserialize<'de> for ContentXml {
                         "version" => version = map.next_value()?,
                         "typeid" => typeid = map.next_value()?,
                         "changed" => changed = map.next_value()?,
+                        "changelog" => changelog = map.next_value()?,
+                        "personid" => personid = map.next_value()?,
+                        "previewpic1" => previewpic1 = map.next_value()?,
+                        "smallpreviewpic1" => smallpreviewpic1 = map.next_value()?,
                         _ => {
                             if !try_parse_download_field(&key, &mut downloads, &mut map)? {
                                 let _ = map.next_value::<serde::de::IgnoredAny>()?;
@@ -184,6 +204,10 @@ impl<'de> Deserialize<'de> for ContentXml {
                     version,
                     typeid,
                     changed,
+                    changelog,
+                    personid,
+                    previewpic1,
+                    smallpreviewpic1,
                     download_links: downloads
                         .into_iter()
                         .filter_map(DownloadParts::into_link)
@@ -215,6 +239,10 @@ impl ContentXml {
             type_id: self.typeid,
             download_links: self.download_links,
             changed_date: self.changed,
+            changelog: self.changelog,
+            author: self.personid,
+            preview_url: self.previewpic1,
+            thumbnail_url: self.smallpreviewpic1,
         }
     }
 }
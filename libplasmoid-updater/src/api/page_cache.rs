@@ -0,0 +1,67 @@
+// SPDX-License-Identifier: GPL-3.0-or-later
+
+use std::{fs, path::PathBuf};
+
+use serde::{Deserialize, Serialize};
+
+use super::backend::{CacheValidators, HttpResponse};
+
+/// Store pages from earlier responses, kept with their cache validators so
+/// they can be revalidated with a conditional request.
+///
+/// Each URL is stored in its own file, named after the MD5 of the URL.
+#[derive(Debug, Clone)]
+pub(crate) struct PageCache {
+    dir: PathBuf,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+pub(crate) struct CachedPage {
+    url: String,
+    pub(crate) validators: CacheValidators,
+    pub(crate) body: String,
+}
+
+impl PageCache {
+    pub(crate) fn new(dir: impl Into<PathBuf>) -> Self {
+        Self { dir: dir.into() }
+    }
+
+    /// The cache under `$XDG_CACHE_HOME/plasmoid-updater/http`.
+    pub(crate) fn default_location() -> Self {
+        Self::new(crate::paths::cache_home().join("plasmoid-updater/http"))
+    }
+
+    fn path(&self, url: &str) -> PathBuf {
+        self.dir.join(format!("{:x}.json", md5::compute(url)))
+    }
+
+    /// Returns the cached page for `url`, if any.
+    pub(crate) fn load(&self, url: &str) -> Option<CachedPage> {
+        let content = fs::read(self.path(url)).ok()?;
+        let page: CachedPage = serde_json::from_slice(&content).ok()?;
+        (page.url == url && !page.validators.is_empty()).then_some(page)
+    }
+
+    /// Caches `response` for `url` if the server sent validators for it.
+    ///
+    /// Failures are logged; the cache is only an optimisation.
+    pub(crate) fn store(&self, url: &str, response: &HttpResponse) {
+        if response.validators.is_empty() {
+            return;
+        }
+
+        let page = CachedPage {
+            url: url.to_string(),
+            validators: response.validators.clone(),
+            body: response.body.clone(),
+        };
+        let written = fs::create_dir_all(&self.dir)
+            .map_err(crate::Error::from)
+            .and_then(|()| Ok(serde_json::to_vec(&page)?))
+            .and_then(|content| Ok(fs::write(self.path(url), content)?));
+        if let Err(e) = written {
+            log::debug!(target: "api", "failed to cache {url}: {e}");
+        }
+    }
+}
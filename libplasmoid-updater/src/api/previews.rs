@@ -0,0 +1,154 @@
+// SPDX-License-Identifier: GPL-3.0-or-later
+
+use std::{
+    fs,
+    path::{Path, PathBuf},
+    time::SystemTime,
+};
+
+use serde::{Deserialize, Serialize};
+
+use crate::{Error, Result, types::StoreEntry};
+
+/// Size the preview cache is pruned to, least recently used first.
+const CACHE_LIMIT: u64 = 64 * 1024 * 1024;
+
+/// Largest preview image downloaded; store previews are far smaller.
+pub(super) const MAX_PREVIEW_SIZE: u64 = 16 * 1024 * 1024;
+
+/// Which of a store entry's preview images to fetch, see
+/// [`download_preview()`](crate::download_preview).
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum PreviewSize {
+    /// The thumbnail the store shows in listings.
+    #[default]
+    Small,
+    /// The full-size screenshot.
+    Large,
+}
+
+/// Returns the preview cache, `$XDG_CACHE_HOME/plasmoid-updater/previews`.
+fn cache_dir() -> PathBuf {
+    crate::paths::cache_home().join("plasmoid-updater/previews")
+}
+
+/// The URL of the `size` preview of `entry`, falling back to the other size
+/// when the store only lists one.
+pub(super) fn preview_url(entry: &StoreEntry, size: PreviewSize) -> Result<&str> {
+    let (preferred, fallback) = match size {
+        PreviewSize::Small => (&entry.thumbnail_url, &entry.preview_url),
+        PreviewSize::Large => (&entry.preview_url, &entry.thumbnail_url),
+    };
+    [preferred, fallback]
+        .into_iter()
+        .find(|url| !url.is_empty())
+        .map(String::as_str)
+        .ok_or_else(|| Error::other(format!("{} has no preview image", entry.name)))
+}
+
+/// Returns the cached copy of the preview at `url`, or runs `download` and
+/// caches the bytes it returns.
+pub(super) fn fetch(
+    content_id: u64,
+    url: &str,
+    download: impl FnOnce() -> Result<Vec<u8>>,
+) -> Result<PathBuf> {
+    fetch_in(&cache_dir(), content_id, url, download)
+}
+
+fn fetch_in(
+    dir: &Path,
+    content_id: u64,
+    url: &str,
+    download: impl FnOnce() -> Result<Vec<u8>>,
+) -> Result<PathBuf> {
+    let path = dir.join(cache_key(content_id, url));
+    if path.is_file() {
+        // Pruning goes by modification time, so a hit counts as recent use.
+        if let Ok(file) = fs::File::options().append(true).open(&path) {
+            let _ = file.set_modified(SystemTime::now());
+        }
+        return Ok(path);
+    }
+
+    let image = download()?;
+    fs::create_dir_all(dir)?;
+    let partial = path.with_extension("partial");
+    fs::write(&partial, image)?;
+    fs::rename(&partial, &path)?;
+    crate::installer::prune_cache(dir, CACHE_LIMIT);
+    Ok(path)
+}
+
+/// File name of a cached preview. The URL is hashed in, so a replaced
+/// screenshot is fetched again; the extension is kept for image viewers.
+fn cache_key(content_id: u64, url: &str) -> String {
+    let name = url.split(['?', '#']).next().unwrap_or(url);
+    let extension = name
+        .rsplit_once('/')
+        .map_or(name, |(_, file)| file)
+        .rsplit_once('.')
+        .map(|(_, ext)| ext.to_ascii_lowercase())
+        .filter(|ext| {
+            (1..=4).contains(&ext.len()) && ext.chars().all(|c| c.is_ascii_alphanumeric())
+        })
+        .unwrap_or_else(|| "img".to_string());
+    format!("{content_id}-{:x}.{extension}", md5::compute(url))
+}
+
+/// Deletes the preview cache. Returns the bytes freed.
+pub(crate) fn clear() -> Result<u64> {
+    let dir = cache_dir();
+    let bytes = crate::du::dir_size(&dir);
+    match fs::remove_dir_all(&dir) {
+        Ok(()) => Ok(bytes),
+        Err(e) if e.kind() == std::io::ErrorKind::NotFound => Ok(0),
+        Err(e) => Err(e.into()),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn cache_keys_keep_image_extensions() {
+        let key = cache_key(1000, "https://images.pling.com/img/00/preview.PNG?v=2");
+        assert!(key.starts_with("1000-") && key.ends_with(".png"), "{key}");
+        assert!(cache_key(1000, "https://example.org/thumb").ends_with(".img"));
+        assert_ne!(
+            cache_key(1000, "https://example.org/a.png"),
+            cache_key(1000, "https://example.org/b.png")
+        );
+    }
+
+    #[test]
+    fn cached_previews_are_not_downloaded_again() {
+        let dir = tempfile::tempdir().unwrap();
+        let url = "https://example.org/preview.png";
+
+        let path = fetch_in(dir.path(), 1000, url, || Ok(b"png".to_vec())).unwrap();
+        let again = fetch_in(dir.path(), 1000, url, || panic!("downloaded twice")).unwrap();
+
+        assert_eq!(path, again);
+        assert_eq!(fs::read(&path).unwrap(), b"png");
+        assert!(!path.with_extension("partial").exists());
+    }
+
+    #[test]
+    fn missing_previews_fall_back_to_the_other_size() {
+        let mut entry = StoreEntry {
+            name: "Clock".to_string(),
+            preview_url: "https://example.org/large.png".to_string(),
+            ..Default::default()
+        };
+        assert_eq!(
+            preview_url(&entry, PreviewSize::Small).unwrap(),
+            "https://example.org/large.png"
+        );
+
+        entry.preview_url.clear();
+        assert!(preview_url(&entry, PreviewSize::Large).is_err());
+    }
+}
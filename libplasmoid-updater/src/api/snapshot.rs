@@ -0,0 +1,111 @@
+// SPDX-License-Identifier: GPL-3.0-or-later
+
+use std::{collections::BTreeMap, fs, path::Path};
+
+use serde::{Deserialize, Serialize};
+
+use crate::{
+    types::{ComponentType, StoreEntry},
+    {Error, Result},
+};
+
+/// Store metadata captured by [`create_snapshot`](crate::create_snapshot).
+///
+/// Setting a snapshot with
+/// [`Config::with_store_snapshot`](crate::Config::with_store_snapshot) makes
+/// checks and updates resolve against it instead of the live store, so every
+/// machine in a rollout sees the same versions. Packages are still downloaded
+/// from the URLs recorded in the snapshot.
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct StoreSnapshot {
+    /// When the snapshot was taken (RFC 3339).
+    pub created_at: String,
+    entries: Vec<StoreEntry>,
+}
+
+impl StoreSnapshot {
+    /// Builds a snapshot of `entries`, keeping one entry per content ID.
+    pub(crate) fn from_entries(entries: impl IntoIterator<Item = StoreEntry>) -> Self {
+        let entries: BTreeMap<u64, StoreEntry> = entries.into_iter().map(|e| (e.id, e)).collect();
+        Self {
+            created_at: chrono::Local::now().to_rfc3339(),
+            entries: entries.into_values().collect(),
+        }
+    }
+
+    /// Reads a snapshot written by [`save`](Self::save).
+    pub fn load(path: impl AsRef<Path>) -> Result<Self> {
+        let content = read(path.as_ref())?;
+        Ok(serde_json::from_slice(&content)?)
+    }
+
+    /// Reads a snapshot and verifies its detached minisign signature
+    /// (`<path>.minisig`) against `trusted_keys`, see
+    /// [`Config::trusted_keys`](crate::Config::trusted_keys).
+    ///
+    /// # Errors
+    ///
+    /// Returns [`Error::SignatureInvalid`] if the signature is missing, or
+    /// was not made by one of `trusted_keys` over this exact file.
+    pub fn load_signed(path: impl AsRef<Path>, trusted_keys: &[String]) -> Result<Self> {
+        let path = path.as_ref();
+        let content = read(path)?;
+        crate::signing::verify(path, &content, trusted_keys)?;
+        Ok(serde_json::from_slice(&content)?)
+    }
+
+    /// Writes the snapshot to `path` as JSON.
+    pub fn save(&self, path: impl AsRef<Path>) -> Result<()> {
+        fs::write(path, serde_json::to_vec_pretty(self)?)?;
+        Ok(())
+    }
+
+    /// Number of store entries in the snapshot.
+    pub fn len(&self) -> usize {
+        self.entries.len()
+    }
+
+    /// Returns `true` if the snapshot holds no store entries.
+    pub fn is_empty(&self) -> bool {
+        self.entries.is_empty()
+    }
+
+    /// The entry with content ID `id`.
+    pub(crate) fn entry(&self, id: u64) -> Option<&StoreEntry> {
+        self.entries.iter().find(|e| e.id == id)
+    }
+
+    /// The entries listed in any of `categories`, like a catalog fetch.
+    pub(crate) fn entries_in(&self, categories: &[ComponentType]) -> Vec<StoreEntry> {
+        self.entries
+            .iter()
+            .filter(|e| categories.iter().any(|ct| ct.matches_type_id(e.type_id)))
+            .cloned()
+            .collect()
+    }
+}
+
+fn read(path: &Path) -> Result<Vec<u8>> {
+    fs::read(path)
+        .map_err(|e| Error::other(format!("failed to read snapshot {}: {e}", path.display())))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn load_signed_reads_verified_snapshot() {
+        let path = concat!(
+            env!("CARGO_MANIFEST_DIR"),
+            "/testdata/signing/snapshot.json"
+        );
+        let key = include_str!("../../testdata/signing/trusted.pub").to_string();
+
+        let snapshot = StoreSnapshot::load_signed(path, &[key]).unwrap();
+
+        assert_eq!(snapshot.len(), 1);
+        assert_eq!(snapshot.entry(1000001).unwrap().version, "2.0");
+        assert_eq!(snapshot.entries_in(&[ComponentType::PlasmaWidget]).len(), 1);
+    }
+}
@@ -0,0 +1,113 @@
+// SPDX-License-Identifier: GPL-3.0-or-later
+
+use std::sync::atomic::{AtomicU64, AtomicUsize, Ordering};
+
+use serde::{Deserialize, Serialize};
+
+/// How much a run used the KDE Store and download hosts.
+///
+/// Returned in [`CheckResult::api_stats`](crate::CheckResult::api_stats) and
+/// [`UpdateResult::api_stats`](crate::UpdateResult::api_stats), and sent with
+/// the [`Event::Summary`](crate::Event::Summary) event.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct ApiStats {
+    /// HTTP requests sent, retries and package downloads included.
+    pub requests: usize,
+    /// Requests repeated after a transient failure or a rate limit.
+    pub retries: usize,
+    /// Responses telling the client to slow down (HTTP 429 or the OCS
+    /// equivalent).
+    pub rate_limited: usize,
+    /// Bytes received in store API responses and package downloads. Pages
+    /// answered with `304 Not Modified` count nothing.
+    pub bytes_downloaded: u64,
+}
+
+impl ApiStats {
+    /// One-line summary for verbose output, e.g.
+    /// `12 requests (1 retry, 0 rate limited), 2.3 MiB`.
+    pub fn summary(&self) -> String {
+        let requests = if self.requests == 1 {
+            "request"
+        } else {
+            "requests"
+        };
+        let retries = if self.retries == 1 {
+            "retry"
+        } else {
+            "retries"
+        };
+        format!(
+            "{} {requests} ({} {retries}, {} rate limited), {:.1} MiB",
+            self.requests,
+            self.retries,
+            self.rate_limited,
+            self.bytes_downloaded as f64 / (1024.0 * 1024.0)
+        )
+    }
+}
+
+/// Counters behind [`ApiStats`], shared by the API client and the installer.
+#[derive(Debug, Default)]
+pub(crate) struct ApiCounters {
+    requests: AtomicUsize,
+    retries: AtomicUsize,
+    rate_limited: AtomicUsize,
+    bytes_downloaded: AtomicU64,
+}
+
+impl ApiCounters {
+    pub(crate) fn request(&self) {
+        self.requests.fetch_add(1, Ordering::Relaxed);
+    }
+
+    pub(crate) fn retry(&self) {
+        self.retries.fetch_add(1, Ordering::Relaxed);
+    }
+
+    pub(crate) fn rate_limited(&self) {
+        self.rate_limited.fetch_add(1, Ordering::Relaxed);
+    }
+
+    pub(crate) fn received(&self, bytes: u64) {
+        self.bytes_downloaded.fetch_add(bytes, Ordering::Relaxed);
+    }
+
+    pub(crate) fn snapshot(&self) -> ApiStats {
+        ApiStats {
+            requests: self.requests.load(Ordering::Relaxed),
+            retries: self.retries.load(Ordering::Relaxed),
+            rate_limited: self.rate_limited.load(Ordering::Relaxed),
+            bytes_downloaded: self.bytes_downloaded.load(Ordering::Relaxed),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn summary_pluralizes_counts() {
+        let stats = ApiStats {
+            requests: 12,
+            retries: 1,
+            rate_limited: 0,
+            bytes_downloaded: 3 * 1024 * 1024 / 2,
+        };
+        assert_eq!(
+            stats.summary(),
+            "12 requests (1 retry, 0 rate limited), 1.5 MiB"
+        );
+
+        let stats = ApiStats {
+            requests: 1,
+            retries: 2,
+            ..Default::default()
+        };
+        assert_eq!(
+            stats.summary(),
+            "1 request (2 retries, 0 rate limited), 0.0 MiB"
+        );
+    }
+}
@@ -0,0 +1,42 @@
+// SPDX-License-Identifier: GPL-3.0-or-later
+
+use std::sync::{
+    Arc,
+    atomic::{AtomicBool, Ordering},
+};
+
+/// Cooperative cancellation for a running [`update`](crate::update).
+///
+/// Cloning shares the flag, so one clone can be handed to the
+/// [`Config`](crate::Config) and another kept to cancel from a different
+/// thread. Components already being installed finish (or roll back) normally;
+/// those not yet started are reported as skipped.
+///
+/// # Example
+///
+/// ```rust
+/// use libplasmoid_updater::{CancelToken, Config};
+///
+/// let token = CancelToken::new();
+/// let config = Config::new().with_cancel_token(token.clone());
+/// // later, from another thread:
+/// token.cancel();
+/// ```
+#[derive(Debug, Clone, Default)]
+pub struct CancelToken(Arc<AtomicBool>);
+
+impl CancelToken {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Requests cancellation.
+    pub fn cancel(&self) {
+        self.0.store(true, Ordering::Release);
+    }
+
+    /// Returns `true` once [`cancel`](Self::cancel) has been called.
+    pub fn is_cancelled(&self) -> bool {
+        self.0.load(Ordering::Acquire)
+    }
+}
@@ -0,0 +1,162 @@
+// SPDX-License-Identifier: GPL-3.0-or-later
+
+use std::cmp::Ordering;
+
+use crate::version;
+
+/// Tags that end a line of text when the changelog is HTML.
+const BLOCK_TAGS: &[&str] = &[
+    "br", "p", "li", "div", "ul", "ol", "h1", "h2", "h3", "h4", "h5", "h6",
+];
+
+/// Longest line still taken as a version heading, in characters.
+const MAX_HEADING_LEN: usize = 80;
+
+/// Returns the entries of a store `changelog` published after `installed`,
+/// up to and including `latest`.
+///
+/// Changelogs with version headings (`## 2.1`, `Version 2.1 (2024-05-01)`,
+/// `v2.1:`) are split into sections and only the newer ones are kept. A
+/// changelog without headings describes the latest release, so all of it is
+/// new. HTML is reduced to plain text and list markers are dropped.
+pub(crate) fn changes_since(changelog: &str, installed: &str, latest: &str) -> Vec<String> {
+    let text = plain_text(changelog);
+    let lines: Vec<&str> = text.lines().map(str::trim).collect();
+    if !lines.iter().any(|line| heading_version(line).is_some()) {
+        return lines.into_iter().filter_map(entry).collect();
+    }
+
+    let mut changes = Vec::new();
+    // Text before the first heading is usually a title, never a release.
+    let mut in_range = false;
+    for line in lines {
+        if let Some(v) = heading_version(line) {
+            in_range = version::compare(v, installed) == Ordering::Greater
+                && version::compare(v, latest) != Ordering::Greater;
+        } else if in_range && let Some(change) = entry(line) {
+            changes.push(change);
+        }
+    }
+    changes
+}
+
+/// Returns the version a line announces, if it is a version heading.
+fn heading_version(line: &str) -> Option<&str> {
+    if line.chars().count() > MAX_HEADING_LEN {
+        return None;
+    }
+    let line = line.trim_start_matches(['#', '*', '-', '=', '•', ' ']);
+    let lower = line.to_ascii_lowercase();
+    let mut keyword = false;
+    let mut rest = line;
+    for prefix in ["version ", "release ", "ver. ", "ver "] {
+        if lower.starts_with(prefix) {
+            rest = rest[prefix.len()..].trim_start();
+            keyword = true;
+            break;
+        }
+    }
+    if rest.starts_with(['v', 'V']) && rest[1..].starts_with(|c: char| c.is_ascii_digit()) {
+        rest = &rest[1..];
+        keyword = true;
+    }
+
+    let end = rest
+        .find(|c: char| !c.is_ascii_digit() && c != '.')
+        .unwrap_or(rest.len());
+    let v = rest[..end].trim_end_matches('.');
+    if !v.starts_with(|c: char| c.is_ascii_digit()) || !(keyword || v.contains('.')) {
+        return None;
+    }
+
+    // "1.5 seconds faster" is a change, "1.5 (2024-05-01)" a heading.
+    let tail = rest[end..].trim_start_matches(['*', '#']).trim_start();
+    let heading = tail.is_empty()
+        || tail.starts_with([':', '(', '[', '-', '–', '—', ','])
+        || tail.starts_with(|c: char| c.is_ascii_digit());
+    heading.then_some(v)
+}
+
+/// A change from one line of text, without its list marker.
+fn entry(line: &str) -> Option<String> {
+    let text = line
+        .trim_start_matches(['-', '*', '•', '+'])
+        .trim()
+        .trim_end_matches(['#', '='])
+        .trim();
+    (!text.is_empty() && !text.chars().all(|c| c == '-' || c == '=')).then(|| text.to_string())
+}
+
+/// Reduces HTML to text, turning block tags into line breaks.
+fn plain_text(changelog: &str) -> String {
+    let mut text = String::with_capacity(changelog.len());
+    let mut tag: Option<String> = None;
+    for c in changelog.chars() {
+        match (&mut tag, c) {
+            (None, '<') => tag = Some(String::new()),
+            (Some(name), '>') => {
+                let name = name.trim_start_matches('/').to_ascii_lowercase();
+                let name = name.split([' ', '/']).next().unwrap_or_default();
+                if BLOCK_TAGS.contains(&name) {
+                    text.push('\n');
+                }
+                tag = None;
+            }
+            (Some(name), c) => name.push(c),
+            (None, c) => text.push(c),
+        }
+    }
+    text.replace("\r\n", "\n")
+        .replace("&nbsp;", " ")
+        .replace("&lt;", "<")
+        .replace("&gt;", ">")
+        .replace("&quot;", "\"")
+        .replace("&#39;", "'")
+        .replace("&amp;", "&")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn keeps_sections_between_installed_and_latest() {
+        let changelog = "# Changelog\n\n## 2.1 (2026-03-01)\n- Fix tooltip\n\n\
+                         ## v2.0\n* New layout\n* Faster refresh\n\n## 1.9\n- Old fix\n";
+
+        assert_eq!(
+            changes_since(changelog, "1.9", "2.1"),
+            ["Fix tooltip", "New layout", "Faster refresh"]
+        );
+        assert_eq!(changes_since(changelog, "2.0", "2.0"), Vec::<String>::new());
+    }
+
+    #[test]
+    fn changelog_without_headings_is_all_new() {
+        let changelog = "<p>Plasma 6 port</p><ul><li>New icon &amp; colors</li><li>1.5 seconds faster startup</li></ul>";
+
+        assert_eq!(
+            changes_since(changelog, "1.0", "2.0"),
+            [
+                "Plasma 6 port",
+                "New icon & colors",
+                "1.5 seconds faster startup"
+            ]
+        );
+    }
+
+    #[test]
+    fn recognizes_heading_styles() {
+        for (line, expected) in [
+            ("Version 3 (2026-01-01)", Some("3")),
+            ("v1.2.0:", Some("1.2.0")),
+            ("**1.4** - 2025-12-24", Some("1.4")),
+            ("1.2.3", Some("1.2.3")),
+            ("1.5 seconds faster", None),
+            ("2 new options", None),
+            ("Added version check", None),
+        ] {
+            assert_eq!(heading_version(line), expected, "{line}");
+        }
+    }
+}
@@ -0,0 +1,71 @@
+// SPDX-License-Identifier: GPL-3.0-or-later
+
+use std::collections::HashMap;
+
+use serde::Deserialize;
+
+use crate::{Result, api::ApiClient, version::normalize_version};
+
+#[derive(Debug, Clone, Deserialize)]
+struct Advisory {
+    version: String,
+    reason: String,
+}
+
+/// Known-broken store releases by content ID, as published in the advisory
+/// feed:
+///
+/// ```json
+/// { "1234567": [{ "version": "2.1.0", "reason": "crashes plasmashell on start" }] }
+/// ```
+#[derive(Debug, Default, Deserialize)]
+#[serde(transparent)]
+pub(crate) struct Advisories(HashMap<u64, Vec<Advisory>>);
+
+impl Advisories {
+    pub(crate) fn parse(json: &str) -> Result<Self> {
+        Ok(serde_json::from_str(json)?)
+    }
+
+    pub(crate) fn fetch(client: &ApiClient, url: &str) -> Result<Self> {
+        Self::parse(&client.fetch_text(url)?)
+    }
+
+    /// Returns why `version` of `content_id` is listed as broken, if it is.
+    pub(crate) fn reason(&self, content_id: u64, version: &str) -> Option<&str> {
+        let version = normalize_version(version);
+        self.0
+            .get(&content_id)?
+            .iter()
+            .find(|advisory| normalize_version(&advisory.version) == version)
+            .map(|advisory| advisory.reason.as_str())
+    }
+}
+
+/// Fetches the advisory feed at `url`.
+///
+/// The feed is advisory: if it cannot be fetched, the check goes on without it.
+pub(crate) fn fetch(client: &ApiClient, url: &str) -> Option<Advisories> {
+    Advisories::fetch(client, url)
+        .inspect_err(
+            |e| log::warn!(target: "advisory", "failed to fetch advisories from {url}: {e}"),
+        )
+        .ok()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn listed_versions_are_held() {
+        let advisories = Advisories::parse(
+            r#"{"1000": [{"version": "v2.0", "reason": "crashes plasmashell"}]}"#,
+        )
+        .unwrap();
+
+        assert_eq!(advisories.reason(1000, "2.0"), Some("crashes plasmashell"));
+        assert_eq!(advisories.reason(1000, "2.1"), None);
+        assert_eq!(advisories.reason(2000, "2.0"), None);
+    }
+}
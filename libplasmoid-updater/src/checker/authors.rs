@@ -0,0 +1,129 @@
+// SPDX-License-Identifier: GPL-3.0-or-later
+
+use std::{
+    collections::HashMap,
+    fs,
+    path::{Path, PathBuf},
+};
+
+use serde::{Deserialize, Serialize};
+
+use crate::{Result, types::AvailableUpdate};
+
+/// The store author of each content ID as first seen, so a check notices
+/// when an entry changes hands, see
+/// [`AvailableUpdate::previous_author`].
+///
+/// An author is recorded when a check first resolves the entry, and
+/// replaced only once an update published by the new author is installed.
+#[derive(Debug, Default, Serialize, Deserialize)]
+pub(crate) struct AuthorLedger {
+    authors: HashMap<u64, String>,
+}
+
+/// Returns the ledger file, `$XDG_STATE_HOME/plasmoid-updater/authors.json`.
+/// Kept as state rather than cache: losing it would accept any change.
+fn ledger_path() -> PathBuf {
+    crate::paths::state_home().join("plasmoid-updater/authors.json")
+}
+
+impl AuthorLedger {
+    /// Loads the ledger, or an empty one if there is none or it is
+    /// unreadable.
+    pub(crate) fn load() -> Self {
+        Self::load_from(&ledger_path())
+    }
+
+    fn load_from(path: &Path) -> Self {
+        fs::read(path)
+            .ok()
+            .and_then(|content| serde_json::from_slice(&content).ok())
+            .unwrap_or_default()
+    }
+
+    /// Records `author` for `content_id` if none is recorded yet. Returns
+    /// the recorded author if it is someone else.
+    pub(crate) fn observe(&mut self, content_id: u64, author: &str) -> Option<String> {
+        if author.is_empty() {
+            return None;
+        }
+        let recorded = self
+            .authors
+            .entry(content_id)
+            .or_insert_with(|| author.to_string());
+        (recorded != author).then(|| recorded.clone())
+    }
+
+    /// Records the authors of installed updates as the ones to compare
+    /// against from now on.
+    pub(crate) fn accept(&mut self, installed: &[&AvailableUpdate]) {
+        for update in installed.iter().filter(|u| !u.author.is_empty()) {
+            self.authors
+                .insert(update.content_id, update.author.clone());
+        }
+    }
+
+    /// Writes the ledger. Failures are logged, as a check should not fail
+    /// over them.
+    pub(crate) fn save(&self) {
+        if let Err(e) = self.save_to(&ledger_path()) {
+            log::warn!(target: "checker", "failed to save the store author ledger: {e}");
+        }
+    }
+
+    fn save_to(&self, path: &Path) -> Result<()> {
+        if let Some(parent) = path.parent() {
+            fs::create_dir_all(parent)?;
+        }
+        let partial = path.with_extension("json.partial");
+        fs::write(&partial, serde_json::to_vec(self)?)?;
+        fs::rename(&partial, path)?;
+        Ok(())
+    }
+}
+
+/// Records the authors of the installed `updates` in the ledger.
+pub(crate) fn accept_authors(updates: &[&AvailableUpdate]) {
+    if updates.iter().all(|u| u.author.is_empty()) {
+        return;
+    }
+    let mut ledger = AuthorLedger::load();
+    ledger.accept(updates);
+    ledger.save();
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn first_author_is_kept_until_accepted() {
+        let mut ledger = AuthorLedger::default();
+
+        assert_eq!(ledger.observe(1000, "alice"), None);
+        assert_eq!(ledger.observe(1000, "mallory"), Some("alice".to_string()));
+        assert_eq!(ledger.observe(1000, "mallory"), Some("alice".to_string()));
+        assert_eq!(ledger.observe(1000, ""), None);
+
+        ledger.authors.insert(1000, "mallory".to_string());
+        assert_eq!(ledger.observe(1000, "mallory"), None);
+    }
+
+    #[test]
+    fn ledger_survives_a_round_trip() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("authors.json");
+        let mut ledger = AuthorLedger::default();
+        ledger.observe(1000, "alice");
+
+        ledger.save_to(&path).unwrap();
+
+        let mut loaded = AuthorLedger::load_from(&path);
+        assert_eq!(loaded.observe(1000, "bob"), Some("alice".to_string()));
+        assert!(
+            AuthorLedger::load_from(&dir.path().join("missing"))
+                .authors
+                .is_empty()
+        );
+    }
+}
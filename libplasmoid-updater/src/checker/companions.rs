@@ -0,0 +1,144 @@
+// SPDX-License-Identifier: GPL-3.0-or-later
+
+use std::fs;
+
+use crate::types::{Companion, ComponentType, InstalledComponent};
+
+/// Settings of a global theme's `contents/defaults` that name another
+/// component: section, key and the type of the component.
+const COMPANION_KEYS: &[(&str, &str, ComponentType)] = &[
+    ("[plasmarc][Theme]", "name", ComponentType::PlasmaStyle),
+    (
+        "[kdeglobals][General]",
+        "ColorScheme",
+        ComponentType::ColorScheme,
+    ),
+    ("[kdeglobals][Icons]", "Theme", ComponentType::IconTheme),
+    (
+        "[kcminputrc][Mouse]",
+        "cursorTheme",
+        ComponentType::CursorTheme,
+    ),
+    (
+        "[kwinrc][org.kde.kdecoration2]",
+        "theme",
+        ComponentType::AuroraeDecoration,
+    ),
+    ("[ksplashrc][KSplash]", "Theme", ComponentType::SplashScreen),
+];
+
+/// KWin names Aurorae decorations `__aurorae__svg__<theme>`; other
+/// decorations are compiled plugins the store does not serve.
+const AURORAE_PREFIX: &str = "__aurorae__svg__";
+
+/// Returns the components the defaults of global theme `theme` apply, in
+/// the order they are listed. Empty for other types and themes without
+/// defaults.
+pub(crate) fn suggest(theme: &InstalledComponent) -> Vec<Companion> {
+    if theme.component_type != ComponentType::GlobalTheme {
+        return Vec::new();
+    }
+    let Ok(defaults) = fs::read_to_string(theme.path.join("contents/defaults")) else {
+        return Vec::new();
+    };
+    parse_defaults(&defaults)
+        .into_iter()
+        // Most themes bring their own splash screen in the same package.
+        .filter(|(ct, name)| *ct != ComponentType::SplashScreen || *name != theme.directory_name)
+        .map(|(component_type, name)| {
+            let mut companion = Companion {
+                component_type,
+                name,
+                installed: false,
+            };
+            companion.installed = is_installed(&companion);
+            companion
+        })
+        .collect()
+}
+
+fn parse_defaults(defaults: &str) -> Vec<(ComponentType, String)> {
+    let mut found: Vec<(ComponentType, String)> = Vec::new();
+    let mut section = "";
+    for line in defaults.lines().map(str::trim) {
+        if line.starts_with('[') {
+            section = line;
+            continue;
+        }
+        let Some((key, value)) = line.split_once('=') else {
+            continue;
+        };
+        let Some(&(_, _, component_type)) = COMPANION_KEYS
+            .iter()
+            .find(|&&(s, k, _)| s == section && k == key.trim())
+        else {
+            continue;
+        };
+        let value = value.trim();
+        let name = match component_type {
+            ComponentType::AuroraeDecoration => match value.strip_prefix(AURORAE_PREFIX) {
+                Some(name) => name,
+                None => continue,
+            },
+            _ => value,
+        };
+        if !name.is_empty()
+            && !found
+                .iter()
+                .any(|(ct, n)| *ct == component_type && n == name)
+        {
+            found.push((component_type, name.to_string()));
+        }
+    }
+    found
+}
+
+fn is_installed(companion: &Companion) -> bool {
+    let name = companion.directory_name();
+    let ct = companion.component_type;
+    std::iter::once(ct.user_path())
+        .chain(ct.system_paths())
+        .any(|dir| dir.join(&name).exists())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn defaults_name_style_colors_icons_and_aurorae_decoration() {
+        let defaults = "\
+[kdeglobals][General]
+ColorScheme=SweetDark
+
+[kdeglobals][Icons]
+Theme=candy-icons
+
+[kdeglobals][KDE]
+widgetStyle=Breeze
+
+[plasmarc][Theme]
+name=Sweet
+
+[kwinrc][org.kde.kdecoration2]
+library=org.kde.kwin.aurorae
+theme=__aurorae__svg__Sweet-Dark
+";
+
+        assert_eq!(
+            parse_defaults(defaults),
+            [
+                (ComponentType::ColorScheme, "SweetDark".to_string()),
+                (ComponentType::IconTheme, "candy-icons".to_string()),
+                (ComponentType::PlasmaStyle, "Sweet".to_string()),
+                (ComponentType::AuroraeDecoration, "Sweet-Dark".to_string()),
+            ]
+        );
+    }
+
+    #[test]
+    fn plugin_decorations_are_not_companions() {
+        let defaults = "[kwinrc][org.kde.kdecoration2]\nlibrary=org.kde.breeze\ntheme=Breeze\n";
+        assert!(parse_defaults(defaults).is_empty());
+    }
+}
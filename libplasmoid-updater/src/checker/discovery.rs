@@ -1,17 +1,31 @@
 // SPDX-License-Identifier: GPL-3.0-or-later
 
-use std::{collections::HashSet, fs, path::Path};
+use std::{
+    collections::{HashMap, HashSet},
+    fs,
+    os::unix::fs::MetadataExt,
+    path::{Path, PathBuf},
+};
+
+use nix::unistd::{Uid, User};
 
 use crate::{
     Result, registry,
-    types::{ComponentType, InstalledComponent, PackageMetadata},
+    types::{ComponentType, InstalledComponent, PackageMetadata, UnrecognizedComponent, UserScope},
 };
 
 /// Discovers all installed Plasmoids.
 ///
 /// When `system` is `true`, scans system-wide directories (`/usr/share/...`);
-/// otherwise scans user directories (`~/.local/share/...`).
-pub(crate) fn find_installed(system: bool) -> Result<Vec<InstalledComponent>> {
+/// otherwise scans user directories (`~/.local/share/...`). Only components whose
+/// type passes `include` are returned; directories holding no included type are
+/// not scanned at all. System components owned by a distro package are flagged
+/// with `managed_by_distro`, widgets placed in the user's Plasma layout with
+/// `in_use`.
+pub(crate) fn find_installed(
+    system: bool,
+    include: impl Fn(ComponentType) -> bool,
+) -> Result<Vec<InstalledComponent>> {
     let types = if system {
         ComponentType::all()
     } else {
@@ -22,42 +36,265 @@ pub(crate) fn find_installed(system: bool) -> Result<Vec<InstalledComponent>> {
     let mut scanned_dirs = HashSet::new();
 
     for &component_type in types {
+        if !component_type
+            .shared_path_types()
+            .iter()
+            .any(|&ct| include(ct))
+        {
+            continue;
+        }
+
         if component_type.registry_only() {
             let registry_components = registry::scan_registry_components(component_type)?;
             components.extend(registry_components);
             continue;
         }
 
-        let path = if system {
-            component_type.system_path()
-        } else {
-            component_type.user_path()
-        };
+        for path in package_roots(component_type, system) {
+            if !scanned_dirs.insert(path.clone()) {
+                continue;
+            }
 
-        if path.as_os_str().is_empty() || !path.exists() {
-            continue;
+            // For shared directories (e.g., plasma/look-and-feel), load registry
+            // maps for all component types that use this path so we can assign the
+            // correct type based on which registry file contains the entry.
+            let shared_types = component_type.shared_path_types();
+            let registry_maps: Vec<_> = shared_types
+                .iter()
+                .map(|&ct| (ct, registry::load_registry_map(ct, system)))
+                .collect();
+
+            let discovered = scan_directory(&path, component_type, system, &registry_maps)?;
+            components.extend(discovered);
         }
+    }
 
-        if !scanned_dirs.insert(path.clone()) {
-            continue;
+    // Unmodelled registries are found on disk rather than listed by type.
+    for component_type in registry::generic_types() {
+        if include(component_type) {
+            components.extend(registry::scan_registry_components(component_type)?);
         }
+    }
 
-        // For shared directories (e.g., plasma/look-and-feel), load registry
-        // maps for all component types that use this path so we can assign the
-        // correct type based on which registry file contains the entry.
-        let shared_types = component_type.shared_path_types();
-        let registry_maps: Vec<_> = shared_types
-            .iter()
-            .map(|&ct| (ct, registry::load_registry_map(ct)))
-            .collect();
+    // The same package in several XDG data dirs: Plasma loads the copy from
+    // the first one, so later copies are not listed.
+    let mut seen = HashSet::new();
+    components.retain(|c| {
+        c.component_type.registry_only()
+            || seen.insert((c.component_type, c.directory_name.clone()))
+    });
+
+    // Shared directories are scanned once for all their types; drop the
+    // excluded ones now that each component's real type is known.
+    components.retain(|c| include(c.component_type));
 
-        let discovered = scan_directory(&path, component_type, system, &registry_maps)?;
-        components.extend(discovered);
+    if system {
+        super::distro::mark_distro_owned(&mut components);
+    } else {
+        mark_shadowed(&mut components, ComponentType::system_paths);
     }
+    super::in_use::mark_in_use(&mut components);
 
     Ok(components)
 }
 
+/// Finds directories among the installed packages that look like packages
+/// but whose metadata cannot be read, which [`find_installed`] skips.
+///
+/// A directory counts when it has a `contents/` directory or a metadata file.
+/// Its type is guessed from the directory it sits in, the registry entry
+/// naming it, and for `look-and-feel` from its layout: a package with a
+/// splash screen but no `contents/defaults` is a splash screen.
+pub(crate) fn find_unrecognized(
+    system: bool,
+    include: impl Fn(ComponentType) -> bool,
+) -> Vec<UnrecognizedComponent> {
+    let types = if system {
+        ComponentType::all()
+    } else {
+        ComponentType::all_user()
+    };
+
+    let mut found = Vec::new();
+    let mut scanned_dirs = HashSet::new();
+
+    for &component_type in types {
+        let shared_types = component_type.shared_path_types();
+        if component_type.registry_only() || !shared_types.iter().any(|&ct| include(ct)) {
+            continue;
+        }
+
+        for root in package_roots(component_type, system) {
+            if !scanned_dirs.insert(root.clone()) {
+                continue;
+            }
+            let registry_maps: Vec<_> = shared_types
+                .iter()
+                .map(|&ct| (ct, registry::load_registry_map(ct, system)))
+                .collect();
+            let Ok(entries) = fs::read_dir(&root) else {
+                continue;
+            };
+
+            for path in entries.flatten().map(|e| e.path()) {
+                let Some(directory_name) = path.file_name().and_then(|n| n.to_str()) else {
+                    continue;
+                };
+                if !path.is_dir() || read_package_metadata(&path).is_some() {
+                    continue;
+                }
+                let Some(reason) = unreadable_metadata(&path) else {
+                    continue;
+                };
+                let component_type = registry_maps
+                    .iter()
+                    .find(|(_, map)| map.contains_key(directory_name))
+                    .map(|(ct, _)| *ct)
+                    .unwrap_or_else(|| guess_type(&path, component_type));
+                if !include(component_type) {
+                    continue;
+                }
+
+                found.push(UnrecognizedComponent {
+                    component: InstalledComponent {
+                        name: directory_name.to_string(),
+                        directory_name: directory_name.to_string(),
+                        version: UNKNOWN_VERSION.to_string(),
+                        component_type,
+                        path: path.clone(),
+                        is_system: system,
+                        release_date: String::new(),
+                        managed_by_distro: false,
+                        metadata: Default::default(),
+                        shadowed: None,
+                        in_use: false,
+                    },
+                    reason,
+                });
+            }
+        }
+    }
+
+    found.sort_by(|a, b| a.component.directory_name.cmp(&b.component.directory_name));
+    found
+}
+
+/// Version reported for packages whose metadata cannot be read.
+const UNKNOWN_VERSION: &str = "unknown";
+
+/// Existing directories holding `component_type` packages.
+fn package_roots(component_type: ComponentType, system: bool) -> Vec<PathBuf> {
+    let paths = if system {
+        component_type.system_paths()
+    } else {
+        vec![component_type.user_path()]
+    };
+    paths
+        .into_iter()
+        .filter(|path| !path.as_os_str().is_empty() && path.exists())
+        .collect()
+}
+
+/// Explains why [`read_package_metadata`] found nothing in `package_dir`, or
+/// returns `None` if the directory does not look like a package at all.
+fn unreadable_metadata(package_dir: &Path) -> Option<String> {
+    let json = package_dir.join("metadata.json");
+    let desktop = package_dir.join("metadata.desktop");
+
+    if json.exists() {
+        let error = match fs::read_to_string(&json) {
+            Ok(content) => serde_json::from_str::<PackageMetadata>(&content).err()?,
+            Err(e) => return Some(format!("metadata.json cannot be read: {e}")),
+        };
+        return Some(format!("metadata.json is invalid: {error}"));
+    }
+    if desktop.exists() {
+        return Some(match fs::read_to_string(&desktop) {
+            Ok(_) => "metadata.desktop has no [Desktop Entry] group".to_string(),
+            Err(e) => format!("metadata.desktop cannot be read: {e}"),
+        });
+    }
+    package_dir
+        .join("contents")
+        .is_dir()
+        .then(|| "no metadata.json or metadata.desktop".to_string())
+}
+
+/// Guesses the type of a package without metadata in a directory of
+/// `default_type` packages.
+fn guess_type(package_dir: &Path, default_type: ComponentType) -> ComponentType {
+    if !matches!(
+        default_type,
+        ComponentType::GlobalTheme | ComponentType::SplashScreen
+    ) {
+        return default_type;
+    }
+    let contents = package_dir.join("contents");
+    if contents.join("splash").is_dir() && !contents.join("defaults").exists() {
+        ComponentType::SplashScreen
+    } else {
+        ComponentType::GlobalTheme
+    }
+}
+
+/// Points each user package at the system-wide copy of itself it hides.
+///
+/// Plasma loads the user copy, so the system one is dead weight at best and
+/// at worst the newer of the two.
+fn mark_shadowed(
+    components: &mut [InstalledComponent],
+    system_dirs: impl Fn(ComponentType) -> Vec<PathBuf>,
+) {
+    for component in components
+        .iter_mut()
+        .filter(|c| !c.component_type.registry_only())
+    {
+        component.shadowed = system_dirs(component.component_type)
+            .into_iter()
+            .map(|dir| dir.join(&component.directory_name))
+            .find(|system_copy| read_package_metadata(system_copy).is_some());
+    }
+}
+
+/// Finds accounts under `home_root` (normally `/home`) that have used Plasma.
+///
+/// A directory counts when its owner's passwd home matches it and it contains
+/// a KNewStuff registry directory. Sorted by login name.
+pub(crate) fn find_users(home_root: &Path) -> Result<Vec<UserScope>> {
+    let mut users = Vec::new();
+
+    for entry in fs::read_dir(home_root)?.flatten() {
+        let home = entry.path();
+        let Ok(metadata) = fs::symlink_metadata(&home) else {
+            continue;
+        };
+        if !metadata.is_dir() || !home.join(".local/share/knewstuff3").is_dir() {
+            continue;
+        }
+        let Ok(Some(user)) = User::from_uid(Uid::from_raw(metadata.uid())) else {
+            continue;
+        };
+        if user.dir != home {
+            log::debug!(
+                target: "discovery",
+                "skipping {}: owner {} has home {}",
+                home.display(),
+                user.name,
+                user.dir.display()
+            );
+            continue;
+        }
+        users.push(UserScope {
+            name: user.name,
+            uid: user.uid.as_raw(),
+            home,
+        });
+    }
+
+    users.sort_by(|a, b| a.name.cmp(&b.name));
+    Ok(users)
+}
+
 fn scan_directory(
     dir: &Path,
     default_type: ComponentType,
@@ -87,8 +324,7 @@ fn scan_directory(
             continue;
         };
 
-        let Some(metadata) = read_metadata_json(&path).or_else(|| read_metadata_desktop(&path))
-        else {
+        let Some(metadata) = read_package_metadata(&path) else {
             continue;
         };
 
@@ -113,12 +349,21 @@ fn scan_directory(
             path: path.clone(),
             is_system,
             release_date,
+            managed_by_distro: false,
+            metadata: metadata.details(&path),
+            shadowed: None,
+            in_use: false,
         });
     }
 
     Ok(components)
 }
 
+/// Reads a package's `metadata.json`, falling back to the legacy `metadata.desktop`.
+pub(crate) fn read_package_metadata(package_dir: &Path) -> Option<PackageMetadata> {
+    read_metadata_json(package_dir).or_else(|| read_metadata_desktop(package_dir))
+}
+
 fn read_metadata_json(package_dir: &Path) -> Option<PackageMetadata> {
     let path = package_dir.join("metadata.json");
     let content = fs::read_to_string(&path).ok()?;
@@ -126,18 +371,170 @@ fn read_metadata_json(package_dir: &Path) -> Option<PackageMetadata> {
 }
 
 fn read_metadata_desktop(package_dir: &Path) -> Option<PackageMetadata> {
-    let path = package_dir.join("metadata.desktop");
-    let entry = freedesktop_entry_parser::parse_entry(&path).ok()?;
-    let section = entry.section("Desktop Entry")?;
-
-    let attr = |key: &str| section.attr(key).first().map(|s| s.to_string());
+    let content = fs::read_to_string(package_dir.join("metadata.desktop")).ok()?;
+    let entry = parse_desktop_entry(&content)?;
+    let attr = |key: &str| {
+        entry
+            .get(key)
+            .filter(|v| !v.is_empty())
+            .map(|v| v.to_string())
+    };
 
     Some(PackageMetadata {
         kplugin: Some(crate::types::KPluginInfo {
+            id: attr("X-KDE-PluginInfo-Name"),
             name: attr("Name"),
             version: attr("X-KDE-PluginInfo-Version"),
             icon: attr("Icon"),
             description: attr("Comment"),
+            website: attr("X-KDE-PluginInfo-Website"),
+            authors: attr("X-KDE-PluginInfo-Author")
+                .map(|name| crate::types::KPluginAuthor { name: Some(name) })
+                .into_iter()
+                .collect(),
+            license: attr("X-KDE-PluginInfo-License"),
         }),
     })
 }
+
+/// Parses the `[Desktop Entry]` group of a `.desktop` file into its
+/// unlocalized keys.
+///
+/// Plasma 5-era packages are often sloppy, so unlike a strict parser this
+/// skips malformed lines instead of giving up on the rest of the file,
+/// tolerates a byte order mark and spaces around `=`, and keeps the first of
+/// repeated keys. Returns `None` if the group is missing.
+pub(crate) fn parse_desktop_entry(content: &str) -> Option<HashMap<&str, &str>> {
+    let mut entry = None;
+    for line in content.trim_start_matches('\u{feff}').lines() {
+        let line = line.trim();
+        if line.starts_with('[') {
+            if entry.is_some() {
+                break;
+            }
+            if line == "[Desktop Entry]" {
+                entry = Some(HashMap::new());
+            }
+            continue;
+        }
+        let Some(keys) = entry.as_mut() else {
+            continue;
+        };
+        if line.starts_with('#') {
+            continue;
+        }
+        let Some((key, value)) = line.split_once('=') else {
+            continue;
+        };
+        let key = key.trim();
+        if !key.is_empty() && !key.contains('[') {
+            keys.entry(key).or_insert(value.trim());
+        }
+    }
+    entry
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn user_component(directory_name: &str, component_type: ComponentType) -> InstalledComponent {
+        InstalledComponent::fixture(directory_name, component_type, "")
+    }
+
+    #[test]
+    fn metadata_desktop_survives_sloppy_plasma5_files() {
+        let dir = tempfile::tempdir().unwrap();
+        fs::write(
+            dir.path().join("metadata.desktop"),
+            "\u{feff}# legacy widget\n[Desktop Entry]\nName=Clock\nName[de]=Uhr\nnot a key\n\
+             X-KDE-PluginInfo-Name = org.example.clock\nX-KDE-PluginInfo-Version = 1.2\n\
+             X-KDE-PluginInfo-License=\n\n[Desktop Action open]\nName=Open\n",
+        )
+        .unwrap();
+
+        let metadata = read_package_metadata(dir.path()).unwrap();
+        assert_eq!(metadata.name(), Some("Clock"));
+        assert_eq!(metadata.id(), Some("org.example.clock"));
+        assert_eq!(metadata.version(), Some("1.2"));
+        assert_eq!(metadata.details(dir.path()).license, None);
+    }
+
+    #[test]
+    fn unreadable_metadata_explains_why_packages_are_skipped() {
+        let dir = tempfile::tempdir().unwrap();
+        let package = |name: &str| {
+            let path = dir.path().join(name);
+            fs::create_dir_all(path.join("contents/ui")).unwrap();
+            path
+        };
+
+        let bare = package("bare");
+        fs::write(bare.join("contents/ui/main.qml"), "Item {}").unwrap();
+        let legacy = package("legacy");
+        fs::write(legacy.join("metadata.desktop"), "Name=Clock\n").unwrap();
+        let stray = dir.path().join("stray");
+        fs::create_dir_all(&stray).unwrap();
+
+        assert_eq!(
+            unreadable_metadata(&bare).as_deref(),
+            Some("no metadata.json or metadata.desktop")
+        );
+        assert_eq!(
+            unreadable_metadata(&legacy).as_deref(),
+            Some("metadata.desktop has no [Desktop Entry] group")
+        );
+        assert_eq!(unreadable_metadata(&stray), None);
+    }
+
+    #[test]
+    fn look_and_feel_packages_without_defaults_are_splash_screens() {
+        let dir = tempfile::tempdir().unwrap();
+        let splash = dir.path().join("splash");
+        fs::create_dir_all(splash.join("contents/splash")).unwrap();
+        let theme = dir.path().join("theme");
+        fs::create_dir_all(theme.join("contents/splash")).unwrap();
+        fs::write(theme.join("contents/defaults"), "").unwrap();
+
+        assert_eq!(
+            guess_type(&splash, ComponentType::GlobalTheme),
+            ComponentType::SplashScreen
+        );
+        assert_eq!(
+            guess_type(&theme, ComponentType::SplashScreen),
+            ComponentType::GlobalTheme
+        );
+        assert_eq!(
+            guess_type(&splash, ComponentType::PlasmaWidget),
+            ComponentType::PlasmaWidget
+        );
+    }
+
+    #[test]
+    fn mark_shadowed_finds_system_copies_of_user_packages() {
+        let system = tempfile::tempdir().unwrap();
+        let copy = system.path().join("org.example.clock");
+        fs::create_dir_all(&copy).unwrap();
+        fs::write(
+            copy.join("metadata.json"),
+            r#"{"KPlugin": {"Version": "0.9"}}"#,
+        )
+        .unwrap();
+        fs::create_dir_all(system.path().join("org.example.empty")).unwrap();
+
+        let mut components = vec![
+            user_component("org.example.clock", ComponentType::PlasmaWidget),
+            user_component("org.example.empty", ComponentType::PlasmaWidget),
+            user_component("org.example.clock", ComponentType::ColorScheme),
+        ];
+        // Searched in XDG_DATA_DIRS order, past dirs without a copy.
+        let local = tempfile::tempdir().unwrap();
+        mark_shadowed(&mut components, |_| {
+            vec![local.path().to_path_buf(), system.path().to_path_buf()]
+        });
+
+        assert_eq!(components[0].shadowed.as_deref(), Some(copy.as_path()));
+        assert_eq!(components[1].shadowed, None);
+        assert_eq!(components[2].shadowed, None);
+    }
+}
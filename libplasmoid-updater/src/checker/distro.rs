@@ -0,0 +1,164 @@
+// SPDX-License-Identifier: GPL-3.0-or-later
+
+use std::{
+    collections::HashMap,
+    path::{Path, PathBuf},
+    process::Command,
+};
+
+use crate::types::InstalledComponent;
+
+/// A package manager that can tell which package owns a file.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum PackageManager {
+    Pacman,
+    Dpkg,
+    Rpm,
+}
+
+impl PackageManager {
+    /// Probes for an installed package manager, preferring the native one of
+    /// each family (rpm can be present on Debian for alien, for example).
+    fn detect() -> Option<Self> {
+        [Self::Pacman, Self::Dpkg, Self::Rpm]
+            .into_iter()
+            .find(|pm| which(pm.program()))
+    }
+
+    const fn program(self) -> &'static str {
+        match self {
+            Self::Pacman => "pacman",
+            Self::Dpkg => "dpkg",
+            Self::Rpm => "rpm",
+        }
+    }
+
+    fn query(self, paths: &[&Path]) -> Command {
+        let mut cmd = Command::new(self.program());
+        match self {
+            Self::Pacman => cmd.arg("-Qo"),
+            Self::Dpkg => cmd.arg("-S"),
+            Self::Rpm => cmd.args(["-qf", "--queryformat", "%{NAME}\\n"]),
+        };
+        cmd.args(paths);
+        cmd
+    }
+
+    /// Maps each owned path to its package, given the query output.
+    fn parse(self, paths: &[&Path], stdout: &str) -> HashMap<PathBuf, String> {
+        match self {
+            // "/usr/share/plasma/plasmoids/foo is owned by plasma-foo 1.0-1"
+            Self::Pacman => stdout
+                .lines()
+                .filter_map(|line| {
+                    let (path, rest) = line.split_once(" is owned by ")?;
+                    let package = rest.split_whitespace().next()?;
+                    Some((PathBuf::from(path), package.to_string()))
+                })
+                .collect(),
+            // "plasma-foo:amd64: /usr/share/plasma/plasmoids/foo"
+            Self::Dpkg => stdout
+                .lines()
+                .filter(|line| !line.starts_with("diversion "))
+                .filter_map(|line| {
+                    let split = line.find(": /")?;
+                    let package = line[..split].split(':').next()?;
+                    Some((PathBuf::from(&line[split + 2..]), package.to_string()))
+                })
+                .collect(),
+            // One line per queried path, in order; unowned paths print
+            // "file ... is not owned by any package".
+            Self::Rpm => paths
+                .iter()
+                .zip(stdout.lines())
+                .filter(|(_, line)| !line.contains("is not owned by any package"))
+                .map(|(path, package)| (path.to_path_buf(), package.to_string()))
+                .collect(),
+        }
+    }
+}
+
+/// Flags system components whose files belong to a distro package.
+///
+/// Does nothing for user components or when no supported package manager
+/// (pacman, dpkg, rpm) is installed.
+pub(crate) fn mark_distro_owned(components: &mut [InstalledComponent]) {
+    let paths: Vec<&Path> = components
+        .iter()
+        .filter(|c| c.is_system)
+        .map(|c| c.path.as_path())
+        .collect();
+    if paths.is_empty() {
+        return;
+    }
+
+    let Some(pm) = PackageManager::detect() else {
+        log::debug!(target: "distro", "no supported package manager found");
+        return;
+    };
+
+    // Unowned paths make the query exit non-zero, so only spawn failures matter.
+    let output = match pm.query(&paths).output() {
+        Ok(output) => output,
+        Err(e) => {
+            log::debug!(target: "distro", "failed to run {}: {e}", pm.program());
+            return;
+        }
+    };
+    let owners = pm.parse(&paths, &String::from_utf8_lossy(&output.stdout));
+
+    for component in components.iter_mut().filter(|c| c.is_system) {
+        if let Some(package) = owners.get(&component.path) {
+            log::debug!(
+                target: "distro",
+                "{} is owned by distro package {package}",
+                component.name
+            );
+            component.managed_by_distro = true;
+        }
+    }
+}
+
+fn which(program: &str) -> bool {
+    std::env::var_os("PATH")
+        .is_some_and(|path| std::env::split_paths(&path).any(|dir| dir.join(program).is_file()))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const WIDGET: &str = "/usr/share/plasma/plasmoids/org.example.widget";
+    const OTHER: &str = "/usr/share/plasma/plasmoids/org.example.other";
+
+    fn paths() -> [&'static Path; 2] {
+        [Path::new(WIDGET), Path::new(OTHER)]
+    }
+
+    #[test]
+    fn parses_pacman_owners() {
+        let stdout = format!("{WIDGET} is owned by plasma-widget 1.2-1\n");
+        let owners = PackageManager::Pacman.parse(&paths(), &stdout);
+
+        assert_eq!(owners.len(), 1);
+        assert_eq!(owners[Path::new(WIDGET)], "plasma-widget");
+    }
+
+    #[test]
+    fn parses_dpkg_owners_with_architecture() {
+        let stdout = format!("plasma-widget:amd64: {WIDGET}\n");
+        let owners = PackageManager::Dpkg.parse(&paths(), &stdout);
+
+        assert_eq!(owners.len(), 1);
+        assert_eq!(owners[Path::new(WIDGET)], "plasma-widget");
+    }
+
+    #[test]
+    fn parses_rpm_owners_in_query_order() {
+        let stdout = format!("plasma-widget\nfile {OTHER} is not owned by any package\n");
+        let owners = PackageManager::Rpm.parse(&paths(), &stdout);
+
+        assert_eq!(owners.len(), 1);
+        assert_eq!(owners[Path::new(WIDGET)], "plasma-widget");
+    }
+}
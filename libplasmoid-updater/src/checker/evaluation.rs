@@ -1,7 +1,11 @@
 // SPDX-License-Identifier: GPL-3.0-or-later
 
+use std::{collections::HashSet, path::PathBuf};
+
 use crate::{
-    types::{AvailableUpdate, Diagnostic, InstalledComponent, StoreEntry},
+    types::{
+        AvailableUpdate, ComponentType, Diagnostic, DiagnosticKind, InstalledComponent, StoreEntry,
+    },
     version,
 };
 
@@ -11,13 +15,16 @@ pub(crate) enum ComponentCheckResult {
     Update(Box<AvailableUpdate>),
     Unresolved(Diagnostic),
     CheckFailed(Diagnostic),
-    UpToDate,
+    UpToDate { content_id: u64, author: String },
 }
 
 /// Evaluates a store entry against a component to determine if an update is available based on version and release date.
+///
+/// `gone` holds content IDs the store reported as deleted.
 pub(crate) fn check_component(
     component: &InstalledComponent,
     store_entries: &[StoreEntry],
+    gone: &HashSet<u64>,
     lookup: &IdLookup,
 ) -> ComponentCheckResult {
     let Some(content_id) = resolution::resolve_content_id(component, store_entries, lookup) else {
@@ -61,17 +68,33 @@ pub(crate) fn check_component(
             content_id,
             component.name
         );
-        let diagnostic = Diagnostic::new(
-            component.name.clone(),
-            format!("store entry {content_id} not in fetched data"),
-        )
-        .with_content_id(content_id);
+        let diagnostic = if gone.contains(&content_id) {
+            gone_diagnostic(component, content_id)
+        } else {
+            Diagnostic::new(
+                component.name.clone(),
+                format!("store entry {content_id} not in fetched data"),
+            )
+            .with_content_id(content_id)
+        };
         return ComponentCheckResult::Unresolved(diagnostic);
     };
 
     evaluate_store_entry(component, entry, entry.id)
 }
 
+/// Diagnostic for a component whose store entry was deleted.
+pub(crate) fn gone_diagnostic(component: &InstalledComponent, content_id: u64) -> Diagnostic {
+    let installed_version = (!component.version.is_empty()).then(|| component.version.clone());
+    Diagnostic::new(
+        component.name.clone(),
+        format!("store entry {content_id} no longer exists"),
+    )
+    .with_kind(DiagnosticKind::StoreEntryGone)
+    .with_versions(installed_version, None)
+    .with_content_id(content_id)
+}
+
 /// Shared logic for evaluating a store entry against an installed component.
 ///
 /// Performs version/date comparison and download URL selection, returning
@@ -88,10 +111,13 @@ pub(crate) fn evaluate_store_entry(
         &component.release_date,
         &entry.changed_date,
     ) {
-        return ComponentCheckResult::UpToDate;
+        return ComponentCheckResult::UpToDate {
+            content_id,
+            author: entry.author.clone(),
+        };
     }
 
-    let Some(download_info) = resolution::select_download_with_info(entry, &entry.version) else {
+    let Some(update) = build_update(component, entry, content_id) else {
         log::warn!(
             target: "resolver",
             "no download url for '{}' (id: {})",
@@ -104,11 +130,57 @@ pub(crate) fn evaluate_store_entry(
             component.name.clone(),
             "no download url available".to_string(),
         )
+        .with_kind(DiagnosticKind::CheckFailed)
         .with_versions(installed_version, available_version)
         .with_content_id(content_id);
         return ComponentCheckResult::CheckFailed(diagnostic);
     };
 
+    ComponentCheckResult::Update(Box::new(update))
+}
+
+/// Returns the update `entry` offers for `component`, if it is newer.
+pub(crate) fn update_for(
+    component: &InstalledComponent,
+    entry: &StoreEntry,
+) -> Option<AvailableUpdate> {
+    match evaluate_store_entry(component, entry, entry.id) {
+        ComponentCheckResult::Update(update) => Some(*update),
+        _ => None,
+    }
+}
+
+/// Builds the update installing `entry` as a new component of
+/// `component_type`, or `None` if the entry has no download.
+///
+/// The placeholder `installed` component only carries the store name and
+/// the type; the installer fills in the rest from the package.
+pub(crate) fn new_install(
+    entry: &StoreEntry,
+    component_type: ComponentType,
+) -> Option<AvailableUpdate> {
+    let placeholder = InstalledComponent {
+        name: entry.name.clone(),
+        directory_name: entry.id.to_string(),
+        version: String::new(),
+        component_type,
+        path: PathBuf::new(),
+        is_system: false,
+        release_date: String::new(),
+        managed_by_distro: false,
+        metadata: Default::default(),
+        shadowed: None,
+        in_use: false,
+    };
+    build_update(&placeholder, entry, entry.id)
+}
+
+fn build_update(
+    component: &InstalledComponent,
+    entry: &StoreEntry,
+    content_id: u64,
+) -> Option<AvailableUpdate> {
+    let download_info = resolution::select_download_with_info(entry, &entry.version)?;
     let update = AvailableUpdate::builder(
         component.clone(),
         content_id,
@@ -118,26 +190,25 @@ pub(crate) fn evaluate_store_entry(
     )
     .checksum(download_info.checksum)
     .download_size(download_info.size_kb.map(|kb| kb * 1024))
+    .alternatives(resolution::download_variants(entry))
+    .changelog(entry.changelog.clone())
+    .author(entry.author.clone())
     .build();
-
-    ComponentCheckResult::Update(Box::new(update))
+    Some(update)
 }
 
 #[cfg(test)]
 mod tests {
     use super::*;
     use crate::types::{ComponentType, DownloadLink};
-    use std::{collections::HashMap, path::PathBuf};
+    use std::collections::HashMap;
 
     fn make_component(name: &str, dir_name: &str) -> InstalledComponent {
         InstalledComponent {
-            name: name.to_string(),
             directory_name: dir_name.to_string(),
             version: "1.0.0".to_string(),
-            component_type: ComponentType::PlasmaWidget,
-            path: PathBuf::from("/tmp/test"),
-            is_system: false,
             release_date: "2024-01-01".to_string(),
+            ..InstalledComponent::fixture(name, ComponentType::PlasmaWidget, "/tmp/test")
         }
     }
 
@@ -149,11 +220,16 @@ mod tests {
             type_id,
             download_links: vec![DownloadLink {
                 url: "https://example.com/download.tar.gz".to_string(),
+                name: String::new(),
                 version: version.to_string(),
                 checksum: None,
                 size_kb: None,
             }],
             changed_date: "2025-06-01".to_string(),
+            author: String::new(),
+            preview_url: String::new(),
+            thumbnail_url: String::new(),
+            changelog: String::new(),
         }
     }
 
@@ -168,9 +244,10 @@ mod tests {
         let lookup = IdLookup {
             widgets_id_table: &wid,
             registry_id_cache: &reg,
+            metadata_id_cache: &HashMap::new(),
         };
 
-        let result = check_component(&component, &store_entries, &lookup);
+        let result = check_component(&component, &store_entries, &HashSet::new(), &lookup);
         assert!(matches!(result, ComponentCheckResult::Update(_)));
     }
 
@@ -185,9 +262,10 @@ mod tests {
         let lookup = IdLookup {
             widgets_id_table: &wid,
             registry_id_cache: &reg,
+            metadata_id_cache: &HashMap::new(),
         };
 
-        let result = check_component(&component, &store_entries, &lookup);
+        let result = check_component(&component, &store_entries, &HashSet::new(), &lookup);
         assert!(matches!(result, ComponentCheckResult::Update(_)));
     }
 
@@ -202,9 +280,10 @@ mod tests {
         let lookup = IdLookup {
             widgets_id_table: &wid,
             registry_id_cache: &reg,
+            metadata_id_cache: &HashMap::new(),
         };
 
-        let result = check_component(&component, &store_entries, &lookup);
+        let result = check_component(&component, &store_entries, &HashSet::new(), &lookup);
         assert!(matches!(result, ComponentCheckResult::Unresolved(_)));
     }
 }
@@ -0,0 +1,370 @@
+// SPDX-License-Identifier: GPL-3.0-or-later
+
+use std::slice;
+
+use serde::Serialize;
+
+use crate::{
+    Error, Result,
+    api::ApiClient,
+    config::Config,
+    matcher::PackageMatcher,
+    types::{InstalledComponent, StoreEntry},
+    version::{self, VersionComparison},
+};
+
+use super::{
+    IdLookup, advisories,
+    resolution::{self, ResolutionSource},
+    store,
+};
+
+/// Why a component is or is not offered an update.
+///
+/// Returned by [`explain_component()`](crate::explain_component).
+#[derive(Debug, Clone, Serialize)]
+pub struct Explanation {
+    pub component: InstalledComponent,
+    /// How the store entry was found; `None` if nothing matched.
+    pub resolution: Option<ResolutionSource>,
+    /// Content ID of the matched store entry.
+    pub content_id: Option<u64>,
+    /// Latest version on the store; `None` if no entry was fetched.
+    pub store_version: Option<String>,
+    pub store_release_date: Option<String>,
+    /// How the store release compares to the installed one.
+    pub comparison: Option<VersionComparison>,
+    /// What keeps an update from being offered or installed.
+    pub blockers: Vec<Blocker>,
+    /// Each step of the resolution, in order, e.g. "no content ID in the
+    /// KNewStuff registry".
+    pub trace: Vec<String>,
+}
+
+/// Something that keeps an update from being offered or installed, see
+/// [`Explanation::blockers`].
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+#[serde(tag = "kind", rename_all = "snake_case")]
+pub enum Blocker {
+    /// [`Config::excluded_packages`] matches the component.
+    Excluded,
+    /// The component is not in the selected [`Config::group`].
+    NotInGroup { group: String },
+    /// A distro package installed the component, see
+    /// [`Config::force_distro_owned`].
+    DistroOwned,
+    /// The advisory feed lists the store version as broken, see
+    /// [`Config::advisories_url`].
+    Advisory { reason: String },
+    /// The store entry has no download for the latest version.
+    NoDownload,
+}
+
+impl std::fmt::Display for Blocker {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Self::Excluded => f.write_str("excluded in the config"),
+            Self::NotInGroup { group } => write!(f, "not in the selected group '{group}'"),
+            Self::DistroOwned => f.write_str("installed by a distro package"),
+            Self::Advisory { reason } => write!(f, "held back as known broken: {reason}"),
+            Self::NoDownload => f.write_str("the store entry has no download for it"),
+        }
+    }
+}
+
+impl Explanation {
+    /// Whether [`update()`](crate::update) would install a newer version.
+    pub fn update_offered(&self) -> bool {
+        self.comparison.is_some_and(VersionComparison::is_update) && self.blockers.is_empty()
+    }
+
+    /// One line saying why the component is or is not offered an update.
+    pub fn summary(&self) -> String {
+        let installed = &self.component.version;
+        let (Some(comparison), Some(latest)) = (self.comparison, &self.store_version) else {
+            return match self.content_id {
+                Some(id) => format!("store entry {id} could not be fetched"),
+                None => "not matched to a KDE Store entry".to_string(),
+            };
+        };
+
+        match comparison {
+            _ if comparison.is_update() && !self.blockers.is_empty() => {
+                let blockers: Vec<String> = self.blockers.iter().map(|b| b.to_string()).collect();
+                format!("update to {latest} not offered: {}", blockers.join(", "))
+            }
+            VersionComparison::NewerVersion => format!("update from {installed} to {latest}"),
+            VersionComparison::NewerRelease => {
+                format!("{latest} was uploaded again after the installed copy")
+            }
+            VersionComparison::Same => format!("up to date, the store has {latest} too"),
+            VersionComparison::InstalledNewer => {
+                format!("installed {installed} is newer than the store's {latest}")
+            }
+            VersionComparison::Incomparable => format!(
+                "versions {installed} and {latest} cannot be ordered and the store release is not newer"
+            ),
+        }
+    }
+}
+
+/// Resolves `component` the way a check does, recording each step.
+pub(crate) fn explain(
+    config: &Config,
+    api_client: &ApiClient,
+    component: InstalledComponent,
+) -> Result<Explanation> {
+    let mut trace = Vec::new();
+    let registry_id_cache = crate::registry::build_id_cache(config.system);
+    let metadata_id_cache = resolution::build_metadata_id_cache(slice::from_ref(&component));
+    let lookup = IdLookup {
+        widgets_id_table: &config.widgets_id_table,
+        registry_id_cache: &registry_id_cache,
+        metadata_id_cache: &metadata_id_cache,
+    };
+
+    let dir = &component.directory_name;
+    trace.push(match registry_id_cache.get(dir) {
+        Some(id) => format!("KNewStuff registry records content ID {id}"),
+        None => "no content ID in the KNewStuff registry".to_string(),
+    });
+    trace.push(match metadata_id_cache.get(dir) {
+        Some(id) => format!("metadata website links to content ID {id}"),
+        None => "no store link in the metadata website".to_string(),
+    });
+
+    // Registry-only types are never matched against the catalog by name.
+    let registry_only = component.component_type.registry_only();
+    let fetched = if registry_only {
+        store::FetchedEntries::default()
+    } else {
+        store::fetch_store_entries(
+            api_client,
+            slice::from_ref(&component),
+            &lookup,
+            config.fetch_strategy,
+        )?
+    };
+    let resolved =
+        resolution::resolve_content_id_with_source(&component, &fetched.entries, &lookup);
+    if !matches!(
+        resolved,
+        Some((
+            _,
+            ResolutionSource::Registry | ResolutionSource::MetadataUrl
+        ))
+    ) {
+        trace.push(match resolved {
+            Some((id, ResolutionSource::Name)) => {
+                format!("store entry {id} is named '{}'", component.name)
+            }
+            _ if registry_only => format!(
+                "{} components are not matched by name",
+                component.component_type
+            ),
+            _ => format!("no store entry is named exactly '{}'", component.name),
+        });
+    }
+    match resolved {
+        Some((id, ResolutionSource::IdTable)) => {
+            trace.push(format!("widgets-id table maps {dir} to content ID {id}"));
+        }
+        None => trace.push(format!("widgets-id table has no entry for {dir}")),
+        Some(_) => {}
+    }
+
+    let mut explanation = Explanation {
+        resolution: resolved.map(|(_, source)| source),
+        content_id: resolved.map(|(id, _)| id),
+        store_version: None,
+        store_release_date: None,
+        comparison: None,
+        blockers: config_blockers(config, &component)?,
+        trace,
+        component,
+    };
+    let Some((content_id, source)) = resolved else {
+        return Ok(explanation);
+    };
+    explanation.trace.push(format!(
+        "resolved through the {source} to content ID {content_id}"
+    ));
+
+    let Some(entry) = store_entry(
+        api_client,
+        &explanation.component,
+        &fetched,
+        content_id,
+        registry_only,
+        &mut explanation.trace,
+    ) else {
+        return Ok(explanation);
+    };
+    if entry.id != content_id {
+        explanation.resolution = Some(ResolutionSource::Name);
+    }
+
+    let installed = &explanation.component;
+    let comparison = version::compare_release(
+        &installed.version,
+        &entry.version,
+        &installed.release_date,
+        &entry.changed_date,
+    );
+    explanation.trace.push(format!(
+        "installed {} ({}) against store {} ({})",
+        or_unknown(&installed.version),
+        or_unknown(&installed.release_date),
+        or_unknown(&entry.version),
+        or_unknown(&entry.changed_date),
+    ));
+    if comparison.is_update() {
+        if resolution::select_download_with_info(&entry, &entry.version).is_none() {
+            explanation.blockers.push(Blocker::NoDownload);
+        }
+        if let Some(url) = &config.advisories_url
+            && let Some(advisories) = advisories::fetch(api_client, url)
+            && let Some(reason) = advisories.reason(entry.id, &entry.version)
+        {
+            explanation.blockers.push(Blocker::Advisory {
+                reason: reason.to_string(),
+            });
+        }
+    }
+    explanation.content_id = Some(entry.id);
+    explanation.store_version = Some(entry.version);
+    explanation.store_release_date = Some(entry.changed_date);
+    explanation.comparison = Some(comparison);
+    Ok(explanation)
+}
+
+/// Finds the entry for `content_id` like a check: in the fetched catalog,
+/// by name if the catalog lacks it, or by a request of its own for
+/// registry-only types.
+fn store_entry(
+    api_client: &ApiClient,
+    component: &InstalledComponent,
+    fetched: &store::FetchedEntries,
+    content_id: u64,
+    registry_only: bool,
+    trace: &mut Vec<String>,
+) -> Option<StoreEntry> {
+    if let Some(entry) = resolution::find_store_entry(&fetched.entries, content_id) {
+        return Some(entry.clone());
+    }
+
+    if registry_only {
+        return match api_client.fetch_details(&[content_id]).pop()? {
+            Ok(entry) => Some(entry),
+            Err(Error::ComponentNotFound(_)) => {
+                trace.push(format!("store entry {content_id} no longer exists"));
+                None
+            }
+            Err(e) => {
+                trace.push(format!("fetching store entry {content_id} failed: {e}"));
+                None
+            }
+        };
+    }
+
+    let fallback = resolution::resolve_by_name_only(component, &fetched.entries)
+        .and_then(|id| resolution::find_store_entry(&fetched.entries, id));
+    match fallback {
+        Some(entry) => {
+            trace.push(format!(
+                "store entry {content_id} was not fetched, matched entry {} by name instead",
+                entry.id
+            ));
+            Some(entry.clone())
+        }
+        None if fetched.gone.contains(&content_id) => {
+            trace.push(format!("store entry {content_id} no longer exists"));
+            None
+        }
+        None => {
+            trace.push(format!("store entry {content_id} was not fetched"));
+            None
+        }
+    }
+}
+
+/// Blockers that come from the configuration alone.
+fn config_blockers(config: &Config, component: &InstalledComponent) -> Result<Vec<Blocker>> {
+    let mut blockers = Vec::new();
+    if PackageMatcher::new(&config.excluded_packages)?.matches(component) {
+        blockers.push(Blocker::Excluded);
+    }
+    if let Some(group) = crate::utils::group_matcher(config)?
+        && !group.matches(component)
+    {
+        blockers.push(Blocker::NotInGroup {
+            group: config.group.clone().unwrap_or_default(),
+        });
+    }
+    if component.managed_by_distro && !config.force_distro_owned {
+        blockers.push(Blocker::DistroOwned);
+    }
+    Ok(blockers)
+}
+
+fn or_unknown(value: &str) -> &str {
+    if value.is_empty() { "unknown" } else { value }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::types::ComponentType;
+
+    fn explanation(comparison: Option<VersionComparison>, blockers: Vec<Blocker>) -> Explanation {
+        Explanation {
+            component: InstalledComponent {
+                directory_name: "org.example.clock".to_string(),
+                ..InstalledComponent::fixture("Clock", ComponentType::PlasmaWidget, "")
+            },
+            resolution: comparison.map(|_| ResolutionSource::Registry),
+            content_id: comparison.map(|_| 1000),
+            store_version: comparison.map(|_| "2.0".to_string()),
+            store_release_date: None,
+            comparison,
+            blockers,
+            trace: Vec::new(),
+        }
+    }
+
+    #[test]
+    fn summary_names_the_deciding_reason() {
+        let offered = explanation(Some(VersionComparison::NewerVersion), Vec::new());
+        assert!(offered.update_offered());
+        assert_eq!(offered.summary(), "update from 1.0 to 2.0");
+
+        let held = explanation(
+            Some(VersionComparison::NewerVersion),
+            vec![
+                Blocker::Excluded,
+                Blocker::Advisory {
+                    reason: "crashes plasmashell".to_string(),
+                },
+            ],
+        );
+        assert!(!held.update_offered());
+        assert_eq!(
+            held.summary(),
+            "update to 2.0 not offered: excluded in the config, \
+             held back as known broken: crashes plasmashell"
+        );
+
+        let newer = explanation(
+            Some(VersionComparison::InstalledNewer),
+            vec![Blocker::Excluded],
+        );
+        assert_eq!(
+            newer.summary(),
+            "installed 1.0 is newer than the store's 2.0"
+        );
+        assert_eq!(
+            explanation(None, Vec::new()).summary(),
+            "not matched to a KDE Store entry"
+        );
+    }
+}
@@ -0,0 +1,70 @@
+// SPDX-License-Identifier: GPL-3.0-or-later
+
+use std::{collections::HashSet, fs};
+
+use crate::{
+    paths,
+    types::{ComponentType, InstalledComponent},
+};
+
+/// Flags the widgets and wallpaper plugins that are placed on the desktop or
+/// a panel of the current user's Plasma layout.
+pub(crate) fn mark_in_use(components: &mut [InstalledComponent]) {
+    let Ok(content) = fs::read_to_string(paths::config_home().join(paths::APPLETSRC)) else {
+        return;
+    };
+    let plugins = plugins_in_layout(&content);
+    for component in components.iter_mut().filter(|c| {
+        matches!(
+            c.component_type,
+            ComponentType::PlasmaWidget | ComponentType::WallpaperPlugin
+        )
+    }) {
+        component.in_use = plugins.contains(component.directory_name.as_str());
+    }
+}
+
+/// Plugin IDs of the applets, containments and wallpapers in an appletsrc
+/// file.
+fn plugins_in_layout(content: &str) -> HashSet<&str> {
+    content
+        .lines()
+        .filter_map(|line| line.split_once('='))
+        .filter(|(key, _)| matches!(key.trim(), "plugin" | "wallpaperplugin"))
+        .map(|(_, value)| value.trim())
+        .filter(|value| !value.is_empty())
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn plugins_in_layout_reads_applets_and_wallpapers() {
+        let content = "\
+[Containments][1]
+activityId=abc
+plugin=org.kde.plasma.folder
+wallpaperplugin=com.example.wallpaper
+
+[Containments][2][Applets][5]
+immutability=1
+plugin=org.example.clock
+
+[Containments][2][Applets][5][Configuration][General]
+pluginName=org.example.ignored
+";
+
+        let plugins = plugins_in_layout(content);
+
+        assert_eq!(
+            plugins,
+            HashSet::from([
+                "org.kde.plasma.folder",
+                "com.example.wallpaper",
+                "org.example.clock"
+            ])
+        );
+    }
+}
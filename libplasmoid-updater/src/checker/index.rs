@@ -0,0 +1,203 @@
+// SPDX-License-Identifier: GPL-3.0-or-later
+
+use std::{
+    collections::HashMap,
+    fs,
+    path::{Path, PathBuf},
+    time::{Duration, SystemTime},
+};
+
+use serde::{Deserialize, Serialize};
+
+use crate::{Result, types::InstalledComponent};
+
+/// How long a component found up to date is trusted without asking the
+/// store again.
+const MAX_AGE: Duration = Duration::from_secs(60 * 60);
+
+/// Components found up to date by earlier checks, so a check can skip
+/// those that have not changed since, see [`Config::full_scan`](crate::Config::full_scan).
+///
+/// Entries are keyed by component path. A component is skipped only while
+/// its directory, metadata and version are as they were and the store was
+/// asked about it within [`MAX_AGE`].
+#[derive(Debug, Default, Serialize, Deserialize)]
+pub(crate) struct CheckIndex {
+    components: HashMap<PathBuf, IndexEntry>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+struct IndexEntry {
+    fingerprint: Fingerprint,
+    checked_at: SystemTime,
+}
+
+/// What a component looked like on disk when it was checked.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+struct Fingerprint {
+    modified: Option<SystemTime>,
+    metadata_md5: Option<String>,
+    version: String,
+}
+
+impl Fingerprint {
+    fn of(component: &InstalledComponent) -> Self {
+        Self {
+            modified: fs::metadata(&component.path)
+                .and_then(|m| m.modified())
+                .ok(),
+            metadata_md5: metadata_file(&component.path)
+                .and_then(|path| fs::read(path).ok())
+                .map(|content| format!("{:x}", md5::compute(content))),
+            version: component.version.clone(),
+        }
+    }
+}
+
+/// The file describing the package at `path`: its metadata, or the path
+/// itself for single-file components such as color schemes.
+fn metadata_file(path: &Path) -> Option<PathBuf> {
+    if path.is_file() {
+        return Some(path.to_path_buf());
+    }
+    ["metadata.json", "metadata.desktop"]
+        .iter()
+        .map(|name| path.join(name))
+        .find(|p| p.is_file())
+}
+
+/// Returns the index file, `$XDG_CACHE_HOME/plasmoid-updater/check-index.json`.
+fn index_path() -> PathBuf {
+    crate::paths::cache_home().join("plasmoid-updater/check-index.json")
+}
+
+impl CheckIndex {
+    /// Loads the index, or an empty one if there is none or it is unreadable.
+    pub(crate) fn load() -> Self {
+        Self::load_from(&index_path())
+    }
+
+    fn load_from(path: &Path) -> Self {
+        fs::read(path)
+            .ok()
+            .and_then(|content| serde_json::from_slice(&content).ok())
+            .unwrap_or_default()
+    }
+
+    /// Returns `true` if `component` was found up to date within
+    /// [`MAX_AGE`] of `now` and has not changed on disk since.
+    pub(crate) fn is_unchanged(&self, component: &InstalledComponent, now: SystemTime) -> bool {
+        self.components.get(&component.path).is_some_and(|entry| {
+            now.duration_since(entry.checked_at)
+                .is_ok_and(|age| age < MAX_AGE)
+                && entry.fingerprint == Fingerprint::of(component)
+        })
+    }
+
+    /// Records the outcome of a check that evaluated the components at
+    /// `checked` and found `up_to_date` among them at `now`. The others
+    /// are dropped so the next check evaluates them again, as are entries
+    /// older than [`MAX_AGE`].
+    pub(crate) fn record(
+        &mut self,
+        checked: &[PathBuf],
+        up_to_date: &[InstalledComponent],
+        now: SystemTime,
+    ) {
+        for path in checked {
+            self.components.remove(path);
+        }
+        self.components.retain(|_, entry| {
+            now.duration_since(entry.checked_at)
+                .is_ok_and(|age| age < MAX_AGE)
+        });
+        self.components.extend(up_to_date.iter().map(|c| {
+            let entry = IndexEntry {
+                fingerprint: Fingerprint::of(c),
+                checked_at: now,
+            };
+            (c.path.clone(), entry)
+        }));
+    }
+
+    /// Writes the index. Failures are logged; the index only saves requests.
+    pub(crate) fn save(&self) {
+        if let Err(e) = self.save_to(&index_path()) {
+            log::debug!(target: "checker", "failed to save the check index: {e}");
+        }
+    }
+
+    fn save_to(&self, path: &Path) -> Result<()> {
+        if let Some(parent) = path.parent() {
+            fs::create_dir_all(parent)?;
+        }
+        let partial = path.with_extension("json.partial");
+        fs::write(&partial, serde_json::to_vec(self)?)?;
+        fs::rename(&partial, path)?;
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::types::ComponentType;
+
+    fn component(path: &Path, version: &str) -> InstalledComponent {
+        InstalledComponent {
+            directory_name: "org.example.clock".to_string(),
+            version: version.to_string(),
+            ..InstalledComponent::fixture("Clock", ComponentType::PlasmaWidget, path)
+        }
+    }
+
+    #[test]
+    fn changed_or_stale_components_are_checked_again() {
+        let dir = tempfile::tempdir().unwrap();
+        let package = dir.path().join("org.example.clock");
+        fs::create_dir_all(&package).unwrap();
+        fs::write(
+            package.join("metadata.json"),
+            r#"{"KPlugin":{"Version":"1.0"}}"#,
+        )
+        .unwrap();
+        let clock = component(&package, "1.0");
+        let now = SystemTime::now();
+
+        let mut index = CheckIndex::default();
+        index.record(&[], std::slice::from_ref(&clock), now);
+        let file = dir.path().join("index.json");
+        index.save_to(&file).unwrap();
+        let index = CheckIndex::load_from(&file);
+
+        assert!(index.is_unchanged(&clock, now + Duration::from_secs(60)));
+        assert!(!index.is_unchanged(&clock, now + MAX_AGE));
+        assert!(!index.is_unchanged(&component(&package, "1.1"), now));
+
+        fs::write(
+            package.join("metadata.json"),
+            r#"{"KPlugin":{"Version":"1.1"}}"#,
+        )
+        .unwrap();
+        assert!(!index.is_unchanged(&clock, now));
+    }
+
+    #[test]
+    fn record_replaces_checked_and_expired_entries_only() {
+        let dir = tempfile::tempdir().unwrap();
+        let clock = component(&dir.path().join("clock"), "1.0");
+        let weather = component(&dir.path().join("weather"), "1.0");
+        let notes = component(&dir.path().join("notes"), "1.0");
+        let now = SystemTime::now();
+        let earlier = now - Duration::from_secs(600);
+
+        let mut index = CheckIndex::default();
+        index.record(&[], std::slice::from_ref(&notes), earlier - MAX_AGE);
+        index.record(&[], &[clock.clone(), weather.clone()], earlier);
+        index.record(std::slice::from_ref(&weather.path), &[], now);
+
+        assert_eq!(index.components[&clock.path].checked_at, earlier);
+        assert!(!index.components.contains_key(&weather.path));
+        assert!(!index.components.contains_key(&notes.path));
+    }
+}
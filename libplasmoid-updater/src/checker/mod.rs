@@ -1,17 +1,47 @@
 // SPDX-License-Identifier: GPL-3.0-or-later
 
+mod advisories;
+mod authors;
+mod companions;
 mod discovery;
+mod distro;
 mod evaluation;
+mod explain;
+mod in_use;
+mod index;
 mod registry;
 mod resolution;
 mod store;
 
-use std::collections::HashMap;
+use std::{cell::OnceCell, collections::HashMap, path::PathBuf, time::SystemTime};
 
-use crate::{Result, api::ApiClient, config::Config, types::UpdateCheckResult};
+use crate::{
+    Result,
+    api::ApiClient,
+    config::{Config, FetchStrategy},
+    events::CheckEvent,
+    matcher::PackageMatcher,
+    types::{
+        AvailableUpdate, HeldUpdate, InstalledComponent, RestartAction, StoreEntry,
+        UpdateCheckResult,
+    },
+};
+use advisories::Advisories;
+use authors::AuthorLedger;
+use index::CheckIndex;
 use rayon::iter::{IntoParallelRefIterator, ParallelIterator};
 
-pub(crate) use discovery::find_installed;
+pub(crate) use authors::accept_authors;
+pub(crate) use discovery::{
+    find_installed, find_unrecognized, find_users, parse_desktop_entry, read_package_metadata,
+};
+pub(crate) use evaluation::{new_install, update_for};
+pub(crate) use explain::explain;
+pub use explain::{Blocker, Explanation};
+pub use resolution::ResolutionSource;
+pub(crate) use resolution::{
+    EXACT_TYPE_SCORE, LOOSE_NAME_SCORE, rank_by_name, select_download_with_info,
+};
 
 /// Pre-built lookup tables for resolving component content IDs.
 ///
@@ -20,49 +50,108 @@ pub(crate) use discovery::find_installed;
 pub(crate) struct IdLookup<'a> {
     pub widgets_id_table: &'a HashMap<String, u64>,
     pub registry_id_cache: &'a HashMap<String, u64>,
+    /// Content IDs taken from store URLs in the components' own metadata.
+    pub metadata_id_cache: &'a HashMap<String, u64>,
 }
 
 /// Checks for updates using pre-discovered components.
+///
+/// Components that were up to date a short while ago and have not changed
+/// since are skipped, unless [`Config::full_scan`] is set.
 pub(crate) fn check_with_components(
     config: &Config,
     api_client: &ApiClient,
-    components: Vec<crate::types::InstalledComponent>,
+    components: Vec<InstalledComponent>,
+) -> Result<UpdateCheckResult> {
+    check_streaming(config, api_client, components, &mut |_| {})
+}
+
+/// Like [`check_with_components`], and asks the store about every
+/// component, for callers that need its current view of all of them.
+pub(crate) fn check_all(
+    config: &Config,
+    api_client: &ApiClient,
+    components: Vec<InstalledComponent>,
+) -> Result<UpdateCheckResult> {
+    check(config, api_client, components, &mut |_| {}, false)
+}
+
+/// Like [`check_with_components`], and passes each component's outcome to
+/// `report` as soon as it is known.
+pub(crate) fn check_streaming(
+    config: &Config,
+    api_client: &ApiClient,
+    components: Vec<InstalledComponent>,
+    report: &mut dyn FnMut(CheckEvent),
+) -> Result<UpdateCheckResult> {
+    // A snapshot pins the store, so its results must all come from it.
+    let incremental = !config.full_scan && config.store_snapshot.is_none();
+    check(config, api_client, components, report, incremental)
+}
+
+fn check(
+    config: &Config,
+    api_client: &ApiClient,
+    components: Vec<InstalledComponent>,
+    report: &mut dyn FnMut(CheckEvent),
+    incremental: bool,
 ) -> Result<UpdateCheckResult> {
     if components.is_empty() {
         return Ok(UpdateCheckResult::default());
     }
 
+    let now = SystemTime::now();
+    let mut index = CheckIndex::load();
+    let (skipped, components): (Vec<_>, Vec<_>) = components
+        .into_iter()
+        .partition(|c| incremental && index.is_unchanged(c, now));
+    if !skipped.is_empty() {
+        log::debug!(target: "checker", "{} components unchanged since the last check", skipped.len());
+    }
+
+    let checked: Vec<PathBuf> = components.iter().map(|c| c.path.clone()).collect();
     let (registry_components, regular_components) = store::partition_components(components);
 
     // Build local caches before any network call so fetch_store_entries
     // can resolve known IDs without touching the paginated catalog.
     let registry_id_cache = crate::registry::build_id_cache(config.system);
+    let metadata_id_cache = resolution::build_metadata_id_cache(&regular_components);
 
     let lookup = IdLookup {
         widgets_id_table: &config.widgets_id_table,
         registry_id_cache: &registry_id_cache,
+        metadata_id_cache: &metadata_id_cache,
     };
 
-    let store_entries = store::fetch_store_entries(api_client, &regular_components, &lookup)?;
+    // A few named components are cheaper to fetch by ID than by catalog.
+    let strategy = match config.fetch_strategy {
+        FetchStrategy::Catalog if !config.only_packages.is_empty() => FetchStrategy::Auto,
+        strategy => strategy,
+    };
+    let mut outcomes = Outcomes {
+        result: UpdateCheckResult::default(),
+        finisher: Finisher::new(config, api_client)?,
+        report,
+        up_to_date: Vec::new(),
+        authors: AuthorLedger::load(),
+    };
+    for component in &skipped {
+        (outcomes.report)(CheckEvent::UpToDate(component.clone()));
+    }
 
-    let mut result = UpdateCheckResult::default();
+    let fetched = store::fetch_store_entries(api_client, &regular_components, &lookup, strategy)?;
+    let store_entries = fetched.entries;
+    outcomes.result.catalog = fetched.catalog;
 
     let regular_results: Vec<evaluation::ComponentCheckResult> = regular_components
         .par_iter()
-        .map(|component| evaluation::check_component(component, &store_entries, &lookup))
+        .map(|component| {
+            evaluation::check_component(component, &store_entries, &fetched.gone, &lookup)
+        })
         .collect();
 
-    for check_result in regular_results {
-        match check_result {
-            evaluation::ComponentCheckResult::Update(update) => result.add_update(*update),
-            evaluation::ComponentCheckResult::Unresolved(diagnostic) => {
-                result.add_unresolved(diagnostic);
-            }
-            evaluation::ComponentCheckResult::CheckFailed(diagnostic) => {
-                result.add_check_failure(diagnostic);
-            }
-            evaluation::ComponentCheckResult::UpToDate => {}
-        }
+    for (component, check_result) in regular_components.iter().zip(regular_results) {
+        outcomes.record(component, check_result);
     }
 
     registry::check_components(
@@ -70,8 +159,183 @@ pub(crate) fn check_with_components(
         api_client,
         &store_entries,
         &lookup,
-        &mut result,
+        &mut outcomes,
     );
 
-    Ok(result)
+    if config.store_snapshot.is_none() {
+        index.record(&checked, &outcomes.up_to_date, now);
+        index.save();
+        outcomes.authors.save();
+    }
+    Ok(outcomes.result)
+}
+
+/// Collects component outcomes into the check result, reporting each one.
+pub(crate) struct Outcomes<'a> {
+    result: UpdateCheckResult,
+    finisher: Finisher<'a>,
+    report: &'a mut dyn FnMut(CheckEvent),
+    /// Components found up to date, for the [`CheckIndex`].
+    up_to_date: Vec<InstalledComponent>,
+    authors: AuthorLedger,
+}
+
+impl Outcomes<'_> {
+    pub(crate) fn record(
+        &mut self,
+        component: &InstalledComponent,
+        check_result: evaluation::ComponentCheckResult,
+    ) {
+        let event = match check_result {
+            evaluation::ComponentCheckResult::Update(mut update) => {
+                update.previous_author =
+                    self.author_change(&update.installed, update.content_id, &update.author);
+                self.finisher.finish(*update)
+            }
+            evaluation::ComponentCheckResult::Unresolved(diagnostic) => {
+                CheckEvent::Unresolved(diagnostic)
+            }
+            evaluation::ComponentCheckResult::CheckFailed(diagnostic) => {
+                CheckEvent::CheckFailed(diagnostic)
+            }
+            evaluation::ComponentCheckResult::UpToDate { content_id, author } => {
+                self.author_change(component, content_id, &author);
+                self.up_to_date.push(component.clone());
+                CheckEvent::UpToDate(component.clone())
+            }
+        };
+
+        match &event {
+            CheckEvent::UpdateAvailable(update) => self.result.add_update((**update).clone()),
+            CheckEvent::Held(held) => self.result.held.push((**held).clone()),
+            CheckEvent::Unresolved(diagnostic) => self.result.add_unresolved(diagnostic.clone()),
+            CheckEvent::CheckFailed(diagnostic) => {
+                self.result.add_check_failure(diagnostic.clone());
+            }
+            CheckEvent::UpToDate(_) | CheckEvent::Discovered(_) => {}
+        }
+        (self.report)(event);
+    }
+
+    /// Checks the store author of `content_id` against the [`AuthorLedger`],
+    /// returning the recorded one if the entry changed hands to an author
+    /// not in [`Config::trusted_authors`].
+    fn author_change(
+        &mut self,
+        component: &InstalledComponent,
+        content_id: u64,
+        author: &str,
+    ) -> Option<String> {
+        let previous = self.authors.observe(content_id, author)?;
+        if self
+            .finisher
+            .config
+            .trusted_authors
+            .iter()
+            .any(|a| a == author)
+        {
+            return None;
+        }
+        log::warn!(
+            target: "checker",
+            "store author of {} ({content_id}) changed from {previous} to {author}",
+            component.name
+        );
+        Some(previous)
+    }
+}
+
+/// Applies the per-update settings of [`Config`] to a found update.
+struct Finisher<'a> {
+    config: &'a Config,
+    api_client: &'a ApiClient,
+    restart_required: PackageMatcher,
+    no_restart: PackageMatcher,
+    /// Fetched with the first update found, `None` inside if unavailable.
+    advisories: OnceCell<Option<Advisories>>,
+}
+
+impl<'a> Finisher<'a> {
+    fn new(config: &'a Config, api_client: &'a ApiClient) -> Result<Self> {
+        Ok(Self {
+            config,
+            api_client,
+            restart_required: PackageMatcher::new(&config.restart_required)?,
+            no_restart: PackageMatcher::new(&config.no_restart)?,
+            advisories: OnceCell::new(),
+        })
+    }
+
+    /// Applies [`Config::restart_required`], [`Config::no_restart`] and
+    /// [`Config::download_variants`] and looks up the companions of global
+    /// themes, then holds the update back if the
+    /// advisory feed lists its version as broken.
+    fn finish(&self, mut update: AvailableUpdate) -> CheckEvent {
+        if self.restart_required.matches(&update.installed) {
+            update.restart_action = RestartAction::PlasmashellRestart;
+        } else if self.no_restart.matches(&update.installed) {
+            update.restart_action = RestartAction::None;
+        }
+        crate::utils::apply_download_variant(&mut update, self.config);
+        update.suggested_companions = companions::suggest(&update.installed);
+
+        let reason = self
+            .advisories()
+            .and_then(|a| a.reason(update.content_id, &update.latest_version));
+        match reason {
+            Some(reason) => {
+                log::info!(
+                    target: "advisory",
+                    "holding {} {}: known broken ({reason})",
+                    update.installed.name,
+                    update.latest_version
+                );
+                CheckEvent::Held(Box::new(HeldUpdate {
+                    reason: reason.to_string(),
+                    update,
+                }))
+            }
+            None => CheckEvent::UpdateAvailable(Box::new(update)),
+        }
+    }
+
+    fn advisories(&self) -> Option<&Advisories> {
+        let url = self.config.advisories_url.as_deref()?;
+        self.advisories
+            .get_or_init(|| advisories::fetch(self.api_client, url))
+            .as_ref()
+    }
+}
+
+/// Resolves each component to its KDE Store entry without evaluating updates.
+///
+/// Returns one slot per input component, `None` where no store entry matched.
+pub(crate) fn resolve_store_entries(
+    config: &Config,
+    api_client: &ApiClient,
+    components: &[InstalledComponent],
+) -> Result<Vec<Option<StoreEntry>>> {
+    let registry_id_cache = crate::registry::build_id_cache(config.system);
+    let metadata_id_cache = resolution::build_metadata_id_cache(components);
+    let lookup = IdLookup {
+        widgets_id_table: &config.widgets_id_table,
+        registry_id_cache: &registry_id_cache,
+        metadata_id_cache: &metadata_id_cache,
+    };
+
+    let store_entries =
+        store::fetch_store_entries(api_client, components, &lookup, config.fetch_strategy)?.entries;
+
+    Ok(components
+        .iter()
+        .map(|component| {
+            resolution::resolve_content_id(component, &store_entries, &lookup)
+                .and_then(|id| resolution::find_store_entry(&store_entries, id))
+                .or_else(|| {
+                    resolution::resolve_by_name_only(component, &store_entries)
+                        .and_then(|id| resolution::find_store_entry(&store_entries, id))
+                })
+                .cloned()
+        })
+        .collect())
 }
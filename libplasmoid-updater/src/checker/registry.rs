@@ -3,11 +3,12 @@
 use std::collections::{HashMap, HashSet};
 
 use crate::{
+    Error,
     api::ApiClient,
-    types::{Diagnostic, InstalledComponent, StoreEntry, UpdateCheckResult},
+    types::{Diagnostic, DiagnosticKind, InstalledComponent, StoreEntry},
 };
 
-use super::{IdLookup, evaluation, resolution};
+use super::{IdLookup, Outcomes, evaluation, resolution};
 
 /// Checks if any of the components from the widget-id registry table have updates available.
 pub(crate) fn check_components(
@@ -15,7 +16,7 @@ pub(crate) fn check_components(
     client: &ApiClient,
     store_entries: &[StoreEntry],
     lookup: &IdLookup,
-    result: &mut UpdateCheckResult,
+    outcomes: &mut Outcomes,
 ) {
     let resolved: Vec<(&InstalledComponent, u64)> = registry_components
         .iter()
@@ -33,12 +34,17 @@ pub(crate) fn check_components(
             .collect()
     };
 
+    let mut gone = HashSet::new();
     let fetched: HashMap<u64, StoreEntry> = client
         .fetch_details(&missing_ids)
         .into_iter()
         .zip(missing_ids.iter())
         .filter_map(|(r, &id)| match r {
             Ok(e) => Some((id, e)),
+            Err(Error::ComponentNotFound(_)) => {
+                gone.insert(id);
+                None
+            }
             Err(e) => {
                 log::warn!(
                     target: "resolver",
@@ -55,28 +61,22 @@ pub(crate) fn check_components(
         let entry = resolution::find_store_entry(store_entries, *content_id)
             .or_else(|| fetched.get(content_id));
 
-        match entry {
-            Some(entry) => match evaluation::evaluate_store_entry(component, entry, *content_id) {
-                evaluation::ComponentCheckResult::Update(update) => {
-                    result.add_update(*update);
-                }
-                evaluation::ComponentCheckResult::CheckFailed(diagnostic) => {
-                    result.add_check_failure(diagnostic);
-                }
-                evaluation::ComponentCheckResult::UpToDate => {}
-                evaluation::ComponentCheckResult::Unresolved(_) => {
-                    unreachable!("evaluate_store_entry never returns Unresolved")
-                }
-            },
+        let check_result = match entry {
+            Some(entry) => evaluation::evaluate_store_entry(component, entry, *content_id),
+            None if gone.contains(content_id) => evaluation::ComponentCheckResult::Unresolved(
+                evaluation::gone_diagnostic(component, *content_id),
+            ),
             None => {
                 let diagnostic = Diagnostic::new(
                     component.name.clone(),
                     "failed to fetch store entry".to_string(),
                 )
+                .with_kind(DiagnosticKind::CheckFailed)
                 .with_content_id(*content_id);
-                result.add_check_failure(diagnostic);
+                evaluation::ComponentCheckResult::CheckFailed(diagnostic)
             }
-        }
+        };
+        outcomes.record(component, check_result);
     }
 
     for component in registry_components {
@@ -88,7 +88,10 @@ pub(crate) fn check_components(
                 component.name.clone(),
                 "could not match to kde store entry".to_string(),
             );
-            result.add_unresolved(diagnostic);
+            outcomes.record(
+                component,
+                evaluation::ComponentCheckResult::Unresolved(diagnostic),
+            );
         }
     }
 }
@@ -4,7 +4,9 @@
 
 use std::collections::HashMap;
 
-use crate::types::{InstalledComponent, StoreEntry};
+use serde::Serialize;
+
+use crate::types::{ComponentType, DownloadLink, DownloadVariant, InstalledComponent, StoreEntry};
 use crate::version::normalize_version;
 
 use super::IdLookup;
@@ -17,39 +19,199 @@ pub(crate) struct DownloadInfo {
 
 /// Resolves the KDE Store content ID for an installed component.
 ///
-/// Uses a three-tier resolution strategy:
+/// Uses a four-tier resolution strategy:
 /// 1. KNewStuff registry lookup via pre-built cache (most reliable)
-/// 2. Exact name match from store API results
-/// 3. Fallback widgets-id table
+/// 2. Store URL in the component's own metadata (`Website`)
+/// 3. Exact name match from store API results
+/// 4. Fallback widgets-id table
 pub(crate) fn resolve_content_id(
     component: &InstalledComponent,
     store_entries: &[StoreEntry],
     lookup: &IdLookup,
 ) -> Option<u64> {
+    resolve_content_id_with_source(component, store_entries, lookup).map(|(id, _)| id)
+}
+
+/// Like [`resolve_content_id`], and tells which tier matched.
+pub(crate) fn resolve_content_id_with_source(
+    component: &InstalledComponent,
+    store_entries: &[StoreEntry],
+    lookup: &IdLookup,
+) -> Option<(u64, ResolutionSource)> {
+    let dir = &component.directory_name;
     lookup
         .registry_id_cache
-        .get(&component.directory_name)
-        .copied()
-        .or_else(|| resolve_by_name(component, store_entries))
-        .or_else(|| resolve_by_table(component, lookup.widgets_id_table))
+        .get(dir)
+        .map(|&id| (id, ResolutionSource::Registry))
+        .or_else(|| {
+            let id = *lookup.metadata_id_cache.get(dir)?;
+            Some((id, ResolutionSource::MetadataUrl))
+        })
+        .or_else(|| {
+            Some((
+                resolve_by_name(component, store_entries)?,
+                ResolutionSource::Name,
+            ))
+        })
+        .or_else(|| {
+            let id = resolve_by_table(component, lookup.widgets_id_table)?;
+            Some((id, ResolutionSource::IdTable))
+        })
 }
 
-fn resolve_by_name(component: &InstalledComponent, store_entries: &[StoreEntry]) -> Option<u64> {
-    // Prefer entries that match both name and type
-    let type_match = store_entries.iter().find(|e| {
-        e.name.eq_ignore_ascii_case(&component.name)
-            && component.component_type.matches_type_id(e.type_id)
-    });
-
-    if let Some(entry) = type_match {
-        return Some(entry.id);
+/// Where the KDE Store content ID of a component came from, see
+/// [`Explanation::resolution`](crate::Explanation::resolution).
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum ResolutionSource {
+    /// The component's KNewStuff registry entry.
+    Registry,
+    /// The store link in the component's metadata (`Website`).
+    MetadataUrl,
+    /// A store entry with exactly the component's name.
+    Name,
+    /// [`Config::widgets_id_table`](crate::Config::widgets_id_table).
+    IdTable,
+}
+
+impl std::fmt::Display for ResolutionSource {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.write_str(match self {
+            Self::Registry => "KNewStuff registry",
+            Self::MetadataUrl => "metadata website",
+            Self::Name => "store name",
+            Self::IdTable => "widgets-id table",
+        })
     }
+}
 
-    // Fall back to name-only match (handles miscategorized store entries)
-    store_entries
+/// Maps directory names to content IDs found in the components' metadata
+/// website links, e.g. `https://store.kde.org/p/1234567`.
+pub(crate) fn build_metadata_id_cache(components: &[InstalledComponent]) -> HashMap<String, u64> {
+    components
         .iter()
-        .find(|e| e.name.eq_ignore_ascii_case(&component.name))
-        .map(|e| e.id)
+        .filter_map(|c| {
+            let metadata = super::read_package_metadata(&c.path)?;
+            let id = content_id_from_url(metadata.website()?)?;
+            Some((c.directory_name.clone(), id))
+        })
+        .collect()
+}
+
+/// Hosts serving the KDE Store catalog under `/p/<id>`.
+const STORE_HOSTS: &[&str] = &[
+    "store.kde.org",
+    "kde-look.org",
+    "pling.com",
+    "opendesktop.org",
+];
+
+/// Extracts a content ID from a KDE Store product URL.
+///
+/// Accepts `/p/<id>` links on the store and its mirrors (pling.com,
+/// opendesktop.org, kde-look.org, with or without `www.`) and the legacy
+/// `content/show.php?content=<id>` form.
+pub(crate) fn content_id_from_url(url: &str) -> Option<u64> {
+    let rest = url
+        .trim()
+        .strip_prefix("https://")
+        .or_else(|| url.trim().strip_prefix("http://"))?;
+    let (host, path) = rest.split_once('/')?;
+    let host = host.strip_prefix("www.").unwrap_or(host);
+    if !STORE_HOSTS.contains(&host) {
+        return None;
+    }
+
+    let (path, query) = path.split_once('?').unwrap_or((path, ""));
+    let from_path = path
+        .split('/')
+        .skip_while(|&segment| segment != "p")
+        .nth(1)
+        .and_then(|id| id.parse().ok());
+
+    from_path.or_else(|| {
+        query
+            .split('&')
+            .find_map(|pair| pair.strip_prefix("content="))
+            .and_then(|id| id.parse().ok())
+    })
+}
+
+fn resolve_by_name(component: &InstalledComponent, store_entries: &[StoreEntry]) -> Option<u64> {
+    // Only exact names resolve; similar ones are merely candidates.
+    rank_by_name(&component.name, component.component_type, store_entries)
+        .into_iter()
+        .find(|&(_, score)| score >= EXACT_NAME_SCORE)
+        .map(|(entry, _)| entry.id)
+}
+
+/// Score of an exact, case-insensitive name match of the right type.
+pub(crate) const EXACT_TYPE_SCORE: u8 = 100;
+/// Score of an exact name match of another type, which handles
+/// miscategorized store entries.
+pub(crate) const EXACT_NAME_SCORE: u8 = 90;
+/// Score of a name equal after dropping case, spaces and punctuation.
+pub(crate) const LOOSE_NAME_SCORE: u8 = 80;
+/// Shortest query that may match as part of a longer name.
+const MIN_PARTIAL_LEN: usize = 3;
+
+/// Ranks `store_entries` by how well their name matches `name`, best first;
+/// entries that do not match at all are left out.
+///
+/// Exact names score [`EXACT_TYPE_SCORE`] or [`EXACT_NAME_SCORE`], loose
+/// matches 80, and names containing one another 40 to 70 by how much of the
+/// longer name they cover. Equal scores keep the order of `store_entries`.
+pub(crate) fn rank_by_name<'a>(
+    name: &str,
+    component_type: ComponentType,
+    store_entries: &'a [StoreEntry],
+) -> Vec<(&'a StoreEntry, u8)> {
+    let loose = loose_name(name);
+    let mut ranked: Vec<(&StoreEntry, u8)> = store_entries
+        .iter()
+        .filter_map(|entry| {
+            let score = if entry.name.eq_ignore_ascii_case(name) {
+                if component_type.matches_type_id(entry.type_id) {
+                    EXACT_TYPE_SCORE
+                } else {
+                    EXACT_NAME_SCORE
+                }
+            } else {
+                partial_score(&loose, &loose_name(&entry.name))?
+            };
+            Some((entry, score))
+        })
+        .collect();
+    ranked.sort_by_key(|&(_, score)| std::cmp::Reverse(score));
+    ranked
+}
+
+fn partial_score(query: &str, candidate: &str) -> Option<u8> {
+    if query.is_empty() || candidate.is_empty() {
+        return None;
+    }
+    if query == candidate {
+        return Some(LOOSE_NAME_SCORE);
+    }
+    let (shorter, longer) = if query.len() <= candidate.len() {
+        (query, candidate)
+    } else {
+        (candidate, query)
+    };
+    if shorter.len() < MIN_PARTIAL_LEN || !longer.contains(shorter) {
+        return None;
+    }
+    let coverage = shorter.len() * 30 / longer.len();
+    Some(40 + u8::try_from(coverage).unwrap_or(30))
+}
+
+/// Lowercase letters and digits of `name`, so "Event Calendar" and
+/// "event-calendar" compare equal.
+fn loose_name(name: &str) -> String {
+    name.chars()
+        .filter(|c| c.is_alphanumeric())
+        .flat_map(char::to_lowercase)
+        .collect()
 }
 
 fn resolve_by_table(
@@ -64,22 +226,68 @@ fn is_signature_file(url: &str) -> bool {
     url.ends_with(".asc") || url.ends_with(".sig")
 }
 
+/// Archive links of `entry`, without detached signatures.
+fn download_candidates(entry: &StoreEntry) -> Vec<&DownloadLink> {
+    entry
+        .download_links
+        .iter()
+        .filter(|l| !is_signature_file(&l.url))
+        .collect()
+}
+
+/// All downloads of `entry` as variants the user can choose from, or an
+/// empty list if there is only one.
+pub(crate) fn download_variants(entry: &StoreEntry) -> Vec<DownloadVariant> {
+    let candidates = download_candidates(entry);
+    if candidates.len() < 2 {
+        return Vec::new();
+    }
+    candidates.into_iter().map(DownloadVariant::from).collect()
+}
+
+/// Plasma major version a download is labelled for ("plasma6", "Plasma 5",
+/// "kf6", "qt6", ...), if any.
+fn labelled_plasma_major(link: &DownloadLink) -> Option<u32> {
+    let text = format!("{} {} {}", link.name, link.version, link.url).to_lowercase();
+    ["plasma", "kf", "qt"].iter().find_map(|prefix| {
+        text.match_indices(prefix).find_map(|(i, _)| {
+            text[i + prefix.len()..]
+                .trim_start_matches([' ', '-', '_', '.'])
+                .chars()
+                .next()
+                .filter(|c| matches!(c, '5' | '6'))
+                .and_then(|c| c.to_digit(10))
+        })
+    })
+}
+
 pub(crate) fn select_download_with_info(
     entry: &StoreEntry,
     target_version: &str,
 ) -> Option<DownloadInfo> {
-    if entry.download_links.is_empty() {
+    select_download_for_plasma(entry, target_version, crate::paths::plasma_major_version())
+}
+
+/// Picks the download for `target_version`, skipping links labelled for a
+/// different Plasma major version than `plasma_major` when others remain.
+fn select_download_for_plasma(
+    entry: &StoreEntry,
+    target_version: &str,
+    plasma_major: u32,
+) -> Option<DownloadInfo> {
+    let mut candidates = download_candidates(entry);
+
+    if candidates.is_empty() {
         return None;
     }
 
-    let candidates: Vec<_> = entry
-        .download_links
+    let compatible: Vec<_> = candidates
         .iter()
-        .filter(|l| !is_signature_file(&l.url))
+        .copied()
+        .filter(|l| labelled_plasma_major(l).is_none_or(|m| m == plasma_major))
         .collect();
-
-    if candidates.is_empty() {
-        return None;
+    if !compatible.is_empty() {
+        candidates = compatible;
     }
 
     let link = if candidates.len() == 1 {
@@ -123,17 +331,12 @@ pub(crate) fn resolve_by_name_only(
 mod tests {
     use super::*;
     use crate::types::{ComponentType, InstalledComponent};
-    use std::path::PathBuf;
 
     fn make_component(name: &str, dir_name: &str, ct: ComponentType) -> InstalledComponent {
         InstalledComponent {
-            name: name.to_string(),
             directory_name: dir_name.to_string(),
             version: "1.0.0".to_string(),
-            component_type: ct,
-            path: PathBuf::from("/tmp/test"),
-            is_system: false,
-            release_date: String::new(),
+            ..InstalledComponent::fixture(name, ct, "/tmp/test")
         }
     }
 
@@ -145,6 +348,10 @@ mod tests {
             type_id,
             download_links: vec![],
             changed_date: String::new(),
+            author: String::new(),
+            preview_url: String::new(),
+            thumbnail_url: String::new(),
+            changelog: String::new(),
         }
     }
 
@@ -152,6 +359,43 @@ mod tests {
         (HashMap::new(), HashMap::new())
     }
 
+    #[test]
+    fn content_id_from_store_urls() {
+        assert_eq!(
+            content_id_from_url("https://store.kde.org/p/1234567"),
+            Some(1234567)
+        );
+        assert_eq!(
+            content_id_from_url("https://www.pling.com/p/2000001/"),
+            Some(2000001)
+        );
+        assert_eq!(
+            content_id_from_url("https://www.opendesktop.org/c/1460735/p/998877"),
+            Some(998877)
+        );
+        assert_eq!(
+            content_id_from_url("http://kde-look.org/content/show.php?content=123456"),
+            Some(123456)
+        );
+        assert_eq!(content_id_from_url("https://github.com/example/p/42"), None);
+        assert_eq!(content_id_from_url("https://store.kde.org/browse"), None);
+    }
+
+    #[test]
+    fn metadata_id_beats_name_match() {
+        let component = make_component("Clock", "org.example.clock", ComponentType::PlasmaWidget);
+        let entries = vec![make_entry(100, "Clock", 705)];
+        let (wid, reg) = empty_lookup();
+        let metadata = HashMap::from([("org.example.clock".to_string(), 200)]);
+        let lookup = IdLookup {
+            widgets_id_table: &wid,
+            registry_id_cache: &reg,
+            metadata_id_cache: &metadata,
+        };
+
+        assert_eq!(resolve_content_id(&component, &entries, &lookup), Some(200));
+    }
+
     #[test]
     fn name_match_ignores_type_id() {
         let component = make_component(
@@ -164,6 +408,7 @@ mod tests {
         let lookup = IdLookup {
             widgets_id_table: &wid,
             registry_id_cache: &reg,
+            metadata_id_cache: &HashMap::new(),
         };
 
         let result = resolve_content_id(&component, &entries, &lookup);
@@ -178,6 +423,7 @@ mod tests {
         let lookup = IdLookup {
             widgets_id_table: &wid,
             registry_id_cache: &reg,
+            metadata_id_cache: &HashMap::new(),
         };
 
         let result = resolve_content_id(&component, &entries, &lookup);
@@ -196,12 +442,72 @@ mod tests {
         let lookup = IdLookup {
             widgets_id_table: &wid,
             registry_id_cache: &reg,
+            metadata_id_cache: &HashMap::new(),
         };
 
         let result = resolve_content_id(&component, &entries, &lookup);
         assert_eq!(result, Some(555));
     }
 
+    fn plasma_variants_entry() -> StoreEntry {
+        let link = |name: &str| DownloadLink {
+            url: format!("https://example.com/{name}"),
+            name: name.to_string(),
+            version: "2.0".to_string(),
+            checksum: None,
+            size_kb: None,
+        };
+        StoreEntry {
+            id: 1,
+            name: "Clock".to_string(),
+            version: "2.0".to_string(),
+            type_id: 705,
+            download_links: vec![
+                link("clock-plasma5.tar.gz"),
+                link("clock-plasma6.tar.gz"),
+                link("clock.tar.gz.sig"),
+            ],
+            changed_date: String::new(),
+            author: String::new(),
+            preview_url: String::new(),
+            thumbnail_url: String::new(),
+            changelog: String::new(),
+        }
+    }
+
+    #[test]
+    fn download_skips_links_for_other_plasma_versions() {
+        let entry = plasma_variants_entry();
+
+        let plasma6 = select_download_for_plasma(&entry, "2.0", 6).unwrap();
+        assert_eq!(plasma6.url, "https://example.com/clock-plasma6.tar.gz");
+
+        let plasma5 = select_download_for_plasma(&entry, "2.0", 5).unwrap();
+        assert_eq!(plasma5.url, "https://example.com/clock-plasma5.tar.gz");
+    }
+
+    #[test]
+    fn variants_list_archives_only() {
+        let variants = download_variants(&plasma_variants_entry());
+        let labels: Vec<&str> = variants.iter().map(|v| v.label()).collect();
+        assert_eq!(labels, ["clock-plasma5.tar.gz", "clock-plasma6.tar.gz"]);
+    }
+
+    #[test]
+    fn plasma_major_is_read_from_labels() {
+        let link = |name: &str| DownloadLink {
+            url: "https://example.com/pkg.tar.gz".to_string(),
+            name: name.to_string(),
+            version: String::new(),
+            checksum: None,
+            size_kb: None,
+        };
+        assert_eq!(labelled_plasma_major(&link("Plasma 6 version")), Some(6));
+        assert_eq!(labelled_plasma_major(&link("widget-kf5.zip")), Some(5));
+        assert_eq!(labelled_plasma_major(&link("qtquick-widget.zip")), None);
+        assert_eq!(labelled_plasma_major(&link("widget.zip")), None);
+    }
+
     #[test]
     fn download_link_matches_with_normalized_version() {
         use crate::types::DownloadLink;
@@ -214,18 +520,24 @@ mod tests {
             download_links: vec![
                 DownloadLink {
                     url: "https://example.com/old.tar.gz".to_string(),
+                    name: String::new(),
                     version: "v1.0.0".to_string(),
                     checksum: None,
                     size_kb: None,
                 },
                 DownloadLink {
                     url: "https://example.com/new.tar.gz".to_string(),
+                    name: String::new(),
                     version: "v2.0.0".to_string(),
                     checksum: None,
                     size_kb: None,
                 },
             ],
             changed_date: String::new(),
+            author: String::new(),
+            preview_url: String::new(),
+            thumbnail_url: String::new(),
+            changelog: String::new(),
         };
 
         let result = select_download_with_info(&entry, "2.0.0");
@@ -245,18 +557,24 @@ mod tests {
             download_links: vec![
                 DownloadLink {
                     url: "https://example.com/a.tar.gz".to_string(),
+                    name: String::new(),
                     version: "2.0.0".to_string(),
                     checksum: None,
                     size_kb: None,
                 },
                 DownloadLink {
                     url: "https://example.com/b.tar.gz".to_string(),
+                    name: String::new(),
                     version: "2.0.0".to_string(),
                     checksum: None,
                     size_kb: None,
                 },
             ],
             changed_date: String::new(),
+            author: String::new(),
+            preview_url: String::new(),
+            thumbnail_url: String::new(),
+            changelog: String::new(),
         };
 
         let result = select_download_with_info(&entry, "2.0.0");
@@ -278,6 +596,7 @@ mod tests {
         let lookup = IdLookup {
             widgets_id_table: &wid,
             registry_id_cache: &reg,
+            metadata_id_cache: &HashMap::new(),
         };
 
         let result = resolve_content_id(&component, &entries, &lookup);
@@ -295,18 +614,24 @@ mod tests {
             download_links: vec![
                 DownloadLink {
                     url: "https://example.com/pkg.tar.gz.asc".to_string(),
+                    name: String::new(),
                     version: "1.0.0".to_string(),
                     checksum: None,
                     size_kb: None,
                 },
                 DownloadLink {
                     url: "https://example.com/pkg.tar.gz".to_string(),
+                    name: String::new(),
                     version: "1.0.0".to_string(),
                     checksum: None,
                     size_kb: None,
                 },
             ],
             changed_date: String::new(),
+            author: String::new(),
+            preview_url: String::new(),
+            thumbnail_url: String::new(),
+            changelog: String::new(),
         };
         let result = select_download_with_info(&entry, "1.0.0");
         assert!(result.is_some());
@@ -323,11 +648,16 @@ mod tests {
             type_id: 705,
             download_links: vec![DownloadLink {
                 url: "https://example.com/pkg.tar.gz.asc".to_string(),
+                name: String::new(),
                 version: "1.0.0".to_string(),
                 checksum: None,
                 size_kb: None,
             }],
             changed_date: String::new(),
+            author: String::new(),
+            preview_url: String::new(),
+            thumbnail_url: String::new(),
+            changelog: String::new(),
         };
         let result = select_download_with_info(&entry, "1.0.0");
         assert!(result.is_none());
@@ -345,8 +675,40 @@ mod tests {
         let lookup = IdLookup {
             widgets_id_table: &wid,
             registry_id_cache: &reg,
+            metadata_id_cache: &HashMap::new(),
         };
         let result = resolve_content_id(&component, &entries, &lookup);
         assert_eq!(result, Some(42));
     }
+
+    #[test]
+    fn rank_orders_exact_loose_and_partial_names() {
+        let entries = vec![
+            make_entry(1, "Event Calendar Plus", 705),
+            make_entry(2, "event-calendar", 705),
+            make_entry(3, "Event Calendar", 705),
+            make_entry(4, "Weather", 705),
+        ];
+
+        let ranked: Vec<(u64, u8)> =
+            rank_by_name("Event Calendar", ComponentType::PlasmaWidget, &entries)
+                .into_iter()
+                .map(|(e, score)| (e.id, score))
+                .collect();
+
+        assert_eq!(ranked, [(3, 100), (2, 80), (1, 62)]);
+    }
+
+    #[test]
+    fn partial_name_match_does_not_resolve() {
+        let component = make_component("Clock", "org.example.clock", ComponentType::PlasmaWidget);
+        let entries = vec![make_entry(7, "Clock Plus", 705)];
+
+        assert_eq!(resolve_by_name(&component, &entries), None);
+        assert_eq!(
+            rank_by_name("Clock", ComponentType::PlasmaWidget, &entries).len(),
+            1
+        );
+        assert!(rank_by_name("Cl", ComponentType::PlasmaWidget, &entries).is_empty());
+    }
 }
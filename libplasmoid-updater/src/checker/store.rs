@@ -3,13 +3,22 @@
 use std::collections::HashSet;
 
 use crate::{
-    Result,
+    Error, FetchStrategy, Result,
     api::ApiClient,
-    types::{ComponentType, InstalledComponent, StoreEntry},
+    types::{CatalogFetchReport, ComponentType, InstalledComponent, StoreEntry},
 };
 
 use super::IdLookup;
 
+/// Store entries fetched for a check.
+#[derive(Debug, Default)]
+pub(crate) struct FetchedEntries {
+    pub entries: Vec<StoreEntry>,
+    /// Known content IDs the store no longer has, e.g. deleted entries.
+    pub gone: HashSet<u64>,
+    pub catalog: CatalogFetchReport,
+}
+
 pub(crate) fn partition_components(
     components: Vec<InstalledComponent>,
 ) -> (Vec<InstalledComponent>, Vec<InstalledComponent>) {
@@ -21,19 +30,17 @@ pub(crate) fn partition_components(
 /// Fetches the minimum set of store entries needed to evaluate `regular_components`,
 /// ensuring each entry is retrieved at most once.
 ///
-/// Strategy:
-/// 1. Resolve content IDs from local data (registry cache + widgets-id table) — no network.
-/// 2. Fetch catalog pages for every distinct component type present, regardless of
-///    whether IDs are already known. A single catalog page covers ≤100 entries,
-///    converting O(n) targeted fetches into O(distinct_types) catalog requests.
-/// 3. For known IDs genuinely absent from the catalog, issue one targeted request per ID.
+/// Content IDs are first resolved from local data (registry cache, metadata
+/// store URLs, widgets-id table) without touching the network. `strategy`
+/// then picks between [`fetch_catalog_first`] and [`fetch_targeted`].
 pub(crate) fn fetch_store_entries(
     client: &ApiClient,
     regular_components: &[InstalledComponent],
     lookup: &IdLookup,
-) -> Result<Vec<StoreEntry>> {
+    strategy: FetchStrategy,
+) -> Result<FetchedEntries> {
     if regular_components.is_empty() {
-        return Ok(Vec::new());
+        return Ok(FetchedEntries::default());
     }
 
     let known_ids: HashSet<u64> = regular_components
@@ -41,10 +48,32 @@ pub(crate) fn fetch_store_entries(
         .filter_map(|c| resolve_id_locally(c, lookup))
         .collect();
 
+    let targeted = match strategy {
+        FetchStrategy::Catalog => false,
+        FetchStrategy::Targeted => true,
+        FetchStrategy::Auto => known_ids.len() <= FetchStrategy::AUTO_TARGETED_LIMIT,
+    };
+
+    if targeted {
+        fetch_targeted(client, regular_components, lookup, &known_ids)
+    } else {
+        fetch_catalog_first(client, regular_components, &known_ids)
+    }
+}
+
+/// Fetches catalog pages for every distinct component type present, regardless
+/// of whether IDs are already known. A single catalog page covers ≤100 entries,
+/// converting O(n) targeted fetches into O(distinct_types) catalog requests.
+/// Known IDs genuinely absent from the catalog get one targeted request each.
+fn fetch_catalog_first(
+    client: &ApiClient,
+    regular_components: &[InstalledComponent],
+    known_ids: &HashSet<u64>,
+) -> Result<FetchedEntries> {
     // Always fetch catalog for all distinct component types — not just unresolved ones.
     // When all IDs are locally known, skipping this forces one targeted request per ID.
     let types = distinct_types(regular_components);
-    let catalog_entries = client.fetch_all(&types)?;
+    let (mut catalog_entries, catalog) = client.fetch_all_with_report(&types)?;
 
     // Targeted fetch only for known IDs genuinely absent from the catalog
     // (e.g. old/unlisted components that no longer appear in recent pages).
@@ -55,26 +84,67 @@ pub(crate) fn fetch_store_entries(
         .filter(|id| !catalog_ids.contains(id))
         .collect();
 
-    let targeted_entries: Vec<StoreEntry> = if !missing_ids.is_empty() {
-        client
-            .fetch_details(&missing_ids)
-            .into_iter()
-            .filter_map(|r| r.ok())
-            .collect()
-    } else {
-        Vec::new()
-    };
+    let mut targeted = fetch_known(client, &missing_ids);
+    catalog_entries.append(&mut targeted.entries);
 
-    Ok(catalog_entries
-        .into_iter()
-        .chain(targeted_entries)
-        .collect())
+    Ok(FetchedEntries {
+        entries: catalog_entries,
+        gone: targeted.gone,
+        catalog,
+    })
+}
+
+/// Fetches each known ID directly, and pages through the catalog only for the
+/// types of components whose ID is unknown or could not be fetched, so they
+/// can still be matched by name.
+fn fetch_targeted(
+    client: &ApiClient,
+    regular_components: &[InstalledComponent],
+    lookup: &IdLookup,
+    known_ids: &HashSet<u64>,
+) -> Result<FetchedEntries> {
+    let ids: Vec<u64> = known_ids.iter().copied().collect();
+    let mut fetched = fetch_known(client, &ids);
+
+    let fetched_ids: HashSet<u64> = fetched.entries.iter().map(|e| e.id).collect();
+    let unmatched_types = distinct_types(
+        regular_components
+            .iter()
+            .filter(|c| resolve_id_locally(c, lookup).is_none_or(|id| !fetched_ids.contains(&id))),
+    );
+
+    if !unmatched_types.is_empty() {
+        let (catalog, report) = client.fetch_all_with_report(&unmatched_types)?;
+        fetched.catalog = report;
+        fetched
+            .entries
+            .extend(catalog.into_iter().filter(|e| !fetched_ids.contains(&e.id)));
+    }
+
+    Ok(fetched)
+}
+
+/// Fetches `ids` one by one, noting those the store no longer has.
+fn fetch_known(client: &ApiClient, ids: &[u64]) -> FetchedEntries {
+    let mut fetched = FetchedEntries::default();
+    for (result, &id) in client.fetch_details(ids).into_iter().zip(ids) {
+        match result {
+            Ok(entry) => fetched.entries.push(entry),
+            Err(Error::ComponentNotFound(_)) => {
+                log::debug!(target: "api", "store entry {id} is gone");
+                fetched.gone.insert(id);
+            }
+            Err(e) => log::debug!(target: "api", "targeted fetch of {id} failed: {e}"),
+        }
+    }
+    fetched
 }
 
 fn resolve_id_locally(component: &InstalledComponent, lookup: &IdLookup) -> Option<u64> {
     lookup
         .registry_id_cache
         .get(&component.directory_name)
+        .or_else(|| lookup.metadata_id_cache.get(&component.directory_name))
         .copied()
         .or_else(|| {
             lookup
@@ -84,11 +154,102 @@ fn resolve_id_locally(component: &InstalledComponent, lookup: &IdLookup) -> Opti
         })
 }
 
-fn distinct_types(components: &[InstalledComponent]) -> Vec<ComponentType> {
+fn distinct_types<'a>(
+    components: impl IntoIterator<Item = &'a InstalledComponent>,
+) -> Vec<ComponentType> {
     let mut seen = HashSet::new();
     components
-        .iter()
+        .into_iter()
         .map(|c| c.component_type)
         .filter(|t| seen.insert(*t))
         .collect()
 }
+
+#[cfg(test)]
+mod tests {
+    use std::{collections::HashMap, sync::Arc};
+
+    use super::*;
+    use crate::api::{FixtureBackend, HttpResponse};
+
+    fn plasmoid(directory_name: &str) -> InstalledComponent {
+        InstalledComponent::fixture(directory_name, ComponentType::PlasmaWidget, "")
+    }
+
+    fn fetch(
+        components: &[InstalledComponent],
+        strategy: FetchStrategy,
+    ) -> (FetchedEntries, Vec<String>) {
+        let backend = FixtureBackend::sample();
+        let client = ApiClient::new().with_backend(Arc::new(backend.clone()));
+        let table = HashMap::from([("org.example.clock".to_string(), 1000001)]);
+        let empty = HashMap::new();
+        let lookup = IdLookup {
+            widgets_id_table: &table,
+            registry_id_cache: &empty,
+            metadata_id_cache: &empty,
+        };
+
+        let entries = fetch_store_entries(&client, components, &lookup, strategy).unwrap();
+        (entries, backend.requests())
+    }
+
+    #[test]
+    fn targeted_fetch_skips_catalog_when_all_ids_are_known() {
+        let (fetched, requests) = fetch(&[plasmoid("org.example.clock")], FetchStrategy::Targeted);
+
+        assert_eq!(fetched.entries.len(), 1);
+        assert_eq!(requests, ["/content/data/1000001"]);
+    }
+
+    #[test]
+    fn targeted_fetch_pages_catalog_for_unknown_components() {
+        let components = [plasmoid("org.example.clock"), plasmoid("org.example.other")];
+        let (fetched, requests) = fetch(&components, FetchStrategy::Auto);
+
+        assert_eq!(requests.len(), 2);
+        assert!(requests[1].starts_with("/content/data?categories=705"));
+        let ids: HashSet<u64> = fetched.entries.iter().map(|e| e.id).collect();
+        assert_eq!(
+            ids.len(),
+            fetched.entries.len(),
+            "entries are not duplicated"
+        );
+    }
+
+    #[test]
+    fn targeted_fetch_reports_deleted_entries_as_gone() {
+        let backend = FixtureBackend::sample().with_http_response(
+            "/content/data/1000009",
+            HttpResponse {
+                status: 404,
+                ..HttpResponse::ok("")
+            },
+        );
+        let client = ApiClient::new().with_backend(Arc::new(backend));
+        let table = HashMap::from([
+            ("org.example.clock".to_string(), 1000001),
+            ("org.example.gone".to_string(), 1000009),
+        ]);
+        let empty = HashMap::new();
+        let lookup = IdLookup {
+            widgets_id_table: &table,
+            registry_id_cache: &empty,
+            metadata_id_cache: &empty,
+        };
+        let components = [plasmoid("org.example.clock"), plasmoid("org.example.gone")];
+
+        let fetched =
+            fetch_store_entries(&client, &components, &lookup, FetchStrategy::Targeted).unwrap();
+
+        assert_eq!(fetched.gone, HashSet::from([1000009]));
+        assert!(fetched.entries.iter().any(|e| e.id == 1000001));
+    }
+
+    #[test]
+    fn catalog_fetch_pages_catalog_first() {
+        let (_, requests) = fetch(&[plasmoid("org.example.clock")], FetchStrategy::Catalog);
+
+        assert!(requests[0].starts_with("/content/data?categories=705"));
+    }
+}
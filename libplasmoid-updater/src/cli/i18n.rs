@@ -0,0 +1,262 @@
+// SPDX-License-Identifier: GPL-3.0-or-later
+
+//! Translations of terminal output and prompts.
+//!
+//! Messages are looked up by ID in a Fluent catalog for the language in
+//! `LC_ALL`, `LC_MESSAGES` or `LANG`, in that order. The English text is
+//! written at each call site with [`tr!`](crate::tr) and used whenever the
+//! catalog has no translation, the language is not shipped, or the `i18n`
+//! feature is disabled.
+//!
+//! Catalogs live in `libplasmoid-updater/i18n/<language>.ftl`. To add a
+//! language, copy `de.ftl`, translate the messages and list the file in
+//! `CATALOGS`.
+
+/// A value substituted into a translated message.
+#[doc(hidden)]
+#[derive(Debug, Clone, PartialEq)]
+pub enum Arg {
+    /// Selects plural forms in the catalogs.
+    Number(f64),
+    Text(String),
+}
+
+/// Converts a value for use in a translated message, see [`tr!`](crate::tr).
+#[doc(hidden)]
+pub trait ToArg {
+    fn to_arg(&self) -> Arg;
+}
+
+macro_rules! number_args {
+    ($($ty:ty),*) => {
+        $(impl ToArg for $ty {
+            fn to_arg(&self) -> Arg {
+                Arg::Number(*self as f64)
+            }
+        })*
+    };
+}
+
+number_args!(u8, u16, u32, u64, usize, i32, i64, f64);
+
+impl ToArg for str {
+    fn to_arg(&self) -> Arg {
+        Arg::Text(self.to_string())
+    }
+}
+
+impl ToArg for String {
+    fn to_arg(&self) -> Arg {
+        Arg::Text(self.clone())
+    }
+}
+
+/// Formats a message in the user's language, falling back to English.
+///
+/// ```rust,no_run
+/// use libplasmoid_updater::tr;
+///
+/// let line = tr!("count-updates", "{count} updates available.", count = 3);
+/// ```
+///
+/// Each `name = value` pair is available as `{name}` in the English text and
+/// as `{ $name }` in the catalogs. Numbers select plural forms there.
+#[doc(hidden)]
+#[macro_export]
+macro_rules! tr {
+    ($id:literal, $english:literal $(, $name:ident = $value:expr)* $(,)?) => {{
+        #[allow(unused_imports)]
+        use $crate::cli::i18n::ToArg as _;
+        $(let $name = &$value;)*
+        $crate::cli::i18n::translate($id, &[$((stringify!($name), $name.to_arg())),*])
+        .unwrap_or_else(|| format!($english))
+    }};
+}
+
+/// Looks up `id` in the catalog for the user's language, with `args`
+/// substituted. `None` if there is no translation.
+#[doc(hidden)]
+#[cfg(feature = "i18n")]
+pub fn translate(id: &str, args: &[(&str, Arg)]) -> Option<String> {
+    catalog::translate(id, args)
+}
+
+/// Looks up `id` in the catalog for the user's language, with `args`
+/// substituted. `None` if there is no translation.
+#[doc(hidden)]
+#[cfg(not(feature = "i18n"))]
+pub fn translate(_id: &str, _args: &[(&str, Arg)]) -> Option<String> {
+    None
+}
+
+/// The language of the translations in use, e.g. `de`; `None` for English.
+pub fn language() -> Option<&'static str> {
+    #[cfg(feature = "i18n")]
+    return catalog::get().map(|catalog| catalog.language);
+    #[cfg(not(feature = "i18n"))]
+    None
+}
+
+/// Extracts the language from a POSIX locale such as `de_DE.UTF-8` or
+/// `es_ES@euro`. `None` for the `C` and `POSIX` locales.
+#[cfg_attr(not(feature = "i18n"), allow(dead_code))]
+fn parse_locale(value: &str) -> Option<&str> {
+    let name = value.split(['.', '@']).next()?;
+    let language = name.split(['_', '-']).next()?;
+    match language {
+        "" | "C" | "POSIX" => None,
+        language => Some(language),
+    }
+}
+
+#[cfg(feature = "i18n")]
+mod catalog {
+    use std::sync::OnceLock;
+
+    use fluent_bundle::{FluentArgs, FluentResource, FluentValue, concurrent::FluentBundle};
+    use unic_langid::LanguageIdentifier;
+
+    use super::{Arg, parse_locale};
+
+    /// Shipped translations by language.
+    pub(super) const CATALOGS: &[(&str, &str)] = &[
+        ("de", include_str!("../../i18n/de.ftl")),
+        ("es", include_str!("../../i18n/es.ftl")),
+    ];
+
+    pub(super) struct Catalog {
+        pub(super) language: &'static str,
+        pub(super) bundle: FluentBundle<FluentResource>,
+    }
+
+    static CATALOG: OnceLock<Option<Catalog>> = OnceLock::new();
+
+    pub(super) fn get() -> Option<&'static Catalog> {
+        CATALOG.get_or_init(load).as_ref()
+    }
+
+    pub(super) fn translate(id: &str, args: &[(&str, Arg)]) -> Option<String> {
+        let bundle = &get()?.bundle;
+        let pattern = bundle.get_message(id)?.value()?;
+
+        let mut fluent_args = FluentArgs::with_capacity(args.len());
+        for (name, value) in args {
+            let value = match value {
+                Arg::Number(n) => FluentValue::from(*n),
+                Arg::Text(s) => FluentValue::from(s.as_str()),
+            };
+            fluent_args.set(*name, value);
+        }
+
+        let mut errors = Vec::new();
+        let text = bundle.format_pattern(pattern, Some(&fluent_args), &mut errors);
+        if !errors.is_empty() {
+            log::debug!(target: "i18n", "formatting {id} failed: {errors:?}");
+            return None;
+        }
+        Some(text.into_owned())
+    }
+
+    /// The first of `LC_ALL`, `LC_MESSAGES` and `LANG` that is set decides
+    /// the language, as with gettext.
+    fn requested_language() -> Option<String> {
+        let locale = ["LC_ALL", "LC_MESSAGES", "LANG"]
+            .into_iter()
+            .filter_map(|var| std::env::var(var).ok())
+            .find(|value| !value.is_empty())?;
+        parse_locale(&locale).map(str::to_string)
+    }
+
+    fn load() -> Option<Catalog> {
+        let requested = requested_language()?;
+        let &(language, source) = CATALOGS.iter().find(|(lang, _)| *lang == requested)?;
+        build(language, source)
+    }
+
+    pub(super) fn build(language: &'static str, source: &str) -> Option<Catalog> {
+        let resource = match FluentResource::try_new(source.to_string()) {
+            Ok(resource) => resource,
+            Err((_, errors)) => {
+                log::warn!(target: "i18n", "invalid {language} catalog: {errors:?}");
+                return None;
+            }
+        };
+        let langid: LanguageIdentifier = language.parse().ok()?;
+        let mut bundle = FluentBundle::new_concurrent(vec![langid]);
+        // Unicode isolation marks show up as stray characters in terminals.
+        bundle.set_use_isolating(false);
+        bundle.add_resource(resource).ok()?;
+        Some(Catalog { language, bundle })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn locale_names_map_to_languages() {
+        assert_eq!(parse_locale("de_DE.UTF-8"), Some("de"));
+        assert_eq!(parse_locale("es_ES@euro"), Some("es"));
+        assert_eq!(parse_locale("pt-BR"), Some("pt"));
+        assert_eq!(parse_locale("de"), Some("de"));
+        assert_eq!(parse_locale("C.UTF-8"), None);
+        assert_eq!(parse_locale("POSIX"), None);
+        assert_eq!(parse_locale(""), None);
+    }
+
+    #[test]
+    fn untranslated_messages_fall_back_to_english() {
+        let count = 2;
+        assert_eq!(
+            crate::tr!(
+                "no-such-message",
+                "{count} updates available.",
+                count = count
+            ),
+            "2 updates available."
+        );
+    }
+
+    #[cfg(feature = "i18n")]
+    #[test]
+    fn catalogs_parse_and_translate_the_same_messages() {
+        use std::collections::BTreeSet;
+
+        // Message IDs start a line, attributes and variants are indented.
+        let ids = |source: &str| -> BTreeSet<String> {
+            source
+                .lines()
+                .filter_map(|line| line.split_once(" ="))
+                .map(|(id, _)| id)
+                .filter(|id| id.starts_with(|c: char| c.is_ascii_lowercase()))
+                .map(str::to_string)
+                .collect()
+        };
+
+        let (_, reference) = catalog::CATALOGS[0];
+        for &(language, source) in catalog::CATALOGS {
+            assert!(catalog::build(language, source).is_some(), "{language}");
+            assert_eq!(ids(source), ids(reference), "{language}");
+        }
+    }
+
+    #[cfg(feature = "i18n")]
+    #[test]
+    fn catalog_selects_plural_forms() {
+        let (language, source) = catalog::CATALOGS[0];
+        let catalog = catalog::build(language, source).unwrap();
+        let format = |count: usize| {
+            let message = catalog.bundle.get_message("count-updates").unwrap();
+            let mut args = fluent_bundle::FluentArgs::new();
+            args.set("count", count);
+            let mut errors = Vec::new();
+            catalog
+                .bundle
+                .format_pattern(message.value().unwrap(), Some(&args), &mut errors)
+                .into_owned()
+        };
+        assert_eq!(format(1), "1 Aktualisierung verfügbar.");
+        assert_eq!(format(3), "3 Aktualisierungen verfügbar.");
+    }
+}
@@ -8,8 +8,10 @@
 //! non-CLI consumers (like topgrade) to use the library without pulling in
 //! terminal UI dependencies.
 
+pub mod i18n;
 pub(crate) mod output;
-pub(crate) mod progress;
+pub mod progress;
+pub mod theme;
 pub(crate) mod update_ui;
 
 pub(crate) const CLEAR_LINE_SEQUENCE: &str = "\x1b[1A\r\x1b[2K";
@@ -3,9 +3,12 @@
 use bytesize::ByteSize;
 use comfy_table::{Attribute, Cell, CellAlignment, Table, presets};
 
+use super::theme::{Stream, Style, paint};
 use crate::{
-    FailedUpdate, UpdateResult,
-    types::{AvailableUpdate, InstalledComponent},
+    AdoptedComponent, CatalogFetchReport, ComponentUsage, DiskUsage, DoctorReport, DoctorStatus,
+    FailedUpdate, OutcomeStatus, RegistryIssue, RestartAction, RunComponent, RunRecord, TypeUsage,
+    UpdateResult, tr,
+    types::{AvailableUpdate, InstalledComponent, UnrecognizedComponent},
 };
 
 pub fn format_version(version: &str) -> &str {
@@ -17,7 +20,44 @@ pub fn format_version(version: &str) -> &str {
 }
 
 fn header(name: &str) -> Cell {
-    Cell::new(name).add_attribute(Attribute::Bold)
+    Cell::new(column(name)).add_attribute(Attribute::Bold)
+}
+
+/// Translates a column header given in English.
+fn column(name: &str) -> String {
+    match name {
+        "NAME" => tr!("column-name", "NAME"),
+        "CURRENT" => tr!("column-current", "CURRENT"),
+        "AVAILABLE" => tr!("column-available", "AVAILABLE"),
+        "ID" => tr!("column-id", "ID"),
+        "SIZE" => tr!("column-size", "SIZE"),
+        "TYPE" => tr!("column-type", "TYPE"),
+        "VERSION" => tr!("column-version", "VERSION"),
+        "COUNT" => tr!("column-count", "COUNT"),
+        "FROM" => tr!("column-from", "FROM"),
+        "TO" => tr!("column-to", "TO"),
+        "TIME" => tr!("column-time", "TIME"),
+        "STEP" => tr!("column-step", "STEP"),
+        "ERROR" => tr!("column-error", "ERROR"),
+        "REGISTRY" => tr!("column-registry", "REGISTRY"),
+        "ISSUE" => tr!("column-issue", "ISSUE"),
+        "SEVERITY" => tr!("column-severity", "SEVERITY"),
+        _ => name.to_string(),
+    }
+}
+
+/// Describes a restart action in the user's language, e.g. "restart
+/// plasmashell".
+pub(crate) fn describe_restart_action(action: RestartAction) -> String {
+    match action {
+        RestartAction::None | RestartAction::SddmNone => tr!("restart-none", "do nothing"),
+        RestartAction::FontCacheRefresh => tr!("restart-font-cache", "refresh the font cache"),
+        RestartAction::KWinReconfigure => tr!("restart-kwin", "reconfigure KWin"),
+        RestartAction::LookAndFeelReapply => {
+            tr!("restart-look-and-feel", "re-apply the updated themes")
+        }
+        RestartAction::PlasmashellRestart => tr!("restart-plasmashell", "restart plasmashell"),
+    }
 }
 
 fn right(value: &str) -> Cell {
@@ -34,6 +74,7 @@ impl TableRow for AvailableUpdate {
             Cell::new(&self.installed.name),
             right(format_version(&self.installed.version)),
             right(format_version(&self.latest_version)),
+            Cell::new(self.severity.to_string()),
             right(&self.content_id.to_string()),
             right(&format_download_size(self.download_size)),
             Cell::new(self.installed.component_type.to_string()),
@@ -51,9 +92,78 @@ impl TableRow for InstalledComponent {
     }
 }
 
+/// An installed component listed with its size on disk.
+struct SizedComponent<'a>(&'a InstalledComponent);
+
+impl TableRow for SizedComponent<'_> {
+    fn to_row(&self) -> Vec<Cell> {
+        let mut row = self.0.to_row();
+        row.push(right(&ByteSize(self.0.disk_size()).to_string()));
+        row
+    }
+}
+
+impl TableRow for TypeUsage {
+    fn to_row(&self) -> Vec<Cell> {
+        vec![
+            Cell::new(self.component_type.to_string()),
+            right(&self.components.to_string()),
+            right(&ByteSize(self.bytes).to_string()),
+        ]
+    }
+}
+
+impl TableRow for ComponentUsage {
+    fn to_row(&self) -> Vec<Cell> {
+        vec![
+            Cell::new(&self.name),
+            Cell::new(self.component_type.to_string()),
+            right(&ByteSize(self.bytes).to_string()),
+        ]
+    }
+}
+
 impl TableRow for FailedUpdate {
     fn to_row(&self) -> Vec<Cell> {
-        vec![Cell::new(&self.name), Cell::new(&self.error)]
+        let step = self.operation.map(|op| op.to_string()).unwrap_or_default();
+        vec![
+            Cell::new(&self.name),
+            Cell::new(step),
+            Cell::new(&self.error),
+        ]
+    }
+}
+
+impl TableRow for AdoptedComponent {
+    fn to_row(&self) -> Vec<Cell> {
+        vec![
+            Cell::new(&self.name),
+            right(&self.content_id.to_string()),
+            Cell::new(self.component_type.to_string()),
+        ]
+    }
+}
+
+impl TableRow for RegistryIssue {
+    fn to_row(&self) -> Vec<Cell> {
+        vec![
+            Cell::new(&self.name),
+            Cell::new(&self.registry),
+            Cell::new(self.kind.to_string()),
+        ]
+    }
+}
+
+impl TableRow for RunComponent {
+    fn to_row(&self) -> Vec<Cell> {
+        vec![
+            Cell::new(&self.name),
+            right(format_version(&self.from_version)),
+            right(format_version(&self.to_version)),
+            right(&crate::report::seconds(self.duration_ms)),
+            right(&format_download_size(self.bytes)),
+            Cell::new(self.component_type.to_string()),
+        ]
     }
 }
 
@@ -65,6 +175,7 @@ fn format_download_size(size: Option<u64>) -> String {
 fn print_table<T: TableRow>(items: &[T], headers: &[&str]) {
     let mut table = Table::new();
     table.load_preset(presets::NOTHING);
+    super::theme::style_table(&mut table);
     table.set_header(headers.iter().map(|h| header(h)).collect::<Vec<_>>());
 
     for item in items {
@@ -75,52 +186,353 @@ fn print_table<T: TableRow>(items: &[T], headers: &[&str]) {
 }
 
 pub fn print_updates_table(updates: &[AvailableUpdate]) {
-    let headers = vec!["NAME", "CURRENT", "AVAILABLE", "ID", "SIZE", "TYPE"];
+    let headers = vec![
+        "NAME",
+        "CURRENT",
+        "AVAILABLE",
+        "SEVERITY",
+        "ID",
+        "SIZE",
+        "TYPE",
+    ];
     print_table(updates, &headers);
 }
 
-pub fn print_components_table(components: &[InstalledComponent]) {
-    let headers = vec!["NAME", "VERSION", "TYPE"];
-    print_table(components, &headers);
+pub fn print_components_table(components: &[InstalledComponent], with_size: bool) {
+    if with_size {
+        let sized: Vec<_> = components.iter().map(SizedComponent).collect();
+        print_table(&sized, &["NAME", "VERSION", "TYPE", "SIZE"]);
+    } else {
+        print_table(components, &["NAME", "VERSION", "TYPE"]);
+    }
+}
+
+pub fn print_components_details(components: &[InstalledComponent]) {
+    for component in components {
+        println!(
+            "\n{} {} ({})",
+            component.name,
+            format_version(&component.version),
+            component.component_type
+        );
+        let meta = &component.metadata;
+        let fields = [
+            (tr!("detail-author", "author"), meta.author.as_deref()),
+            (
+                tr!("detail-description", "description"),
+                meta.description.as_deref(),
+            ),
+            (tr!("detail-license", "license"), meta.license.as_deref()),
+            (tr!("detail-website", "website"), meta.website.as_deref()),
+            (tr!("detail-icon", "icon"), meta.icon.as_deref()),
+        ];
+        for (label, value) in fields {
+            if let Some(value) = value {
+                println!("  {label:<12} {value}");
+            }
+        }
+        println!(
+            "  {:<12} {}",
+            tr!("detail-path", "path"),
+            component.path.display()
+        );
+        println!(
+            "  {:<12} {}",
+            tr!("detail-size", "size"),
+            ByteSize(component.disk_size())
+        );
+    }
+}
+
+/// Lists packages whose metadata cannot be read, each with the reason it is
+/// never updated.
+pub fn print_unrecognized(unrecognized: &[UnrecognizedComponent]) {
+    for unknown in unrecognized {
+        let component = &unknown.component;
+        println!(
+            "\n{} {} ({})",
+            component.name, component.version, component.component_type
+        );
+        println!(
+            "  {:<12} {}",
+            tr!("detail-path", "path"),
+            component.path.display()
+        );
+        println!(
+            "  {:<12} {}",
+            tr!("detail-not-updated", "not updated"),
+            unknown.reason
+        );
+    }
+}
+
+/// Prints what changed since the installed version of each update that has
+/// a changelog, at most `limit` entries per update.
+pub fn print_changes(updates: &[AvailableUpdate], limit: Option<usize>) {
+    for update in updates {
+        let changes = &update.changes_since_installed;
+        if changes.is_empty() {
+            continue;
+        }
+        println!(
+            "\n{} {} \u{2192} {}",
+            update.installed.name,
+            format_version(&update.installed.version),
+            format_version(&update.latest_version)
+        );
+        let shown = limit.unwrap_or(changes.len()).min(changes.len());
+        for change in &changes[..shown] {
+            println!("  - {change}");
+        }
+        if shown < changes.len() {
+            let more = changes.len() - shown;
+            println!(
+                "  {}",
+                tr!("changes-more", "... and {more} more", more = more)
+            );
+        }
+    }
+}
+
+pub fn print_disk_usage(usage: &DiskUsage) {
+    if !usage.types.is_empty() {
+        print_table(&usage.types, &["TYPE", "COUNT", "SIZE"]);
+        println!();
+        print_table(&usage.components, &["NAME", "TYPE", "SIZE"]);
+        println!();
+    }
+    for (label, bytes) in [
+        (tr!("usage-installed", "installed"), usage.installed()),
+        (tr!("usage-backups", "backups"), usage.backups),
+        (tr!("usage-versions", "versions"), usage.versions),
+        (tr!("usage-cache", "cache"), usage.cache),
+    ] {
+        println!("{label:<12}{}", ByteSize(bytes));
+    }
+}
+
+/// Prints a recorded update run: updated components, failures with an
+/// excerpt of their error, and what ran afterwards.
+pub fn print_run_record(run: &RunRecord) {
+    let scope = if run.system { "system" } else { "user" };
+    println!(
+        "{}",
+        tr!(
+            "run-header",
+            "{scope} run started {started}, took {took}",
+            scope = scope,
+            started = run.started_at,
+            took = crate::report::seconds(run.duration_ms),
+        )
+    );
+
+    let updated: Vec<RunComponent> = run.with_status(OutcomeStatus::Succeeded).cloned().collect();
+    if !updated.is_empty() {
+        println!();
+        print_table(&updated, &["NAME", "FROM", "TO", "TIME", "SIZE", "TYPE"]);
+    }
+    let mut failed = run.with_status(OutcomeStatus::Failed).peekable();
+    if failed.peek().is_some() {
+        println!("\n{}", tr!("run-failed", "failed:"));
+        for component in failed {
+            let error = component.error.as_deref().unwrap_or_default();
+            println!(
+                "  {} {}: {}",
+                component.name,
+                format_version(&component.to_version),
+                crate::report::error_excerpt(error)
+            );
+        }
+    }
+    for (label, names) in [
+        (tr!("run-rolled-back", "rolled back"), &run.rolled_back),
+        (tr!("run-skipped", "skipped"), &run.skipped),
+    ] {
+        if !names.is_empty() {
+            println!("\n{label}: {}", names.join(", "));
+        }
+    }
+    if !run.restart_actions.is_empty() {
+        let actions: Vec<String> = run
+            .restart_actions
+            .iter()
+            .map(|&action| describe_restart_action(action))
+            .collect();
+        let then = tr!("run-then", "then");
+        let actions = actions.join(&format!(", {then} "));
+        println!(
+            "\n{}",
+            tr!("run-afterwards", "afterwards: {actions}", actions = actions)
+        );
+    }
+
+    println!(
+        "\n{}",
+        tr!(
+            "run-totals",
+            "{updated} updated, {failed} failed, {downloaded} downloaded; store usage: {usage}",
+            updated = run.with_status(OutcomeStatus::Succeeded).count(),
+            failed = run.with_status(OutcomeStatus::Failed).count(),
+            downloaded = ByteSize(run.total_bytes()).to_string(),
+            usage = run.api_stats.summary(),
+        )
+    );
+}
+
+pub fn print_doctor_report(report: &DoctorReport) {
+    println!(
+        "libplasmoid-updater {} ({})",
+        report.library_version,
+        report.features.join(", ")
+    );
+    for check in &report.checks {
+        let (status, style) = match check.status {
+            DoctorStatus::Ok => ("ok", Style::Green),
+            DoctorStatus::Warning => ("warn", Style::Yellow),
+            DoctorStatus::Failed => ("FAIL", Style::Red),
+        };
+        let status = paint(Stream::Stdout, style, format!("{status:<6}"));
+        println!("{status}{:<20}{}", check.name, check.detail);
+    }
 }
 
 pub fn print_error_table(update_result: &UpdateResult) {
-    let headers = vec!["NAME", "ERROR"];
+    let headers = vec!["NAME", "STEP", "ERROR"];
     print_table(&update_result.failed, &headers);
 }
 
+pub fn print_adopted_table(adopted: &[AdoptedComponent]) {
+    let headers = vec!["NAME", "ID", "TYPE"];
+    print_table(adopted, &headers);
+}
+
+pub fn print_registry_issues(issues: &[RegistryIssue]) {
+    let headers = vec!["NAME", "REGISTRY", "ISSUE"];
+    print_table(issues, &headers);
+}
+
 pub fn print_summary(update_result: &UpdateResult) {
     let total =
         update_result.succeeded.len() + update_result.failed.len() + update_result.skipped.len();
 
-    if update_result.unverified.is_empty() {
-        println!(
-            "Update Summary: {} succeeded, {} failed, {} skipped ({} total)",
-            update_result.succeeded.len(),
-            update_result.failed.len(),
-            update_result.skipped.len(),
-            total,
-        );
+    let mut notes = Vec::new();
+    if !update_result.unverified.is_empty() {
+        let count = update_result.unverified.len();
+        notes.push(tr!(
+            "summary-unverified",
+            "{count} unverified",
+            count = count
+        ));
+    }
+    if !update_result.unhealthy.is_empty() {
+        let count = update_result.unhealthy.len();
+        notes.push(tr!("summary-unhealthy", "{count} unhealthy", count = count));
+    }
+    if !update_result.rolled_back.is_empty() {
+        let count = update_result.rolled_back.len();
+        notes.push(tr!(
+            "summary-rolled-back",
+            "{count} rolled back",
+            count = count
+        ));
+    }
+    let retried = update_result
+        .outcomes
+        .iter()
+        .filter(|o| o.status == OutcomeStatus::Succeeded && o.retries > 0)
+        .count();
+    if retried > 0 {
+        notes.push(tr!(
+            "summary-retried",
+            "{retried} after retry",
+            retried = retried
+        ));
+    }
+    let succeeded = if notes.is_empty() {
+        update_result.succeeded.len().to_string()
     } else {
-        println!(
-            "Update Summary: {} succeeded ({} unverified), {} failed, {} skipped ({} total)",
-            update_result.succeeded.len(),
-            update_result.unverified.len(),
-            update_result.failed.len(),
-            update_result.skipped.len(),
-            total,
+        format!("{} ({})", update_result.succeeded.len(), notes.join(", "))
+    };
+
+    println!(
+        "{}",
+        tr!(
+            "summary",
+            "Update Summary: {succeeded} succeeded, {failed} failed, {skipped} skipped ({total} total)",
+            succeeded = succeeded,
+            failed = update_result.failed.len(),
+            skipped = update_result.skipped.len(),
+            total = total,
+        )
+    );
+    for u in &update_result.unverified {
+        let actual = match &u.actual_version {
+            Some(version) => version.clone(),
+            None => tr!("summary-unreadable", "(unreadable)"),
+        };
+        let line = tr!(
+            "summary-unverified-line",
+            "unverified: {name} — expected {expected}, found {actual}",
+            name = u.name,
+            expected = u.expected_version,
+            actual = actual,
+        );
+        println!("  {line}");
+    }
+    for u in &update_result.unhealthy {
+        let line = tr!(
+            "summary-unhealthy-line",
+            "unhealthy: {name} — {problems}",
+            name = u.name,
+            problems = u.problems.join("; "),
+        );
+        println!("  {line}");
+    }
+    for name in &update_result.rolled_back {
+        let line = tr!(
+            "summary-rolled-back-line",
+            "rolled back after plasmashell crashed: {name}",
+            name = name,
+        );
+        println!("  {line}");
+    }
+}
+
+/// Warns that some catalog pages failed, so updates may be missing.
+pub fn print_catalog_warning(report: &CatalogFetchReport) {
+    if !report.is_complete() {
+        eprintln!(
+            "{}",
+            tr!(
+                "catalog-incomplete",
+                "warning: store results incomplete, {failed}/{pages} pages failed, results may be missing",
+                failed = report.failed_pages,
+                pages = report.pages,
+            )
         );
-        for u in &update_result.unverified {
-            let actual = u.actual_version.as_deref().unwrap_or("(unreadable)");
-            println!(
-                "  unverified: {} — expected {}, found {}",
-                u.name, u.expected_version, actual,
-            );
-        }
     }
 }
 
-pub fn print_count_message(count: usize, item_type: &str) {
+pub fn print_update_count(count: usize) {
+    let plural = if count == 1 { "" } else { "s" };
+    println!(
+        "{}",
+        tr!(
+            "count-updates",
+            "{count} update{plural} available.",
+            count = count
+        )
+    );
+}
+
+pub fn print_installed_count(count: usize) {
     let plural = if count == 1 { "" } else { "s" };
-    println!("{} {}{} available.", count, item_type, plural);
+    println!(
+        "{}",
+        tr!(
+            "count-installed",
+            "{count} installed component{plural} available.",
+            count = count
+        )
+    );
 }
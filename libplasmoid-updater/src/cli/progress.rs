@@ -1,10 +1,171 @@
 // SPDX-License-Identifier: GPL-3.0-or-later
 
+//! Progress display of checks and updates, see [`ProgressMode`].
+
+use std::{
+    str::FromStr,
+    sync::atomic::{AtomicU8, Ordering},
+};
+
 use indicatif::{ProgressBar, ProgressStyle};
+use parking_lot::Mutex;
+use serde::{Deserialize, Serialize};
+
+use super::{
+    theme::{Stream, use_color},
+    update_ui::{UpdateUi, stage_label},
+};
+use crate::{tr, types::AvailableUpdate};
+
+/// How the progress of checks and updates is shown, see
+/// [`set_progress_mode`].
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum ProgressMode {
+    /// A spinner while checking and a live row per component while
+    /// updating, redrawn in place (default).
+    #[default]
+    Fancy,
+    /// One timestamped line per step and component, for screen readers and
+    /// CI logs.
+    Plain,
+    /// No progress output, only results.
+    None,
+}
+
+impl FromStr for ProgressMode {
+    type Err = crate::Error;
+
+    fn from_str(s: &str) -> crate::Result<Self> {
+        match s {
+            "fancy" => Ok(Self::Fancy),
+            "plain" => Ok(Self::Plain),
+            "none" => Ok(Self::None),
+            _ => Err(crate::Error::other(format!(
+                "invalid progress mode '{s}', expected fancy, plain or none"
+            ))),
+        }
+    }
+}
+
+static MODE: AtomicU8 = AtomicU8::new(0);
+
+/// Sets how the built-in terminal output shows progress, for the whole
+/// process.
+///
+/// Defaults to [`ProgressMode::Fancy`].
+pub fn set_progress_mode(mode: ProgressMode) {
+    MODE.store(mode as u8, Ordering::Relaxed);
+}
+
+/// The mode set with [`set_progress_mode`].
+pub fn progress_mode() -> ProgressMode {
+    match MODE.load(Ordering::Relaxed) {
+        1 => ProgressMode::Plain,
+        2 => ProgressMode::None,
+        _ => ProgressMode::Fancy,
+    }
+}
+
+/// Shows the progress of the components of an update run, addressed by
+/// their index in the run.
+pub(crate) trait ProgressReporter: Send + Sync {
+    /// The component reached `stage` (0 backup, 1 download, 2 extract,
+    /// 3 install).
+    fn stage(&self, index: usize, stage: u8);
+    fn complete(&self, index: usize, succeeded: bool);
+    /// The component is attempted again after a transient failure.
+    fn restart(&self, index: usize);
+    fn finish(self: Box<Self>);
+}
+
+/// The reporter for the current [`ProgressMode`].
+pub(crate) fn update_reporter(updates: &[&AvailableUpdate]) -> Box<dyn ProgressReporter> {
+    match progress_mode() {
+        ProgressMode::Fancy => Box::new(UpdateUi::new(updates)),
+        ProgressMode::Plain => Box::new(PlainProgress::new(updates)),
+        ProgressMode::None => Box::new(NoProgress),
+    }
+}
+
+/// Prints a line per change, prefixed with the time.
+struct PlainProgress {
+    names: Vec<String>,
+    /// Last stage printed per component, so repeated reports stay silent.
+    stages: Mutex<Vec<Option<u8>>>,
+}
+
+impl PlainProgress {
+    fn new(updates: &[&AvailableUpdate]) -> Self {
+        Self {
+            names: updates.iter().map(|u| u.installed.name.clone()).collect(),
+            stages: Mutex::new(vec![None; updates.len()]),
+        }
+    }
+
+    fn print(&self, index: usize, status: impl FnOnce(&str) -> String) {
+        if let Some(name) = self.names.get(index) {
+            println!("{}", timestamped(&status(name)));
+        }
+    }
+}
+
+impl ProgressReporter for PlainProgress {
+    fn stage(&self, index: usize, stage: u8) {
+        let mut stages = self.stages.lock();
+        let Some(last) = stages.get_mut(index) else {
+            return;
+        };
+        if last.replace(stage) != Some(stage) {
+            self.print(index, |name| format!("{name}: {}", stage_label(stage)));
+        }
+    }
+
+    fn complete(&self, index: usize, succeeded: bool) {
+        self.print(index, |name| {
+            if succeeded {
+                tr!("task-updated", "{name} (updated)", name = name)
+            } else {
+                tr!("task-failed-line", "{name} (failed)", name = name)
+            }
+        });
+    }
+
+    fn restart(&self, index: usize) {
+        if let Some(last) = self.stages.lock().get_mut(index) {
+            *last = None;
+        }
+        self.print(index, |name| {
+            tr!("task-retrying", "{name} (retrying)", name = name)
+        });
+    }
+
+    fn finish(self: Box<Self>) {}
+}
+
+struct NoProgress;
+
+impl ProgressReporter for NoProgress {
+    fn stage(&self, _index: usize, _stage: u8) {}
+    fn complete(&self, _index: usize, _succeeded: bool) {}
+    fn restart(&self, _index: usize) {}
+    fn finish(self: Box<Self>) {}
+}
+
+/// `line` prefixed with the local time, e.g. `[14:03:27] Clock: Downloading`.
+fn timestamped(line: &str) -> String {
+    format!("[{}] {line}", chrono::Local::now().format("%H:%M:%S"))
+}
 
 fn spinner_style() -> ProgressStyle {
+    // indicatif draws on stderr.
+    let template = if use_color(Stream::Stderr) {
+        " {spinner:.cyan} {msg}"
+    } else {
+        " {spinner} {msg}"
+    };
     ProgressStyle::default_spinner()
-        .template(" {spinner:.cyan} {msg}")
+        .template(template)
         .unwrap_or_else(|_| ProgressStyle::default_spinner())
 }
 
@@ -16,7 +177,38 @@ fn create_spinner(msg: impl Into<String>) -> ProgressBar {
     pb
 }
 
-/// Creates a spinner for the "Fetching component data" phase.
-pub fn create_fetch_spinner() -> ProgressBar {
-    create_spinner("Fetching component data")
+/// Creates a spinner for the "Fetching component data" phase. In plain mode
+/// the phase is announced once instead.
+pub(crate) fn create_fetch_spinner() -> ProgressBar {
+    let message = tr!("fetching", "Fetching component data");
+    match progress_mode() {
+        ProgressMode::Fancy => create_spinner(message),
+        ProgressMode::Plain => {
+            eprintln!("{}", timestamped(&message));
+            ProgressBar::hidden()
+        }
+        ProgressMode::None => ProgressBar::hidden(),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn progress_modes_parse_from_config_names() {
+        assert_eq!(
+            "plain".parse::<ProgressMode>().unwrap(),
+            ProgressMode::Plain
+        );
+        assert_eq!("none".parse::<ProgressMode>().unwrap(), ProgressMode::None);
+        assert!("quiet".parse::<ProgressMode>().is_err());
+    }
+
+    #[test]
+    fn timestamps_prefix_the_local_time() {
+        let line = timestamped("Clock: Downloading");
+        assert_eq!(line.len(), "[00:00:00] Clock: Downloading".len());
+        assert!(line.starts_with('[') && line.ends_with("] Clock: Downloading"));
+    }
 }
@@ -0,0 +1,196 @@
+// SPDX-License-Identifier: GPL-3.0-or-later
+
+//! Colors and text styles of the terminal output.
+//!
+//! Everything the `cli` feature prints is styled through [`paint`], so one
+//! [`ColorMode`] decides for tables, the progress display, prompts and
+//! error labels alike.
+
+use std::{
+    fmt::Display,
+    str::FromStr,
+    sync::atomic::{AtomicU8, Ordering},
+};
+
+use is_terminal::IsTerminal;
+use serde::{Deserialize, Serialize};
+
+/// When terminal output is colored, see [`set_color_mode`].
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum ColorMode {
+    /// Color output to a terminal, unless `NO_COLOR` is set, `CLICOLOR` is
+    /// `0` or `TERM` is `dumb`. `CLICOLOR_FORCE` colors any output (default).
+    #[default]
+    Auto,
+    /// Always color, even when output is piped.
+    Always,
+    /// Never color.
+    Never,
+}
+
+impl FromStr for ColorMode {
+    type Err = crate::Error;
+
+    fn from_str(s: &str) -> crate::Result<Self> {
+        match s {
+            "auto" => Ok(Self::Auto),
+            "always" => Ok(Self::Always),
+            "never" => Ok(Self::Never),
+            _ => Err(crate::Error::other(format!(
+                "invalid color mode '{s}', expected auto, always or never"
+            ))),
+        }
+    }
+}
+
+/// Where styled text is written.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Stream {
+    Stdout,
+    Stderr,
+}
+
+impl Stream {
+    fn is_terminal(self) -> bool {
+        match self {
+            Self::Stdout => std::io::stdout().is_terminal(),
+            Self::Stderr => std::io::stderr().is_terminal(),
+        }
+    }
+}
+
+/// A text style of the terminal output.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Style {
+    /// Headers.
+    Bold,
+    /// Success.
+    Green,
+    /// Failures and errors.
+    Red,
+    /// Warnings and work in progress.
+    Yellow,
+    /// Timings and other secondary information.
+    Cyan,
+}
+
+impl Style {
+    const fn code(self) -> &'static str {
+        match self {
+            Self::Bold => "\x1b[1m",
+            Self::Green => "\x1b[32m",
+            Self::Red => "\x1b[31m",
+            Self::Yellow => "\x1b[33m",
+            Self::Cyan => "\x1b[36m",
+        }
+    }
+}
+
+const RESET: &str = "\x1b[0m";
+
+static MODE: AtomicU8 = AtomicU8::new(0);
+
+/// Sets when the built-in terminal output is colored, for the whole process.
+///
+/// Defaults to [`ColorMode::Auto`].
+pub fn set_color_mode(mode: ColorMode) {
+    MODE.store(mode as u8, Ordering::Relaxed);
+}
+
+/// The mode set with [`set_color_mode`].
+pub fn color_mode() -> ColorMode {
+    match MODE.load(Ordering::Relaxed) {
+        1 => ColorMode::Always,
+        2 => ColorMode::Never,
+        _ => ColorMode::Auto,
+    }
+}
+
+/// Whether text written to `stream` is colored.
+pub fn use_color(stream: Stream) -> bool {
+    match color_mode() {
+        ColorMode::Always => true,
+        ColorMode::Never => false,
+        ColorMode::Auto => auto_color(|var| std::env::var(var).ok(), || stream.is_terminal()),
+    }
+}
+
+/// Applies the conventions of <https://no-color.org> and
+/// <https://bixense.com/clicolors/>, `NO_COLOR` taking precedence.
+fn auto_color(var: impl Fn(&str) -> Option<String>, is_terminal: impl FnOnce() -> bool) -> bool {
+    let set = |name| var(name).is_some_and(|value| !value.is_empty());
+    if set("NO_COLOR") {
+        return false;
+    }
+    if set("CLICOLOR_FORCE") && var("CLICOLOR_FORCE").as_deref() != Some("0") {
+        return true;
+    }
+    if var("CLICOLOR").as_deref() == Some("0") || var("TERM").as_deref() == Some("dumb") {
+        return false;
+    }
+    is_terminal()
+}
+
+/// `text` in `style` if `stream` is colored, otherwise unchanged.
+pub fn paint(stream: Stream, style: Style, text: impl Display) -> String {
+    if use_color(stream) {
+        format!("{}{text}{RESET}", style.code())
+    } else {
+        text.to_string()
+    }
+}
+
+/// Colors for interactive prompts, which are drawn on stderr.
+pub(crate) fn prompt_config() -> inquire::ui::RenderConfig<'static> {
+    if use_color(Stream::Stderr) {
+        inquire::ui::RenderConfig::default()
+    } else {
+        inquire::ui::RenderConfig::empty()
+    }
+}
+
+/// Styles tables like the rest of the output on stdout.
+pub(crate) fn style_table(table: &mut comfy_table::Table) {
+    if use_color(Stream::Stdout) {
+        table.enforce_styling();
+    } else {
+        table.force_no_tty();
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn auto(vars: &[(&str, &str)], is_terminal: bool) -> bool {
+        auto_color(
+            |name| {
+                vars.iter()
+                    .find(|(var, _)| *var == name)
+                    .map(|(_, value)| value.to_string())
+            },
+            || is_terminal,
+        )
+    }
+
+    #[test]
+    fn auto_mode_follows_terminal_and_environment() {
+        assert!(auto(&[], true));
+        assert!(!auto(&[], false));
+        assert!(!auto(&[("NO_COLOR", "1")], true));
+        assert!(auto(&[("NO_COLOR", "")], true));
+        assert!(!auto(&[("CLICOLOR", "0")], true));
+        assert!(!auto(&[("TERM", "dumb")], true));
+        assert!(auto(&[("CLICOLOR_FORCE", "1")], false));
+        assert!(!auto(&[("CLICOLOR_FORCE", "0")], false));
+        assert!(!auto(&[("NO_COLOR", "1"), ("CLICOLOR_FORCE", "1")], true));
+    }
+
+    #[test]
+    fn color_modes_parse_from_config_names() {
+        assert_eq!("never".parse::<ColorMode>().unwrap(), ColorMode::Never);
+        assert_eq!("always".parse::<ColorMode>().unwrap(), ColorMode::Always);
+        assert!("sometimes".parse::<ColorMode>().is_err());
+    }
+}
@@ -13,15 +13,15 @@ use std::{
 use is_terminal::IsTerminal;
 use parking_lot::Mutex;
 
-use crate::types::AvailableUpdate;
-
-// ── ANSI color codes ─────────────────────────────────────────────────────────
+use super::{
+    progress::ProgressReporter,
+    theme::{Stream, Style, paint},
+};
+use crate::{tr, types::AvailableUpdate};
 
-const YELLOW: &str = "\x1b[33m";
-const GREEN: &str = "\x1b[32m";
-const RED: &str = "\x1b[31m";
-const CYAN: &str = "\x1b[36m";
-const RESET: &str = "\x1b[0m";
+fn styled(style: Style, text: impl std::fmt::Display) -> String {
+    paint(Stream::Stdout, style, text)
+}
 
 // ── Spinner ───────────────────────────────────────────────────────────────────
 
@@ -41,20 +41,20 @@ fn progress_bar(stage: u8) -> String {
     let filled = stage.min(BAR_WIDTH as u8) as usize;
     let empty = BAR_WIDTH - filled;
     format!(
-        "[{GREEN}{}{RESET}{}]",
-        BAR_FILL.to_string().repeat(filled),
+        "[{}{}]",
+        styled(Style::Green, BAR_FILL.to_string().repeat(filled)),
         " ".repeat(empty),
     )
 }
 
 // ── Stage labels ──────────────────────────────────────────────────────────────
 
-fn stage_label(stage: u8) -> &'static str {
+pub(super) fn stage_label(stage: u8) -> String {
     match stage {
-        0 => "Backing up",
-        1 => "Downloading",
-        2 => "Extracting",
-        _ => "Installing",
+        0 => tr!("stage-backup", "Backing up"),
+        1 => tr!("stage-download", "Downloading"),
+        2 => tr!("stage-extract", "Extracting"),
+        _ => tr!("stage-install", "Installing"),
     }
 }
 
@@ -114,18 +114,21 @@ fn render_row(state: &TaskState, width: usize) -> String {
 }
 
 fn render_complete_row(state: &TaskState, time_str: &str, width: usize) -> String {
-    let (icon_color, icon, status_color, status_label) = match state.status {
-        TaskStatus::Succeeded => (GREEN, '✓', GREEN, "Done"),
-        _ => (RED, '✗', RED, "Failed"),
+    let (style, icon, status_label) = match state.status {
+        TaskStatus::Succeeded => (Style::Green, '✓', tr!("task-done", "Done")),
+        _ => (Style::Red, '✗', tr!("task-failed", "Failed")),
     };
 
     // Visible text: "{icon} {name} {status}"
     let visible_left = format!("{icon} {} {}", state.name, status_label);
-    let padding = padding_between(visible_left.len(), time_str.len(), width);
+    let padding = padding_between(visible_left.chars().count(), time_str.len(), width);
 
     format!(
-        "{icon_color}{icon}{RESET} {} {status_color}{status_label}{RESET}{padding}{CYAN}{time_str}{RESET}",
+        "{} {} {}{padding}{}",
+        styled(style, icon),
         state.name,
+        styled(style, status_label),
+        styled(Style::Cyan, time_str),
     )
 }
 
@@ -146,11 +149,13 @@ fn render_progress_row(
         state.name,
         " ".repeat(BAR_WIDTH)
     );
-    let padding = padding_between(visible_left.len(), time_str.len(), width);
+    let padding = padding_between(visible_left.chars().count(), time_str.len(), width);
 
     format!(
-        "{YELLOW}{spinner}{RESET} {} {bar} {label}{padding}{CYAN}{time_str}{RESET}",
+        "{} {} {bar} {label}{padding}{}",
+        styled(Style::Yellow, spinner),
         state.name,
+        styled(Style::Cyan, time_str),
     )
 }
 
@@ -241,20 +246,18 @@ impl UpdateUi {
             is_tty,
         }
     }
+}
 
-    /// Returns a reporter closure that advances the named task through stages.
-    pub(crate) fn reporter(&self, index: usize) -> impl Fn(u8) {
-        let states = Arc::clone(&self.states);
-        move |stage: u8| {
-            let mut locked = states.lock();
-            if let Some(task) = locked.get_mut(index) {
-                task.stage = stage;
-            }
+impl ProgressReporter for UpdateUi {
+    /// Advances the task through stages.
+    fn stage(&self, index: usize, stage: u8) {
+        if let Some(task) = self.states.lock().get_mut(index) {
+            task.stage = stage;
         }
     }
 
     /// Marks a task as complete with a success or failure status.
-    pub(crate) fn complete_task(&self, index: usize, succeeded: bool) {
+    fn complete(&self, index: usize, succeeded: bool) {
         if self.is_tty {
             let mut locked = self.states.lock();
             if let Some(task) = locked.get_mut(index) {
@@ -267,17 +270,28 @@ impl UpdateUi {
         } else {
             let locked = self.states.lock();
             if let Some(task) = locked.get(index) {
+                let name = &task.name;
                 if succeeded {
-                    println!("  \u{2713} {} (updated)", task.name);
+                    let line = tr!("task-updated", "{name} (updated)", name = name);
+                    println!("  \u{2713} {line}");
                 } else {
-                    println!("  \u{2717} {} (failed)", task.name);
+                    let line = tr!("task-failed-line", "{name} (failed)", name = name);
+                    println!("  \u{2717} {line}");
                 }
             }
         }
     }
 
+    /// Puts a completed task back in progress for another attempt.
+    fn restart(&self, index: usize) {
+        let mut locked = self.states.lock();
+        if let Some(task) = locked.get_mut(index) {
+            *task = TaskState::new(std::mem::take(&mut task.name));
+        }
+    }
+
     /// Stops the render thread and performs a final render pass.
-    pub(crate) fn finish(mut self) {
+    fn finish(mut self: Box<Self>) {
         if let Some(thread) = self.render_thread.take() {
             self.stop.store(true, Ordering::Release);
             thread.join().ok();
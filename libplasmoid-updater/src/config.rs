@@ -1,7 +1,21 @@
 // SPDX-License-Identifier: GPL-3.0-or-later
 
 use std::collections::HashMap;
-use std::sync::LazyLock;
+use std::path::PathBuf;
+use std::sync::{Arc, LazyLock};
+use std::time::Duration;
+
+use serde::{Deserialize, Serialize};
+
+use crate::{
+    api::{ApiConfig, HttpBackend, StoreCredentials, StoreSnapshot},
+    cancel::CancelToken,
+    events::{Event, EventSink},
+    hooks::Hooks,
+    notify::Notifier,
+    types::{AvailableUpdate, ComponentType, InstalledComponent},
+    version::UpdateSeverity,
+};
 
 /// Default embedded widgets-id mapping file provided by Apdatifier.
 ///
@@ -12,7 +26,7 @@ const DEFAULT_WIDGETS_ID: &str = include_str!("../widgets-id");
 static DEFAULT_WIDGETS_TABLE: LazyLock<HashMap<String, u64>> =
     LazyLock::new(|| Config::parse_widgets_id(DEFAULT_WIDGETS_ID));
 
-/// Controls plasmashell restart behavior after updates.
+/// Controls plasmashell restart and theme re-apply after updates.
 #[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
 pub enum RestartBehavior {
     /// Never restart plasmashell (default).
@@ -25,6 +39,57 @@ pub enum RestartBehavior {
     Prompt,
 }
 
+/// How the checker queries the KDE Store for installed components.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum FetchStrategy {
+    /// Page through the whole catalog of every installed component type,
+    /// then fetch known IDs missing from it one by one (default).
+    #[default]
+    Catalog,
+    /// Fetch components with a locally known content ID (registry, metadata,
+    /// widgets-id table) one by one, and page through the catalog only for
+    /// the types of components without one.
+    Targeted,
+    /// [`Targeted`](Self::Targeted) when at most
+    /// [`FetchStrategy::AUTO_TARGETED_LIMIT`] IDs are known, otherwise
+    /// [`Catalog`](Self::Catalog).
+    Auto,
+}
+
+impl FetchStrategy {
+    /// Above this many known IDs, [`Auto`](Self::Auto) pages the catalog:
+    /// one request per component stops being cheaper than a few catalog pages.
+    pub const AUTO_TARGETED_LIMIT: usize = 20;
+}
+
+/// What happens to the files of a component that is replaced or removed.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum RemovalMode {
+    /// Delete the files once they are backed up (default). Only the newest
+    /// five backups per component type are kept.
+    #[default]
+    Backup,
+    /// Also move the replaced or removed files to the XDG trash, where the
+    /// file manager can restore them. Only for user components: system
+    /// components fall back to [`Backup`](Self::Backup).
+    Trash,
+}
+
+/// What an update run does when selected updates would write to the same
+/// files, see [`FileConflict`](crate::FileConflict).
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum ConflictPolicy {
+    /// Install nothing and return [`Error::FileConflicts`](crate::Error::FileConflicts)
+    /// (default).
+    #[default]
+    Fail,
+    /// Warn and install them anyway; the one installed last wins.
+    Warn,
+}
+
 /// Configuration for libplasmoid-updater operations.
 ///
 /// This struct contains all configuration options used by the library.
@@ -55,7 +120,7 @@ pub enum RestartBehavior {
 ///     .with_widgets_id_table(widgets_table)
 ///     .with_restart(RestartBehavior::Always);
 /// ```
-#[derive(Debug, Clone, Default)]
+#[derive(Debug, Clone)]
 pub struct Config {
     /// If `true`, operate on system-wide components (in `/usr/share`).
     /// If `false` (default), operate on user components (in `~/.local/share`).
@@ -65,19 +130,58 @@ pub struct Config {
     /// Packages to exclude from updates.
     ///
     /// Can match either directory names (e.g., "org.kde.plasma.systemmonitor")
-    /// or display names (e.g., "System Monitor"). Components in this list
-    /// will be skipped during update operations.
+    /// or display names (e.g., "System Monitor"). Entries may be globs
+    /// (`org.kde.plasma.*`) or, prefixed with `re:`, regular expressions.
+    /// Components in this list will be skipped during update operations.
     pub excluded_packages: Vec<String>,
 
+    /// Components to restrict discovery to, matched like
+    /// [`excluded_packages`](Self::excluded_packages).
+    ///
+    /// When non-empty, only matching components are checked, so the store is
+    /// only queried for them: [`FetchStrategy::Catalog`] is treated as
+    /// [`FetchStrategy::Auto`]. Exclusions still apply on top.
+    pub only_packages: Vec<String>,
+
+    /// Component types to ignore entirely.
+    ///
+    /// Components of these types are not discovered, so their store categories
+    /// are never fetched and they never appear in check or update results.
+    pub excluded_types: Vec<ComponentType>,
+
+    /// Component types to restrict discovery to.
+    ///
+    /// When non-empty, only these types are discovered and fetched from the
+    /// store. Exclusions still apply on top.
+    pub included_types: Vec<ComponentType>,
+
+    /// When `true`, entries of KNewStuff registries this crate does not model
+    /// (Kate themes, Kdenlive titles, ...) are discovered as [`ComponentType::Other`],
+    /// checked by the content ID their registry records and updated in
+    /// place. Off by default.
+    pub enable_generic_kns: bool,
+
+    /// Named groups of packages, e.g. `"work" => ["widgetA", "widgetB"]`.
+    ///
+    /// Entries use the same matching rules as [`excluded_packages`](Self::excluded_packages).
+    pub groups: HashMap<String, Vec<String>>,
+
+    /// Group from [`groups`](Self::groups) to restrict updates to.
+    ///
+    /// When set, only components matching the group are offered for update.
+    /// Naming a group that is not defined is an error.
+    pub group: Option<String>,
+
     /// Widget ID fallback table mapping directory names to KDE Store content IDs.
     ///
     /// This table is used as a fallback when content ID resolution via KNewStuff
-    /// registry or exact name matching fails. The library uses a three-tier
-    /// resolution strategy:
+    /// registry, package metadata or exact name matching fails. The library
+    /// uses a four-tier resolution strategy:
     ///
     /// 1. KNewStuff registry lookup (most reliable)
-    /// 2. Exact name match from KDE Store API
-    /// 3. Fallback to this widgets_id_table
+    /// 2. KDE Store URL in the package's `metadata.json` `Website` field
+    /// 3. Exact name match from KDE Store API
+    /// 4. Fallback to this widgets_id_table
     ///
     /// # Format
     ///
@@ -89,12 +193,80 @@ pub struct Config {
     pub widgets_id_table: HashMap<String, u64>,
 
     /// Controls plasmashell restart behavior after successful updates.
+    ///
+    /// Also governs re-applying active themes, see [`Self::reapply_active_themes`].
+    /// See [`ComponentType::restart_action`] for what each type needs.
     pub restart: RestartBehavior,
 
+    /// Components that need plasmashell restarted after an update whatever
+    /// their type, e.g. wallpaper plugins that cache their QML.
+    ///
+    /// Entries use the same matching rules as [`excluded_packages`](Self::excluded_packages)
+    /// and take precedence over [`no_restart`](Self::no_restart). The result
+    /// is [`AvailableUpdate::restart_action`](crate::AvailableUpdate::restart_action).
+    pub restart_required: Vec<String>,
+
+    /// Components that take effect without any restart whatever their type,
+    /// e.g. widgets that reload themselves.
+    ///
+    /// Entries use the same matching rules as [`excluded_packages`](Self::excluded_packages).
+    pub no_restart: Vec<String>,
+
+    /// How long to watch plasmashell after restarting it for crashes: a
+    /// failed or self-restarted systemd unit, or a new core dump.
+    ///
+    /// [`Config::new`] sets [`Self::DEFAULT_CRASH_MONITOR_WINDOW`]; `None`
+    /// disables monitoring.
+    pub crash_monitor_window: Option<Duration>,
+
+    /// When `true`, components needing a plasmashell restart are rolled back
+    /// from their backups if plasmashell crashes after restarting, and
+    /// plasmashell is restarted again. Otherwise the rollback is offered
+    /// interactively when possible. Off by default.
+    pub rollback_on_crash: bool,
+
+    /// When `true` (default), the user's desktop and panel layout
+    /// (`plasma-org.kde.plasma.desktop-appletsrc` and `plasmashellrc`) is
+    /// backed up before updating components that need a plasmashell restart,
+    /// and restored along with them by the crash rollback. Ignored for system
+    /// components.
+    pub backup_layout: bool,
+
+    /// When `true` (default), ask KWin to reconfigure after KWin effects,
+    /// scripts, switchers or Aurorae decorations were updated, so the new
+    /// versions load right away.
+    pub reload_kwin: bool,
+
+    /// When `true` (default), Plasma's cached renderings of an updated Plasma
+    /// style, global theme or Aurorae decoration are removed from `~/.cache`,
+    /// so the new version isn't drawn as a mix of old and new SVG elements.
+    pub clear_theme_caches: bool,
+
+    /// When `true` (default), an updated global theme, Plasma style or color
+    /// scheme that is currently in use is re-applied with the matching
+    /// `plasma-apply-*` tool. Like restarting plasmashell, this follows
+    /// [`Self::restart`]: it happens with `Always`, is offered with `Prompt`.
+    pub reapply_active_themes: bool,
+
     /// When `true`, skip interactive prompts and apply all non-excluded updates
     /// automatically. Has no effect without the `cli` feature.
     pub auto_confirm: bool,
 
+    /// Largest [`UpdateSeverity`] installed without asking, e.g.
+    /// [`UpdateSeverity::Patch`] so scheduled runs leave minor and major
+    /// updates for manual review. They are reported as skipped.
+    ///
+    /// `None` (default) installs every update. Only applies where no one
+    /// picks the updates: not to the interactive selection, nor to
+    /// components named in [`Self::only_packages`].
+    pub auto_apply: Option<UpdateSeverity>,
+
+    /// Retry passes at the end of an update run for components that failed
+    /// with a transient error, see [`Error::is_transient`](crate::Error::is_transient).
+    /// The first pass waits 2 seconds, each further one twice as long.
+    /// Default: 0, no retries.
+    pub retry_failed: u8,
+
     /// Maximum number of parallel installation threads.
     ///
     /// `None` (default) uses the number of logical CPU threads available.
@@ -109,9 +281,290 @@ pub struct Config {
     /// Uses a 3-tier fallback: logind DBus → `systemd-inhibit` subprocess → no-op.
     /// Set to `false` if the caller handles its own power management inhibition.
     pub inhibit_idle: bool,
+
+    /// Root directory for per-run temporary download/extraction directories.
+    ///
+    /// `None` (default) uses `$TMPDIR`, falling back to `/tmp`. Each run creates
+    /// a private (`0700`) subdirectory that is removed when the run finishes;
+    /// subdirectories left behind by crashed runs are cleaned up after
+    /// [`Self::stale_temp_max_age`].
+    pub temp_dir: Option<PathBuf>,
+
+    /// Age after which temp directories left behind by crashed runs are
+    /// removed from [`Self::temp_dir`] at the start of a run.
+    ///
+    /// [`Config::new`] sets [`Self::DEFAULT_STALE_TEMP_MAX_AGE`]; `None`
+    /// disables the cleanup.
+    pub stale_temp_max_age: Option<Duration>,
+
+    /// When `true` (default), only download packages over HTTPS.
+    pub require_https: bool,
+
+    /// Hosts packages may be downloaded from; empty (default) allows any.
+    ///
+    /// Entries are host names, or `*.example.com` for a domain and all its
+    /// subdomains. Redirect targets are checked too; a download from any other
+    /// host fails with [`Error::UntrustedDownloadHost`](crate::Error::UntrustedDownloadHost).
+    pub allowed_download_hosts: Vec<String>,
+
+    /// Hosts packages are never downloaded from, in the same format as
+    /// [`Self::allowed_download_hosts`]. Takes precedence over the allowlist.
+    pub denied_download_hosts: Vec<String>,
+
+    /// Largest package download in bytes; `None` disables the limit.
+    ///
+    /// Defaults to [`Self::DEFAULT_MAX_DOWNLOAD_SIZE`]. Downloads announcing
+    /// a larger `Content-Length` are refused, and streamed downloads are
+    /// aborted once they pass the limit.
+    pub max_download_size: Option<u64>,
+
+    /// Size cap in bytes of the package cache in
+    /// `$XDG_CACHE_HOME/plasmoid-updater/downloads`; `None` disables it.
+    ///
+    /// Defaults to [`Self::DEFAULT_DOWNLOAD_CACHE_LIMIT`]. Packages are
+    /// reused by components sharing a download, e.g. a global theme and the
+    /// splash screen from the same pack, and by later runs until a new
+    /// version is published. The least recently used ones are evicted first.
+    pub download_cache_limit: Option<u64>,
+
+    /// Starts downloading updates while the check is still running, so
+    /// large batches need not wait for every component to be checked;
+    /// `false` (default) downloads once the check is done.
+    ///
+    /// Packages are fetched into the download cache as their update is
+    /// found, and installs pick them up from there. Only updates that are
+    /// installed without asking are fetched early, so it has no effect
+    /// with the interactive selection, nor without a
+    /// [`Self::download_cache_limit`].
+    pub pipeline_downloads: bool,
+
+    /// Number of installed versions to keep per component for
+    /// [`downgrade`](crate::downgrade); `0` (default) keeps none.
+    ///
+    /// Copies live in `$XDG_DATA_HOME/plasmoid-updater/versions/<component>/<version>/`
+    /// (`/var/lib/plasmoid-updater/versions` for system components). Both the
+    /// version being replaced and the newly installed one are kept.
+    pub keep_versions: usize,
+
+    /// What happens to the old files of an updated, downgraded or removed
+    /// component, see [`RemovalMode`]. [`RemovalMode::Backup`] by default.
+    pub removal_mode: RemovalMode,
+
+    /// What happens when selected updates would write to the same files,
+    /// which is checked before anything is installed. [`ConflictPolicy::Fail`]
+    /// by default.
+    pub conflict_policy: ConflictPolicy,
+
+    /// When `true` (default from [`Config::new`]), validate each component
+    /// after installing it: its metadata and entry point (e.g. `main.qml`
+    /// for widgets) must exist, QML files must have balanced brackets, and
+    /// `kpackagetool6 --show` must load the package. Problems are reported
+    /// in [`UpdateResult::unhealthy`](crate::UpdateResult::unhealthy).
+    pub health_check: bool,
+
+    /// When `true`, a component failing the [`health_check`](Self::health_check)
+    /// is rolled back and reported as failed. Off by default.
+    pub rollback_unhealthy: bool,
+
+    /// When `true`, an updated package that only ships the Plasma 5-era
+    /// `metadata.desktop` gets a `metadata.json` generated next to it, which
+    /// Plasma 6 needs to load it. Off by default.
+    pub migrate_legacy_metadata: bool,
+
+    /// Shell commands run before and after each component update, see
+    /// [`Hooks`]. None by default.
+    pub hooks: Hooks,
+
+    /// Where [`update()`](crate::update) sends a summary after installing or
+    /// failing to install anything, see [`Notifier`]. Empty by default; a
+    /// notifier that fails is logged and skipped.
+    pub notifiers: Vec<Notifier>,
+
+    /// How store entries are fetched during checks; see [`FetchStrategy`].
+    pub fetch_strategy: FetchStrategy,
+
+    /// Timeouts and retry policy for store requests and downloads.
+    pub api: ApiConfig,
+
+    /// Credentials for KDE Store API requests, for higher rate limits. `None`
+    /// (default) sends anonymous requests. Ignored with a custom
+    /// [`Config::http_backend`].
+    pub store_credentials: Option<StoreCredentials>,
+
+    /// When `true` (default from [`Config::new`]), store pages are cached
+    /// under `$XDG_CACHE_HOME/plasmoid-updater/http` together with their
+    /// `ETag`/`Last-Modified`, and re-requested conditionally. A
+    /// `304 Not Modified` answer reuses the cached page.
+    pub conditional_requests: bool,
+
+    /// When `true`, every component is checked against the store. By
+    /// default (`false`) a check skips components found up to date within
+    /// the last hour whose directory, metadata and version have not changed
+    /// since, and reports them as up to date. The index of those lives in
+    /// `$XDG_CACHE_HOME/plasmoid-updater/check-index.json`; runs against a
+    /// [`Self::store_snapshot`] never use it.
+    pub full_scan: bool,
+
+    /// Store metadata to check against instead of the live KDE Store.
+    ///
+    /// `None` (default) queries the store. See [`StoreSnapshot`].
+    pub store_snapshot: Option<Arc<StoreSnapshot>>,
+
+    /// Minisign public keys trusted to sign snapshots, given as the base64
+    /// key line or the full content of a `minisign.pub` file.
+    ///
+    /// Used by [`StoreSnapshot::load_signed`]. Empty by default.
+    pub trusted_keys: Vec<String>,
+
+    /// Advisory feed listing known-broken store releases, see
+    /// [`HeldUpdate`](crate::HeldUpdate).
+    ///
+    /// [`Config::new`] sets [`Self::DEFAULT_ADVISORIES_URL`]; `None` skips
+    /// advisories. Updates to a listed version are held back instead of
+    /// offered.
+    pub advisories_url: Option<String>,
+
+    /// Store accounts expected to take over entries, e.g. a known new
+    /// maintainer. Updates they publish are not flagged as an author change,
+    /// see [`AvailableUpdate::previous_author`].
+    pub trusted_authors: Vec<String>,
+
+    /// When `true`, updates whose store entry changed author since it was
+    /// first seen need confirmation: the interactive selection asks, runs
+    /// without it skip them. Components named in [`Self::only_packages`]
+    /// count as confirmed. `false` (default) only warns.
+    pub confirm_author_changes: bool,
+
+    /// When `true`, the missing companions of an updated global theme, see
+    /// [`AvailableUpdate::suggested_companions`](crate::AvailableUpdate::suggested_companions),
+    /// are installed from the KDE Store without asking. `false` (default)
+    /// asks in interactive runs and installs none otherwise.
+    pub install_companions: bool,
+
+    /// File the gauges of each check and update run are written to for the
+    /// Prometheus node exporter's textfile collector, e.g.
+    /// `/var/lib/node_exporter/textfile/plasmoid_updater.prom`, see
+    /// [`Metrics`](crate::Metrics). `None` (default) writes none.
+    pub metrics_file: Option<PathBuf>,
+
+    /// Receives progress [`Event`]s during checks and updates.
+    ///
+    /// When set, the built-in terminal output of the `cli` feature is turned
+    /// off and updates are applied without prompting.
+    pub event_sink: Option<EventSink>,
+
+    /// Transport for KDE Store API requests.
+    ///
+    /// `None` (default) queries the store over HTTPS. Component downloads are
+    /// not affected.
+    pub http_backend: Option<Arc<dyn HttpBackend>>,
+
+    /// When `true`, also update system components owned by a distro package.
+    ///
+    /// Off by default: the package manager would revert the files on its next
+    /// upgrade, and package verification would report them as modified.
+    pub force_distro_owned: bool,
+
+    /// Preferred download for components whose store entry offers several,
+    /// e.g. `"org.example.clock" => "plasma6"`.
+    ///
+    /// Keys are directory or display names; values select an
+    /// [`AvailableUpdate::alternatives`](crate::AvailableUpdate::alternatives)
+    /// entry as described in [`DownloadVariant::matches`](crate::DownloadVariant::matches).
+    /// Components without an entry get the download matching the running
+    /// Plasma version, and are asked about interactively with the `cli` feature.
+    pub download_variants: HashMap<String, String>,
+
+    /// Token checked before each component is installed.
+    ///
+    /// Once cancelled, components not yet started are skipped.
+    pub cancel_token: Option<CancelToken>,
+}
+
+/// Same as [`Config::new`], so struct update syntax keeps the secure
+/// defaults.
+impl Default for Config {
+    fn default() -> Self {
+        Self {
+            system: false,
+            excluded_packages: Vec::new(),
+            only_packages: Vec::new(),
+            excluded_types: Vec::new(),
+            included_types: Vec::new(),
+            enable_generic_kns: false,
+            groups: HashMap::new(),
+            group: None,
+            widgets_id_table: DEFAULT_WIDGETS_TABLE.clone(),
+            restart: RestartBehavior::default(),
+            restart_required: Vec::new(),
+            no_restart: Vec::new(),
+            crash_monitor_window: Some(Self::DEFAULT_CRASH_MONITOR_WINDOW),
+            rollback_on_crash: false,
+            backup_layout: true,
+            reload_kwin: true,
+            clear_theme_caches: true,
+            reapply_active_themes: true,
+            auto_confirm: false,
+            auto_apply: None,
+            retry_failed: 0,
+            threads: None,
+            skip_plasma_detection: false,
+            inhibit_idle: true,
+            temp_dir: None,
+            stale_temp_max_age: Some(Self::DEFAULT_STALE_TEMP_MAX_AGE),
+            require_https: true,
+            allowed_download_hosts: Vec::new(),
+            denied_download_hosts: Vec::new(),
+            max_download_size: Some(Self::DEFAULT_MAX_DOWNLOAD_SIZE),
+            download_cache_limit: Some(Self::DEFAULT_DOWNLOAD_CACHE_LIMIT),
+            pipeline_downloads: false,
+            keep_versions: 0,
+            removal_mode: RemovalMode::default(),
+            conflict_policy: ConflictPolicy::default(),
+            health_check: true,
+            rollback_unhealthy: false,
+            migrate_legacy_metadata: false,
+            hooks: Hooks::default(),
+            notifiers: Vec::new(),
+            fetch_strategy: FetchStrategy::default(),
+            api: ApiConfig::default(),
+            store_credentials: None,
+            conditional_requests: true,
+            full_scan: false,
+            store_snapshot: None,
+            trusted_keys: Vec::new(),
+            advisories_url: Some(Self::DEFAULT_ADVISORIES_URL.to_string()),
+            trusted_authors: Vec::new(),
+            confirm_author_changes: false,
+            install_companions: false,
+            metrics_file: None,
+            event_sink: None,
+            http_backend: None,
+            force_distro_owned: false,
+            download_variants: HashMap::new(),
+            cancel_token: None,
+        }
+    }
 }
 
 impl Config {
+    /// Default for [`Self::max_download_size`]: 500 MiB.
+    pub const DEFAULT_MAX_DOWNLOAD_SIZE: u64 = 500 * 1024 * 1024;
+
+    /// Default for [`Self::download_cache_limit`]: 1 GiB.
+    pub const DEFAULT_DOWNLOAD_CACHE_LIMIT: u64 = 1024 * 1024 * 1024;
+
+    /// Default for [`Self::crash_monitor_window`]: 15 seconds.
+    pub const DEFAULT_CRASH_MONITOR_WINDOW: Duration = Duration::from_secs(15);
+
+    /// Default for [`Self::stale_temp_max_age`]: 1 day.
+    pub const DEFAULT_STALE_TEMP_MAX_AGE: Duration = Duration::from_secs(24 * 60 * 60);
+
+    /// Default for [`Self::advisories_url`]: the feed published in the
+    /// project repository.
+    pub const DEFAULT_ADVISORIES_URL: &str =
+        "https://raw.githubusercontent.com/uwuclxdy/plasmoid-updater/main/advisories.json";
+
     /// Creates a new configuration with default values.
     ///
     /// Default values:
@@ -119,16 +572,24 @@ impl Config {
     /// - `excluded_packages`: empty
     /// - `widgets_id_table`: loaded from embedded widgets-id file
     /// - `restart`: [`RestartBehavior::Never`]
+    /// - `backup_layout`: true
+    /// - `reload_kwin`: true
+    /// - `clear_theme_caches`: true
+    /// - `reapply_active_themes`: true
+    /// - `require_https`: true
+    /// - `health_check`: true
+    /// - `conditional_requests`: true
+    /// - `max_download_size`: [`Self::DEFAULT_MAX_DOWNLOAD_SIZE`]
+    /// - `download_cache_limit`: [`Self::DEFAULT_DOWNLOAD_CACHE_LIMIT`]
+    /// - `crash_monitor_window`: [`Self::DEFAULT_CRASH_MONITOR_WINDOW`]
+    /// - `stale_temp_max_age`: [`Self::DEFAULT_STALE_TEMP_MAX_AGE`]
+    /// - `advisories_url`: [`Self::DEFAULT_ADVISORIES_URL`]
     ///
     /// The embedded widgets-id table provides fallback content ID mappings
     /// for components that cannot be resolved via KNewStuff registry or
     /// exact name matching.
     pub fn new() -> Self {
-        Self {
-            widgets_id_table: DEFAULT_WIDGETS_TABLE.clone(),
-            inhibit_idle: true,
-            ..Default::default()
-        }
+        Self::default()
     }
 
     /// Sets whether to operate on system-wide components.
@@ -176,7 +637,8 @@ impl Config {
     /// Sets the list of Plasmoids to exclude from updates.
     ///
     /// Components in this list will be skipped during updates.
-    /// The list can contain either directory names or display names.
+    /// The list can contain either directory names or display names, as exact
+    /// names, globs (`*` and `?`) or `re:`-prefixed regular expressions.
     ///
     /// # Arguments
     ///
@@ -191,6 +653,8 @@ impl Config {
     ///     .with_excluded_packages(vec![
     ///         "org.kde.plasma.systemmonitor".to_string(),
     ///         "Problematic Widget".to_string(),
+    ///         "com.github.someone.*".to_string(),
+    ///         r"re:^org\.kde\.(clock|timer)$".to_string(),
     ///     ]);
     /// ```
     pub fn with_excluded_packages(mut self, packages: Vec<String>) -> Self {
@@ -198,169 +662,993 @@ impl Config {
         self
     }
 
-    /// Sets the plasmashell restart behavior after updates.
+    /// Restricts checks and updates to the components matching `packages`.
     ///
     /// # Example
     ///
     /// ```rust
-    /// use libplasmoid_updater::{Config, RestartBehavior};
+    /// use libplasmoid_updater::Config;
     ///
-    /// let config = Config::new().with_restart(RestartBehavior::Always);
+    /// let config = Config::new().with_only_packages(vec![
+    ///     "org.kde.plasma.systemmonitor".to_string(),
+    ///     "Weather*".to_string(),
+    /// ]);
     /// ```
-    pub fn with_restart(mut self, restart: RestartBehavior) -> Self {
-        self.restart = restart;
+    pub fn with_only_packages(mut self, packages: Vec<String>) -> Self {
+        self.only_packages = packages;
         self
     }
 
-    /// Parses a widgets-id table from a string.
+    /// Sets the component types to ignore entirely.
     ///
-    /// The format is one entry per line: `content_id directory_name`
-    /// Lines starting with `#` are comments.
-    pub fn parse_widgets_id(content: &str) -> HashMap<String, u64> {
-        let mut table = HashMap::with_capacity(content.lines().count());
-        for line in content.lines() {
-            if let Some((id, name)) = parse_widgets_id_line(line) {
-                table.insert(name, id);
-            }
-        }
-        table
+    /// # Example
+    ///
+    /// ```rust
+    /// use libplasmoid_updater::{ComponentType, Config};
+    ///
+    /// let config = Config::new()
+    ///     .with_excluded_types(vec![ComponentType::IconTheme, ComponentType::GlobalTheme]);
+    /// ```
+    pub fn with_excluded_types(mut self, types: Vec<ComponentType>) -> Self {
+        self.excluded_types = types;
+        self
     }
 
-    /// Sets whether to skip interactive prompts and apply all non-excluded
-    /// updates automatically.
+    /// Restricts discovery to the given component types.
     ///
-    /// Has no effect without the `cli` feature enabled.
+    /// An empty list (the default) means every type is considered.
+    ///
+    /// # Example
+    ///
+    /// ```rust
+    /// use libplasmoid_updater::{ComponentType, Config};
+    ///
+    /// let config = Config::new().with_included_types(vec![ComponentType::PlasmaWidget]);
+    /// ```
+    pub fn with_included_types(mut self, types: Vec<ComponentType>) -> Self {
+        self.included_types = types;
+        self
+    }
+
+    /// Sets whether entries of unmodelled KNewStuff registries are
+    /// discovered and updated as [`ComponentType::Other`].
     ///
     /// # Example
     ///
     /// ```rust
     /// use libplasmoid_updater::Config;
     ///
-    /// let config = Config::new().with_auto_confirm(true);
+    /// let config = Config::new().with_generic_kns(true);
+    /// assert!(config.enable_generic_kns);
     /// ```
-    pub fn with_auto_confirm(mut self, auto_confirm: bool) -> Self {
-        self.auto_confirm = auto_confirm;
+    pub fn with_generic_kns(mut self, enabled: bool) -> Self {
+        self.enable_generic_kns = enabled;
         self
     }
 
-    /// Sets the maximum number of parallel installation threads.
+    /// Sets the named package groups available to [`with_group`](Self::with_group).
     ///
-    /// By default (`None`), the library uses the number of logical CPUs.
-    /// Setting this to a specific value pins the thread pool to exactly
-    /// that many threads.
+    /// # Example
+    ///
+    /// ```rust
+    /// use std::collections::HashMap;
+    /// use libplasmoid_updater::Config;
+    ///
+    /// let config = Config::new().with_groups(HashMap::from([(
+    ///     "work".to_string(),
+    ///     vec!["widgetA".to_string(), "widgetB".to_string()],
+    /// )]));
+    /// ```
+    pub fn with_groups(mut self, groups: HashMap<String, Vec<String>>) -> Self {
+        self.groups = groups;
+        self
+    }
+
+    /// Restricts updates to the components of a named group.
     ///
     /// # Example
     ///
     /// ```rust
+    /// use std::collections::HashMap;
     /// use libplasmoid_updater::Config;
     ///
-    /// let config = Config::new().with_threads(4);
+    /// let config = Config::new()
+    ///     .with_groups(HashMap::from([("work".to_string(), vec!["widgetA".to_string()])]))
+    ///     .with_group("work");
     /// ```
-    pub fn with_threads(mut self, threads: usize) -> Self {
-        self.threads = Some(threads);
+    pub fn with_group(mut self, group: impl Into<String>) -> Self {
+        self.group = Some(group.into());
         self
     }
 
-    /// Sets whether to skip KDE Plasma environment detection.
+    /// Sets a sink receiving progress events.
     ///
-    /// When `true`, the library proceeds without checking for the KNewStuff3
-    /// registry directory. Useful for CI, testing, or non-standard KDE setups.
+    /// # Example
+    ///
+    /// ```rust
+    /// use libplasmoid_updater::{Config, EventSink};
+    ///
+    /// let config = Config::new().with_event_sink(EventSink::json_lines(std::io::stdout()));
+    /// ```
+    pub fn with_event_sink(mut self, sink: EventSink) -> Self {
+        self.event_sink = Some(sink);
+        self
+    }
+
+    /// Replaces the transport used for KDE Store API requests.
     ///
     /// # Example
     ///
     /// ```rust
-    /// use libplasmoid_updater::Config;
+    /// use libplasmoid_updater::{Config, FixtureBackend};
     ///
-    /// let config = Config::new().with_skip_plasma_detection(true);
+    /// // Answer store queries from the bundled sample responses.
+    /// let config = Config::new().with_http_backend(FixtureBackend::sample());
     /// ```
-    pub fn with_skip_plasma_detection(mut self, skip: bool) -> Self {
-        self.skip_plasma_detection = skip;
+    pub fn with_http_backend(mut self, backend: impl HttpBackend + 'static) -> Self {
+        self.http_backend = Some(Arc::new(backend));
         self
     }
 
-    /// Sets whether to inhibit system idle/sleep/shutdown during installs.
+    /// Allows updating system components owned by a distro package.
     ///
-    /// Defaults to `true`. Set to `false` if the calling application handles
-    /// its own power management inhibition (e.g., a GUI app using DBus directly).
+    /// # Example
+    ///
+    /// ```rust
+    /// use libplasmoid_updater::Config;
+    ///
+    /// let config = Config::new().with_system(true).with_force_distro_owned(true);
+    /// ```
+    pub fn with_force_distro_owned(mut self, force: bool) -> Self {
+        self.force_distro_owned = force;
+        self
+    }
+
+    /// Sets the preferred download per component, see [`Self::download_variants`].
     ///
     /// # Example
     ///
     /// ```rust
+    /// use std::collections::HashMap;
     /// use libplasmoid_updater::Config;
     ///
-    /// let config = Config::new().with_inhibit_idle(false);
-    /// assert!(!config.inhibit_idle);
+    /// let config = Config::new().with_download_variants(HashMap::from([(
+    ///     "org.example.clock".to_string(),
+    ///     "plasma6".to_string(),
+    /// )]));
     /// ```
-    pub fn with_inhibit_idle(mut self, inhibit: bool) -> Self {
-        self.inhibit_idle = inhibit;
+    pub fn with_download_variants(mut self, variants: HashMap<String, String>) -> Self {
+        self.download_variants = variants;
         self
     }
-}
 
-pub(crate) fn parse_widgets_id_line(line: &str) -> Option<(u64, String)> {
-    let line = line.trim();
-    if line.is_empty() || line.starts_with('#') {
-        return None;
+    /// Returns the configured download selector for `component`, if any.
+    pub(crate) fn download_variant_for(&self, component: &InstalledComponent) -> Option<&str> {
+        self.download_variants
+            .get(&component.directory_name)
+            .or_else(|| self.download_variants.get(&component.name))
+            .map(String::as_str)
     }
 
-    let mut parts = line.splitn(2, ' ');
-    let id = parts.next()?.parse::<u64>().ok()?;
-    let name = parts.next()?.trim();
-    if name.is_empty() {
-        return None;
+    /// Lets another thread stop an update between components.
+    ///
+    /// # Example
+    ///
+    /// ```rust
+    /// use libplasmoid_updater::{CancelToken, Config};
+    ///
+    /// let token = CancelToken::new();
+    /// let config = Config::new().with_cancel_token(token.clone());
+    /// ```
+    pub fn with_cancel_token(mut self, token: CancelToken) -> Self {
+        self.cancel_token = Some(token);
+        self
     }
-    Some((id, name.to_string()))
-}
 
-#[cfg(test)]
-mod tests {
-    use super::*;
+    /// Whether `update` may be installed without asking under
+    /// [`Self::auto_apply`].
+    pub(crate) fn auto_applies(&self, update: &AvailableUpdate) -> bool {
+        !self.only_packages.is_empty() || self.auto_apply.is_none_or(|max| update.severity <= max)
+    }
 
-    #[test]
-    fn test_parse_widgets_id_line_valid() {
-        let line = "998890 com.bxabi.bumblebee-indicator";
-        let result = parse_widgets_id_line(line);
-        assert_eq!(
-            result,
-            Some((998890, "com.bxabi.bumblebee-indicator".to_string()))
-        );
+    /// Returns `true` if the run was cancelled through [`Self::cancel_token`].
+    pub(crate) fn is_cancelled(&self) -> bool {
+        self.cancel_token
+            .as_ref()
+            .is_some_and(CancelToken::is_cancelled)
     }
 
-    #[test]
-    fn test_parse_widgets_id_line_comment() {
-        let line = "#2182964 adhe.menu.11 #Ignored, not a unique ID";
-        let result = parse_widgets_id_line(line);
-        assert_eq!(result, None);
+    pub(crate) fn emit(&self, event: impl FnOnce() -> Event) {
+        if let Some(sink) = &self.event_sink {
+            sink.emit(event());
+        }
     }
 
-    #[test]
-    fn test_parse_widgets_id_line_empty() {
-        let line = "";
-        let result = parse_widgets_id_line(line);
-        assert_eq!(result, None);
+    /// Returns `true` if the built-in terminal output should be shown.
+    #[cfg(feature = "cli")]
+    pub(crate) fn terminal_output(&self) -> bool {
+        self.event_sink.is_none()
     }
 
-    #[test]
-    fn test_parse_widgets_id_table() {
-        let content = "998890 com.bxabi.bumblebee-indicator\n\
-                       998913 org.kde.plasma.awesomewidget\n\
-                       # Comment line\n\
-                       1155946 com.dschopf.plasma.qalculate\n";
-        let table = Config::parse_widgets_id(content);
-        assert_eq!(table.len(), 3);
-        assert_eq!(table.get("com.bxabi.bumblebee-indicator"), Some(&998890));
-        assert_eq!(table.get("org.kde.plasma.awesomewidget"), Some(&998913));
-        assert_eq!(table.get("com.dschopf.plasma.qalculate"), Some(&1155946));
+    /// Returns `true` if components of `component_type` should be considered.
+    pub(crate) fn includes_type(&self, component_type: ComponentType) -> bool {
+        if matches!(component_type, ComponentType::Other(_)) && !self.enable_generic_kns {
+            return false;
+        }
+        (self.included_types.is_empty() || self.included_types.contains(&component_type))
+            && !self.excluded_types.contains(&component_type)
     }
 
-    #[test]
-    fn test_default_widgets_id_table_loads() {
-        let config = Config::new();
-        // Verify the embedded file is loaded and contains expected entries
-        assert!(
-            !config.widgets_id_table.is_empty(),
-            "Default widgets_id_table should not be empty"
-        );
+    /// Sets the plasmashell restart behavior after updates.
+    ///
+    /// # Example
+    ///
+    /// ```rust
+    /// use libplasmoid_updater::{Config, RestartBehavior};
+    ///
+    /// let config = Config::new().with_restart(RestartBehavior::Always);
+    /// ```
+    pub fn with_restart(mut self, restart: RestartBehavior) -> Self {
+        self.restart = restart;
+        self
+    }
+
+    /// Sets the components that always need a plasmashell restart.
+    ///
+    /// # Example
+    ///
+    /// ```rust
+    /// use libplasmoid_updater::Config;
+    ///
+    /// let config = Config::new().with_restart_required(vec!["com.example.wallpaper".to_string()]);
+    /// ```
+    pub fn with_restart_required(mut self, packages: Vec<String>) -> Self {
+        self.restart_required = packages;
+        self
+    }
+
+    /// Sets the components that never need a restart.
+    ///
+    /// # Example
+    ///
+    /// ```rust
+    /// use libplasmoid_updater::Config;
+    ///
+    /// let config = Config::new().with_no_restart(vec!["org.kde.plasma.*".to_string()]);
+    /// ```
+    pub fn with_no_restart(mut self, packages: Vec<String>) -> Self {
+        self.no_restart = packages;
+        self
+    }
+
+    /// Sets how long plasmashell is watched for crashes after a restart;
+    /// `None` disables monitoring.
+    ///
+    /// # Example
+    ///
+    /// ```rust
+    /// use std::time::Duration;
+    /// use libplasmoid_updater::Config;
+    ///
+    /// let config = Config::new().with_crash_monitor_window(Some(Duration::from_secs(30)));
+    /// ```
+    pub fn with_crash_monitor_window(mut self, window: Option<Duration>) -> Self {
+        self.crash_monitor_window = window;
+        self
+    }
+
+    /// Sets whether updates are rolled back automatically when plasmashell
+    /// crashes after restarting.
+    ///
+    /// # Example
+    ///
+    /// ```rust
+    /// use libplasmoid_updater::{Config, RestartBehavior};
+    ///
+    /// let config = Config::new()
+    ///     .with_restart(RestartBehavior::Always)
+    ///     .with_rollback_on_crash(true);
+    /// ```
+    pub fn with_rollback_on_crash(mut self, rollback: bool) -> Self {
+        self.rollback_on_crash = rollback;
+        self
+    }
+
+    /// Sets whether the Plasma layout is backed up before updating widgets
+    /// that need a plasmashell restart.
+    ///
+    /// # Example
+    ///
+    /// ```rust
+    /// use libplasmoid_updater::Config;
+    ///
+    /// let config = Config::new().with_backup_layout(false);
+    /// ```
+    pub fn with_backup_layout(mut self, backup_layout: bool) -> Self {
+        self.backup_layout = backup_layout;
+        self
+    }
+
+    /// Sets whether KWin is reconfigured after KWin-related updates.
+    ///
+    /// # Example
+    ///
+    /// ```rust
+    /// use libplasmoid_updater::Config;
+    ///
+    /// let config = Config::new().with_reload_kwin(false);
+    /// ```
+    pub fn with_reload_kwin(mut self, reload_kwin: bool) -> Self {
+        self.reload_kwin = reload_kwin;
+        self
+    }
+
+    /// Sets whether Plasma's SVG and theme caches are cleared after theme
+    /// updates.
+    ///
+    /// # Example
+    ///
+    /// ```rust
+    /// use libplasmoid_updater::Config;
+    ///
+    /// let config = Config::new().with_clear_theme_caches(false);
+    /// assert!(!config.clear_theme_caches);
+    /// ```
+    pub fn with_clear_theme_caches(mut self, enabled: bool) -> Self {
+        self.clear_theme_caches = enabled;
+        self
+    }
+
+    /// Sets whether updated themes that are in use get re-applied.
+    ///
+    /// # Example
+    ///
+    /// ```rust
+    /// use libplasmoid_updater::Config;
+    ///
+    /// let config = Config::new().with_reapply_active_themes(false);
+    /// ```
+    pub fn with_reapply_active_themes(mut self, reapply: bool) -> Self {
+        self.reapply_active_themes = reapply;
+        self
+    }
+
+    /// Parses a widgets-id table from a string.
+    ///
+    /// The format is one entry per line: `content_id directory_name`
+    /// Lines starting with `#` are comments.
+    pub fn parse_widgets_id(content: &str) -> HashMap<String, u64> {
+        let mut table = HashMap::with_capacity(content.lines().count());
+        for line in content.lines() {
+            if let Some((id, name)) = parse_widgets_id_line(line) {
+                table.insert(name, id);
+            }
+        }
+        table
+    }
+
+    /// Sets whether to skip interactive prompts and apply all non-excluded
+    /// updates automatically.
+    ///
+    /// Has no effect without the `cli` feature enabled.
+    ///
+    /// # Example
+    ///
+    /// ```rust
+    /// use libplasmoid_updater::Config;
+    ///
+    /// let config = Config::new().with_auto_confirm(true);
+    /// ```
+    pub fn with_auto_confirm(mut self, auto_confirm: bool) -> Self {
+        self.auto_confirm = auto_confirm;
+        self
+    }
+
+    /// Sets the largest [`UpdateSeverity`] installed without asking, see
+    /// [`Self::auto_apply`].
+    ///
+    /// # Example
+    ///
+    /// ```rust
+    /// use libplasmoid_updater::{Config, UpdateSeverity};
+    ///
+    /// let config = Config::new().with_auto_apply(Some(UpdateSeverity::Patch));
+    /// ```
+    pub fn with_auto_apply(mut self, severity: Option<UpdateSeverity>) -> Self {
+        self.auto_apply = severity;
+        self
+    }
+
+    /// Sets how many times components that failed with a transient error,
+    /// like a dropped connection, are retried after the rest of the run.
+    ///
+    /// # Example
+    ///
+    /// ```rust
+    /// use libplasmoid_updater::Config;
+    ///
+    /// let config = Config::new().with_retry_failed(2);
+    /// assert_eq!(config.retry_failed, 2);
+    /// ```
+    pub fn with_retry_failed(mut self, passes: u8) -> Self {
+        self.retry_failed = passes;
+        self
+    }
+
+    /// Sets the maximum number of parallel installation threads.
+    ///
+    /// By default (`None`), the library uses the number of logical CPUs.
+    /// Setting this to a specific value pins the thread pool to exactly
+    /// that many threads.
+    ///
+    /// # Example
+    ///
+    /// ```rust
+    /// use libplasmoid_updater::Config;
+    ///
+    /// let config = Config::new().with_threads(4);
+    /// ```
+    pub fn with_threads(mut self, threads: usize) -> Self {
+        self.threads = Some(threads);
+        self
+    }
+
+    /// Sets whether to skip KDE Plasma environment detection.
+    ///
+    /// When `true`, the library proceeds without checking for the KNewStuff3
+    /// registry directory. Useful for CI, testing, or non-standard KDE setups.
+    ///
+    /// # Example
+    ///
+    /// ```rust
+    /// use libplasmoid_updater::Config;
+    ///
+    /// let config = Config::new().with_skip_plasma_detection(true);
+    /// ```
+    pub fn with_skip_plasma_detection(mut self, skip: bool) -> Self {
+        self.skip_plasma_detection = skip;
+        self
+    }
+
+    /// Sets whether to inhibit system idle/sleep/shutdown during installs.
+    ///
+    /// Defaults to `true`. Set to `false` if the calling application handles
+    /// its own power management inhibition (e.g., a GUI app using DBus directly).
+    ///
+    /// # Example
+    ///
+    /// ```rust
+    /// use libplasmoid_updater::Config;
+    ///
+    /// let config = Config::new().with_inhibit_idle(false);
+    /// assert!(!config.inhibit_idle);
+    /// ```
+    pub fn with_inhibit_idle(mut self, inhibit: bool) -> Self {
+        self.inhibit_idle = inhibit;
+        self
+    }
+
+    /// Sets whether package downloads must use HTTPS. Defaults to `true`.
+    ///
+    /// # Example
+    ///
+    /// ```rust
+    /// use libplasmoid_updater::Config;
+    ///
+    /// let config = Config::new().with_require_https(false);
+    /// ```
+    pub fn with_require_https(mut self, require: bool) -> Self {
+        self.require_https = require;
+        self
+    }
+
+    /// Restricts package downloads to these hosts, see
+    /// [`Self::allowed_download_hosts`].
+    ///
+    /// # Example
+    ///
+    /// ```rust
+    /// use libplasmoid_updater::Config;
+    ///
+    /// let config = Config::new().with_allowed_download_hosts(vec![
+    ///     "*.kde.org".to_string(),
+    ///     "*.pling.com".to_string(),
+    /// ]);
+    /// ```
+    pub fn with_allowed_download_hosts(mut self, hosts: Vec<String>) -> Self {
+        self.allowed_download_hosts = hosts;
+        self
+    }
+
+    /// Never downloads packages from these hosts, see
+    /// [`Self::denied_download_hosts`].
+    ///
+    /// # Example
+    ///
+    /// ```rust
+    /// use libplasmoid_updater::Config;
+    ///
+    /// let config = Config::new().with_denied_download_hosts(vec!["example.com".to_string()]);
+    /// ```
+    pub fn with_denied_download_hosts(mut self, hosts: Vec<String>) -> Self {
+        self.denied_download_hosts = hosts;
+        self
+    }
+
+    /// Sets the largest allowed package download in bytes, or `None` for no
+    /// limit.
+    ///
+    /// # Example
+    ///
+    /// ```rust
+    /// use libplasmoid_updater::Config;
+    ///
+    /// let config = Config::new().with_max_download_size(Some(100 * 1024 * 1024));
+    /// ```
+    pub fn with_max_download_size(mut self, max: Option<u64>) -> Self {
+        self.max_download_size = max;
+        self
+    }
+
+    /// Sets the size cap of the download cache in bytes, or `None` to
+    /// disable caching.
+    ///
+    /// # Example
+    ///
+    /// ```rust
+    /// use libplasmoid_updater::Config;
+    ///
+    /// let config = Config::new().with_download_cache_limit(Some(256 * 1024 * 1024));
+    /// ```
+    pub fn with_download_cache_limit(mut self, limit: Option<u64>) -> Self {
+        self.download_cache_limit = limit;
+        self
+    }
+
+    /// Downloads updates while the check is still running, see
+    /// [`Self::pipeline_downloads`].
+    ///
+    /// # Example
+    ///
+    /// ```rust
+    /// use libplasmoid_updater::Config;
+    ///
+    /// let config = Config::new()
+    ///     .with_auto_confirm(true)
+    ///     .with_pipeline_downloads(true);
+    /// ```
+    pub fn with_pipeline_downloads(mut self, pipeline: bool) -> Self {
+        self.pipeline_downloads = pipeline;
+        self
+    }
+
+    /// Keeps the last `count` installed versions of each component, see
+    /// [`Self::keep_versions`].
+    ///
+    /// # Example
+    ///
+    /// ```rust
+    /// use libplasmoid_updater::Config;
+    ///
+    /// let config = Config::new().with_keep_versions(3);
+    /// ```
+    pub fn with_keep_versions(mut self, count: usize) -> Self {
+        self.keep_versions = count;
+        self
+    }
+
+    /// Sets what happens to the files of replaced and removed components.
+    ///
+    /// # Example
+    ///
+    /// ```rust
+    /// use libplasmoid_updater::{Config, RemovalMode};
+    ///
+    /// let config = Config::new().with_removal_mode(RemovalMode::Trash);
+    /// assert_eq!(config.removal_mode, RemovalMode::Trash);
+    /// ```
+    pub fn with_removal_mode(mut self, mode: RemovalMode) -> Self {
+        self.removal_mode = mode;
+        self
+    }
+
+    /// Sets what happens when selected updates would write to the same
+    /// files, see [`Self::conflict_policy`].
+    ///
+    /// # Example
+    ///
+    /// ```rust
+    /// use libplasmoid_updater::{Config, ConflictPolicy};
+    ///
+    /// let config = Config::new().with_conflict_policy(ConflictPolicy::Warn);
+    /// ```
+    pub fn with_conflict_policy(mut self, policy: ConflictPolicy) -> Self {
+        self.conflict_policy = policy;
+        self
+    }
+
+    /// Sets whether installed components are validated after updating.
+    ///
+    /// # Example
+    ///
+    /// ```rust
+    /// use libplasmoid_updater::Config;
+    ///
+    /// let config = Config::new().with_health_check(false);
+    /// assert!(!config.health_check);
+    /// ```
+    pub fn with_health_check(mut self, enabled: bool) -> Self {
+        self.health_check = enabled;
+        self
+    }
+
+    /// Sets whether components failing the post-install health check are
+    /// rolled back.
+    ///
+    /// # Example
+    ///
+    /// ```rust
+    /// use libplasmoid_updater::Config;
+    ///
+    /// let config = Config::new().with_rollback_unhealthy(true);
+    /// ```
+    pub fn with_rollback_unhealthy(mut self, rollback: bool) -> Self {
+        self.rollback_unhealthy = rollback;
+        self
+    }
+
+    /// Sets whether updated packages with only a `metadata.desktop` get a
+    /// generated `metadata.json`.
+    ///
+    /// # Example
+    ///
+    /// ```rust
+    /// use libplasmoid_updater::Config;
+    ///
+    /// let config = Config::new().with_migrate_legacy_metadata(true);
+    /// assert!(config.migrate_legacy_metadata);
+    /// ```
+    pub fn with_migrate_legacy_metadata(mut self, migrate: bool) -> Self {
+        self.migrate_legacy_metadata = migrate;
+        self
+    }
+
+    /// Sets the commands run around each component update.
+    ///
+    /// # Example
+    ///
+    /// ```rust
+    /// use libplasmoid_updater::{Config, Hooks};
+    ///
+    /// let config = Config::new().with_hooks(Hooks::new().with_post_update("echo done"));
+    /// assert!(config.hooks.post_update.is_some());
+    /// ```
+    pub fn with_hooks(mut self, hooks: Hooks) -> Self {
+        self.hooks = hooks;
+        self
+    }
+
+    /// Sets where update summaries are sent.
+    ///
+    /// # Example
+    ///
+    /// ```rust
+    /// use libplasmoid_updater::{Config, Notifier};
+    ///
+    /// let config = Config::new().with_notifiers(vec![Notifier::Desktop]);
+    /// assert_eq!(config.notifiers, [Notifier::Desktop]);
+    /// ```
+    pub fn with_notifiers(mut self, notifiers: Vec<Notifier>) -> Self {
+        self.notifiers = notifiers;
+        self
+    }
+
+    /// Sets the timeouts and retry policy for store requests and downloads.
+    ///
+    /// # Example
+    ///
+    /// ```rust
+    /// use std::time::Duration;
+    /// use libplasmoid_updater::{ApiConfig, Config};
+    ///
+    /// let api = ApiConfig::new().with_max_attempts(5);
+    /// let config = Config::new().with_api(api);
+    /// assert_eq!(config.api.max_attempts, 5);
+    /// ```
+    pub fn with_api(mut self, api: ApiConfig) -> Self {
+        self.api = api;
+        self
+    }
+
+    /// Sets the credentials sent with KDE Store API requests.
+    ///
+    /// # Example
+    ///
+    /// ```rust
+    /// use libplasmoid_updater::{Config, StoreCredentials};
+    ///
+    /// let config = Config::new().with_store_credentials(StoreCredentials::Keyring {
+    ///     username: "me".to_string(),
+    /// });
+    /// assert!(config.store_credentials.is_some());
+    /// ```
+    pub fn with_store_credentials(mut self, credentials: StoreCredentials) -> Self {
+        self.store_credentials = Some(credentials);
+        self
+    }
+
+    /// Sets how store entries are fetched during checks.
+    ///
+    /// # Example
+    ///
+    /// ```rust
+    /// use libplasmoid_updater::{Config, FetchStrategy};
+    ///
+    /// let config = Config::new().with_fetch_strategy(FetchStrategy::Targeted);
+    /// ```
+    pub fn with_fetch_strategy(mut self, strategy: FetchStrategy) -> Self {
+        self.fetch_strategy = strategy;
+        self
+    }
+
+    /// Sets whether store pages are cached and revalidated with conditional
+    /// requests.
+    ///
+    /// # Example
+    ///
+    /// ```rust
+    /// use libplasmoid_updater::Config;
+    ///
+    /// // Always download full store pages.
+    /// let config = Config::new().with_conditional_requests(false);
+    /// ```
+    pub fn with_conditional_requests(mut self, enabled: bool) -> Self {
+        self.conditional_requests = enabled;
+        self
+    }
+
+    /// Sets whether every component is checked against the store, see
+    /// [`Self::full_scan`].
+    ///
+    /// # Example
+    ///
+    /// ```rust
+    /// use libplasmoid_updater::Config;
+    ///
+    /// let config = Config::new().with_full_scan(true);
+    /// ```
+    pub fn with_full_scan(mut self, full: bool) -> Self {
+        self.full_scan = full;
+        self
+    }
+
+    /// Resolves checks and updates against `snapshot` instead of the live
+    /// KDE Store.
+    ///
+    /// # Example
+    ///
+    /// ```rust,no_run
+    /// use libplasmoid_updater::{Config, StoreSnapshot};
+    ///
+    /// let snapshot = StoreSnapshot::load("approved.json")?;
+    /// let config = Config::new().with_store_snapshot(snapshot);
+    /// # Ok::<(), libplasmoid_updater::Error>(())
+    /// ```
+    pub fn with_store_snapshot(mut self, snapshot: StoreSnapshot) -> Self {
+        self.store_snapshot = Some(Arc::new(snapshot));
+        self
+    }
+
+    /// Sets the minisign public keys trusted to sign snapshots.
+    ///
+    /// # Example
+    ///
+    /// ```rust,no_run
+    /// use libplasmoid_updater::{Config, StoreSnapshot};
+    ///
+    /// let config = Config::new().with_trusted_keys(vec![
+    ///     "RWQf6LRCGA9i53mlYecO4IzT51TGPpvWucNSCh1CBM0QTaLn73Y7GFO3".to_string(),
+    /// ]);
+    /// let snapshot = StoreSnapshot::load_signed("approved.json", &config.trusted_keys)?;
+    /// let config = config.with_store_snapshot(snapshot);
+    /// # Ok::<(), libplasmoid_updater::Error>(())
+    /// ```
+    pub fn with_trusted_keys(mut self, keys: Vec<String>) -> Self {
+        self.trusted_keys = keys;
+        self
+    }
+
+    /// Sets the advisory feed consulted during checks; `None` ignores
+    /// advisories.
+    ///
+    /// # Example
+    ///
+    /// ```rust
+    /// use libplasmoid_updater::Config;
+    ///
+    /// let config = Config::new()
+    ///     .with_advisories_url(Some("https://example.org/advisories.json".to_string()));
+    /// let ignoring = Config::new().with_advisories_url(None);
+    /// ```
+    pub fn with_advisories_url(mut self, url: Option<String>) -> Self {
+        self.advisories_url = url;
+        self
+    }
+
+    /// Sets the store accounts whose takeover of an entry is not flagged,
+    /// see [`Self::trusted_authors`].
+    ///
+    /// # Example
+    ///
+    /// ```rust
+    /// use libplasmoid_updater::Config;
+    ///
+    /// let config = Config::new().with_trusted_authors(vec!["new-maintainer".to_string()]);
+    /// ```
+    pub fn with_trusted_authors(mut self, authors: Vec<String>) -> Self {
+        self.trusted_authors = authors;
+        self
+    }
+
+    /// Sets whether updates from a changed store author need confirmation,
+    /// see [`Self::confirm_author_changes`].
+    ///
+    /// # Example
+    ///
+    /// ```rust
+    /// use libplasmoid_updater::Config;
+    ///
+    /// let config = Config::new().with_confirm_author_changes(true);
+    /// ```
+    pub fn with_confirm_author_changes(mut self, confirm: bool) -> Self {
+        self.confirm_author_changes = confirm;
+        self
+    }
+
+    /// Sets whether the missing companions of updated global themes are
+    /// installed without asking, see [`Self::install_companions`].
+    ///
+    /// # Example
+    ///
+    /// ```rust
+    /// use libplasmoid_updater::Config;
+    ///
+    /// let config = Config::new().with_install_companions(true);
+    /// ```
+    pub fn with_install_companions(mut self, install: bool) -> Self {
+        self.install_companions = install;
+        self
+    }
+
+    /// Sets the file metrics are written to after each run, see
+    /// [`Self::metrics_file`].
+    ///
+    /// # Example
+    ///
+    /// ```rust
+    /// use libplasmoid_updater::Config;
+    ///
+    /// let config = Config::new()
+    ///     .with_metrics_file(Some("/var/lib/node_exporter/textfile/plasmoid_updater.prom".into()));
+    /// ```
+    pub fn with_metrics_file(mut self, path: Option<PathBuf>) -> Self {
+        self.metrics_file = path;
+        self
+    }
+
+    /// Sets the root directory for temporary download and extraction files.
+    ///
+    /// Useful when `/tmp` is a small tmpfs and large themes fail to extract.
+    ///
+    /// # Example
+    ///
+    /// ```rust
+    /// use libplasmoid_updater::Config;
+    ///
+    /// let config = Config::new().with_temp_dir("/var/tmp");
+    /// assert!(config.temp_dir.is_some());
+    /// ```
+    pub fn with_temp_dir(mut self, dir: impl Into<PathBuf>) -> Self {
+        self.temp_dir = Some(dir.into());
+        self
+    }
+
+    /// Sets the age after which temp directories of crashed runs are
+    /// removed; `None` keeps them.
+    ///
+    /// # Example
+    ///
+    /// ```rust
+    /// use std::time::Duration;
+    /// use libplasmoid_updater::Config;
+    ///
+    /// let week = Duration::from_secs(7 * 24 * 60 * 60);
+    /// let config = Config::new().with_stale_temp_max_age(Some(week));
+    /// assert_eq!(config.stale_temp_max_age, Some(week));
+    /// ```
+    pub fn with_stale_temp_max_age(mut self, max_age: Option<Duration>) -> Self {
+        self.stale_temp_max_age = max_age;
+        self
+    }
+}
+
+pub(crate) fn parse_widgets_id_line(line: &str) -> Option<(u64, String)> {
+    let line = line.trim();
+    if line.is_empty() || line.starts_with('#') {
+        return None;
+    }
+
+    let mut parts = line.splitn(2, ' ');
+    let id = parts.next()?.parse::<u64>().ok()?;
+    let name = parts.next()?.trim();
+    if name.is_empty() {
+        return None;
+    }
+    Some((id, name.to_string()))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn default_keeps_download_safeguards() {
+        let config = Config {
+            system: true,
+            ..Default::default()
+        };
+        assert!(config.require_https);
+        assert_eq!(
+            config.max_download_size,
+            Some(Config::DEFAULT_MAX_DOWNLOAD_SIZE)
+        );
+    }
+
+    #[test]
+    fn test_parse_widgets_id_line_valid() {
+        let line = "998890 com.bxabi.bumblebee-indicator";
+        let result = parse_widgets_id_line(line);
+        assert_eq!(
+            result,
+            Some((998890, "com.bxabi.bumblebee-indicator".to_string()))
+        );
+    }
+
+    #[test]
+    fn test_parse_widgets_id_line_comment() {
+        let line = "#2182964 adhe.menu.11 #Ignored, not a unique ID";
+        let result = parse_widgets_id_line(line);
+        assert_eq!(result, None);
+    }
+
+    #[test]
+    fn test_parse_widgets_id_line_empty() {
+        let line = "";
+        let result = parse_widgets_id_line(line);
+        assert_eq!(result, None);
+    }
+
+    #[test]
+    fn test_parse_widgets_id_table() {
+        let content = "998890 com.bxabi.bumblebee-indicator\n\
+                       998913 org.kde.plasma.awesomewidget\n\
+                       # Comment line\n\
+                       1155946 com.dschopf.plasma.qalculate\n";
+        let table = Config::parse_widgets_id(content);
+        assert_eq!(table.len(), 3);
+        assert_eq!(table.get("com.bxabi.bumblebee-indicator"), Some(&998890));
+        assert_eq!(table.get("org.kde.plasma.awesomewidget"), Some(&998913));
+        assert_eq!(table.get("com.dschopf.plasma.qalculate"), Some(&1155946));
+    }
+
+    #[test]
+    fn test_default_widgets_id_table_loads() {
+        let config = Config::new();
+        // Verify the embedded file is loaded and contains expected entries
+        assert!(
+            !config.widgets_id_table.is_empty(),
+            "Default widgets_id_table should not be empty"
+        );
         // Check for a few known entries from the widgets-id file
         assert_eq!(
             config.widgets_id_table.get("com.bxabi.bumblebee-indicator"),
@@ -404,4 +1692,16 @@ mod tests {
         let fresh = Config::parse_widgets_id(DEFAULT_WIDGETS_ID);
         assert_eq!(cached, &fresh);
     }
+
+    #[test]
+    fn included_types_restrict_and_exclusions_still_apply() {
+        let config = Config::new()
+            .with_included_types(vec![ComponentType::PlasmaWidget, ComponentType::IconTheme])
+            .with_excluded_types(vec![ComponentType::IconTheme]);
+
+        assert!(config.includes_type(ComponentType::PlasmaWidget));
+        assert!(!config.includes_type(ComponentType::IconTheme));
+        assert!(!config.includes_type(ComponentType::KWinScript));
+        assert!(Config::new().includes_type(ComponentType::KWinScript));
+    }
 }
@@ -0,0 +1,251 @@
+// SPDX-License-Identifier: GPL-3.0-or-later
+
+use std::{
+    path::{Path, PathBuf},
+    process::Command,
+    time::Instant,
+};
+
+use serde::Serialize;
+
+use crate::{Config, Environment, Error, api::ApiClient, installer, paths};
+
+/// Cargo features the library was built with, as listed in `Cargo.toml`.
+const FEATURES: &[(&str, bool)] = &[
+    ("cli", cfg!(feature = "cli")),
+    ("inhibit", cfg!(feature = "inhibit")),
+    ("i18n", cfg!(feature = "i18n")),
+    ("rustls", cfg!(feature = "rustls")),
+    ("native-tls", cfg!(feature = "native-tls")),
+    ("debug", cfg!(feature = "debug")),
+];
+
+/// Environment diagnostics for bug reports, returned by
+/// [`doctor()`](crate::doctor).
+#[derive(Debug, Clone, Serialize)]
+pub struct DoctorReport {
+    /// Version of libplasmoid-updater, see [`VERSION`](crate::VERSION).
+    pub library_version: String,
+    /// Cargo features the library was built with.
+    pub features: Vec<String>,
+    /// Version printed by `plasmashell --version`, if it runs.
+    pub plasma_version: Option<String>,
+    /// Desktop, session and Plasma installation found.
+    pub environment: Environment,
+    /// Round trip of a small KDE Store request in milliseconds, `None` if
+    /// it failed.
+    pub api_latency_ms: Option<u64>,
+    /// Individual checks, in the order they ran.
+    pub checks: Vec<DoctorCheck>,
+}
+
+impl DoctorReport {
+    /// Returns `true` if any check failed outright.
+    pub fn has_failures(&self) -> bool {
+        self.checks.iter().any(|c| c.status == DoctorStatus::Failed)
+    }
+
+    /// Prints the report, one line per check.
+    #[cfg(feature = "cli")]
+    pub fn print(&self) {
+        crate::cli::output::print_doctor_report(self);
+    }
+}
+
+/// One check of a [`DoctorReport`].
+#[derive(Debug, Clone, Serialize)]
+pub struct DoctorCheck {
+    /// What was checked, e.g. `bsdtar` or `store api`.
+    pub name: String,
+    pub status: DoctorStatus,
+    /// What was found, or what is wrong and what it breaks.
+    pub detail: String,
+}
+
+/// Outcome of a [`DoctorCheck`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum DoctorStatus {
+    Ok,
+    /// Works, but something may go wrong, e.g. the store rate limits us.
+    Warning,
+    /// Updates cannot work until this is fixed.
+    Failed,
+}
+
+impl DoctorCheck {
+    fn new(name: &str, status: DoctorStatus, detail: impl Into<String>) -> Self {
+        Self {
+            name: name.to_string(),
+            status,
+            detail: detail.into(),
+        }
+    }
+}
+
+pub(crate) fn run(config: &Config) -> DoctorReport {
+    let plasma_version = plasma_version();
+    let environment = Environment::detect();
+    let mut checks = vec![
+        plasma_check(&environment, plasma_version.as_deref()),
+        desktop_check(&environment),
+    ];
+
+    for (tool, needed_for) in [
+        (
+            "kpackagetool6",
+            "widgets and other packages cannot be installed",
+        ),
+        ("bsdtar", "downloaded packages cannot be extracted"),
+    ] {
+        checks.push(match find_in_path(tool) {
+            Some(path) => DoctorCheck::new(tool, DoctorStatus::Ok, path.display().to_string()),
+            None => DoctorCheck::new(
+                tool,
+                DoctorStatus::Failed,
+                format!("not found in PATH; {needed_for}"),
+            ),
+        });
+    }
+
+    checks.push(if config.system {
+        system_scope_check()
+    } else {
+        writable_check("data directory", &paths::data_home())
+    });
+    let registry = if config.system {
+        paths::system_knewstuff_dir()
+    } else {
+        paths::knewstuff_dir()
+    };
+    checks.push(writable_check("knewstuff registry", &registry));
+
+    let client = ApiClient::from_config(config);
+    let started = Instant::now();
+    let probed = client.and_then(|client| client.probe());
+    let api_latency_ms = probed
+        .is_ok()
+        .then(|| u64::try_from(started.elapsed().as_millis()).unwrap_or(u64::MAX));
+    checks.push(match probed {
+        Ok(()) => DoctorCheck::new(
+            "store api",
+            DoctorStatus::Ok,
+            format!("reachable in {} ms", api_latency_ms.unwrap_or_default()),
+        ),
+        Err(Error::RateLimited) => DoctorCheck::new(
+            "store api",
+            DoctorStatus::Warning,
+            "rate limited; checks may fail until the limit resets",
+        ),
+        Err(e) => DoctorCheck::new("store api", DoctorStatus::Failed, e.to_string()),
+    });
+
+    DoctorReport {
+        library_version: crate::VERSION.to_string(),
+        features: FEATURES
+            .iter()
+            .filter(|(_, enabled)| *enabled)
+            .map(|(name, _)| name.to_string())
+            .collect(),
+        plasma_version,
+        environment,
+        api_latency_ms,
+        checks,
+    }
+}
+
+fn plasma_check(environment: &Environment, version: Option<&str>) -> DoctorCheck {
+    if let Some(problem) = environment.problem() {
+        return DoctorCheck::new("plasma", DoctorStatus::Failed, problem);
+    }
+    match version {
+        Some(version) => DoctorCheck::new("plasma", DoctorStatus::Ok, version),
+        None => DoctorCheck::new(
+            "plasma",
+            DoctorStatus::Warning,
+            "plasmashell not found; restarts after updates will fail",
+        ),
+    }
+}
+
+/// Outside a Plasma session updates still work, but restarting plasmashell
+/// and re-applying themes do not.
+fn desktop_check(environment: &Environment) -> DoctorCheck {
+    let status = if environment.is_kde_session() {
+        DoctorStatus::Ok
+    } else {
+        DoctorStatus::Warning
+    };
+    let mut detail = environment.summary();
+    if status == DoctorStatus::Warning {
+        detail.push_str("; restarts after updates are skipped outside a Plasma session");
+    }
+    DoctorCheck::new("desktop", status, detail)
+}
+
+/// Runs `plasmashell --version`, which prints e.g. `plasmashell 6.3.4`.
+fn plasma_version() -> Option<String> {
+    let output = Command::new("plasmashell").arg("--version").output().ok()?;
+    let stdout = String::from_utf8_lossy(&output.stdout);
+    let version = stdout.trim().trim_start_matches("plasmashell").trim();
+    (output.status.success() && !version.is_empty()).then(|| version.to_string())
+}
+
+fn system_scope_check() -> DoctorCheck {
+    match installer::privilege::immutable_system() {
+        Some(system) => DoctorCheck::new(
+            "system scope",
+            DoctorStatus::Failed,
+            format!("system components are read-only on {system}"),
+        ),
+        None => DoctorCheck::new("system scope", DoctorStatus::Ok, "writable with sudo"),
+    }
+}
+
+/// Checks that a file can be created in `dir`, or in its closest existing
+/// ancestor if it does not exist yet.
+fn writable_check(name: &str, dir: &Path) -> DoctorCheck {
+    let Some(existing) = dir.ancestors().find(|d| d.is_dir()) else {
+        return DoctorCheck::new(name, DoctorStatus::Failed, "no parent directory exists");
+    };
+    match tempfile::tempfile_in(existing) {
+        Ok(_) if existing == dir => {
+            DoctorCheck::new(name, DoctorStatus::Ok, dir.display().to_string())
+        }
+        Ok(_) => DoctorCheck::new(
+            name,
+            DoctorStatus::Ok,
+            format!("{} (will be created)", dir.display()),
+        ),
+        Err(e) => DoctorCheck::new(
+            name,
+            DoctorStatus::Failed,
+            format!("{} is not writable: {e}", existing.display()),
+        ),
+    }
+}
+
+pub(crate) fn find_in_path(program: &str) -> Option<PathBuf> {
+    let path = std::env::var_os("PATH")?;
+    std::env::split_paths(&path)
+        .map(|dir| dir.join(program))
+        .find(|candidate| candidate.is_file())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn writable_check_accepts_missing_dir_under_writable_parent() {
+        let dir = tempfile::tempdir().unwrap();
+
+        let existing = writable_check("data", dir.path());
+        let missing = writable_check("data", &dir.path().join("a/b"));
+
+        assert_eq!(existing.status, DoctorStatus::Ok);
+        assert_eq!(existing.detail, dir.path().display().to_string());
+        assert_eq!(missing.status, DoctorStatus::Ok);
+        assert!(missing.detail.ends_with("(will be created)"));
+    }
+}
@@ -0,0 +1,187 @@
+// SPDX-License-Identifier: GPL-3.0-or-later
+
+use std::{
+    collections::HashMap,
+    fs,
+    path::{Path, PathBuf},
+};
+
+use rayon::iter::{IntoParallelIterator, IntoParallelRefIterator, ParallelIterator};
+use serde::Serialize;
+
+use crate::types::{ComponentType, InstalledComponent};
+
+/// Disk space taken by installed components and by the updater's own data.
+///
+/// Returned by [`disk_usage()`](crate::disk_usage). Sizes are in bytes.
+#[derive(Debug, Clone, Default, Serialize)]
+pub struct DiskUsage {
+    /// Installed components, largest first.
+    pub components: Vec<ComponentUsage>,
+    /// Installed components per type, largest first.
+    pub types: Vec<TypeUsage>,
+    /// Backups taken before updates and removals.
+    pub backups: u64,
+    /// Versions kept for [`downgrade()`](crate::downgrade).
+    pub versions: u64,
+    /// Cached store pages and other cached data, backups excluded.
+    pub cache: u64,
+}
+
+impl DiskUsage {
+    /// Bytes taken by all installed components.
+    pub fn installed(&self) -> u64 {
+        self.types.iter().map(|t| t.bytes).sum()
+    }
+
+    /// Bytes the updater itself can free: backups, kept versions and cache.
+    pub fn reclaimable(&self) -> u64 {
+        self.backups + self.versions + self.cache
+    }
+}
+
+/// Size of one installed component, see [`DiskUsage`].
+#[derive(Debug, Clone, Serialize)]
+pub struct ComponentUsage {
+    pub name: String,
+    pub directory_name: String,
+    pub component_type: ComponentType,
+    pub path: PathBuf,
+    pub bytes: u64,
+}
+
+/// Total size of the installed components of one type, see [`DiskUsage`].
+#[derive(Debug, Clone, Serialize)]
+pub struct TypeUsage {
+    pub component_type: ComponentType,
+    /// Number of installed components of the type.
+    pub components: usize,
+    pub bytes: u64,
+}
+
+/// Bytes `path` occupies on disk, following no symlinks. Subdirectories are
+/// walked in parallel.
+pub(crate) fn dir_size(path: &Path) -> u64 {
+    let Ok(meta) = fs::symlink_metadata(path) else {
+        return 0;
+    };
+    if !meta.is_dir() {
+        return meta.len();
+    }
+    let Ok(entries) = fs::read_dir(path) else {
+        return 0;
+    };
+    let entries: Vec<PathBuf> = entries.flatten().map(|e| e.path()).collect();
+    entries.into_par_iter().map(|p| dir_size(&p)).sum()
+}
+
+/// Measures `components` and the updater's data directories.
+pub(crate) fn measure(
+    components: &[InstalledComponent],
+    backups: &Path,
+    versions: &Path,
+    cache: &Path,
+) -> DiskUsage {
+    let mut usages: Vec<ComponentUsage> = components
+        .par_iter()
+        .map(|c| ComponentUsage {
+            name: c.name.clone(),
+            directory_name: c.directory_name.clone(),
+            component_type: c.component_type,
+            path: c.path.clone(),
+            bytes: dir_size(&c.path),
+        })
+        .collect();
+    usages.sort_by(|a, b| b.bytes.cmp(&a.bytes).then_with(|| a.name.cmp(&b.name)));
+
+    let mut by_type: HashMap<ComponentType, TypeUsage> = HashMap::new();
+    for usage in &usages {
+        let total = by_type
+            .entry(usage.component_type)
+            .or_insert_with(|| TypeUsage {
+                component_type: usage.component_type,
+                components: 0,
+                bytes: 0,
+            });
+        total.components += 1;
+        total.bytes += usage.bytes;
+    }
+    let mut types: Vec<TypeUsage> = by_type.into_values().collect();
+    types.sort_by(|a, b| {
+        b.bytes.cmp(&a.bytes).then_with(|| {
+            a.component_type
+                .to_string()
+                .cmp(&b.component_type.to_string())
+        })
+    });
+
+    // Backups live in the cache directory but are reported on their own.
+    let cache = fs::read_dir(cache)
+        .map(|entries| {
+            entries
+                .flatten()
+                .map(|e| e.path())
+                .filter(|p| p != backups)
+                .map(|p| dir_size(&p))
+                .sum()
+        })
+        .unwrap_or(0);
+
+    DiskUsage {
+        components: usages,
+        types,
+        backups: dir_size(backups),
+        versions: dir_size(versions),
+        cache,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn component(dir: &Path, name: &str, ct: ComponentType, bytes: usize) -> InstalledComponent {
+        let path = dir.join(name);
+        fs::create_dir_all(path.join("contents")).unwrap();
+        fs::write(path.join("contents/main.qml"), vec![b'x'; bytes]).unwrap();
+        InstalledComponent::fixture(name, ct, path)
+    }
+
+    #[test]
+    fn dir_size_sums_nested_files() {
+        let dir = tempfile::tempdir().unwrap();
+        fs::create_dir_all(dir.path().join("a/b")).unwrap();
+        fs::write(dir.path().join("a/one"), [0; 10]).unwrap();
+        fs::write(dir.path().join("a/b/two"), [0; 32]).unwrap();
+
+        assert_eq!(dir_size(dir.path()), 42);
+        assert_eq!(dir_size(&dir.path().join("a/one")), 10);
+        assert_eq!(dir_size(&dir.path().join("missing")), 0);
+    }
+
+    #[test]
+    fn measure_groups_by_type_and_separates_backups_from_cache() {
+        let dir = tempfile::tempdir().unwrap();
+        let components = [
+            component(dir.path(), "clock", ComponentType::PlasmaWidget, 100),
+            component(dir.path(), "weather", ComponentType::PlasmaWidget, 300),
+            component(dir.path(), "nord", ComponentType::GlobalTheme, 250),
+        ];
+        let cache = dir.path().join("cache");
+        let backups = cache.join("backups");
+        fs::create_dir_all(&backups).unwrap();
+        fs::write(backups.join("old"), [0; 50]).unwrap();
+        fs::create_dir_all(cache.join("http")).unwrap();
+        fs::write(cache.join("http/page.json"), [0; 7]).unwrap();
+
+        let usage = measure(&components, &backups, &dir.path().join("versions"), &cache);
+
+        let names: Vec<&str> = usage.components.iter().map(|c| c.name.as_str()).collect();
+        assert_eq!(names, ["weather", "nord", "clock"]);
+        assert_eq!(usage.types[0].component_type, ComponentType::PlasmaWidget);
+        assert_eq!((usage.types[0].components, usage.types[0].bytes), (2, 400));
+        assert_eq!(usage.installed(), 650);
+        assert_eq!((usage.backups, usage.cache, usage.versions), (50, 7, 0));
+        assert_eq!(usage.reclaimable(), 57);
+    }
+}
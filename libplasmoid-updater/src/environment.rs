@@ -0,0 +1,216 @@
+// SPDX-License-Identifier: GPL-3.0-or-later
+
+use std::path::PathBuf;
+
+use serde::Serialize;
+
+use crate::{doctor::find_in_path, paths};
+
+/// What plasmoid-updater runs in, see [`Environment::detect`].
+///
+/// Updates need only a home that Plasma has used, which is why running
+/// over SSH or from a timer outside a desktop session works. The rest is
+/// reported by `doctor` and used to explain what is wrong when no such home
+/// is found.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct Environment {
+    /// Desktops named in `XDG_CURRENT_DESKTOP`, e.g. `["KDE"]` or
+    /// `["ubuntu", "GNOME"]`. Empty outside a desktop session.
+    pub desktops: Vec<String>,
+    pub session: SessionType,
+    /// Running under the Windows Subsystem for Linux.
+    pub wsl: bool,
+    /// Where `plasmashell` was found in `PATH`.
+    pub plasmashell: Option<PathBuf>,
+    /// The user's KNewStuff registry directory.
+    pub knewstuff_dir: PathBuf,
+    /// Whether [`knewstuff_dir`](Self::knewstuff_dir) exists, which Plasma
+    /// creates the first time it runs for the user.
+    pub knewstuff_found: bool,
+}
+
+/// The kind of graphical session, from `XDG_SESSION_TYPE` or the display
+/// variables.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum SessionType {
+    Wayland,
+    X11,
+    /// A text console or SSH login.
+    Tty,
+    Unknown,
+}
+
+impl SessionType {
+    fn as_str(self) -> &'static str {
+        match self {
+            Self::Wayland => "Wayland",
+            Self::X11 => "X11",
+            Self::Tty => "no graphical session",
+            Self::Unknown => "unknown session",
+        }
+    }
+}
+
+impl Environment {
+    /// Inspects the environment variables, `PATH` and the user's data
+    /// directory.
+    pub fn detect() -> Self {
+        let osrelease = std::fs::read_to_string("/proc/sys/kernel/osrelease").ok();
+        let knewstuff_dir = paths::knewstuff_dir();
+        Self {
+            knewstuff_found: knewstuff_dir.exists(),
+            knewstuff_dir,
+            plasmashell: find_in_path("plasmashell"),
+            ..Self::from_vars(|name| std::env::var(name).ok(), osrelease.as_deref())
+        }
+    }
+
+    fn from_vars(var: impl Fn(&str) -> Option<String>, osrelease: Option<&str>) -> Self {
+        let desktops = var("XDG_CURRENT_DESKTOP")
+            .unwrap_or_default()
+            .split(':')
+            .map(str::trim)
+            .filter(|desktop| !desktop.is_empty())
+            .map(str::to_string)
+            .collect();
+        let session = match var("XDG_SESSION_TYPE").as_deref() {
+            Some("wayland") => SessionType::Wayland,
+            Some("x11") => SessionType::X11,
+            Some("tty") => SessionType::Tty,
+            _ if var("WAYLAND_DISPLAY").is_some() => SessionType::Wayland,
+            _ if var("DISPLAY").is_some() => SessionType::X11,
+            _ if var("SSH_CONNECTION").is_some() => SessionType::Tty,
+            _ => SessionType::Unknown,
+        };
+        let wsl = var("WSL_DISTRO_NAME").is_some()
+            || osrelease.is_some_and(|release| release.to_lowercase().contains("microsoft"));
+        Self {
+            desktops,
+            session,
+            wsl,
+            plasmashell: None,
+            knewstuff_dir: PathBuf::new(),
+            knewstuff_found: false,
+        }
+    }
+
+    /// Whether the current session is a Plasma desktop, which distros name
+    /// `KDE` or `plasma` in `XDG_CURRENT_DESKTOP`.
+    pub fn is_kde_session(&self) -> bool {
+        self.desktops.iter().any(|desktop| {
+            desktop.eq_ignore_ascii_case("kde") || desktop.eq_ignore_ascii_case("plasma")
+        })
+    }
+
+    /// Whether components can be managed here: Plasma has used this home.
+    pub fn is_kde(&self) -> bool {
+        self.knewstuff_found
+    }
+
+    /// One line for `doctor`, e.g. `KDE on Wayland, plasmashell found`.
+    pub fn summary(&self) -> String {
+        let desktop = match self.desktops.as_slice() {
+            [] => "no desktop".to_string(),
+            desktops => desktops.join(":"),
+        };
+        let mut summary = format!("{desktop} on {}", self.session.as_str());
+        if self.wsl {
+            summary.push_str(" under WSL");
+        }
+        summary.push_str(match self.plasmashell {
+            Some(_) => ", plasmashell found",
+            None => ", plasmashell not found",
+        });
+        summary
+    }
+
+    /// Why Plasma was not detected and what to do about it, `None` if it was.
+    pub(crate) fn problem(&self) -> Option<String> {
+        if self.is_kde() {
+            return None;
+        }
+        let cause = if self.wsl {
+            "running under WSL, where Plasma does not run by default".to_string()
+        } else if !self.desktops.is_empty() && !self.is_kde_session() {
+            format!(
+                "the desktop is {} and this home has never run Plasma",
+                self.desktops.join(":")
+            )
+        } else if self.plasmashell.is_none() {
+            "plasmashell is not installed".to_string()
+        } else {
+            "Plasma has not run for this user yet; log in to a Plasma session once".to_string()
+        };
+        Some(format!(
+            "{cause} (no KNewStuff directory at {}); pass --force-non-kde to manage this home anyway",
+            self.knewstuff_dir.display()
+        ))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn from(vars: &[(&str, &str)], osrelease: Option<&str>) -> Environment {
+        Environment::from_vars(
+            |name| {
+                vars.iter()
+                    .find(|(var, _)| *var == name)
+                    .map(|(_, value)| value.to_string())
+            },
+            osrelease,
+        )
+    }
+
+    #[test]
+    fn desktop_and_session_come_from_xdg_variables() {
+        let env = from(
+            &[
+                ("XDG_CURRENT_DESKTOP", "KDE"),
+                ("XDG_SESSION_TYPE", "wayland"),
+            ],
+            None,
+        );
+        assert!(env.is_kde_session());
+        assert_eq!(env.session, SessionType::Wayland);
+
+        let env = from(
+            &[("XDG_CURRENT_DESKTOP", "ubuntu:GNOME"), ("DISPLAY", ":0")],
+            None,
+        );
+        assert_eq!(env.desktops, ["ubuntu", "GNOME"]);
+        assert!(!env.is_kde_session());
+        assert_eq!(env.session, SessionType::X11);
+
+        assert!(from(&[("XDG_CURRENT_DESKTOP", "plasma")], None).is_kde_session());
+        assert_eq!(
+            from(&[("SSH_CONNECTION", "10.0.0.2 22")], None).session,
+            SessionType::Tty
+        );
+        assert_eq!(from(&[], None).session, SessionType::Unknown);
+    }
+
+    #[test]
+    fn wsl_is_detected_from_kernel_or_variables() {
+        assert!(from(&[], Some("5.15.153.1-microsoft-standard-WSL2")).wsl);
+        assert!(from(&[("WSL_DISTRO_NAME", "Ubuntu")], None).wsl);
+        assert!(!from(&[], Some("6.8.0-arch1-1")).wsl);
+    }
+
+    #[test]
+    fn missing_plasma_explains_the_likely_cause() {
+        let mut env = from(&[("XDG_CURRENT_DESKTOP", "GNOME")], None);
+        env.plasmashell = Some(PathBuf::from("/usr/bin/plasmashell"));
+        let problem = env.problem().unwrap();
+        assert!(problem.starts_with("the desktop is GNOME"), "{problem}");
+        assert!(problem.ends_with("--force-non-kde to manage this home anyway"));
+
+        env.wsl = true;
+        assert!(env.problem().unwrap().starts_with("running under WSL"));
+
+        env.knewstuff_found = true;
+        assert_eq!(env.problem(), None);
+    }
+}
@@ -1,13 +1,19 @@
 // SPDX-License-Identifier: GPL-3.0-or-later
 
+use std::fmt;
+
+use serde::Serialize;
+
 /// Errors that can occur during plasmoid-updater operations.
 #[derive(thiserror::Error, Debug)]
 pub enum Error {
     #[error("unsupported operating system: {0}")]
     UnsupportedOS(String),
 
-    #[error("KDE Plasma desktop environment not detected")]
-    NotKDE,
+    /// No home Plasma has used was found; the message says why and what
+    /// to do, see [`Environment`](crate::Environment).
+    #[error("KDE Plasma not detected: {0}")]
+    NotKDE(String),
 
     #[error("network request failed: {0}")]
     Network(#[from] reqwest::Error),
@@ -18,6 +24,15 @@ pub enum Error {
     #[error("api returned error status: {0}")]
     ApiError(u16),
 
+    /// The store answered with a server error or a page that is not OCS
+    /// XML, as during maintenance. `retry_hint` is the `Retry-After` delay
+    /// in seconds, if the store sent one.
+    #[error("KDE Store is unavailable (HTTP {status}){}", retry_suffix(*.retry_hint))]
+    StoreUnavailable {
+        status: u16,
+        retry_hint: Option<u64>,
+    },
+
     #[error("failed to parse xml: {0}")]
     XmlParse(String),
 
@@ -33,9 +48,18 @@ pub enum Error {
     #[error("extraction failed: {0}")]
     ExtractionFailed(String),
 
+    #[error("archive rejected as unsafe: {0}")]
+    MaliciousArchive(String),
+
     #[error("installation failed: {0}")]
     InstallFailed(String),
 
+    #[error("installed component failed health check: {0}")]
+    HealthCheckFailed(String),
+
+    #[error("SDDM theme validation failed: {0}")]
+    SddmValidationFailed(String),
+
     #[error("could not resolve content id for: {0}")]
     IdResolutionFailed(String),
 
@@ -48,9 +72,32 @@ pub enum Error {
     #[error("download failed: {0}")]
     DownloadFailed(String),
 
+    #[error("untrusted download host '{host}': {reason}")]
+    UntrustedDownloadHost { host: String, reason: String },
+
+    #[error("signature verification failed: {0}")]
+    SignatureInvalid(String),
+
     #[error("checksum mismatch: expected {expected}, got {actual}")]
     ChecksumMismatch { expected: String, actual: String },
 
+    /// The store serves a different package for a version than the one
+    /// installed before, see [`reinstall()`](crate::reinstall).
+    #[error(
+        "the store package of version {version} changed since it was installed: \
+         expected sha256 {expected}, got {actual}"
+    )]
+    PayloadChanged {
+        version: String,
+        expected: String,
+        actual: String,
+    },
+
+    /// Selected updates would write to the same files; nothing was
+    /// installed. See [`Config::conflict_policy`](crate::Config::conflict_policy).
+    #[error("updates would overwrite each other's files: {}", join_conflicts(.0))]
+    FileConflicts(Vec<crate::FileConflict>),
+
     #[error("metadata not found in package")]
     MetadataNotFound,
 
@@ -68,6 +115,9 @@ pub enum Error {
         restore_error: String,
     },
 
+    #[error("update hook failed: {0}")]
+    HookFailed(String),
+
     #[error("missing required dependency: {0}")]
     MissingDependency(String),
 
@@ -79,20 +129,182 @@ pub enum Error {
 
     #[error("another plasmoid-updater instance is already running")]
     AlreadyRunning,
+
+    #[error(
+        "system components are read-only on {0}; install components per user instead, \
+         or through the system's own package manager"
+    )]
+    ImmutableSystem(String),
+
+    /// `source` annotated with the component and step it failed in, see
+    /// [`Error::context`].
+    #[error("{context}: {source}")]
+    Context {
+        context: ErrorContext,
+        source: Box<Error>,
+    },
+}
+
+/// Step of a component update an [`Error`] happened in.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum Operation {
+    /// Running an update hook.
+    Hook,
+    /// Backing up the installed version.
+    Backup,
+    /// Downloading the package; the target is its URL.
+    Download,
+    /// Unpacking the downloaded package; the target is the archive.
+    Extract,
+    /// Moving files into place or running `kpackagetool6`; the target is
+    /// the install path.
+    Install,
+    /// Checking the installed component, see
+    /// [`Config::with_health_check`](crate::Config::with_health_check).
+    HealthCheck,
+    /// Patching metadata and updating the KNewStuff registry.
+    PostInstall,
+    /// Putting the backup back after a failed install.
+    Restore,
+}
+
+impl fmt::Display for Operation {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(match self {
+            Self::Hook => "hook",
+            Self::Backup => "backup",
+            Self::Download => "download",
+            Self::Extract => "extract",
+            Self::Install => "install",
+            Self::HealthCheck => "health check",
+            Self::PostInstall => "post-install",
+            Self::Restore => "restore",
+        })
+    }
+}
+
+/// Where an [`Error`] happened: the component, the step and the path or URL
+/// it was working on. Any of them may be unknown.
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize)]
+pub struct ErrorContext {
+    /// Display name of the component.
+    pub component: Option<String>,
+    pub operation: Option<Operation>,
+    /// Path or URL the operation was working on.
+    pub target: Option<String>,
+}
+
+impl ErrorContext {
+    pub(crate) fn operation(operation: Operation) -> Self {
+        Self {
+            operation: Some(operation),
+            ..Self::default()
+        }
+    }
+
+    pub(crate) fn component(name: impl Into<String>) -> Self {
+        Self {
+            component: Some(name.into()),
+            ..Self::default()
+        }
+    }
+
+    pub(crate) fn target(mut self, target: impl fmt::Display) -> Self {
+        self.target = Some(target.to_string());
+        self
+    }
+
+    /// Fills the fields left empty with those of `outer`.
+    fn merge(&mut self, outer: Self) {
+        self.component = self.component.take().or(outer.component);
+        self.operation = self.operation.or(outer.operation);
+        self.target = self.target.take().or(outer.target);
+    }
+}
+
+impl fmt::Display for ErrorContext {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let mut parts = Vec::new();
+        if let Some(component) = &self.component {
+            parts.push(component.clone());
+        }
+        if let Some(operation) = self.operation {
+            parts.push(operation.to_string());
+        }
+        if let Some(target) = &self.target {
+            parts.push(format!("({target})"));
+        }
+        f.write_str(&parts.join(" "))
+    }
+}
+
+/// Adds [`ErrorContext`] to the error of a [`Result`](crate::Result).
+pub(crate) trait ResultExt<T> {
+    fn context(self, context: impl FnOnce() -> ErrorContext) -> crate::Result<T>;
+}
+
+impl<T> ResultExt<T> for crate::Result<T> {
+    fn context(self, context: impl FnOnce() -> ErrorContext) -> crate::Result<T> {
+        self.map_err(|e| e.context(context()))
+    }
 }
 
 impl Error {
+    /// Annotates the error with where it happened. Context already attached
+    /// takes precedence, so the innermost step and target are kept while
+    /// outer callers add what the inner ones did not know, like the
+    /// component name.
+    pub fn context(self, context: ErrorContext) -> Self {
+        match self {
+            Self::Context {
+                context: mut inner,
+                source,
+            } => {
+                inner.merge(context);
+                Self::Context {
+                    context: inner,
+                    source,
+                }
+            }
+            source => Self::Context {
+                context,
+                source: Box::new(source),
+            },
+        }
+    }
+
+    /// Returns the component, step and target the error happened in, if
+    /// known.
+    pub fn error_context(&self) -> Option<&ErrorContext> {
+        match self {
+            Self::Context { context, .. } => Some(context),
+            _ => None,
+        }
+    }
+
+    /// Returns the error without its [`ErrorContext`].
+    pub fn root_cause(&self) -> &Error {
+        match self {
+            Self::Context { source, .. } => source.root_cause(),
+            error => error,
+        }
+    }
+
     /// Returns `true` for expected, non-error conditions (e.g., no updates found).
     pub fn is_skippable(&self) -> bool {
         matches!(
-            self,
+            self.root_cause(),
             Self::NoUpdatesAvailable | Self::ComponentNotFound(_) | Self::AlreadyRunning
         )
     }
 
     /// Returns `true` for temporary failures that may succeed on retry.
     pub fn is_transient(&self) -> bool {
-        matches!(self, Self::Network(_) | Self::RateLimited)
+        matches!(
+            self.root_cause(),
+            Self::Network(_) | Self::RateLimited | Self::StoreUnavailable { .. }
+        )
     }
 
     /// Returns `true` for permanent failures that require user intervention.
@@ -101,6 +313,21 @@ impl Error {
     }
 }
 
+fn join_conflicts(conflicts: &[crate::FileConflict]) -> String {
+    conflicts
+        .iter()
+        .map(ToString::to_string)
+        .collect::<Vec<_>>()
+        .join("; ")
+}
+
+fn retry_suffix(retry_hint: Option<u64>) -> String {
+    retry_hint.map_or_else(
+        || ", try again later".to_string(),
+        |secs| format!(", try again in {secs}s"),
+    )
+}
+
 macro_rules! error_ctor {
     ($($name:ident => $variant:ident),* $(,)?) => {
         $(
@@ -115,10 +342,15 @@ impl Error {
     error_ctor!(
         xml_parse => XmlParse,
         extraction => ExtractionFailed,
+        malicious_archive => MaliciousArchive,
         install => InstallFailed,
+        health_check => HealthCheckFailed,
+        sddm_validation => SddmValidationFailed,
         download => DownloadFailed,
+        signature => SignatureInvalid,
         backup => BackupFailed,
         restart => RestartFailed,
+        hook => HookFailed,
     );
 
     pub fn other(msg: impl Into<String>) -> Self {
@@ -165,4 +397,46 @@ mod tests {
         assert!(!err.is_transient());
         assert!(!err.is_skippable());
     }
+
+    #[test]
+    fn context_keeps_innermost_step_and_adds_component() {
+        let err = Error::download("HTTP 404")
+            .context(
+                ErrorContext::operation(Operation::Download).target("https://example.org/a.tar.gz"),
+            )
+            .context(ErrorContext::component("Nord").target("/unused"));
+
+        let context = err.error_context().unwrap();
+        assert_eq!(context.component.as_deref(), Some("Nord"));
+        assert_eq!(context.operation, Some(Operation::Download));
+        assert_eq!(
+            context.target.as_deref(),
+            Some("https://example.org/a.tar.gz")
+        );
+        assert!(matches!(err.root_cause(), Error::DownloadFailed(_)));
+        assert_eq!(
+            err.to_string(),
+            "Nord download (https://example.org/a.tar.gz): download failed: HTTP 404"
+        );
+    }
+
+    #[test]
+    fn store_unavailable_is_transient_and_mentions_retry_hint() {
+        let err = Error::StoreUnavailable {
+            status: 503,
+            retry_hint: Some(120),
+        };
+        assert!(err.is_transient());
+        assert_eq!(
+            err.to_string(),
+            "KDE Store is unavailable (HTTP 503), try again in 120s"
+        );
+    }
+
+    #[test]
+    fn context_does_not_change_classification() {
+        let err = Error::RateLimited.context(ErrorContext::component("Nord"));
+        assert!(err.is_transient());
+        assert!(!err.is_fatal());
+    }
 }
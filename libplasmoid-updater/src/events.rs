@@ -0,0 +1,210 @@
+// SPDX-License-Identifier: GPL-3.0-or-later
+
+use std::{io::Write, sync::Arc};
+
+use parking_lot::Mutex;
+use serde::Serialize;
+
+use crate::{
+    Operation,
+    api::ApiStats,
+    types::{AvailableUpdate, ComponentType, Diagnostic, HeldUpdate, InstalledComponent},
+};
+
+/// A progress event emitted while checking for or applying updates.
+///
+/// Serializes as a flat JSON object tagged by `event`, e.g.
+/// `{"event":"component-succeeded","name":"Clock"}`.
+#[derive(Debug, Clone, Serialize)]
+#[serde(tag = "event", rename_all = "kebab-case")]
+pub enum Event {
+    /// Discovery of installed components and the store lookup have started.
+    CheckStarted { system: bool },
+    /// An update is available for an installed component.
+    UpdateFound {
+        name: String,
+        directory_name: String,
+        component_type: ComponentType,
+        content_id: u64,
+        installed_version: String,
+        latest_version: String,
+    },
+    /// A component being updated moved to the next install stage.
+    DownloadProgress { name: String, stage: InstallStage },
+    /// A component was updated successfully.
+    ComponentSucceeded { name: String },
+    /// A component failed to update.
+    ComponentFailed {
+        name: String,
+        error: String,
+        /// Step the update failed in, if known.
+        operation: Option<Operation>,
+    },
+    /// The run finished. Emitted once, last.
+    Summary {
+        available: usize,
+        succeeded: usize,
+        failed: usize,
+        unresolved: usize,
+        /// How heavy the run was on the store and download hosts.
+        api: ApiStats,
+    },
+}
+
+impl Event {
+    pub(crate) fn update_found(update: &AvailableUpdate) -> Self {
+        Self::UpdateFound {
+            name: update.installed.name.clone(),
+            directory_name: update.installed.directory_name.clone(),
+            component_type: update.installed.component_type,
+            content_id: update.content_id,
+            installed_version: update.installed.version.clone(),
+            latest_version: update.latest_version.clone(),
+        }
+    }
+}
+
+/// Progress of [`check_updates_streaming()`](crate::check_updates_streaming),
+/// reported as each component is evaluated.
+///
+/// [`Discovered`](Self::Discovered) comes first; every component it lists is
+/// then reported by exactly one of the other variants, in no particular order.
+/// Updates are reported the way they end up in the final
+/// [`CheckResult`](crate::CheckResult): with restart overrides and download
+/// variants applied, and held back if an advisory lists them.
+#[derive(Debug, Clone)]
+pub enum CheckEvent {
+    /// The installed components that are about to be checked.
+    Discovered(Vec<InstalledComponent>),
+    /// An update is available.
+    UpdateAvailable(Box<AvailableUpdate>),
+    /// An update is available but held back, see
+    /// [`Config::advisories_url`](crate::Config::advisories_url).
+    Held(Box<HeldUpdate>),
+    /// The component is at the latest store version.
+    UpToDate(InstalledComponent),
+    /// The component could not be matched to a store entry.
+    Unresolved(Diagnostic),
+    /// The component was matched but could not be checked.
+    CheckFailed(Diagnostic),
+}
+
+/// Stage a component update has reached.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum InstallStage {
+    /// The backup is done and the archive is being downloaded.
+    Downloading,
+    /// The download is done and the archive is being extracted.
+    Extracting,
+    /// The files are being moved into place.
+    Installing,
+}
+
+impl InstallStage {
+    /// Maps the installer's numeric reporter stages onto named stages.
+    pub(crate) fn from_reporter(stage: u8) -> Option<Self> {
+        match stage {
+            1 => Some(Self::Downloading),
+            2 => Some(Self::Extracting),
+            3 => Some(Self::Installing),
+            _ => None,
+        }
+    }
+}
+
+/// Receives [`Event`]s as a run progresses.
+///
+/// Events may be delivered from installer worker threads, so the callback must
+/// be thread-safe. Setting a sink via
+/// [`Config::with_event_sink`](crate::Config::with_event_sink) also turns off the
+/// built-in terminal output (spinner, tables, progress display and prompts),
+/// since the sink is expected to drive its own UI.
+#[derive(Clone)]
+pub struct EventSink(Arc<dyn Fn(&Event) + Send + Sync>);
+
+impl EventSink {
+    /// Creates a sink that calls `callback` for every event.
+    ///
+    /// # Example
+    ///
+    /// ```rust
+    /// use libplasmoid_updater::{Config, Event, EventSink};
+    ///
+    /// let sink = EventSink::new(|event: &Event| {
+    ///     if let Event::ComponentFailed { name, error, .. } = event {
+    ///         eprintln!("{name}: {error}");
+    ///     }
+    /// });
+    /// let config = Config::new().with_event_sink(sink);
+    /// ```
+    pub fn new(callback: impl Fn(&Event) + Send + Sync + 'static) -> Self {
+        Self(Arc::new(callback))
+    }
+
+    /// Creates a sink writing each event as one line of JSON to `writer`.
+    ///
+    /// Each line is flushed immediately so readers see events as they happen.
+    pub fn json_lines(writer: impl Write + Send + 'static) -> Self {
+        let writer = Mutex::new(writer);
+        Self::new(move |event| {
+            let Ok(line) = serde_json::to_string(event) else {
+                return;
+            };
+            let mut writer = writer.lock();
+            if let Err(e) = writeln!(writer, "{line}").and_then(|()| writer.flush()) {
+                log::warn!(target: "events", "failed to write event: {e}");
+            }
+        })
+    }
+
+    pub(crate) fn emit(&self, event: Event) {
+        (self.0)(&event);
+    }
+}
+
+impl std::fmt::Debug for EventSink {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.write_str("EventSink")
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[derive(Clone, Default)]
+    struct SharedBuf(Arc<Mutex<Vec<u8>>>);
+
+    impl Write for SharedBuf {
+        fn write(&mut self, buf: &[u8]) -> std::io::Result<usize> {
+            self.0.lock().write(buf)
+        }
+
+        fn flush(&mut self) -> std::io::Result<()> {
+            Ok(())
+        }
+    }
+
+    #[test]
+    fn json_lines_writes_one_tagged_object_per_line() {
+        let buf = SharedBuf::default();
+        let sink = EventSink::json_lines(buf.clone());
+
+        sink.emit(Event::CheckStarted { system: false });
+        sink.emit(Event::DownloadProgress {
+            name: "Clock".to_string(),
+            stage: InstallStage::Extracting,
+        });
+
+        let output = String::from_utf8(buf.0.lock().clone()).unwrap();
+        let lines: Vec<&str> = output.lines().collect();
+        assert_eq!(
+            lines,
+            [
+                r#"{"event":"check-started","system":false}"#,
+                r#"{"event":"download-progress","name":"Clock","stage":"extracting"}"#,
+            ]
+        );
+    }
+}
@@ -0,0 +1,233 @@
+// SPDX-License-Identifier: GPL-3.0-or-later
+
+use std::{
+    fs,
+    path::{Path, PathBuf},
+    time::Duration,
+};
+
+use serde::{Deserialize, Serialize};
+
+use crate::{
+    ApiStats, OutcomeStatus, Result, UpdateResult,
+    types::{AvailableUpdate, ComponentType, RestartAction},
+};
+
+/// Runs kept in the history; older ones are deleted.
+const MAX_RUNS: usize = 50;
+
+/// One [`update()`](crate::update) run as kept in the history, see
+/// [`last_run()`](crate::last_run).
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct RunRecord {
+    /// When the run started (RFC 3339).
+    pub started_at: String,
+    /// Wall time of the whole run, the check included, in milliseconds.
+    pub duration_ms: u64,
+    /// `true` for a run on system-wide components.
+    pub system: bool,
+    /// Every component the run tried to install, in selection order.
+    pub components: Vec<RunComponent>,
+    /// Components left alone, e.g. held back or owned by a distro package.
+    pub skipped: Vec<String>,
+    /// Components rolled back after plasmashell crashed.
+    pub rolled_back: Vec<String>,
+    /// Restart actions that ran after the updates, in order.
+    pub restart_actions: Vec<RestartAction>,
+    pub api_stats: ApiStats,
+}
+
+/// A component in a [`RunRecord`].
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct RunComponent {
+    pub name: String,
+    pub component_type: ComponentType,
+    /// Version installed before the run.
+    pub from_version: String,
+    /// Version the run installed, or tried to.
+    pub to_version: String,
+    pub status: OutcomeStatus,
+    pub duration_ms: u64,
+    /// Size of the downloaded package, `None` unless it installed.
+    pub bytes: Option<u64>,
+    /// SHA-256 of the downloaded package, `None` unless it installed.
+    #[serde(default)]
+    pub sha256: Option<String>,
+    /// Why the update failed, for [`OutcomeStatus::Failed`].
+    pub error: Option<String>,
+}
+
+impl RunRecord {
+    /// Builds the record of a run that started at `started_at` and took
+    /// `elapsed`, installing `selected`.
+    pub(crate) fn new(
+        started_at: String,
+        elapsed: Duration,
+        system: bool,
+        selected: &[&AvailableUpdate],
+        result: &UpdateResult,
+    ) -> Self {
+        let components = result
+            .outcomes
+            .iter()
+            .filter_map(|outcome| {
+                let update = selected.iter().find(|u| u.installed.name == outcome.name)?;
+                Some(RunComponent {
+                    name: outcome.name.clone(),
+                    component_type: update.installed.component_type,
+                    from_version: update.installed.version.clone(),
+                    to_version: update.latest_version.clone(),
+                    status: outcome.status,
+                    duration_ms: millis(outcome.duration),
+                    bytes: outcome.bytes,
+                    sha256: outcome.sha256.clone(),
+                    error: outcome.error.clone(),
+                })
+            })
+            .collect();
+
+        Self {
+            started_at,
+            duration_ms: millis(elapsed),
+            system,
+            components,
+            skipped: result.skipped.clone(),
+            rolled_back: result.rolled_back.clone(),
+            restart_actions: result.restart_actions.clone(),
+            api_stats: result.api_stats,
+        }
+    }
+
+    /// Returns the bytes downloaded across all components.
+    pub fn total_bytes(&self) -> u64 {
+        self.components.iter().filter_map(|c| c.bytes).sum()
+    }
+
+    /// Returns the components with the given status.
+    pub fn with_status(&self, status: OutcomeStatus) -> impl Iterator<Item = &RunComponent> {
+        self.components.iter().filter(move |c| c.status == status)
+    }
+
+    /// Renders the run as a self-contained HTML page, for saving or sharing.
+    pub fn to_html(&self) -> String {
+        crate::report::run_html(self)
+    }
+
+    /// Prints the run as tables for the terminal.
+    #[cfg(feature = "cli")]
+    pub fn print(&self) {
+        crate::cli::output::print_run_record(self);
+    }
+}
+
+fn millis(duration: Duration) -> u64 {
+    u64::try_from(duration.as_millis()).unwrap_or(u64::MAX)
+}
+
+/// Returns the directory holding the history.
+///
+/// User runs are kept under `$XDG_STATE_HOME/plasmoid-updater/history`,
+/// system runs next to the system registry.
+fn history_dir(system: bool) -> PathBuf {
+    if system {
+        crate::paths::system_state_dir("history")
+    } else {
+        crate::paths::state_dir("history")
+    }
+}
+
+/// Adds `record` to the history, deleting the oldest runs beyond
+/// [`MAX_RUNS`]. Failures are logged; a run is never failed for them.
+pub(crate) fn record(record: &RunRecord) {
+    let name = chrono::Local::now()
+        .format("%Y-%m-%dT%H-%M-%S%.3f")
+        .to_string();
+    if let Err(e) = record_in(&history_dir(record.system), &name, record) {
+        log::warn!(target: "history", "failed to record the run: {e}");
+    }
+}
+
+fn record_in(dir: &Path, name: &str, record: &RunRecord) -> Result<()> {
+    fs::create_dir_all(dir)?;
+    fs::write(
+        dir.join(format!("{name}.json")),
+        serde_json::to_vec_pretty(record)?,
+    )?;
+
+    let runs = run_files(dir);
+    for old in runs.iter().skip(MAX_RUNS) {
+        let _ = fs::remove_file(old);
+    }
+    Ok(())
+}
+
+/// Returns the most recent run, if any was recorded.
+pub(crate) fn last_run(system: bool) -> Option<RunRecord> {
+    last_run_in(&history_dir(system))
+}
+
+fn last_run_in(dir: &Path) -> Option<RunRecord> {
+    run_files(dir).into_iter().find_map(|path| {
+        let content = fs::read(&path).ok()?;
+        serde_json::from_slice(&content)
+            .inspect_err(|e| log::debug!(target: "history", "skipping {}: {e}", path.display()))
+            .ok()
+    })
+}
+
+/// Run files in `dir`, newest first. Their names sort by start time.
+fn run_files(dir: &Path) -> Vec<PathBuf> {
+    let Ok(entries) = fs::read_dir(dir) else {
+        return Vec::new();
+    };
+    let mut files: Vec<PathBuf> = entries
+        .flatten()
+        .map(|e| e.path())
+        .filter(|p| p.extension().is_some_and(|ext| ext == "json"))
+        .collect();
+    files.sort_by(|a, b| b.cmp(a));
+    files
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn run(name: &str) -> RunRecord {
+        RunRecord {
+            started_at: "2025-01-01T00:00:00+00:00".to_string(),
+            duration_ms: 1200,
+            system: false,
+            components: vec![RunComponent {
+                name: name.to_string(),
+                component_type: ComponentType::PlasmaWidget,
+                from_version: "1.0".to_string(),
+                to_version: "2.0".to_string(),
+                status: OutcomeStatus::Succeeded,
+                duration_ms: 800,
+                bytes: Some(2048),
+                sha256: None,
+                error: None,
+            }],
+            skipped: Vec::new(),
+            rolled_back: Vec::new(),
+            restart_actions: vec![RestartAction::PlasmashellRestart],
+            api_stats: ApiStats::default(),
+        }
+    }
+
+    #[test]
+    fn last_run_is_the_newest_and_old_runs_are_pruned() {
+        let dir = tempfile::tempdir().unwrap();
+        assert!(last_run_in(dir.path()).is_none());
+
+        for i in 0..=MAX_RUNS {
+            record_in(dir.path(), &format!("{i:04}"), &run(&format!("run {i}"))).unwrap();
+        }
+        fs::write(dir.path().join("9999-broken.json"), "{").unwrap();
+
+        let last = last_run_in(dir.path()).unwrap();
+        assert_eq!(last.components[0].name, format!("run {MAX_RUNS}"));
+        assert_eq!(run_files(dir.path()).len(), MAX_RUNS + 1);
+    }
+}
@@ -0,0 +1,207 @@
+// SPDX-License-Identifier: GPL-3.0-or-later
+
+use std::process::{Command, Stdio};
+
+use crate::{Error, Result, types::AvailableUpdate};
+
+/// Shell commands run around each component update.
+///
+/// Each command runs with `sh -c` and gets the update in its environment:
+/// `PLASMOID_NAME`, `PLASMOID_DIRECTORY`, `PLASMOID_TYPE`, `PLASMOID_PATH`,
+/// `PLASMOID_CONTENT_ID`, `OLD_VERSION` and `NEW_VERSION`, plus `ERROR` for
+/// [`on_failure`](Self::with_on_failure). Components update in parallel, so hooks
+/// for different components may run at the same time. Output is logged.
+///
+/// # Example
+///
+/// ```rust
+/// use libplasmoid_updater::{Config, Hooks};
+///
+/// let hooks = Hooks::new()
+///     .with_pre_update("git -C ~ commit -qam \"before $PLASMOID_NAME $NEW_VERSION\"")
+///     .with_on_failure("notify-send \"$PLASMOID_NAME failed: $ERROR\"");
+/// let config = Config::new().with_hooks(hooks);
+/// ```
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct Hooks {
+    /// Runs before the component is backed up; a non-zero exit skips the
+    /// update and fails it.
+    pub pre_update: Option<String>,
+    /// Runs after the component was installed.
+    pub post_update: Option<String>,
+    /// Runs after an update failed and the component was restored.
+    pub on_failure: Option<String>,
+}
+
+impl Hooks {
+    /// Creates an empty set of hooks.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Sets the command run before each update.
+    ///
+    /// # Example
+    ///
+    /// ```rust
+    /// use libplasmoid_updater::Hooks;
+    ///
+    /// let hooks = Hooks::new().with_pre_update("echo $PLASMOID_NAME");
+    /// assert_eq!(hooks.pre_update.as_deref(), Some("echo $PLASMOID_NAME"));
+    /// ```
+    pub fn with_pre_update(mut self, command: impl Into<String>) -> Self {
+        self.pre_update = Some(command.into());
+        self
+    }
+
+    /// Sets the command run after each successful update.
+    ///
+    /// # Example
+    ///
+    /// ```rust
+    /// use libplasmoid_updater::Hooks;
+    ///
+    /// let hooks = Hooks::new().with_post_update("echo $NEW_VERSION");
+    /// assert!(hooks.post_update.is_some());
+    /// ```
+    pub fn with_post_update(mut self, command: impl Into<String>) -> Self {
+        self.post_update = Some(command.into());
+        self
+    }
+
+    /// Sets the command run after each failed update.
+    ///
+    /// # Example
+    ///
+    /// ```rust
+    /// use libplasmoid_updater::Hooks;
+    ///
+    /// let hooks = Hooks::new().with_on_failure("echo $ERROR");
+    /// assert!(hooks.on_failure.is_some());
+    /// ```
+    pub fn with_on_failure(mut self, command: impl Into<String>) -> Self {
+        self.on_failure = Some(command.into());
+        self
+    }
+
+    /// Runs [`pre_update`](Self::pre_update), failing if it exits non-zero.
+    pub(crate) fn pre_update(&self, update: &AvailableUpdate) -> Result<()> {
+        let Some(command) = &self.pre_update else {
+            return Ok(());
+        };
+        run("pre_update", command, update, None)
+    }
+
+    /// Runs [`post_update`](Self::post_update); failures are only logged.
+    pub(crate) fn post_update(&self, update: &AvailableUpdate) {
+        if let Some(command) = &self.post_update
+            && let Err(e) = run("post_update", command, update, None)
+        {
+            log::warn!(target: "hooks", "{e}");
+        }
+    }
+
+    /// Runs [`on_failure`](Self::on_failure); failures are only logged.
+    pub(crate) fn on_failure(&self, update: &AvailableUpdate, error: &Error) {
+        if let Some(command) = &self.on_failure
+            && let Err(e) = run("on_failure", command, update, Some(error))
+        {
+            log::warn!(target: "hooks", "{e}");
+        }
+    }
+}
+
+fn run(hook: &str, command: &str, update: &AvailableUpdate, error: Option<&Error>) -> Result<()> {
+    let component = &update.installed;
+    log::debug!(target: "hooks", "running {hook} for {}", component.name);
+
+    let mut cmd = Command::new("sh");
+    cmd.arg("-c")
+        .arg(command)
+        .env("PLASMOID_NAME", &component.name)
+        .env("PLASMOID_DIRECTORY", &component.directory_name)
+        .env("PLASMOID_TYPE", component.component_type.to_string())
+        .env("PLASMOID_PATH", &component.path)
+        .env("PLASMOID_CONTENT_ID", update.content_id.to_string())
+        .env("OLD_VERSION", &component.version)
+        .env("NEW_VERSION", &update.latest_version)
+        .stdin(Stdio::null());
+    if let Some(error) = error {
+        cmd.env("ERROR", error.to_string());
+    }
+
+    let output = cmd
+        .output()
+        .map_err(|e| Error::hook(format!("{hook} for {}: {e}", component.name)))?;
+    for line in String::from_utf8_lossy(&output.stdout)
+        .lines()
+        .chain(String::from_utf8_lossy(&output.stderr).lines())
+    {
+        log::info!(target: "hooks", "{hook}: {line}");
+    }
+
+    if !output.status.success() {
+        return Err(Error::hook(format!(
+            "{hook} for {} exited with {}",
+            component.name, output.status
+        )));
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::types::{ComponentType, InstalledComponent};
+
+    fn update() -> AvailableUpdate {
+        let component = InstalledComponent {
+            directory_name: "org.example.clock".to_string(),
+            ..InstalledComponent::fixture(
+                "Clock",
+                ComponentType::PlasmaWidget,
+                "/tmp/org.example.clock",
+            )
+        };
+        AvailableUpdate::builder(
+            component,
+            1000,
+            "2.0".to_string(),
+            String::new(),
+            String::new(),
+        )
+        .build()
+    }
+
+    #[test]
+    fn hooks_see_the_update_in_their_environment() {
+        let dir = tempfile::tempdir().unwrap();
+        let out = dir.path().join("out");
+        let hooks = Hooks::new().with_on_failure(format!(
+            "echo \"$PLASMOID_NAME $OLD_VERSION $NEW_VERSION $PLASMOID_CONTENT_ID $ERROR\" > {}",
+            out.display()
+        ));
+
+        hooks.on_failure(&update(), &Error::other("boom"));
+
+        assert_eq!(
+            std::fs::read_to_string(out).unwrap(),
+            "Clock 1.0 2.0 1000 boom\n"
+        );
+    }
+
+    #[test]
+    fn failing_pre_update_hook_is_an_error() {
+        assert!(Hooks::new().pre_update(&update()).is_ok());
+        assert!(
+            Hooks::new()
+                .with_pre_update("true")
+                .pre_update(&update())
+                .is_ok()
+        );
+        assert!(matches!(
+            Hooks::new().with_pre_update("exit 3").pre_update(&update()),
+            Err(Error::HookFailed(_))
+        ));
+    }
+}
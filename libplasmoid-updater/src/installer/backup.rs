@@ -12,9 +12,21 @@ use crate::{
 
 const MAX_BACKUPS_PER_TYPE: usize = 5;
 
-/// Returns the base backup directory.
-fn backup_base_dir() -> PathBuf {
-    crate::paths::cache_home().join("plasmoid-updater/backups")
+/// Backup subdirectory holding the Plasma layout, next to the type ones.
+const LAYOUT_SUBDIR: &str = "layout";
+
+/// Config files plasmashell keeps the desktop and panel layout in.
+const LAYOUT_FILES: [&str; 2] = [crate::paths::APPLETSRC, "plasmashellrc"];
+
+/// Returns the base backup directory, `$XDG_STATE_HOME/plasmoid-updater/backups`.
+///
+/// Earlier releases kept backups under `~/.cache`; the first call moves an
+/// existing directory over.
+pub(crate) fn backup_base_dir() -> PathBuf {
+    crate::paths::adopt_legacy(
+        &crate::paths::state_dir("backups"),
+        &crate::paths::cache_home().join("plasmoid-updater/backups"),
+    )
 }
 
 /// Generates a timestamp string for backup directories.
@@ -70,6 +82,82 @@ pub(crate) fn backup_component(component: &InstalledComponent) -> Result<Option<
     Ok(Some(backup_path))
 }
 
+/// Returns the most recent backup of `component`, if any.
+pub(crate) fn latest_backup(component: &InstalledComponent) -> Option<PathBuf> {
+    backups_in(&backup_base_dir(), component).into_iter().next()
+}
+
+/// Returns the backups of `component`, newest first.
+pub(crate) fn backups(component: &InstalledComponent) -> Vec<PathBuf> {
+    backups_in(&backup_base_dir(), component)
+}
+
+fn backups_in(base: &Path, component: &InstalledComponent) -> Vec<PathBuf> {
+    let Ok(entries) = fs::read_dir(base) else {
+        return Vec::new();
+    };
+    let mut timestamps: Vec<PathBuf> = entries.flatten().map(|e| e.path()).collect();
+    // Timestamp names sort chronologically.
+    timestamps.sort();
+
+    timestamps
+        .into_iter()
+        .rev()
+        .map(|dir| {
+            dir.join(component.component_type.backup_subdir())
+                .join(&component.directory_name)
+        })
+        .filter(|path| path.exists())
+        .collect()
+}
+
+/// Copies the user's Plasma layout files into a new backup. Returns the
+/// backup directory, or `None` if there is no layout to back up.
+pub(crate) fn backup_layout() -> Result<Option<PathBuf>> {
+    backup_layout_in(&crate::paths::config_home(), &backup_base_dir())
+}
+
+fn backup_layout_in(config_home: &Path, base: &Path) -> Result<Option<PathBuf>> {
+    let files: Vec<PathBuf> = LAYOUT_FILES
+        .iter()
+        .map(|name| config_home.join(name))
+        .filter(|path| path.is_file())
+        .collect();
+    if files.is_empty() {
+        return Ok(None);
+    }
+
+    let backup_dir = base.join(timestamp()).join(LAYOUT_SUBDIR);
+    fs::create_dir_all(&backup_dir).map_err(|e| Error::backup(format!("create dir: {e}")))?;
+    for file in &files {
+        let name = file.file_name().unwrap_or_default();
+        fs::copy(file, backup_dir.join(name))
+            .map_err(|e| Error::backup(format!("copy {}: {e}", file.display())))?;
+    }
+
+    cleanup_old_backups_in(base, LAYOUT_SUBDIR, MAX_BACKUPS_PER_TYPE);
+    Ok(Some(backup_dir))
+}
+
+/// Puts the layout files saved by [`backup_layout`] back in place.
+/// plasmashell must not be running, or it overwrites them when it quits.
+pub(crate) fn restore_layout(backup_dir: &Path) -> Result<()> {
+    restore_layout_in(backup_dir, &crate::paths::config_home())
+}
+
+fn restore_layout_in(backup_dir: &Path, config_home: &Path) -> Result<()> {
+    use super::install::atomic_install_file;
+
+    for name in LAYOUT_FILES {
+        let backup = backup_dir.join(name);
+        if backup.is_file() {
+            atomic_install_file(&backup, &config_home.join(name))
+                .map_err(|e| Error::backup(format!("restore {name}: {e}")))?;
+        }
+    }
+    Ok(())
+}
+
 /// Restores a component from backup atomically.
 ///
 /// Uses `atomic_install_file` / `atomic_install_dir` so the original path is
@@ -166,6 +254,29 @@ mod tests {
     use super::*;
     use crate::types::ComponentType;
 
+    #[test]
+    fn layout_backup_round_trips() {
+        let dir = tempfile::tempdir().unwrap();
+        let config_home = dir.path().join("config");
+        let base = dir.path().join("backups");
+        assert!(backup_layout_in(&config_home, &base).unwrap().is_none());
+
+        fs::create_dir_all(&config_home).unwrap();
+        let appletsrc = config_home.join(crate::paths::APPLETSRC);
+        fs::write(&appletsrc, "[Containments][1]\nplugin=org.kde.panel\n").unwrap();
+        let backup = backup_layout_in(&config_home, &base).unwrap().unwrap();
+        fs::write(&appletsrc, "scrambled").unwrap();
+
+        restore_layout_in(&backup, &config_home).unwrap();
+
+        assert!(backup.ends_with(LAYOUT_SUBDIR));
+        assert_eq!(
+            fs::read_to_string(&appletsrc).unwrap(),
+            "[Containments][1]\nplugin=org.kde.panel\n"
+        );
+        assert!(!config_home.join("plasmashellrc").exists());
+    }
+
     #[test]
     fn cleanup_old_backups_keeps_recent() {
         let base = tempfile::tempdir().unwrap();
@@ -300,4 +411,27 @@ mod tests {
             .count();
         assert_eq!(count, 3);
     }
+
+    #[test]
+    fn backups_lists_component_backups_newest_first() {
+        let base = tempfile::tempdir().unwrap();
+        let subdir = ComponentType::PlasmaWidget.backup_subdir();
+        for (ts, name) in [
+            ("2024-01-01T00-00-00", "org.example.clock"),
+            ("2024-01-02T00-00-00", "org.example.clock"),
+            ("2024-01-03T00-00-00", "org.example.other"),
+        ] {
+            std::fs::create_dir_all(base.path().join(ts).join(subdir).join(name)).unwrap();
+        }
+        let component = InstalledComponent {
+            directory_name: "org.example.clock".to_string(),
+            ..InstalledComponent::fixture("Clock", ComponentType::PlasmaWidget, "")
+        };
+
+        let backup = |ts: &str| base.path().join(ts).join(subdir).join("org.example.clock");
+        assert_eq!(
+            backups_in(base.path(), &component),
+            [backup("2024-01-02T00-00-00"), backup("2024-01-01T00-00-00")]
+        );
+    }
 }
@@ -0,0 +1,179 @@
+// SPDX-License-Identifier: GPL-3.0-or-later
+
+use std::{fs, path::Path};
+
+use crate::{
+    paths,
+    types::{ComponentType, InstalledComponent},
+};
+
+/// Removes the cached renderings of `component` from `~/.cache`, so the
+/// updated theme is drawn from its new files instead of a mix of old and
+/// new elements, see [`Config::clear_theme_caches`](crate::Config::clear_theme_caches).
+/// Returns the number of files removed.
+pub(super) fn invalidate(component: &InstalledComponent) -> usize {
+    invalidate_in(&paths::cache_home(), component)
+}
+
+/// Cache files holding renderings of `component`.
+enum Stale {
+    /// Every file whose name starts with this.
+    Prefix(&'static str),
+    /// The caches of the Plasma style with this name.
+    Style(String),
+}
+
+impl Stale {
+    fn matches(&self, file_name: &str) -> bool {
+        match self {
+            Self::Prefix(prefix) => file_name.starts_with(prefix),
+            // `plasma-svgelements-<name>_v6.0`, `plasma_theme_<name>.kcache`;
+            // stopping at `_` or `.` keeps `breeze` from matching `breeze-dark`.
+            Self::Style(name) => ["plasma-svgelements-", "plasma_theme_"]
+                .iter()
+                .filter_map(|kind| file_name.strip_prefix(kind)?.strip_prefix(name.as_str()))
+                .any(|rest| rest.is_empty() || rest.starts_with(['_', '.'])),
+        }
+    }
+}
+
+fn stale_caches(component: &InstalledComponent) -> Vec<Stale> {
+    match component.component_type {
+        // Aurorae draws through KSvg, whose element cache is keyed by the
+        // Plasma style rather than the decoration, so all of them go.
+        ComponentType::AuroraeDecoration => vec![Stale::Prefix("plasma-svgelements")],
+        ComponentType::PlasmaStyle => vec![Stale::Style(component.directory_name.clone())],
+        ComponentType::GlobalTheme => bundled_style(&component.path)
+            .into_iter()
+            .chain([component.directory_name.clone()])
+            .map(Stale::Style)
+            .collect(),
+        _ => Vec::new(),
+    }
+}
+
+/// Reads the Plasma style a global theme applies from the `[plasmarc][Theme]`
+/// group of its `contents/defaults`.
+fn bundled_style(package: &Path) -> Option<String> {
+    let defaults = fs::read_to_string(package.join("contents/defaults")).ok()?;
+    let mut in_group = false;
+    for line in defaults.lines().map(str::trim) {
+        if line.starts_with('[') {
+            in_group = line == "[plasmarc][Theme]";
+        } else if in_group
+            && let Some((key, value)) = line.split_once('=')
+            && key.trim() == "name"
+            && !value.trim().is_empty()
+        {
+            return Some(value.trim().to_string());
+        }
+    }
+    None
+}
+
+fn invalidate_in(cache_dir: &Path, component: &InstalledComponent) -> usize {
+    let stale = stale_caches(component);
+    if stale.is_empty() {
+        return 0;
+    }
+    let Ok(entries) = fs::read_dir(cache_dir) else {
+        return 0;
+    };
+
+    let mut removed = 0;
+    for entry in entries.flatten() {
+        let path = entry.path();
+        let is_stale = path
+            .file_name()
+            .and_then(|n| n.to_str())
+            .is_some_and(|name| stale.iter().any(|s| s.matches(name)));
+        if !is_stale || !path.is_file() {
+            continue;
+        }
+        match fs::remove_file(&path) {
+            Ok(()) => removed += 1,
+            Err(e) => log::debug!(target: "cache", "failed to remove {}: {e}", path.display()),
+        }
+    }
+    removed
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn component(component_type: ComponentType) -> InstalledComponent {
+        themed(component_type, "Breezemite", Path::new("/tmp/Breezemite"))
+    }
+
+    fn themed(component_type: ComponentType, name: &str, path: &Path) -> InstalledComponent {
+        InstalledComponent::fixture(name, component_type, path)
+    }
+
+    #[test]
+    fn aurorae_update_clears_svg_element_caches_only() {
+        let cache = tempfile::tempdir().unwrap();
+        for name in [
+            "plasma-svgelements-default",
+            "plasma-svgelements-breeze-dark_v6.0",
+            "icon-cache.kcache",
+        ] {
+            fs::write(cache.path().join(name), "cached").unwrap();
+        }
+
+        let widget = invalidate_in(cache.path(), &component(ComponentType::PlasmaWidget));
+        let aurorae = invalidate_in(cache.path(), &component(ComponentType::AuroraeDecoration));
+
+        assert_eq!((widget, aurorae), (0, 2));
+        assert!(cache.path().join("icon-cache.kcache").exists());
+    }
+
+    fn fill_cache(dir: &Path, names: &[&str]) {
+        for name in names {
+            fs::write(dir.join(name), "cached").unwrap();
+        }
+    }
+
+    #[test]
+    fn plasma_style_update_clears_only_its_own_caches() {
+        let cache = tempfile::tempdir().unwrap();
+        fill_cache(
+            cache.path(),
+            &[
+                "plasma-svgelements-Sweet_v6.0",
+                "plasma_theme_Sweet.kcache",
+                "plasma_theme_Sweet_v6.0.kcache",
+                "plasma-svgelements-Sweet-Dark_v6.0",
+                "plasma_theme_Sweet-Dark.kcache",
+            ],
+        );
+
+        let style = themed(ComponentType::PlasmaStyle, "Sweet", Path::new("/tmp/Sweet"));
+        assert_eq!(invalidate_in(cache.path(), &style), 3);
+        assert!(cache.path().join("plasma_theme_Sweet-Dark.kcache").exists());
+    }
+
+    #[test]
+    fn global_theme_update_clears_the_style_it_applies() {
+        let cache = tempfile::tempdir().unwrap();
+        let package = tempfile::tempdir().unwrap();
+        fs::create_dir_all(package.path().join("contents")).unwrap();
+        fs::write(
+            package.path().join("contents/defaults"),
+            "[kdeglobals][KDE]\nwidgetStyle=Breeze\n\n[plasmarc][Theme]\nname=Sweet\n",
+        )
+        .unwrap();
+        fill_cache(
+            cache.path(),
+            &["plasma_theme_Sweet.kcache", "plasma_theme_default.kcache"],
+        );
+
+        let theme = themed(
+            ComponentType::GlobalTheme,
+            "com.github.eliverlara.sweet",
+            package.path(),
+        );
+        assert_eq!(invalidate_in(cache.path(), &theme), 1);
+        assert!(cache.path().join("plasma_theme_default.kcache").exists());
+    }
+}
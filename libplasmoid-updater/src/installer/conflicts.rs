@@ -0,0 +1,145 @@
+// SPDX-License-Identifier: GPL-3.0-or-later
+
+use std::{fmt, path::PathBuf};
+
+use serde::Serialize;
+
+use crate::types::AvailableUpdate;
+
+/// Selected updates that would write to the same files, e.g. an icon theme
+/// and a cursor theme with the same directory name in `icons`. Found before
+/// anything is installed, see [`Config::conflict_policy`](crate::Config::conflict_policy).
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct FileConflict {
+    /// The file or directory the components share; for a package nested in
+    /// another one, the outer one.
+    pub path: PathBuf,
+    /// Name and type of each component, e.g. `Sweet (Icon Theme)`.
+    pub components: Vec<String>,
+}
+
+impl fmt::Display for FileConflict {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let (last, rest) = self
+            .components
+            .split_last()
+            .expect("a conflict has at least two components");
+        write!(
+            f,
+            "{} and {last} write to {}",
+            rest.join(", "),
+            self.path.display()
+        )
+    }
+}
+
+/// Returns the groups of `updates` whose destinations are the same path or
+/// lie inside one another, in the order of `updates`.
+pub(crate) fn find_conflicts(updates: &[&AvailableUpdate]) -> Vec<FileConflict> {
+    let mut conflicts: Vec<FileConflict> = Vec::new();
+    for (i, a) in updates.iter().enumerate() {
+        for b in &updates[i + 1..] {
+            let (a_path, b_path) = (&a.installed.path, &b.installed.path);
+            // Every path starts with the empty one, which no update writes to.
+            if a_path.as_os_str().is_empty() || b_path.as_os_str().is_empty() {
+                continue;
+            }
+            let path = if b_path.starts_with(a_path) {
+                a_path
+            } else if a_path.starts_with(b_path) {
+                b_path
+            } else {
+                continue;
+            };
+
+            let labels = [label(a), label(b)];
+            match conflicts.iter_mut().find(|c| &c.path == path) {
+                Some(conflict) => {
+                    for label in labels {
+                        if !conflict.components.contains(&label) {
+                            conflict.components.push(label);
+                        }
+                    }
+                }
+                None => conflicts.push(FileConflict {
+                    path: path.clone(),
+                    components: labels.into(),
+                }),
+            }
+        }
+    }
+    conflicts
+}
+
+fn label(update: &AvailableUpdate) -> String {
+    format!(
+        "{} ({})",
+        update.installed.name, update.installed.component_type
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::types::{ComponentType, InstalledComponent};
+
+    fn update(name: &str, component_type: ComponentType, path: &str) -> AvailableUpdate {
+        let installed = InstalledComponent::fixture(name, component_type, path);
+        AvailableUpdate::builder(
+            installed,
+            1,
+            "2.0".to_string(),
+            String::new(),
+            String::new(),
+        )
+        .build()
+    }
+
+    #[test]
+    fn same_and_nested_destinations_conflict() {
+        let icons = update(
+            "Sweet",
+            ComponentType::IconTheme,
+            "/home/u/.local/share/icons/Sweet",
+        );
+        let cursors = update(
+            "Sweet",
+            ComponentType::CursorTheme,
+            "/home/u/.local/share/icons/Sweet",
+        );
+        let nested = update(
+            "Sweet cursors",
+            ComponentType::Other("icons.knsregistry"),
+            "/home/u/.local/share/icons/Sweet/cursors",
+        );
+        let clock = update(
+            "Clock",
+            ComponentType::PlasmaWidget,
+            "/home/u/.local/share/plasma/plasmoids/clock",
+        );
+
+        let conflicts = find_conflicts(&[&icons, &clock, &cursors, &nested]);
+
+        assert_eq!(conflicts.len(), 1);
+        assert_eq!(
+            conflicts[0].components,
+            [
+                "Sweet (Icon Theme)",
+                "Sweet (Cursor Theme)",
+                "Sweet cursors (Other (icons))"
+            ]
+        );
+        assert_eq!(
+            conflicts[0].to_string(),
+            "Sweet (Icon Theme), Sweet (Cursor Theme) and Sweet cursors (Other (icons)) write to \
+             /home/u/.local/share/icons/Sweet"
+        );
+    }
+
+    #[test]
+    fn sibling_prefixes_do_not_conflict() {
+        let a = update("Sweet", ComponentType::IconTheme, "/icons/Sweet");
+        let b = update("Sweet-Dark", ComponentType::IconTheme, "/icons/Sweet-Dark");
+        assert!(find_conflicts(&[&a, &b]).is_empty());
+    }
+}
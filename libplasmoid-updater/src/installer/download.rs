@@ -1,53 +1,136 @@
 // SPDX-License-Identifier: GPL-3.0-or-later
 
 use std::{
-    fs::{self, File},
+    fs::{self, File, Permissions},
     io::{Read, Write},
-    path::{Path, PathBuf},
+    os::unix::fs::{DirBuilderExt, MetadataExt, PermissionsExt},
+    path::{Component, Path, PathBuf},
     process::Command,
-    sync::atomic::{AtomicUsize, Ordering},
-    time::Duration,
+    time::{Duration, SystemTime},
 };
 
-use crate::{Error, Result};
+use super::policy::DownloadPolicy;
+use crate::{Error, Result, api::ApiCounters};
 
-const DOWNLOAD_TIMEOUT_SECS: u64 = 60;
 const DOWNLOAD_BUFFER_SIZE: usize = 8192;
 
-/// Creates a temporary directory that is automatically cleaned up on drop.
-pub(crate) fn create_temp_dir() -> Result<tempfile::TempDir> {
-    let base = std::env::var("TMPDIR")
-        .map(PathBuf::from)
-        .unwrap_or_else(|_| PathBuf::from("/tmp"));
+/// Prefix of per-run temp directories. Distinct from the `/tmp/plasmoid-updater-<uid>`
+/// runtime fallback so stale cleanup never touches the lock directory.
+const TEMP_DIR_PREFIX: &str = "plasmoid-updater-tmp-";
+
+/// Returns the root under which per-run temp directories are created.
+///
+/// Uses `configured` if set, otherwise `$TMPDIR` or `/tmp`.
+pub(crate) fn temp_root(configured: Option<&Path>) -> PathBuf {
+    configured.map_or_else(std::env::temp_dir, Path::to_path_buf)
+}
+
+/// Creates a private (`0700`) temporary directory under `root` that is
+/// automatically cleaned up on drop.
+pub(crate) fn create_temp_dir(root: &Path) -> Result<tempfile::TempDir> {
+    if !root.exists() {
+        fs::DirBuilder::new()
+            .recursive(true)
+            .mode(0o700)
+            .create(root)
+            .map_err(|e| Error::other(format!("failed to create temp root: {e}")))?;
+    }
     tempfile::Builder::new()
-        .prefix("plasmoid-updater-")
-        .tempdir_in(base)
+        .prefix(TEMP_DIR_PREFIX)
+        .permissions(Permissions::from_mode(0o700))
+        .tempdir_in(root)
         .map_err(|e| Error::other(format!("failed to create temp dir: {e}")))
 }
 
+/// Removes temp directories from previous runs that are older than `max_age`.
+///
+/// Only directories carrying our prefix and owned by the current user are
+/// considered, so other users' in-flight runs on a shared `/tmp` are left alone.
+/// Returns the number of directories removed.
+pub(crate) fn cleanup_stale_temp_dirs(root: &Path, max_age: Duration) -> usize {
+    let Ok(entries) = fs::read_dir(root) else {
+        return 0;
+    };
+
+    let uid = nix::unistd::Uid::effective().as_raw();
+    let now = SystemTime::now();
+    let mut removed = 0;
+
+    for entry in entries.flatten() {
+        if !entry
+            .file_name()
+            .to_string_lossy()
+            .starts_with(TEMP_DIR_PREFIX)
+        {
+            continue;
+        }
+        // symlink_metadata: never follow a planted symlink out of the temp root
+        let Ok(meta) = entry.path().symlink_metadata() else {
+            continue;
+        };
+        if !meta.is_dir() || meta.uid() != uid {
+            continue;
+        }
+        let is_stale = meta
+            .modified()
+            .ok()
+            .and_then(|m| now.duration_since(m).ok())
+            .is_some_and(|age| age > max_age);
+        if !is_stale {
+            continue;
+        }
+
+        match fs::remove_dir_all(entry.path()) {
+            Ok(()) => removed += 1,
+            Err(e) => log::debug!(
+                target: "cleanup",
+                "failed to remove stale temp dir {}: {e}",
+                entry.path().display()
+            ),
+        }
+    }
+
+    if removed > 0 {
+        log::debug!(target: "cleanup", "removed {removed} stale temp dir(s) from {}", root.display());
+    }
+    removed
+}
+
+/// Returns where the package at `url` is downloaded to in `temp_path`.
+///
+/// `directory_name` namespaces the file, preventing collisions when several
+/// components download in parallel; the URL's file name keeps the extension
+/// package type detection relies on.
+pub(crate) fn download_dest(temp_path: &Path, url: &str, directory_name: &str) -> PathBuf {
+    let file_name = url.rsplit('/').next().unwrap_or("package.tar.gz");
+    temp_path.join(format!("{directory_name}_{file_name}"))
+}
+
 /// Downloads a package with optional checksum verification.
 ///
-/// `directory_name` is used to namespace the download file, preventing
-/// filename collisions when multiple components download in parallel.
+/// The file is written to [`download_dest`]. Both `url` and the URL reached after redirects must pass `policy`, and
+/// the download is aborted once it grows past the policy's size limit.
 pub(crate) fn download_package(
     client: &reqwest::blocking::Client,
+    policy: &DownloadPolicy,
     url: &str,
     expected_checksum: Option<&str>,
     directory_name: &str,
-    counter: &AtomicUsize,
+    stats: &ApiCounters,
     temp_path: &Path,
 ) -> Result<PathBuf> {
-    let file_name = url.rsplit('/').next().unwrap_or("package.tar.gz");
+    let dest = download_dest(temp_path, url, directory_name);
 
-    let dest = temp_path.join(format!("{directory_name}_{file_name}"));
+    policy.check(url)?;
 
-    counter.fetch_add(1, Ordering::Relaxed);
-    let response = client
-        .get(url)
-        .timeout(Duration::from_secs(DOWNLOAD_TIMEOUT_SECS))
-        .send()
-        .map_err(|e| Error::download(format!("request failed: {e}")))?;
+    stats.request();
+    let response = client.get(url).timeout(policy.timeout()).send()?;
+    policy.check(response.url().as_str())?;
 
+    if response.status() == reqwest::StatusCode::TOO_MANY_REQUESTS {
+        stats.rate_limited();
+        return Err(Error::RateLimited);
+    }
     if !response.status().is_success() {
         return Err(Error::download(format!(
             "http status {}",
@@ -55,8 +138,13 @@ pub(crate) fn download_package(
         )));
     }
 
+    if let Some(length) = response.content_length() {
+        policy.check_size(length)?;
+    }
+
     let mut file = File::create(&dest)?;
     let mut hasher = md5::Context::new();
+    let mut total: u64 = 0;
 
     let mut reader = response;
     let mut buffer = [0u8; DOWNLOAD_BUFFER_SIZE];
@@ -70,6 +158,15 @@ pub(crate) fn download_package(
             break;
         }
 
+        // Content-Length can be missing or wrong, so count what arrives too.
+        total += bytes_read as u64;
+        stats.received(bytes_read as u64);
+        if let Err(e) = policy.check_size(total) {
+            drop(file);
+            fs::remove_file(&dest).ok();
+            return Err(e);
+        }
+
         let chunk = &buffer[..bytes_read];
         hasher.consume(chunk);
         file.write_all(chunk)?;
@@ -82,14 +179,22 @@ pub(crate) fn download_package(
             fs::remove_file(&dest).ok();
             return Err(Error::checksum(expected, actual));
         }
-        log::debug!(target: "checksum", "verified md5 for {file_name}");
+        log::debug!(target: "checksum", "verified md5 for {}", dest.display());
     }
 
     Ok(dest)
 }
 
 /// Extracts a package archive to the destination directory using `bsdtar`.
+///
+/// The archive is rejected with [`Error::MaliciousArchive`] if an entry has an
+/// absolute path or a `..` component, or if the extracted tree contains a
+/// symlink pointing outside `dest`.
 pub(crate) fn extract_archive(archive_path: &Path, dest: &Path) -> Result<()> {
+    for entry in list_archive(archive_path)? {
+        validate_entry_path(&entry)?;
+    }
+
     fs::create_dir_all(dest)?;
 
     let output = Command::new("bsdtar")
@@ -112,20 +217,240 @@ pub(crate) fn extract_archive(archive_path: &Path, dest: &Path) -> Result<()> {
         return Err(Error::extraction(detail));
     }
 
+    if let Err(e) = validate_extracted_tree(dest) {
+        let _ = fs::remove_dir_all(dest);
+        return Err(e);
+    }
+
     Ok(())
 }
 
+/// Lists the entry names of an archive with `bsdtar -t`.
+fn list_archive(archive_path: &Path) -> Result<Vec<String>> {
+    let output = Command::new("bsdtar")
+        .arg("-tf")
+        .arg(archive_path)
+        .output()
+        .map_err(|e| Error::extraction(format!("failed to run bsdtar: {e}")))?;
+
+    if !output.status.success() {
+        let stderr = String::from_utf8_lossy(&output.stderr);
+        return Err(Error::extraction(format!(
+            "failed to list archive: {}",
+            stderr.trim()
+        )));
+    }
+
+    Ok(String::from_utf8_lossy(&output.stdout)
+        .lines()
+        .map(str::to_string)
+        .collect())
+}
+
+/// Rejects archive entry names that would land outside the extraction root.
+fn validate_entry_path(name: &str) -> Result<()> {
+    let path = Path::new(name);
+    if path.is_absolute() {
+        return Err(Error::malicious_archive(format!(
+            "entry '{name}' has an absolute path"
+        )));
+    }
+    if path.components().any(|c| c == Component::ParentDir) {
+        return Err(Error::malicious_archive(format!(
+            "entry '{name}' escapes the archive root"
+        )));
+    }
+    Ok(())
+}
+
+/// Walks an extracted tree and rejects symlinks that resolve outside `root`.
+///
+/// Targets are resolved lexically, so a link is judged by where it points
+/// rather than by what currently exists there.
+fn validate_extracted_tree(root: &Path) -> Result<()> {
+    let mut pending = vec![root.to_path_buf()];
+    while let Some(dir) = pending.pop() {
+        for entry in fs::read_dir(&dir)? {
+            let path = entry?.path();
+            let meta = path.symlink_metadata()?;
+            if meta.is_dir() {
+                pending.push(path);
+            } else if meta.file_type().is_symlink() {
+                let target = fs::read_link(&path)?;
+                if !symlink_stays_within(root, &path, &target) {
+                    let relative = path.strip_prefix(root).unwrap_or(&path);
+                    return Err(Error::malicious_archive(format!(
+                        "symlink '{}' points outside the archive ({})",
+                        relative.display(),
+                        target.display()
+                    )));
+                }
+            }
+        }
+    }
+    Ok(())
+}
+
+fn symlink_stays_within(root: &Path, link: &Path, target: &Path) -> bool {
+    if target.is_absolute() {
+        return false;
+    }
+
+    let base = link.parent().unwrap_or(root);
+    let Ok(relative) = base.strip_prefix(root) else {
+        return false;
+    };
+
+    let mut depth: usize = 0;
+    for component in relative.components().chain(target.components()) {
+        match component {
+            Component::Normal(_) => depth += 1,
+            Component::ParentDir => match depth.checked_sub(1) {
+                Some(d) => depth = d,
+                None => return false,
+            },
+            Component::CurDir => {}
+            Component::RootDir | Component::Prefix(_) => return false,
+        }
+    }
+    true
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
 
+    fn age_dir(path: &Path, age: Duration) {
+        let dir = File::open(path).unwrap();
+        dir.set_modified(SystemTime::now() - age).unwrap();
+    }
+
+    /// Builds a tar of `file` stored under `stored_name` in `dir`.
+    fn crafted_tar(dir: &Path, stored_name: &str) -> PathBuf {
+        fs::write(dir.join("payload"), b"data").unwrap();
+        let archive = dir.join("crafted.tar");
+        let status = Command::new("bsdtar")
+            .current_dir(dir)
+            .args(["-cPf", "crafted.tar", "-s"])
+            .arg(format!(",^payload$,{stored_name},"))
+            .arg("payload")
+            .status()
+            .unwrap();
+        assert!(status.success());
+        archive
+    }
+
+    #[test]
+    fn extract_rejects_parent_dir_entries() {
+        let dir = tempfile::tempdir().unwrap();
+        let archive = crafted_tar(dir.path(), "../escaped");
+        let dest = dir.path().join("out");
+
+        let err = extract_archive(&archive, &dest).unwrap_err();
+        assert!(matches!(err, Error::MaliciousArchive(_)), "{err}");
+        assert!(!dir.path().join("escaped").exists());
+    }
+
+    #[test]
+    fn extract_rejects_absolute_entries() {
+        let dir = tempfile::tempdir().unwrap();
+        let target = dir.path().join("absolute");
+        let archive = crafted_tar(dir.path(), &target.to_string_lossy());
+
+        let err = extract_archive(&archive, &dir.path().join("out")).unwrap_err();
+        assert!(matches!(err, Error::MaliciousArchive(_)), "{err}");
+        assert!(!target.exists());
+    }
+
+    #[test]
+    fn extract_rejects_escaping_symlinks() {
+        let dir = tempfile::tempdir().unwrap();
+        let src = dir.path().join("src");
+        fs::create_dir_all(src.join("contents")).unwrap();
+        std::os::unix::fs::symlink("../../../etc/passwd", src.join("contents/link")).unwrap();
+        let archive = dir.path().join("sym.tar");
+        let status = Command::new("bsdtar")
+            .arg("-cf")
+            .arg(&archive)
+            .arg("-C")
+            .arg(&src)
+            .arg("contents")
+            .status()
+            .unwrap();
+        assert!(status.success());
+        let dest = dir.path().join("out");
+
+        let err = extract_archive(&archive, &dest).unwrap_err();
+        assert!(matches!(err, Error::MaliciousArchive(_)), "{err}");
+        assert!(!dest.exists());
+    }
+
+    #[test]
+    fn symlinks_inside_the_root_are_allowed() {
+        let root = Path::new("/x/extract");
+        let link = root.join("pkg/contents/ui/link");
+        assert!(symlink_stays_within(
+            root,
+            &link,
+            Path::new("../config/main.xml")
+        ));
+        assert!(symlink_stays_within(
+            root,
+            &link,
+            Path::new("../../../README")
+        ));
+        assert!(!symlink_stays_within(
+            root,
+            &link,
+            Path::new("../../../../etc")
+        ));
+        assert!(!symlink_stays_within(root, &link, Path::new("/etc/passwd")));
+    }
+
     #[test]
     fn create_temp_dir_is_raii() {
-        let temp = create_temp_dir().unwrap();
+        let root = tempfile::tempdir().unwrap();
+        let temp = create_temp_dir(root.path()).unwrap();
         let path = temp.path().to_path_buf();
         assert!(path.exists());
         std::fs::write(path.join("test.txt"), b"data").unwrap();
         drop(temp);
         assert!(!path.exists());
     }
+
+    #[test]
+    fn create_temp_dir_is_private() {
+        let root = tempfile::tempdir().unwrap();
+        let temp = create_temp_dir(root.path()).unwrap();
+        let mode = fs::metadata(temp.path()).unwrap().permissions().mode();
+        assert_eq!(mode & 0o777, 0o700);
+    }
+
+    #[test]
+    fn create_temp_dir_creates_missing_root() {
+        let base = tempfile::tempdir().unwrap();
+        let root = base.path().join("nested/root");
+        let temp = create_temp_dir(&root).unwrap();
+        assert!(temp.path().starts_with(&root));
+    }
+
+    #[test]
+    fn cleanup_removes_only_stale_prefixed_dirs() {
+        let root = tempfile::tempdir().unwrap();
+        let stale = root.path().join(format!("{TEMP_DIR_PREFIX}old"));
+        let fresh = root.path().join(format!("{TEMP_DIR_PREFIX}new"));
+        let foreign = root.path().join("something-else");
+        for dir in [&stale, &fresh, &foreign] {
+            fs::create_dir(dir).unwrap();
+        }
+        age_dir(&stale, Duration::from_secs(3 * 24 * 60 * 60));
+        age_dir(&foreign, Duration::from_secs(3 * 24 * 60 * 60));
+
+        let removed = cleanup_stale_temp_dirs(root.path(), Duration::from_secs(24 * 60 * 60));
+
+        assert_eq!(removed, 1);
+        assert!(!stale.exists());
+        assert!(fresh.exists());
+        assert!(foreign.exists());
+    }
 }
@@ -0,0 +1,292 @@
+// SPDX-License-Identifier: GPL-3.0-or-later
+
+use std::{
+    collections::HashMap,
+    fs,
+    path::{Path, PathBuf},
+    sync::{Arc, LazyLock},
+    time::SystemTime,
+};
+
+use parking_lot::Mutex;
+
+use crate::{Result, types::AvailableUpdate};
+
+use super::download::download_dest;
+
+/// Downloads being fetched in this process, so components sharing a package
+/// wait for the first download instead of fetching it again.
+static IN_FLIGHT: LazyLock<Mutex<HashMap<String, Arc<Mutex<()>>>>> =
+    LazyLock::new(Default::default);
+
+/// Returns the download cache, `$XDG_CACHE_HOME/plasmoid-updater/downloads`.
+fn cache_dir() -> PathBuf {
+    crate::paths::cache_home().join("plasmoid-updater/downloads")
+}
+
+/// Returns the package of `update` from the cache, or runs `download` and
+/// caches its result.
+///
+/// Packages are keyed by their checksum when the store publishes one, so
+/// theme packs sharing a payload share an entry, and by URL and version
+/// otherwise. The returned file is a copy in `temp_path` the caller may
+/// consume. With `limit` `None` the cache is bypassed.
+pub(crate) fn fetch(
+    update: &AvailableUpdate,
+    limit: Option<u64>,
+    temp_path: &Path,
+    download: impl FnOnce() -> Result<PathBuf>,
+) -> Result<PathBuf> {
+    let Some(limit) = limit else {
+        return download();
+    };
+    fetch_in(&cache_dir(), update, limit, temp_path, download)
+}
+
+fn fetch_in(
+    dir: &Path,
+    update: &AvailableUpdate,
+    limit: u64,
+    temp_path: &Path,
+    download: impl FnOnce() -> Result<PathBuf>,
+) -> Result<PathBuf> {
+    let key = cache_key(update);
+    let lock = Arc::clone(IN_FLIGHT.lock().entry(key.clone()).or_default());
+    let _guard = lock.lock();
+
+    let cached = dir.join(&key);
+    if cached.is_file() && matches_checksum(&cached, update.checksum.as_deref()) {
+        let dest = download_dest(
+            temp_path,
+            &update.download_url,
+            &update.installed.directory_name,
+        );
+        fs::copy(&cached, &dest)?;
+        // Pruning goes by modification time, so a hit counts as recent use.
+        if let Ok(file) = fs::File::options().append(true).open(&cached) {
+            let _ = file.set_modified(SystemTime::now());
+        }
+        log::debug!(target: "download", "reusing cached package for {}", update.installed.name);
+        return Ok(dest);
+    }
+
+    let downloaded = download()?;
+    if let Err(e) = store(dir, &key, &downloaded, limit) {
+        log::debug!(target: "download", "failed to cache {}: {e}", downloaded.display());
+    }
+    Ok(downloaded)
+}
+
+/// Downloads the package of `update` into the cache unless it is there
+/// already, so a later [`fetch`] finds it. Returns `false` if the package
+/// was not cached, e.g. for being larger than `limit`.
+pub(crate) fn prefetch(
+    update: &AvailableUpdate,
+    limit: u64,
+    download: impl FnOnce() -> Result<PathBuf>,
+) -> Result<bool> {
+    prefetch_in(&cache_dir(), update, limit, download)
+}
+
+fn prefetch_in(
+    dir: &Path,
+    update: &AvailableUpdate,
+    limit: u64,
+    download: impl FnOnce() -> Result<PathBuf>,
+) -> Result<bool> {
+    let key = cache_key(update);
+    let lock = Arc::clone(IN_FLIGHT.lock().entry(key.clone()).or_default());
+    let _guard = lock.lock();
+
+    let cached = dir.join(&key);
+    if cached.is_file() && matches_checksum(&cached, update.checksum.as_deref()) {
+        return Ok(true);
+    }
+
+    let downloaded = download()?;
+    let stored = store(dir, &key, &downloaded, limit);
+    let _ = fs::remove_file(&downloaded);
+    stored?;
+    Ok(cached.is_file())
+}
+
+fn cache_key(update: &AvailableUpdate) -> String {
+    match &update.checksum {
+        Some(checksum) => format!("md5-{}", checksum.to_lowercase()),
+        None => format!(
+            "url-{:x}",
+            md5::compute(format!(
+                "{}\n{}",
+                update.download_url, update.latest_version
+            ))
+        ),
+    }
+}
+
+fn matches_checksum(path: &Path, checksum: Option<&str>) -> bool {
+    let Some(expected) = checksum else {
+        return true;
+    };
+    fs::read(path)
+        .is_ok_and(|content| format!("{:x}", md5::compute(content)).eq_ignore_ascii_case(expected))
+}
+
+fn store(dir: &Path, key: &str, downloaded: &Path, limit: u64) -> Result<()> {
+    if fs::metadata(downloaded)?.len() > limit {
+        return Ok(());
+    }
+    fs::create_dir_all(dir)?;
+    let partial = dir.join(format!(".{key}.partial"));
+    fs::copy(downloaded, &partial)?;
+    fs::rename(&partial, dir.join(key))?;
+    prune(dir, limit);
+    Ok(())
+}
+
+/// Removes the least recently used files in `dir` until they fit `limit`.
+pub(crate) fn prune(dir: &Path, limit: u64) {
+    let Ok(entries) = fs::read_dir(dir) else {
+        return;
+    };
+    let mut files: Vec<(SystemTime, u64, PathBuf)> = entries
+        .flatten()
+        .filter_map(|e| {
+            let meta = e.metadata().ok().filter(|m| m.is_file())?;
+            Some((meta.modified().ok()?, meta.len(), e.path()))
+        })
+        .collect();
+    let mut total: u64 = files.iter().map(|(_, len, _)| len).sum();
+    files.sort();
+
+    for (_, len, path) in files {
+        if total <= limit {
+            break;
+        }
+        match fs::remove_file(&path) {
+            Ok(()) => total -= len,
+            Err(e) => log::debug!(target: "download", "failed to remove {}: {e}", path.display()),
+        }
+    }
+}
+
+/// Deletes the download cache. Returns the bytes freed.
+pub(crate) fn clear() -> Result<u64> {
+    clear_in(&cache_dir())
+}
+
+fn clear_in(dir: &Path) -> Result<u64> {
+    let bytes = crate::du::dir_size(dir);
+    match fs::remove_dir_all(dir) {
+        Ok(()) => Ok(bytes),
+        Err(e) if e.kind() == std::io::ErrorKind::NotFound => Ok(0),
+        Err(e) => Err(e.into()),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::types::{ComponentType, InstalledComponent};
+
+    fn update(url: &str, checksum: Option<&str>) -> AvailableUpdate {
+        let installed =
+            InstalledComponent::fixture("Nord", ComponentType::GlobalTheme, "/nonexistent/Nord");
+        let mut update =
+            AvailableUpdate::builder(installed, 1, "2.0".into(), url.into(), String::new()).build();
+        update.checksum = checksum.map(str::to_string);
+        update
+    }
+
+    fn download_to(temp: &Path, content: &str) -> Result<PathBuf> {
+        let path = temp.join("fresh.tar.gz");
+        fs::write(&path, content)?;
+        Ok(path)
+    }
+
+    #[test]
+    fn second_fetch_reuses_cached_package() {
+        let dir = tempfile::tempdir().unwrap();
+        let cache = dir.path().join("downloads");
+        let update = update("https://example.org/nord.tar.gz", None);
+
+        let first = fetch_in(&cache, &update, 1024, dir.path(), || {
+            download_to(dir.path(), "package")
+        })
+        .unwrap();
+        let second = fetch_in(&cache, &update, 1024, dir.path(), || {
+            panic!("package should come from the cache")
+        })
+        .unwrap();
+
+        assert_eq!(first, dir.path().join("fresh.tar.gz"));
+        assert_eq!(second, dir.path().join("Nord_nord.tar.gz"));
+        assert_eq!(fs::read_to_string(second).unwrap(), "package");
+    }
+
+    #[test]
+    fn checksum_mismatch_downloads_again() {
+        let dir = tempfile::tempdir().unwrap();
+        let cache = dir.path().join("downloads");
+        let checksum = format!("{:x}", md5::compute("package"));
+        let update = update("https://example.org/nord.tar.gz", Some(&checksum));
+        fs::create_dir_all(&cache).unwrap();
+        fs::write(cache.join(cache_key(&update)), "corrupt").unwrap();
+
+        let fetched = fetch_in(&cache, &update, 1024, dir.path(), || {
+            download_to(dir.path(), "package")
+        })
+        .unwrap();
+
+        assert_eq!(fetched, dir.path().join("fresh.tar.gz"));
+        assert_eq!(
+            fs::read_to_string(cache.join(cache_key(&update))).unwrap(),
+            "package"
+        );
+    }
+
+    #[test]
+    fn prefetched_package_is_fetched_from_the_cache() {
+        let dir = tempfile::tempdir().unwrap();
+        let cache = dir.path().join("downloads");
+        let nord = update("https://example.org/nord.tar.gz", None);
+
+        let prefetched =
+            prefetch_in(&cache, &nord, 1024, || download_to(dir.path(), "package")).unwrap();
+        assert!(prefetched);
+        assert!(!dir.path().join("fresh.tar.gz").exists());
+        assert!(prefetch_in(&cache, &nord, 1024, || panic!("package is already cached")).unwrap());
+
+        let fetched = fetch_in(&cache, &nord, 1024, dir.path(), || {
+            panic!("package should come from the cache")
+        })
+        .unwrap();
+        assert_eq!(fs::read_to_string(fetched).unwrap(), "package");
+
+        let large = update("https://example.org/large.tar.gz", None);
+        assert!(!prefetch_in(&cache, &large, 4, || download_to(dir.path(), "package")).unwrap());
+    }
+
+    #[test]
+    fn prune_removes_oldest_until_under_limit() {
+        let dir = tempfile::tempdir().unwrap();
+        for (name, age) in [("old", 300), ("mid", 200), ("new", 100)] {
+            let path = dir.path().join(name);
+            fs::write(&path, [0; 10]).unwrap();
+            let modified = SystemTime::now() - std::time::Duration::from_secs(age);
+            fs::File::options()
+                .append(true)
+                .open(&path)
+                .unwrap()
+                .set_modified(modified)
+                .unwrap();
+        }
+
+        prune(dir.path(), 20);
+
+        assert!(!dir.path().join("old").exists());
+        assert!(dir.path().join("mid").exists());
+        assert!(dir.path().join("new").exists());
+        assert_eq!(clear_in(dir.path()).unwrap(), 20);
+        assert_eq!(clear_in(dir.path()).unwrap(), 0);
+    }
+}
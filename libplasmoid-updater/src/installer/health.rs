@@ -0,0 +1,298 @@
+// SPDX-License-Identifier: GPL-3.0-or-later
+
+use std::{fs, path::Path};
+
+use crate::types::{ComponentType, InstalledComponent};
+
+use super::install::{has_wallpaper_images, kpackagetool_cmd, resolve_plugin_id};
+
+/// Validates `component` as installed, see [`Config::health_check`](crate::Config::health_check).
+///
+/// Checks that the package metadata and the entry point required by its type
+/// exist, that every QML file has balanced brackets, and finally that
+/// `kpackagetool6 --show` can load the package. Returns the problems found,
+/// empty if the component looks healthy.
+pub(super) fn check(component: &InstalledComponent) -> Vec<String> {
+    let path = &component.path;
+    if !path.exists() {
+        return vec![format!("{} is missing", path.display())];
+    }
+    if path.is_file() {
+        return match fs::metadata(path) {
+            Ok(meta) if meta.len() == 0 => vec![format!("{} is empty", path.display())],
+            _ => Vec::new(),
+        };
+    }
+
+    let mut problems = Vec::new();
+    let kpackage_type = component.component_type.kpackage_type();
+
+    if kpackage_type.is_some() {
+        problems.extend(check_metadata(path));
+    }
+
+    for alternatives in required_files(component) {
+        if !alternatives.iter().any(|file| path.join(file).is_file()) {
+            problems.push(format!("missing {}", alternatives.join(" or ")));
+        }
+    }
+
+    if component.component_type == ComponentType::Wallpaper && !has_wallpaper_images(path) {
+        problems.push("no images in contents/images".to_string());
+    }
+
+    for qml in find_qml_files(path) {
+        let relative = qml.strip_prefix(path).unwrap_or(&qml).display().to_string();
+        let checked = fs::read_to_string(&qml)
+            .map_err(|e| e.to_string())
+            .and_then(|source| check_qml_syntax(&source));
+        if let Err(e) = checked {
+            problems.push(format!("{relative}: {e}"));
+        }
+    }
+
+    if problems.is_empty()
+        && let Some(kpackage_type) = kpackage_type
+        && let Some(problem) = kpackage_show(component, kpackage_type)
+    {
+        problems.push(problem);
+    }
+
+    problems
+}
+
+fn check_metadata(path: &Path) -> Option<String> {
+    let json = path.join("metadata.json");
+    if json.is_file() {
+        let content = match fs::read_to_string(&json) {
+            Ok(content) => content,
+            Err(e) => return Some(format!("metadata.json: {e}")),
+        };
+        return serde_json::from_str::<serde_json::Value>(&content)
+            .err()
+            .map(|e| format!("metadata.json: {e}"));
+    }
+    (!path.join("metadata.desktop").is_file())
+        .then(|| "no metadata.json or metadata.desktop".to_string())
+}
+
+/// Entry points a type needs; each slot lists acceptable alternatives.
+fn required_files(component: &InstalledComponent) -> Vec<Vec<String>> {
+    let one = |file: &str| vec![file.to_string()];
+    match component.component_type {
+        ComponentType::PlasmaWidget => {
+            let script = main_script(&component.path).unwrap_or_else(|| "ui/main.qml".into());
+            vec![one(&format!("contents/{script}"))]
+        }
+        ComponentType::WallpaperPlugin | ComponentType::KWinSwitcher => {
+            vec![one("contents/ui/main.qml")]
+        }
+        ComponentType::KWinScript | ComponentType::KWinEffect => {
+            let mut alternatives = vec![
+                "contents/code/main.js".to_string(),
+                "contents/ui/main.qml".to_string(),
+            ];
+            if let Some(script) = main_script(&component.path) {
+                alternatives.insert(0, format!("contents/{script}"));
+            }
+            vec![alternatives]
+        }
+        ComponentType::SplashScreen => vec![one("contents/splash/Splash.qml")],
+        _ => Vec::new(),
+    }
+}
+
+/// `X-Plasma-MainScript` from the package metadata, relative to `contents/`.
+fn main_script(path: &Path) -> Option<String> {
+    if let Ok(content) = fs::read_to_string(path.join("metadata.json"))
+        && let Ok(json) = serde_json::from_str::<serde_json::Value>(&content)
+        && let Some(script) = json.get("X-Plasma-MainScript").and_then(|v| v.as_str())
+    {
+        return Some(script.to_string());
+    }
+
+    let content = fs::read_to_string(path.join("metadata.desktop")).ok()?;
+    crate::checker::parse_desktop_entry(&content)?
+        .get("X-Plasma-MainScript")
+        .map(|script| script.to_string())
+}
+
+fn find_qml_files(dir: &Path) -> Vec<std::path::PathBuf> {
+    let mut files = Vec::new();
+    let Ok(entries) = fs::read_dir(dir) else {
+        return files;
+    };
+    for entry in entries.flatten() {
+        let path = entry.path();
+        let Ok(file_type) = entry.file_type() else {
+            continue;
+        };
+        if file_type.is_dir() {
+            files.extend(find_qml_files(&path));
+        } else if file_type.is_file() && path.extension().is_some_and(|ext| ext == "qml") {
+            files.push(path);
+        }
+    }
+    files
+}
+
+/// Checks that brackets in a QML document are balanced, skipping strings and
+/// comments.
+///
+/// This catches truncated or mangled files without a QML engine; it does not
+/// parse the language.
+fn check_qml_syntax(source: &str) -> std::result::Result<(), String> {
+    if source.trim().is_empty() {
+        return Err("file is empty".to_string());
+    }
+
+    let mut stack: Vec<(char, usize)> = Vec::new();
+    let mut line = 1;
+    let mut chars = source.chars().peekable();
+
+    while let Some(c) = chars.next() {
+        match c {
+            '\n' => line += 1,
+            '/' if chars.peek() == Some(&'/') => {
+                for c in chars.by_ref() {
+                    if c == '\n' {
+                        line += 1;
+                        break;
+                    }
+                }
+            }
+            '/' if chars.peek() == Some(&'*') => {
+                chars.next();
+                let mut prev = ' ';
+                let mut closed = false;
+                for c in chars.by_ref() {
+                    if c == '\n' {
+                        line += 1;
+                    }
+                    if prev == '*' && c == '/' {
+                        closed = true;
+                        break;
+                    }
+                    prev = c;
+                }
+                if !closed {
+                    return Err("unterminated comment".to_string());
+                }
+            }
+            '"' | '\'' | '`' => {
+                let start = line;
+                let mut closed = false;
+                while let Some(s) = chars.next() {
+                    match s {
+                        '\\' => line += usize::from(chars.next() == Some('\n')),
+                        '\n' => {
+                            line += 1;
+                            if c != '`' {
+                                break;
+                            }
+                        }
+                        s if s == c => {
+                            closed = true;
+                            break;
+                        }
+                        _ => {}
+                    }
+                }
+                if !closed {
+                    return Err(format!("unterminated string on line {start}"));
+                }
+            }
+            '{' | '[' | '(' => stack.push((c, line)),
+            '}' | ']' | ')' => {
+                let expected = match c {
+                    '}' => '{',
+                    ']' => '[',
+                    _ => '(',
+                };
+                match stack.pop() {
+                    Some((open, _)) if open == expected => {}
+                    Some((open, opened)) => {
+                        return Err(format!(
+                            "'{c}' on line {line} does not close '{open}' from line {opened}"
+                        ));
+                    }
+                    None => return Err(format!("unexpected '{c}' on line {line}")),
+                }
+            }
+            _ => {}
+        }
+    }
+
+    match stack.pop() {
+        Some((open, opened)) => Err(format!("'{open}' on line {opened} is never closed")),
+        None => Ok(()),
+    }
+}
+
+/// Asks `kpackagetool6` to load the installed package. Skipped when the tool
+/// is not installed.
+fn kpackage_show(component: &InstalledComponent, kpackage_type: &str) -> Option<String> {
+    let global = super::privilege::is_system_path(&component.path);
+    let mut cmd = kpackagetool_cmd(kpackage_type, false);
+    if global {
+        cmd.arg("--global");
+    }
+    let output = cmd
+        .arg("--show")
+        .arg(resolve_plugin_id(component).as_ref())
+        .output()
+        .inspect_err(|e| log::debug!(target: "health", "kpackagetool6 unavailable: {e}"))
+        .ok()?;
+
+    (!output.status.success()).then(|| {
+        let stderr = String::from_utf8_lossy(&output.stderr);
+        format!("kpackagetool6 --show failed: {}", stderr.trim())
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn qml_syntax_accepts_strings_and_comments_with_brackets() {
+        let source = r#"
+import QtQuick
+// closing } in a comment
+Item {
+    /* and ( in a block */
+    property string label: "a { b"
+    property var list: [1, 2, (3)]
+    function f() { return `x ${'}'}`; }
+}
+"#;
+        assert_eq!(check_qml_syntax(source), Ok(()));
+    }
+
+    #[test]
+    fn qml_syntax_reports_unbalanced_brackets() {
+        assert_eq!(
+            check_qml_syntax("Item {\n    Rectangle {\n}\n"),
+            Err("'{' on line 1 is never closed".to_string())
+        );
+        assert!(check_qml_syntax("Item { ] }").is_err());
+        assert!(check_qml_syntax("Item { text: \"open }").is_err());
+        assert!(check_qml_syntax("  \n").is_err());
+    }
+
+    #[test]
+    fn widget_without_main_qml_is_unhealthy() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("org.example.clock");
+        fs::create_dir_all(path.join("contents/ui")).unwrap();
+        fs::write(path.join("metadata.json"), r#"{"KPlugin": {"Id": "x"}}"#).unwrap();
+        let component = InstalledComponent {
+            directory_name: "org.example.clock".to_string(),
+            ..InstalledComponent::fixture("Clock", ComponentType::PlasmaWidget, path.clone())
+        };
+
+        assert_eq!(required_files(&component), [["contents/ui/main.qml"]]);
+        let problems = check(&component);
+        assert_eq!(problems, ["missing contents/ui/main.qml"]);
+    }
+}
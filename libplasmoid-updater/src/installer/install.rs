@@ -19,6 +19,9 @@ use crate::{
 
 const COLOR_SCHEME_EXTENSIONS: &[&str] = &[".colors", ".colorscheme"];
 const IMAGE_EXTENSIONS: &[&str] = &[".jpg", ".jpeg", ".png", ".webp", ".svg"];
+const FONT_EXTENSIONS: &[&str] = &[".ttf", ".otf", ".ttc", ".otc", ".pfb", ".pcf", ".woff2"];
+/// Keys only found in the `.desktop` file of a KRunner D-Bus plugin.
+const KRUNNER_DBUS_KEYS: &[&str] = &["X-Plasma-DBusRunner-Service", "X-Plasma-API=DBus"];
 
 // --- Generic Recursive Directory Search ---
 
@@ -83,18 +86,24 @@ fn temp_sibling(path: &Path, suffix: &str) -> PathBuf {
 ///
 /// Copies `src` to a hidden sibling path (`.{name}.plasmoid-updater-new`), then
 /// renames it into place. On POSIX this rename is atomic and replaces any existing
-/// `dest` without a window where `dest` is absent.
+/// `dest` without a window where `dest` is absent. A replaced `dest` passes its
+/// owner and mode on to the new file.
 pub(super) fn atomic_install_file(src: &Path, dest: &Path) -> Result<()> {
     if let Some(parent) = dest.parent() {
         privilege::create_dir_all(parent)?;
     }
+    let previous = privilege::Ownership::of(dest);
     let temp = temp_sibling(dest, ".plasmoid-updater-new");
     // Clean up a leftover from a previous crash
     if temp.exists() {
         let _ = privilege::remove_file(&temp);
     }
     privilege::copy_file(src, &temp)?;
+    if let Some(previous) = previous {
+        previous.apply(&temp)?;
+    }
     privilege::rename(&temp, dest)?;
+    privilege::restore_security_context(dest);
     Ok(())
 }
 
@@ -103,10 +112,12 @@ pub(super) fn atomic_install_file(src: &Path, dest: &Path) -> Result<()> {
 /// Copies `src` contents to `.{name}.plasmoid-updater-new/`, renames the existing
 /// `dest` to `.{name}.plasmoid-updater-old/`, renames new into place, then removes old.
 /// All renames are within the same parent directory so they are on the same filesystem.
+/// The new directory gets the owner (recursively) and mode of the one it replaces.
 pub(super) fn atomic_install_dir(src: &Path, dest: &Path) -> Result<()> {
     if let Some(parent) = dest.parent() {
         privilege::create_dir_all(parent)?;
     }
+    let previous = privilege::Ownership::of(dest);
     let temp_new = temp_sibling(dest, ".plasmoid-updater-new");
     let temp_old = temp_sibling(dest, ".plasmoid-updater-old");
     // Clean up leftovers from a previous crash
@@ -119,11 +130,15 @@ pub(super) fn atomic_install_dir(src: &Path, dest: &Path) -> Result<()> {
     // Write new content to temp
     privilege::create_dir_all(&temp_new)?;
     privilege::copy_dir(src, &temp_new)?;
+    if let Some(previous) = previous {
+        previous.apply(&temp_new)?;
+    }
     // Atomic swap
     if dest.exists() || dest.symlink_metadata().is_ok() {
         privilege::rename(dest, &temp_old)?;
     }
     privilege::rename(&temp_new, dest)?;
+    privilege::restore_security_context(dest);
     // Best-effort cleanup of old
     if temp_old.exists() {
         let _ = privilege::remove_dir_all(&temp_old);
@@ -166,65 +181,141 @@ pub(super) fn patch_metadata(
 }
 
 /// Patches a `metadata.desktop` file to update the `X-KDE-PluginInfo-Version` field.
+///
+/// Only the `[Desktop Entry]` group is touched; the key is matched with spaces
+/// around `=` and appended to the group if missing. Line endings and every
+/// other line are kept as they are.
 pub(super) fn patch_metadata_desktop(metadata_path: &Path, new_version: &str) -> Result<()> {
+    const VERSION_KEY: &str = "X-KDE-PluginInfo-Version";
+
     let content = fs::read_to_string(metadata_path)?;
     let line_ending = if content.contains("\r\n") {
         "\r\n"
     } else {
         "\n"
     };
+    let version_line = format!("{VERSION_KEY}={new_version}");
+
+    let mut lines: Vec<String> = Vec::new();
+    let mut in_entry = false;
+    // Index after the last key of the `[Desktop Entry]` group.
+    let mut entry_end: Option<usize> = None;
     let mut found = false;
-    let patched: String = content
-        .lines()
-        .map(|line| {
-            if line.starts_with("X-KDE-PluginInfo-Version=") {
+    for line in content.lines() {
+        let trimmed = line.trim();
+        if trimmed.starts_with('[') {
+            in_entry = trimmed == "[Desktop Entry]";
+            if in_entry {
+                entry_end = Some(lines.len() + 1);
+            }
+        } else if in_entry && !trimmed.is_empty() && !trimmed.starts_with('#') {
+            entry_end = Some(lines.len() + 1);
+            if !found
+                && trimmed
+                    .split_once('=')
+                    .is_some_and(|(k, _)| k.trim() == VERSION_KEY)
+            {
                 found = true;
-                format!("X-KDE-PluginInfo-Version={new_version}")
-            } else {
-                line.to_string()
+                lines.push(version_line.clone());
+                continue;
             }
-        })
-        .collect::<Vec<_>>()
-        .join(line_ending);
-
-    // Preserve trailing newline if original had one
-    let patched = if content.ends_with('\n') && !patched.ends_with('\n') {
-        patched + line_ending
-    } else {
-        patched
-    };
+        }
+        lines.push(line.to_string());
+    }
 
     if !found {
-        log::debug!(target: "patch", "no X-KDE-PluginInfo-Version field in {}", metadata_path.display());
-        return Ok(());
+        let Some(index) = entry_end else {
+            log::debug!(target: "patch", "no [Desktop Entry] group in {}", metadata_path.display());
+            return Ok(());
+        };
+        lines.insert(index, version_line);
+    }
+
+    let mut patched = lines.join(line_ending);
+    // Preserve trailing newline if original had one
+    if content.ends_with('\n') {
+        patched.push_str(line_ending);
     }
 
     privilege::write_file(metadata_path, patched.as_bytes())?;
     Ok(())
 }
 
-// --- Plugin ID Resolution ---
+/// Generates `metadata.json` from a package's legacy `metadata.desktop`.
+///
+/// Plasma 6 only reads `metadata.json`, so Plasma 5-era packages that ship
+/// just the `.desktop` file stop loading. Returns `false` if the package
+/// already has a `metadata.json` or has no usable `metadata.desktop`.
+pub(super) fn migrate_metadata_desktop(
+    package_dir: &Path,
+    component_type: ComponentType,
+) -> Result<bool> {
+    let json_path = package_dir.join("metadata.json");
+    if json_path.exists() {
+        return Ok(false);
+    }
+    let Ok(content) = fs::read_to_string(package_dir.join("metadata.desktop")) else {
+        return Ok(false);
+    };
+    let Some(entry) = crate::checker::parse_desktop_entry(&content) else {
+        return Ok(false);
+    };
 
-/// Reads the KPlugin.Id from a component's metadata.json, falling back to directory_name.
-fn resolve_plugin_id(component: &InstalledComponent) -> Cow<'_, str> {
-    let metadata_path = component.path.join("metadata.json");
-    if let Ok(content) = fs::read_to_string(&metadata_path)
-        && let Ok(json) = serde_json::from_str::<serde_json::Value>(&content)
-        && let Some(id) = json
-            .get("KPlugin")
-            .and_then(|kp| kp.get("Id"))
-            .and_then(|v| v.as_str())
-        && !id.is_empty()
+    let mut kplugin = serde_json::Map::new();
+    for (json_key, desktop_key) in [
+        ("Id", "X-KDE-PluginInfo-Name"),
+        ("Name", "Name"),
+        ("Description", "Comment"),
+        ("Icon", "Icon"),
+        ("Version", "X-KDE-PluginInfo-Version"),
+        ("Category", "X-KDE-PluginInfo-Category"),
+        ("License", "X-KDE-PluginInfo-License"),
+        ("Website", "X-KDE-PluginInfo-Website"),
+    ] {
+        if let Some(value) = entry.get(desktop_key).filter(|v| !v.is_empty()) {
+            kplugin.insert(json_key.to_string(), (*value).into());
+        }
+    }
+    if let Some(author) = entry
+        .get("X-KDE-PluginInfo-Author")
+        .filter(|v| !v.is_empty())
     {
-        return Cow::Owned(id.to_string());
+        let mut author = serde_json::json!({ "Name": author });
+        if let Some(email) = entry
+            .get("X-KDE-PluginInfo-Email")
+            .filter(|v| !v.is_empty())
+        {
+            author["Email"] = (*email).into();
+        }
+        kplugin.insert("Authors".to_string(), serde_json::json!([author]));
+    }
+
+    let mut json = serde_json::json!({ "KPlugin": kplugin });
+    if let Some(kpackage_type) = component_type.kpackage_type() {
+        json["KPackageStructure"] = kpackage_type.into();
     }
-    Cow::Borrowed(&component.directory_name)
+    if let Some(script) = entry.get("X-Plasma-MainScript") {
+        json["X-Plasma-MainScript"] = (*script).into();
+    }
+
+    let generated = serde_json::to_string_pretty(&json)?;
+    privilege::write_file(&json_path, generated.as_bytes())?;
+    Ok(true)
+}
+
+// --- Plugin ID Resolution ---
+
+/// Reads the plugin ID from a component's package metadata, falling back to directory_name.
+pub(super) fn resolve_plugin_id(component: &InstalledComponent) -> Cow<'_, str> {
+    crate::checker::read_package_metadata(&component.path)
+        .and_then(|m| m.id().filter(|id| !id.is_empty()).map(str::to_string))
+        .map_or(Cow::Borrowed(&component.directory_name), Cow::Owned)
 }
 
 // --- kpackagetool Installation ---
 
 /// Builds a base `kpackagetool6` command with `-t <type>`, `sudo`, and `--global` as needed.
-fn kpackagetool_cmd(kpackage_type: &str, global: bool) -> std::process::Command {
+pub(super) fn kpackagetool_cmd(kpackage_type: &str, global: bool) -> std::process::Command {
     let mut cmd = if global {
         privilege::sudo_command("kpackagetool6")
     } else {
@@ -334,6 +425,39 @@ pub(super) fn install_via_kpackage(
 
 // --- Component Locators ---
 
+/// Finds the component in a new package of `component_type` extracted to
+/// `extract_dir`, returning its location and the name it gets installed
+/// under: its plugin ID if it has metadata, otherwise the name of the
+/// directory or file holding the component.
+pub(super) fn locate_new_package(
+    extract_dir: &Path,
+    component_type: ComponentType,
+) -> Option<(PathBuf, String)> {
+    let found = match component_type {
+        ComponentType::ColorScheme => locate_color_scheme_file(extract_dir),
+        ComponentType::KRunnerPlugin => locate_krunner_plugin(extract_dir, None),
+        ComponentType::Font => locate_font_file(extract_dir),
+        ComponentType::IconTheme => find_icon_theme_dir(extract_dir),
+        ComponentType::CursorTheme => find_cursor_theme_dir(extract_dir),
+        ComponentType::Wallpaper => find_wallpaper_source(extract_dir, None),
+        ct if ct.kpackage_type().is_some() => find_package_dir(extract_dir).or_else(|| {
+            ct.has_direct_fallback()
+                .then(|| find_component_root_in_archive(extract_dir, ct))
+                .flatten()
+        }),
+        ct => find_component_root_in_archive(extract_dir, ct),
+    }?;
+
+    let id = crate::checker::read_package_metadata(&found)
+        .and_then(|m| m.id().filter(|id| !id.is_empty()).map(str::to_string));
+    let name = id.or_else(|| {
+        (found != extract_dir)
+            .then(|| found.file_name()?.to_str().map(str::to_string))
+            .flatten()
+    })?;
+    Some((found, name))
+}
+
 /// Locates a color scheme file in an archive directory.
 ///
 /// First tries matching by file extension (`.colors`, `.colorscheme`).
@@ -366,6 +490,26 @@ fn locate_color_scheme_file(dir: &Path) -> Option<PathBuf> {
     })
 }
 
+/// Locates a KRunner plugin file in an archive directory.
+///
+/// Prefers a file named `file_name`, the one the installed plugin uses;
+/// otherwise takes the first `.desktop` file declaring a D-Bus runner.
+fn locate_krunner_plugin(dir: &Path, file_name: Option<&str>) -> Option<PathBuf> {
+    if let Some(file_name) = file_name
+        && let Some(path) = find_file_in_dir(dir, |path| {
+            path.file_name().and_then(|n| n.to_str()) == Some(file_name)
+        })
+    {
+        return Some(path);
+    }
+
+    find_file_in_dir(dir, |path| {
+        path.extension().is_some_and(|ext| ext == "desktop")
+            && fs::read_to_string(path)
+                .is_ok_and(|text| KRUNNER_DBUS_KEYS.iter().any(|key| text.contains(key)))
+    })
+}
+
 /// Finds the root directory of a component within an extracted archive.
 fn find_component_root_in_archive(
     extract_dir: &Path,
@@ -406,6 +550,9 @@ fn has_component_structure(dir: &Path, component_type: ComponentType) -> bool {
         ComponentType::KWinSwitcher => {
             dir.join("metadata.json").exists() || dir.join("contents").exists()
         }
+        ComponentType::CalendarPlugin => {
+            dir.join("metadata.json").exists() || dir.join("qmldir").exists()
+        }
         _ => false,
     }
 }
@@ -414,30 +561,66 @@ fn find_icon_theme_dir(extract_dir: &Path) -> Option<PathBuf> {
     find_in_dir(extract_dir, |d| d.join("index.theme").exists())
 }
 
-fn find_wallpaper_source(extract_dir: &Path) -> Option<PathBuf> {
-    // directory-based wallpaper (with contents/ or metadata.json)
-    if let Some(dir) = find_in_dir(extract_dir, |d| {
-        d.join("contents").exists() || d.join("metadata.json").exists()
-    }) {
+fn find_cursor_theme_dir(extract_dir: &Path) -> Option<PathBuf> {
+    find_in_dir(extract_dir, |d| d.join("cursors").is_dir())
+}
+
+/// Finds the wallpaper in an archive: a wallpaper package if there is one,
+/// otherwise a loose image. A wallpaper installed as a package (`dest` is a
+/// directory) only accepts a package, so a slideshow's images are never
+/// flattened into a single file.
+fn find_wallpaper_source(extract_dir: &Path, dest: Option<&Path>) -> Option<PathBuf> {
+    if let Some(dir) = find_in_dir(extract_dir, is_wallpaper_package) {
         return Some(dir);
     }
+    if dest.is_some_and(Path::is_dir) {
+        return None;
+    }
 
-    // single-file wallpaper (image file)
-    if let Ok(entries) = fs::read_dir(extract_dir) {
-        for entry in entries.flatten() {
-            let path = entry.path();
-            if path.is_file()
-                && let Some(name) = path.file_name().and_then(|n| n.to_str())
-            {
-                let lower = name.to_lowercase();
-                if IMAGE_EXTENSIONS.iter().any(|ext| lower.ends_with(ext)) {
-                    return Some(path);
-                }
-            }
-        }
+    // Single-file wallpaper: the image it was installed as, or the first
+    // one at the top of the archive.
+    let name = dest.and_then(Path::file_name);
+    if let Some(same_name) = name.and_then(|name| {
+        find_file_in_dir(extract_dir, |f| f.file_name() == Some(name) && is_image(f))
+    }) {
+        return Some(same_name);
     }
+    fs::read_dir(extract_dir)
+        .ok()?
+        .flatten()
+        .map(|entry| entry.path())
+        .find(|path| path.is_file() && is_image(path))
+}
 
-    None
+/// A KPackage wallpaper: `metadata.json` (or Plasma 5's `metadata.desktop`)
+/// next to `contents/images/<width>x<height>.<ext>`, with an optional
+/// `contents/images_dark/` for the dark variant.
+fn is_wallpaper_package(dir: &Path) -> bool {
+    dir.join("contents/images").is_dir()
+        || dir.join("contents/images_dark").is_dir()
+        || ((dir.join("metadata.json").is_file() || dir.join("metadata.desktop").is_file())
+            && dir.join("contents").is_dir())
+}
+
+/// Returns `true` if the wallpaper package at `dir` has at least one image
+/// in `contents/images` or `contents/images_dark`.
+pub(super) fn has_wallpaper_images(dir: &Path) -> bool {
+    ["contents/images", "contents/images_dark"]
+        .iter()
+        .any(|sub| {
+            fs::read_dir(dir.join(sub))
+                .into_iter()
+                .flatten()
+                .flatten()
+                .any(|entry| entry.path().is_file() && is_image(&entry.path()))
+        })
+}
+
+fn is_image(path: &Path) -> bool {
+    path.file_name().and_then(|n| n.to_str()).is_some_and(|n| {
+        let lower = n.to_lowercase();
+        IMAGE_EXTENSIONS.iter().any(|ext| lower.ends_with(ext))
+    })
 }
 
 // --- Direct Installation Methods ---
@@ -448,6 +631,13 @@ pub(super) fn install_direct(extract_dir: &Path, component: &InstalledComponent)
         ComponentType::ColorScheme => install_color_scheme(extract_dir, &component.path),
         ComponentType::IconTheme => install_icon_theme(extract_dir, &component.path),
         ComponentType::Wallpaper => install_wallpaper(extract_dir, component),
+        ComponentType::KRunnerPlugin => install_krunner_plugin(extract_dir, &component.path),
+        ComponentType::CursorTheme => install_cursor_theme(extract_dir, &component.path),
+        ComponentType::CalendarPlugin => {
+            install_theme_dir(extract_dir, &component.path, component.component_type)
+        }
+        ComponentType::Font => install_font(extract_dir, &component.path),
+        ComponentType::Other(_) => install_generic(extract_dir, &component.path),
         ComponentType::AuroraeDecoration
         | ComponentType::GlobalTheme
         | ComponentType::PlasmaStyle
@@ -471,6 +661,108 @@ fn install_color_scheme(extract_dir: &Path, dest_path: &Path) -> Result<()> {
     Ok(())
 }
 
+/// Replaces the plugin file only; a D-Bus service or script the package
+/// ships next to it is not installed.
+fn install_krunner_plugin(extract_dir: &Path, dest_path: &Path) -> Result<()> {
+    let file_name = dest_path.file_name().and_then(|n| n.to_str());
+    let plugin = locate_krunner_plugin(extract_dir, file_name)
+        .ok_or_else(|| Error::install("no KRunner plugin (.desktop) found in archive"))?;
+
+    atomic_install_file(&plugin, dest_path)?;
+    log::debug!(target: "install", "copied KRunner plugin to {}", dest_path.display());
+    Ok(())
+}
+
+/// Replaces the font file of the same name, or the archive's first font if
+/// it was renamed. A font recorded as a directory is replaced like
+/// [`install_generic`] does.
+fn install_font(extract_dir: &Path, dest_path: &Path) -> Result<()> {
+    if dest_path.is_dir() {
+        return install_generic(extract_dir, dest_path);
+    }
+
+    let name = dest_path.file_name();
+    let font = find_file_in_dir(extract_dir, |f| f.file_name() == name)
+        .or_else(|| locate_font_file(extract_dir))
+        .ok_or_else(|| Error::install("no font file found in archive"))?;
+
+    atomic_install_file(&font, dest_path)?;
+    log::debug!(target: "install", "copied font to {}", dest_path.display());
+    Ok(())
+}
+
+fn locate_font_file(extract_dir: &Path) -> Option<PathBuf> {
+    find_file_in_dir(extract_dir, |f| {
+        f.file_name()
+            .and_then(|n| n.to_str())
+            .is_some_and(|n| is_font_name(&n.to_lowercase()))
+    })
+}
+
+fn is_font_name(lower: &str) -> bool {
+    FONT_EXTENSIONS.iter().any(|ext| lower.ends_with(ext))
+}
+
+/// Replaces the file or directory an unmodelled registry entry records with
+/// the archive's entry of the same name, or the archive's only file or
+/// top-level directory.
+fn install_generic(extract_dir: &Path, dest_path: &Path) -> Result<()> {
+    let name = dest_path.file_name();
+    let not_found = || {
+        Error::install(format!(
+            "no {} found in archive",
+            dest_path.file_name().unwrap_or_default().to_string_lossy()
+        ))
+    };
+
+    if dest_path.is_dir() {
+        let source = find_in_dir(extract_dir, |d| d != extract_dir && d.file_name() == name)
+            .or_else(|| sole_entry(extract_dir).filter(|p| p.is_dir()))
+            .ok_or_else(not_found)?;
+        atomic_install_dir(&source, dest_path)?;
+    } else {
+        let source = find_file_in_dir(extract_dir, |f| f.file_name() == name)
+            .or_else(|| sole_file(extract_dir))
+            .ok_or_else(not_found)?;
+        atomic_install_file(&source, dest_path)?;
+    }
+    log::debug!(target: "install", "copied {} to {}", extract_dir.display(), dest_path.display());
+    Ok(())
+}
+
+/// Returns the only entry directly inside `dir`, if there is exactly one.
+fn sole_entry(dir: &Path) -> Option<PathBuf> {
+    let mut entries = fs::read_dir(dir).ok()?.flatten();
+    let first = entries.next()?;
+    entries.next().is_none().then(|| first.path())
+}
+
+/// Returns the only file anywhere below `dir`, if there is exactly one.
+fn sole_file(dir: &Path) -> Option<PathBuf> {
+    let mut found = None;
+    let mut pending = vec![dir.to_path_buf()];
+    while let Some(current) = pending.pop() {
+        for entry in fs::read_dir(&current).ok()?.flatten() {
+            let path = entry.path();
+            if path.is_dir() {
+                pending.push(path);
+            } else if found.replace(path).is_some() {
+                return None;
+            }
+        }
+    }
+    found
+}
+
+fn install_cursor_theme(extract_dir: &Path, dest_dir: &Path) -> Result<()> {
+    let source_dir = find_cursor_theme_dir(extract_dir)
+        .ok_or_else(|| Error::install("no cursor theme (cursors/) found in archive"))?;
+
+    atomic_install_dir(&source_dir, dest_dir)?;
+    log::debug!(target: "install", "copied cursor theme to {}", dest_dir.display());
+    Ok(())
+}
+
 fn install_icon_theme(extract_dir: &Path, dest_dir: &Path) -> Result<()> {
     let source_dir = find_icon_theme_dir(extract_dir)
         .ok_or_else(|| Error::install("no icon theme (index.theme) found in archive"))?;
@@ -481,15 +773,24 @@ fn install_icon_theme(extract_dir: &Path, dest_dir: &Path) -> Result<()> {
 }
 
 fn install_wallpaper(extract_dir: &Path, component: &InstalledComponent) -> Result<()> {
-    let source = find_wallpaper_source(extract_dir)
-        .ok_or_else(|| Error::install("no wallpaper found in archive"))?;
-
     let dest = &component.path;
+    let source = find_wallpaper_source(extract_dir, Some(dest)).ok_or_else(|| {
+        if dest.is_dir() {
+            Error::install("no wallpaper package (contents/images) found in archive")
+        } else {
+            Error::install("no wallpaper found in archive")
+        }
+    })?;
 
     if source.is_file() {
         atomic_install_file(&source, dest)?;
         log::debug!(target: "install", "copied wallpaper to {}", dest.display());
     } else {
+        if !has_wallpaper_images(&source) {
+            return Err(Error::install(
+                "wallpaper package has no images in contents/images",
+            ));
+        }
         atomic_install_dir(&source, dest)?;
         log::debug!(target: "install", "copied wallpaper dir to {}", dest.display());
     }
@@ -524,13 +825,22 @@ pub(super) fn is_single_file_component(path: &Path, component_type: ComponentTyp
         ComponentType::ColorScheme => COLOR_SCHEME_EXTENSIONS
             .iter()
             .any(|ext| lower.ends_with(ext)),
-        ComponentType::Wallpaper => IMAGE_EXTENSIONS.iter().any(|ext| lower.ends_with(ext)),
+        ComponentType::Wallpaper => is_image(path),
+        ComponentType::KRunnerPlugin => lower.ends_with(".desktop"),
+        ComponentType::Font => is_font_name(&lower),
         _ => false,
     }
 }
 
 pub(super) fn install_raw_file(downloaded: &Path, component: &InstalledComponent) -> Result<()> {
     let dest = &component.path;
+    // e.g. a slideshow wallpaper whose release is a lone preview image
+    if dest.is_dir() {
+        return Err(Error::install(format!(
+            "{} is a package directory; a single file can't replace it",
+            dest.display()
+        )));
+    }
     atomic_install_file(downloaded, dest)?;
     log::debug!(target: "install", "copied raw file to {}", dest.display());
     Ok(())
@@ -540,6 +850,172 @@ pub(super) fn install_raw_file(downloaded: &Path, component: &InstalledComponent
 mod tests {
     use super::*;
 
+    fn wallpaper_at(path: &Path) -> InstalledComponent {
+        InstalledComponent {
+            directory_name: path.file_name().unwrap().to_string_lossy().into_owned(),
+            ..InstalledComponent::fixture("Mountains", ComponentType::Wallpaper, path)
+        }
+    }
+
+    /// Writes a slideshow wallpaper package as store releases ship it.
+    fn write_wallpaper_package(root: &Path, images: &[&str]) {
+        fs::create_dir_all(root.join("contents/images")).unwrap();
+        fs::create_dir_all(root.join("contents/images_dark")).unwrap();
+        fs::write(
+            root.join("metadata.json"),
+            r#"{"KPlugin": {"Id": "Mountains", "Name": "Mountains"}}"#,
+        )
+        .unwrap();
+        fs::write(root.join("contents/screenshot.png"), "preview").unwrap();
+        for image in images {
+            fs::write(root.join("contents/images").join(image), *image).unwrap();
+            fs::write(root.join("contents/images_dark").join(image), *image).unwrap();
+        }
+    }
+
+    #[test]
+    fn wallpaper_package_keeps_its_structure() {
+        let dir = tempfile::tempdir().unwrap();
+        let extract = dir.path().join("extract");
+        fs::create_dir_all(&extract).unwrap();
+        fs::write(extract.join("README.jpg"), "not the wallpaper").unwrap();
+        write_wallpaper_package(
+            &extract.join("Mountains-2.0/Mountains"),
+            &["1920x1080.jpg", "3840x2160.jpg", "1080x1920.png"],
+        );
+        let dest = dir.path().join("wallpapers/Mountains");
+        write_wallpaper_package(&dest, &["1920x1080.jpg"]);
+
+        install_wallpaper(&extract, &wallpaper_at(&dest)).unwrap();
+
+        assert!(dest.join("metadata.json").is_file());
+        assert!(dest.join("contents/screenshot.png").is_file());
+        for image in ["1920x1080.jpg", "3840x2160.jpg", "1080x1920.png"] {
+            assert!(dest.join("contents/images").join(image).is_file());
+            assert!(dest.join("contents/images_dark").join(image).is_file());
+        }
+    }
+
+    #[test]
+    fn wallpaper_package_is_not_replaced_by_a_loose_image_or_empty_package() {
+        let dir = tempfile::tempdir().unwrap();
+        let dest = dir.path().join("wallpapers/Mountains");
+        write_wallpaper_package(&dest, &["1920x1080.jpg"]);
+        let component = wallpaper_at(&dest);
+
+        let loose = dir.path().join("loose");
+        fs::create_dir_all(&loose).unwrap();
+        fs::write(loose.join("1920x1080.jpg"), "flat").unwrap();
+        assert!(install_wallpaper(&loose, &component).is_err());
+
+        let empty = dir.path().join("empty");
+        write_wallpaper_package(&empty.join("Mountains"), &[]);
+        assert!(install_wallpaper(&empty, &component).is_err());
+
+        let raw = dir.path().join("preview.jpg");
+        fs::write(&raw, "flat").unwrap();
+        assert!(install_raw_file(&raw, &component).is_err());
+
+        assert!(dest.join("contents/images/1920x1080.jpg").is_file());
+    }
+
+    #[test]
+    fn single_image_wallpaper_prefers_its_installed_name() {
+        let dir = tempfile::tempdir().unwrap();
+        let extract = dir.path().join("extract");
+        fs::create_dir_all(extract.join("pack")).unwrap();
+        fs::write(extract.join("pack/Sunset.jpg"), "new").unwrap();
+        fs::write(extract.join("preview.png"), "preview").unwrap();
+        let dest = dir.path().join("wallpapers/Sunset.jpg");
+        fs::create_dir_all(dest.parent().unwrap()).unwrap();
+        fs::write(&dest, "old").unwrap();
+
+        install_wallpaper(&extract, &wallpaper_at(&dest)).unwrap();
+
+        assert_eq!(fs::read_to_string(&dest).unwrap(), "new");
+    }
+
+    #[test]
+    fn krunner_plugin_is_found_by_installed_name_or_dbus_keys() {
+        let dir = tempfile::tempdir().unwrap();
+        let src = dir.path().join("runner-1.2/package");
+        fs::create_dir_all(&src).unwrap();
+        fs::write(
+            dir.path().join("runner-1.2/README.desktop"),
+            "[Desktop Entry]\n",
+        )
+        .unwrap();
+        fs::write(
+            src.join("plasma-runner-translate.desktop"),
+            "[Desktop Entry]\nX-Plasma-API=DBus\nX-Plasma-DBusRunner-Service=org.example.translate\n",
+        )
+        .unwrap();
+        fs::write(src.join("other.desktop"), "[Desktop Entry]\n").unwrap();
+
+        let by_keys = locate_krunner_plugin(dir.path(), None).unwrap();
+        let by_name = locate_krunner_plugin(dir.path(), Some("other.desktop")).unwrap();
+
+        assert_eq!(by_keys, src.join("plasma-runner-translate.desktop"));
+        assert_eq!(by_name, src.join("other.desktop"));
+    }
+
+    #[test]
+    fn generic_install_replaces_recorded_file_or_dir() {
+        let dir = tempfile::tempdir().unwrap();
+        let extract = dir.path().join("extract");
+        fs::create_dir_all(extract.join("pkg/docs")).unwrap();
+        fs::write(extract.join("pkg/docs/README"), "readme").unwrap();
+        fs::write(extract.join("pkg/Inter.ttf"), "new font").unwrap();
+
+        let font = dir.path().join("fonts/Inter.ttf");
+        fs::create_dir_all(font.parent().unwrap()).unwrap();
+        fs::write(&font, "old font").unwrap();
+        install_generic(&extract, &font).unwrap();
+        assert_eq!(fs::read_to_string(&font).unwrap(), "new font");
+
+        let theme = dir.path().join("katepart5/syntax");
+        fs::create_dir_all(&theme).unwrap();
+        install_generic(&extract, &theme).unwrap();
+        assert!(theme.join("docs/README").exists());
+        assert!(theme.join("Inter.ttf").exists());
+    }
+
+    #[test]
+    fn font_is_replaced_by_name_or_first_font_file() {
+        let dir = tempfile::tempdir().unwrap();
+        let extract = dir.path().join("extract");
+        fs::create_dir_all(extract.join("Inter-4.0/extras")).unwrap();
+        fs::write(extract.join("Inter-4.0/LICENSE.txt"), "OFL").unwrap();
+        fs::write(extract.join("Inter-4.0/extras/Inter.ttf"), "new").unwrap();
+
+        let fonts = dir.path().join("fonts");
+        fs::create_dir_all(&fonts).unwrap();
+        let same_name = fonts.join("Inter.ttf");
+        let renamed = fonts.join("Inter-Regular.otf");
+        fs::write(&same_name, "old").unwrap();
+        fs::write(&renamed, "old").unwrap();
+
+        install_font(&extract, &same_name).unwrap();
+        install_font(&extract, &renamed).unwrap();
+
+        assert_eq!(fs::read_to_string(&same_name).unwrap(), "new");
+        assert_eq!(fs::read_to_string(&renamed).unwrap(), "new");
+        assert!(is_single_file_component(
+            Path::new("Inter.TTF"),
+            ComponentType::Font
+        ));
+    }
+
+    #[test]
+    fn cursor_theme_dir_is_found_by_cursors_subdir() {
+        let dir = tempfile::tempdir().unwrap();
+        let theme = dir.path().join("Bibata-Modern/Bibata-Modern-Ice");
+        fs::create_dir_all(theme.join("cursors")).unwrap();
+        fs::write(dir.path().join("Bibata-Modern/README"), "").unwrap();
+
+        assert_eq!(find_cursor_theme_dir(dir.path()), Some(theme));
+    }
+
     #[test]
     fn atomic_install_file_creates_dest() {
         let dir = tempfile::tempdir().unwrap();
@@ -556,6 +1032,47 @@ mod tests {
         assert!(!temp.exists());
     }
 
+    #[test]
+    fn atomic_install_file_keeps_mode_of_replaced_file() {
+        use std::os::unix::fs::PermissionsExt;
+
+        let dir = tempfile::tempdir().unwrap();
+        let src = dir.path().join("new.colors");
+        let dest = dir.path().join("scheme.colors");
+        std::fs::write(&src, b"new").unwrap();
+        std::fs::write(&dest, b"old").unwrap();
+        std::fs::set_permissions(&src, std::fs::Permissions::from_mode(0o600)).unwrap();
+        std::fs::set_permissions(&dest, std::fs::Permissions::from_mode(0o644)).unwrap();
+
+        atomic_install_file(&src, &dest).unwrap();
+
+        let mode = std::fs::metadata(&dest).unwrap().permissions().mode() & 0o7777;
+        assert_eq!(mode, 0o644);
+        assert_eq!(std::fs::read_to_string(&dest).unwrap(), "new");
+    }
+
+    #[test]
+    fn atomic_install_dir_keeps_mode_of_replaced_dir() {
+        use std::os::unix::fs::PermissionsExt;
+
+        let dir = tempfile::tempdir().unwrap();
+        let src = dir.path().join("src");
+        let dest = dir.path().join("widget");
+        std::fs::create_dir_all(&src).unwrap();
+        std::fs::write(src.join("main.qml"), b"new").unwrap();
+        std::fs::create_dir_all(&dest).unwrap();
+        std::fs::set_permissions(&dest, std::fs::Permissions::from_mode(0o750)).unwrap();
+
+        atomic_install_dir(&src, &dest).unwrap();
+
+        let mode = std::fs::metadata(&dest).unwrap().permissions().mode() & 0o7777;
+        assert_eq!(mode, 0o750);
+        assert_eq!(
+            std::fs::read_to_string(dest.join("main.qml")).unwrap(),
+            "new"
+        );
+    }
+
     #[test]
     fn atomic_install_file_replaces_existing() {
         let dir = tempfile::tempdir().unwrap();
@@ -659,6 +1176,64 @@ mod tests {
         assert!(!without_cr.contains('\n'));
     }
 
+    #[test]
+    fn patch_metadata_desktop_only_touches_desktop_entry_group() {
+        let dir = tempfile::tempdir().unwrap();
+        let file = dir.path().join("metadata.desktop");
+        std::fs::write(
+            &file,
+            "[Desktop Entry]\nName=Test\nX-KDE-PluginInfo-Version = 1.0\n\n\
+             [Desktop Action x]\nX-KDE-PluginInfo-Version=1.0\n",
+        )
+        .unwrap();
+        patch_metadata_desktop(&file, "2.0").unwrap();
+        assert_eq!(
+            std::fs::read_to_string(&file).unwrap(),
+            "[Desktop Entry]\nName=Test\nX-KDE-PluginInfo-Version=2.0\n\n\
+             [Desktop Action x]\nX-KDE-PluginInfo-Version=1.0\n"
+        );
+    }
+
+    #[test]
+    fn patch_metadata_desktop_adds_missing_version() {
+        let dir = tempfile::tempdir().unwrap();
+        let file = dir.path().join("metadata.desktop");
+        std::fs::write(&file, "[Desktop Entry]\nName=Test\n\n[Other]\nKey=1\n").unwrap();
+        patch_metadata_desktop(&file, "2.0").unwrap();
+        assert_eq!(
+            std::fs::read_to_string(&file).unwrap(),
+            "[Desktop Entry]\nName=Test\nX-KDE-PluginInfo-Version=2.0\n\n[Other]\nKey=1\n"
+        );
+    }
+
+    #[test]
+    fn migrate_metadata_desktop_generates_metadata_json() {
+        let dir = tempfile::tempdir().unwrap();
+        std::fs::write(
+            dir.path().join("metadata.desktop"),
+            "[Desktop Entry]\nName=Clock\nComment=Shows the time\n\
+             X-KDE-PluginInfo-Name=org.example.clock\nX-KDE-PluginInfo-Version=1.0\n\
+             X-KDE-PluginInfo-Author=Jane\nX-KDE-PluginInfo-Email=jane@example.org\n\
+             X-Plasma-MainScript=ui/main.qml\n",
+        )
+        .unwrap();
+
+        assert!(migrate_metadata_desktop(dir.path(), ComponentType::PlasmaWidget).unwrap());
+        let json: serde_json::Value = serde_json::from_str(
+            &std::fs::read_to_string(dir.path().join("metadata.json")).unwrap(),
+        )
+        .unwrap();
+        assert_eq!(json["KPackageStructure"], "Plasma/Applet");
+        assert_eq!(json["X-Plasma-MainScript"], "ui/main.qml");
+        assert_eq!(json["KPlugin"]["Id"], "org.example.clock");
+        assert_eq!(json["KPlugin"]["Version"], "1.0");
+        assert_eq!(json["KPlugin"]["Description"], "Shows the time");
+        assert_eq!(json["KPlugin"]["Authors"][0]["Email"], "jane@example.org");
+
+        // An existing metadata.json is never overwritten.
+        assert!(!migrate_metadata_desktop(dir.path(), ComponentType::PlasmaWidget).unwrap());
+    }
+
     #[test]
     fn patch_metadata_desktop_preserves_lf() {
         let dir = tempfile::tempdir().unwrap();
@@ -685,13 +1260,8 @@ mod tests {
         .unwrap();
 
         let component = InstalledComponent {
-            name: "Test".to_string(),
             directory_name: "org.kde.wrong.name".to_string(),
-            version: "1.0".to_string(),
-            component_type: ComponentType::PlasmaWidget,
-            path: dir.path().to_path_buf(),
-            is_system: false,
-            release_date: String::new(),
+            ..InstalledComponent::fixture("Test", ComponentType::PlasmaWidget, dir.path())
         };
 
         let id = resolve_plugin_id(&component);
@@ -703,13 +1273,8 @@ mod tests {
         let dir = tempfile::tempdir().unwrap();
         // No metadata.json
         let component = InstalledComponent {
-            name: "Test".to_string(),
             directory_name: "org.kde.fallback".to_string(),
-            version: "1.0".to_string(),
-            component_type: ComponentType::PlasmaWidget,
-            path: dir.path().to_path_buf(),
-            is_system: false,
-            release_date: String::new(),
+            ..InstalledComponent::fixture("Test", ComponentType::PlasmaWidget, dir.path())
         };
 
         let id = resolve_plugin_id(&component);
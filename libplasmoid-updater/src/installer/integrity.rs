@@ -0,0 +1,237 @@
+// SPDX-License-Identifier: GPL-3.0-or-later
+
+use std::{
+    collections::BTreeMap,
+    fs,
+    io::Read as _,
+    path::{Path, PathBuf},
+};
+
+use serde::{Deserialize, Serialize};
+
+use crate::{
+    Result,
+    types::{ComponentType, InstalledComponent},
+};
+
+/// File hashes of a component as it was installed, see [`record`].
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+struct FileManifest {
+    version: String,
+    /// When the hashes were taken (RFC 3339).
+    recorded_at: String,
+    /// MD5 of every file, keyed by its path relative to the component.
+    files: BTreeMap<String, String>,
+}
+
+/// Result of comparing an installed component against the file hashes
+/// recorded when it was last installed or updated.
+///
+/// Returned by [`verify()`](crate::verify).
+#[derive(Debug, Clone, Serialize)]
+pub struct IntegrityReport {
+    pub name: String,
+    pub directory_name: String,
+    pub component_type: ComponentType,
+    /// Version the hashes were recorded for; `None` if the component was
+    /// never installed or updated by this tool, so there is nothing to
+    /// compare against.
+    pub recorded_version: Option<String>,
+    /// Files whose contents changed, relative to the component directory.
+    pub modified: Vec<String>,
+    /// Recorded files that no longer exist.
+    pub missing: Vec<String>,
+    /// Files that were not there when the hashes were recorded.
+    pub extra: Vec<String>,
+}
+
+impl IntegrityReport {
+    /// Returns `true` if hashes were recorded and every file still matches.
+    pub fn is_intact(&self) -> bool {
+        self.recorded_version.is_some()
+            && self.modified.is_empty()
+            && self.missing.is_empty()
+            && self.extra.is_empty()
+    }
+}
+
+/// Returns the directory holding the recorded hashes.
+///
+/// User components are tracked under `$XDG_STATE_HOME/plasmoid-updater/integrity`,
+/// system components next to the system registry.
+fn integrity_dir(system: bool) -> PathBuf {
+    if system {
+        crate::paths::system_state_dir("integrity")
+    } else {
+        crate::paths::state_dir("integrity")
+    }
+}
+
+fn manifest_path(component: &InstalledComponent) -> PathBuf {
+    integrity_dir(component.is_system).join(format!("{}.json", component.directory_name))
+}
+
+/// Hashes the files of `component` as installed at `version` and stores them
+/// for [`verify`], replacing any earlier record.
+pub(crate) fn record(component: &InstalledComponent, version: &str) -> Result<()> {
+    let manifest = FileManifest {
+        version: version.to_string(),
+        recorded_at: chrono::Local::now().to_rfc3339(),
+        files: hash_files(&component.path)?,
+    };
+    let path = manifest_path(component);
+    if let Some(parent) = path.parent() {
+        fs::create_dir_all(parent)?;
+    }
+    fs::write(&path, serde_json::to_vec_pretty(&manifest)?)?;
+    log::debug!(
+        target: "integrity",
+        "recorded {} files of {}",
+        manifest.files.len(),
+        component.name
+    );
+    Ok(())
+}
+
+/// Drops the recorded hashes of a removed component.
+pub(crate) fn forget(component: &InstalledComponent) {
+    let path = manifest_path(component);
+    if let Err(e) = fs::remove_file(&path)
+        && e.kind() != std::io::ErrorKind::NotFound
+    {
+        log::debug!(target: "integrity", "failed to remove {}: {e}", path.display());
+    }
+}
+
+/// Compares the files of `component` against its recorded hashes.
+pub(crate) fn verify(component: &InstalledComponent) -> Result<IntegrityReport> {
+    let mut report = IntegrityReport {
+        name: component.name.clone(),
+        directory_name: component.directory_name.clone(),
+        component_type: component.component_type,
+        recorded_version: None,
+        modified: Vec::new(),
+        missing: Vec::new(),
+        extra: Vec::new(),
+    };
+
+    let Ok(content) = fs::read(manifest_path(component)) else {
+        return Ok(report);
+    };
+    let recorded: FileManifest = serde_json::from_slice(&content)?;
+    let current = hash_files(&component.path)?;
+
+    for (file, hash) in &recorded.files {
+        match current.get(file) {
+            Some(current_hash) if current_hash == hash => {}
+            Some(_) => report.modified.push(file.clone()),
+            None => report.missing.push(file.clone()),
+        }
+    }
+    report.extra = current
+        .into_keys()
+        .filter(|file| !recorded.files.contains_key(file))
+        .collect();
+    report.recorded_version = Some(recorded.version);
+    Ok(report)
+}
+
+/// Hashes every file under `root`, or `root` itself for single-file
+/// components. Symlinks are not followed; their target path is hashed.
+fn hash_files(root: &Path) -> Result<BTreeMap<String, String>> {
+    let mut files = BTreeMap::new();
+    if !root.is_dir() {
+        if root.symlink_metadata().is_ok() {
+            let name = root
+                .file_name()
+                .map(|n| n.to_string_lossy().into_owned())
+                .unwrap_or_default();
+            files.insert(name, hash_file(root)?);
+        }
+        return Ok(files);
+    }
+    walk(root, root, &mut files)?;
+    Ok(files)
+}
+
+fn walk(root: &Path, dir: &Path, files: &mut BTreeMap<String, String>) -> Result<()> {
+    for entry in fs::read_dir(dir)? {
+        let path = entry?.path();
+        let meta = path.symlink_metadata()?;
+        if meta.is_dir() {
+            walk(root, &path, files)?;
+            continue;
+        }
+        let relative = path.strip_prefix(root).unwrap_or(&path);
+        files.insert(relative.to_string_lossy().into_owned(), hash_file(&path)?);
+    }
+    Ok(())
+}
+
+fn hash_file(path: &Path) -> Result<String> {
+    let meta = path.symlink_metadata()?;
+    if meta.is_symlink() {
+        let target = fs::read_link(path)?;
+        return Ok(format!(
+            "{:x}",
+            md5::compute(target.as_os_str().as_encoded_bytes())
+        ));
+    }
+
+    let mut file = fs::File::open(path)?;
+    let mut hasher = md5::Context::new();
+    let mut buffer = [0u8; 64 * 1024];
+    loop {
+        let n = file.read(&mut buffer)?;
+        if n == 0 {
+            break;
+        }
+        hasher.consume(&buffer[..n]);
+    }
+    Ok(format!("{:x}", hasher.finalize()))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn hashes_detect_modified_missing_and_extra_files() {
+        let dir = tempfile::tempdir().unwrap();
+        let root = dir.path().join("org.example.clock");
+        fs::create_dir_all(root.join("contents/ui")).unwrap();
+        fs::write(root.join("metadata.json"), "{}").unwrap();
+        fs::write(root.join("contents/ui/main.qml"), "Item {}").unwrap();
+        let recorded = hash_files(&root).unwrap();
+        assert_eq!(
+            recorded.keys().collect::<Vec<_>>(),
+            ["contents/ui/main.qml", "metadata.json"]
+        );
+
+        fs::write(root.join("contents/ui/main.qml"), "Rectangle {}").unwrap();
+        fs::remove_file(root.join("metadata.json")).unwrap();
+        fs::write(root.join("notes.txt"), "").unwrap();
+        let current = hash_files(&root).unwrap();
+
+        assert_ne!(
+            recorded["contents/ui/main.qml"],
+            current["contents/ui/main.qml"]
+        );
+        assert!(!current.contains_key("metadata.json"));
+        assert!(current.contains_key("notes.txt"));
+    }
+
+    #[test]
+    fn single_file_component_is_keyed_by_file_name() {
+        let dir = tempfile::tempdir().unwrap();
+        let file = dir.path().join("Nord.colors");
+        fs::write(&file, "[Colors:Window]").unwrap();
+
+        let files = hash_files(&file).unwrap();
+        assert_eq!(files.keys().collect::<Vec<_>>(), ["Nord.colors"]);
+        assert_eq!(
+            files["Nord.colors"],
+            format!("{:x}", md5::compute("[Colors:Window]"))
+        );
+    }
+}
@@ -0,0 +1,235 @@
+// SPDX-License-Identifier: GPL-3.0-or-later
+
+use std::{
+    fs,
+    path::{Path, PathBuf},
+};
+
+use serde::{Deserialize, Serialize};
+
+use crate::{
+    Result, registry,
+    types::{AvailableUpdate, InstalledComponent},
+};
+
+use super::{backup::restore_component, integrity, remove_files};
+
+/// An install, update or downgrade that was interrupted before it finished,
+/// e.g. by a crash or power loss, leaving the component half written.
+///
+/// Returned by [`pending_recovery()`](crate::pending_recovery) and undone by
+/// [`recover()`](crate::recover).
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct IncompleteTransaction {
+    /// The component as it was before the transaction started; for a new
+    /// install, the component being installed.
+    pub component: InstalledComponent,
+    /// KDE Store content ID being installed, `0` if not from the store.
+    pub content_id: u64,
+    /// Version that was being installed.
+    pub target_version: String,
+    /// Backup of the component taken before the transaction, restored by
+    /// recovery. `None` for a new install: recovery removes the partial
+    /// files instead.
+    pub backup: Option<PathBuf>,
+    /// When the transaction started (RFC 3339).
+    pub started_at: String,
+    /// Process that ran the transaction.
+    pub pid: u32,
+}
+
+/// Journal entry of a running transaction, see [`begin`].
+///
+/// Dropping it without [`finish`](Self::finish) leaves the entry in place,
+/// so an unwinding panic is recovered like a crash.
+#[must_use = "the journal entry must be finished once the install is complete"]
+pub(crate) struct Transaction {
+    path: PathBuf,
+}
+
+impl Transaction {
+    /// Removes the journal entry once the component files are consistent
+    /// again, either fully installed or restored.
+    pub(crate) fn finish(self) {
+        remove_entry(&self.path);
+    }
+}
+
+/// Returns the directory holding the journal.
+///
+/// User components are tracked under `$XDG_STATE_HOME/plasmoid-updater/journal`,
+/// system components next to the system registry.
+fn journal_dir(system: bool) -> PathBuf {
+    if system {
+        crate::paths::system_state_dir("journal")
+    } else {
+        crate::paths::state_dir("journal")
+    }
+}
+
+fn entry_path(component: &InstalledComponent) -> PathBuf {
+    let key = md5::compute(component.path.as_os_str().as_encoded_bytes());
+    journal_dir(component.is_system).join(format!("{key:x}.json"))
+}
+
+/// Records that `update` is about to overwrite `update.installed`, which was
+/// backed up to `backup`. Called before the first destructive step.
+pub(crate) fn begin(update: &AvailableUpdate, backup: Option<&Path>) -> Result<Transaction> {
+    let transaction = IncompleteTransaction {
+        component: update.installed.clone(),
+        content_id: update.content_id,
+        target_version: update.latest_version.clone(),
+        backup: backup.map(Path::to_path_buf),
+        started_at: chrono::Local::now().to_rfc3339(),
+        pid: std::process::id(),
+    };
+    let path = entry_path(&transaction.component);
+    if let Some(parent) = path.parent() {
+        fs::create_dir_all(parent)?;
+    }
+    // Written to a sibling first so a crash never leaves a truncated entry.
+    let partial = path.with_extension("json.partial");
+    fs::write(&partial, serde_json::to_vec_pretty(&transaction)?)?;
+    fs::rename(&partial, &path)?;
+    Ok(Transaction { path })
+}
+
+/// Returns the transactions of `system` or user components whose process is
+/// gone without finishing them, oldest first.
+pub(crate) fn incomplete(system: bool) -> Vec<IncompleteTransaction> {
+    incomplete_in(&journal_dir(system))
+}
+
+fn incomplete_in(dir: &Path) -> Vec<IncompleteTransaction> {
+    let Ok(entries) = fs::read_dir(dir) else {
+        return Vec::new();
+    };
+    let mut transactions: Vec<IncompleteTransaction> = entries
+        .flatten()
+        .map(|e| e.path())
+        .filter(|path| path.extension().is_some_and(|ext| ext == "json"))
+        .filter_map(|path| {
+            let content = fs::read(&path).ok()?;
+            match serde_json::from_slice(&content) {
+                Ok(transaction) => Some(transaction),
+                Err(e) => {
+                    log::warn!(target: "journal", "ignoring {}: {e}", path.display());
+                    None
+                }
+            }
+        })
+        .filter(|t: &IncompleteTransaction| !is_running(t.pid))
+        .collect();
+    transactions.sort_by(|a, b| a.started_at.cmp(&b.started_at));
+    transactions
+}
+
+/// Returns `true` if `pid` is another live process, whose transaction may
+/// still be in progress.
+fn is_running(pid: u32) -> bool {
+    pid != std::process::id() && Path::new("/proc").join(pid.to_string()).exists()
+}
+
+/// Undoes an interrupted transaction: restores the backup, or removes the
+/// partial files of a new install, then drops the journal entry.
+pub(crate) fn recover(transaction: &IncompleteTransaction) -> Result<()> {
+    let component = &transaction.component;
+    match &transaction.backup {
+        Some(backup) => {
+            restore_component(backup, &component.path)?;
+            if transaction.content_id != 0 {
+                let previous = AvailableUpdate::builder(
+                    component.clone(),
+                    transaction.content_id,
+                    component.version.clone(),
+                    String::new(),
+                    component.release_date.clone(),
+                )
+                .build();
+                if let Err(e) = registry::update_registry_after_install(&previous) {
+                    log::warn!(target: "registry", "failed to update: {e}");
+                }
+            }
+            super::record_integrity(component, &component.version);
+        }
+        None => {
+            if component.path.symlink_metadata().is_ok() {
+                remove_files(&component.path)?;
+            }
+            integrity::forget(component);
+            if let Err(e) = registry::unregister_component(component) {
+                log::warn!(target: "registry", "failed to update: {e}");
+            }
+        }
+    }
+
+    remove_entry(&entry_path(component));
+    log::info!(
+        target: "journal",
+        "recovered {} from an interrupted install of {}",
+        component.name,
+        transaction.target_version
+    );
+    Ok(())
+}
+
+fn remove_entry(path: &Path) {
+    if let Err(e) = fs::remove_file(path)
+        && e.kind() != std::io::ErrorKind::NotFound
+    {
+        log::warn!(target: "journal", "failed to remove {}: {e}", path.display());
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn transaction(pid: u32, started_at: &str) -> IncompleteTransaction {
+        IncompleteTransaction {
+            component: InstalledComponent {
+                directory_name: "org.example.clock".to_string(),
+                ..InstalledComponent::fixture(
+                    "Clock",
+                    crate::types::ComponentType::PlasmaWidget,
+                    "/tmp/org.example.clock",
+                )
+            },
+            content_id: 1,
+            target_version: "2.0".to_string(),
+            backup: None,
+            started_at: started_at.to_string(),
+            pid,
+        }
+    }
+
+    #[test]
+    fn incomplete_skips_live_processes_and_sorts_by_start() {
+        let dir = tempfile::tempdir().unwrap();
+        // PID 1 is always running; u32::MAX never is.
+        let entries = [
+            ("a.json", transaction(u32::MAX, "2026-01-02T00:00:00+00:00")),
+            ("b.json", transaction(1, "2026-01-01T00:00:00+00:00")),
+            ("c.json", transaction(u32::MAX, "2026-01-01T00:00:00+00:00")),
+        ];
+        for (name, t) in &entries {
+            fs::write(dir.path().join(name), serde_json::to_vec(t).unwrap()).unwrap();
+        }
+        fs::write(dir.path().join("d.json"), "not json").unwrap();
+        fs::write(dir.path().join("e.json.partial"), "{").unwrap();
+
+        let pending = incomplete_in(dir.path());
+
+        let started: Vec<&str> = pending.iter().map(|t| t.started_at.as_str()).collect();
+        assert_eq!(
+            started,
+            ["2026-01-01T00:00:00+00:00", "2026-01-02T00:00:00+00:00"]
+        );
+        assert!(pending.iter().all(|t| t.pid == u32::MAX));
+    }
+
+    #[test]
+    fn own_process_is_not_running_elsewhere() {
+        assert!(!is_running(std::process::id()));
+    }
+}
@@ -5,32 +5,65 @@
 // GPL-2.0-only OR GPL-3.0-only OR LicenseRef-KDE-Accepted-GPL
 
 mod backup;
+mod cache;
+mod conflicts;
 mod download;
+mod download_cache;
+mod health;
 mod inhibit;
 mod install;
+mod integrity;
+mod journal;
 mod lock;
-mod plasmashell;
+mod pins;
+mod policy;
+mod prefetch;
 pub(crate) mod privilege;
+mod restart;
+mod sddm;
+mod trash;
+mod versions;
 
 use std::{
     fs,
     io::Read as _,
     path::{Path, PathBuf},
-    sync::atomic::AtomicUsize,
 };
 
 use crate::{
+    Config, RemovalMode,
+    api::ApiCounters,
+    error::{ErrorContext, Operation, ResultExt as _},
     registry,
     types::{AvailableUpdate, ComponentType, InstalledComponent},
     {Error, Result},
 };
-use backup::{backup_component, restore_component};
+use backup::{backup_component, latest_backup, restore_component};
+use policy::DownloadPolicy;
 
 use crate::version::normalize_version;
 
+pub(crate) use backup::{backup_base_dir as backup_dir, backup_layout, backups};
+pub use conflicts::FileConflict;
+pub(crate) use conflicts::find_conflicts;
+pub(crate) use download_cache::{clear as clear_download_cache, prune as prune_cache};
 pub(crate) use inhibit::InhibitGuard;
+pub use integrity::IntegrityReport;
+pub(crate) use integrity::verify;
+pub use journal::IncompleteTransaction;
+#[cfg(feature = "test-support")]
+pub(crate) use journal::begin as begin_transaction;
+pub(crate) use journal::{incomplete as incomplete_transactions, recover as recover_transaction};
 pub(crate) use lock::UpdateLock;
-pub(crate) use plasmashell::{any_requires_restart, restart_plasmashell};
+pub use pins::PayloadPin;
+pub(crate) use pins::pin as payload_pin;
+pub(crate) use prefetch::Prefetcher;
+pub(crate) use restart::{
+    RestartHealth, describe as describe_restart_action, monitor_restart, required_actions,
+    restart_with_layout, run_action,
+};
+pub use versions::RetainedVersion;
+pub(crate) use versions::{downgrade, retained_versions, versions_dir};
 
 /// Outcome of a single component update, including post-install verification.
 pub(crate) struct InstallOutcome {
@@ -40,6 +73,12 @@ pub(crate) struct InstallOutcome {
     pub expected_version: String,
     /// The version actually found on disk after install, if readable.
     pub actual_version: Option<String>,
+    /// Health check problems of the installed component, empty if healthy.
+    pub problems: Vec<String>,
+    /// Size of the downloaded package in bytes.
+    pub bytes: u64,
+    /// SHA-256 of the downloaded package, hex encoded.
+    pub sha256: String,
 }
 
 /// Updates a single component using the provided HTTP client.
@@ -49,37 +88,486 @@ pub(crate) struct InstallOutcome {
 /// - `2` --- download done, extraction starting
 /// - `3` --- extraction done, install starting
 ///
-/// `counter` is incremented once for each HTTP request made.
+/// Requests and downloaded bytes are added to `stats`.
+///
+/// The configured [`Hooks`](crate::Hooks) run around the update; a failing
+/// `pre_update` hook fails the update before anything is touched.
 pub(crate) fn update_component(
     update: &AvailableUpdate,
     client: &reqwest::blocking::Client,
+    config: &Config,
+    reporter: impl Fn(u8),
+    stats: &ApiCounters,
+) -> Result<InstallOutcome> {
+    with_hooks(update, config, || {
+        run_update(update, client, config, reporter, stats, None)
+    })
+}
+
+/// Installs the payload recorded in `pin` over `update.installed` again,
+/// like [`update_component`].
+///
+/// The package is downloaded afresh and must hash to `pin.sha256`; if the
+/// store now serves something else for the same version, this fails with
+/// [`Error::PayloadChanged`] before the installed files are touched.
+pub(crate) fn reinstall_component(
+    pin: &PayloadPin,
+    component: &InstalledComponent,
+    client: &reqwest::blocking::Client,
+    config: &Config,
+    stats: &ApiCounters,
+) -> Result<InstallOutcome> {
+    let update = pin.to_update(component.clone());
+    with_hooks(&update, config, || {
+        run_update(&update, client, config, |_| {}, stats, Some(&pin.sha256))
+    })
+}
+
+fn with_hooks(
+    update: &AvailableUpdate,
+    config: &Config,
+    run: impl FnOnce() -> Result<InstallOutcome>,
+) -> Result<InstallOutcome> {
+    let result = config
+        .hooks
+        .pre_update(update)
+        .context(|| ErrorContext::operation(Operation::Hook))
+        .and_then(|()| run())
+        .context(|| ErrorContext::component(&update.installed.name));
+    match &result {
+        Ok(_) => config.hooks.post_update(update),
+        Err(e) => config.hooks.on_failure(update, e),
+    }
+    result
+}
+
+fn run_update(
+    update: &AvailableUpdate,
+    client: &reqwest::blocking::Client,
+    config: &Config,
     reporter: impl Fn(u8),
-    counter: &AtomicUsize,
+    stats: &ApiCounters,
+    pinned: Option<&str>,
 ) -> Result<InstallOutcome> {
     let component = &update.installed;
-    let temp = download::create_temp_dir()?;
+    let temp = download::create_temp_dir(&download::temp_root(config.temp_dir.as_deref()))?;
+    let policy = DownloadPolicy::from_config(config);
+    let verified = pinned
+        .map(|sha256| download_pinned(client, &policy, update, sha256, stats, temp.path()))
+        .transpose()?;
 
-    let backup_path = create_backup(component)?;
+    let backup_path = create_backup(component)
+        .context(|| ErrorContext::operation(Operation::Backup).target(component.path.display()))?;
+    if config.keep_versions > 0 {
+        let current = versions::current_record(component, update.content_id);
+        retain_version(component, &current, config.keep_versions);
+    }
+    let transaction = journal::begin(update, backup_path.as_deref())?;
     reporter(1);
 
-    match perform_installation(update, client, &reporter, counter, temp.path()) {
-        Ok(()) => {
-            post_install_tasks(update)?;
-            let outcome = verify_installed_version(update);
+    let installed = perform_installation(
+        update,
+        client,
+        &policy,
+        &reporter,
+        stats,
+        temp.path(),
+        verified,
+    )
+    .and_then(|payload| {
+        check_installed(component, config)
+            .context(|| {
+                ErrorContext::operation(Operation::HealthCheck).target(component.path.display())
+            })
+            .map(|problems| (payload, problems))
+    });
+    match installed {
+        Ok(((bytes, sha256), problems)) => {
+            transaction.finish();
+            trash_replaced(component, backup_path.as_deref(), config);
+            post_install_tasks(update, config)
+                .context(|| ErrorContext::operation(Operation::PostInstall))?;
+            if config.keep_versions > 0 {
+                let installed = versions::installed_record(update);
+                retain_version(component, &installed, config.keep_versions);
+            }
+            if update.content_id != 0 {
+                pins::record(update, &sha256);
+            }
+            let mut outcome = verify_installed_version(update);
+            outcome.problems = problems;
+            outcome.bytes = bytes;
+            outcome.sha256 = sha256;
             log::info!(target: "update", "updated {}", component.name);
             Ok(outcome)
         }
         Err(e) => {
             log::error!(target: "install", "failed for {}: {e}", component.name);
             if let Some(ref backup) = backup_path {
+                // A failed restore keeps the journal entry for a later recovery.
                 handle_installation_failure(backup, &component.path, &e)?;
             }
+            transaction.finish();
             Err(e)
         }
     }
     // temp is dropped here, auto-cleanup
 }
 
+/// Puts back the version `update` replaced, from the backup taken before
+/// installing it, and records that version in the KNewStuff registry.
+pub(crate) fn rollback_update(update: &AvailableUpdate) -> Result<()> {
+    let component = &update.installed;
+    let backup = latest_backup(component)
+        .ok_or_else(|| Error::backup(format!("no backup of {} to roll back to", component.name)))?;
+    restore_component(&backup, &component.path)?;
+
+    // The store download of the replaced version is not known.
+    let previous = AvailableUpdate::builder(
+        component.clone(),
+        update.content_id,
+        component.version.clone(),
+        String::new(),
+        component.release_date.clone(),
+    )
+    .build();
+    if let Err(e) = registry::update_registry_after_install(&previous) {
+        log::warn!(target: "registry", "failed to update: {e}");
+    }
+    record_integrity(component, &component.version);
+
+    log::info!(
+        target: "restore",
+        "rolled back {} to {}",
+        component.name,
+        component.version
+    );
+    Ok(())
+}
+
+/// Downloads and installs a component that is not installed yet.
+///
+/// `update.installed` only needs the name and type: the directory name is
+/// read from the package, and the component goes under the user or system
+/// path of its type depending on [`Config::system`]. Returns the installed
+/// component.
+pub(crate) fn install_new(
+    update: &AvailableUpdate,
+    client: &reqwest::blocking::Client,
+    config: &Config,
+    stats: &ApiCounters,
+) -> Result<InstalledComponent> {
+    let temp = download::create_temp_dir(&download::temp_root(config.temp_dir.as_deref()))?;
+    let policy = DownloadPolicy::from_config(config);
+    let downloaded = download_with_error_handling(client, &policy, update, stats, temp.path())?;
+    let sha256 = pins::payload_sha256(&downloaded)?;
+    let package = unpack_new(&downloaded, update.installed.component_type, temp.path())?;
+    let component = install_unpacked(&package, update, config)?;
+    pins::record(
+        &AvailableUpdate {
+            installed: component.clone(),
+            ..update.clone()
+        },
+        &sha256,
+    );
+    Ok(component)
+}
+
+/// Installs a component from a package file on disk, for
+/// [`install_from_file()`](crate::install_from_file).
+///
+/// The name and version come from the package metadata. The component is
+/// not linked to a store entry; its KNewStuff registry entry is left to the
+/// caller.
+pub(crate) fn install_from_file(
+    path: &Path,
+    component_type: ComponentType,
+    config: &Config,
+) -> Result<InstalledComponent> {
+    if !path.is_file() {
+        return Err(Error::install(format!("{} is not a file", path.display())));
+    }
+
+    let temp = download::create_temp_dir(&download::temp_root(config.temp_dir.as_deref()))?;
+    let package = unpack_new(path, component_type, temp.path())?;
+    let metadata = crate::checker::read_package_metadata(&package.root);
+    let component = InstalledComponent {
+        name: metadata
+            .as_ref()
+            .and_then(|m| m.name())
+            .unwrap_or(&package.name)
+            .to_string(),
+        directory_name: package.name.clone(),
+        version: String::new(),
+        component_type,
+        path: PathBuf::new(),
+        is_system: config.system,
+        release_date: String::new(),
+        managed_by_distro: false,
+        metadata: Default::default(),
+        shadowed: None,
+        in_use: false,
+    };
+    let version = metadata
+        .as_ref()
+        .and_then(|m| m.version())
+        .unwrap_or_default()
+        .to_string();
+    let update =
+        AvailableUpdate::builder(component, 0, version, String::new(), String::new()).build();
+    install_unpacked(&package, &update, config)
+}
+
+/// A package unpacked for a fresh install.
+struct NewPackage {
+    /// The package file itself for raw-file components, otherwise the
+    /// directory it was extracted to.
+    source: PathBuf,
+    raw: bool,
+    /// The component inside `source`.
+    root: PathBuf,
+    /// Directory or file name the component is installed under.
+    name: String,
+}
+
+fn unpack_new(
+    package_path: &Path,
+    component_type: ComponentType,
+    temp_path: &Path,
+) -> Result<NewPackage> {
+    let not_found = || Error::install(format!("no {component_type} found in the package"));
+
+    if is_raw_file(package_path, component_type) {
+        let name = package_path
+            .file_name()
+            .and_then(|n| n.to_str())
+            .ok_or_else(not_found)?;
+        return Ok(NewPackage {
+            source: package_path.to_path_buf(),
+            raw: true,
+            root: package_path.to_path_buf(),
+            name: name.to_string(),
+        });
+    }
+
+    let extract_dir = temp_path.join("extract-new");
+    download::extract_archive(package_path, &extract_dir)?;
+    let (root, name) =
+        install::locate_new_package(&extract_dir, component_type).ok_or_else(not_found)?;
+    Ok(NewPackage {
+        source: extract_dir,
+        raw: false,
+        root,
+        name,
+    })
+}
+
+/// Installs an unpacked package as a new component, see [`install_new`].
+fn install_unpacked(
+    package: &NewPackage,
+    update: &AvailableUpdate,
+    config: &Config,
+) -> Result<InstalledComponent> {
+    let component_type = update.installed.component_type;
+    let base = if config.system {
+        component_type.system_path()
+    } else {
+        component_type.user_path()
+    };
+    let component = InstalledComponent {
+        path: base.join(&package.name),
+        directory_name: package.name.clone(),
+        version: update.latest_version.clone(),
+        is_system: config.system,
+        ..update.installed.clone()
+    };
+    if component.path.symlink_metadata().is_ok() {
+        return Err(Error::install(format!(
+            "{} is already installed at {}",
+            component.name,
+            component.path.display()
+        )));
+    }
+
+    let installed = AvailableUpdate {
+        installed: component.clone(),
+        ..update.clone()
+    };
+    let transaction = journal::begin(&installed, None)?;
+    let result = if package.raw {
+        install::install_raw_file(&package.source, &component)
+    } else {
+        install_extracted(&package.source, &component, &update.latest_version)
+    };
+    if let Err(e) = result {
+        log::error!(target: "install", "failed for {}: {e}", component.name);
+        // A failed cleanup keeps the journal entry for a later recovery.
+        match discard_partial_install(&component.path) {
+            Ok(()) => transaction.finish(),
+            Err(cleanup) => {
+                log::warn!(target: "install", "failed to remove {}: {cleanup}", component.path.display());
+            }
+        }
+        return Err(e);
+    }
+    transaction.finish();
+
+    post_install_tasks(&installed, config)?;
+    match check_health(&component, config) {
+        Ok(problems) => {
+            for problem in problems {
+                log::warn!(target: "install", "{}: {problem}", component.name);
+            }
+        }
+        Err(e) => {
+            let _ = remove_files(&component.path);
+            integrity::forget(&component);
+            pins::forget(&component);
+            if let Err(e) = registry::unregister_component(&component) {
+                log::warn!(target: "registry", "failed to update: {e}");
+            }
+            return Err(e);
+        }
+    }
+
+    log::info!(
+        target: "install",
+        "installed {} to {}",
+        component.name,
+        component.path.display()
+    );
+    Ok(component)
+}
+
+/// Deletes an installed component after backing it up, and drops its
+/// KNewStuff registry entry. With [`RemovalMode::Trash`], the files are
+/// moved to the trash instead.
+pub(crate) fn remove_component(component: &InstalledComponent, config: &Config) -> Result<()> {
+    create_backup(component)?;
+    if uses_trash(component, config) {
+        if let Err(e) = trash::move_to_trash(&component.path) {
+            log::warn!(target: "trash", "failed to trash {}: {e}", component.path.display());
+            remove_files(&component.path)?;
+        }
+    } else {
+        remove_files(&component.path)?;
+    }
+    integrity::forget(component);
+    pins::forget(component);
+
+    if let Err(e) = registry::unregister_component(component) {
+        log::warn!(target: "registry", "failed to update: {e}");
+    }
+
+    log::info!(
+        target: "install",
+        "removed {} from {}",
+        component.name,
+        component.path.display()
+    );
+    Ok(())
+}
+
+fn uses_trash(component: &InstalledComponent, config: &Config) -> bool {
+    config.removal_mode == RemovalMode::Trash && !component.is_system
+}
+
+/// Puts a copy of the files a successful update or downgrade replaced, taken
+/// from their `backup`, in the trash if [`RemovalMode::Trash`] is set.
+fn trash_replaced(component: &InstalledComponent, backup: Option<&Path>, config: &Config) {
+    let Some(backup) = backup.filter(|_| uses_trash(component, config)) else {
+        return;
+    };
+    match trash::copy_to_trash(backup, &component.path) {
+        Ok(trashed) => log::debug!(
+            target: "trash",
+            "moved {} {} to {}",
+            component.name,
+            component.version,
+            trashed.display()
+        ),
+        Err(e) => log::warn!(target: "trash", "failed to trash {}: {e}", component.name),
+    }
+}
+
+fn remove_files(path: &Path) -> Result<()> {
+    if path.is_dir() {
+        fs::remove_dir_all(path)?;
+    } else {
+        fs::remove_file(path)?;
+    }
+    Ok(())
+}
+
+/// Removes whatever a failed new install left at `path`.
+fn discard_partial_install(path: &Path) -> Result<()> {
+    if path.symlink_metadata().is_ok() {
+        remove_files(path)?;
+    }
+    Ok(())
+}
+
+/// Removes temp directories left behind by previous (crashed) runs.
+///
+/// Called once per run before any component is installed.
+pub(crate) fn cleanup_stale_temp_dirs(config: &Config) {
+    let Some(max_age) = config.stale_temp_max_age else {
+        return;
+    };
+    let root = download::temp_root(config.temp_dir.as_deref());
+    download::cleanup_stale_temp_dirs(&root, max_age);
+}
+
+/// Adds a copy to the versioned store; failures only cost the downgrade
+/// option, so they are logged rather than failing the update.
+fn retain_version(component: &InstalledComponent, record: &versions::RetainedVersion, keep: usize) {
+    if let Err(e) = versions::retain(component, record, keep) {
+        log::warn!(
+            target: "versions",
+            "failed to retain {} {}: {e}",
+            component.name,
+            record.version
+        );
+    }
+}
+
+/// Runs the post-install health check if [`Config::health_check`] is set.
+///
+/// Problems fail the update, so it is rolled back, when
+/// [`Config::rollback_unhealthy`] is set; otherwise they are returned.
+fn check_health(component: &InstalledComponent, config: &Config) -> Result<Vec<String>> {
+    if !config.health_check {
+        return Ok(Vec::new());
+    }
+
+    let problems = health::check(component);
+    if problems.is_empty() {
+        log::debug!(target: "health", "{} passed", component.name);
+        return Ok(problems);
+    }
+
+    log::warn!(
+        target: "health",
+        "{} failed: {}",
+        component.name,
+        problems.join("; ")
+    );
+    if config.rollback_unhealthy {
+        return Err(Error::health_check(problems.join("; ")));
+    }
+    Ok(problems)
+}
+
+/// Runs the post-install checks: the health check, and for SDDM themes the
+/// login-screen checks, which fail the update whatever the config says.
+fn check_installed(component: &InstalledComponent, config: &Config) -> Result<Vec<String>> {
+    let mut problems = check_health(component, config)?;
+    if component.component_type == ComponentType::SddmTheme {
+        problems.extend(sddm::validate(component)?);
+    }
+    Ok(problems)
+}
+
 fn create_backup(component: &InstalledComponent) -> Result<Option<PathBuf>> {
     let backup_path = backup_component(component)?;
     if let Some(ref path) = backup_path {
@@ -91,20 +579,19 @@ fn create_backup(component: &InstalledComponent) -> Result<Option<PathBuf>> {
 fn perform_installation(
     update: &AvailableUpdate,
     client: &reqwest::blocking::Client,
+    policy: &DownloadPolicy,
     reporter: &dyn Fn(u8),
-    counter: &AtomicUsize,
+    stats: &ApiCounters,
     temp_path: &Path,
-) -> Result<()> {
+    downloaded: Option<PathBuf>,
+) -> Result<(u64, String)> {
     let component = &update.installed;
-    let downloaded_path = download_with_error_handling(
-        client,
-        &update.download_url,
-        update.checksum.as_deref(),
-        &component.name,
-        &component.directory_name,
-        counter,
-        temp_path,
-    )?;
+    let downloaded_path = match downloaded {
+        Some(path) => path,
+        None => download_with_error_handling(client, policy, update, stats, temp_path)?,
+    };
+    let bytes = fs::metadata(&downloaded_path).map_or(0, |m| m.len());
+    let sha256 = pins::payload_sha256(&downloaded_path)?;
     reporter(2);
 
     execute_installation(
@@ -113,24 +600,64 @@ fn perform_installation(
         &update.latest_version,
         reporter,
         temp_path,
-    )
+    )?;
+    Ok((bytes, sha256))
 }
 
 fn download_with_error_handling(
     client: &reqwest::blocking::Client,
-    url: &str,
-    checksum: Option<&str>,
-    component_name: &str,
-    directory_name: &str,
-    counter: &AtomicUsize,
+    policy: &DownloadPolicy,
+    update: &AvailableUpdate,
+    stats: &ApiCounters,
+    temp_path: &Path,
+) -> Result<PathBuf> {
+    download_cache::fetch(update, policy.cache_limit(), temp_path, || {
+        download::download_package(
+            client,
+            policy,
+            &update.download_url,
+            update.checksum.as_deref(),
+            &update.installed.directory_name,
+            stats,
+            temp_path,
+        )
+    })
+    .map_err(|e| {
+        log::error!(target: "download", "failed for {}: {e}", update.installed.name);
+        e
+    })
+    .context(|| ErrorContext::operation(Operation::Download).target(&update.download_url))
+}
+
+/// Downloads the package of `update`, bypassing the download cache, and
+/// checks it against the `sha256` pinned when it was first installed.
+fn download_pinned(
+    client: &reqwest::blocking::Client,
+    policy: &DownloadPolicy,
+    update: &AvailableUpdate,
+    sha256: &str,
+    stats: &ApiCounters,
     temp_path: &Path,
 ) -> Result<PathBuf> {
-    download::download_package(client, url, checksum, directory_name, counter, temp_path).map_err(
-        |e| {
-            log::error!(target: "download", "failed for {}: {e}", component_name);
-            e
-        },
+    let downloaded = download::download_package(
+        client,
+        policy,
+        &update.download_url,
+        None,
+        &update.installed.directory_name,
+        stats,
+        temp_path,
     )
+    .context(|| ErrorContext::operation(Operation::Download).target(&update.download_url))?;
+    let actual = pins::payload_sha256(&downloaded)?;
+    if actual != sha256 {
+        return Err(Error::PayloadChanged {
+            version: update.latest_version.clone(),
+            expected: sha256.to_string(),
+            actual,
+        });
+    }
+    Ok(downloaded)
 }
 
 fn execute_installation(
@@ -140,23 +667,38 @@ fn execute_installation(
     reporter: &dyn Fn(u8),
     temp_path: &Path,
 ) -> Result<()> {
-    let is_single_file_type = matches!(
-        component.component_type,
-        ComponentType::ColorScheme | ComponentType::Wallpaper,
-    );
-    let is_raw_file = install::is_single_file_component(downloaded_path, component.component_type)
-        || (is_single_file_type && !has_archive_magic(downloaded_path));
-
-    if is_raw_file {
+    if is_raw_file(downloaded_path, component.component_type)
+        || (is_generic_file(component) && !has_archive_magic(downloaded_path))
+    {
         let result = install::install_raw_file(downloaded_path, component);
         let _ = fs::remove_file(downloaded_path);
         reporter(3);
-        result
+        result.context(|| install_context(component))
     } else {
         install_from_archive(downloaded_path, component, new_version, reporter, temp_path)
     }
 }
 
+/// Returns `true` if `downloaded_path` is the component itself rather than
+/// an archive holding it, e.g. a bare `.colors` file or image.
+fn is_raw_file(downloaded_path: &Path, component_type: ComponentType) -> bool {
+    let is_single_file_type = matches!(
+        component_type,
+        ComponentType::ColorScheme
+            | ComponentType::Wallpaper
+            | ComponentType::KRunnerPlugin
+            | ComponentType::Font,
+    );
+    install::is_single_file_component(downloaded_path, component_type)
+        || (is_single_file_type && !has_archive_magic(downloaded_path))
+}
+
+/// Returns `true` for an unmodelled registry entry recording a single file,
+/// e.g. a font, which stores may serve without an archive around it.
+fn is_generic_file(component: &InstalledComponent) -> bool {
+    matches!(component.component_type, ComponentType::Other(_)) && !component.path.is_dir()
+}
+
 /// Checks the first bytes of a file for known archive format signatures.
 fn has_archive_magic(path: &Path) -> bool {
     let Ok(mut file) = fs::File::open(path) else {
@@ -194,10 +736,16 @@ fn install_from_archive(
     if let Err(e) = download::extract_archive(downloaded_path, &extract_dir) {
         // For single-file types (color schemes, wallpapers), the download might
         // be the raw file itself served without the expected extension.
-        if matches!(
-            component.component_type,
-            ComponentType::ColorScheme | ComponentType::Wallpaper
-        ) {
+        // A rejected archive is never retried as a raw file.
+        if !matches!(e, Error::MaliciousArchive(_))
+            && (matches!(
+                component.component_type,
+                ComponentType::ColorScheme
+                    | ComponentType::Wallpaper
+                    | ComponentType::KRunnerPlugin
+                    | ComponentType::Font
+            ) || is_generic_file(component))
+        {
             log::debug!(
                 target: "extract",
                 "extraction failed for {}, trying raw file install: {e}",
@@ -206,64 +754,112 @@ fn install_from_archive(
             reporter(3);
             let result = install::install_raw_file(downloaded_path, component);
             let _ = fs::remove_file(downloaded_path);
-            return result;
+            return result.context(|| install_context(component));
         }
         log::error!(target: "extract", "failed for {}: {e}", component.name);
         let _ = fs::remove_file(downloaded_path);
-        return Err(e);
+        return Err(e.context(
+            ErrorContext::operation(Operation::Extract).target(downloaded_path.display()),
+        ));
     }
 
     let _ = fs::remove_file(downloaded_path);
     reporter(3);
 
-    let result = if component.component_type.kpackage_type().is_some() {
-        match install::install_via_kpackage(&extract_dir, component, new_version) {
-            Ok(()) => Ok(()),
-            Err(e) if component.component_type.has_direct_fallback() => {
-                log::warn!(
-                    target: "install",
-                    "kpackagetool6 failed for {}, falling back to direct install: {e}",
-                    component.name,
-                );
-                install::install_direct(&extract_dir, component)
-            }
-            Err(e) => Err(e),
-        }
-    } else {
-        install::install_direct(&extract_dir, component)
-    };
-
+    let result = install_extracted(&extract_dir, component, new_version);
     let _ = fs::remove_dir_all(&extract_dir);
-    result
+    result.context(|| install_context(component))
+}
+
+fn install_context(component: &InstalledComponent) -> ErrorContext {
+    ErrorContext::operation(Operation::Install).target(component.path.display())
+}
+
+fn install_extracted(
+    extract_dir: &Path,
+    component: &InstalledComponent,
+    new_version: &str,
+) -> Result<()> {
+    if component.component_type.kpackage_type().is_none() {
+        return install::install_direct(extract_dir, component);
+    }
+    match install::install_via_kpackage(extract_dir, component, new_version) {
+        Ok(()) => Ok(()),
+        Err(e) if component.component_type.has_direct_fallback() => {
+            log::warn!(
+                target: "install",
+                "kpackagetool6 failed for {}, falling back to direct install: {e}",
+                component.name,
+            );
+            install::install_direct(extract_dir, component)
+        }
+        Err(e) => Err(e),
+    }
 }
 
-fn post_install_tasks(update: &AvailableUpdate) -> Result<()> {
+fn post_install_tasks(update: &AvailableUpdate, config: &Config) -> Result<()> {
     let component = &update.installed;
 
     let installed_json = component.path.join("metadata.json");
     let installed_desktop = component.path.join("metadata.desktop");
 
-    if installed_json.exists() {
-        if let Err(e) = install::patch_metadata(
+    if config.migrate_legacy_metadata {
+        match install::migrate_metadata_desktop(&component.path, component.component_type) {
+            Ok(true) => {
+                log::info!(target: "patch", "generated metadata.json for {}", component.name)
+            }
+            Ok(false) => {}
+            Err(e) => log::warn!(target: "patch", "failed to generate metadata.json: {e}"),
+        }
+    }
+
+    if installed_json.exists()
+        && let Err(e) = install::patch_metadata(
             &installed_json,
             component.component_type,
             &update.latest_version,
-        ) {
-            log::warn!(target: "patch", "failed to update installed metadata: {e}");
-        }
-    } else if installed_desktop.exists()
+        )
+    {
+        log::warn!(target: "patch", "failed to update installed metadata: {e}");
+    }
+    if installed_desktop.exists()
         && let Err(e) = install::patch_metadata_desktop(&installed_desktop, &update.latest_version)
     {
         log::warn!(target: "patch", "failed to update installed metadata.desktop: {e}");
     }
 
-    if let Err(e) = registry::update_registry_after_install(update) {
+    // Packages installed from a local file have no store entry to record.
+    if update.content_id != 0
+        && let Err(e) = registry::update_registry_after_install(update)
+    {
         log::warn!(target: "registry", "failed to update: {e}");
     }
+    record_integrity(component, &update.latest_version);
+
+    let cleared = if config.clear_theme_caches {
+        cache::invalidate(component)
+    } else {
+        0
+    };
+    if cleared > 0 {
+        log::debug!(
+            target: "cache",
+            "removed {cleared} cached renderings for {}",
+            component.name
+        );
+    }
 
     Ok(())
 }
 
+/// Records the installed files of `component` for [`verify`]. Failures are
+/// logged and never fail the install.
+fn record_integrity(component: &InstalledComponent, version: &str) {
+    if let Err(e) = integrity::record(component, version) {
+        log::warn!(target: "integrity", "failed to record {}: {e}", component.name);
+    }
+}
+
 fn verify_installed_version(update: &AvailableUpdate) -> InstallOutcome {
     let component = &update.installed;
     let expected = &update.latest_version;
@@ -295,6 +891,9 @@ fn verify_installed_version(update: &AvailableUpdate) -> InstallOutcome {
         verified,
         expected_version: expected.clone(),
         actual_version: actual,
+        problems: Vec::new(),
+        bytes: 0,
+        sha256: String::new(),
     }
 }
 
@@ -304,35 +903,14 @@ fn read_installed_version(component: &InstalledComponent) -> Option<String> {
         return read_version_from_registry(component);
     }
 
-    // Try metadata.json first
-    let json_path = component.path.join("metadata.json");
-    if json_path.exists()
-        && let Ok(content) = fs::read_to_string(&json_path)
-        && let Ok(meta) = serde_json::from_str::<crate::types::PackageMetadata>(&content)
-        && let Some(v) = meta.version()
-    {
-        return Some(v.to_string());
-    }
-
-    // Fall back to metadata.desktop
-    let desktop_path = component.path.join("metadata.desktop");
-    if desktop_path.exists()
-        && let Ok(content) = fs::read_to_string(&desktop_path)
-    {
-        for line in content.lines() {
-            if let Some(version) = line.strip_prefix("X-KDE-PluginInfo-Version=") {
-                return Some(version.to_string());
-            }
-        }
-    }
-
-    None
+    crate::checker::read_package_metadata(&component.path)
+        .and_then(|m| m.version().map(str::to_string))
 }
 
 fn read_version_from_registry(component: &InstalledComponent) -> Option<String> {
     use crate::registry::RegistryManager;
 
-    let manager = RegistryManager::for_component_type(component.component_type)?;
+    let manager = RegistryManager::for_scope(component.component_type, component.is_system)?;
     let entries = manager.read_entries().ok()?;
     entries
         .iter()
@@ -355,9 +933,96 @@ fn handle_installation_failure(
         Err(Error::InstallAndRestoreFailed {
             install_error: original_error.to_string(),
             restore_error: restore_err.to_string(),
-        })
+        }
+        .context(ErrorContext::operation(Operation::Restore).target(backup_path.display())))
     } else {
         log::info!(target: "restore", "no changes were made");
         Ok(())
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::{
+        process::Command,
+        time::{Duration, SystemTime},
+    };
+
+    #[test]
+    fn failed_new_installs_leave_nothing_behind() {
+        let dir = tempfile::tempdir().unwrap();
+        let partial = dir.path().join("org.example.widget");
+        fs::create_dir_all(partial.join("contents")).unwrap();
+        fs::write(partial.join("metadata.json"), b"{}").unwrap();
+
+        discard_partial_install(&partial).unwrap();
+        assert!(!partial.exists());
+        discard_partial_install(&partial).unwrap();
+    }
+
+    #[test]
+    fn stale_temp_cleanup_follows_config() {
+        let root = tempfile::tempdir().unwrap();
+        let stale = download::create_temp_dir(root.path()).unwrap().keep();
+        let hour = Duration::from_secs(60 * 60);
+        fs::File::open(&stale)
+            .unwrap()
+            .set_modified(SystemTime::now() - 2 * hour)
+            .unwrap();
+        let config = Config::new().with_temp_dir(root.path());
+
+        cleanup_stale_temp_dirs(&config.clone().with_stale_temp_max_age(None));
+        cleanup_stale_temp_dirs(&config.clone().with_stale_temp_max_age(Some(3 * hour)));
+        assert!(stale.exists());
+
+        cleanup_stale_temp_dirs(&config.with_stale_temp_max_age(Some(hour)));
+        assert!(!stale.exists());
+    }
+
+    #[test]
+    fn malicious_archives_of_generic_components_are_not_installed_raw() {
+        let dir = tempfile::tempdir().unwrap();
+        fs::write(dir.path().join("payload"), b"data").unwrap();
+        let status = Command::new("bsdtar")
+            .current_dir(dir.path())
+            .args([
+                "-cPf",
+                "crafted.tar",
+                "-s",
+                ",^payload$,../escaped,",
+                "payload",
+            ])
+            .status()
+            .unwrap();
+        assert!(status.success());
+        let target = dir.path().join("titles/Intro.kdenlivetitle");
+        fs::create_dir_all(target.parent().unwrap()).unwrap();
+        fs::write(&target, b"old").unwrap();
+        let component = InstalledComponent {
+            directory_name: "Intro.kdenlivetitle".to_string(),
+            ..InstalledComponent::fixture(
+                "Intro",
+                ComponentType::Other("kdenlive_titles.knsregistry"),
+                target.clone(),
+            )
+        };
+        let temp = dir.path().join("temp");
+        fs::create_dir_all(&temp).unwrap();
+
+        let err = install_from_archive(
+            &dir.path().join("crafted.tar"),
+            &component,
+            "2.0",
+            &|_| {},
+            &temp,
+        )
+        .unwrap_err();
+
+        assert!(
+            matches!(err.root_cause(), Error::MaliciousArchive(_)),
+            "{err}"
+        );
+        assert_eq!(fs::read(&target).unwrap(), b"old");
+    }
+}
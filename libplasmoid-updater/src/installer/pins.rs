@@ -0,0 +1,153 @@
+// SPDX-License-Identifier: GPL-3.0-or-later
+
+use std::{
+    fs,
+    io::Read as _,
+    path::{Path, PathBuf},
+};
+
+use serde::{Deserialize, Serialize};
+use sha2::{Digest, Sha256};
+
+use crate::{
+    Result,
+    types::{AvailableUpdate, InstalledComponent},
+};
+
+/// The store payload a component was last installed from, see
+/// [`reinstall()`](crate::reinstall).
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct PayloadPin {
+    pub version: String,
+    pub content_id: u64,
+    pub download_url: String,
+    pub release_date: String,
+    /// SHA-256 of the downloaded package, hex encoded.
+    pub sha256: String,
+    /// When the payload was installed (RFC 3339).
+    pub pinned_at: String,
+}
+
+impl PayloadPin {
+    /// The update that installs this payload over `component` again.
+    pub(crate) fn to_update(&self, component: InstalledComponent) -> AvailableUpdate {
+        AvailableUpdate::builder(
+            component,
+            self.content_id,
+            self.version.clone(),
+            self.download_url.clone(),
+            self.release_date.clone(),
+        )
+        .build()
+    }
+}
+
+/// Returns the directory holding the pins.
+///
+/// User components are tracked under `$XDG_STATE_HOME/plasmoid-updater/pins`,
+/// system components next to the system registry.
+fn pins_dir(system: bool) -> PathBuf {
+    if system {
+        crate::paths::system_state_dir("pins")
+    } else {
+        crate::paths::state_dir("pins")
+    }
+}
+
+fn pin_path(component: &InstalledComponent) -> PathBuf {
+    pins_dir(component.is_system).join(format!("{}.json", component.directory_name))
+}
+
+/// Hashes the package at `path`.
+pub(crate) fn payload_sha256(path: &Path) -> Result<String> {
+    let mut file = fs::File::open(path)?;
+    let mut hasher = Sha256::new();
+    let mut buffer = [0u8; 64 * 1024];
+    loop {
+        let read = file.read(&mut buffer)?;
+        if read == 0 {
+            break;
+        }
+        hasher.update(&buffer[..read]);
+    }
+    Ok(format!("{:x}", hasher.finalize()))
+}
+
+/// Records that `update` was installed from a package hashing to `sha256`,
+/// replacing any earlier pin. Failures are logged; the install stands.
+pub(crate) fn record(update: &AvailableUpdate, sha256: &str) {
+    let pin = PayloadPin {
+        version: update.latest_version.clone(),
+        content_id: update.content_id,
+        download_url: update.download_url.clone(),
+        release_date: update.release_date.clone(),
+        sha256: sha256.to_string(),
+        pinned_at: chrono::Local::now().to_rfc3339(),
+    };
+    if let Err(e) = write_pin(&pin_path(&update.installed), &pin) {
+        log::warn!(target: "pins", "failed to record {}: {e}", update.installed.name);
+    }
+}
+
+fn write_pin(path: &Path, pin: &PayloadPin) -> Result<()> {
+    if let Some(parent) = path.parent() {
+        fs::create_dir_all(parent)?;
+    }
+    let partial = path.with_extension("json.partial");
+    fs::write(&partial, serde_json::to_vec_pretty(pin)?)?;
+    fs::rename(&partial, path)?;
+    Ok(())
+}
+
+/// Returns the pin of `component`, if it was installed by this crate.
+pub(crate) fn pin(component: &InstalledComponent) -> Option<PayloadPin> {
+    read_pin(&pin_path(component))
+}
+
+fn read_pin(path: &Path) -> Option<PayloadPin> {
+    let content = fs::read(path).ok()?;
+    serde_json::from_slice(&content)
+        .inspect_err(|e| log::debug!(target: "pins", "skipping {}: {e}", path.display()))
+        .ok()
+}
+
+/// Drops the pin of a removed component.
+pub(crate) fn forget(component: &InstalledComponent) {
+    let _ = fs::remove_file(pin_path(component));
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn payload_hash_is_hex_sha256() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("package.tar.gz");
+        fs::write(&path, b"abc").unwrap();
+
+        assert_eq!(
+            payload_sha256(&path).unwrap(),
+            "ba7816bf8f01cfea414140de5dae2223b00361a396177a9cb410ff61f20015ad"
+        );
+    }
+
+    #[test]
+    fn pins_survive_a_round_trip() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("pins/org.example.clock.json");
+        let pin = PayloadPin {
+            version: "2.0".to_string(),
+            content_id: 1000,
+            download_url: "https://example.org/clock-2.0.tar.gz".to_string(),
+            release_date: "2025-01-01T00:00:00+00:00".to_string(),
+            sha256: "ba7816bf".to_string(),
+            pinned_at: "2025-01-02T00:00:00+00:00".to_string(),
+        };
+
+        write_pin(&path, &pin).unwrap();
+
+        assert_eq!(read_pin(&path), Some(pin));
+        assert_eq!(read_pin(&dir.path().join("missing.json")), None);
+    }
+}
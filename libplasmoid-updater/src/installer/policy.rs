@@ -0,0 +1,160 @@
+// SPDX-License-Identifier: GPL-3.0-or-later
+
+use std::time::Duration;
+
+use reqwest::Url;
+
+use crate::{Config, Error, Result};
+
+/// Which downloads may be fetched, from [`Config::require_https`],
+/// [`Config::allowed_download_hosts`], [`Config::denied_download_hosts`] and
+/// [`Config::max_download_size`], how long they may take and how much of
+/// them is cached, see [`Config::download_cache_limit`].
+///
+/// Host patterns are either an exact host name or `*.example.com`, which
+/// matches `example.com` and every subdomain of it.
+pub(crate) struct DownloadPolicy<'a> {
+    require_https: bool,
+    allowed: &'a [String],
+    denied: &'a [String],
+    max_size: Option<u64>,
+    timeout: Duration,
+    cache_limit: Option<u64>,
+}
+
+impl<'a> DownloadPolicy<'a> {
+    pub(crate) fn from_config(config: &'a Config) -> Self {
+        Self {
+            require_https: config.require_https,
+            allowed: &config.allowed_download_hosts,
+            denied: &config.denied_download_hosts,
+            max_size: config.max_download_size,
+            timeout: config.api.download_timeout,
+            cache_limit: config.download_cache_limit,
+        }
+    }
+
+    /// Time allowed for a whole download, see [`ApiConfig::download_timeout`](crate::ApiConfig::download_timeout).
+    pub(crate) fn timeout(&self) -> Duration {
+        self.timeout
+    }
+
+    /// Size cap of the download cache, `None` if downloads are not cached.
+    pub(crate) fn cache_limit(&self) -> Option<u64> {
+        self.cache_limit
+    }
+
+    /// Fails if `size` bytes exceed [`Config::max_download_size`].
+    pub(crate) fn check_size(&self, size: u64) -> Result<()> {
+        match self.max_size {
+            Some(max) if size > max => Err(Error::download(format!(
+                "download exceeds the size limit of {max} bytes"
+            ))),
+            _ => Ok(()),
+        }
+    }
+
+    /// Returns [`Error::UntrustedDownloadHost`] unless `url` may be fetched.
+    pub(crate) fn check(&self, url: &str) -> Result<()> {
+        let parsed =
+            Url::parse(url).map_err(|e| Error::download(format!("invalid url {url}: {e}")))?;
+        let host = parsed.host_str().unwrap_or_default().to_lowercase();
+        let untrusted = |reason: &str| Error::UntrustedDownloadHost {
+            host: host.clone(),
+            reason: reason.to_string(),
+        };
+
+        match parsed.scheme() {
+            "https" => {}
+            "http" if !self.require_https => {}
+            "http" => return Err(untrusted("plain HTTP download, HTTPS is required")),
+            scheme => return Err(untrusted(&format!("unsupported scheme '{scheme}'"))),
+        }
+
+        if self.denied.iter().any(|p| host_matches(p, &host)) {
+            return Err(untrusted("host is denied"));
+        }
+        if !self.allowed.is_empty() && !self.allowed.iter().any(|p| host_matches(p, &host)) {
+            return Err(untrusted("host is not in the allowlist"));
+        }
+
+        Ok(())
+    }
+}
+
+fn host_matches(pattern: &str, host: &str) -> bool {
+    let pattern = pattern.trim().to_lowercase();
+    match pattern.strip_prefix("*.") {
+        Some(domain) => {
+            host == domain
+                || host
+                    .strip_suffix(domain)
+                    .is_some_and(|sub| sub.ends_with('.'))
+        }
+        None => host == pattern,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn policy<'a>(
+        require_https: bool,
+        allowed: &'a [String],
+        denied: &'a [String],
+    ) -> DownloadPolicy<'a> {
+        DownloadPolicy {
+            require_https,
+            allowed,
+            denied,
+            max_size: None,
+            timeout: crate::ApiConfig::DEFAULT_DOWNLOAD_TIMEOUT,
+            cache_limit: None,
+        }
+    }
+
+    #[test]
+    fn plain_http_is_rejected_when_https_is_required() {
+        let url = "http://files.kde.org/clock.tar.gz";
+        assert!(matches!(
+            policy(true, &[], &[]).check(url),
+            Err(Error::UntrustedDownloadHost { .. })
+        ));
+        assert!(policy(false, &[], &[]).check(url).is_ok());
+        assert!(policy(true, &[], &[]).check("file:///etc/passwd").is_err());
+    }
+
+    #[test]
+    fn allowlist_accepts_wildcard_subdomains_only() {
+        let allowed = ["*.kde.org".to_string(), "github.com".to_string()];
+        let policy = policy(true, &allowed, &[]);
+
+        assert!(policy.check("https://files.kde.org/a.tar.gz").is_ok());
+        assert!(policy.check("https://kde.org/a.tar.gz").is_ok());
+        assert!(policy.check("https://GitHub.com/a.tar.gz").is_ok());
+        assert!(policy.check("https://notkde.org/a.tar.gz").is_err());
+        assert!(policy.check("https://api.github.com/a.tar.gz").is_err());
+    }
+
+    #[test]
+    fn size_limit_is_inclusive() {
+        let limited = DownloadPolicy {
+            max_size: Some(1024),
+            ..policy(true, &[], &[])
+        };
+        assert!(limited.check_size(1024).is_ok());
+        assert!(limited.check_size(1025).is_err());
+        assert!(policy(true, &[], &[]).check_size(u64::MAX).is_ok());
+    }
+
+    #[test]
+    fn denylist_wins_over_allowlist() {
+        let allowed = ["*.example.com".to_string()];
+        let denied = ["evil.example.com".to_string()];
+        let policy = policy(true, &allowed, &denied);
+
+        assert!(policy.check("https://cdn.example.com/a.tar.gz").is_ok());
+        assert!(policy.check("https://evil.example.com/a.tar.gz").is_err());
+    }
+}
@@ -0,0 +1,99 @@
+// SPDX-License-Identifier: GPL-3.0-or-later
+
+use std::{
+    sync::{Arc, mpsc},
+    thread::Scope,
+};
+
+use parking_lot::Mutex;
+
+use crate::{Config, Result, api::ApiCounters, types::AvailableUpdate};
+
+use super::{download, download_cache, policy::DownloadPolicy};
+
+/// Downloads packages into the download cache on background threads while
+/// the check is still running, see [`Config::pipeline_downloads`].
+///
+/// Installs fetch their package through the same cache and wait for a
+/// download in progress, so no package is fetched twice. Dropping the
+/// prefetcher lets the threads finish the queued downloads and exit.
+pub(crate) struct Prefetcher {
+    queue: mpsc::Sender<AvailableUpdate>,
+}
+
+impl Prefetcher {
+    /// Starts the download threads in `scope`, one per install thread.
+    /// Returns `None` if the download cache is disabled, since there is
+    /// nowhere to keep the packages until they are installed.
+    pub(crate) fn start<'scope, 'env: 'scope>(
+        scope: &'scope Scope<'scope, 'env>,
+        client: &'env reqwest::blocking::Client,
+        stats: &Arc<ApiCounters>,
+        config: &'env Config,
+    ) -> Option<Self> {
+        let limit = config.download_cache_limit?;
+        let (queue, receiver) = mpsc::channel::<AvailableUpdate>();
+        let receiver = Arc::new(Mutex::new(receiver));
+
+        let workers = config
+            .threads
+            .or_else(|| std::thread::available_parallelism().ok().map(Into::into))
+            .unwrap_or(1)
+            .max(1);
+        for _ in 0..workers {
+            let receiver = Arc::clone(&receiver);
+            let stats = Arc::clone(stats);
+            scope.spawn(move || {
+                let policy = DownloadPolicy::from_config(config);
+                loop {
+                    // A `while let` would hold the lock through the download.
+                    let next = receiver.lock().recv();
+                    let Ok(update) = next else {
+                        break;
+                    };
+                    if !config.is_cancelled() {
+                        prefetch(&update, client, &policy, limit, &stats, config);
+                    }
+                }
+            });
+        }
+        Some(Self { queue })
+    }
+
+    /// Queues the package of `update` for download.
+    pub(crate) fn queue(&self, update: &AvailableUpdate) {
+        let _ = self.queue.send(update.clone());
+    }
+}
+
+fn prefetch(
+    update: &AvailableUpdate,
+    client: &reqwest::blocking::Client,
+    policy: &DownloadPolicy,
+    limit: u64,
+    stats: &ApiCounters,
+    config: &Config,
+) {
+    let name = &update.installed.name;
+    let prefetched = download::create_temp_dir(&download::temp_root(config.temp_dir.as_deref()))
+        .and_then(|temp| -> Result<bool> {
+            download_cache::prefetch(update, limit, || {
+                download::download_package(
+                    client,
+                    policy,
+                    &update.download_url,
+                    update.checksum.as_deref(),
+                    &update.installed.directory_name,
+                    stats,
+                    temp.path(),
+                )
+            })
+        });
+    match prefetched {
+        Ok(true) => log::debug!(target: "download", "prefetched the package of {name}"),
+        Ok(false) => {
+            log::debug!(target: "download", "package of {name} is too large to prefetch");
+        }
+        Err(e) => log::debug!(target: "download", "prefetching {name} failed: {e}"),
+    }
+}
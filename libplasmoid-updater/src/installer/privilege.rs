@@ -1,22 +1,70 @@
 // SPDX-License-Identifier: GPL-3.0-or-later
 
-use std::{path::Path, process::Command};
+use std::{
+    os::unix::fs::{MetadataExt, PermissionsExt},
+    path::Path,
+    process::Command,
+};
 
-use crate::{Error, Result};
+use crate::{Error, Result, types::UserScope};
 
 /// Checks if the current process is running as root (UID 0).
 pub(crate) fn is_root() -> bool {
     nix::unistd::Uid::effective().is_root()
 }
 
-/// Checks if a path is in a system directory (requires root to modify).
+/// Checks if a path is in a system directory: under `/usr`, `/lib` or
+/// `/etc`, or in one of the system data dirs listed in `XDG_DATA_DIRS`.
 pub(crate) fn is_system_path(path: &Path) -> bool {
-    path.starts_with("/usr") || path.starts_with("/lib") || path.starts_with("/etc")
+    path.starts_with("/usr")
+        || path.starts_with("/lib")
+        || path.starts_with("/etc")
+        || crate::paths::system_data_dirs()
+            .iter()
+            .any(|dir| path.starts_with(dir))
 }
 
-/// Returns true if writing to `path` requires privilege escalation.
+/// Returns true if writing to `path` requires privilege escalation: it is a
+/// system path the current user cannot write to. System dirs the user owns,
+/// e.g. a `/usr/local/share` handed to a group, are written directly.
 pub(crate) fn needs_sudo(path: &Path) -> bool {
-    is_system_path(path) && !is_root()
+    is_system_path(path) && !is_root() && !is_writable(path)
+}
+
+/// Returns true if the current user may replace or create `path`: its
+/// nearest existing parent and, if it exists, `path` itself are writable.
+fn is_writable(path: &Path) -> bool {
+    use nix::unistd::{AccessFlags, access};
+
+    let writable = |p: &Path| access(p, AccessFlags::W_OK).is_ok();
+    let parent_writable = path
+        .ancestors()
+        .skip(1)
+        .find(|p| p.symlink_metadata().is_ok())
+        .is_some_and(writable);
+    parent_writable && (path.symlink_metadata().is_err() || writable(path))
+}
+
+/// Describes the system if its system data directories cannot be written
+/// even as root: NixOS, ostree-based distros (Fedora Atomic, Kinoite) or a
+/// read-only `/usr/share` mount.
+pub(crate) fn immutable_system() -> Option<String> {
+    immutable_system_in(Path::new("/"))
+}
+
+fn immutable_system_in(root: &Path) -> Option<String> {
+    use nix::sys::statvfs::{FsFlags, statvfs};
+
+    if root.join("etc/NIXOS").exists() {
+        return Some("NixOS".to_string());
+    }
+    if root.join("run/ostree-booted").exists() {
+        return Some("an ostree-based system".to_string());
+    }
+    let usr_share = root.join("usr/share");
+    statvfs(&usr_share)
+        .is_ok_and(|stat| stat.flags().contains(FsFlags::ST_RDONLY))
+        .then(|| format!("this system ({} is mounted read-only)", usr_share.display()))
 }
 
 // --- Privileged Command Execution ---
@@ -49,12 +97,34 @@ pub(crate) fn sudo_command(program: &str) -> Command {
     }
 }
 
+/// Creates a `Command` that runs `program` as another user.
+///
+/// `sudo` keeps `SUDO_USER` pointing at the invoking admin, which would make
+/// path resolution pick the admin's home, so `SUDO_USER_HOME` is set to the
+/// target user's home explicitly.
+pub(crate) fn user_command(user: &UserScope, program: &std::ffi::OsStr) -> Command {
+    let mut cmd = Command::new("sudo");
+    cmd.args(["-u", &user.name, "-H", "--", "env"])
+        .arg(format!("SUDO_USER_HOME={}", user.home.display()))
+        .arg(program);
+    cmd
+}
+
 // --- Privileged File Operations ---
 
 /// Copies a file, using sudo if the destination requires it.
+///
+/// Mode, timestamps and extended attributes are kept; with sudo the copy is
+/// owned by root rather than the invoking user.
 pub(crate) fn copy_file(src: &Path, dest: &Path) -> Result<()> {
     if needs_sudo(dest) {
-        run_sudo(&["cp", "-f", &src.to_string_lossy(), &dest.to_string_lossy()])
+        run_sudo(&[
+            "cp",
+            "-af",
+            "--no-preserve=ownership",
+            &src.to_string_lossy(),
+            &dest.to_string_lossy(),
+        ])
     } else {
         std::fs::copy(src, dest)?;
         Ok(())
@@ -63,13 +133,15 @@ pub(crate) fn copy_file(src: &Path, dest: &Path) -> Result<()> {
 
 /// Recursively copies a directory's contents, using sudo if the destination requires it.
 ///
-/// Uses `cp -rfT` for sudo to avoid creating a nested subdirectory when the
-/// destination already exists.
+/// Uses `cp -afT` for sudo to avoid creating a nested subdirectory when the
+/// destination already exists. As with [`copy_file`], modes, timestamps and
+/// extended attributes are kept but not the invoking user's ownership.
 pub(crate) fn copy_dir(src: &Path, dest: &Path) -> Result<()> {
     if needs_sudo(dest) {
         run_sudo(&[
             "cp",
-            "-rfT",
+            "-afT",
+            "--no-preserve=ownership",
             &src.to_string_lossy(),
             &dest.to_string_lossy(),
         ])
@@ -122,6 +194,139 @@ pub(crate) fn rename(src: &Path, dest: &Path) -> Result<()> {
     }
 }
 
+// --- Ownership and Permissions ---
+
+/// Owner and mode of an installed file or directory, captured before it is
+/// replaced so the replacement can be given the same.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub(crate) struct Ownership {
+    pub uid: u32,
+    pub gid: u32,
+    pub mode: u32,
+}
+
+impl Ownership {
+    /// Reads the ownership of `path` without following symlinks.
+    pub(crate) fn of(path: &Path) -> Option<Self> {
+        let meta = path.symlink_metadata().ok()?;
+        if meta.file_type().is_symlink() {
+            return None;
+        }
+        Some(Self {
+            uid: meta.uid(),
+            gid: meta.gid(),
+            mode: meta.mode() & 0o7777,
+        })
+    }
+
+    /// Gives `path` this ownership: the owner recursively, the mode on
+    /// `path` itself (contents keep the modes they were installed with).
+    pub(crate) fn apply(&self, path: &Path) -> Result<()> {
+        let Some(current) = Self::of(path) else {
+            return Ok(());
+        };
+
+        if (current.uid, current.gid) != (self.uid, self.gid) {
+            self.chown(path)?;
+        }
+        if current.mode != self.mode {
+            self.chmod(path)?;
+        }
+
+        Ok(())
+    }
+
+    fn chown(&self, path: &Path) -> Result<()> {
+        if needs_sudo(path) {
+            return run_sudo(&[
+                "chown",
+                "-R",
+                "--no-dereference",
+                &format!("{}:{}", self.uid, self.gid),
+                &path.to_string_lossy(),
+            ]);
+        }
+
+        let uid = Some(nix::unistd::Uid::from_raw(self.uid));
+        let gid = Some(nix::unistd::Gid::from_raw(self.gid));
+        let paths = walk_tree(path);
+        for entry in &paths {
+            nix::unistd::fchownat(
+                nix::fcntl::AT_FDCWD,
+                entry.as_path(),
+                uid,
+                gid,
+                nix::fcntl::AtFlags::AT_SYMLINK_NOFOLLOW,
+            )
+            .map_err(|e| Error::install(format!("failed to chown {}: {e}", entry.display())))?;
+        }
+        Ok(())
+    }
+
+    fn chmod(&self, path: &Path) -> Result<()> {
+        if needs_sudo(path) {
+            run_sudo(&[
+                "chmod",
+                &format!("{:o}", self.mode),
+                &path.to_string_lossy(),
+            ])
+        } else {
+            std::fs::set_permissions(path, std::fs::Permissions::from_mode(self.mode))?;
+            Ok(())
+        }
+    }
+}
+
+/// Lists `path` and, for directories, everything below it (not following symlinks).
+fn walk_tree(path: &Path) -> Vec<std::path::PathBuf> {
+    let mut paths = vec![path.to_path_buf()];
+    let mut index = 0;
+    while index < paths.len() {
+        let is_dir = paths[index]
+            .symlink_metadata()
+            .is_ok_and(|m| m.file_type().is_dir());
+        if is_dir && let Ok(entries) = std::fs::read_dir(&paths[index]) {
+            paths.extend(entries.flatten().map(|e| e.path()));
+        }
+        index += 1;
+    }
+    paths
+}
+
+/// Resets SELinux labels on a freshly installed system path, since copies
+/// from the temp dir carry its labels. Does nothing without SELinux, when
+/// `restorecon` is not installed or the path is not a system path.
+pub(crate) fn restore_security_context(path: &Path) {
+    if !is_system_path(path) || !selinux_enabled() {
+        return;
+    }
+    if crate::doctor::find_in_path("restorecon").is_none() {
+        log::debug!(target: "privilege", "SELinux is enabled but restorecon is not in PATH");
+        return;
+    }
+
+    let status = sudo_command("restorecon")
+        .args(["-R", &path.to_string_lossy()])
+        .stdout(std::process::Stdio::null())
+        .stderr(std::process::Stdio::null())
+        .status();
+
+    match status {
+        Ok(status) if !status.success() => {
+            log::debug!(target: "privilege", "restorecon failed for {}: {status}", path.display());
+        }
+        Err(e) if e.kind() != std::io::ErrorKind::NotFound => {
+            log::debug!(target: "privilege", "failed to run restorecon: {e}");
+        }
+        _ => {}
+    }
+}
+
+/// `true` if the SELinux filesystem is mounted, i.e. SELinux is enabled.
+fn selinux_enabled() -> bool {
+    Path::new("/sys/fs/selinux/enforce").exists()
+}
+
 /// Writes content to a file, using sudo tee if the path requires it.
 pub(crate) fn write_file(path: &Path, content: &[u8]) -> Result<()> {
     if needs_sudo(path) {
@@ -174,6 +379,33 @@ mod tests {
         }
     }
 
+    #[test]
+    fn writability_is_judged_by_nearest_existing_parent() {
+        let dir = tempfile::tempdir().unwrap();
+        assert!(is_writable(&dir.path().join("missing/deeper/file")));
+        if !is_root() {
+            assert!(!is_writable(Path::new("/usr/share/missing-dir")));
+        }
+    }
+
+    #[test]
+    fn immutable_system_detected_from_marker_files() {
+        let root = tempfile::tempdir().unwrap();
+        std::fs::create_dir_all(root.path().join("usr/share")).unwrap();
+        assert_eq!(immutable_system_in(root.path()), None);
+
+        std::fs::create_dir_all(root.path().join("run")).unwrap();
+        std::fs::write(root.path().join("run/ostree-booted"), "").unwrap();
+        assert_eq!(
+            immutable_system_in(root.path()).as_deref(),
+            Some("an ostree-based system")
+        );
+
+        std::fs::create_dir_all(root.path().join("etc")).unwrap();
+        std::fs::write(root.path().join("etc/NIXOS"), "").unwrap();
+        assert_eq!(immutable_system_in(root.path()).as_deref(), Some("NixOS"));
+    }
+
     #[test]
     fn test_sudo_command() {
         let cmd = sudo_command("kpackagetool6");
@@ -312,4 +544,67 @@ mod tests {
             "content"
         );
     }
+
+    #[test]
+    fn ownership_apply_restores_mode() {
+        let dir = tempfile::tempdir().unwrap();
+        let file = dir.path().join("file");
+        std::fs::write(&file, b"data").unwrap();
+        std::fs::set_permissions(&file, std::fs::Permissions::from_mode(0o600)).unwrap();
+
+        let wanted = Ownership {
+            mode: 0o644,
+            ..Ownership::of(&file).unwrap()
+        };
+        wanted.apply(&file).unwrap();
+
+        assert_eq!(Ownership::of(&file), Some(wanted));
+    }
+
+    #[test]
+    fn ownership_of_ignores_symlinks() {
+        let dir = tempfile::tempdir().unwrap();
+        let link = dir.path().join("link");
+        std::os::unix::fs::symlink(dir.path(), &link).unwrap();
+
+        assert_eq!(Ownership::of(&link), None);
+    }
+
+    #[test]
+    fn copy_file_keeps_mode() {
+        let dir = tempfile::tempdir().unwrap();
+        let src = dir.path().join("script.sh");
+        let dest = dir.path().join("copy.sh");
+        std::fs::write(&src, b"#!/bin/sh").unwrap();
+        std::fs::set_permissions(&src, std::fs::Permissions::from_mode(0o750)).unwrap();
+
+        copy_file(&src, &dest).unwrap();
+
+        assert_eq!(Ownership::of(&dest).unwrap().mode, 0o750);
+    }
+
+    #[test]
+    fn user_command_runs_as_target_user_with_their_home() {
+        let user = UserScope {
+            name: "alice".to_string(),
+            uid: 1001,
+            home: "/home/alice".into(),
+        };
+        let cmd = user_command(&user, "plasmoid-updater".as_ref());
+
+        assert_eq!(cmd.get_program(), "sudo");
+        let args: Vec<_> = cmd.get_args().collect();
+        assert_eq!(
+            args,
+            [
+                "-u",
+                "alice",
+                "-H",
+                "--",
+                "env",
+                "SUDO_USER_HOME=/home/alice",
+                "plasmoid-updater"
+            ]
+        );
+    }
 }
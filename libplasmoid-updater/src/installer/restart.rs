@@ -0,0 +1,451 @@
+// SPDX-License-Identifier: GPL-3.0-or-later
+
+use std::{
+    collections::BTreeSet,
+    path::Path,
+    process::Command,
+    thread,
+    time::{Duration, Instant},
+};
+
+use crate::{
+    paths,
+    types::{AvailableUpdate, ComponentType, InstalledComponent, RestartAction},
+    {Error, Result},
+};
+
+fn get_user_id() -> String {
+    std::env::var("UID").unwrap_or_else(|_| nix::unistd::Uid::current().as_raw().to_string())
+}
+
+/// Builds a command that talks to the user's session services, pointing it at
+/// the session bus and runtime dir when they are not set (e.g. under sudo).
+fn session_command(program: &str) -> Command {
+    let mut cmd = Command::new(program);
+    let uid = get_user_id();
+
+    if std::env::var("DBUS_SESSION_BUS_ADDRESS").is_err() {
+        cmd.env(
+            "DBUS_SESSION_BUS_ADDRESS",
+            format!("unix:path=/run/user/{uid}/bus"),
+        );
+    }
+
+    if std::env::var("XDG_RUNTIME_DIR").is_err() {
+        cmd.env("XDG_RUNTIME_DIR", format!("/run/user/{uid}"));
+    }
+
+    cmd
+}
+
+/// Restarts the plasmashell service via systemd.
+fn restart_plasmashell() -> Result<()> {
+    let status = session_command("systemctl")
+        .args(["--user", "restart", PLASMASHELL_UNIT])
+        .status()
+        .map_err(|e| Error::restart(format!("failed to run systemctl: {e}")))?;
+
+    if !status.success() {
+        return Err(Error::restart(format!(
+            "systemctl exited with status {status}"
+        )));
+    }
+
+    Ok(())
+}
+
+/// Stops plasmashell, puts the layout from `backup` back and starts it
+/// again. The layout cannot be restored while plasmashell runs, as it saves
+/// its own copy on exit.
+pub(crate) fn restart_with_layout(backup: &Path) -> Result<()> {
+    plasmashell_unit("stop")?;
+    let restored = super::backup::restore_layout(backup);
+    plasmashell_unit("start")?;
+    restored
+}
+
+fn plasmashell_unit(verb: &str) -> Result<()> {
+    let status = session_command("systemctl")
+        .args(["--user", verb, PLASMASHELL_UNIT])
+        .status()
+        .map_err(|e| Error::restart(format!("failed to run systemctl: {e}")))?;
+
+    if !status.success() {
+        return Err(Error::restart(format!(
+            "systemctl {verb} exited with status {status}"
+        )));
+    }
+
+    Ok(())
+}
+
+const PLASMASHELL_UNIT: &str = "plasma-plasmashell.service";
+
+/// How often [`monitor_restart`] looks at plasmashell.
+const MONITOR_POLL_INTERVAL: Duration = Duration::from_secs(1);
+
+/// How plasmashell fared after a restart, see [`monitor_restart`].
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub(crate) enum RestartHealth {
+    Stable,
+    /// plasmashell crashed; the reason says how this was detected.
+    Crashed(String),
+}
+
+/// Watches plasmashell for `window` after a restart.
+///
+/// Reports a crash as soon as the systemd unit has failed, systemd has
+/// restarted it on its own, or `coredumpctl` lists a plasmashell core dump
+/// since monitoring began. Missing tools are treated as "no crash seen".
+pub(crate) fn monitor_restart(window: Duration) -> RestartHealth {
+    let since = chrono::Local::now().format("%Y-%m-%d %H:%M:%S").to_string();
+    let baseline = plasmashell_unit_state().map(|state| state.restarts);
+    let deadline = Instant::now() + window;
+
+    loop {
+        if let Some(state) = plasmashell_unit_state() {
+            if state.active == "failed" {
+                return RestartHealth::Crashed(format!(
+                    "{PLASMASHELL_UNIT} failed ({})",
+                    state.result
+                ));
+            }
+            if baseline.is_some_and(|restarts| state.restarts > restarts) {
+                return RestartHealth::Crashed(format!(
+                    "{PLASMASHELL_UNIT} exited and was restarted by systemd"
+                ));
+            }
+        }
+        if let Some(dump) = plasmashell_coredump(&since) {
+            return RestartHealth::Crashed(format!("plasmashell dumped core: {dump}"));
+        }
+
+        let now = Instant::now();
+        if now >= deadline {
+            return RestartHealth::Stable;
+        }
+        thread::sleep(MONITOR_POLL_INTERVAL.min(deadline - now));
+    }
+}
+
+/// Properties of the plasmashell systemd unit relevant to crash detection.
+#[derive(Debug, Default, PartialEq, Eq)]
+struct UnitState {
+    active: String,
+    result: String,
+    restarts: u32,
+}
+
+fn plasmashell_unit_state() -> Option<UnitState> {
+    let output = session_command("systemctl")
+        .args(["--user", "show", PLASMASHELL_UNIT])
+        .args(["-p", "ActiveState", "-p", "Result", "-p", "NRestarts"])
+        .output()
+        .ok()?;
+    output
+        .status
+        .success()
+        .then(|| parse_unit_state(&String::from_utf8_lossy(&output.stdout)))
+}
+
+/// Parses `systemctl show` output (`Key=Value` lines).
+fn parse_unit_state(output: &str) -> UnitState {
+    let mut state = UnitState::default();
+    for (key, value) in output.lines().filter_map(|line| line.split_once('=')) {
+        match key {
+            "ActiveState" => state.active = value.to_string(),
+            "Result" => state.result = value.to_string(),
+            "NRestarts" => state.restarts = value.parse().unwrap_or_default(),
+            _ => {}
+        }
+    }
+    state
+}
+
+/// First plasmashell core dump recorded since `since`, as listed by
+/// `coredumpctl`.
+fn plasmashell_coredump(since: &str) -> Option<String> {
+    let output = Command::new("coredumpctl")
+        .args(["list", "--no-pager", "--no-legend", "--since", since])
+        .arg("COREDUMP_COMM=plasmashell")
+        .output()
+        .ok()?;
+    if !output.status.success() {
+        // coredumpctl exits non-zero when nothing matches.
+        return None;
+    }
+    String::from_utf8_lossy(&output.stdout)
+        .lines()
+        .map(str::trim)
+        .find(|line| !line.is_empty())
+        .map(str::to_string)
+}
+
+/// Rebuilds the fontconfig cache so applications see updated font files.
+fn refresh_font_cache() -> Result<()> {
+    let status = Command::new("fc-cache")
+        .arg("-f")
+        .status()
+        .map_err(|e| Error::restart(format!("failed to run fc-cache: {e}")))?;
+    if !status.success() {
+        return Err(Error::restart(format!(
+            "fc-cache exited with status {status}"
+        )));
+    }
+    Ok(())
+}
+
+/// Asks KWin to reload its configuration so updated effects, scripts,
+/// switchers and window decorations are picked up without logging out.
+///
+/// Calls `org.kde.KWin.reconfigure` on the session bus (requires `inhibit`
+/// feature / `zbus`), falling back to `qdbus6` or `qdbus`. When an Aurorae
+/// decoration is among `updates`, the `org.kde.KWin.reloadConfig` signal is
+/// emitted first, as the window decoration settings do, so the decoration
+/// re-reads its theme.
+fn reload_kwin(updates: &[&AvailableUpdate]) -> Result<()> {
+    if updates
+        .iter()
+        .any(|u| u.installed.component_type == ComponentType::AuroraeDecoration)
+        && let Err(e) = emit_reload_config()
+    {
+        log::warn!(target: "restart", "failed to signal KWin to reload its config: {e}");
+    }
+
+    #[cfg(feature = "inhibit")]
+    match dbus_reconfigure_kwin() {
+        Ok(()) => return Ok(()),
+        Err(e) => log::debug!(target: "restart", "KWin reconfigure over DBus failed: {e}"),
+    }
+
+    let mut last_error = None;
+    for program in ["qdbus6", "qdbus"] {
+        match session_command(program)
+            .args(["org.kde.KWin", "/KWin", "reconfigure"])
+            .status()
+        {
+            Ok(status) if status.success() => return Ok(()),
+            Ok(status) => last_error = Some(format!("{program} exited with status {status}")),
+            Err(e) => last_error = Some(format!("failed to run {program}: {e}")),
+        }
+    }
+
+    Err(Error::restart(last_error.unwrap_or_default()))
+}
+
+/// Emits `org.kde.KWin.reloadConfig` on `/KWin`, falling back to `dbus-send`.
+fn emit_reload_config() -> Result<()> {
+    #[cfg(feature = "inhibit")]
+    match dbus_emit_reload_config() {
+        Ok(()) => return Ok(()),
+        Err(e) => log::debug!(target: "restart", "KWin reloadConfig over DBus failed: {e}"),
+    }
+
+    let status = session_command("dbus-send")
+        .args([
+            "--session",
+            "--type=signal",
+            "/KWin",
+            "org.kde.KWin.reloadConfig",
+        ])
+        .status()
+        .map_err(|e| Error::restart(format!("failed to run dbus-send: {e}")))?;
+    if !status.success() {
+        return Err(Error::restart(format!(
+            "dbus-send exited with status {status}"
+        )));
+    }
+    Ok(())
+}
+
+#[cfg(feature = "inhibit")]
+fn dbus_emit_reload_config() -> zbus::Result<()> {
+    let conn = zbus::blocking::Connection::session()?;
+    conn.emit_signal(None::<&str>, "/KWin", "org.kde.KWin", "reloadConfig", &())?;
+    Ok(())
+}
+
+#[cfg(feature = "inhibit")]
+fn dbus_reconfigure_kwin() -> zbus::Result<()> {
+    let conn = zbus::blocking::Connection::session()?;
+    conn.call_method(
+        Some("org.kde.KWin"),
+        "/KWin",
+        Some("org.kde.KWin"),
+        "reconfigure",
+        &(),
+    )?;
+    Ok(())
+}
+
+/// Re-applies every updated theme that is currently in use (global theme,
+/// Plasma style or color scheme), so the new version replaces the copy
+/// Plasma has already loaded.
+fn reapply_active_themes(updates: &[&AvailableUpdate]) -> Result<()> {
+    let mut result = Ok(());
+    for component in updated_active_themes(updates) {
+        if let Err(e) = apply_theme(component) {
+            log::warn!(target: "restart", "failed to re-apply {}: {e}", component.name);
+            result = Err(e);
+        }
+    }
+    result
+}
+
+fn apply_theme(component: &InstalledComponent) -> Result<()> {
+    let name = theme_name(component);
+    let (program, args): (&str, &[&str]) = match component.component_type {
+        ComponentType::GlobalTheme => ("plasma-apply-lookandfeel", &["--apply", name]),
+        ComponentType::PlasmaStyle => ("plasma-apply-desktoptheme", &[name]),
+        ComponentType::ColorScheme => ("plasma-apply-colorscheme", &[name]),
+        _ => return Ok(()),
+    };
+
+    let status = session_command(program)
+        .args(args)
+        .status()
+        .map_err(|e| Error::restart(format!("failed to run {program}: {e}")))?;
+
+    if !status.success() {
+        return Err(Error::restart(format!(
+            "{program} exited with status {status}"
+        )));
+    }
+
+    Ok(())
+}
+
+/// Returns the updated themes that are currently applied.
+fn updated_active_themes<'a>(updates: &[&'a AvailableUpdate]) -> Vec<&'a InstalledComponent> {
+    updates
+        .iter()
+        .map(|u| &u.installed)
+        .filter(|c| active_theme(c.component_type).is_some_and(|active| active == theme_name(c)))
+        .collect()
+}
+
+/// Name Plasma uses for a theme: the package ID, or the file stem for color
+/// schemes (`Foo.colors` is referred to as `Foo`).
+fn theme_name(component: &InstalledComponent) -> &str {
+    match component.component_type {
+        ComponentType::ColorScheme => component
+            .directory_name
+            .strip_suffix(".colors")
+            .unwrap_or(&component.directory_name),
+        _ => &component.directory_name,
+    }
+}
+
+/// Reads the active theme of `component_type` from the Plasma config files.
+fn active_theme(component_type: ComponentType) -> Option<String> {
+    let (file, section, key) = match component_type {
+        ComponentType::GlobalTheme => ("kdeglobals", "KDE", "LookAndFeelPackage"),
+        ComponentType::PlasmaStyle => ("plasmarc", "Theme", "name"),
+        ComponentType::ColorScheme => ("kdeglobals", "General", "ColorScheme"),
+        _ => return None,
+    };
+    read_config_value(&paths::config_home().join(file), section, key)
+}
+
+fn read_config_value(path: &Path, section: &str, key: &str) -> Option<String> {
+    let entry = freedesktop_entry_parser::parse_entry(path).ok()?;
+    entry
+        .section(section)?
+        .attr(key)
+        .first()
+        .map(|s| s.to_string())
+}
+
+/// Returns the distinct actions `updates` need, least disruptive first.
+/// Types that need nothing are left out, as is re-applying themes when none
+/// of the updated ones is in use.
+pub(crate) fn required_actions(updates: &[&AvailableUpdate]) -> BTreeSet<RestartAction> {
+    let mut actions: BTreeSet<_> = updates
+        .iter()
+        .map(|u| u.restart_action)
+        .filter(|&action| action != RestartAction::None)
+        .collect();
+
+    if actions.contains(&RestartAction::LookAndFeelReapply)
+        && updated_active_themes(updates).is_empty()
+    {
+        actions.remove(&RestartAction::LookAndFeelReapply);
+    }
+
+    actions
+}
+
+/// Performs `action` for `updates`. No-op actions succeed immediately.
+pub(crate) fn run_action(action: RestartAction, updates: &[&AvailableUpdate]) -> Result<()> {
+    match action {
+        RestartAction::None | RestartAction::SddmNone => Ok(()),
+        RestartAction::FontCacheRefresh => refresh_font_cache(),
+        RestartAction::KWinReconfigure => reload_kwin(updates),
+        RestartAction::LookAndFeelReapply => reapply_active_themes(updates),
+        RestartAction::PlasmashellRestart => restart_plasmashell(),
+    }
+}
+
+/// Short imperative description, e.g. for prompts and warnings.
+pub(crate) const fn describe(action: RestartAction) -> &'static str {
+    match action {
+        RestartAction::None | RestartAction::SddmNone => "do nothing",
+        RestartAction::FontCacheRefresh => "refresh the font cache",
+        RestartAction::KWinReconfigure => "reconfigure KWin",
+        RestartAction::LookAndFeelReapply => "re-apply the updated themes",
+        RestartAction::PlasmashellRestart => "restart plasmashell",
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn component(component_type: ComponentType, directory_name: &str) -> InstalledComponent {
+        InstalledComponent::fixture(directory_name, component_type, std::path::PathBuf::new())
+    }
+
+    #[test]
+    fn parse_unit_state_reads_systemctl_show_output() {
+        let state = parse_unit_state("ActiveState=failed\nResult=core-dump\nNRestarts=3\n");
+        assert_eq!(
+            state,
+            UnitState {
+                active: "failed".to_string(),
+                result: "core-dump".to_string(),
+                restarts: 3,
+            }
+        );
+        assert_eq!(parse_unit_state("").restarts, 0);
+    }
+
+    #[test]
+    fn theme_name_strips_color_scheme_extension() {
+        let scheme = component(ComponentType::ColorScheme, "Nordic.colors");
+        assert_eq!(theme_name(&scheme), "Nordic");
+
+        let theme = component(ComponentType::GlobalTheme, "org.example.desktop");
+        assert_eq!(theme_name(&theme), "org.example.desktop");
+    }
+
+    #[test]
+    fn read_config_value_finds_key_in_section() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("kdeglobals");
+        std::fs::write(
+            &path,
+            "[General]\nColorScheme=Nordic\n\n[KDE]\nLookAndFeelPackage=org.example.desktop\n",
+        )
+        .unwrap();
+
+        assert_eq!(
+            read_config_value(&path, "General", "ColorScheme").as_deref(),
+            Some("Nordic")
+        );
+        assert_eq!(
+            read_config_value(&path, "KDE", "LookAndFeelPackage").as_deref(),
+            Some("org.example.desktop")
+        );
+        assert_eq!(read_config_value(&path, "KDE", "ColorScheme"), None);
+    }
+}
@@ -0,0 +1,273 @@
+// SPDX-License-Identifier: GPL-3.0-or-later
+
+use std::{
+    fs,
+    path::{Path, PathBuf},
+};
+
+use crate::{Error, Result, types::InstalledComponent};
+
+/// Where SDDM looks for themes when its config doesn't set `ThemeDir`.
+const DEFAULT_THEME_DIR: &str = "/usr/share/sddm/themes";
+
+/// `theme.conf` keys naming a file in the theme directory.
+const FILE_KEYS: &[&str] = &["background", "Background"];
+
+/// Checks an updated SDDM theme can still draw the login screen.
+///
+/// A missing main QML file, or the active theme (`[Theme] Current` in the
+/// SDDM config) no longer resolving, leaves SDDM without a greeter, so these
+/// fail with [`Error::SddmValidationFailed`] and the update is rolled back.
+/// Broken `theme.conf` references and a missing preview are returned as
+/// problems instead.
+pub(super) fn validate(component: &InstalledComponent) -> Result<Vec<String>> {
+    validate_in(component, Path::new("/"))
+}
+
+fn validate_in(component: &InstalledComponent, root: &Path) -> Result<Vec<String>> {
+    let dir = &component.path;
+    let theme = ThemeMetadata::read(dir);
+    let mut fatal = Vec::new();
+    let mut problems = Vec::new();
+
+    if !dir.join(&theme.main_script).is_file() {
+        log::warn!(
+            target: "health",
+            "{} dropped {}; SDDM would have no greeter",
+            component.name,
+            theme.main_script
+        );
+        fatal.push(format!("missing {}", theme.main_script));
+    }
+
+    let config = dir.join(&theme.config_file);
+    if config.is_file() {
+        problems.extend(check_theme_config(dir, &config, &theme.config_file));
+    } else {
+        problems.push(format!("missing {}", theme.config_file));
+    }
+
+    if let Some(screenshot) = &theme.screenshot
+        && !dir.join(screenshot).is_file()
+    {
+        problems.push(format!("preview {screenshot} is missing"));
+    }
+
+    if let Some((current, theme_dir)) = active_theme(root)
+        && current == component.directory_name
+        && !theme_dir.join(&current).join(&theme.main_script).is_file()
+    {
+        fatal.push(format!(
+            "active theme '{current}' no longer resolves in {}",
+            theme_dir.display()
+        ));
+    }
+
+    if !fatal.is_empty() {
+        return Err(Error::sddm_validation(fatal.join("; ")));
+    }
+    Ok(problems)
+}
+
+/// The `[SddmGreeterTheme]` group of a theme's `metadata.desktop`.
+struct ThemeMetadata {
+    main_script: String,
+    config_file: String,
+    screenshot: Option<String>,
+}
+
+impl ThemeMetadata {
+    fn read(dir: &Path) -> Self {
+        let entry = freedesktop_entry_parser::parse_entry(dir.join("metadata.desktop")).ok();
+        let value = |key: &str| {
+            entry
+                .as_ref()?
+                .section("SddmGreeterTheme")?
+                .attr(key)
+                .first()
+                .map(|s| s.trim().to_string())
+                .filter(|s| !s.is_empty())
+        };
+        Self {
+            main_script: value("MainScript").unwrap_or_else(|| "Main.qml".to_string()),
+            config_file: value("ConfigFile").unwrap_or_else(|| "theme.conf".to_string()),
+            screenshot: value("Screenshot"),
+        }
+    }
+}
+
+/// Returns problems with `theme.conf`: an unreadable file, a missing
+/// `[General]` group, or a file key pointing at nothing.
+fn check_theme_config(dir: &Path, config: &Path, name: &str) -> Vec<String> {
+    let entry = match freedesktop_entry_parser::parse_entry(config) {
+        Ok(entry) => entry,
+        Err(e) => return vec![format!("{name}: {e}")],
+    };
+    let Some(general) = entry.section("General") else {
+        return vec![format!("{name}: no [General] group")];
+    };
+
+    FILE_KEYS
+        .iter()
+        .filter_map(|&key| {
+            let value = general.attr(key).first()?.trim().to_string();
+            // Colors and empty values are not files.
+            if value.is_empty() || value.starts_with('#') || dir.join(&value).exists() {
+                return None;
+            }
+            Some(format!("{name}: {key} {value} does not exist"))
+        })
+        .collect()
+}
+
+/// SDDM config files below `root`, lowest precedence first.
+fn config_files(root: &Path) -> Vec<PathBuf> {
+    let mut files = Vec::new();
+    for dir in ["usr/lib/sddm/sddm.conf.d", "etc/sddm.conf.d"] {
+        let mut conf: Vec<PathBuf> = fs::read_dir(root.join(dir))
+            .into_iter()
+            .flatten()
+            .flatten()
+            .map(|entry| entry.path())
+            .filter(|path| path.extension().is_some_and(|ext| ext == "conf"))
+            .collect();
+        conf.sort();
+        files.extend(conf);
+    }
+    files.push(root.join("etc/sddm.conf"));
+    files
+}
+
+/// Returns the configured theme and the directory SDDM loads it from, or
+/// `None` if SDDM uses its built-in theme.
+fn active_theme(root: &Path) -> Option<(String, PathBuf)> {
+    let mut current = None;
+    let mut theme_dir = PathBuf::from(DEFAULT_THEME_DIR);
+
+    for file in config_files(root) {
+        let Ok(entry) = freedesktop_entry_parser::parse_entry(&file) else {
+            continue;
+        };
+        let Some(section) = entry.section("Theme") else {
+            continue;
+        };
+        if let Some(value) = section.attr("Current").first() {
+            current = Some(value.trim().to_string());
+        }
+        if let Some(value) = section.attr("ThemeDir").first() {
+            theme_dir = PathBuf::from(value.trim());
+        }
+    }
+
+    current
+        .filter(|name| !name.is_empty())
+        .map(|name| (name, theme_dir))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::types::ComponentType;
+
+    fn theme(dir: &Path) -> InstalledComponent {
+        InstalledComponent {
+            directory_name: dir.file_name().unwrap().to_string_lossy().into_owned(),
+            is_system: true,
+            ..InstalledComponent::fixture("Sugar", ComponentType::SddmTheme, dir)
+        }
+    }
+
+    /// Writes a minimal SDDM theme: metadata, `Main.qml`, `theme.conf` and
+    /// the background it names.
+    fn write_theme(dir: &Path) {
+        fs::create_dir_all(dir.join("Backgrounds")).unwrap();
+        fs::write(
+            dir.join("metadata.desktop"),
+            "[SddmGreeterTheme]\nName=Sugar\nMainScript=Main.qml\nConfigFile=theme.conf\nScreenshot=preview.png\n",
+        )
+        .unwrap();
+        fs::write(dir.join("Main.qml"), "import QtQuick\n\nItem {}\n").unwrap();
+        fs::write(
+            dir.join("theme.conf"),
+            "[General]\nbackground=Backgrounds/mountain.jpg\ncolor=#1d99f3\n",
+        )
+        .unwrap();
+        fs::write(dir.join("Backgrounds/mountain.jpg"), "jpg").unwrap();
+        fs::write(dir.join("preview.png"), "png").unwrap();
+    }
+
+    fn select_theme(root: &Path, conf: &str, name: &str, theme_dir: &Path) {
+        let path = root.join(conf);
+        fs::create_dir_all(path.parent().unwrap()).unwrap();
+        fs::write(
+            path,
+            format!(
+                "[Theme]\nCurrent={name}\nThemeDir={}\n",
+                theme_dir.display()
+            ),
+        )
+        .unwrap();
+    }
+
+    #[test]
+    fn complete_theme_passes() {
+        let root = tempfile::tempdir().unwrap();
+        let themes = root.path().join("themes");
+        write_theme(&themes.join("sugar"));
+        select_theme(root.path(), "etc/sddm.conf.d/theme.conf", "sugar", &themes);
+
+        let problems = validate_in(&theme(&themes.join("sugar")), root.path()).unwrap();
+
+        assert!(problems.is_empty(), "{problems:?}");
+    }
+
+    #[test]
+    fn dropped_main_qml_fails() {
+        let root = tempfile::tempdir().unwrap();
+        let dir = root.path().join("themes/sugar");
+        write_theme(&dir);
+        fs::remove_file(dir.join("Main.qml")).unwrap();
+
+        let err = validate_in(&theme(&dir), root.path()).unwrap_err();
+
+        assert!(matches!(err, Error::SddmValidationFailed(ref m) if m.contains("Main.qml")));
+    }
+
+    #[test]
+    fn active_theme_must_resolve_in_configured_theme_dir() {
+        let root = tempfile::tempdir().unwrap();
+        let themes = root.path().join("themes");
+        write_theme(&themes.join("sugar"));
+        // Overridden by /etc/sddm.conf, which SDDM reads last.
+        select_theme(root.path(), "etc/sddm.conf.d/theme.conf", "sugar", &themes);
+        select_theme(
+            root.path(),
+            "etc/sddm.conf",
+            "sugar",
+            &root.path().join("elsewhere"),
+        );
+
+        let err = validate_in(&theme(&themes.join("sugar")), root.path()).unwrap_err();
+
+        assert!(matches!(err, Error::SddmValidationFailed(ref m) if m.contains("active theme")));
+    }
+
+    #[test]
+    fn broken_theme_conf_and_preview_are_problems() {
+        let root = tempfile::tempdir().unwrap();
+        let dir = root.path().join("themes/sugar");
+        write_theme(&dir);
+        fs::remove_file(dir.join("Backgrounds/mountain.jpg")).unwrap();
+        fs::remove_file(dir.join("preview.png")).unwrap();
+
+        let problems = validate_in(&theme(&dir), root.path()).unwrap();
+
+        assert_eq!(
+            problems,
+            [
+                "theme.conf: background Backgrounds/mountain.jpg does not exist",
+                "preview preview.png is missing",
+            ]
+        );
+    }
+}
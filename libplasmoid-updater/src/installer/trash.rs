@@ -0,0 +1,174 @@
+// SPDX-License-Identifier: GPL-3.0-or-later
+//
+// Trash layout from the FreeDesktop.org Trash specification
+// (https://specifications.freedesktop.org/trash-spec/latest/)
+
+use std::{
+    fs,
+    io::Write as _,
+    path::{Path, PathBuf},
+};
+
+use crate::{Error, Result};
+
+use super::backup::copy_dir_recursive;
+
+/// Returns the user's home trash, `$XDG_DATA_HOME/Trash`.
+fn trash_dir() -> PathBuf {
+    crate::paths::data_home().join("Trash")
+}
+
+/// Moves `path` to the trash, where Dolphin can restore it. Falls back to
+/// copying and deleting if the trash is on another filesystem.
+///
+/// Returns the path of the trashed copy.
+pub(crate) fn move_to_trash(path: &Path) -> Result<PathBuf> {
+    trash_in(&trash_dir(), path, path, false)
+}
+
+/// Copies `source` to the trash as the deleted `original`, so restoring it
+/// from the trash puts it back at `original`.
+///
+/// Returns the path of the trashed copy.
+pub(crate) fn copy_to_trash(source: &Path, original: &Path) -> Result<PathBuf> {
+    trash_in(&trash_dir(), source, original, true)
+}
+
+fn trash_in(trash: &Path, source: &Path, original: &Path, keep_source: bool) -> Result<PathBuf> {
+    let files = trash.join("files");
+    let info = trash.join("info");
+    fs::create_dir_all(&files)?;
+    fs::create_dir_all(&info)?;
+
+    let name = original
+        .file_name()
+        .ok_or_else(|| Error::other(format!("cannot trash {}", original.display())))?
+        .to_string_lossy();
+    // The info file is created first: it reserves the name.
+    let mut n = 1;
+    let (dest, info_path) = loop {
+        let name = match n {
+            1 => name.to_string(),
+            n => format!("{name} ({n})"),
+        };
+        let (dest, info_path) = (files.join(&name), info.join(format!("{name}.trashinfo")));
+        n += 1;
+        if dest.symlink_metadata().is_ok() {
+            continue;
+        }
+        match reserve(&info_path, original) {
+            Ok(()) => break (dest, info_path),
+            Err(e) if e.kind() == std::io::ErrorKind::AlreadyExists => continue,
+            Err(e) => return Err(e.into()),
+        }
+    };
+
+    let moved = if keep_source {
+        copy(source, &dest)
+    } else {
+        fs::rename(source, &dest)
+            .map_err(Error::from)
+            .or_else(|_| copy(source, &dest).and_then(|()| remove(source)))
+    };
+    if let Err(e) = moved {
+        let _ = fs::remove_file(&info_path);
+        return Err(e);
+    }
+    Ok(dest)
+}
+
+fn reserve(info_path: &Path, original: &Path) -> std::io::Result<()> {
+    let mut file = fs::OpenOptions::new()
+        .write(true)
+        .create_new(true)
+        .open(info_path)?;
+    write!(
+        file,
+        "[Trash Info]\nPath={}\nDeletionDate={}\n",
+        encode_path(original),
+        chrono::Local::now().format("%Y-%m-%dT%H:%M:%S")
+    )
+}
+
+fn copy(source: &Path, dest: &Path) -> Result<()> {
+    if source.is_dir() {
+        copy_dir_recursive(source, dest)
+    } else {
+        fs::copy(source, dest)?;
+        Ok(())
+    }
+}
+
+fn remove(path: &Path) -> Result<()> {
+    if path.is_dir() {
+        fs::remove_dir_all(path)?;
+    } else {
+        fs::remove_file(path)?;
+    }
+    Ok(())
+}
+
+/// Percent-encodes `path` for the `Path=` key, which holds a URL path.
+fn encode_path(path: &Path) -> String {
+    path.as_os_str()
+        .as_encoded_bytes()
+        .iter()
+        .map(|&b| match b {
+            b'A'..=b'Z' | b'a'..=b'z' | b'0'..=b'9' | b'/' | b'-' | b'_' | b'.' | b'~' => {
+                (b as char).to_string()
+            }
+            b => format!("%{b:02X}"),
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn encode_path_escapes_reserved_bytes() {
+        assert_eq!(
+            encode_path(Path::new("/home/me/My Widget/ü")),
+            "/home/me/My%20Widget/%C3%BC"
+        );
+    }
+
+    #[test]
+    fn move_to_trash_writes_info_and_avoids_collisions() {
+        let dir = tempfile::tempdir().unwrap();
+        let trash = dir.path().join("Trash");
+        let component = dir.path().join("org.example.clock");
+
+        for version in ["1.0", "2.0"] {
+            fs::create_dir(&component).unwrap();
+            fs::write(component.join("version"), version).unwrap();
+            trash_in(&trash, &component, &component, false).unwrap();
+            assert!(!component.exists());
+        }
+
+        let second = trash.join("files/org.example.clock (2)");
+        assert_eq!(fs::read_to_string(second.join("version")).unwrap(), "2.0");
+        let info = fs::read_to_string(trash.join("info/org.example.clock (2).trashinfo")).unwrap();
+        assert!(info.starts_with("[Trash Info]\n"));
+        assert!(info.contains(&format!("\nPath={}\n", encode_path(&component))));
+        assert!(info.contains("\nDeletionDate="));
+    }
+
+    #[test]
+    fn copy_to_trash_keeps_source_and_records_original() {
+        let dir = tempfile::tempdir().unwrap();
+        let trash = dir.path().join("Trash");
+        let backup = dir.path().join("backup.colors");
+        let original = dir.path().join("Nord.colors");
+        fs::write(&backup, "[General]").unwrap();
+
+        let trashed = trash_in(&trash, &backup, &original, true).unwrap();
+
+        assert!(backup.exists());
+        assert_eq!(trashed, trash.join("files/Nord.colors"));
+        assert_eq!(fs::read_to_string(trashed).unwrap(), "[General]");
+        let info = fs::read_to_string(trash.join("info/Nord.colors.trashinfo")).unwrap();
+        assert!(info.contains(&encode_path(&original)));
+    }
+}
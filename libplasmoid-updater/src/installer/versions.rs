@@ -0,0 +1,321 @@
+// SPDX-License-Identifier: GPL-3.0-or-later
+
+use std::{
+    fs,
+    path::{Path, PathBuf},
+};
+
+use serde::{Deserialize, Serialize};
+
+use super::backup::{backup_component, copy_dir_recursive, restore_component};
+use crate::{
+    Config, registry,
+    types::{AvailableUpdate, InstalledComponent},
+    version::normalize_version,
+    {Error, Result},
+};
+
+/// Describes a retained copy, stored next to it.
+const MANIFEST_FILE: &str = "retained.json";
+
+/// A previously installed version kept for [`downgrade`](crate::downgrade),
+/// see [`Config::keep_versions`].
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct RetainedVersion {
+    pub version: String,
+    pub content_id: u64,
+    /// Store download the version came from; empty if it predates the updater.
+    pub download_url: String,
+    pub release_date: String,
+    /// When the copy was taken (RFC 3339).
+    pub retained_at: String,
+}
+
+/// Returns the root of the versioned store.
+///
+/// User components are kept under `$XDG_DATA_HOME/plasmoid-updater/versions`,
+/// system components next to the system registry.
+pub(crate) fn versions_dir(system: bool) -> PathBuf {
+    if system {
+        crate::paths::system_state_dir("versions")
+    } else {
+        crate::paths::data_home().join("plasmoid-updater/versions")
+    }
+}
+
+/// Directory name for a version; versions are free-form store strings.
+fn version_key(version: &str) -> String {
+    let key: String = version
+        .chars()
+        .map(|c| if c == '/' || c.is_control() { '_' } else { c })
+        .collect();
+    match key.trim_start_matches('.') {
+        "" => "unversioned".to_string(),
+        trimmed => trimmed.to_string(),
+    }
+}
+
+/// Copies `component` as currently installed into the versioned store and
+/// prunes the store to the `keep` newest copies.
+///
+/// The copy is recorded as `record.version`; an existing copy of that version
+/// is replaced.
+pub(crate) fn retain(
+    component: &InstalledComponent,
+    record: &RetainedVersion,
+    keep: usize,
+) -> Result<()> {
+    retain_in(&versions_dir(component.is_system), component, record, keep)
+}
+
+fn retain_in(
+    base: &Path,
+    component: &InstalledComponent,
+    record: &RetainedVersion,
+    keep: usize,
+) -> Result<()> {
+    let Some(file_name) = component.path.file_name() else {
+        return Ok(());
+    };
+    if !component.path.exists() {
+        return Ok(());
+    }
+
+    let component_dir = base.join(&component.directory_name);
+    let dir = component_dir.join(version_key(&record.version));
+    if dir.exists() {
+        fs::remove_dir_all(&dir)?;
+    }
+    fs::create_dir_all(&dir)?;
+
+    let copy = dir.join(file_name);
+    let copied = if component.path.is_file() {
+        fs::copy(&component.path, &copy)
+            .map(|_| ())
+            .map_err(Error::from)
+    } else {
+        copy_dir_recursive(&component.path, &copy)
+    };
+    if let Err(e) = copied {
+        let _ = fs::remove_dir_all(&dir);
+        return Err(e);
+    }
+
+    fs::write(dir.join(MANIFEST_FILE), serde_json::to_vec_pretty(record)?)?;
+    log::debug!(
+        target: "versions",
+        "retained {} {} in {}",
+        component.name,
+        record.version,
+        dir.display()
+    );
+
+    prune(&component_dir, keep);
+    Ok(())
+}
+
+/// Removes all but the `keep` most recently retained versions.
+fn prune(component_dir: &Path, keep: usize) {
+    for (path, _) in read_retained(component_dir).into_iter().skip(keep) {
+        if let Err(e) = fs::remove_dir_all(&path) {
+            log::debug!(target: "versions", "failed to prune {}: {e}", path.display());
+        }
+    }
+}
+
+/// Reads the retained versions in `component_dir`, newest first.
+fn read_retained(component_dir: &Path) -> Vec<(PathBuf, RetainedVersion)> {
+    let Ok(entries) = fs::read_dir(component_dir) else {
+        return Vec::new();
+    };
+
+    let mut retained: Vec<_> = entries
+        .flatten()
+        .filter_map(|entry| {
+            let content = fs::read(entry.path().join(MANIFEST_FILE)).ok()?;
+            let record: RetainedVersion = serde_json::from_slice(&content).ok()?;
+            Some((entry.path(), record))
+        })
+        .collect();
+    retained.sort_by(|a, b| b.1.retained_at.cmp(&a.1.retained_at));
+    retained
+}
+
+/// Lists the retained versions of `component`, newest first.
+pub(crate) fn retained_versions(component: &InstalledComponent) -> Vec<RetainedVersion> {
+    read_retained(&versions_dir(component.is_system).join(&component.directory_name))
+        .into_iter()
+        .map(|(_, record)| record)
+        .collect()
+}
+
+/// Record for the version of `component` that is installed right now.
+pub(crate) fn current_record(component: &InstalledComponent, content_id: u64) -> RetainedVersion {
+    RetainedVersion {
+        version: component.version.clone(),
+        content_id,
+        download_url: String::new(),
+        release_date: component.release_date.clone(),
+        retained_at: chrono::Local::now().to_rfc3339(),
+    }
+}
+
+/// Record for the version `update` just installed.
+pub(crate) fn installed_record(update: &AvailableUpdate) -> RetainedVersion {
+    RetainedVersion {
+        version: update.latest_version.clone(),
+        content_id: update.content_id,
+        download_url: update.download_url.clone(),
+        release_date: update.release_date.clone(),
+        retained_at: chrono::Local::now().to_rfc3339(),
+    }
+}
+
+/// Swaps the retained `version` of `component` into place and records it in
+/// the KNewStuff registry.
+///
+/// The replaced version is retained first when [`Config::keep_versions`] is
+/// set, and restored from a backup if the swap fails.
+pub(crate) fn downgrade(
+    component: &InstalledComponent,
+    version: &str,
+    config: &Config,
+) -> Result<()> {
+    let component_dir = versions_dir(component.is_system).join(&component.directory_name);
+    let retained = read_retained(&component_dir);
+    let Some((dir, record)) = retained
+        .iter()
+        .find(|(_, r)| r.version == version)
+        .or_else(|| {
+            retained
+                .iter()
+                .find(|(_, r)| normalize_version(&r.version) == normalize_version(version))
+        })
+        .cloned()
+    else {
+        let available: Vec<&str> = retained.iter().map(|(_, r)| r.version.as_str()).collect();
+        let available = if available.is_empty() {
+            "none".to_string()
+        } else {
+            available.join(", ")
+        };
+        return Err(Error::other(format!(
+            "no retained version {version} of {} (retained: {available})",
+            component.name
+        )));
+    };
+
+    let Some(file_name) = component.path.file_name() else {
+        return Err(Error::install(format!(
+            "invalid install path {}",
+            component.path.display()
+        )));
+    };
+    let source = dir.join(file_name);
+
+    if config.keep_versions > 0 && record.version != component.version {
+        // Keep one extra slot so the copy being restored is not pruned.
+        let current = current_record(component, record.content_id);
+        if let Err(e) = retain(component, &current, config.keep_versions + 1) {
+            log::warn!(
+                target: "versions",
+                "failed to retain {} {}: {e}",
+                component.name,
+                component.version
+            );
+        }
+    }
+
+    let backup = backup_component(component)?;
+    let update = AvailableUpdate::builder(
+        component.clone(),
+        record.content_id,
+        record.version.clone(),
+        record.download_url.clone(),
+        record.release_date.clone(),
+    )
+    .build();
+    let transaction = super::journal::begin(&update, backup.as_deref())?;
+    if let Err(e) = restore_component(&source, &component.path) {
+        if let Some(backup) = backup {
+            restore_component(&backup, &component.path).map_err(|restore| {
+                Error::InstallAndRestoreFailed {
+                    install_error: e.to_string(),
+                    restore_error: restore.to_string(),
+                }
+            })?;
+        }
+        transaction.finish();
+        return Err(e);
+    }
+    transaction.finish();
+    super::trash_replaced(component, backup.as_deref(), config);
+
+    if let Err(e) = registry::update_registry_after_install(&update) {
+        log::warn!(target: "registry", "failed to update: {e}");
+    }
+    super::record_integrity(component, &record.version);
+
+    if config.keep_versions > 0 {
+        prune(&component_dir, config.keep_versions);
+    }
+
+    log::info!(target: "versions", "downgraded {} to {}", component.name, record.version);
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::types::ComponentType;
+
+    fn component(path: PathBuf, version: &str) -> InstalledComponent {
+        InstalledComponent {
+            directory_name: "org.example.clock".to_string(),
+            version: version.to_string(),
+            ..InstalledComponent::fixture("Clock", ComponentType::PlasmaWidget, path)
+        }
+    }
+
+    fn record(version: &str, retained_at: &str) -> RetainedVersion {
+        RetainedVersion {
+            version: version.to_string(),
+            content_id: 1000,
+            download_url: String::new(),
+            release_date: String::new(),
+            retained_at: retained_at.to_string(),
+        }
+    }
+
+    #[test]
+    fn retain_copies_component_and_prunes_oldest() {
+        let base = tempfile::tempdir().unwrap();
+        let installed = base.path().join("installed/org.example.clock");
+        fs::create_dir_all(&installed).unwrap();
+
+        for (i, version) in ["1.0", "2.0", "3.0"].iter().enumerate() {
+            fs::write(installed.join("main.qml"), version).unwrap();
+            let timestamp = format!("2026-01-0{}T00:00:00+00:00", i + 1);
+            retain_in(
+                &base.path().join("versions"),
+                &component(installed.clone(), version),
+                &record(version, &timestamp),
+                2,
+            )
+            .unwrap();
+        }
+
+        let retained = read_retained(&base.path().join("versions/org.example.clock"));
+        let versions: Vec<&str> = retained.iter().map(|(_, r)| r.version.as_str()).collect();
+        assert_eq!(versions, ["3.0", "2.0"]);
+        let copy = retained[1].0.join("org.example.clock/main.qml");
+        assert_eq!(fs::read_to_string(copy).unwrap(), "2.0");
+    }
+
+    #[test]
+    fn version_key_is_a_single_safe_path_segment() {
+        assert_eq!(version_key("1.2.3"), "1.2.3");
+        assert_eq!(version_key("../../etc"), "_.._etc");
+        assert_eq!(version_key(""), "unversioned");
+    }
+}
@@ -11,34 +11,89 @@
 // and installation process knowledge comes from KDE Discover's source code.
 
 pub(crate) mod api;
+pub(crate) mod cancel;
+pub(crate) mod changelog;
 pub(crate) mod checker;
 pub(crate) mod config;
+pub(crate) mod doctor;
+pub(crate) mod du;
+pub(crate) mod environment;
 pub(crate) mod error;
+pub(crate) mod events;
+pub(crate) mod history;
+pub(crate) mod hooks;
 pub(crate) mod installer;
+pub(crate) mod manifest;
+pub(crate) mod matcher;
+pub(crate) mod metrics;
+pub(crate) mod notify;
 pub(crate) mod paths;
 pub(crate) mod registry;
+pub(crate) mod report;
+pub(crate) mod signing;
 pub(crate) mod types;
 pub(crate) mod utils;
 pub(crate) mod version;
 
 #[cfg(feature = "cli")]
 pub mod cli;
+#[cfg(feature = "test-support")]
+pub mod test_support;
+
+use std::{
+    collections::HashSet,
+    path::{Path, PathBuf},
+    time::Duration,
+};
 
 use api::ApiClient;
-use serde::Serialize;
+use serde::{Deserialize, Serialize};
 use types::UpdateCheckResult;
 
-pub use config::{Config, RestartBehavior};
-pub use error::Error;
-pub use types::{AvailableUpdate, ComponentType, Diagnostic, InstalledComponent};
+pub use api::{
+    ApiConfig, ApiStats, CacheValidators, FixtureBackend, HttpBackend, HttpResponse, PreviewSize,
+    StoreCredentials, StoreSnapshot,
+};
+pub use cancel::CancelToken;
+pub use checker::{Blocker, Explanation, ResolutionSource};
+#[cfg(feature = "cli")]
+pub use cli::progress::{ProgressMode, set_progress_mode};
+#[cfg(feature = "cli")]
+pub use cli::theme::{ColorMode, set_color_mode};
+pub use config::{Config, ConflictPolicy, FetchStrategy, RemovalMode, RestartBehavior};
+pub use doctor::{DoctorCheck, DoctorReport, DoctorStatus};
+pub use du::{ComponentUsage, DiskUsage, TypeUsage};
+pub use environment::{Environment, SessionType};
+pub use error::{Error, ErrorContext, Operation};
+pub use events::{CheckEvent, Event, EventSink, InstallStage};
+pub use history::{RunComponent, RunRecord};
+pub use hooks::Hooks;
+pub use installer::{
+    FileConflict, IncompleteTransaction, IntegrityReport, PayloadPin, RetainedVersion,
+};
+pub use manifest::{ApplyResult, Manifest, ManifestEntry};
+pub use metrics::Metrics;
+pub use notify::Notifier;
+pub use registry::{RegistryIssue, RegistryIssueKind, RegistryReport};
+pub use types::{
+    AvailableUpdate, CatalogFetchReport, Companion, ComponentMetadata, ComponentType, Diagnostic,
+    DiagnosticKind, DownloadVariant, HeldUpdate, InstalledComponent, InstalledQuery, InstalledSort,
+    InstalledStatus, RestartAction, UnrecognizedComponent, UserScope,
+};
+pub use version::{UpdateSeverity, VersionComparison};
 
 /// A specialized `Result` type for libplasmoid-updater operations.
 pub type Result<T> = std::result::Result<T, Error>;
 
+/// Version of this library, e.g. for bug reports.
+pub const VERSION: &str = env!("CARGO_PKG_VERSION");
+
 /// Checks for available updates to installed KDE Plasma components.
 ///
 /// Scans the local filesystem for installed KDE components and queries the KDE Store API
 /// for newer versions. Returns an empty [`CheckResult`] when no updates are found — not an error.
+/// Components found up to date within the last hour that have not changed on disk are not
+/// asked about again unless [`Config::full_scan`] is set.
 ///
 /// With the `cli` feature enabled, displays a spinner during fetch and a summary table of updates.
 ///
@@ -49,13 +104,105 @@ pub type Result<T> = std::result::Result<T, Error>;
 pub fn check(config: &Config) -> Result<CheckResult> {
     crate::utils::validate_environment(config.skip_plasma_detection)?;
 
-    let api_client = ApiClient::new();
+    let api_client = ApiClient::from_config(config)?;
     let result = crate::utils::fetch_updates(&api_client, config)?;
 
     #[cfg(feature = "cli")]
-    crate::utils::display_check_results(&result);
+    if config.terminal_output() {
+        crate::utils::display_check_results(&result);
+    }
+
+    let api_stats = api_client.stats();
+    log::info!(target: "api", "store usage: {}", api_stats.summary());
+    config.emit(|| Event::Summary {
+        available: result.updates.len(),
+        succeeded: 0,
+        failed: 0,
+        unresolved: result.unresolved.len() + result.check_failures.len(),
+        api: api_stats,
+    });
+    metrics::record(config, result.updates.len(), None);
+
+    Ok(CheckResult::from_internal(result, api_stats))
+}
+
+/// Checks only the components named in `names` for updates.
+///
+/// Like [`check()`] with [`Config::only_packages`] set to `names`: other
+/// components are dropped right after discovery, so the store is only
+/// queried for the named ones. Names match a display or directory name and
+/// may be globs or `re:` regexes. Use [`Config::included_types`] to check
+/// whole component types.
+///
+/// # Example
+///
+/// ```rust,no_run
+/// use libplasmoid_updater::{Config, check_updates_for};
+///
+/// let result = check_updates_for(&["org.kde.plasma.systemmonitor"], &Config::new())?;
+/// println!("{} update(s)", result.update_count());
+/// # Ok::<(), libplasmoid_updater::Error>(())
+/// ```
+///
+/// # Errors
+///
+/// Same as [`check()`], and an error if a name is an invalid pattern.
+pub fn check_updates_for(names: &[&str], config: &Config) -> Result<CheckResult> {
+    let config = config
+        .clone()
+        .with_only_packages(names.iter().map(|name| name.to_string()).collect());
+    check(&config)
+}
+
+/// Checks for updates like [`check()`], passing each component's outcome to
+/// `on_event` as soon as it is known.
+///
+/// Lets a GUI list installed components right after discovery and fill in
+/// their status while the store is still being queried, instead of waiting
+/// for the whole check. `on_event` is called on the calling thread; see
+/// [`CheckEvent`] for the order of events. The returned [`CheckResult`] holds
+/// the same outcomes. No spinner or summary table is printed.
+///
+/// # Example
+///
+/// ```rust,no_run
+/// use libplasmoid_updater::{CheckEvent, Config, check_updates_streaming};
+///
+/// let result = check_updates_streaming(&Config::new(), |event| match event {
+///     CheckEvent::Discovered(components) => println!("checking {}", components.len()),
+///     CheckEvent::UpdateAvailable(update) => {
+///         println!("{}: {}", update.installed.name, update.latest_version)
+///     }
+///     _ => {}
+/// })?;
+/// println!("{} update(s)", result.update_count());
+/// # Ok::<(), libplasmoid_updater::Error>(())
+/// ```
+///
+/// # Errors
+///
+/// Same as [`check()`].
+pub fn check_updates_streaming(
+    config: &Config,
+    mut on_event: impl FnMut(CheckEvent),
+) -> Result<CheckResult> {
+    crate::utils::validate_environment(config.skip_plasma_detection)?;
+
+    let api_client = ApiClient::from_config(config)?;
+    let result = crate::utils::fetch_updates_with(&api_client, config, Some(&mut on_event))?;
+
+    let api_stats = api_client.stats();
+    log::info!(target: "api", "store usage: {}", api_stats.summary());
+    config.emit(|| Event::Summary {
+        available: result.updates.len(),
+        succeeded: 0,
+        failed: 0,
+        unresolved: result.unresolved.len() + result.check_failures.len(),
+        api: api_stats,
+    });
+    metrics::record(config, result.updates.len(), None);
 
-    Ok(CheckResult::from_internal(result))
+    Ok(CheckResult::from_internal(result, api_stats))
 }
 
 /// Result of checking for available updates.
@@ -68,10 +215,21 @@ pub struct CheckResult {
     pub available_updates: Vec<AvailableUpdate>,
     /// Components that could not be checked, with the reason for each failure.
     pub diagnostics: Vec<Diagnostic>,
+    /// Updates held back because an advisory lists the new version as known
+    /// broken, see [`Config::advisories_url`].
+    pub held: Vec<HeldUpdate>,
+    /// User components hiding a system-wide copy of themselves, see
+    /// [`find_duplicates()`].
+    pub shadowed: Vec<InstalledComponent>,
+    /// Catalog pages fetched and failed; when some failed, store entries
+    /// are missing and components may show up as unresolved.
+    pub catalog: CatalogFetchReport,
+    /// Requests and traffic the check caused.
+    pub api_stats: ApiStats,
 }
 
 impl CheckResult {
-    pub(crate) fn from_internal(result: UpdateCheckResult) -> Self {
+    pub(crate) fn from_internal(result: UpdateCheckResult, api_stats: ApiStats) -> Self {
         let diagnostics = result
             .unresolved
             .into_iter()
@@ -81,6 +239,10 @@ impl CheckResult {
         Self {
             available_updates: result.updates,
             diagnostics,
+            held: result.held,
+            shadowed: result.shadowed,
+            catalog: result.catalog,
+            api_stats,
         }
     }
 
@@ -94,68 +256,370 @@ impl CheckResult {
         self.available_updates.len()
     }
 
-    /// Returns `true` if there are no updates and no diagnostics.
+    /// Returns `true` if there are no updates, held updates or diagnostics.
     pub fn is_empty(&self) -> bool {
-        self.available_updates.is_empty() && self.diagnostics.is_empty()
+        self.available_updates.is_empty() && self.diagnostics.is_empty() && self.held.is_empty()
     }
+
+    /// Renders the result as a Markdown report: a table of the available
+    /// updates (component, current and available version, changelog excerpt),
+    /// followed by held updates and components that could not be checked.
+    ///
+    /// Meant for cron mail or pasting into chat.
+    pub fn to_markdown(&self) -> String {
+        crate::report::check_markdown(self)
+    }
+}
+
+/// Converges the installed components on `manifest`.
+///
+/// Listed components that are missing are installed from the KDE Store and
+/// outdated ones are updated; listed components already up to date are left
+/// alone, so running it twice changes nothing the second time. With
+/// `remove_extras`, installed components that came from the store (they have
+/// a KNewStuff registry entry) but are not listed are removed, after a backup.
+/// Never prompts.
+///
+/// # Example
+///
+/// ```rust,no_run
+/// use libplasmoid_updater::{Config, Manifest, apply};
+///
+/// let manifest = Manifest::load("plasma-components.json")?;
+/// let result = apply(&manifest, false, &Config::new())?;
+/// println!("changed: {}", result.changed());
+/// # Ok::<(), libplasmoid_updater::Error>(())
+/// ```
+///
+/// # Errors
+///
+/// - [`Error::AlreadyRunning`] — another update is in progress
+/// - [`Error::ImmutableSystem`] — [`Config::system`] is set on an immutable
+///   distro (NixOS, ostree) or a read-only `/usr/share`
+/// - [`Error::FileConflicts`] — updates would write to the same files and
+///   [`Config::conflict_policy`] is [`ConflictPolicy::Fail`]
+///
+/// Also returns an error if the installed components cannot be scanned or
+/// the store catalog cannot be fetched. Components that fail are reported in
+/// [`ApplyResult::failed`].
+pub fn apply(manifest: &Manifest, remove_extras: bool, config: &Config) -> Result<ApplyResult> {
+    crate::utils::validate_system_scope(config)?;
+    let _lock = installer::UpdateLock::acquire()?;
+    crate::utils::validate_environment(config.skip_plasma_detection)?;
+    manifest::apply(manifest, remove_extras, config)
+}
+
+/// Lists installed components whose KDE Store entry no longer exists.
+///
+/// These components can never update again. Each is reported as a
+/// [`Diagnostic`] of kind [`DiagnosticKind::StoreEntryGone`]; [`check()`]
+/// reports them the same way among its other diagnostics.
+///
+/// # Errors
+///
+/// - [`Error::UnsupportedOS`] — not running on Linux
+/// - [`Error::NotKDE`] — KDE Plasma not detected
+///
+/// Also returns an error if the store catalog cannot be fetched.
+pub fn orphans(config: &Config) -> Result<Vec<Diagnostic>> {
+    crate::utils::validate_environment(config.skip_plasma_detection)?;
+
+    let api_client = ApiClient::from_config(config)?;
+    let components = checker::find_installed(config.system, |ct| config.includes_type(ct))?;
+    let result = checker::check_all(config, &api_client, components)?;
+
+    Ok(result
+        .unresolved
+        .into_iter()
+        .filter(|d| d.kind == DiagnosticKind::StoreEntryGone)
+        .collect())
+}
+
+/// Captures the store entries a check fetches into a [`StoreSnapshot`].
+///
+/// Scans installed components like [`check()`] and records every store entry
+/// fetched to evaluate them, without printing results. Save the snapshot with
+/// [`StoreSnapshot::save`] and pass it to [`Config::with_store_snapshot`] on
+/// other machines to roll out exactly these versions.
+///
+/// # Errors
+///
+/// - [`Error::UnsupportedOS`] — not running on Linux
+/// - [`Error::NotKDE`] — KDE Plasma not detected
+///
+/// Also returns an error if the store catalog cannot be fetched.
+pub fn create_snapshot(config: &Config) -> Result<StoreSnapshot> {
+    crate::utils::validate_environment(config.skip_plasma_detection)?;
+
+    let api_client = ApiClient::from_config(config)?.with_recording();
+    let components = checker::find_installed(config.system, |ct| config.includes_type(ct))?;
+    checker::check_all(config, &api_client, components)?;
+
+    Ok(StoreSnapshot::from_entries(api_client.recorded_entries()))
 }
 
 /// Downloads and installs all available updates for installed KDE Plasma components.
 ///
 /// Runs the full update pipeline: scan installed components, check for updates, select
 /// which to apply, then download and install. Handles plasmashell restart based on
-/// [`Config::restart`]. Components in [`Config::excluded_packages`] are always skipped.
+/// [`Config::restart`] and KWin reconfiguration based on [`Config::reload_kwin`].
+/// Components in [`Config::excluded_packages`] are always skipped, and when
+/// [`Config::group`] is set only that group's components are considered. System
+/// components owned by a distro package are skipped unless
+/// [`Config::force_distro_owned`] is set, and updates an advisory lists as
+/// known broken are always skipped (see [`Config::advisories_url`]). Runs
+/// that install anything are kept in the history, see [`last_run()`].
+///
+/// Installs interrupted by an earlier crash (see [`pending_recovery()`]) are
+/// undone first when [`Config::auto_confirm`] is set or the user agrees at a
+/// prompt, and left alone otherwise.
 ///
 /// With the `cli` feature enabled and [`Config::auto_confirm`] unset, shows an interactive
-/// multi-select menu. Otherwise, all available updates are applied automatically.
+/// multi-select menu. Otherwise, all available updates are applied automatically,
+/// and with [`Config::pipeline_downloads`] they start downloading while the
+/// check is still running.
 ///
 /// # Errors
 ///
-/// Returns an [`Error`] if environment validation, network requests, or installation fails.
+/// Returns an [`Error`] if environment validation, network requests, or installation fails,
+/// or if [`Config::group`] names a group that is not defined. Returns
+/// [`Error::ImmutableSystem`] if [`Config::system`] is set on an immutable
+/// distro (NixOS, ostree) or a read-only `/usr/share`, and
+/// [`Error::FileConflicts`] without installing anything if selected updates
+/// would write to the same files, see [`Config::conflict_policy`].
 pub fn update(config: &Config) -> Result<UpdateResult> {
+    let started = std::time::Instant::now();
+    let started_at = chrono::Local::now().to_rfc3339();
+    crate::utils::validate_system_scope(config)?;
     let _lock = installer::UpdateLock::acquire()?;
+    crate::utils::offer_recovery(config);
     crate::utils::validate_environment(config.skip_plasma_detection)?;
+    let excluded = matcher::PackageMatcher::new(&config.excluded_packages)?;
+    let group = crate::utils::group_matcher(config)?;
+
+    let api_client = ApiClient::from_config(config)?;
+    std::thread::scope(|scope| {
+        let prefetcher = (config.pipeline_downloads
+            && !crate::utils::selects_interactively(config))
+        .then(|| {
+            installer::Prefetcher::start(
+                scope,
+                api_client.http_client(),
+                &api_client.counters(),
+                config,
+            )
+        })
+        .flatten();
+        let check_result = match &prefetcher {
+            Some(prefetcher) => crate::utils::fetch_updates_prefetching(
+                &api_client,
+                config,
+                prefetcher,
+                &excluded,
+                group.as_ref(),
+            ),
+            None => crate::utils::fetch_updates(&api_client, config),
+        }?;
+        // Queued downloads go on while the updates install.
+        drop(prefetcher);
+
+        install_checked(
+            config,
+            &api_client,
+            check_result,
+            &excluded,
+            group.as_ref(),
+            started,
+            started_at,
+        )
+    })
+}
 
-    let api_client = ApiClient::new();
-    let check_result = crate::utils::fetch_updates(&api_client, config)?;
+/// The part of [`update()`] after the check: selects the updates, installs
+/// them and records the run that began at `started` (`started_at` in
+/// RFC 3339).
+fn install_checked(
+    config: &Config,
+    api_client: &ApiClient,
+    mut check_result: UpdateCheckResult,
+    excluded: &matcher::PackageMatcher,
+    group: Option<&matcher::PackageMatcher>,
+    started: std::time::Instant,
+    started_at: String,
+) -> Result<UpdateResult> {
+    // Updates outside the group are still pending.
+    let available = check_result.updates.len();
+    if let Some(group) = group {
+        check_result.updates.retain(|u| group.matches(&u.installed));
+    }
+
+    let mut skipped = if config.force_distro_owned {
+        Vec::new()
+    } else {
+        crate::utils::take_distro_owned(&mut check_result.updates, config)
+    };
+    skipped.extend(crate::utils::report_held(&check_result.held, config));
+
+    let unresolved: Vec<Diagnostic> = check_result
+        .unresolved
+        .iter()
+        .chain(&check_result.check_failures)
+        .cloned()
+        .collect();
+    let summary = |result: &UpdateResult| Event::Summary {
+        available: check_result.updates.len(),
+        succeeded: result.succeeded.len(),
+        failed: result.failed.len(),
+        unresolved: result.unresolved.len(),
+        api: result.api_stats,
+    };
+    let finish = |result: &UpdateResult| {
+        config.emit(|| summary(result));
+        metrics::record(
+            config,
+            available.saturating_sub(result.succeeded.len()),
+            Some(result.failed.len()),
+        );
+    };
 
     if check_result.updates.is_empty() {
         #[cfg(feature = "cli")]
-        println!("no updates available");
+        if config.terminal_output() {
+            println!("{}", tr!("no-updates", "no updates available"));
+        }
 
-        return Ok(UpdateResult::default());
+        let result = UpdateResult {
+            skipped,
+            unresolved,
+            api_stats: api_client.stats(),
+            ..Default::default()
+        };
+        finish(&result);
+        return Ok(result);
     }
 
-    let selected = crate::utils::select_updates(&check_result.updates, config)?;
+    let selected = crate::utils::select_updates(&check_result.updates, config, excluded)?;
+    let selected =
+        crate::utils::add_companion_updates(selected, &check_result.updates, config, excluded);
+    let selected = crate::utils::apply_severity_policy(selected, config, &mut skipped);
+    let selected = crate::utils::choose_variants(selected, config)?;
+    let selected = crate::utils::confirm_in_use(selected, config, &mut skipped);
+    let selected = crate::utils::confirm_author_changes(selected, config, &mut skipped);
 
     if selected.is_empty() {
         #[cfg(feature = "cli")]
-        println!("nothing to update");
+        if config.terminal_output() {
+            println!("{}", tr!("nothing-to-update", "nothing to update"));
+        }
 
-        return Ok(UpdateResult::default());
+        let result = UpdateResult {
+            skipped,
+            unresolved,
+            api_stats: api_client.stats(),
+            ..Default::default()
+        };
+        finish(&result);
+        return Ok(result);
     }
 
-    let result = crate::utils::install_selected_updates(&selected, &api_client, config)?;
+    let selected: Vec<&AvailableUpdate> = selected.iter().collect();
+    crate::utils::check_conflicts(&selected, config)?;
+    let layout_backup = crate::utils::backup_layout(&selected, config);
+    let mut result = crate::utils::install_selected_updates(&selected, api_client, config)?;
+    let installed: Vec<&AvailableUpdate> = selected
+        .iter()
+        .copied()
+        .filter(|u| result.succeeded.contains(&u.installed.name))
+        .collect();
+    checker::accept_authors(&installed);
+    crate::utils::install_missing_companions(&installed, api_client, config, &mut result);
+    result.layout_backup = layout_backup;
+    result.skipped.extend(skipped);
+    result.unresolved = unresolved;
+
+    crate::utils::handle_restart(config, &check_result.updates, &mut result);
+    notify::notify_result(config, api_client.http_client(), &result);
+    result.api_stats = api_client.stats();
+    log::info!(target: "api", "store usage: {}", result.api_stats.summary());
 
     #[cfg(feature = "debug")]
-    {
-        let n = api_client.request_count();
-        let plural = if n == 1 { "" } else { "s" };
-        println!("{n} web request{plural}");
-    }
+    println!("{}", result.api_stats.summary());
 
-    crate::utils::handle_restart(config, &check_result.updates, &result);
+    history::record(&RunRecord::new(
+        started_at,
+        started.elapsed(),
+        config.system,
+        &selected,
+        &result,
+    ));
+    finish(&result);
 
     Ok(result)
 }
 
+/// How a single component fared in an update run, see
+/// [`UpdateResult::outcomes`].
+#[derive(Debug, Clone, Serialize)]
+pub struct ComponentOutcome {
+    /// Display name of the component.
+    pub name: String,
+    pub status: OutcomeStatus,
+    /// Time spent on the component, from backup to post-install checks.
+    /// Serialized as whole milliseconds in `duration_ms`.
+    #[serde(rename = "duration_ms", serialize_with = "serialize_millis")]
+    pub duration: Duration,
+    /// Size of the downloaded package, `None` unless it installed.
+    pub bytes: Option<u64>,
+    /// SHA-256 of the downloaded package, `None` unless it installed.
+    pub sha256: Option<String>,
+    /// Why the update failed, for [`OutcomeStatus::Failed`].
+    pub error: Option<String>,
+    /// Retry passes the component went through after a transient failure,
+    /// see [`Config::retry_failed`]. The other fields describe the last
+    /// attempt.
+    pub retries: u8,
+}
+
+/// Status of a [`ComponentOutcome`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum OutcomeStatus {
+    Succeeded,
+    Failed,
+    /// Not attempted because the run was cancelled.
+    Skipped,
+}
+
+fn serialize_millis<S: serde::Serializer>(
+    duration: &Duration,
+    serializer: S,
+) -> std::result::Result<S::Ok, S::Error> {
+    serializer.serialize_u64(u64::try_from(duration.as_millis()).unwrap_or(u64::MAX))
+}
+
 /// A component that failed to update, with the error message.
 #[derive(Debug, Clone, Serialize)]
 pub struct FailedUpdate {
     /// Display name of the component that failed.
     pub name: String,
-    /// Human-readable error description.
+    /// Human-readable error description, without the step and target.
     pub error: String,
+    /// Step the update failed in, if known.
+    pub operation: Option<Operation>,
+    /// Path or URL the failing step was working on, if known.
+    pub target: Option<String>,
+}
+
+impl FailedUpdate {
+    pub(crate) fn new(name: impl Into<String>, error: &Error) -> Self {
+        let context = error.error_context();
+        Self {
+            name: name.into(),
+            error: error.root_cause().to_string(),
+            operation: context.and_then(|c| c.operation),
+            target: context.and_then(|c| c.target.clone()),
+        }
+    }
 }
 
 /// A component that installed successfully but whose post-install version
@@ -170,6 +634,16 @@ pub struct UnverifiedUpdate {
     pub actual_version: Option<String>,
 }
 
+/// A component that installed but failed the post-install health check, see
+/// [`Config::health_check`].
+#[derive(Debug, Clone, Serialize)]
+pub struct UnhealthyUpdate {
+    /// Display name of the component.
+    pub name: String,
+    /// What the health check found wrong.
+    pub problems: Vec<String>,
+}
+
 /// Result of performing updates.
 ///
 /// Returned by [`update()`](crate::update). Tracks which components succeeded,
@@ -182,6 +656,28 @@ pub struct UpdateResult {
     /// Components that installed successfully but whose post-install version
     /// could not be verified to match the expected version.
     pub unverified: Vec<UnverifiedUpdate>,
+    /// Components that installed but failed the post-install health check.
+    /// Empty when [`Config::rollback_unhealthy`] is set, as those are
+    /// rolled back and reported in `failed`.
+    pub unhealthy: Vec<UnhealthyUpdate>,
+    /// Components rolled back because plasmashell crashed after restarting,
+    /// see [`Config::crash_monitor_window`]. They are also in `succeeded`.
+    pub rolled_back: Vec<String>,
+    /// Backup of the desktop and panel layout taken before the run, see
+    /// [`Config::backup_layout`]. Copy its files back to `~/.config` while
+    /// plasmashell is stopped to undo layout damage by hand.
+    pub layout_backup: Option<PathBuf>,
+    /// Components that could not be checked for updates, with the reason for each.
+    pub unresolved: Vec<Diagnostic>,
+    /// Every component the run tried to install, in the order the updates
+    /// were selected regardless of which finished first. The lists above
+    /// follow the same order.
+    pub outcomes: Vec<ComponentOutcome>,
+    /// Requests and traffic the run caused, the check and downloads included.
+    pub api_stats: ApiStats,
+    /// Restart actions that ran after the updates, in order. Actions that
+    /// failed, were declined at the prompt or are disabled are left out.
+    pub restart_actions: Vec<RestartAction>,
 }
 
 impl UpdateResult {
@@ -196,6 +692,7 @@ impl UpdateResult {
             && self.failed.is_empty()
             && self.skipped.is_empty()
             && self.unverified.is_empty()
+            && self.unhealthy.is_empty()
     }
 
     /// Returns the number of successfully updated components.
@@ -208,6 +705,25 @@ impl UpdateResult {
         self.failed.len()
     }
 
+    /// Returns the outcome of the component named `name`, if the run tried
+    /// to install it.
+    pub fn outcome(&self, name: &str) -> Option<&ComponentOutcome> {
+        self.outcomes.iter().find(|o| o.name == name)
+    }
+
+    /// Returns the bytes downloaded across all components.
+    pub fn total_bytes(&self) -> u64 {
+        self.outcomes.iter().filter_map(|o| o.bytes).sum()
+    }
+
+    /// Renders the result as a Markdown report listing updated, failed,
+    /// skipped and rolled back components.
+    ///
+    /// Meant for cron mail or pasting into chat.
+    pub fn to_markdown(&self) -> String {
+        crate::report::update_markdown(self)
+    }
+
     /// Prints a formatted table of failed updates to stdout.
     #[cfg(feature = "cli")]
     pub fn print_error_table(&self) {
@@ -230,7 +746,234 @@ impl UpdateResult {
 ///
 /// Returns an error if the filesystem scan fails.
 pub fn get_installed(config: &Config) -> Result<Vec<InstalledComponent>> {
-    checker::find_installed(config.system)
+    checker::find_installed(config.system, |ct| config.includes_type(ct))
+}
+
+/// Returns directories among the installed packages whose metadata is missing
+/// or cannot be read.
+///
+/// [`get_installed()`] skips them, so they are never checked or updated; each
+/// [`UnrecognizedComponent`] says why. Only scans the filesystem.
+///
+/// # Example
+///
+/// ```rust,no_run
+/// use libplasmoid_updater::{Config, find_unrecognized};
+///
+/// for unknown in find_unrecognized(&Config::new()) {
+///     println!("{}: {}", unknown.component.path.display(), unknown.reason);
+/// }
+/// ```
+pub fn find_unrecognized(config: &Config) -> Vec<UnrecognizedComponent> {
+    checker::find_unrecognized(config.system, |ct| config.includes_type(ct))
+}
+
+/// Measures the disk space taken by installed components, per component and
+/// per type, and by the updater's backups, kept versions and cache.
+///
+/// Only scans the filesystem; directories are measured in parallel.
+///
+/// # Example
+///
+/// ```rust,no_run
+/// use libplasmoid_updater::{Config, disk_usage};
+///
+/// let usage = disk_usage(&Config::new())?;
+/// println!("{} bytes installed", usage.installed());
+/// for t in &usage.types {
+///     println!("{}: {} components, {} bytes", t.component_type, t.components, t.bytes);
+/// }
+/// # Ok::<(), libplasmoid_updater::Error>(())
+/// ```
+///
+/// # Errors
+///
+/// Returns an error if the filesystem scan fails.
+pub fn disk_usage(config: &Config) -> Result<DiskUsage> {
+    let components = get_installed(config)?;
+    Ok(du::measure(
+        &components,
+        &installer::backup_dir(),
+        &installer::versions_dir(config.system),
+        &paths::cache_home().join("plasmoid-updater"),
+    ))
+}
+
+/// Diagnoses the environment updates depend on.
+///
+/// Reports the library version and features, the Plasma version, whether
+/// `kpackagetool6` and `bsdtar` are installed, whether the component and
+/// KNewStuff registry directories are writable, and whether the KDE Store
+/// answers, how fast, and if it is rate limiting. Sends one store request.
+///
+/// # Example
+///
+/// ```rust,no_run
+/// use libplasmoid_updater::{Config, DoctorStatus, doctor};
+///
+/// let report = doctor(&Config::new());
+/// for check in &report.checks {
+///     if check.status != DoctorStatus::Ok {
+///         println!("{}: {}", check.name, check.detail);
+///     }
+/// }
+/// ```
+pub fn doctor(config: &Config) -> DoctorReport {
+    doctor::run(config)
+}
+
+/// Deletes the package download cache, see [`Config::download_cache_limit`],
+/// and the cached store previews, see [`download_preview()`]. Returns the
+/// number of bytes freed.
+///
+/// Backups, kept versions and cached store pages are left alone.
+///
+/// # Errors
+///
+/// Returns an error if a cache exists but cannot be removed.
+pub fn clean_cache() -> Result<u64> {
+    Ok(installer::clear_download_cache()? + api::clear_previews()?)
+}
+
+/// Returns a local copy of a preview image of KDE Store entry `content_id`,
+/// e.g. to show next to an available update.
+///
+/// Previews are cached under `$XDG_CACHE_HOME/plasmoid-updater/previews`, so
+/// repeated calls return the same file without a download. The cache is kept
+/// under 64 MiB by evicting the least recently used previews and is emptied
+/// by [`clean_cache()`]. If the entry only has one size, that one is returned.
+///
+/// # Example
+///
+/// ```rust,no_run
+/// use libplasmoid_updater::{Config, PreviewSize, check, download_preview};
+///
+/// let config = Config::new();
+/// for update in check(&config)?.available_updates {
+///     let image = download_preview(update.content_id, PreviewSize::Small, &config)?;
+///     println!("{}: {}", update.installed.name, image.display());
+/// }
+/// # Ok::<(), libplasmoid_updater::Error>(())
+/// ```
+///
+/// # Errors
+///
+/// Returns an error if the entry cannot be fetched, has no preview image, or
+/// the image cannot be downloaded or cached.
+pub fn download_preview(content_id: u64, size: PreviewSize, config: &Config) -> Result<PathBuf> {
+    let api_client = ApiClient::from_config(config)?;
+    let entry = api_client
+        .fetch_details(&[content_id])
+        .pop()
+        .ok_or_else(|| Error::other(format!("no store entry {content_id}")))??;
+    api_client.download_preview(&entry, size)
+}
+
+/// Returns installed components matching `query`, in its order.
+///
+/// [`InstalledStatus::All`] only scans the filesystem like [`get_installed()`];
+/// the other statuses check the components against the KDE Store. Restrict
+/// the component types with [`Config::included_types`].
+///
+/// # Example
+///
+/// ```rust,no_run
+/// use libplasmoid_updater::{Config, InstalledQuery, InstalledSort, list_installed};
+///
+/// let query = InstalledQuery {
+///     sort: InstalledSort::Size,
+///     ..Default::default()
+/// };
+/// for component in list_installed(&Config::new(), query)? {
+///     println!("{} {}", component.name, component.disk_size());
+/// }
+/// # Ok::<(), libplasmoid_updater::Error>(())
+/// ```
+///
+/// # Errors
+///
+/// Returns an error if the filesystem scan fails, or if the store catalog
+/// cannot be fetched for a status filter.
+pub fn list_installed(config: &Config, query: InstalledQuery) -> Result<Vec<InstalledComponent>> {
+    let mut components = get_installed(config)?;
+
+    match query.status {
+        InstalledStatus::All => {}
+        InstalledStatus::Outdated => {
+            let api_client = ApiClient::from_config(config)?;
+            let result = checker::check_with_components(config, &api_client, components.clone())?;
+            let outdated: HashSet<&Path> = result
+                .updates
+                .iter()
+                .chain(result.held.iter().map(|h| &h.update))
+                .map(|u| u.installed.path.as_path())
+                .collect();
+            components.retain(|c| outdated.contains(c.path.as_path()));
+        }
+        InstalledStatus::Orphaned => {
+            let api_client = ApiClient::from_config(config)?;
+            let entries = checker::resolve_store_entries(config, &api_client, &components)?;
+            components = components
+                .into_iter()
+                .zip(entries)
+                .filter_map(|(component, entry)| entry.is_none().then_some(component))
+                .collect();
+        }
+    }
+
+    query.sort.sort(&mut components);
+    Ok(components)
+}
+
+/// Returns the most recent [`update()`] run that installed anything, for
+/// [`Config::system`] runs or user runs.
+///
+/// The last 50 runs are kept under `$XDG_STATE_HOME/plasmoid-updater/history`
+/// (`/var/lib/plasmoid-updater/history` for system runs).
+///
+/// # Example
+///
+/// ```rust,no_run
+/// use libplasmoid_updater::{Config, last_run};
+///
+/// if let Some(run) = last_run(&Config::new()) {
+///     std::fs::write("report.html", run.to_html())?;
+/// }
+/// # Ok::<(), std::io::Error>(())
+/// ```
+pub fn last_run(config: &Config) -> Option<RunRecord> {
+    history::last_run(config.system)
+}
+
+/// Returns the local accounts under `/home` that have per-user Plasma components.
+///
+/// Intended for admin runs on shared machines: each returned [`UserScope`] can
+/// run the updater as that user via [`UserScope::command`], so files stay owned
+/// by the account and its own registry is updated.
+///
+/// # Errors
+///
+/// Returns an error if `/home` cannot be read.
+pub fn find_users() -> Result<Vec<UserScope>> {
+    checker::find_users(std::path::Path::new("/home"))
+}
+
+/// Describes the system if system-wide components cannot be written on it,
+/// even as root: NixOS, ostree-based distros (Fedora Atomic, Kinoite) and
+/// read-only `/usr/share` mounts. There, operations with [`Config::system`]
+/// that change files fail with [`Error::ImmutableSystem`].
+///
+/// # Example
+///
+/// ```rust,no_run
+/// use libplasmoid_updater::immutable_system;
+///
+/// if let Some(system) = immutable_system() {
+///     println!("only user components can be updated on {system}");
+/// }
+/// ```
+pub fn immutable_system() -> Option<String> {
+    installer::privilege::immutable_system()
 }
 
 /// Downloads and installs a single component update with automatic backup and rollback.
@@ -242,24 +985,921 @@ pub fn get_installed(config: &Config) -> Result<Vec<InstalledComponent>> {
 ///
 /// # Errors
 ///
-/// Returns an error if download, installation, or backup operations fail.
+/// Returns an error if download, installation, or backup operations fail,
+/// or [`Error::ImmutableSystem`] if [`Config::system`] is set on an
+/// immutable distro.
 pub fn install_update(update: &AvailableUpdate, config: &Config) -> Result<()> {
+    crate::utils::validate_system_scope(config)?;
+    let _lock = installer::UpdateLock::acquire()?;
+    let _inhibit = if config.inhibit_idle {
+        installer::InhibitGuard::acquire()
+    } else {
+        installer::InhibitGuard::None
+    };
+
+    installer::cleanup_stale_temp_dirs(config);
+
+    let api_client = ApiClient::from_config(config)?;
+    let counter = api_client.counters();
+    installer::update_component(update, api_client.http_client(), config, |_| {}, &counter)
+        .map(|_| ())
+}
+
+/// Installs a component from a package file on disk, such as a `.plasmoid`
+/// or `.tar.gz` downloaded by hand from a project's release page.
+///
+/// The package is extracted and validated like a store download, installed
+/// under the user or system path of `component_type` depending on
+/// [`Config::system`], and health-checked. Afterwards the component is
+/// matched against the KDE Store on a best-effort basis: if an entry is found
+/// it is recorded in the user's KNewStuff registry, like [`adopt()`], so
+/// later [`update()`] runs keep it current. Store lookup failures are logged and
+/// never fail the install.
+///
+/// # Example
+///
+/// ```rust,no_run
+/// use libplasmoid_updater::{ComponentType, Config, install_from_file};
+///
+/// let result = install_from_file(
+///     "org.kde.plasma.mywidget.plasmoid",
+///     ComponentType::PlasmaWidget,
+///     &Config::new(),
+/// )?;
+/// println!("installed {}", result.component.name);
+/// # Ok::<(), libplasmoid_updater::Error>(())
+/// ```
+///
+/// # Errors
+///
+/// - [`Error::AlreadyRunning`] — another update is in progress
+/// - [`Error::ImmutableSystem`] — [`Config::system`] is set on an immutable
+///   distro (NixOS, ostree) or a read-only `/usr/share`
+/// - [`Error::InstallFailed`] — the file is not a package of `component_type`,
+///   or the component is already installed
+/// - [`Error::HealthCheckFailed`] — the installed component failed the
+///   health check and was removed again
+pub fn install_from_file(
+    path: impl AsRef<Path>,
+    component_type: ComponentType,
+    config: &Config,
+) -> Result<FileInstallResult> {
+    crate::utils::validate_system_scope(config)?;
+    let _lock = installer::UpdateLock::acquire()?;
+    crate::utils::validate_environment(config.skip_plasma_detection)?;
+
+    let installed = installer::install_from_file(path.as_ref(), component_type, config)?;
+    // Rediscover the component so store resolution sees its full metadata.
+    let component = checker::find_installed(config.system, |ct| ct == component_type)
+        .ok()
+        .and_then(|found| found.into_iter().find(|c| c.path == installed.path))
+        .unwrap_or(installed);
+
+    let api_client = ApiClient::from_config(config)?;
+    let content_id = match checker::resolve_store_entries(
+        config,
+        &api_client,
+        std::slice::from_ref(&component),
+    ) {
+        Ok(entries) => match entries.into_iter().flatten().next() {
+            // KNewStuff registries are per-user, like in adopt().
+            Some(entry) if !config.system => {
+                if let Err(e) = adopt_component(component.clone(), &entry) {
+                    log::warn!(target: "registry", "failed to register {}: {e}", component.name);
+                }
+                Some(entry.id)
+            }
+            entry => entry.map(|e| e.id),
+        },
+        Err(e) => {
+            log::warn!(target: "install", "store lookup for {} failed: {e}", component.name);
+            None
+        }
+    };
+
+    Ok(FileInstallResult {
+        component,
+        content_id,
+    })
+}
+
+/// A component installed by [`install_from_file()`].
+#[derive(Debug, Clone, Serialize)]
+pub struct FileInstallResult {
+    /// The installed component.
+    pub component: InstalledComponent,
+    /// KDE Store content ID the component was matched to, or `None` if it
+    /// is not on the store and will not receive updates.
+    pub content_id: Option<u64>,
+}
+
+/// Lists the versions of `component` kept by [`Config::keep_versions`],
+/// newest first.
+///
+/// `component` is a component name or directory name.
+///
+/// # Errors
+///
+/// Returns [`Error::ComponentNotFound`] if `component` is not installed.
+pub fn retained_versions(component: &str, config: &Config) -> Result<Vec<RetainedVersion>> {
+    let installed = find_component(component, config)?;
+    Ok(installer::retained_versions(&installed))
+}
+
+/// Swaps a version kept by [`Config::keep_versions`] back into place and
+/// records it in the KNewStuff registry.
+///
+/// Like [`install_update()`], does not restart plasmashell. The replaced
+/// version is retained as well, so the downgrade can be undone.
+///
+/// # Errors
+///
+/// - [`Error::ComponentNotFound`] — `component` is not installed
+/// - [`Error::AlreadyRunning`] — another update is in progress
+/// - [`Error::ImmutableSystem`] — [`Config::system`] is set on an immutable
+///   distro (NixOS, ostree) or a read-only `/usr/share`
+///
+/// Also returns an error if `version` was not retained or the files cannot be
+/// swapped.
+pub fn downgrade(component: &str, version: &str, config: &Config) -> Result<()> {
+    crate::utils::validate_system_scope(config)?;
+    let _lock = installer::UpdateLock::acquire()?;
+    let installed = find_component(component, config)?;
+    installer::downgrade(&installed, version, config)
+}
+
+/// Downloads the exact package `component` was last installed or updated
+/// from again and installs it over the current files, e.g. to repair local
+/// edits.
+///
+/// The SHA-256 of every installed package is pinned under
+/// `$XDG_STATE_HOME/plasmoid-updater/pins` (`/var/lib/plasmoid-updater/pins`
+/// for system components). If the store now serves a different package for
+/// the same version, the reinstall is refused rather than picking up a
+/// swapped payload. Like [`install_update()`], does not restart plasmashell.
+///
+/// # Errors
+///
+/// - [`Error::ComponentNotFound`] — `component` is not installed
+/// - [`Error::PayloadChanged`] — the downloaded package does not match the
+///   pinned hash; nothing was changed
+/// - [`Error::AlreadyRunning`] — another update is in progress
+/// - [`Error::ImmutableSystem`] — [`Config::system`] is set on an immutable
+///   distro (NixOS, ostree) or a read-only `/usr/share`
+///
+/// Also returns an error if no package was pinned for `component`, e.g.
+/// because it was installed some other way, or the install fails.
+pub fn reinstall(component: &str, config: &Config) -> Result<PayloadPin> {
+    crate::utils::validate_system_scope(config)?;
     let _lock = installer::UpdateLock::acquire()?;
+    let installed = find_component(component, config)?;
+    let pin = installer::payload_pin(&installed).ok_or_else(|| {
+        Error::other(format!(
+            "no package is pinned for {}; it was not installed or updated by plasmoid-updater",
+            installed.name
+        ))
+    })?;
     let _inhibit = if config.inhibit_idle {
         installer::InhibitGuard::acquire()
     } else {
         installer::InhibitGuard::None
     };
 
-    let api_client = ApiClient::new();
-    let counter = api_client.request_counter();
-    installer::update_component(update, api_client.http_client(), |_| {}, &counter).map(|_| ())
+    installer::cleanup_stale_temp_dirs(config);
+
+    let api_client = ApiClient::from_config(config)?;
+    let counter = api_client.counters();
+    installer::reinstall_component(&pin, &installed, api_client.http_client(), config, &counter)?;
+    Ok(pin)
+}
+
+/// Compares installed files against the hashes recorded when each component
+/// was last installed, updated, rolled back or downgraded by this crate.
+///
+/// Reports modified, missing and extra files per component, e.g. after a
+/// misbehaving update or manual edits. Hashes live under
+/// `$XDG_STATE_HOME/plasmoid-updater/integrity` (`/var/lib/plasmoid-updater/integrity`
+/// for system components). Components installed some other way have no
+/// record; their [`IntegrityReport::recorded_version`] is `None`.
+///
+/// `component` is a component name or directory name; `None` verifies every
+/// installed component.
+///
+/// # Example
+///
+/// ```rust,no_run
+/// use libplasmoid_updater::{Config, verify};
+///
+/// for report in verify(None, &Config::new())? {
+///     for file in &report.modified {
+///         println!("{}: {file} was modified", report.name);
+///     }
+/// }
+/// # Ok::<(), libplasmoid_updater::Error>(())
+/// ```
+///
+/// # Errors
+///
+/// Returns [`Error::ComponentNotFound`] if `component` is not installed, or
+/// an error if a component's files cannot be read.
+pub fn verify(component: Option<&str>, config: &Config) -> Result<Vec<IntegrityReport>> {
+    let components = match component {
+        Some(name) => vec![find_component(name, config)?],
+        None => checker::find_installed(config.system, |ct| config.includes_type(ct))?,
+    };
+    components.iter().map(installer::verify).collect()
+}
+
+/// Returns installs, updates and downgrades that were interrupted, e.g. by a
+/// crash or power loss, and may have left a component half written.
+///
+/// Each destructive step is recorded in a journal under
+/// `$XDG_STATE_HOME/plasmoid-updater/journal` (`/var/lib/plasmoid-updater/journal`
+/// with [`Config::system`]) and dropped once it completes. Transactions of a
+/// process that is still running are not returned. Undo them with
+/// [`recover()`].
+///
+/// # Example
+///
+/// ```rust,no_run
+/// use libplasmoid_updater::{Config, pending_recovery};
+///
+/// for transaction in pending_recovery(&Config::new()) {
+///     println!(
+///         "{} was interrupted while installing {}",
+///         transaction.component.name, transaction.target_version
+///     );
+/// }
+/// ```
+pub fn pending_recovery(config: &Config) -> Vec<IncompleteTransaction> {
+    installer::incomplete_transactions(config.system)
+}
+
+/// Undoes every transaction returned by [`pending_recovery()`]: components
+/// that were being updated or downgraded are restored from the backup taken
+/// beforehand, and partially installed new components are removed.
+///
+/// # Example
+///
+/// ```rust,no_run
+/// use libplasmoid_updater::{Config, recover};
+///
+/// let result = recover(&Config::new())?;
+/// println!("restored {} component(s)", result.recovered.len());
+/// # Ok::<(), libplasmoid_updater::Error>(())
+/// ```
+///
+/// # Errors
+///
+/// Returns [`Error::ImmutableSystem`] for system components on a read-only
+/// system, or [`Error::AlreadyRunning`] if another instance holds the update
+/// lock. Components that fail to recover are listed in
+/// [`RecoveryResult::failed`] and stay pending.
+pub fn recover(config: &Config) -> Result<RecoveryResult> {
+    crate::utils::validate_system_scope(config)?;
+    let _lock = installer::UpdateLock::acquire()?;
+    let mut result = RecoveryResult::default();
+    for transaction in installer::incomplete_transactions(config.system) {
+        let name = transaction.component.name.clone();
+        match installer::recover_transaction(&transaction) {
+            Ok(()) => result.recovered.push(name),
+            Err(e) => result.failed.push(FailedUpdate::new(name, &e)),
+        }
+    }
+    Ok(result)
+}
+
+/// Outcome of [`recover()`].
+#[derive(Debug, Clone, Default, Serialize)]
+pub struct RecoveryResult {
+    /// Components put back in the state before the interrupted transaction.
+    pub recovered: Vec<String>,
+    /// Components that could not be recovered.
+    pub failed: Vec<FailedUpdate>,
+}
+
+/// Rates an installed component on the KDE Store with `score` from 1 (bad)
+/// to 10 (excellent), like the rating control in Discover.
+///
+/// `component` is a component name or directory name; it is matched to its
+/// store entry like during a check. Rating needs
+/// [`Config::store_credentials`]. Returns the rated content ID.
+///
+/// # Example
+///
+/// ```rust,no_run
+/// use libplasmoid_updater::{Config, StoreCredentials, rate};
+///
+/// let config = Config::new().with_store_credentials(StoreCredentials::Keyring {
+///     username: "me".to_string(),
+/// });
+/// rate("org.kde.plasma.mywidget", 9, &config)?;
+/// # Ok::<(), libplasmoid_updater::Error>(())
+/// ```
+///
+/// # Errors
+///
+/// - [`Error::Config`] — no store credentials are configured
+/// - [`Error::ComponentNotFound`] — `component` is not installed
+/// - [`Error::IdResolutionFailed`] — `component` matches no store entry
+/// - [`Error::ApiError`] — the store refused the vote, e.g. because the
+///   credentials are wrong
+pub fn rate(component: &str, score: u8, config: &Config) -> Result<u64> {
+    if config.store_credentials.is_none() && config.http_backend.is_none() {
+        return Err(Error::Config(
+            "rating needs store credentials (store_credentials)".to_string(),
+        ));
+    }
+
+    let installed = find_component(component, config)?;
+    let api_client = ApiClient::from_config(config)?;
+    let entry =
+        checker::resolve_store_entries(config, &api_client, std::slice::from_ref(&installed))?
+            .into_iter()
+            .flatten()
+            .next()
+            .ok_or_else(|| Error::IdResolutionFailed(installed.name.clone()))?;
+
+    api_client.rate_content(entry.id, score)?;
+    Ok(entry.id)
+}
+
+fn find_component(component: &str, config: &Config) -> Result<InstalledComponent> {
+    checker::find_installed(config.system, |ct| config.includes_type(ct))?
+        .into_iter()
+        .find(|c| c.name == component || c.directory_name == component)
+        .ok_or_else(|| Error::ComponentNotFound(component.to_string()))
+}
+
+/// Gathers everything known about one installed component into a
+/// [`ComponentReport`].
+///
+/// Merges the on-disk package metadata, the KNewStuff registry entry, the
+/// matched KDE Store entry, backups and retained versions, and how the
+/// configuration treats the component. `component` is a component name or
+/// directory name.
+///
+/// # Example
+///
+/// ```rust,no_run
+/// use libplasmoid_updater::{Config, inspect_component};
+///
+/// let report = inspect_component("org.kde.plasma.weather", &Config::new())?;
+/// if let Some(store) = &report.store {
+///     println!("{} -> {}", report.installed.version, store.latest_version);
+/// }
+/// # Ok::<(), libplasmoid_updater::Error>(())
+/// ```
+///
+/// # Errors
+///
+/// - [`Error::ComponentNotFound`] — `component` is not installed
+///
+/// Also returns an error if the store catalog cannot be fetched or a pattern
+/// in [`Config::excluded_packages`] or [`Config::groups`] is invalid.
+pub fn inspect_component(component: &str, config: &Config) -> Result<ComponentReport> {
+    let installed = find_component(component, config)?;
+
+    let registry = registry::load_registry_map(installed.component_type, installed.is_system)
+        .remove(&installed.directory_name)
+        .map(|entry| RegistryRecord {
+            content_id: registry::build_id_cache(installed.is_system)
+                .get(&installed.directory_name)
+                .copied(),
+            version: entry.version,
+            release_date: entry.release_date,
+            installed_path: entry.installed_path,
+        });
+
+    let api_client = ApiClient::from_config(config)?;
+    let store =
+        checker::resolve_store_entries(config, &api_client, std::slice::from_ref(&installed))?
+            .pop()
+            .flatten()
+            .map(|entry| StoreRecord {
+                update_available: version::is_update_available_with_date(
+                    &installed.version,
+                    &entry.version,
+                    &installed.release_date,
+                    &entry.changed_date,
+                ),
+                store_url: format!("https://store.kde.org/p/{}", entry.id),
+                downloads: entry
+                    .download_links
+                    .iter()
+                    .map(DownloadVariant::from)
+                    .collect(),
+                content_id: entry.id,
+                name: entry.name,
+                latest_version: entry.version,
+                release_date: entry.changed_date,
+                changelog: entry.changelog,
+            });
+
+    let mut groups = Vec::new();
+    for (name, packages) in &config.groups {
+        if matcher::PackageMatcher::new(packages)?.matches(&installed) {
+            groups.push(name.clone());
+        }
+    }
+    groups.sort();
+
+    Ok(ComponentReport {
+        registry,
+        store,
+        backups: installer::backups(&installed),
+        retained: installer::retained_versions(&installed),
+        payload: installer::payload_pin(&installed),
+        excluded: matcher::PackageMatcher::new(&config.excluded_packages)?.matches(&installed),
+        groups,
+        download_variant: config.download_variant_for(&installed).map(str::to_string),
+        installed,
+    })
+}
+
+/// Explains why `component` is or is not offered an update.
+///
+/// Resolves the component to its KDE Store entry the way [`check()`] does
+/// and reports each step: the KNewStuff registry, the metadata website,
+/// the store name and the widgets-id table. It then compares the installed
+/// release to the store's, and lists what keeps an update back: an
+/// exclusion, a group, a distro package, an advisory or a missing
+/// download. Components are always asked about, whatever
+/// [`Config::full_scan`] says. `component` is a component name or
+/// directory name.
+///
+/// # Example
+///
+/// ```rust,no_run
+/// use libplasmoid_updater::{Config, explain_component};
+///
+/// let explanation = explain_component("org.kde.plasma.weather", &Config::new())?;
+/// println!("{}", explanation.summary());
+/// for step in &explanation.trace {
+///     println!("  {step}");
+/// }
+/// # Ok::<(), libplasmoid_updater::Error>(())
+/// ```
+///
+/// # Errors
+///
+/// - [`Error::ComponentNotFound`] — `component` is not installed
+///
+/// Also returns an error if the store catalog cannot be fetched or a pattern
+/// in [`Config::excluded_packages`] or [`Config::groups`] is invalid.
+pub fn explain_component(component: &str, config: &Config) -> Result<Explanation> {
+    let installed = find_component(component, config)?;
+    let api_client = ApiClient::from_config(config)?;
+    checker::explain(config, &api_client, installed)
+}
+
+/// Everything known about one installed component.
+///
+/// Returned by [`inspect_component()`].
+#[derive(Debug, Clone, Serialize)]
+pub struct ComponentReport {
+    pub installed: InstalledComponent,
+    /// The component's KNewStuff registry entry; `None` if it is unregistered.
+    pub registry: Option<RegistryRecord>,
+    /// The matched KDE Store entry; `None` if no entry matched.
+    pub store: Option<StoreRecord>,
+    /// Backups taken before past updates, newest first.
+    pub backups: Vec<PathBuf>,
+    /// Versions kept by [`Config::keep_versions`], newest first.
+    pub retained: Vec<RetainedVersion>,
+    /// The store package the component was last installed from, see
+    /// [`reinstall()`]; `None` if it was installed some other way.
+    pub payload: Option<PayloadPin>,
+    /// Whether [`Config::excluded_packages`] matches the component.
+    pub excluded: bool,
+    /// Names of the [`Config::groups`] that list the component.
+    pub groups: Vec<String>,
+    /// Download chosen for the component in [`Config::download_variants`].
+    pub download_variant: Option<String>,
+}
+
+/// A component's entry in its KNewStuff registry file.
+#[derive(Debug, Clone, Serialize)]
+pub struct RegistryRecord {
+    /// KDE Store content ID the entry records, if any.
+    pub content_id: Option<u64>,
+    pub version: String,
+    pub release_date: String,
+    pub installed_path: PathBuf,
+}
+
+/// The KDE Store entry a component was matched to.
+#[derive(Debug, Clone, Serialize)]
+pub struct StoreRecord {
+    pub content_id: u64,
+    /// Name of the entry on the store.
+    pub name: String,
+    pub latest_version: String,
+    pub release_date: String,
+    pub store_url: String,
+    /// Release notes of the latest version; may be empty.
+    pub changelog: String,
+    /// Every download the entry offers.
+    pub downloads: Vec<DownloadVariant>,
+    /// Whether the latest version is newer than the installed one.
+    pub update_available: bool,
+}
+
+/// Finds the KDE Store entries that may be the package behind `name_or_dir`,
+/// best match first, using the same matching as [`check()`].
+///
+/// If a component of `component_type` with that name or directory name is
+/// installed, the entry it resolves to during a check comes first. Entries
+/// listed for the name in [`Config::widgets_id_table`] follow, then catalog
+/// entries of `component_type` by name: exact names score 100 (90 for
+/// another type), names equal but for case and punctuation 80, and names
+/// containing one another 40 to 70. Checks only act on exact matches; the
+/// rest are suggestions.
+///
+/// # Example
+///
+/// ```rust,no_run
+/// use libplasmoid_updater::{ComponentType, Config, resolve_store_entry};
+///
+/// let candidates =
+///     resolve_store_entry("Event Calendar", ComponentType::PlasmaWidget, &Config::new())?;
+/// for candidate in &candidates {
+///     println!("{} {} ({})", candidate.score, candidate.name, candidate.store_url);
+/// }
+/// # Ok::<(), libplasmoid_updater::Error>(())
+/// ```
+///
+/// # Errors
+///
+/// Returns an error if the store catalog cannot be fetched.
+pub fn resolve_store_entry(
+    name_or_dir: &str,
+    component_type: ComponentType,
+    config: &Config,
+) -> Result<Vec<StoreCandidate>> {
+    let api_client = ApiClient::from_config(config)?;
+    let mut candidates = Vec::new();
+
+    let installed = checker::find_installed(config.system, |ct| ct == component_type)?
+        .into_iter()
+        .find(|c| c.name == name_or_dir || c.directory_name == name_or_dir);
+    if let Some(installed) = &installed
+        && let Some(entry) =
+            checker::resolve_store_entries(config, &api_client, std::slice::from_ref(installed))?
+                .pop()
+                .flatten()
+    {
+        candidates.push(StoreCandidate::new(
+            &entry,
+            checker::EXACT_TYPE_SCORE,
+            MatchSource::Installed,
+        ));
+    }
+
+    if let Some(&id) = config.widgets_id_table.get(name_or_dir)
+        && let Some(Ok(entry)) = api_client.fetch_details(&[id]).pop()
+    {
+        candidates.push(StoreCandidate::new(
+            &entry,
+            checker::EXACT_TYPE_SCORE,
+            MatchSource::IdTable,
+        ));
+    }
+
+    let name = installed.as_ref().map_or(name_or_dir, |c| c.name.as_str());
+    let entries = api_client.fetch_all(&[component_type])?;
+    candidates.extend(
+        checker::rank_by_name(name, component_type, &entries)
+            .into_iter()
+            .map(|(entry, score)| StoreCandidate::new(entry, score, MatchSource::Name)),
+    );
+
+    // Keep the best match of each entry; the sort is stable, so installed
+    // resolutions stay ahead of equal name matches.
+    candidates.sort_by_key(|c| std::cmp::Reverse(c.score));
+    let mut seen = HashSet::new();
+    candidates.retain(|c| seen.insert(c.content_id));
+    Ok(candidates)
+}
+
+/// A KDE Store entry returned by [`resolve_store_entry()`].
+#[derive(Debug, Clone, Serialize)]
+pub struct StoreCandidate {
+    pub content_id: u64,
+    /// Name of the entry on the store.
+    pub name: String,
+    pub latest_version: String,
+    pub release_date: String,
+    pub store_url: String,
+    /// How well the entry matches, from 40 to 100.
+    pub score: u8,
+    pub matched_by: MatchSource,
+}
+
+impl StoreCandidate {
+    fn new(entry: &types::StoreEntry, score: u8, matched_by: MatchSource) -> Self {
+        Self {
+            content_id: entry.id,
+            name: entry.name.clone(),
+            latest_version: entry.version.clone(),
+            release_date: entry.changed_date.clone(),
+            store_url: format!("https://store.kde.org/p/{}", entry.id),
+            score,
+            matched_by,
+        }
+    }
+}
+
+/// How a [`StoreCandidate`] was found.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum MatchSource {
+    /// The installed component resolves to it, through its KNewStuff
+    /// registry entry, its metadata website or its name.
+    Installed,
+    /// [`Config::widgets_id_table`] lists it for the directory name.
+    IdTable,
+    /// Its store name matches.
+    Name,
+}
+
+/// Registers manually installed components in the KNewStuff registry.
+///
+/// Components present on disk but missing from their `.knsregistry` file (for
+/// example widgets cloned with git) are invisible to Discover. Each one is matched
+/// to a KDE Store entry and recorded with its current on-disk version, so both
+/// Discover and [`check()`] track it from then on.
+///
+/// Pass a component name or directory name to adopt a single component, or
+/// `None` to adopt every unregistered user component. Registry-only types
+/// (color schemes, icon themes, wallpapers) are always registered and are skipped.
+///
+/// # Errors
+///
+/// - [`Error::ComponentNotFound`] — `component` is not installed or already registered
+/// - [`Error::AlreadyRunning`] — another update is in progress
+///
+/// Also returns an error if the store catalog cannot be fetched or a registry
+/// file cannot be written.
+pub fn adopt(component: Option<&str>, config: &Config) -> Result<AdoptResult> {
+    let _lock = installer::UpdateLock::acquire()?;
+    crate::utils::validate_environment(config.skip_plasma_detection)?;
+
+    // KNewStuff registries are per-user and only track user-local installs.
+    let candidates: Vec<InstalledComponent> =
+        checker::find_installed(false, |ct| config.includes_type(ct))?
+            .into_iter()
+            .filter(|c| !c.component_type.registry_only())
+            .filter(|c| component.is_none_or(|n| c.name == n || c.directory_name == n))
+            .filter(|c| !registry::is_registered(c))
+            .collect();
+
+    if let Some(name) = component
+        && candidates.is_empty()
+    {
+        return Err(Error::ComponentNotFound(format!(
+            "{name} (not installed or already registered)"
+        )));
+    }
+
+    let mut result = AdoptResult::default();
+    if candidates.is_empty() {
+        return Ok(result);
+    }
+
+    let api_client = ApiClient::from_config(config)?;
+    let entries = checker::resolve_store_entries(config, &api_client, &candidates)?;
+
+    for (component, entry) in candidates.into_iter().zip(entries) {
+        let Some(entry) = entry else {
+            let installed_version =
+                (!component.version.is_empty()).then(|| component.version.clone());
+            result.unresolved.push(
+                Diagnostic::new(
+                    component.name,
+                    "could not match to kde store entry".to_string(),
+                )
+                .with_versions(installed_version, None),
+            );
+            continue;
+        };
+
+        result.adopted.push(adopt_component(component, &entry)?);
+    }
+
+    Ok(result)
+}
+
+/// Writes the KNewStuff registry entry linking `component` to the store
+/// `entry`.
+fn adopt_component(
+    mut component: InstalledComponent,
+    entry: &types::StoreEntry,
+) -> Result<AdoptedComponent> {
+    // Shared directories (look-and-feel) default to the first type during
+    // discovery; the store category tells us which registry really owns it.
+    if let Some(&ct) = component
+        .component_type
+        .shared_path_types()
+        .iter()
+        .find(|ct| ct.matches_type_id(entry.type_id))
+    {
+        component.component_type = ct;
+    }
+
+    let download_url = checker::select_download_with_info(entry, &component.version)
+        .map(|d| d.url)
+        .unwrap_or_default();
+    // Recording the store date for an older install would hide the update.
+    let release_date = if version::normalize_version(&entry.version)
+        == version::normalize_version(&component.version)
+    {
+        entry.changed_date.as_str()
+    } else {
+        ""
+    };
+
+    registry::register_component(&component, entry.id, &download_url, release_date)?;
+    Ok(AdoptedComponent {
+        name: component.name,
+        directory_name: component.directory_name,
+        component_type: component.component_type,
+        content_id: entry.id,
+    })
+}
+
+/// A component that was added to the KNewStuff registry by [`adopt()`].
+#[derive(Debug, Clone, Serialize)]
+pub struct AdoptedComponent {
+    /// Display name of the component.
+    pub name: String,
+    /// Directory name on disk.
+    pub directory_name: String,
+    /// Registry the entry was written to.
+    pub component_type: ComponentType,
+    /// KDE Store content ID the component was matched to.
+    pub content_id: u64,
+}
+
+/// Result of adopting unregistered components.
+///
+/// Returned by [`adopt()`](crate::adopt).
+#[derive(Debug, Clone, Default, Serialize)]
+pub struct AdoptResult {
+    /// Components written to the registry.
+    pub adopted: Vec<AdoptedComponent>,
+    /// Components that could not be matched to a KDE Store entry.
+    pub unresolved: Vec<Diagnostic>,
+}
+
+impl AdoptResult {
+    /// Returns `true` if there was nothing to adopt.
+    pub fn is_empty(&self) -> bool {
+        self.adopted.is_empty() && self.unresolved.is_empty()
+    }
+
+    /// Prints a formatted table of adopted components to stdout.
+    #[cfg(feature = "cli")]
+    pub fn print_table(&self) {
+        crate::cli::output::print_adopted_table(&self.adopted);
+    }
+}
+
+/// Checks the KNewStuff registry files against what is installed on disk.
+///
+/// Reports orphaned entries, duplicates, version mismatches, and entries that
+/// point at the wrong metadata file. Nothing is modified; see [`repair_registry()`].
+///
+/// # Errors
+///
+/// Returns an error if a registry file cannot be read.
+pub fn audit_registry() -> Result<RegistryReport> {
+    registry::audit()
+}
+
+/// Fixes the issues found by [`audit_registry()`] and writes the registry files back.
+///
+/// Orphaned and duplicate entries are removed; versions and installed file paths
+/// are corrected from on-disk metadata. The returned report lists what was fixed.
+///
+/// # Errors
+///
+/// Returns an error if another update is running or a registry file cannot be
+/// read or written.
+pub fn repair_registry() -> Result<RegistryReport> {
+    let _lock = installer::UpdateLock::acquire()?;
+    registry::repair()
+}
+
+/// Finds user components installed a second time system-wide.
+///
+/// Plasma loads the copy under `~/.local/share`, so the system copy in
+/// `/usr/share` is hidden and the version Plasma reports can differ from the
+/// one the distro package claims. Each [`Duplicate`] names the redundant copy;
+/// nothing is modified, see [`dedupe()`].
+///
+/// # Errors
+///
+/// Returns an error if the filesystem scan fails.
+pub fn find_duplicates(config: &Config) -> Result<Vec<Duplicate>> {
+    // Only user scans look for the system copy a package shadows.
+    let components = checker::find_installed(false, |ct| config.includes_type(ct))?;
+
+    Ok(components
+        .into_iter()
+        .filter_map(|user| {
+            let system_path = user.shadowed.clone()?;
+            let system_version = checker::read_package_metadata(&system_path)
+                .and_then(|m| m.version().map(str::to_string))
+                .unwrap_or_default();
+            let redundant = if version::compare(&user.version, &system_version).is_gt() {
+                DuplicateCopy::System
+            } else {
+                DuplicateCopy::User
+            };
+            Some(Duplicate {
+                user,
+                system_path,
+                system_version,
+                redundant,
+                removed: false,
+            })
+        })
+        .collect())
+}
+
+/// Removes the redundant copies found by [`find_duplicates()`].
+///
+/// A user copy that is not newer than the system copy is backed up, deleted
+/// and dropped from the KNewStuff registry, uncovering the system copy. System
+/// copies are left alone: they belong to a distro package or need root, so
+/// the returned list only reports them with `removed` unset.
+///
+/// # Errors
+///
+/// Returns an error if another update is running, or if the filesystem scan
+/// or a removal fails.
+pub fn dedupe(config: &Config) -> Result<Vec<Duplicate>> {
+    let _lock = installer::UpdateLock::acquire()?;
+    let mut duplicates = find_duplicates(config)?;
+
+    for duplicate in &mut duplicates {
+        if duplicate.redundant == DuplicateCopy::User {
+            installer::remove_component(&duplicate.user, config)?;
+            duplicate.removed = true;
+        }
+    }
+
+    Ok(duplicates)
+}
+
+/// A package installed both for the user and system-wide.
+///
+/// Returned by [`find_duplicates()`] and [`dedupe()`].
+#[derive(Debug, Clone, Serialize)]
+pub struct Duplicate {
+    /// The user copy, which is the one Plasma loads.
+    pub user: InstalledComponent,
+    /// Path of the hidden system-wide copy.
+    pub system_path: PathBuf,
+    /// Version of the system copy; empty if its metadata has none.
+    pub system_version: String,
+    /// Which copy can go: the older one, or the user copy if both match.
+    pub redundant: DuplicateCopy,
+    /// `true` once [`dedupe()`] deleted the redundant user copy.
+    pub removed: bool,
+}
+
+/// Which copy of a [`Duplicate`] is redundant.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum DuplicateCopy {
+    /// The user copy is not newer than the system copy it hides.
+    User,
+    /// The user copy is newer; the system copy only takes up space.
+    System,
+}
+
+/// Prints the [`changes_since_installed`](AvailableUpdate::changes_since_installed)
+/// of each update under its name and versions.
+#[cfg(feature = "cli")]
+#[doc(hidden)]
+pub fn show_changes(updates: &[AvailableUpdate]) {
+    cli::output::print_changes(updates, None);
+}
+
+/// Prints a [`DiskUsage`] as tables: installed sizes per type, the largest
+/// components, and the updater's own data.
+#[cfg(feature = "cli")]
+#[doc(hidden)]
+pub fn show_disk_usage(usage: &DiskUsage) {
+    cli::output::print_disk_usage(usage);
 }
 
 /// Discovers and prints all installed KDE components as a formatted table.
 ///
 /// Scans the filesystem and KNewStuff registry without making network requests.
-/// Prints a count header followed by a table of all discovered components.
+/// Same as [`show_installed_with()`] with the default [`InstalledQuery`].
 ///
 /// # Errors
 ///
@@ -267,15 +1907,46 @@ pub fn install_update(update: &AvailableUpdate, config: &Config) -> Result<()> {
 #[cfg(feature = "cli")]
 #[doc(hidden)]
 pub fn show_installed(config: &Config) -> Result<()> {
-    let components = checker::find_installed(config.system)?;
+    show_installed_with(config, InstalledQuery::default(), false)
+}
 
-    if components.is_empty() {
-        println!("no components installed");
+/// Discovers and prints installed KDE components as a formatted table.
+///
+/// Lists the components matching `query` like [`list_installed()`], then
+/// prints a count header followed by a table, or with `verbose` a block per
+/// component including its package metadata and size. Packages whose
+/// metadata cannot be read follow with version `unknown` and the reason, see
+/// [`find_unrecognized()`].
+///
+/// # Errors
+///
+/// Returns an error if the filesystem scan fails, or if the store catalog
+/// cannot be fetched for a status filter.
+#[cfg(feature = "cli")]
+#[doc(hidden)]
+pub fn show_installed_with(config: &Config, query: InstalledQuery, verbose: bool) -> Result<()> {
+    let components = list_installed(config, query)?;
+    // Unreadable packages cannot be matched to the store, so they belong to
+    // every listing except the outdated one.
+    let unrecognized = match query.status {
+        InstalledStatus::Outdated => Vec::new(),
+        _ => find_unrecognized(config),
+    };
+
+    if components.is_empty() && unrecognized.is_empty() {
+        println!("{}", tr!("no-components", "no components installed"));
         return Ok(());
     }
 
-    cli::output::print_count_message(components.len(), "installed component");
-    cli::output::print_components_table(&components);
+    if !components.is_empty() {
+        cli::output::print_installed_count(components.len());
+        if verbose {
+            cli::output::print_components_details(&components);
+        } else {
+            cli::output::print_components_table(&components, query.sort == InstalledSort::Size);
+        }
+    }
+    cli::output::print_unrecognized(&unrecognized);
 
     Ok(())
 }
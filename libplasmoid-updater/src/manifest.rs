@@ -0,0 +1,256 @@
+// SPDX-License-Identifier: GPL-3.0-or-later
+
+use std::{
+    collections::{HashMap, HashSet},
+    fs,
+    path::Path,
+};
+
+use serde::{Deserialize, Serialize};
+
+use crate::{
+    Config, Error, FailedUpdate, Result,
+    api::ApiClient,
+    checker, installer, registry,
+    types::{AvailableUpdate, ComponentType, InstalledComponent, StoreEntry},
+};
+
+/// The KDE Store components a machine should have, for
+/// [`apply()`](crate::apply).
+///
+/// Stored as JSON:
+///
+/// ```json
+/// {
+///   "components": [
+///     { "content_id": 998890, "name": "Clock" },
+///     { "content_id": 1294604, "type": "kwin_script" }
+///   ]
+/// }
+/// ```
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Manifest {
+    /// Components to install and keep up to date.
+    pub components: Vec<ManifestEntry>,
+}
+
+/// One component listed in a [`Manifest`].
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct ManifestEntry {
+    /// KDE Store content ID.
+    pub content_id: u64,
+    /// Display name, only used in reports before the component is installed.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub name: Option<String>,
+    /// Component type to install as; taken from the store category if unset.
+    #[serde(default, rename = "type", skip_serializing_if = "Option::is_none")]
+    pub component_type: Option<ComponentType>,
+}
+
+impl Manifest {
+    /// Reads a manifest from a JSON file.
+    pub fn load(path: impl AsRef<Path>) -> Result<Self> {
+        let content = read(path.as_ref())?;
+        Ok(serde_json::from_slice(&content)?)
+    }
+
+    /// Reads a manifest and verifies its detached minisign signature
+    /// (`<path>.minisig`) against `trusted_keys`, like
+    /// [`StoreSnapshot::load_signed`](crate::StoreSnapshot::load_signed).
+    ///
+    /// # Errors
+    ///
+    /// Returns [`Error::SignatureInvalid`] if the signature is missing, or
+    /// was not made by one of `trusted_keys` over this exact file.
+    pub fn load_signed(path: impl AsRef<Path>, trusted_keys: &[String]) -> Result<Self> {
+        let path = path.as_ref();
+        let content = read(path)?;
+        crate::signing::verify(path, &content, trusted_keys)?;
+        Ok(serde_json::from_slice(&content)?)
+    }
+
+    /// Writes the manifest to `path` as JSON.
+    pub fn save(&self, path: impl AsRef<Path>) -> Result<()> {
+        fs::write(path, serde_json::to_vec_pretty(self)?)?;
+        Ok(())
+    }
+}
+
+fn read(path: &Path) -> Result<Vec<u8>> {
+    fs::read(path)
+        .map_err(|e| Error::other(format!("failed to read manifest {}: {e}", path.display())))
+}
+
+/// What [`apply()`](crate::apply) changed to converge on a [`Manifest`].
+#[derive(Debug, Clone, Default, Serialize)]
+pub struct ApplyResult {
+    /// Listed components that were missing and got installed.
+    pub installed: Vec<String>,
+    /// Listed components that were updated to the latest store version.
+    pub updated: Vec<String>,
+    /// Unlisted components that were removed.
+    pub removed: Vec<String>,
+    /// Listed components that were already installed and up to date.
+    pub unchanged: Vec<String>,
+    /// Components that could not be installed, updated or removed.
+    pub failed: Vec<FailedUpdate>,
+}
+
+impl ApplyResult {
+    /// Returns `true` if anything was installed, updated or removed.
+    pub fn changed(&self) -> bool {
+        !self.installed.is_empty() || !self.updated.is_empty() || !self.removed.is_empty()
+    }
+
+    /// Returns `true` if any component failed.
+    pub fn has_failures(&self) -> bool {
+        !self.failed.is_empty()
+    }
+}
+
+pub(crate) fn apply(
+    manifest: &Manifest,
+    remove_extras: bool,
+    config: &Config,
+) -> Result<ApplyResult> {
+    let api_client = ApiClient::from_config(config)?;
+    let components = checker::find_installed(config.system, |ct| config.includes_type(ct))?;
+    let entries = checker::resolve_store_entries(config, &api_client, &components)?;
+
+    let present: HashMap<u64, (&InstalledComponent, &StoreEntry)> = components
+        .iter()
+        .zip(&entries)
+        .filter_map(|(component, entry)| {
+            let entry = entry.as_ref()?;
+            Some((entry.id, (component, entry)))
+        })
+        .collect();
+
+    let mut result = ApplyResult::default();
+    let mut updates = Vec::new();
+    let mut missing = Vec::new();
+    for wanted in &manifest.components {
+        match present.get(&wanted.content_id) {
+            Some((component, entry)) => match checker::update_for(component, entry) {
+                Some(update) => updates.push(update),
+                None => result.unchanged.push(component.name.clone()),
+            },
+            None => missing.push(wanted),
+        }
+    }
+
+    if !updates.is_empty() {
+        crate::utils::apply_download_variants(&mut updates, config);
+        let selected: Vec<&AvailableUpdate> = updates.iter().collect();
+        crate::utils::check_conflicts(&selected, config)?;
+        let mut updated = crate::utils::install_selected_updates(&selected, &api_client, config)?;
+        crate::utils::handle_restart(config, &updates, &mut updated);
+        result.updated = updated.succeeded;
+        result.failed = updated.failed;
+    }
+
+    install_missing(&missing, &api_client, config, &mut result);
+
+    if remove_extras {
+        let wanted: HashSet<u64> = manifest.components.iter().map(|c| c.content_id).collect();
+        for (component, entry) in components.iter().zip(&entries) {
+            let Some(entry) = entry else {
+                continue;
+            };
+            // Only remove what was installed from the store, never files a
+            // distro package or the user put there by hand.
+            if wanted.contains(&entry.id)
+                || component.managed_by_distro
+                || component.is_system != config.system
+                || !registry::is_registered(component)
+            {
+                continue;
+            }
+            match installer::remove_component(component, config) {
+                Ok(()) => result.removed.push(component.name.clone()),
+                Err(e) => result.failed.push(FailedUpdate::new(&component.name, &e)),
+            }
+        }
+    }
+
+    Ok(result)
+}
+
+fn install_missing(
+    missing: &[&ManifestEntry],
+    api_client: &ApiClient,
+    config: &Config,
+    result: &mut ApplyResult,
+) {
+    let ids: Vec<u64> = missing.iter().map(|m| m.content_id).collect();
+    let details = api_client.fetch_details(&ids);
+    let counter = api_client.counters();
+
+    for (wanted, entry) in missing.iter().zip(details) {
+        let installed = entry.and_then(|entry| {
+            let component_type = wanted
+                .component_type
+                .or_else(|| store_type(entry.type_id))
+                .ok_or_else(|| Error::other(format!("unknown store category {}", entry.type_id)))?;
+            if !config.includes_type(component_type) {
+                return Ok(None);
+            }
+            let mut update = checker::new_install(&entry, component_type)
+                .ok_or_else(|| Error::download("no download url available"))?;
+            crate::utils::apply_download_variants(std::slice::from_mut(&mut update), config);
+            installer::install_new(&update, api_client.http_client(), config, &counter).map(Some)
+        });
+
+        match installed {
+            Ok(Some(component)) => result.installed.push(component.name),
+            Ok(None) => {}
+            Err(e) => result.failed.push(FailedUpdate::new(
+                wanted
+                    .name
+                    .clone()
+                    .unwrap_or_else(|| wanted.content_id.to_string()),
+                &e,
+            )),
+        }
+    }
+}
+
+/// Component type of a store category, preferring an exact category match
+/// over the widget subcategory range.
+fn store_type(type_id: u16) -> Option<ComponentType> {
+    let all = ComponentType::all().iter().copied();
+    all.clone()
+        .find(|ct| ct.category_id() == type_id)
+        .or_else(|| all.into_iter().find(|ct| ct.matches_type_id(type_id)))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn manifest_reads_type_and_optional_name() {
+        let manifest: Manifest = serde_json::from_str(
+            r#"{"components": [
+                {"content_id": 1000, "name": "Clock"},
+                {"content_id": 2000, "type": "kwin_script"}
+            ]}"#,
+        )
+        .unwrap();
+
+        assert_eq!(manifest.components[0].name.as_deref(), Some("Clock"));
+        assert_eq!(manifest.components[0].component_type, None);
+        assert_eq!(
+            manifest.components[1].component_type,
+            Some(ComponentType::KWinScript)
+        );
+    }
+
+    #[test]
+    fn store_type_prefers_exact_category() {
+        for &ct in ComponentType::all() {
+            assert_eq!(store_type(ct.category_id()), Some(ct));
+        }
+        assert_eq!(store_type(714), Some(ComponentType::PlasmaWidget));
+    }
+}
@@ -0,0 +1,141 @@
+// SPDX-License-Identifier: GPL-3.0-or-later
+
+use regex::Regex;
+
+use crate::{Error, Result, types::InstalledComponent};
+
+/// Prefix marking a pattern as a regular expression instead of a glob.
+const REGEX_PREFIX: &str = "re:";
+
+/// Matches components against user-supplied package patterns.
+///
+/// Each pattern is either a glob (`org.kde.plasma.*`, where `*` matches any run
+/// of characters and `?` a single one) or, with a `re:` prefix, a regular
+/// expression. Globs are anchored to the whole name; regexes match anywhere
+/// unless anchored explicitly. Plain names without wildcards match exactly.
+#[derive(Debug, Clone, Default)]
+pub(crate) struct PackageMatcher {
+    patterns: Vec<Regex>,
+}
+
+impl PackageMatcher {
+    pub(crate) fn new(patterns: &[String]) -> Result<Self> {
+        let patterns = patterns.iter().map(|p| compile(p)).collect::<Result<_>>()?;
+        Ok(Self { patterns })
+    }
+
+    /// Returns `true` if any pattern matches the directory or display name.
+    pub(crate) fn matches(&self, component: &InstalledComponent) -> bool {
+        self.patterns
+            .iter()
+            .any(|p| p.is_match(&component.directory_name) || p.is_match(&component.name))
+    }
+}
+
+fn compile(pattern: &str) -> Result<Regex> {
+    let source = match pattern.strip_prefix(REGEX_PREFIX) {
+        Some(expr) => expr.to_string(),
+        None => glob_to_regex(pattern),
+    };
+
+    Regex::new(&source)
+        .map_err(|e| Error::other(format!("invalid package pattern '{pattern}': {e}")))
+}
+
+fn glob_to_regex(glob: &str) -> String {
+    let mut source = String::with_capacity(glob.len() + 2);
+    source.push('^');
+    for c in glob.chars() {
+        match c {
+            '*' => source.push_str(".*"),
+            '?' => source.push('.'),
+            c => source.push_str(&regex::escape(c.encode_utf8(&mut [0; 4]))),
+        }
+    }
+    source.push('$');
+    source
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::types::ComponentType;
+
+    fn component(directory_name: &str, name: &str) -> InstalledComponent {
+        InstalledComponent {
+            directory_name: directory_name.to_string(),
+            ..InstalledComponent::fixture(name, ComponentType::PlasmaWidget, "")
+        }
+    }
+
+    fn matcher(patterns: &[&str]) -> PackageMatcher {
+        let patterns: Vec<String> = patterns.iter().map(|p| p.to_string()).collect();
+        PackageMatcher::new(&patterns).unwrap()
+    }
+
+    #[test]
+    fn plain_names_match_exactly() {
+        let m = matcher(&["org.kde.clock", "System Monitor"]);
+
+        assert!(m.matches(&component("org.kde.clock", "Clock")));
+        assert!(m.matches(&component("org.kde.sysmon", "System Monitor")));
+        assert!(!m.matches(&component("org.kde.clockwork", "Clockwork")));
+        assert!(!m.matches(&component("orgxkde.clock", "Other")));
+    }
+
+    #[test]
+    fn globs_match_whole_name() {
+        let m = matcher(&["org.kde.plasma.*", "widget-?"]);
+
+        assert!(m.matches(&component("org.kde.plasma.battery", "Battery")));
+        assert!(m.matches(&component("widget-a", "A")));
+        assert!(!m.matches(&component("widget-ab", "AB")));
+        assert!(!m.matches(&component("com.org.kde.plasma.x", "X")));
+    }
+
+    #[test]
+    fn regex_patterns_use_prefix() {
+        let m = matcher(&[r"re:^com\.github\..*"]);
+
+        assert!(m.matches(&component("com.github.user.widget", "Widget")));
+        assert!(!m.matches(&component("org.github.widget", "Widget")));
+    }
+
+    #[test]
+    fn invalid_regex_is_an_error() {
+        assert!(PackageMatcher::new(&["re:(".to_string()]).is_err());
+    }
+
+    fn grouped_config() -> crate::Config {
+        crate::Config::new().with_groups(std::collections::HashMap::from([(
+            "work".to_string(),
+            vec!["org.kde.clock".to_string(), "org.example.*".to_string()],
+        )]))
+    }
+
+    #[test]
+    fn selected_group_matches_its_members() {
+        let m = crate::utils::group_matcher(&grouped_config().with_group("work"))
+            .unwrap()
+            .unwrap();
+
+        assert!(m.matches(&component("org.kde.clock", "Clock")));
+        assert!(m.matches(&component("org.example.notes", "Notes")));
+        assert!(!m.matches(&component("org.kde.sysmon", "System Monitor")));
+    }
+
+    #[test]
+    fn no_group_selects_everything() {
+        assert!(
+            crate::utils::group_matcher(&grouped_config())
+                .unwrap()
+                .is_none()
+        );
+    }
+
+    #[test]
+    fn unknown_group_is_an_error() {
+        let err = crate::utils::group_matcher(&grouped_config().with_group("home")).unwrap_err();
+        assert!(err.to_string().contains("unknown update group 'home'"));
+    }
+}
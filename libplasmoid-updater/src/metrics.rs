@@ -0,0 +1,156 @@
+// SPDX-License-Identifier: GPL-3.0-or-later
+
+use std::{fs, path::Path};
+
+use crate::{Config, Result};
+
+const UPDATES_AVAILABLE: &str = "plasmoid_updater_updates_available";
+const LAST_CHECK: &str = "plasmoid_updater_last_check_timestamp_seconds";
+const FAILED_UPDATES: &str = "plasmoid_updater_failed_updates";
+
+/// Gauges for the Prometheus node exporter's textfile collector, written
+/// after each check and update run when [`Config::metrics_file`] is set.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct Metrics {
+    /// Updates still available after the run.
+    pub updates_available: usize,
+    /// When the store was last checked, in seconds since the Unix epoch.
+    pub last_check_timestamp: i64,
+    /// Updates that failed in the last update run.
+    pub failed_updates: usize,
+}
+
+impl Metrics {
+    /// Renders the gauges in the Prometheus text exposition format.
+    ///
+    /// # Example
+    ///
+    /// ```rust
+    /// use libplasmoid_updater::Metrics;
+    ///
+    /// let metrics = Metrics { updates_available: 2, ..Default::default() };
+    /// assert!(metrics.to_textfile().contains("plasmoid_updater_updates_available 2\n"));
+    /// ```
+    pub fn to_textfile(&self) -> String {
+        let mut text = String::new();
+        for (name, help, value) in [
+            (
+                UPDATES_AVAILABLE,
+                "Updates available after the last check or update run.",
+                self.updates_available.to_string(),
+            ),
+            (
+                LAST_CHECK,
+                "Unix time of the last check of the KDE Store.",
+                self.last_check_timestamp.to_string(),
+            ),
+            (
+                FAILED_UPDATES,
+                "Updates that failed in the last update run.",
+                self.failed_updates.to_string(),
+            ),
+        ] {
+            text.push_str(&format!(
+                "# HELP {name} {help}\n# TYPE {name} gauge\n{name} {value}\n"
+            ));
+        }
+        text
+    }
+
+    /// Reads gauges written by [`Self::write`]; `None` if `path` cannot be
+    /// read. Unknown lines are ignored and missing gauges are zero.
+    pub fn read(path: &Path) -> Option<Self> {
+        let text = fs::read_to_string(path).ok()?;
+        let mut metrics = Self::default();
+        for line in text.lines().filter(|line| !line.starts_with('#')) {
+            let Some((name, value)) = line.split_once(' ') else {
+                continue;
+            };
+            let value = value.trim();
+            match name {
+                UPDATES_AVAILABLE => metrics.updates_available = value.parse().unwrap_or(0),
+                LAST_CHECK => metrics.last_check_timestamp = value.parse().unwrap_or(0),
+                FAILED_UPDATES => metrics.failed_updates = value.parse().unwrap_or(0),
+                _ => {}
+            }
+        }
+        Some(metrics)
+    }
+
+    /// Writes the gauges to `path`, replacing it atomically so the collector
+    /// never reads a partial file.
+    pub fn write(&self, path: &Path) -> Result<()> {
+        if let Some(parent) = path.parent().filter(|p| !p.as_os_str().is_empty()) {
+            fs::create_dir_all(parent)?;
+        }
+        let partial = path.with_extension("prom.partial");
+        fs::write(&partial, self.to_textfile())?;
+        fs::rename(&partial, path)?;
+        Ok(())
+    }
+}
+
+/// Writes the metrics of a run to [`Config::metrics_file`], if set.
+/// `failed_updates` is `None` after a check, which keeps the count of the
+/// last update run. Runs limited to [`Config::only_packages`] write nothing,
+/// as they only count some components. Failures are logged; the run is never
+/// failed for them.
+pub(crate) fn record(config: &Config, updates_available: usize, failed_updates: Option<usize>) {
+    let Some(path) = config
+        .metrics_file
+        .as_ref()
+        .filter(|_| config.only_packages.is_empty())
+    else {
+        return;
+    };
+    let failed_updates = failed_updates
+        .or_else(|| Metrics::read(path).map(|m| m.failed_updates))
+        .unwrap_or(0);
+    let metrics = Metrics {
+        updates_available,
+        last_check_timestamp: chrono::Local::now().timestamp(),
+        failed_updates,
+    };
+    if let Err(e) = metrics.write(path) {
+        log::warn!(target: "metrics", "failed to write {}: {e}", path.display());
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn textfile_round_trips_and_declares_gauges() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("textfile/plasmoid_updater.prom");
+        let metrics = Metrics {
+            updates_available: 3,
+            last_check_timestamp: 1_735_689_600,
+            failed_updates: 1,
+        };
+
+        metrics.write(&path).unwrap();
+
+        let text = fs::read_to_string(&path).unwrap();
+        assert!(text.contains("# TYPE plasmoid_updater_failed_updates gauge\n"));
+        assert!(text.contains("\nplasmoid_updater_last_check_timestamp_seconds 1735689600\n"));
+        assert_eq!(Metrics::read(&path), Some(metrics));
+        assert!(!path.with_extension("prom.partial").exists());
+    }
+
+    #[test]
+    fn checks_keep_the_failures_of_the_last_update() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("plasmoid_updater.prom");
+        let config = Config::new().with_metrics_file(Some(path.clone()));
+
+        record(&config, 4, Some(2));
+        record(&config, 1, None);
+
+        let metrics = Metrics::read(&path).unwrap();
+        assert_eq!(metrics.updates_available, 1);
+        assert_eq!(metrics.failed_updates, 2);
+        assert!(metrics.last_check_timestamp > 0);
+    }
+}
@@ -0,0 +1,286 @@
+// SPDX-License-Identifier: GPL-3.0-or-later
+
+use std::process::{Command, Stdio};
+
+use serde::{Deserialize, Serialize};
+
+use crate::{Config, Error, Result, UpdateResult};
+
+/// Where [`update()`](crate::update) reports what it installed, see
+/// [`Config::notifiers`].
+///
+/// Deserializes from a table tagged by `kind`, e.g.
+/// `{ kind = "ntfy", url = "https://ntfy.sh/my-topic" }`.
+///
+/// # Example
+///
+/// ```rust
+/// use libplasmoid_updater::{Config, Notifier};
+///
+/// let config = Config::new().with_notifiers(vec![
+///     Notifier::Desktop,
+///     Notifier::Ntfy {
+///         url: "https://ntfy.sh/my-plasma-box".to_string(),
+///         token: None,
+///     },
+/// ]);
+/// ```
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(tag = "kind", rename_all = "lowercase")]
+pub enum Notifier {
+    /// A desktop notification through `org.freedesktop.Notifications`,
+    /// falling back to `notify-send`.
+    Desktop,
+    /// A message published to an ntfy topic.
+    Ntfy {
+        /// Topic URL, e.g. `https://ntfy.sh/my-topic`.
+        url: String,
+        /// Access token sent as a bearer token, for protected topics.
+        #[serde(default)]
+        token: Option<String>,
+    },
+    /// A message sent to a Gotify server.
+    Gotify {
+        /// Server URL; the message is posted to `<url>/message`.
+        url: String,
+        /// Application token.
+        token: String,
+    },
+}
+
+/// Summary of an update run, as sent to each [`Notifier`].
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub(crate) struct Notification {
+    pub(crate) title: String,
+    pub(crate) message: String,
+    /// `true` if any component failed; raises the push priority.
+    pub(crate) failed: bool,
+}
+
+impl Notification {
+    /// Summarizes `result`, or `None` if nothing was installed or failed.
+    pub(crate) fn from_result(result: &UpdateResult) -> Option<Self> {
+        if result.succeeded.is_empty() && result.failed.is_empty() {
+            return None;
+        }
+
+        let failed = result.has_failures();
+        let title = if failed {
+            "Plasma component updates failed"
+        } else {
+            "Plasma components updated"
+        };
+
+        let mut lines = Vec::new();
+        if !result.succeeded.is_empty() {
+            lines.push(format!(
+                "Updated {}: {}",
+                result.succeeded.len(),
+                result.succeeded.join(", ")
+            ));
+        }
+        lines.extend(
+            result
+                .failed
+                .iter()
+                .map(|f| format!("Failed {}: {}", f.name, f.error)),
+        );
+
+        Some(Self {
+            title: title.to_string(),
+            message: lines.join("\n"),
+            failed,
+        })
+    }
+}
+
+impl Notifier {
+    /// Sends `notification`, using `client` for push services.
+    pub(crate) fn send(
+        &self,
+        notification: &Notification,
+        client: &reqwest::blocking::Client,
+    ) -> Result<()> {
+        let request = match self {
+            Self::Desktop => return send_desktop(notification),
+            Self::Ntfy { url, token } => {
+                let priority = if notification.failed {
+                    "high"
+                } else {
+                    "default"
+                };
+                let tags = if notification.failed {
+                    "warning"
+                } else {
+                    "package"
+                };
+                let request = client
+                    .post(url)
+                    .header("Title", &notification.title)
+                    .header("Priority", priority)
+                    .header("Tags", tags)
+                    .body(notification.message.clone());
+                match token {
+                    Some(token) => request.bearer_auth(token),
+                    None => request,
+                }
+            }
+            Self::Gotify { url, token } => {
+                let body = serde_json::json!({
+                    "title": notification.title,
+                    "message": notification.message,
+                    "priority": if notification.failed { 8 } else { 5 },
+                });
+                client
+                    .post(format!("{}/message", url.trim_end_matches('/')))
+                    .header("X-Gotify-Key", token)
+                    .header("Content-Type", "application/json")
+                    .body(body.to_string())
+            }
+        };
+
+        let response = request.send()?;
+        if !response.status().is_success() {
+            return Err(Error::other(format!(
+                "{} returned {}",
+                self.kind(),
+                response.status()
+            )));
+        }
+        Ok(())
+    }
+
+    fn kind(&self) -> &'static str {
+        match self {
+            Self::Desktop => "desktop",
+            Self::Ntfy { .. } => "ntfy",
+            Self::Gotify { .. } => "gotify",
+        }
+    }
+}
+
+/// Sends the summary of `result` to every configured notifier. Failures are
+/// logged and never fail the update.
+pub(crate) fn notify_result(
+    config: &Config,
+    client: &reqwest::blocking::Client,
+    result: &UpdateResult,
+) {
+    if config.notifiers.is_empty() {
+        return;
+    }
+    let Some(notification) = Notification::from_result(result) else {
+        return;
+    };
+    for notifier in &config.notifiers {
+        match notifier.send(&notification, client) {
+            Ok(()) => log::debug!(target: "notify", "sent {} notification", notifier.kind()),
+            Err(e) => log::warn!(target: "notify", "{} notification failed: {e}", notifier.kind()),
+        }
+    }
+}
+
+fn send_desktop(notification: &Notification) -> Result<()> {
+    #[cfg(feature = "inhibit")]
+    match send_dbus(notification) {
+        Ok(()) => return Ok(()),
+        Err(e) => log::debug!(target: "notify", "DBus notification failed: {e}"),
+    }
+
+    let urgency = if notification.failed {
+        "critical"
+    } else {
+        "normal"
+    };
+    let status = Command::new("notify-send")
+        .args(["--app-name", "plasmoid-updater", "--urgency", urgency])
+        .arg(&notification.title)
+        .arg(&notification.message)
+        .stdin(Stdio::null())
+        .status()
+        .map_err(|e| Error::other(format!("failed to run notify-send: {e}")))?;
+    if !status.success() {
+        return Err(Error::other(format!("notify-send exited with {status}")));
+    }
+    Ok(())
+}
+
+#[cfg(feature = "inhibit")]
+fn send_dbus(notification: &Notification) -> zbus::Result<()> {
+    use std::collections::HashMap;
+    use zbus::{blocking::Connection, zvariant::Value};
+
+    // Urgency hint: 1 normal, 2 critical.
+    let urgency: u8 = if notification.failed { 2 } else { 1 };
+    let hints = HashMap::from([("urgency", Value::from(urgency))]);
+
+    Connection::session()?.call_method(
+        Some("org.freedesktop.Notifications"),
+        "/org/freedesktop/Notifications",
+        Some("org.freedesktop.Notifications"),
+        "Notify",
+        &(
+            "plasmoid-updater",
+            0u32,
+            "system-software-update",
+            notification.title.as_str(),
+            notification.message.as_str(),
+            Vec::<&str>::new(),
+            hints,
+            -1i32,
+        ),
+    )?;
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::FailedUpdate;
+
+    #[test]
+    fn summary_lists_succeeded_and_failed_components() {
+        assert_eq!(Notification::from_result(&UpdateResult::default()), None);
+
+        let result = UpdateResult {
+            succeeded: vec!["Clock".to_string(), "Weather".to_string()],
+            failed: vec![FailedUpdate::new("Notes", &Error::download("404"))],
+            ..Default::default()
+        };
+        let notification = Notification::from_result(&result).unwrap();
+
+        assert!(notification.failed);
+        assert_eq!(notification.title, "Plasma component updates failed");
+        assert_eq!(
+            notification.message,
+            "Updated 2: Clock, Weather\nFailed Notes: download failed: 404"
+        );
+    }
+
+    #[test]
+    fn notifiers_deserialize_tagged_by_kind() {
+        let notifiers: Vec<Notifier> = serde_json::from_str(
+            r#"[
+                {"kind": "desktop"},
+                {"kind": "ntfy", "url": "https://ntfy.sh/topic"},
+                {"kind": "gotify", "url": "https://gotify.example", "token": "abc"}
+            ]"#,
+        )
+        .unwrap();
+
+        assert_eq!(
+            notifiers,
+            [
+                Notifier::Desktop,
+                Notifier::Ntfy {
+                    url: "https://ntfy.sh/topic".to_string(),
+                    token: None,
+                },
+                Notifier::Gotify {
+                    url: "https://gotify.example".to_string(),
+                    token: "abc".to_string(),
+                },
+            ]
+        );
+    }
+}
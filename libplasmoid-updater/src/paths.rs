@@ -17,6 +17,93 @@ pub(crate) fn cache_home() -> PathBuf {
         .unwrap_or_else(|_| user_home().join(".cache"))
 }
 
+/// Returns the user's state directory, respecting XDG_STATE_HOME.
+pub(crate) fn state_home() -> PathBuf {
+    std::env::var("XDG_STATE_HOME")
+        .map(PathBuf::from)
+        .unwrap_or_else(|_| user_home().join(".local/state"))
+}
+
+/// Returns `$XDG_STATE_HOME/plasmoid-updater/<name>`, for records that must
+/// survive a cache wipe but are of no use on another machine: transaction
+/// journals, recorded hashes, pins, history and backups.
+pub(crate) fn state_dir(name: &str) -> PathBuf {
+    state_home().join("plasmoid-updater").join(name)
+}
+
+/// Returns `/var/lib/plasmoid-updater/<name>`, where the records of system
+/// components are kept, next to the system registry.
+pub(crate) fn system_state_dir(name: &str) -> PathBuf {
+    Path::new("/var/lib/plasmoid-updater").join(name)
+}
+
+/// Moves `legacy` to `dir` unless `dir` already exists. Returns the
+/// directory to use, which stays `legacy` if it cannot be moved, e.g. when
+/// the two are on different filesystems.
+pub(crate) fn adopt_legacy(dir: &Path, legacy: &Path) -> PathBuf {
+    if dir.exists() || !legacy.exists() {
+        return dir.to_path_buf();
+    }
+    let moved = dir
+        .parent()
+        .map_or(Ok(()), std::fs::create_dir_all)
+        .and_then(|()| std::fs::rename(legacy, dir));
+    match moved {
+        Ok(()) => {
+            log::info!(target: "paths", "moved {} to {}", legacy.display(), dir.display());
+            dir.to_path_buf()
+        }
+        Err(e) => {
+            log::warn!(
+                target: "paths",
+                "could not move {} to {}, still using it: {e}",
+                legacy.display(),
+                dir.display()
+            );
+            legacy.to_path_buf()
+        }
+    }
+}
+
+/// Returns the user's config directory, respecting XDG_CONFIG_HOME.
+pub(crate) fn config_home() -> PathBuf {
+    std::env::var("XDG_CONFIG_HOME")
+        .map(PathBuf::from)
+        .unwrap_or_else(|_| user_home().join(".config"))
+}
+
+/// Plasma's desktop and panel layout, under [`config_home()`].
+pub(crate) const APPLETSRC: &str = "plasma-org.kde.plasma.desktop-appletsrc";
+
+/// Data directory every distro ships Plasma components in.
+pub(crate) const SYSTEM_DATA_DIR: &str = "/usr/share";
+
+/// Returns the system-wide data directories from `XDG_DATA_DIRS` in
+/// precedence order, e.g. `/usr/local/share` or the Flatpak exports.
+///
+/// Follows the XDG default of `/usr/local/share:/usr/share` when unset.
+/// Relative entries and directories under the user's home (per-user Flatpak
+/// exports) are skipped, and `/usr/share` is always included.
+pub(crate) fn system_data_dirs() -> Vec<PathBuf> {
+    parse_data_dirs(std::env::var("XDG_DATA_DIRS").ok().as_deref(), user_home())
+}
+
+fn parse_data_dirs(value: Option<&str>, home: &Path) -> Vec<PathBuf> {
+    let value = value
+        .filter(|v| !v.trim().is_empty())
+        .unwrap_or("/usr/local/share:/usr/share");
+    let mut dirs: Vec<PathBuf> = Vec::new();
+    for dir in value.split(':').map(|d| Path::new(d.trim_end_matches('/'))) {
+        if dir.is_absolute() && !dir.starts_with(home) && !dirs.iter().any(|d| d == dir) {
+            dirs.push(dir.to_path_buf());
+        }
+    }
+    if !dirs.iter().any(|d| d == Path::new(SYSTEM_DATA_DIR)) {
+        dirs.push(PathBuf::from(SYSTEM_DATA_DIR));
+    }
+    dirs
+}
+
 /// Returns the XDG runtime directory, or a UID-namespaced /tmp fallback.
 pub(crate) fn runtime_dir() -> PathBuf {
     std::env::var("XDG_RUNTIME_DIR")
@@ -29,18 +116,26 @@ pub(crate) fn runtime_dir() -> PathBuf {
         })
 }
 
+/// Returns the running Plasma major version from `KDE_SESSION_VERSION`,
+/// assuming Plasma 6 outside a session (e.g. over SSH or from a timer).
+pub(crate) fn plasma_major_version() -> u32 {
+    std::env::var("KDE_SESSION_VERSION")
+        .ok()
+        .and_then(|v| v.trim().parse().ok())
+        .unwrap_or(6)
+}
+
 /// Returns the KNewStuff3 registry directory.
 pub(crate) fn knewstuff_dir() -> PathBuf {
     data_home().join("knewstuff3")
 }
 
-/// Returns true if KDE Plasma is detected on this system.
+/// Returns the registry directory for system-wide installs.
 ///
-/// Checks for the presence of the KNewStuff3 registry directory, which is
-/// created when KDE Plasma runs for the first time. This works over SSH and
-/// in headless environments where session environment variables are not set.
-pub(crate) fn is_kde() -> bool {
-    knewstuff_dir().exists()
+/// KNewStuff only reads the per-user registry, so components updated with
+/// `--system` are tracked here instead of polluting the invoking user's files.
+pub(crate) fn system_knewstuff_dir() -> PathBuf {
+    system_state_dir("knewstuff3")
 }
 
 static USER_HOME: OnceLock<PathBuf> = OnceLock::new();
@@ -117,4 +212,56 @@ mod tests {
             assert!(ch.starts_with(user_home()));
         }
     }
+
+    #[test]
+    fn legacy_state_is_moved_once() {
+        let root = tempfile::tempdir().unwrap();
+        let legacy = root.path().join("cache/plasmoid-updater/backups");
+        let dir = root.path().join("state/plasmoid-updater/backups");
+        std::fs::create_dir_all(legacy.join("2025-01-01T00-00-00")).unwrap();
+
+        assert_eq!(adopt_legacy(&dir, &legacy), dir);
+        assert!(dir.join("2025-01-01T00-00-00").is_dir());
+        assert!(!legacy.exists());
+
+        // A legacy directory recreated by an older release is left alone.
+        std::fs::create_dir_all(&legacy).unwrap();
+        assert_eq!(adopt_legacy(&dir, &legacy), dir);
+        assert!(legacy.exists());
+    }
+
+    #[test]
+    fn data_dirs_follow_xdg_order_without_user_dirs() {
+        let home = Path::new("/home/me");
+        let dirs = parse_data_dirs(
+            Some(
+                "/usr/local/share/:/home/me/.local/share/flatpak/exports/share:relative:/var/lib/flatpak/exports/share:/usr/share:/usr/local/share",
+            ),
+            home,
+        );
+        assert_eq!(
+            dirs,
+            [
+                PathBuf::from("/usr/local/share"),
+                PathBuf::from("/var/lib/flatpak/exports/share"),
+                PathBuf::from("/usr/share"),
+            ]
+        );
+    }
+
+    #[test]
+    fn data_dirs_default_and_always_include_usr_share() {
+        let home = Path::new("/home/me");
+        assert_eq!(
+            parse_data_dirs(None, home),
+            [
+                PathBuf::from("/usr/local/share"),
+                PathBuf::from("/usr/share")
+            ]
+        );
+        assert_eq!(
+            parse_data_dirs(Some("/opt/kde/share"), home),
+            [PathBuf::from("/opt/kde/share"), PathBuf::from("/usr/share")]
+        );
+    }
 }
@@ -0,0 +1,404 @@
+// SPDX-License-Identifier: GPL-3.0-or-later
+//
+// KNewStuff registry format based on KDE Discover (https://invent.kde.org/plasma/discover) -
+// GPL-2.0-only OR GPL-3.0-only OR LicenseRef-KDE-Accepted-GPL
+
+use std::{
+    collections::HashMap,
+    fs,
+    path::{Path, PathBuf},
+};
+
+use serde::Serialize;
+
+use crate::{Result, types::ComponentType, version::normalize_version};
+
+use super::{
+    RegistryManager, registry_path, utils,
+    xml::{self, EntryEdit, RawEntry},
+};
+
+/// Result of auditing the KNewStuff registry files.
+///
+/// Returned by [`audit_registry()`](crate::audit_registry) and
+/// [`repair_registry()`](crate::repair_registry). After a repair, `issues`
+/// lists what was fixed.
+#[derive(Debug, Clone, Default, Serialize)]
+pub struct RegistryReport {
+    /// Problems found, in registry file order.
+    pub issues: Vec<RegistryIssue>,
+    /// Whether the issues were written back as fixes.
+    pub repaired: bool,
+}
+
+impl RegistryReport {
+    /// Returns `true` if no issues were found.
+    pub fn is_clean(&self) -> bool {
+        self.issues.is_empty()
+    }
+
+    /// Prints a formatted table of registry issues to stdout.
+    #[cfg(feature = "cli")]
+    pub fn print_table(&self) {
+        crate::cli::output::print_registry_issues(&self.issues);
+    }
+}
+
+/// A single inconsistent entry in a `.knsregistry` file.
+#[derive(Debug, Clone, Serialize)]
+pub struct RegistryIssue {
+    /// Registry file name, e.g. `plasmoids.knsregistry`.
+    pub registry: String,
+    /// Display name recorded in the entry.
+    pub name: String,
+    /// What is wrong with the entry.
+    pub kind: RegistryIssueKind,
+}
+
+/// The kind of inconsistency found in a registry entry.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+#[serde(tag = "kind", rename_all = "snake_case")]
+pub enum RegistryIssueKind {
+    /// The recorded installed file no longer exists; the entry is removed.
+    Orphaned { path: PathBuf },
+    /// Another entry points at the same component; the earlier one is removed.
+    Duplicate,
+    /// The recorded version differs from the on-disk metadata.
+    VersionMismatch { recorded: String, on_disk: String },
+    /// The recorded metadata file is missing but its sibling format exists.
+    InstalledFileMismatch { recorded: PathBuf, on_disk: PathBuf },
+}
+
+impl std::fmt::Display for RegistryIssueKind {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Self::Orphaned { path } => write!(f, "orphaned: {} missing", path.display()),
+            Self::Duplicate => write!(f, "duplicate entry"),
+            Self::VersionMismatch { recorded, on_disk } => {
+                write!(
+                    f,
+                    "version mismatch: {recorded} recorded, {on_disk} on disk"
+                )
+            }
+            Self::InstalledFileMismatch { recorded, on_disk } => write!(
+                f,
+                "installed file mismatch: {} recorded, {} on disk",
+                recorded.display(),
+                on_disk.display()
+            ),
+        }
+    }
+}
+
+/// Issues and their fixes for a single registry file.
+struct FileAudit {
+    issues: Vec<RegistryIssue>,
+    edits: HashMap<usize, EntryEdit>,
+}
+
+/// Audits every registry file without modifying anything.
+pub(crate) fn audit() -> Result<RegistryReport> {
+    let mut report = RegistryReport::default();
+    for file_audit in audit_files()? {
+        report.issues.extend(file_audit.issues);
+    }
+    Ok(report)
+}
+
+/// Audits every registry file and writes the fixes back.
+///
+/// Each file is re-audited under the registry lock, so fixes always apply to
+/// the content actually being rewritten.
+pub(crate) fn repair() -> Result<RegistryReport> {
+    let mut report = RegistryReport {
+        repaired: true,
+        ..Default::default()
+    };
+
+    for &ct in ComponentType::all() {
+        let Some(manager) = RegistryManager::for_component_type(ct) else {
+            continue;
+        };
+        if !manager.path().exists() {
+            continue;
+        }
+        let registry = registry_name(manager.path());
+
+        let mut issues = Vec::new();
+        manager.modify(|content| {
+            let file_audit = audit_entries(ct, &registry, &xml::parse_raw_entries(content));
+            issues = file_audit.issues;
+            if file_audit.edits.is_empty() {
+                return Ok(None);
+            }
+            xml::apply_edits(content, &file_audit.edits).map(Some)
+        })?;
+
+        if !issues.is_empty() {
+            log::debug!(
+                target: "registry",
+                "repaired {} entries in {}",
+                issues.len(),
+                manager.path().display()
+            );
+        }
+        report.issues.extend(issues);
+    }
+
+    Ok(report)
+}
+
+fn audit_files() -> Result<Vec<FileAudit>> {
+    let mut audits = Vec::new();
+
+    for &ct in ComponentType::all() {
+        let Some(path) = registry_path(ct) else {
+            continue;
+        };
+        if !path.exists() {
+            continue;
+        }
+        let content = fs::read_to_string(&path)?;
+        let registry = registry_name(&path);
+        audits.push(audit_entries(
+            ct,
+            &registry,
+            &xml::parse_raw_entries(&content),
+        ));
+    }
+
+    Ok(audits)
+}
+
+fn registry_name(path: &Path) -> String {
+    path.file_name()
+        .map(|n| n.to_string_lossy().into_owned())
+        .unwrap_or_default()
+}
+
+fn audit_entries(component_type: ComponentType, registry: &str, entries: &[RawEntry]) -> FileAudit {
+    let mut issues = Vec::new();
+    let mut edits: HashMap<usize, EntryEdit> = HashMap::new();
+    let mut issue = |name: &str, kind: RegistryIssueKind| {
+        issues.push(RegistryIssue {
+            registry: registry.to_string(),
+            name: name.to_string(),
+            kind,
+        });
+    };
+
+    // First pass: resolve each live entry to a path that exists, or drop it.
+    let mut live: Vec<(usize, PathBuf)> = Vec::new();
+
+    for (index, entry) in entries.iter().enumerate() {
+        if entry.status == "deleted" {
+            continue;
+        }
+        let Some(recorded) = entry.first_installed_path() else {
+            continue;
+        };
+
+        if recorded.exists() {
+            live.push((index, recorded));
+            continue;
+        }
+
+        if let Some(on_disk) = sibling_metadata_file(&recorded) {
+            issue(
+                &entry.name,
+                RegistryIssueKind::InstalledFileMismatch {
+                    recorded,
+                    on_disk: on_disk.clone(),
+                },
+            );
+            edits.entry(index).or_default().installed_file =
+                Some(on_disk.to_string_lossy().into_owned());
+            live.push((index, on_disk));
+            continue;
+        }
+
+        issue(&entry.name, RegistryIssueKind::Orphaned { path: recorded });
+        edits.entry(index).or_default().remove = true;
+    }
+
+    // Second pass: keep only the last entry per component, then check versions.
+    let mut last_by_key: HashMap<String, usize> = HashMap::new();
+    for (index, path) in &live {
+        last_by_key.insert(dedupe_key(&entries[*index], path), *index);
+    }
+
+    for (index, path) in &live {
+        let entry = &entries[*index];
+        if last_by_key.get(&dedupe_key(entry, path)) != Some(index) {
+            issue(&entry.name, RegistryIssueKind::Duplicate);
+            // A duplicate is dropped entirely; other fixes to it are moot.
+            edits.insert(
+                *index,
+                EntryEdit {
+                    remove: true,
+                    ..Default::default()
+                },
+            );
+            continue;
+        }
+
+        if component_type.registry_only() {
+            continue;
+        }
+        let package_dir = utils::resolve_component_path(path.clone());
+        let Some(on_disk) = crate::checker::read_package_metadata(&package_dir)
+            .and_then(|m| m.version().map(str::to_string))
+            .filter(|v| !v.is_empty())
+        else {
+            continue;
+        };
+        if normalize_version(&on_disk) != normalize_version(&entry.version) {
+            issue(
+                &entry.name,
+                RegistryIssueKind::VersionMismatch {
+                    recorded: entry.version.clone(),
+                    on_disk: on_disk.clone(),
+                },
+            );
+            edits.entry(*index).or_default().version = Some(on_disk);
+        }
+    }
+
+    FileAudit { issues, edits }
+}
+
+/// Entries describe the same component when they share a directory name.
+fn dedupe_key(entry: &RawEntry, path: &Path) -> String {
+    utils::extract_directory_name(path).unwrap_or_else(|| entry.name.clone())
+}
+
+/// Returns the other metadata file format next to a missing `metadata.json`
+/// or `metadata.desktop`, if that one exists.
+fn sibling_metadata_file(recorded: &Path) -> Option<PathBuf> {
+    let sibling = match recorded.file_name()?.to_str()? {
+        "metadata.json" => "metadata.desktop",
+        "metadata.desktop" => "metadata.json",
+        _ => return None,
+    };
+    let candidate = recorded.with_file_name(sibling);
+    candidate.exists().then_some(candidate)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn registry_with(entries: &[(&str, &str, &Path)]) -> String {
+        let mut xml = String::from("<hotnewstuffregistry>\n");
+        for (name, version, installed) in entries {
+            xml.push_str(&format!(
+                "  <stuff category=\"705\">\n    <name>{name}</name>\n    <version>{version}</version>\n    <installedfile>{}</installedfile>\n  </stuff>\n",
+                installed.display()
+            ));
+        }
+        xml.push_str("</hotnewstuffregistry>\n");
+        xml
+    }
+
+    fn write_package(root: &Path, dir: &str, version: &str) -> PathBuf {
+        let package = root.join(dir);
+        fs::create_dir_all(&package).unwrap();
+        fs::write(
+            package.join("metadata.json"),
+            format!(r#"{{"KPlugin": {{"Name": "{dir}", "Version": "{version}"}}}}"#),
+        )
+        .unwrap();
+        package
+    }
+
+    fn kinds(audit: &FileAudit) -> Vec<&RegistryIssueKind> {
+        audit.issues.iter().map(|i| &i.kind).collect()
+    }
+
+    #[test]
+    fn clean_registry_has_no_issues() {
+        let tmp = tempfile::tempdir().unwrap();
+        let pkg = write_package(tmp.path(), "clock", "1.0");
+        let xml = registry_with(&[("Clock", "1.0", &pkg.join("metadata.json"))]);
+
+        let audit = audit_entries(
+            ComponentType::PlasmaWidget,
+            "plasmoids.knsregistry",
+            &xml::parse_raw_entries(&xml),
+        );
+        assert!(audit.issues.is_empty());
+        assert!(audit.edits.is_empty());
+    }
+
+    #[test]
+    fn detects_orphans_duplicates_and_mismatches() {
+        let tmp = tempfile::tempdir().unwrap();
+        let clock = write_package(tmp.path(), "clock", "2.0");
+        let legacy = tmp.path().join("legacy");
+        fs::create_dir_all(&legacy).unwrap();
+        fs::write(legacy.join("metadata.desktop"), "[Desktop Entry]\n").unwrap();
+
+        let xml = registry_with(&[
+            ("Gone", "1.0", &tmp.path().join("gone/metadata.json")),
+            ("Clock", "1.0", &clock.join("metadata.json")),
+            ("Clock", "1.5", &clock.join("metadata.json")),
+            ("Legacy", "1.0", &legacy.join("metadata.json")),
+        ]);
+
+        let audit = audit_entries(
+            ComponentType::PlasmaWidget,
+            "plasmoids.knsregistry",
+            &xml::parse_raw_entries(&xml),
+        );
+
+        assert_eq!(
+            kinds(&audit),
+            vec![
+                &RegistryIssueKind::Orphaned {
+                    path: tmp.path().join("gone/metadata.json")
+                },
+                &RegistryIssueKind::InstalledFileMismatch {
+                    recorded: legacy.join("metadata.json"),
+                    on_disk: legacy.join("metadata.desktop"),
+                },
+                &RegistryIssueKind::Duplicate,
+                &RegistryIssueKind::VersionMismatch {
+                    recorded: "1.5".to_string(),
+                    on_disk: "2.0".to_string(),
+                },
+            ]
+        );
+
+        let repaired = xml::apply_edits(&xml, &audit.edits).unwrap();
+        let entries = xml::parse_raw_entries(&repaired);
+        assert_eq!(entries.len(), 2);
+        assert_eq!(entries[0].name, "Clock");
+        assert_eq!(entries[0].version, "2.0");
+        assert_eq!(
+            entries[1].first_installed_path(),
+            Some(legacy.join("metadata.desktop"))
+        );
+
+        let again = audit_entries(
+            ComponentType::PlasmaWidget,
+            "plasmoids.knsregistry",
+            &entries,
+        );
+        assert!(again.issues.is_empty());
+    }
+
+    #[test]
+    fn deleted_entries_are_ignored() {
+        let tmp = tempfile::tempdir().unwrap();
+        let xml = format!(
+            "<hotnewstuffregistry>\n  <stuff>\n    <name>Old</name>\n    <status>deleted</status>\n    <installedfile>{}</installedfile>\n  </stuff>\n</hotnewstuffregistry>\n",
+            tmp.path().join("old/metadata.json").display()
+        );
+        let audit = audit_entries(
+            ComponentType::PlasmaWidget,
+            "plasmoids.knsregistry",
+            &xml::parse_raw_entries(&xml),
+        );
+        assert!(audit.issues.is_empty());
+    }
+}
@@ -3,11 +3,20 @@
 // KNewStuff registry format based on KDE Discover (https://invent.kde.org/plasma/discover) -
 // GPL-2.0-only OR GPL-3.0-only OR LicenseRef-KDE-Accepted-GPL
 
-use std::{collections::HashMap, fs, path::PathBuf};
+use std::{
+    collections::HashMap,
+    fs::{self, File, OpenOptions, Permissions},
+    io::Write,
+    os::unix::fs::{MetadataExt, PermissionsExt},
+    path::{Path, PathBuf},
+    time::SystemTime,
+};
 
-use crate::{Result, types::ComponentType};
+use nix::fcntl::{Flock, FlockArg};
 
-use super::{registry_path, utils, xml};
+use crate::{Error, Result, types::ComponentType};
+
+use super::{registry_path, system_registry_path, utils, xml};
 
 /// Entry from a KNewStuff registry file.
 #[derive(Debug, Clone)]
@@ -16,8 +25,13 @@ pub(crate) struct RegistryEntry {
     pub(crate) version: String,
     pub(crate) installed_path: PathBuf,
     pub(crate) release_date: String,
+    /// Whether the entry points at a system-wide install (`/usr`, `/lib`).
+    pub(crate) is_system: bool,
 }
 
+/// How many times a modification is retried when the file changes underneath it.
+const MAX_MODIFY_ATTEMPTS: usize = 3;
+
 /// Manages KNewStuff registry files for a specific component type.
 ///
 /// Provides a higher-level API for reading, finding, and updating
@@ -30,7 +44,31 @@ impl RegistryManager {
     /// Creates a `RegistryManager` for the given component type.
     /// Returns `None` if the component type has no associated registry file.
     pub(crate) fn for_component_type(component_type: ComponentType) -> Option<Self> {
-        registry_path(component_type).map(|file_path| Self { file_path })
+        registry_path(component_type).map(Self::new)
+    }
+
+    /// Creates a `RegistryManager` for the system-wide registry of a component type.
+    pub(crate) fn for_system(component_type: ComponentType) -> Option<Self> {
+        system_registry_path(component_type).map(Self::new)
+    }
+
+    /// Creates a `RegistryManager` for the registry matching a component's scope.
+    pub(crate) fn for_scope(component_type: ComponentType, system: bool) -> Option<Self> {
+        if system {
+            Self::for_system(component_type)
+        } else {
+            Self::for_component_type(component_type)
+        }
+    }
+
+    /// Creates a `RegistryManager` for an explicit registry file path.
+    pub(crate) fn new(file_path: PathBuf) -> Self {
+        Self { file_path }
+    }
+
+    /// Returns the path of the managed registry file.
+    pub(crate) fn path(&self) -> &Path {
+        &self.file_path
     }
 
     /// Reads all entries from the registry file.
@@ -55,4 +93,181 @@ impl RegistryManager {
             })
             .collect()
     }
+
+    /// Read-modify-writes the registry file safely.
+    ///
+    /// `edit` receives the current content (an empty registry when the file is
+    /// missing) and returns the new content, or `None` to leave it untouched.
+    /// The file is flocked for the duration, and written to a temp file in the
+    /// same directory that is renamed over the original. Discover does not take
+    /// the lock, so if the file's mtime or content changed while `edit` ran the
+    /// whole cycle is retried against the fresh content.
+    ///
+    /// Returns `true` if the file was written.
+    pub(crate) fn modify(
+        &self,
+        mut edit: impl FnMut(&str) -> Result<Option<String>>,
+    ) -> Result<bool> {
+        if let Some(parent) = self.file_path.parent() {
+            fs::create_dir_all(parent)?;
+        }
+
+        for attempt in 1..=MAX_MODIFY_ATTEMPTS {
+            let _lock = lock_file(&self.file_path)?;
+            let before = Snapshot::read(&self.file_path)?;
+
+            let current = if before.content.trim().is_empty() {
+                xml::create_empty_registry()
+            } else {
+                before.content.clone()
+            };
+            let Some(updated) = edit(&current)? else {
+                return Ok(false);
+            };
+
+            if Snapshot::read(&self.file_path)? != before {
+                log::debug!(
+                    target: "registry",
+                    "{} changed during update (attempt {attempt}), retrying",
+                    self.file_path.display()
+                );
+                continue;
+            }
+
+            write_atomic(&self.file_path, &updated)?;
+            return Ok(true);
+        }
+
+        Err(Error::other(format!(
+            "registry {} kept changing during update",
+            self.file_path.display()
+        )))
+    }
+}
+
+/// Observed state of a registry file, used to detect concurrent writers.
+#[derive(PartialEq, Eq)]
+struct Snapshot {
+    modified: Option<SystemTime>,
+    content: String,
+}
+
+impl Snapshot {
+    fn read(path: &Path) -> Result<Self> {
+        let modified = fs::metadata(path).and_then(|m| m.modified()).ok();
+        let content = match fs::read_to_string(path) {
+            Ok(content) => content,
+            Err(e) if e.kind() == std::io::ErrorKind::NotFound => String::new(),
+            Err(e) => return Err(e.into()),
+        };
+        Ok(Self { modified, content })
+    }
+}
+
+/// Takes an exclusive flock on `path`, creating it if missing.
+///
+/// A writer that renamed a new file into place while we waited leaves us
+/// holding the lock on the old inode, so the lock is only returned once it
+/// covers the file currently at `path`.
+fn lock_file(path: &Path) -> Result<Flock<File>> {
+    loop {
+        let file = OpenOptions::new()
+            .create(true)
+            .truncate(false)
+            .write(true)
+            .open(path)?;
+        let flock = Flock::lock(file, FlockArg::LockExclusive).map_err(|(_, errno)| {
+            Error::other(format!("failed to lock {}: {errno}", path.display()))
+        })?;
+
+        let locked = flock.metadata()?;
+        match fs::metadata(path) {
+            Ok(current) if current.ino() == locked.ino() && current.dev() == locked.dev() => {
+                return Ok(flock);
+            }
+            _ => continue,
+        }
+    }
+}
+
+/// Writes `content` to a temp file next to `path` and renames it into place,
+/// keeping the original file's permissions.
+fn write_atomic(path: &Path, content: &str) -> Result<()> {
+    let dir = path.parent().unwrap_or(Path::new("."));
+    let permissions = fs::metadata(path)
+        .map(|m| m.permissions())
+        .unwrap_or_else(|_| Permissions::from_mode(0o644));
+
+    let mut tmp = tempfile::Builder::new()
+        .prefix(".knsregistry-")
+        .tempfile_in(dir)?;
+    tmp.write_all(content.as_bytes())?;
+    tmp.as_file().set_permissions(permissions)?;
+    tmp.as_file().sync_all()?;
+    tmp.persist(path).map_err(|e| Error::Io(e.error))?;
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn modify_creates_missing_registry() {
+        let tmp = tempfile::tempdir().unwrap();
+        let manager = RegistryManager::new(tmp.path().join("knewstuff3/plasmoids.knsregistry"));
+
+        let written = manager
+            .modify(|content| {
+                Ok(Some(content.replace(
+                    "</hotnewstuffregistry>",
+                    "<x/></hotnewstuffregistry>",
+                )))
+            })
+            .unwrap();
+
+        assert!(written);
+        let content = fs::read_to_string(manager.path()).unwrap();
+        assert!(content.contains("<hotnewstuffregistry>\n<x/></hotnewstuffregistry>"));
+    }
+
+    #[test]
+    fn modify_returning_none_leaves_file_untouched() {
+        let tmp = tempfile::tempdir().unwrap();
+        let path = tmp.path().join("plasmoids.knsregistry");
+        fs::write(&path, "<hotnewstuffregistry/>").unwrap();
+        fs::set_permissions(&path, Permissions::from_mode(0o640)).unwrap();
+
+        let manager = RegistryManager::new(path.clone());
+        assert!(!manager.modify(|_| Ok(None)).unwrap());
+        assert_eq!(fs::read_to_string(&path).unwrap(), "<hotnewstuffregistry/>");
+
+        manager.modify(|_| Ok(Some("<new/>".to_string()))).unwrap();
+        assert_eq!(fs::read_to_string(&path).unwrap(), "<new/>");
+        let mode = fs::metadata(&path).unwrap().permissions().mode() & 0o777;
+        assert_eq!(mode, 0o640);
+    }
+
+    #[test]
+    fn modify_retries_after_concurrent_write() {
+        let tmp = tempfile::tempdir().unwrap();
+        let path = tmp.path().join("plasmoids.knsregistry");
+        fs::write(&path, "<a/>").unwrap();
+
+        let manager = RegistryManager::new(path.clone());
+        let mut seen = Vec::new();
+        manager
+            .modify(|content| {
+                seen.push(content.to_string());
+                if seen.len() == 1 {
+                    // Simulate Discover writing without taking the lock.
+                    fs::write(&path, "<b/>").unwrap();
+                }
+                Ok(Some(format!("{content}<ours/>")))
+            })
+            .unwrap();
+
+        assert_eq!(seen, ["<a/>", "<b/>"]);
+        assert_eq!(fs::read_to_string(&path).unwrap(), "<b/><ours/>");
+    }
 }
@@ -3,17 +3,24 @@
 // KNewStuff registry format based on KDE Discover (https://invent.kde.org/plasma/discover) -
 // GPL-2.0-only OR GPL-3.0-only OR LicenseRef-KDE-Accepted-GPL
 
+mod audit;
 mod manager;
 mod utils;
 mod xml;
 
+pub use audit::{RegistryIssue, RegistryIssueKind, RegistryReport};
+pub(crate) use audit::{audit, repair};
 pub(crate) use manager::{RegistryEntry, RegistryManager};
 
-use std::{collections::HashMap, fs, path::PathBuf};
+use std::{
+    collections::{HashMap, HashSet},
+    fs,
+    path::PathBuf,
+};
 
 use crate::{
     Result,
-    types::{AvailableUpdate, ComponentType, InstalledComponent},
+    types::{AvailableUpdate, ComponentType, InstalledComponent, intern_registry_name},
 };
 
 /// Scans registry files to discover installed components.
@@ -38,8 +45,12 @@ pub(crate) fn scan_registry_components(
                 version: entry.version,
                 component_type,
                 path,
-                is_system: false,
+                is_system: entry.is_system,
                 release_date: entry.release_date,
+                managed_by_distro: false,
+                metadata: Default::default(),
+                shadowed: None,
+                in_use: false,
             })
         })
         .collect();
@@ -47,12 +58,59 @@ pub(crate) fn scan_registry_components(
     Ok(components)
 }
 
-/// Loads registry entries into a map keyed by directory name.
+/// Returns a [`ComponentType::Other`] for every registry file in the user's
+/// KNewStuff directory that no modelled type owns, sorted by file name.
+pub(crate) fn generic_types() -> Vec<ComponentType> {
+    let known: HashSet<&str> = ComponentType::all()
+        .iter()
+        .filter_map(|ct| ct.registry_file())
+        .collect();
+    let Ok(entries) = fs::read_dir(crate::paths::knewstuff_dir()) else {
+        return Vec::new();
+    };
+
+    let mut names: Vec<String> = entries
+        .flatten()
+        .filter_map(|entry| entry.file_name().into_string().ok())
+        .filter(|name| name.ends_with(".knsregistry") && !known.contains(name.as_str()))
+        .collect();
+    names.sort();
+
+    names
+        .iter()
+        .map(|name| ComponentType::Other(intern_registry_name(name)))
+        .collect()
+}
+
+/// Loads registry entries of one scope into a map keyed by directory name.
 /// Used to look up release dates for installed components.
-pub(crate) fn load_registry_map(component_type: ComponentType) -> HashMap<String, RegistryEntry> {
-    RegistryManager::for_component_type(component_type)
-        .map(|m| m.load_entry_map())
-        .unwrap_or_default()
+///
+/// System entries come from the system registry, plus any recorded in the
+/// user's registry by older versions; user entries never include system paths.
+pub(crate) fn load_registry_map(
+    component_type: ComponentType,
+    system: bool,
+) -> HashMap<String, RegistryEntry> {
+    let user = RegistryManager::for_component_type(component_type);
+    let system_manager = system
+        .then(|| RegistryManager::for_system(component_type))
+        .flatten();
+
+    [user, system_manager]
+        .into_iter()
+        .flatten()
+        .flat_map(|m| m.load_entry_map())
+        .filter(|(_, entry)| entry.is_system == system)
+        .collect()
+}
+
+/// Returns true if any registry file that could own this component lists its directory.
+pub(crate) fn is_registered(component: &InstalledComponent) -> bool {
+    component
+        .component_type
+        .shared_path_types()
+        .iter()
+        .any(|&ct| load_registry_map(ct, false).contains_key(&component.directory_name))
 }
 
 /// Returns the filesystem path to the KNewStuff registry file for a component type.
@@ -62,9 +120,16 @@ pub(crate) fn registry_path(component_type: ComponentType) -> Option<PathBuf> {
         .map(|f| crate::paths::knewstuff_dir().join(f))
 }
 
+/// Returns the filesystem path to the system-wide registry file for a component type.
+pub(crate) fn system_registry_path(component_type: ComponentType) -> Option<PathBuf> {
+    component_type
+        .registry_file()
+        .map(|f| crate::paths::system_knewstuff_dir().join(f))
+}
+
 /// Returns true if the given path belongs to a system-wide installation.
 fn is_system_path(path: &str) -> bool {
-    path.starts_with("/usr") || path.starts_with("/lib")
+    crate::installer::privilege::is_system_path(std::path::Path::new(path))
 }
 
 /// Builds a directory_name -> content_id lookup cache from all registry files.
@@ -72,54 +137,65 @@ fn is_system_path(path: &str) -> bool {
 /// Reads each registry file once and extracts directory names and content IDs,
 /// eliminating the need for per-component file I/O during resolution.
 ///
-/// When `system` is true, only entries whose installed path starts with "/usr"
-/// or "/lib" are included. When false, only user-local entries are included.
+/// When `system` is true, the system registry is read as well, and only entries
+/// whose installed path starts with "/usr" or "/lib" are included. When false,
+/// only user-local entries are included.
 ///
 /// Registry-only component types (color schemes, wallpapers, icon themes) are
 /// always included regardless of the `system` flag, since KNewStuff registries
 /// are per-user and always store user-local paths even for system-wide installs.
 pub(crate) fn build_id_cache(system: bool) -> HashMap<String, u64> {
     let mut cache = HashMap::new();
-    let knewstuff = crate::paths::knewstuff_dir();
+    let mut dirs = vec![crate::paths::knewstuff_dir()];
+    if system {
+        dirs.push(crate::paths::system_knewstuff_dir());
+    }
 
-    for &ct in ComponentType::all() {
+    let types = ComponentType::all().iter().copied().chain(generic_types());
+    for ct in types {
         let Some(file) = ct.registry_file() else {
             continue;
         };
-        let path = knewstuff.join(file);
-        let Ok(content) = fs::read_to_string(&path) else {
-            continue;
-        };
-
-        // Registry-only types have no metadata files on disk; their identity
-        // comes solely from the KNewStuff registry which always uses user paths.
-        // Skipping them under --system would lose all ID mappings, causing
-        // wrong name-based fallback matches against unrelated store entries.
-        let skip_path_filter = ct.registry_only();
-
-        for raw in xml::parse_raw_entries(&content) {
-            let Some(id) = raw.content_id() else {
+        for dir in &dirs {
+            let Ok(content) = fs::read_to_string(dir.join(file)) else {
                 continue;
             };
-            if let Some(installed_path) = raw.first_installed_path()
-                && let Some(dir_name) = utils::extract_directory_name(&installed_path)
-                && (skip_path_filter || system == is_system_path(&installed_path.to_string_lossy()))
-            {
-                cache.insert(dir_name, id);
-            }
+            collect_ids(&mut cache, ct, &content, system);
         }
     }
 
     cache
 }
 
+/// Adds the directory-name -> content-id pairs of one registry file to `cache`.
+fn collect_ids(cache: &mut HashMap<String, u64>, ct: ComponentType, content: &str, system: bool) {
+    // Registry-only types have no metadata files on disk; their identity
+    // comes solely from the KNewStuff registry which always uses user paths.
+    // Skipping them under --system would lose all ID mappings, causing
+    // wrong name-based fallback matches against unrelated store entries.
+    let skip_path_filter = ct.registry_only();
+
+    for raw in xml::parse_raw_entries(content) {
+        let Some(id) = raw.content_id() else {
+            continue;
+        };
+        if let Some(installed_path) = raw.first_installed_path()
+            && let Some(dir_name) = utils::extract_directory_name(&installed_path)
+            && (skip_path_filter || system == is_system_path(&installed_path.to_string_lossy()))
+        {
+            cache.insert(dir_name, id);
+        }
+    }
+}
+
 /// Updates the KNS registry after a successful component update.
 /// This ensures Discover sees the correct installed version.
 /// If the entry doesn't exist, it creates a new one.
 pub(crate) fn update_registry_after_install(update: &AvailableUpdate) -> Result<()> {
     let component = &update.installed;
 
-    let Some(reg_path) = registry_path(component.component_type) else {
+    let Some(manager) = RegistryManager::for_scope(component.component_type, component.is_system)
+    else {
         log::debug!(
             target: "registry",
             "no registry file for {}",
@@ -128,17 +204,17 @@ pub(crate) fn update_registry_after_install(update: &AvailableUpdate) -> Result<
         return Ok(());
     };
 
-    let release_date = utils::extract_date_from_iso(&update.release_date);
-
-    if let Some(parent) = reg_path.parent() {
-        fs::create_dir_all(parent)?;
+    if component.is_system && !nix::unistd::Uid::effective().is_root() {
+        log::warn!(
+            target: "registry",
+            "skipping registry update for {}: {} is only writable by root",
+            component.name,
+            manager.path().display()
+        );
+        return Ok(());
     }
 
-    let content = if reg_path.exists() {
-        fs::read_to_string(&reg_path)?
-    } else {
-        xml::create_empty_registry()
-    };
+    let release_date = utils::extract_date_from_iso(&update.release_date);
 
     let fields = xml::UpdateFields {
         directory_name: &component.directory_name,
@@ -148,37 +224,104 @@ pub(crate) fn update_registry_after_install(update: &AvailableUpdate) -> Result<
         installed_path: &component.path,
         release_date: &release_date,
     };
+    let entry = xml::NewEntry {
+        name: &component.name,
+        component_type: component.component_type,
+        content_id: update.content_id,
+        version: &update.latest_version,
+        download_url: &update.download_url,
+        installed_path: &component.path,
+        release_date: &release_date,
+    };
 
-    let updated = xml::update_entry(&content, &fields)?;
+    let mut added = false;
+    manager.modify(|content| {
+        let updated = xml::update_entry(content, &fields)?;
+        added = updated.is_none();
+        Ok(Some(
+            updated.unwrap_or_else(|| xml::add_entry(content, &entry)),
+        ))
+    })?;
 
-    if let Some(new_content) = updated {
-        fs::write(&reg_path, new_content)?;
-        log::debug!(
-            target: "registry",
-            "updated {} for {}",
-            reg_path.display(),
-            component.name
-        );
-    } else {
-        let entry = xml::NewEntry {
-            name: &component.name,
-            component_type: component.component_type,
-            content_id: update.content_id,
-            version: &update.latest_version,
-            download_url: &update.download_url,
-            installed_path: &component.path,
-            release_date: &release_date,
-        };
-        let new_content = xml::add_entry(&content, &entry);
-        fs::write(&reg_path, new_content)?;
-        log::debug!(
-            target: "registry",
-            "added {} to {}",
-            component.name,
-            reg_path.display()
-        );
+    let action = if added { "added" } else { "updated" };
+    log::debug!(
+        target: "registry",
+        "{action} {} in {}",
+        component.name,
+        manager.path().display()
+    );
+
+    Ok(())
+}
+
+/// Adds a registry entry for a component that was installed outside KNewStuff,
+/// recording its current on-disk version against the given store content ID.
+pub(crate) fn register_component(
+    component: &InstalledComponent,
+    content_id: u64,
+    download_url: &str,
+    release_date: &str,
+) -> Result<()> {
+    let Some(manager) = RegistryManager::for_component_type(component.component_type) else {
+        return Ok(());
+    };
+
+    let release_date = utils::extract_date_from_iso(release_date);
+    let entry = xml::NewEntry {
+        name: &component.name,
+        component_type: component.component_type,
+        content_id,
+        version: &component.version,
+        download_url,
+        installed_path: &component.path,
+        release_date: &release_date,
+    };
+    manager.modify(|content| Ok(Some(xml::add_entry(content, &entry))))?;
+    log::debug!(
+        target: "registry",
+        "adopted {} into {}",
+        component.name,
+        manager.path().display()
+    );
+
+    Ok(())
+}
+
+/// Removes the registry entries recording `component`'s install path, after
+/// its files were deleted.
+pub(crate) fn unregister_component(component: &InstalledComponent) -> Result<()> {
+    let Some(manager) = RegistryManager::for_component_type(component.component_type) else {
+        return Ok(());
+    };
+    if !manager.path().exists() {
+        return Ok(());
     }
 
+    manager.modify(|content| {
+        let edits: HashMap<usize, xml::EntryEdit> = xml::parse_raw_entries(content)
+            .iter()
+            .enumerate()
+            .filter(|(_, entry)| entry.first_installed_path().as_ref() == Some(&component.path))
+            .map(|(index, _)| {
+                let edit = xml::EntryEdit {
+                    remove: true,
+                    ..Default::default()
+                };
+                (index, edit)
+            })
+            .collect();
+        if edits.is_empty() {
+            return Ok(None);
+        }
+        xml::apply_edits(content, &edits).map(Some)
+    })?;
+    log::debug!(
+        target: "registry",
+        "removed {} from {}",
+        component.name,
+        manager.path().display()
+    );
+
     Ok(())
 }
 
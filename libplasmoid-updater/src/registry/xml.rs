@@ -5,10 +5,14 @@
 
 use std::{
     borrow::Cow,
+    collections::HashMap,
     path::{Path, PathBuf},
 };
 
-use quick_xml::{Reader, Writer, events::Event};
+use quick_xml::{
+    Reader, Writer,
+    events::{BytesEnd, BytesStart, BytesText, Event},
+};
 
 use crate::{
     types::ComponentType,
@@ -20,10 +24,11 @@ use super::{manager::RegistryEntry, utils};
 /// Raw fields collected from a single `<stuff>` entry during XML parsing.
 #[derive(Default)]
 pub(super) struct RawEntry {
-    name: String,
-    version: String,
+    pub(super) name: String,
+    pub(super) version: String,
     id_text: String,
     release_date: String,
+    pub(super) status: String,
     installed_files: Vec<String>,
     uninstalled_files: Vec<String>,
 }
@@ -51,6 +56,27 @@ pub(super) struct NewEntry<'a> {
     pub release_date: &'a str,
 }
 
+/// Changes to apply to a single `<stuff>` entry via [`apply_edits`].
+#[derive(Debug, Default, Clone)]
+pub(super) struct EntryEdit {
+    /// Drop the whole entry.
+    pub remove: bool,
+    /// Replacement `<version>` text.
+    pub version: Option<String>,
+    /// Replacement text for the first `<installedfile>`.
+    pub installed_file: Option<String>,
+}
+
+impl EntryEdit {
+    fn replacement_for(&self, element: &[u8]) -> Option<&str> {
+        match element {
+            b"version" => self.version.as_deref(),
+            b"installedfile" => self.installed_file.as_deref(),
+            _ => None,
+        }
+    }
+}
+
 /// Fields to update in a registry entry.
 pub(super) struct UpdateFields<'a> {
     pub directory_name: &'a str,
@@ -90,11 +116,9 @@ pub(super) fn parse_raw_entries(xml: &str) -> Vec<RawEntry> {
                     current = RawEntry::default();
                 }
             }
-            Ok(Event::End(e)) => {
-                if e.name().as_ref() == b"stuff" && in_entry {
-                    entries.push(std::mem::take(&mut current));
-                    in_entry = false;
-                }
+            Ok(Event::End(e)) if e.name().as_ref() == b"stuff" && in_entry => {
+                entries.push(std::mem::take(&mut current));
+                in_entry = false;
             }
             Ok(Event::Text(e)) => {
                 if !in_entry {
@@ -113,6 +137,7 @@ pub(super) fn parse_raw_entries(xml: &str) -> Vec<RawEntry> {
                     b"releasedate" => {
                         current.release_date = String::from_utf8_lossy(e.as_ref()).into_owned();
                     }
+                    b"status" => current.status = String::from_utf8_lossy(e.as_ref()).into_owned(),
                     b"installedfile" => {
                         current
                             .installed_files
@@ -144,11 +169,13 @@ pub(super) fn parse_registry_entries(xml: &str) -> Vec<RegistryEntry> {
             if raw.name.is_empty() || installed_path.as_os_str().is_empty() {
                 return None;
             }
+            let is_system = super::is_system_path(&installed_path.to_string_lossy());
             Some(RegistryEntry {
                 name: raw.name,
                 version: raw.version,
                 installed_path,
                 release_date: raw.release_date,
+                is_system,
             })
         })
         .collect()
@@ -181,12 +208,15 @@ fn escape_xml_text(s: &str) -> Cow<'_, str> {
 }
 
 /// Adds a new entry to the registry XML.
+///
+/// Existing content is kept verbatim; the new entry is spliced in before the
+/// closing tag using the indentation of the entries already in the file.
 pub(super) fn add_entry(xml: &str, entry: &NewEntry) -> String {
     let category_id = entry.component_type.category_id();
     let store_url = format!("https://store.kde.org/p/{}", entry.content_id);
     let installed_file = utils::registry_installed_file_path(entry.installed_path);
 
-    let new_entry = format!(
+    let template = format!(
         r#"  <stuff category="{category_id}">
     <name>{name}</name>
     <providerid>api.kde-look.org</providerid>
@@ -214,6 +244,10 @@ pub(super) fn add_entry(xml: &str, entry: &NewEntry) -> String {
         content_id = entry.content_id,
         release_date = entry.release_date,
     );
+    let new_entry = match entry_indent(xml) {
+        Some(indent) if indent != "  " => reindent(&template, indent),
+        _ => template,
+    };
 
     if let Some(pos) = xml.rfind("</hotnewstuffregistry>") {
         let suffix = "</hotnewstuffregistry>\n";
@@ -233,6 +267,26 @@ pub(super) fn add_entry(xml: &str, entry: &NewEntry) -> String {
     }
 }
 
+/// Returns the indentation unit used before `<stuff>` elements in the document.
+fn entry_indent(xml: &str) -> Option<&str> {
+    let pos = xml.find("<stuff")?;
+    let line_start = xml[..pos].rfind('\n').map_or(0, |i| i + 1);
+    let indent = &xml[line_start..pos];
+    (!indent.is_empty() && indent.chars().all(|c| c == ' ' || c == '\t')).then_some(indent)
+}
+
+/// Rewrites a two-space-indented template to use `indent` per level.
+fn reindent(template: &str, indent: &str) -> String {
+    template
+        .lines()
+        .map(|line| {
+            let trimmed = line.trim_start_matches(' ');
+            let levels = (line.len() - trimmed.len()) / 2;
+            format!("{}{trimmed}\n", indent.repeat(levels))
+        })
+        .collect()
+}
+
 /// Updates an existing entry in the registry XML.
 /// Returns `Some(new_xml)` if entry was found and updated, `None` if not found.
 pub(super) fn update_entry(xml: &str, fields: &UpdateFields) -> Result<Option<String>> {
@@ -270,24 +324,22 @@ fn find_target_index(xml: &str, directory_name: &str) -> Option<usize> {
                     current_matches = false;
                 }
             }
-            Ok(Event::Text(e)) if in_entry => {
-                if !current_matches
+            Ok(Event::Text(e))
+                if in_entry
+                    && !current_matches
                     && matches!(
                         current_element.as_slice(),
                         b"installedfile" | b"uninstalledfile"
-                    )
-                {
-                    // Check path segments at byte level — no UTF-8 conversion needed.
-                    current_matches = (*e).split(|&b| b == b'/').any(|seg| seg == dir_bytes);
-                }
+                    ) =>
+            {
+                // Check path segments at byte level — no UTF-8 conversion needed.
+                current_matches = (*e).split(|&b| b == b'/').any(|seg| seg == dir_bytes);
             }
-            Ok(Event::End(e)) => {
-                if e.name().as_ref() == b"stuff" && in_entry {
-                    if current_matches {
-                        return entry_index;
-                    }
-                    in_entry = false;
+            Ok(Event::End(e)) if e.name().as_ref() == b"stuff" && in_entry => {
+                if current_matches {
+                    return entry_index;
                 }
+                in_entry = false;
             }
             Ok(Event::Eof) | Err(_) => break,
             _ => {}
@@ -297,56 +349,173 @@ fn find_target_index(xml: &str, directory_name: &str) -> Option<usize> {
     None
 }
 
+/// Registry fields owned by this tool, in the order KNewStuff writes them.
+/// Everything else in an entry (author, summary, previews, tags, …) is
+/// passed through untouched.
+const OWNED_FIELDS: [&str; 6] = [
+    "version",
+    "installedfile",
+    "id",
+    "releasedate",
+    "payload",
+    "status",
+];
+
 /// Rewrites the registry XML, updating fields in the target entry.
+///
+/// The document is streamed without trimming, so formatting, unknown elements
+/// and attributes survive byte-for-byte. Within the target entry, owned fields
+/// are replaced, missing ones are appended before `</stuff>`, a stale
+/// `<uninstalledfile>` becomes `<installedfile>`, and extra file entries are
+/// dropped in favour of the single installed path.
 fn rewrite_with_updates(
     xml: &str,
     target_index: usize,
     fields: &UpdateFields,
 ) -> Result<Option<String>> {
     let mut reader = Reader::from_str(xml);
-    reader.config_mut().trim_text(true);
-
     let mut writer = Writer::new(Vec::new());
-    let mut current_element = Vec::new();
+
     let mut entry_index: Option<usize> = None;
+    let mut in_target = false;
+    let mut depth = 0usize;
+    let mut seen: Vec<&str> = Vec::new();
+    // Owned field currently open in the target entry, and its replacement
+    // text if not yet written.
+    let mut open_field: Option<&str> = None;
+    let mut pending: Option<Cow<str>> = None;
+    let mut skip_until_depth: Option<usize> = None;
+    let mut held_whitespace: Option<BytesText> = None;
+    let mut child_indent = String::from("\n    ");
 
     loop {
-        match reader.read_event() {
-            Ok(Event::Start(e)) => {
-                let qname = e.name();
-                let name = qname.as_ref();
-                current_element.clear();
-                current_element.extend_from_slice(name);
-
-                if name == b"stuff" {
-                    entry_index = Some(entry_index.map_or(0, |i| i + 1));
+        let event = reader
+            .read_event()
+            .map_err(|e| Error::xml_parse(format!("registry xml parse error: {e}")))?;
+
+        if let Some(target_depth) = skip_until_depth {
+            match event {
+                Event::Start(_) => depth += 1,
+                Event::End(_) => {
+                    depth -= 1;
+                    if depth == target_depth {
+                        skip_until_depth = None;
+                    }
                 }
-
-                writer.write_event(Event::Start(e.clone()))?;
+                _ => {}
             }
-            Ok(Event::End(e)) => {
-                writer.write_event(Event::End(e))?;
+            continue;
+        }
+
+        // The original content of a replaced field may span several events
+        // (text, entity references, CDATA); all of it gives way to the new value.
+        if open_field.is_some()
+            && matches!(
+                event,
+                Event::Text(_) | Event::CData(_) | Event::GeneralRef(_)
+            )
+        {
+            if let Some(text) = pending.take() {
+                writer.write_event(Event::Text(BytesText::new(&text)))?;
             }
-            Ok(Event::Text(e)) => {
-                let in_target = entry_index == Some(target_index);
-
-                if in_target
-                    && let Some(replacement) = get_field_replacement(&current_element, fields)
-                {
-                    writer.write_event(Event::Text(quick_xml::events::BytesText::new(
-                        &replacement,
-                    )))?;
+            continue;
+        }
+
+        // Whitespace between the target's children is held back so missing
+        // fields can be inserted before `</stuff>` with the entry's own indentation.
+        if in_target
+            && depth == 2
+            && let Event::Text(ref t) = event
+            && t.iter().all(u8::is_ascii_whitespace)
+        {
+            flush_whitespace(&mut writer, &mut held_whitespace)?;
+            held_whitespace = Some(t.clone().into_owned());
+            continue;
+        }
+
+        match event {
+            Event::Start(e) => {
+                depth += 1;
+                let field = (in_target && depth == 3)
+                    .then(|| owned_field(e.name().as_ref()))
+                    .flatten();
+
+                if let Some(field) = field {
+                    if seen.contains(&field) {
+                        // Extra file entry: drop it along with its indentation.
+                        held_whitespace = None;
+                        skip_until_depth = Some(depth - 1);
+                        continue;
+                    }
+                    seen.push(field);
+                    note_indent(&mut child_indent, &held_whitespace);
+                    flush_whitespace(&mut writer, &mut held_whitespace)?;
+                    writer.write_event(Event::Start(renamed_start(&e, field)))?;
+                    open_field = Some(field);
+                    pending = Some(get_field_replacement(field, fields));
                     continue;
                 }
 
-                writer.write_event(Event::Text(e))?;
+                if depth == 2 && e.name().as_ref() == b"stuff" {
+                    let index = entry_index.map_or(0, |i| i + 1);
+                    entry_index = Some(index);
+                    in_target = index == target_index;
+                    seen.clear();
+                } else if in_target && depth == 3 {
+                    note_indent(&mut child_indent, &held_whitespace);
+                }
+                flush_whitespace(&mut writer, &mut held_whitespace)?;
+                writer.write_event(Event::Start(e))?;
             }
-            Ok(Event::Eof) => break,
-            Ok(e) => {
-                writer.write_event(e)?;
+            Event::Empty(e) => {
+                let field = (in_target && depth == 2)
+                    .then(|| owned_field(e.name().as_ref()))
+                    .flatten();
+
+                match field {
+                    Some(field) if seen.contains(&field) => held_whitespace = None,
+                    Some(field) => {
+                        seen.push(field);
+                        note_indent(&mut child_indent, &held_whitespace);
+                        flush_whitespace(&mut writer, &mut held_whitespace)?;
+                        let text = get_field_replacement(field, fields);
+                        write_text_element(&mut writer, renamed_start(&e, field), &text)?;
+                    }
+                    None => {
+                        flush_whitespace(&mut writer, &mut held_whitespace)?;
+                        writer.write_event(Event::Empty(e))?;
+                    }
+                }
+            }
+            Event::End(e) => {
+                if in_target && depth == 2 {
+                    for field in OWNED_FIELDS.into_iter().filter(|f| !seen.contains(f)) {
+                        writer.write_event(Event::Text(BytesText::from_escaped(
+                            child_indent.as_str(),
+                        )))?;
+                        let text = get_field_replacement(field, fields);
+                        write_text_element(&mut writer, BytesStart::new(field), &text)?;
+                    }
+                    in_target = false;
+                }
+                flush_whitespace(&mut writer, &mut held_whitespace)?;
+
+                match open_field.take() {
+                    Some(field) => {
+                        // `<version></version>` has no text event; emit the value here.
+                        if let Some(text) = pending.take() {
+                            writer.write_event(Event::Text(BytesText::new(&text)))?;
+                        }
+                        writer.write_event(Event::End(BytesEnd::new(field)))?;
+                    }
+                    None => writer.write_event(Event::End(e))?,
+                }
+                depth -= 1;
             }
-            Err(e) => {
-                return Err(Error::xml_parse(format!("registry xml parse error: {e}")));
+            Event::Eof => break,
+            e => {
+                flush_whitespace(&mut writer, &mut held_whitespace)?;
+                writer.write_event(e)?;
             }
         }
     }
@@ -356,24 +525,369 @@ fn rewrite_with_updates(
     Ok(Some(result))
 }
 
-/// Returns the replacement value for a field being updated, or None if no replacement.
+/// Maps an element name to the owned field it represents. A stale
+/// `<uninstalledfile>` counts as `installedfile`, since the update reinstalls it.
+fn owned_field(element: &[u8]) -> Option<&'static str> {
+    if element == b"uninstalledfile" {
+        return Some("installedfile");
+    }
+    OWNED_FIELDS
+        .into_iter()
+        .find(|field| field.as_bytes() == element)
+}
+
+/// Returns `start` renamed to `field`, keeping its attributes.
+fn renamed_start<'a>(start: &BytesStart<'a>, field: &str) -> BytesStart<'a> {
+    if start.name().as_ref() == field.as_bytes() {
+        return start.clone();
+    }
+    BytesStart::new(field.to_string()).with_attributes(start.attributes().flatten())
+}
+
+/// Remembers the whitespace preceding a child element as the entry's indentation.
+fn note_indent(child_indent: &mut String, held: &Option<BytesText>) {
+    if let Some(ws) = held {
+        *child_indent = String::from_utf8_lossy(ws).into_owned();
+    }
+}
+
+fn flush_whitespace(writer: &mut Writer<Vec<u8>>, held: &mut Option<BytesText>) -> Result<()> {
+    if let Some(ws) = held.take() {
+        writer.write_event(Event::Text(ws))?;
+    }
+    Ok(())
+}
+
+/// Returns the replacement value for an owned field.
 ///
 /// Returns borrowed slices where possible to avoid allocation for fields that
 /// are already owned by the caller (`version`, `payload`, `releasedate`, `status`).
 /// Only `id` (integer formatting) and `installedfile` (path formatting) allocate.
-fn get_field_replacement<'a>(
-    element_name: &[u8],
-    fields: &'a UpdateFields,
-) -> Option<Cow<'a, str>> {
-    match element_name {
-        b"version" => Some(Cow::Borrowed(fields.new_version)),
-        b"id" => Some(Cow::Owned(fields.content_id.to_string())),
-        b"payload" => Some(Cow::Borrowed(fields.download_url)),
-        b"releasedate" => Some(Cow::Borrowed(fields.release_date)),
-        b"status" => Some(Cow::Borrowed("installed")),
-        b"installedfile" | b"uninstalledfile" => Some(Cow::Owned(
-            utils::registry_installed_file_path(fields.installed_path),
-        )),
-        _ => None,
+fn get_field_replacement<'a>(field: &str, fields: &'a UpdateFields) -> Cow<'a, str> {
+    match field {
+        "version" => Cow::Borrowed(fields.new_version),
+        "id" => Cow::Owned(fields.content_id.to_string()),
+        "payload" => Cow::Borrowed(fields.download_url),
+        "releasedate" => Cow::Borrowed(fields.release_date),
+        "status" => Cow::Borrowed("installed"),
+        "installedfile" => Cow::Owned(utils::registry_installed_file_path(fields.installed_path)),
+        _ => Cow::Borrowed(""),
+    }
+}
+
+/// Rewrites registry XML, applying `edits` keyed by 0-based `<stuff>` index.
+///
+/// Unlike [`update_entry`], text is not trimmed: whitespace and every element
+/// not targeted by an edit pass through byte-for-byte, so a repair only
+/// touches the entries it fixes.
+pub(super) fn apply_edits(xml: &str, edits: &HashMap<usize, EntryEdit>) -> Result<String> {
+    let mut reader = Reader::from_str(xml);
+    let mut writer = Writer::new(Vec::new());
+
+    let mut entry_index: Option<usize> = None;
+    let mut current_edit: Option<&EntryEdit> = None;
+    let mut skipping = false;
+    let mut skip_trailing_whitespace = false;
+    let mut installed_file_replaced = false;
+    let mut replacing = false;
+    let mut pending: Option<&str> = None;
+
+    loop {
+        let event = reader
+            .read_event()
+            .map_err(|e| Error::xml_parse(format!("registry xml parse error: {e}")))?;
+
+        if skip_trailing_whitespace {
+            skip_trailing_whitespace = false;
+            if let Event::Text(ref t) = event
+                && t.iter().all(u8::is_ascii_whitespace)
+            {
+                continue;
+            }
+        }
+
+        // Replaced text may span several events (text, entity references, CDATA).
+        if replacing
+            && matches!(
+                event,
+                Event::Text(_) | Event::CData(_) | Event::GeneralRef(_)
+            )
+        {
+            if let Some(text) = pending.take() {
+                writer.write_event(Event::Text(BytesText::new(text)))?;
+            }
+            continue;
+        }
+
+        match event {
+            Event::Start(e) => {
+                if e.name().as_ref() == b"stuff" {
+                    let index = entry_index.map_or(0, |i| i + 1);
+                    entry_index = Some(index);
+                    current_edit = edits.get(&index);
+                    installed_file_replaced = false;
+                    if current_edit.is_some_and(|edit| edit.remove) {
+                        skipping = true;
+                        continue;
+                    }
+                }
+                if skipping {
+                    continue;
+                }
+                pending = next_replacement(
+                    current_edit,
+                    e.name().as_ref(),
+                    &mut installed_file_replaced,
+                );
+                replacing = pending.is_some();
+                writer.write_event(Event::Start(e))?;
+            }
+            Event::Empty(e) => {
+                if skipping {
+                    continue;
+                }
+                match next_replacement(
+                    current_edit,
+                    e.name().as_ref(),
+                    &mut installed_file_replaced,
+                ) {
+                    Some(text) => write_text_element(&mut writer, e, text)?,
+                    None => writer.write_event(Event::Empty(e))?,
+                }
+            }
+            Event::Text(e) => {
+                if !skipping {
+                    writer.write_event(Event::Text(e))?;
+                }
+            }
+            Event::End(e) => {
+                if skipping {
+                    if e.name().as_ref() == b"stuff" {
+                        skipping = false;
+                        current_edit = None;
+                        skip_trailing_whitespace = true;
+                    }
+                    continue;
+                }
+                // `<version></version>` has no text event; emit the replacement here.
+                if let Some(text) = pending.take() {
+                    writer.write_event(Event::Text(BytesText::new(text)))?;
+                }
+                replacing = false;
+                if e.name().as_ref() == b"stuff" {
+                    current_edit = None;
+                }
+                writer.write_event(Event::End(e))?;
+            }
+            Event::Eof => break,
+            e => {
+                if !skipping {
+                    writer.write_event(e)?;
+                }
+            }
+        }
+    }
+
+    String::from_utf8(writer.into_inner())
+        .map_err(|e| Error::xml_parse(format!("invalid utf8 in registry: {e}")))
+}
+
+/// Returns the replacement text for `element` in the entry being edited, if any.
+/// Only the first `<installedfile>` of an entry is replaced.
+fn next_replacement<'a>(
+    edit: Option<&'a EntryEdit>,
+    element: &[u8],
+    installed_file_replaced: &mut bool,
+) -> Option<&'a str> {
+    let replacement = edit?.replacement_for(element)?;
+    if element == b"installedfile" {
+        if *installed_file_replaced {
+            return None;
+        }
+        *installed_file_replaced = true;
+    }
+    Some(replacement)
+}
+
+/// Expands a self-closing `<element/>` into `<element>text</element>`.
+fn write_text_element(writer: &mut Writer<Vec<u8>>, start: BytesStart, text: &str) -> Result<()> {
+    let end = BytesEnd::new(String::from_utf8_lossy(start.name().as_ref()).into_owned());
+    writer.write_event(Event::Start(start))?;
+    writer.write_event(Event::Text(BytesText::new(text)))?;
+    writer.write_event(Event::End(end))?;
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Registry as written by Discover (QDom, one-space indent, self-closing empties).
+    const DISCOVER_REGISTRY: &str = include_str!("testdata/plasmoids.knsregistry");
+
+    fn fields<'a>(dir: &'a str, path: &'a Path) -> UpdateFields<'a> {
+        UpdateFields {
+            directory_name: dir,
+            content_id: 2130967,
+            new_version: "1.3.0",
+            download_url: "https://files06.pling.com/new.plasmoid?a=1&b=2",
+            installed_path: path,
+            release_date: "2025-01-15",
+        }
+    }
+
+    #[test]
+    fn add_entry_keeps_existing_content_and_indentation() {
+        let entry = NewEntry {
+            name: "Clock & Date",
+            component_type: ComponentType::PlasmaWidget,
+            content_id: 42,
+            version: "1.0",
+            download_url: "https://example.org/clock.plasmoid",
+            installed_path: Path::new("/home/user/.local/share/plasma/plasmoids/clock"),
+            release_date: "2025-01-01",
+        };
+        let out = add_entry(DISCOVER_REGISTRY, &entry);
+
+        let close = DISCOVER_REGISTRY.rfind("</hotnewstuffregistry>").unwrap();
+        assert!(out.starts_with(&DISCOVER_REGISTRY[..close]));
+        assert!(out.contains("\n <stuff category=\"705\">\n  <name>Clock &amp; Date</name>\n"));
+        assert!(out.ends_with("  <status>installed</status>\n </stuff>\n</hotnewstuffregistry>\n"));
+        assert_eq!(parse_raw_entries(&out).len(), 3);
+    }
+
+    #[test]
+    fn parse_registry_entries_records_scope() {
+        let xml = r#"<hotnewstuffregistry>
+ <stuff><name>User</name><installedfile>/home/u/.local/share/plasma/plasmoids/a/metadata.json</installedfile></stuff>
+ <stuff><name>System</name><installedfile>/usr/share/plasma/plasmoids/b/metadata.json</installedfile></stuff>
+</hotnewstuffregistry>"#;
+        let entries = parse_registry_entries(xml);
+        assert!(!entries[0].is_system);
+        assert!(entries[1].is_system);
+    }
+
+    #[test]
+    fn update_entry_matches_golden_file() {
+        let path =
+            Path::new("/home/user/.local/share/plasma/plasmoids/luisbocanegra.panel.colorizer");
+        let out = update_entry(
+            DISCOVER_REGISTRY,
+            &fields("luisbocanegra.panel.colorizer", path),
+        )
+        .unwrap()
+        .unwrap();
+        assert_eq!(out, include_str!("testdata/plasmoids.updated.knsregistry"));
+    }
+
+    #[test]
+    fn update_entry_preserves_untargeted_entries_byte_for_byte() {
+        let path =
+            Path::new("/home/user/.local/share/plasma/plasmoids/luisbocanegra.panel.colorizer");
+        let out = update_entry(
+            DISCOVER_REGISTRY,
+            &fields("luisbocanegra.panel.colorizer", path),
+        )
+        .unwrap()
+        .unwrap();
+        let kurve_start = DISCOVER_REGISTRY
+            .find(" <stuff category=\"Plasma 6 Widgets\">\n  <name>Kurve")
+            .unwrap();
+        assert!(out.ends_with(&DISCOVER_REGISTRY[kurve_start..]));
+    }
+
+    #[test]
+    fn update_entry_reinstalls_deleted_entry_and_adds_missing_fields() {
+        let path =
+            Path::new("/home/user/.local/share/plasma/plasmoids/luisbocanegra.audio.visualizer");
+        let out = update_entry(
+            DISCOVER_REGISTRY,
+            &fields("luisbocanegra.audio.visualizer", path),
+        )
+        .unwrap()
+        .unwrap();
+
+        let entries = parse_raw_entries(&out);
+        let kurve = &entries[1];
+        assert_eq!(kurve.status, "installed");
+        assert_eq!(kurve.version, "1.3.0");
+        assert_eq!(kurve.release_date, "2025-01-15");
+        assert_eq!(kurve.installed_files.len(), 1);
+        assert!(kurve.uninstalled_files.is_empty());
+        assert!(out.contains("\n  <releasedate>2025-01-15</releasedate>\n  <payload>https://files06.pling.com/new.plasmoid?a=1&amp;b=2</payload>\n </stuff>"));
+        assert!(out.contains("<author homepage=\"https://store.kde.org/u/luisbocanegra\" id=\"luisbocanegra\">luisbocanegra</author>"));
+        assert!(out.contains(
+            "<preview>https://images.pling.com/img/00/00/80/60/79/2151247/preview.png</preview>"
+        ));
+    }
+
+    const REGISTRY: &str = r#"<?xml version="1.0" encoding="UTF-8"?>
+<!DOCTYPE khotnewstuff3>
+<hotnewstuffregistry>
+  <stuff category="705">
+    <name>First</name>
+    <author>someone</author>
+    <version>1.0</version>
+    <installedfile>/home/u/.local/share/plasma/plasmoids/first/metadata.json</installedfile>
+  </stuff>
+  <stuff category="705">
+    <name>Second</name>
+    <version></version>
+    <installedfile>/home/u/.local/share/plasma/plasmoids/second/metadata.json</installedfile>
+  </stuff>
+</hotnewstuffregistry>
+"#;
+
+    #[test]
+    fn apply_edits_without_edits_round_trips() {
+        let out = apply_edits(REGISTRY, &HashMap::new()).unwrap();
+        assert_eq!(out, REGISTRY);
+    }
+
+    #[test]
+    fn apply_edits_removes_entry_cleanly() {
+        let edits = HashMap::from([(
+            0,
+            EntryEdit {
+                remove: true,
+                ..Default::default()
+            },
+        )]);
+        let out = apply_edits(REGISTRY, &edits).unwrap();
+        assert!(!out.contains("First"));
+        assert!(
+            out.contains("<hotnewstuffregistry>\n  <stuff category=\"705\">\n    <name>Second")
+        );
+        assert_eq!(parse_raw_entries(&out).len(), 1);
+    }
+
+    #[test]
+    fn apply_edits_replaces_fields_and_keeps_others() {
+        let edits = HashMap::from([
+            (
+                0,
+                EntryEdit {
+                    version: Some("2.0".to_string()),
+                    installed_file: Some("/x/first/metadata.desktop".to_string()),
+                    ..Default::default()
+                },
+            ),
+            (
+                1,
+                EntryEdit {
+                    version: Some("3.0".to_string()),
+                    ..Default::default()
+                },
+            ),
+        ]);
+        let out = apply_edits(REGISTRY, &edits).unwrap();
+        let entries = parse_raw_entries(&out);
+        assert_eq!(entries[0].version, "2.0");
+        assert_eq!(
+            entries[0].first_installed_path(),
+            Some(PathBuf::from("/x/first/metadata.desktop"))
+        );
+        assert_eq!(entries[1].version, "3.0");
+        assert!(out.contains("<author>someone</author>"));
     }
 }
@@ -0,0 +1,363 @@
+// SPDX-License-Identifier: GPL-3.0-or-later
+
+use std::fmt::Write;
+
+use crate::{CheckResult, Diagnostic, OutcomeStatus, RunRecord, UpdateResult};
+
+/// Longest changelog excerpt shown in a table cell, in characters.
+const EXCERPT_LEN: usize = 80;
+
+/// Longest error excerpt shown in a run report, in characters.
+const ERROR_EXCERPT_LEN: usize = 160;
+
+pub(crate) fn check_markdown(result: &CheckResult) -> String {
+    let mut out = String::from("# Plasma component updates\n\n");
+    if !result.catalog.is_complete() {
+        let _ = writeln!(
+            out,
+            "> Store results incomplete: {}/{} catalog pages failed, updates may be missing.\n",
+            result.catalog.failed_pages, result.catalog.pages
+        );
+    }
+
+    if result.available_updates.is_empty() {
+        out.push_str("No updates available.\n");
+    } else {
+        out.push_str("| Component | Current | Available | Severity | Changes |\n");
+        out.push_str("| --- | --- | --- | --- | --- |\n");
+        for update in &result.available_updates {
+            let _ = writeln!(
+                out,
+                "| [{}]({}) | {} | {} | {} | {} |",
+                cell(&update.installed.name),
+                update.store_url,
+                cell(version(&update.installed.version)),
+                cell(version(&update.latest_version)),
+                update.severity,
+                cell(&excerpt(&update.changelog)),
+            );
+        }
+    }
+
+    if !result.held.is_empty() {
+        out.push_str("\n## Held back\n\n");
+        for held in &result.held {
+            let _ = writeln!(
+                out,
+                "- {} {}: {}",
+                held.update.installed.name, held.update.latest_version, held.reason
+            );
+        }
+    }
+    diagnostics_section(&mut out, &result.diagnostics);
+    out
+}
+
+pub(crate) fn update_markdown(result: &UpdateResult) -> String {
+    let mut out = String::from("# Plasma component update report\n\n");
+
+    if result.is_empty() {
+        out.push_str("Nothing was updated.\n");
+        return out;
+    }
+
+    let _ = writeln!(
+        out,
+        "{} updated, {} failed, {} skipped.",
+        result.success_count(),
+        result.failure_count(),
+        result.skipped.len()
+    );
+
+    list_section(&mut out, "Updated", &result.succeeded);
+    if !result.failed.is_empty() {
+        out.push_str("\n## Failed\n\n| Component | Error |\n| --- | --- |\n");
+        for failed in &result.failed {
+            let _ = writeln!(out, "| {} | {} |", cell(&failed.name), cell(&failed.error));
+        }
+    }
+    if !result.unhealthy.is_empty() {
+        out.push_str("\n## Failed health check\n\n");
+        for unhealthy in &result.unhealthy {
+            let _ = writeln!(
+                out,
+                "- {}: {}",
+                unhealthy.name,
+                unhealthy.problems.join("; ")
+            );
+        }
+    }
+    if !result.unverified.is_empty() {
+        out.push_str("\n## Unverified\n\n");
+        for unverified in &result.unverified {
+            let actual = unverified.actual_version.as_deref().unwrap_or("unknown");
+            let _ = writeln!(
+                out,
+                "- {}: expected {}, found {actual}",
+                unverified.name, unverified.expected_version
+            );
+        }
+    }
+    list_section(&mut out, "Rolled back", &result.rolled_back);
+    list_section(&mut out, "Skipped", &result.skipped);
+    diagnostics_section(&mut out, &result.unresolved);
+    out
+}
+
+pub(crate) fn run_html(run: &RunRecord) -> String {
+    let failed = run.with_status(OutcomeStatus::Failed).count();
+    let mut out = String::from(
+        "<!DOCTYPE html>\n<html lang=\"en\">\n<head>\n<meta charset=\"utf-8\">\n\
+         <title>Plasma component update report</title>\n\
+         <style>body{font-family:sans-serif;margin:2em}table{border-collapse:collapse}\
+         th,td{border:1px solid #ccc;padding:.3em .6em;text-align:left}\
+         .failed{color:#b00}</style>\n</head>\n<body>\n",
+    );
+    let _ = writeln!(
+        out,
+        "<h1>Plasma component update report</h1>\n<p>{} run started {}, took {}.</p>",
+        if run.system { "System" } else { "User" },
+        html(&run.started_at),
+        seconds(run.duration_ms)
+    );
+    let _ = writeln!(
+        out,
+        "<p>{} updated, {failed} failed, {} skipped; {} downloaded. Store usage: {}.</p>",
+        run.with_status(OutcomeStatus::Succeeded).count(),
+        run.skipped.len(),
+        bytes(run.total_bytes()),
+        html(&run.api_stats.summary())
+    );
+
+    let updated: Vec<_> = run.with_status(OutcomeStatus::Succeeded).collect();
+    if !updated.is_empty() {
+        out.push_str(
+            "<h2>Updated</h2>\n<table>\n<tr><th>Component</th><th>Type</th><th>From</th>\
+             <th>To</th><th>Time</th><th>Size</th></tr>\n",
+        );
+        for c in updated {
+            let _ = writeln!(
+                out,
+                "<tr><td>{}</td><td>{}</td><td>{}</td><td>{}</td><td>{}</td><td>{}</td></tr>",
+                html(&c.name),
+                html(&c.component_type.to_string()),
+                html(version(&c.from_version)),
+                html(version(&c.to_version)),
+                seconds(c.duration_ms),
+                c.bytes.map(bytes).unwrap_or_default()
+            );
+        }
+        out.push_str("</table>\n");
+    }
+
+    if failed > 0 {
+        out.push_str(
+            "<h2>Failed</h2>\n<table>\n<tr><th>Component</th><th>Version</th>\
+             <th>Time</th><th>Error</th></tr>\n",
+        );
+        for c in run.with_status(OutcomeStatus::Failed) {
+            let _ = writeln!(
+                out,
+                "<tr class=\"failed\"><td>{}</td><td>{}</td><td>{}</td><td>{}</td></tr>",
+                html(&c.name),
+                html(version(&c.to_version)),
+                seconds(c.duration_ms),
+                html(&error_excerpt(c.error.as_deref().unwrap_or_default()))
+            );
+        }
+        out.push_str("</table>\n");
+    }
+
+    let restarts: Vec<String> = run
+        .restart_actions
+        .iter()
+        .map(|&action| crate::installer::describe_restart_action(action).to_string())
+        .collect();
+    html_list(&mut out, "Restart actions taken", &restarts);
+    html_list(&mut out, "Rolled back", &run.rolled_back);
+    html_list(&mut out, "Skipped", &run.skipped);
+    out.push_str("</body>\n</html>\n");
+    out
+}
+
+fn html_list(out: &mut String, title: &str, items: &[String]) {
+    if items.is_empty() {
+        return;
+    }
+    let _ = writeln!(out, "<h2>{title}</h2>\n<ul>");
+    for item in items {
+        let _ = writeln!(out, "<li>{}</li>", html(item));
+    }
+    out.push_str("</ul>\n");
+}
+
+/// Escapes `text` for HTML element content.
+fn html(text: &str) -> String {
+    text.replace('&', "&amp;")
+        .replace('<', "&lt;")
+        .replace('>', "&gt;")
+        .replace('"', "&quot;")
+}
+
+/// First line of an error message, cut to [`ERROR_EXCERPT_LEN`] characters.
+pub(crate) fn error_excerpt(error: &str) -> String {
+    let line = error.lines().next().unwrap_or_default().trim();
+    if line.chars().count() <= ERROR_EXCERPT_LEN {
+        return line.to_string();
+    }
+    let cut: String = line.chars().take(ERROR_EXCERPT_LEN - 1).collect();
+    format!("{}…", cut.trim_end())
+}
+
+/// Formats milliseconds as seconds with one decimal, e.g. `1.2s`.
+pub(crate) fn seconds(millis: u64) -> String {
+    format!("{:.1}s", millis as f64 / 1000.0)
+}
+
+/// Formats a byte count with a binary unit, e.g. `1.5 MiB`.
+fn bytes(bytes: u64) -> String {
+    const UNITS: [&str; 4] = ["B", "KiB", "MiB", "GiB"];
+    let mut value = bytes as f64;
+    let mut unit = 0;
+    while value >= 1024.0 && unit < UNITS.len() - 1 {
+        value /= 1024.0;
+        unit += 1;
+    }
+    if unit == 0 {
+        format!("{bytes} B")
+    } else {
+        format!("{value:.1} {}", UNITS[unit])
+    }
+}
+
+fn list_section(out: &mut String, title: &str, names: &[String]) {
+    if names.is_empty() {
+        return;
+    }
+    let _ = writeln!(out, "\n## {title}\n");
+    for name in names {
+        let _ = writeln!(out, "- {name}");
+    }
+}
+
+fn diagnostics_section(out: &mut String, diagnostics: &[Diagnostic]) {
+    if diagnostics.is_empty() {
+        return;
+    }
+    out.push_str("\n## Not checked\n\n");
+    for diagnostic in diagnostics {
+        let _ = writeln!(out, "- {}: {}", diagnostic.name, diagnostic.reason);
+    }
+}
+
+fn version(version: &str) -> &str {
+    if version.is_empty() { "-" } else { version }
+}
+
+/// Escapes `text` for a table cell.
+fn cell(text: &str) -> String {
+    text.replace('|', "\\|")
+}
+
+/// First [`EXCERPT_LEN`] characters of a store changelog, as one line of
+/// plain text.
+fn excerpt(changelog: &str) -> String {
+    let mut text = String::new();
+    let mut in_tag = false;
+    for c in changelog.chars() {
+        match c {
+            '<' => {
+                in_tag = true;
+                text.push(' ');
+            }
+            '>' if in_tag => in_tag = false,
+            _ if !in_tag => text.push(c),
+            _ => {}
+        }
+    }
+
+    let words: Vec<&str> = text.split_whitespace().collect();
+    let text = words.join(" ");
+    if text.chars().count() <= EXCERPT_LEN {
+        return text;
+    }
+    let cut: String = text.chars().take(EXCERPT_LEN - 1).collect();
+    format!("{}…", cut.trim_end())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{Error, FailedUpdate};
+
+    #[test]
+    fn excerpt_strips_markup_and_truncates() {
+        assert_eq!(excerpt(""), "");
+        assert_eq!(
+            excerpt("<p>Fixed the clock.</p><br/>\n- New   font"),
+            "Fixed the clock. - New font"
+        );
+
+        let long = "word ".repeat(40);
+        let short = excerpt(&long);
+        assert!(short.chars().count() <= EXCERPT_LEN);
+        assert!(short.ends_with('…'));
+    }
+
+    #[test]
+    fn run_report_escapes_and_excerpts_errors() {
+        use crate::{RunComponent, types::ComponentType};
+
+        let component = |name: &str, status, error: Option<&str>| RunComponent {
+            name: name.to_string(),
+            component_type: ComponentType::PlasmaWidget,
+            from_version: "1.0".to_string(),
+            to_version: "2.0".to_string(),
+            status,
+            duration_ms: 1500,
+            bytes: error.is_none().then_some(3 * 1024 * 1024 / 2),
+            sha256: None,
+            error: error.map(str::to_string),
+        };
+        let long_error = format!("download failed: {}\nsecond line", "x".repeat(300));
+        let run = RunRecord {
+            started_at: "2025-01-01T00:00:00+00:00".to_string(),
+            duration_ms: 4000,
+            system: false,
+            components: vec![
+                component("<Clock>", OutcomeStatus::Succeeded, None),
+                component("Notes", OutcomeStatus::Failed, Some(&long_error)),
+            ],
+            skipped: Vec::new(),
+            rolled_back: Vec::new(),
+            restart_actions: vec![crate::RestartAction::PlasmashellRestart],
+            api_stats: Default::default(),
+        };
+
+        let page = run_html(&run);
+        assert!(page.contains("<td>&lt;Clock&gt;</td><td>Plasma Widget</td><td>1.0</td><td>2.0</td><td>1.5s</td><td>1.5 MiB</td>"));
+        assert!(page.contains("<li>restart plasmashell</li>"));
+        assert!(!page.contains("second line"));
+        let excerpt = error_excerpt(&long_error);
+        assert_eq!(excerpt.chars().count(), ERROR_EXCERPT_LEN);
+        assert!(page.contains(&excerpt));
+    }
+
+    #[test]
+    fn update_report_tables_failures() {
+        let result = UpdateResult {
+            succeeded: vec!["Clock".to_string()],
+            failed: vec![FailedUpdate::new("Notes", &Error::other("bad | archive"))],
+            ..Default::default()
+        };
+
+        assert_eq!(
+            update_markdown(&result),
+            "# Plasma component update report\n\n\
+             1 updated, 1 failed, 0 skipped.\n\n\
+             ## Updated\n\n- Clock\n\n\
+             ## Failed\n\n| Component | Error |\n| --- | --- |\n| Notes | bad \\| archive |\n"
+        );
+        assert!(update_markdown(&UpdateResult::default()).contains("Nothing was updated."));
+    }
+}
@@ -0,0 +1,116 @@
+// SPDX-License-Identifier: GPL-3.0-or-later
+
+use std::path::{Path, PathBuf};
+
+use minisign_verify::{PublicKey, Signature};
+
+use crate::{Error, Result};
+
+/// Path of the detached minisign signature for `path`, e.g.
+/// `snapshot.json.minisig`.
+pub(crate) fn signature_path(path: &Path) -> PathBuf {
+    let mut name = path.as_os_str().to_owned();
+    name.push(".minisig");
+    PathBuf::from(name)
+}
+
+/// Parses a trusted key, either the base64 key line or the whole content of
+/// a `minisign.pub` file.
+fn parse_key(key: &str) -> Result<PublicKey> {
+    let key = key.trim();
+    let parsed = if key.contains('\n') {
+        PublicKey::decode(key)
+    } else {
+        PublicKey::from_base64(key)
+    };
+    parsed.map_err(|e| Error::Config(format!("invalid trusted key '{key}': {e}")))
+}
+
+/// Verifies `data`, read from `path`, against its detached minisign
+/// signature next to it.
+///
+/// Succeeds if any of `trusted_keys` made the signature. Legacy
+/// (non-prehashed) signatures are rejected.
+pub(crate) fn verify(path: &Path, data: &[u8], trusted_keys: &[String]) -> Result<()> {
+    if trusted_keys.is_empty() {
+        return Err(Error::Config(
+            "no trusted keys configured to verify signatures".to_string(),
+        ));
+    }
+
+    let sig_path = signature_path(path);
+    let content = std::fs::read_to_string(&sig_path)
+        .map_err(|e| Error::signature(format!("cannot read {}: {e}", sig_path.display())))?;
+    let signature = Signature::decode(&content)
+        .map_err(|e| Error::signature(format!("malformed {}: {e}", sig_path.display())))?;
+
+    for key in trusted_keys {
+        if parse_key(key)?.verify(data, &signature, false).is_ok() {
+            log::debug!(
+                target: "signing",
+                "verified {} ({})",
+                path.display(),
+                signature.trusted_comment()
+            );
+            return Ok(());
+        }
+    }
+
+    Err(Error::signature(format!(
+        "{} is not signed by a trusted key",
+        path.display()
+    )))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const SNAPSHOT: &str = concat!(
+        env!("CARGO_MANIFEST_DIR"),
+        "/testdata/signing/snapshot.json"
+    );
+    const TRUSTED_PUB: &str = include_str!("../testdata/signing/trusted.pub");
+    const OTHER_KEY: &str = "RWQi6rzZMeDKF4syUjV9W+bAQM/pFazpKAfhK9Dn38h44d8si9FywQsN";
+
+    fn data() -> Vec<u8> {
+        std::fs::read(SNAPSHOT).unwrap()
+    }
+
+    #[test]
+    fn accepts_signature_from_any_trusted_key() {
+        let keys = [OTHER_KEY.to_string(), TRUSTED_PUB.to_string()];
+        verify(Path::new(SNAPSHOT), &data(), &keys).unwrap();
+
+        let key_line = TRUSTED_PUB.lines().nth(1).unwrap().to_string();
+        verify(Path::new(SNAPSHOT), &data(), &[key_line]).unwrap();
+    }
+
+    #[test]
+    fn rejects_tampered_data_and_unknown_keys() {
+        let mut tampered = data();
+        tampered[0] = b' ';
+        let trusted = [TRUSTED_PUB.to_string()];
+        assert!(matches!(
+            verify(Path::new(SNAPSHOT), &tampered, &trusted),
+            Err(Error::SignatureInvalid(_))
+        ));
+        assert!(matches!(
+            verify(Path::new(SNAPSHOT), &data(), &[OTHER_KEY.to_string()]),
+            Err(Error::SignatureInvalid(_))
+        ));
+    }
+
+    #[test]
+    fn missing_signature_is_rejected() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("snapshot.json");
+        std::fs::write(&path, data()).unwrap();
+
+        let trusted = [TRUSTED_PUB.to_string()];
+        assert!(matches!(
+            verify(&path, &data(), &trusted),
+            Err(Error::SignatureInvalid(_))
+        ));
+    }
+}
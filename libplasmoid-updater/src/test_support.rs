@@ -0,0 +1,801 @@
+// SPDX-License-Identifier: GPL-3.0-or-later
+
+//! Sandboxed environment for end-to-end tests.
+//!
+//! A [`Sandbox`] points `XDG_DATA_HOME`, `XDG_CACHE_HOME`, `XDG_STATE_HOME` and `XDG_RUNTIME_DIR`
+//! at a temporary directory, puts a fake `kpackagetool6` first on `PATH`, and
+//! runs a local HTTP server standing in for the KDE Store (OCS API and package
+//! downloads). Installed components, registry entries and store contents are
+//! all fake, so full check → update → rollback runs work offline.
+//!
+//! Environment variables are process-wide: only one sandbox exists at a time
+//! (others block in [`Sandbox::new`]), and code outside the sandbox must not
+//! read or modify the environment while one is alive.
+//!
+//! ```no_run
+//! use libplasmoid_updater::test_support::{FakeComponent, Sandbox};
+//!
+//! let sandbox = Sandbox::new().unwrap();
+//! let clock = FakeComponent::plasmoid("org.example.clock", "Clock", "1.0");
+//! sandbox.install(&clock, Some(1000)).unwrap();
+//! sandbox.publish(1000, &clock.with_version("2.0")).unwrap();
+//!
+//! let result = libplasmoid_updater::update(&sandbox.config()).unwrap();
+//! assert_eq!(result.succeeded, ["Clock"]);
+//! assert_eq!(sandbox.installed_version(&clock).as_deref(), Some("2.0"));
+//! ```
+
+use std::{
+    collections::HashMap,
+    ffi::OsString,
+    fs,
+    io::{BufRead, BufReader, Read, Write},
+    net::{TcpListener, TcpStream},
+    os::unix::fs::PermissionsExt,
+    path::{Path, PathBuf},
+    process::Command,
+    sync::{
+        Arc,
+        atomic::{AtomicBool, Ordering},
+    },
+    thread::JoinHandle,
+};
+
+use parking_lot::{Mutex, MutexGuard};
+use tempfile::TempDir;
+
+use crate::{
+    Config, Error, HttpBackend, HttpResponse, Result,
+    types::{ComponentType, InstalledComponent},
+};
+
+const OCS_PATH: &str = "/ocs/v1";
+const ADVISORIES_PATH: &str = "/advisories.json";
+const RELEASE_DATE: &str = "2025-01-01T00:00:00+00:00";
+
+/// Environment variables a sandbox overrides, restored when it is dropped.
+const SANDBOX_VARS: [&str; 6] = [
+    "XDG_DATA_HOME",
+    "XDG_CONFIG_HOME",
+    "XDG_CACHE_HOME",
+    "XDG_STATE_HOME",
+    "XDG_RUNTIME_DIR",
+    "PATH",
+];
+
+static SANDBOX_LOCK: Mutex<()> = Mutex::new(());
+
+/// A fake installed or published component.
+#[derive(Debug, Clone)]
+pub struct FakeComponent {
+    pub id: String,
+    pub name: String,
+    pub version: String,
+    pub component_type: ComponentType,
+    /// Content of `contents/ui/main.qml`.
+    pub main_qml: String,
+    /// The store user publishing the release.
+    pub author: String,
+}
+
+impl FakeComponent {
+    /// A plasma widget with the given plugin id, display name and version.
+    pub fn plasmoid(id: &str, name: &str, version: &str) -> Self {
+        Self {
+            id: id.to_string(),
+            name: name.to_string(),
+            version: version.to_string(),
+            component_type: ComponentType::PlasmaWidget,
+            main_qml: "import QtQuick\n\nItem {}\n".to_string(),
+            author: "alice".to_string(),
+        }
+    }
+
+    /// The same component at another version.
+    pub fn with_version(&self, version: &str) -> Self {
+        Self {
+            version: version.to_string(),
+            ..self.clone()
+        }
+    }
+
+    /// The same component with another `main.qml`, e.g. a broken one to fail
+    /// the post-install health check.
+    pub fn with_main_qml(&self, main_qml: &str) -> Self {
+        Self {
+            main_qml: main_qml.to_string(),
+            ..self.clone()
+        }
+    }
+
+    /// The same component published by another store user.
+    pub fn with_author(&self, author: &str) -> Self {
+        Self {
+            author: author.to_string(),
+            ..self.clone()
+        }
+    }
+
+    fn write_package(&self, dir: &Path) -> Result<()> {
+        let kpackage_type = self
+            .component_type
+            .kpackage_type()
+            .ok_or_else(|| Error::other("fake components must be kpackage types"))?;
+        let metadata = serde_json::json!({
+            "KPackageStructure": kpackage_type,
+            "KPlugin": {
+                "Id": self.id,
+                "Name": self.name,
+                "Version": self.version,
+            },
+        });
+
+        fs::create_dir_all(dir.join("contents/ui"))?;
+        fs::write(
+            dir.join("metadata.json"),
+            serde_json::to_string_pretty(&metadata).map_err(|e| Error::other(e.to_string()))?,
+        )?;
+        fs::write(
+            dir.join("contents/marker"),
+            format!("{} {}\n", self.id, self.version),
+        )?;
+        fs::write(dir.join("contents/ui/main.qml"), &self.main_qml)?;
+        Ok(())
+    }
+}
+
+/// An isolated environment with fake components and a mock KDE Store.
+///
+/// Everything is removed and the environment restored when it is dropped.
+pub struct Sandbox {
+    root: TempDir,
+    server: MockServer,
+    saved_env: Vec<(&'static str, Option<OsString>)>,
+    _lock: MutexGuard<'static, ()>,
+}
+
+impl Sandbox {
+    /// Creates the sandbox and switches the process environment to it.
+    pub fn new() -> Result<Self> {
+        let lock = SANDBOX_LOCK.lock();
+        let root = tempfile::tempdir()?;
+
+        for dir in [
+            "data/knewstuff3",
+            "config",
+            "cache",
+            "state",
+            "runtime",
+            "bin",
+            "tmp",
+            "store",
+        ] {
+            fs::create_dir_all(root.path().join(dir))?;
+        }
+        write_fake_kpackagetool(&root.path().join("bin"))?;
+
+        let saved_env = SANDBOX_VARS
+            .iter()
+            .map(|&var| (var, std::env::var_os(var)))
+            .collect();
+
+        let path = match std::env::var_os("PATH") {
+            Some(path) => {
+                let mut dirs = vec![root.path().join("bin")];
+                dirs.extend(std::env::split_paths(&path));
+                std::env::join_paths(dirs).map_err(|e| Error::other(e.to_string()))?
+            }
+            None => root.path().join("bin").into_os_string(),
+        };
+
+        // SAFETY: SANDBOX_LOCK serialises sandboxes, and the module docs
+        // require that nothing else touches the environment meanwhile.
+        unsafe {
+            std::env::set_var("XDG_DATA_HOME", root.path().join("data"));
+            std::env::set_var("XDG_CONFIG_HOME", root.path().join("config"));
+            std::env::set_var("XDG_CACHE_HOME", root.path().join("cache"));
+            std::env::set_var("XDG_STATE_HOME", root.path().join("state"));
+            std::env::set_var("XDG_RUNTIME_DIR", root.path().join("runtime"));
+            std::env::set_var("PATH", path);
+        }
+
+        Ok(Self {
+            server: MockServer::start()?,
+            root,
+            saved_env,
+            _lock: lock,
+        })
+    }
+
+    /// Root of the sandbox.
+    pub fn root(&self) -> &Path {
+        self.root.path()
+    }
+
+    /// The sandbox's `XDG_DATA_HOME`.
+    pub fn data_home(&self) -> PathBuf {
+        self.root().join("data")
+    }
+
+    /// The sandbox's `XDG_CONFIG_HOME`, where Plasma keeps its layout.
+    pub fn config_home(&self) -> PathBuf {
+        self.root().join("config")
+    }
+
+    /// The sandbox's `XDG_STATE_HOME`, where backups and journals are kept.
+    pub fn state_home(&self) -> PathBuf {
+        self.root().join("state")
+    }
+
+    /// The sandbox's `XDG_CACHE_HOME`, where downloads are cached.
+    pub fn cache_home(&self) -> PathBuf {
+        self.root().join("cache")
+    }
+
+    /// Base URL of the mock store server, e.g. `http://127.0.0.1:40000`.
+    pub fn server_url(&self) -> &str {
+        &self.server.base_url
+    }
+
+    /// Installation directory of `component`.
+    pub fn component_path(&self, component: &FakeComponent) -> PathBuf {
+        component.component_type.user_path().join(&component.id)
+    }
+
+    /// Installs `component` and, given a store content ID, registers it in the
+    /// KNewStuff registry so it can be matched to the store.
+    pub fn install(&self, component: &FakeComponent, content_id: Option<u64>) -> Result<PathBuf> {
+        let path = self.component_path(component);
+        component.write_package(&path)?;
+
+        if let Some(content_id) = content_id {
+            let installed = InstalledComponent {
+                directory_name: component.id.clone(),
+                version: component.version.clone(),
+                ..InstalledComponent::fixture(
+                    &component.name,
+                    component.component_type,
+                    path.clone(),
+                )
+            };
+            let url = self.server.file_url(content_id, component);
+            crate::registry::register_component(&installed, content_id, &url, RELEASE_DATE)?;
+        }
+
+        Ok(path)
+    }
+
+    /// Writes `contents` to `path` and registers it as `component` under
+    /// `content_id`, for types installed as a single file rather than a
+    /// package, e.g. [`ComponentType::Other`].
+    pub fn install_raw(
+        &self,
+        component: &FakeComponent,
+        path: &Path,
+        contents: &[u8],
+        content_id: u64,
+    ) -> Result<()> {
+        if let Some(parent) = path.parent() {
+            fs::create_dir_all(parent)?;
+        }
+        fs::write(path, contents)?;
+
+        let installed = InstalledComponent {
+            directory_name: component.id.clone(),
+            version: component.version.clone(),
+            ..InstalledComponent::fixture(&component.name, component.component_type, path)
+        };
+        let url = self.server.file_url(content_id, component);
+        crate::registry::register_component(&installed, content_id, &url, RELEASE_DATE)
+    }
+
+    /// Publishes `component` in the mock store under `content_id`, replacing
+    /// any previous release.
+    pub fn publish(&self, content_id: u64, component: &FakeComponent) -> Result<()> {
+        let archive = self
+            .root()
+            .join("store")
+            .join(format!("{content_id}.tar.gz"));
+        self.write_archive(component, &archive)?;
+
+        self.server
+            .publish(content_id, component, fs::read(&archive)?);
+        Ok(())
+    }
+
+    /// Packs `component` into a gzipped tarball at `archive`, as the store
+    /// would serve it.
+    pub fn write_archive(&self, component: &FakeComponent, archive: &Path) -> Result<()> {
+        let staging = tempfile::tempdir_in(self.root().join("store"))?;
+        component.write_package(&staging.path().join(&component.id))?;
+
+        let status = Command::new("bsdtar")
+            .arg("-czf")
+            .arg(archive)
+            .arg("-C")
+            .arg(staging.path())
+            .arg(&component.id)
+            .status()?;
+        if !status.success() {
+            return Err(Error::other("bsdtar failed to build the fake package"));
+        }
+        Ok(())
+    }
+
+    /// Publishes a release whose download is not a valid archive, so
+    /// installing it fails and the update has to roll back.
+    pub fn publish_broken(&self, content_id: u64, component: &FakeComponent) {
+        self.server
+            .publish(content_id, component, b"not an archive".to_vec());
+    }
+
+    /// Publishes a release whose download is `contents` as is, e.g. a bare
+    /// file served without an archive around it.
+    pub fn publish_raw(&self, content_id: u64, component: &FakeComponent, contents: &[u8]) {
+        self.server
+            .publish(content_id, component, contents.to_vec());
+    }
+
+    /// Answers the next `times` downloads of `content_id` with HTTP 429, as
+    /// a store under load would.
+    pub fn throttle_downloads(&self, content_id: u64, times: usize) {
+        self.server.state.lock().throttled.insert(content_id, times);
+    }
+
+    /// Leaves the installed `component` half overwritten by `to`, with a
+    /// journal entry pointing at a backup, as if the process crashed in the
+    /// middle of updating it.
+    pub fn interrupt_update(
+        &self,
+        component: &FakeComponent,
+        to: &FakeComponent,
+        content_id: u64,
+    ) -> Result<()> {
+        let path = self.component_path(component);
+        let backup = self.root().join("backup").join(&component.id);
+        component.write_package(&backup)?;
+
+        let installed = InstalledComponent {
+            directory_name: component.id.clone(),
+            version: component.version.clone(),
+            release_date: RELEASE_DATE.to_string(),
+            ..InstalledComponent::fixture(&component.name, component.component_type, path.clone())
+        };
+        let url = self.server.file_url(content_id, to);
+        let update = crate::AvailableUpdate::builder(
+            installed,
+            content_id,
+            to.version.clone(),
+            url,
+            RELEASE_DATE.to_string(),
+        )
+        .build();
+        // Never finished, like after a crash.
+        let _ = crate::installer::begin_transaction(&update, Some(&backup))?;
+
+        to.write_package(&path)?;
+        fs::remove_file(path.join("contents/ui/main.qml"))?;
+        Ok(())
+    }
+
+    /// Version recorded in the installed component's metadata.
+    pub fn installed_version(&self, component: &FakeComponent) -> Option<String> {
+        let content =
+            fs::read_to_string(self.component_path(component).join("metadata.json")).ok()?;
+        let json: serde_json::Value = serde_json::from_str(&content).ok()?;
+        json["KPlugin"]["Version"].as_str().map(str::to_string)
+    }
+
+    /// Serves `json` as the advisory feed at [`Self::advisories_url`].
+    pub fn publish_advisories(&self, json: impl Into<String>) {
+        self.server.state.lock().advisories = Some(json.into());
+    }
+
+    /// URL of the advisory feed served by the mock server.
+    pub fn advisories_url(&self) -> String {
+        format!("{}{ADVISORIES_PATH}", self.server.base_url)
+    }
+
+    /// Paths requested from the mock server so far, in order.
+    pub fn requests(&self) -> Vec<String> {
+        self.server.state.lock().requests.clone()
+    }
+
+    /// The last vote received for `content_id`, as the OCS percentage.
+    pub fn vote(&self, content_id: u64) -> Option<u16> {
+        self.server.state.lock().votes.get(&content_id).copied()
+    }
+
+    /// A backend sending store API requests to the mock server.
+    pub fn backend(&self) -> impl HttpBackend + 'static {
+        MockBackend {
+            base_url: format!("{}{OCS_PATH}", self.server.base_url),
+            client: reqwest::blocking::Client::new(),
+        }
+    }
+
+    /// A configuration wired to the sandbox: mock store backend, plain HTTP
+    /// downloads, no prompts, no plasmashell restart or KWin reload, no idle
+    /// inhibition and no advisory feed.
+    pub fn config(&self) -> Config {
+        Config::new()
+            .with_http_backend(self.backend())
+            .with_skip_plasma_detection(true)
+            .with_auto_confirm(true)
+            .with_inhibit_idle(false)
+            .with_reload_kwin(false)
+            .with_require_https(false)
+            .with_temp_dir(self.root().join("tmp"))
+            .with_advisories_url(None)
+    }
+}
+
+impl Drop for Sandbox {
+    fn drop(&mut self) {
+        // SAFETY: still holding SANDBOX_LOCK; see Sandbox::new.
+        unsafe {
+            for (var, value) in &self.saved_env {
+                match value {
+                    Some(value) => std::env::set_var(var, value),
+                    None => std::env::remove_var(var),
+                }
+            }
+        }
+    }
+}
+
+impl std::fmt::Debug for Sandbox {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("Sandbox")
+            .field("root", &self.root.path())
+            .field("server", &self.server.base_url)
+            .finish()
+    }
+}
+
+/// Writes a `kpackagetool6` stand-in that copies packages into `XDG_DATA_HOME`.
+fn write_fake_kpackagetool(bin: &Path) -> Result<()> {
+    let mut roots = String::new();
+    for &ct in ComponentType::all() {
+        if let (Some(kpackage_type), Some(suffix)) = (ct.kpackage_type(), ct.user_suffix()) {
+            roots.push_str(&format!(
+                "  \"{kpackage_type}\") root=\"$XDG_DATA_HOME/{suffix}\" ;;\n"
+            ));
+        }
+    }
+
+    let script = format!(
+        r#"#!/bin/sh
+# kpackagetool6 stand-in written by libplasmoid_updater::test_support
+while [ $# -gt 0 ]; do
+  case "$1" in
+    -t) type="$2"; shift 2 ;;
+    -u|-i|-r|--show) action="$1"; target="$2"; shift 2 ;;
+    --global) echo "global installs are not supported in the sandbox" >&2; exit 1 ;;
+    *) shift ;;
+  esac
+done
+case "$type" in
+{roots}  *) echo "unknown package type: $type" >&2; exit 1 ;;
+esac
+if [ "$action" = "--show" ]; then
+  [ -d "$root/$target" ] && exit 0
+  echo "package $target not found" >&2
+  exit 1
+fi
+if [ "$action" = "-r" ]; then
+  rm -rf "$root/$target"
+  exit 0
+fi
+id=$(basename "$target")
+mkdir -p "$root" && rm -rf "$root/$id" && cp -R "$target" "$root/$id"
+"#
+    );
+
+    let path = bin.join("kpackagetool6");
+    fs::write(&path, script)?;
+    fs::set_permissions(&path, fs::Permissions::from_mode(0o755))?;
+    Ok(())
+}
+
+/// Forwards store API requests to the mock server.
+#[derive(Debug)]
+struct MockBackend {
+    base_url: String,
+    client: reqwest::blocking::Client,
+}
+
+impl HttpBackend for MockBackend {
+    fn get(&self, url: &str) -> Result<HttpResponse> {
+        self.forward(self.client.get(self.mock_url(url)))
+    }
+
+    fn post_form(&self, url: &str, form: &[(&str, &str)]) -> Result<HttpResponse> {
+        let body: Vec<String> = form.iter().map(|(k, v)| format!("{k}={v}")).collect();
+        self.forward(self.client.post(self.mock_url(url)).body(body.join("&")))
+    }
+}
+
+impl MockBackend {
+    /// Maps a store API URL onto the mock server.
+    fn mock_url(&self, url: &str) -> String {
+        // Store API requests go to the mock server; anything else, such as the
+        // advisory feed, is requested as is.
+        match url.strip_prefix(crate::api::DEFAULT_BASE_URL) {
+            Some(path) => format!("{}{path}", self.base_url),
+            None => url.to_string(),
+        }
+    }
+
+    fn forward(&self, request: reqwest::blocking::RequestBuilder) -> Result<HttpResponse> {
+        let response = request.send()?;
+        Ok(HttpResponse {
+            status: response.status().as_u16(),
+            retry_after: None,
+            body: response.text()?,
+            validators: Default::default(),
+        })
+    }
+}
+
+struct Release {
+    component: FakeComponent,
+    archive: Vec<u8>,
+}
+
+#[derive(Default)]
+struct StoreState {
+    releases: HashMap<u64, Release>,
+    advisories: Option<String>,
+    requests: Vec<String>,
+    /// OCS vote (score times ten) received per content ID.
+    votes: HashMap<u64, u16>,
+    /// Downloads still to be answered with HTTP 429, per content ID.
+    throttled: HashMap<u64, usize>,
+}
+
+/// Minimal HTTP/1.1 server serving OCS XML and package archives.
+struct MockServer {
+    base_url: String,
+    state: Arc<Mutex<StoreState>>,
+    stop: Arc<AtomicBool>,
+    thread: Option<JoinHandle<()>>,
+}
+
+impl MockServer {
+    fn start() -> Result<Self> {
+        let listener = TcpListener::bind("127.0.0.1:0")?;
+        let base_url = format!("http://{}", listener.local_addr()?);
+        let state = Arc::new(Mutex::new(StoreState::default()));
+        let stop = Arc::new(AtomicBool::new(false));
+
+        let thread = {
+            let state = Arc::clone(&state);
+            let stop = Arc::clone(&stop);
+            let base_url = base_url.clone();
+            std::thread::spawn(move || {
+                for stream in listener.incoming() {
+                    if stop.load(Ordering::Acquire) {
+                        break;
+                    }
+                    if let Ok(stream) = stream {
+                        let _ = serve(stream, &state, &base_url);
+                    }
+                }
+            })
+        };
+
+        Ok(Self {
+            base_url,
+            state,
+            stop,
+            thread: Some(thread),
+        })
+    }
+
+    fn file_url(&self, content_id: u64, component: &FakeComponent) -> String {
+        format!(
+            "{}/files/{content_id}/{}-{}.tar.gz",
+            self.base_url, component.id, component.version
+        )
+    }
+
+    fn publish(&self, content_id: u64, component: &FakeComponent, archive: Vec<u8>) {
+        self.state.lock().releases.insert(
+            content_id,
+            Release {
+                component: component.clone(),
+                archive,
+            },
+        );
+    }
+}
+
+impl Drop for MockServer {
+    fn drop(&mut self) {
+        self.stop.store(true, Ordering::Release);
+        // Wake the accept loop so it sees the stop flag.
+        let _ = TcpStream::connect(self.base_url.trim_start_matches("http://"));
+        if let Some(thread) = self.thread.take() {
+            let _ = thread.join();
+        }
+    }
+}
+
+fn serve(stream: TcpStream, state: &Mutex<StoreState>, base_url: &str) -> std::io::Result<()> {
+    let mut reader = BufReader::new(stream.try_clone()?);
+    let mut request_line = String::new();
+    reader.read_line(&mut request_line)?;
+    let mut header = String::new();
+    let mut content_length = 0;
+    while reader.read_line(&mut header)? > 2 {
+        if let Some((name, value)) = header.split_once(':')
+            && name.eq_ignore_ascii_case("content-length")
+        {
+            content_length = value.trim().parse().unwrap_or(0);
+        }
+        header.clear();
+    }
+    let mut request_body = vec![0; content_length];
+    reader.read_exact(&mut request_body)?;
+
+    let path = request_line
+        .split_whitespace()
+        .nth(1)
+        .unwrap_or("/")
+        .to_string();
+
+    let (status, body) = {
+        let mut state = state.lock();
+        state.requests.push(path.clone());
+        record_vote(&mut state, &path, &request_body);
+        if take_throttled(&mut state, &path) {
+            ("429 Too Many Requests", Vec::new())
+        } else {
+            route(&state, &path, base_url)
+        }
+    };
+
+    let mut stream = stream;
+    write!(
+        stream,
+        "HTTP/1.1 {status}\r\nContent-Length: {}\r\nConnection: close\r\n\r\n",
+        body.len()
+    )?;
+    stream.write_all(&body)?;
+    stream.flush()
+}
+
+/// Counts down [`StoreState::throttled`] for a package download, returning
+/// `true` while it should still be refused.
+fn take_throttled(state: &mut StoreState, path: &str) -> bool {
+    let Some(remaining) = path
+        .strip_prefix("/files/")
+        .and_then(|rest| rest.split('/').next())
+        .and_then(|id| id.parse::<u64>().ok())
+        .and_then(|id| state.throttled.get_mut(&id))
+        .filter(|remaining| **remaining > 0)
+    else {
+        return false;
+    };
+    *remaining -= 1;
+    true
+}
+
+fn route(state: &StoreState, path: &str, base_url: &str) -> (&'static str, Vec<u8>) {
+    const OK: &str = "200 OK";
+    const NOT_FOUND: &str = "404 Not Found";
+
+    if path == ADVISORIES_PATH {
+        return match &state.advisories {
+            Some(json) => (OK, json.clone().into_bytes()),
+            None => (NOT_FOUND, Vec::new()),
+        };
+    }
+
+    if let Some(rest) = path.strip_prefix("/files/") {
+        let release = rest
+            .split('/')
+            .next()
+            .and_then(|id| id.parse().ok())
+            .and_then(|id: u64| state.releases.get(&id));
+        return match release {
+            Some(release) => (OK, release.archive.clone()),
+            None => (NOT_FOUND, Vec::new()),
+        };
+    }
+
+    if let Some(file) = path.strip_prefix("/previews/") {
+        let known = file
+            .split(['-', '.'])
+            .next()
+            .and_then(|id| id.parse().ok())
+            .is_some_and(|id: u64| state.releases.contains_key(&id));
+        return match known {
+            true => (OK, format!("preview {file}").into_bytes()),
+            false => (NOT_FOUND, Vec::new()),
+        };
+    }
+
+    let Some(api_path) = path.strip_prefix(OCS_PATH) else {
+        return (NOT_FOUND, Vec::new());
+    };
+
+    if let Some(id) = api_path.strip_prefix("/content/data/") {
+        let entries: Vec<_> = id
+            .parse()
+            .ok()
+            .and_then(|id: u64| state.releases.get_key_value(&id))
+            .into_iter()
+            .collect();
+        return (OK, ocs_response(&entries, base_url).into_bytes());
+    }
+
+    if let Some(id) = api_path.strip_prefix("/content/vote/") {
+        let known = id
+            .parse()
+            .is_ok_and(|id: u64| state.releases.contains_key(&id));
+        return if known {
+            (OK, ocs_response(&[], base_url).into_bytes())
+        } else {
+            (NOT_FOUND, Vec::new())
+        };
+    }
+
+    if let Some(query) = api_path.strip_prefix("/content/data?") {
+        let categories: Vec<u16> = query
+            .split('&')
+            .find_map(|pair| pair.strip_prefix("categories="))
+            .map(|list| list.split('x').filter_map(|c| c.parse().ok()).collect())
+            .unwrap_or_default();
+        let entries: Vec<_> = state
+            .releases
+            .iter()
+            .filter(|(_, r)| categories.contains(&r.component.component_type.category_id()))
+            .collect();
+        return (OK, ocs_response(&entries, base_url).into_bytes());
+    }
+
+    (NOT_FOUND, Vec::new())
+}
+
+/// Remembers the score of a `POST /content/vote/<id>` request.
+fn record_vote(state: &mut StoreState, path: &str, body: &[u8]) {
+    let Some(id) = path
+        .strip_prefix(OCS_PATH)
+        .and_then(|p| p.strip_prefix("/content/vote/"))
+        .and_then(|id| id.parse().ok())
+    else {
+        return;
+    };
+    let vote = String::from_utf8_lossy(body)
+        .split('&')
+        .find_map(|pair| pair.strip_prefix("vote=")?.parse().ok());
+    if let Some(vote) = vote {
+        state.votes.insert(id, vote);
+    }
+}
+
+fn ocs_response(entries: &[(&u64, &Release)], base_url: &str) -> String {
+    let mut xml = format!(
+        "<?xml version=\"1.0\"?>\n<ocs>\n <meta>\n  <status>ok</status>\n  <statuscode>100</statuscode>\n  <totalitems>{}</totalitems>\n </meta>\n <data>\n",
+        entries.len()
+    );
+    for &(id, release) in entries {
+        let c = &release.component;
+        let checksum = format!("{:x}", md5::compute(&release.archive));
+        xml.push_str(&format!(
+            "  <content details=\"full\">\n   <id>{id}</id>\n   <name>{}</name>\n   <version>{}</version>\n   <typeid>{}</typeid>\n   <personid>{}</personid>\n   <changed>{RELEASE_DATE}</changed>\n   <downloadlink1>{base_url}/files/{id}/{}-{}.tar.gz</downloadlink1>\n   <download_version1>{}</download_version1>\n   <downloadmd5sum1>{checksum}</downloadmd5sum1>\n   <downloadsize1>{}</downloadsize1>\n   <previewpic1>{base_url}/previews/{id}.png</previewpic1>\n   <smallpreviewpic1>{base_url}/previews/{id}-small.png</smallpreviewpic1>\n  </content>\n",
+            quick_xml::escape::escape(c.name.as_str()),
+            quick_xml::escape::escape(c.version.as_str()),
+            c.component_type.category_id(),
+            quick_xml::escape::escape(c.author.as_str()),
+            c.id,
+            c.version,
+            quick_xml::escape::escape(c.version.as_str()),
+            release.archive.len().div_ceil(1024),
+        ));
+    }
+    xml.push_str(" </data>\n</ocs>\n");
+    xml
+}
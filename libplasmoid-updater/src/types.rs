@@ -1,9 +1,15 @@
 // SPDX-License-Identifier: GPL-3.0-or-later
 
-use std::path::PathBuf;
+use std::{
+    collections::HashSet,
+    path::{Path, PathBuf},
+    sync::{Mutex, OnceLock},
+};
 
 use serde::{Deserialize, Serialize};
 
+use crate::version::UpdateSeverity;
+
 /// KDE Store category IDs for supported component types.
 const CATEGORY_PLASMA_WIDGET: u16 = 705;
 const CATEGORY_WALLPAPER_PLUGIN: u16 = 715;
@@ -18,6 +24,12 @@ const CATEGORY_SPLASH_SCREEN: u16 = 708;
 const CATEGORY_SDDM_THEME: u16 = 101;
 const CATEGORY_ICON_THEME: u16 = 132;
 const CATEGORY_WALLPAPER: u16 = 299;
+const CATEGORY_KRUNNER_PLUGIN: u16 = 628;
+const CATEGORY_CALENDAR_PLUGIN: u16 = 630;
+const CATEGORY_CURSOR_THEME: u16 = 107;
+const CATEGORY_FONT: u16 = 140;
+/// Stand-in for the unknown store category of [`ComponentType::Other`].
+const CATEGORY_UNKNOWN: u16 = 0;
 
 /// Type of KDE Plasma component.
 ///
@@ -28,8 +40,11 @@ const CATEGORY_WALLPAPER: u16 = 299;
 pub enum ComponentType {
     PlasmaWidget,
     WallpaperPlugin,
+    #[serde(alias = "kwin_effect")]
     KWinEffect,
+    #[serde(alias = "kwin_script")]
     KWinScript,
+    #[serde(alias = "kwin_switcher")]
     KWinSwitcher,
     GlobalTheme,
     PlasmaStyle,
@@ -39,9 +54,103 @@ pub enum ComponentType {
     SddmTheme,
     IconTheme,
     Wallpaper,
+    /// A KRunner D-Bus plugin: a `.desktop` file in `krunner/dbusplugins`
+    /// naming the D-Bus service that answers queries.
+    #[serde(alias = "krunner_plugin")]
+    KRunnerPlugin,
+    /// A Plasma calendar event plugin (holidays, astronomical events, ...)
+    /// for the digital clock.
+    CalendarPlugin,
+    CursorTheme,
+    /// A font file in `fonts`, as installed by the font manager's Get New Stuff.
+    Font,
+    /// An entry of a KNewStuff registry this crate does not model (Kate
+    /// themes, Kdenlive titles, ...), named by its registry file, e.g.
+    /// `kdenlive_titles.knsregistry`.
+    ///
+    /// Only discovered with [`Config::with_generic_kns`](crate::Config::with_generic_kns).
+    /// Updates replace the file or directory the registry records.
+    Other(#[serde(deserialize_with = "deserialize_registry_name")] RegistryName),
+}
+
+/// Spelled as an alias so serde's derive doesn't borrow it from the input,
+/// which would tie every deserialized `ComponentType` to `'static` data.
+type RegistryName = &'static str;
+
+/// Returns a `'static` copy of a registry file name for
+/// [`ComponentType::Other`], so the type stays `Copy`. Each name is leaked
+/// once; there are only as many as registry files on disk.
+pub(crate) fn intern_registry_name(name: &str) -> &'static str {
+    static NAMES: OnceLock<Mutex<HashSet<&'static str>>> = OnceLock::new();
+
+    let mut names = NAMES
+        .get_or_init(Default::default)
+        .lock()
+        .unwrap_or_else(|e| e.into_inner());
+    if let Some(&interned) = names.get(name) {
+        return interned;
+    }
+    let interned: &'static str = Box::leak(name.to_owned().into_boxed_str());
+    names.insert(interned);
+    interned
+}
+
+fn deserialize_registry_name<'de, D>(deserializer: D) -> Result<&'static str, D::Error>
+where
+    D: serde::Deserializer<'de>,
+{
+    let name = String::deserialize(deserializer)?;
+    Ok(intern_registry_name(&name))
+}
+
+/// What has to happen after an update for the new version to take effect.
+///
+/// Variants are ordered from least to most disruptive.
+#[derive(
+    Debug, Clone, Copy, Default, PartialEq, Eq, PartialOrd, Ord, Hash, Serialize, Deserialize,
+)]
+#[serde(rename_all = "snake_case")]
+pub enum RestartAction {
+    /// Picked up automatically, or the next time it is selected.
+    #[default]
+    None,
+    /// SDDM reads its theme at the next login; nothing can be restarted.
+    SddmNone,
+    /// `fc-cache -f`, so applications see the new font files.
+    FontCacheRefresh,
+    /// `org.kde.KWin.reconfigure`, see [`Config::reload_kwin`](crate::Config::reload_kwin).
+    KWinReconfigure,
+    /// Re-apply the global theme, Plasma style or color scheme if the updated
+    /// one is in use, see [`Config::reapply_active_themes`](crate::Config::reapply_active_themes).
+    LookAndFeelReapply,
+    /// Restart plasmashell.
+    PlasmashellRestart,
 }
 
 impl ComponentType {
+    /// Returns what has to be restarted or re-applied after updating a
+    /// component of this type.
+    pub const fn restart_action(self) -> RestartAction {
+        match self {
+            Self::PlasmaWidget | Self::WallpaperPlugin => RestartAction::PlasmashellRestart,
+            Self::KWinEffect | Self::KWinScript | Self::KWinSwitcher | Self::AuroraeDecoration => {
+                RestartAction::KWinReconfigure
+            }
+            Self::GlobalTheme | Self::PlasmaStyle | Self::ColorScheme => {
+                RestartAction::LookAndFeelReapply
+            }
+            Self::SddmTheme => RestartAction::SddmNone,
+            Self::Font => RestartAction::FontCacheRefresh,
+            Self::CalendarPlugin => RestartAction::PlasmashellRestart,
+            Self::SplashScreen
+            | Self::IconTheme
+            | Self::Wallpaper
+            | Self::KRunnerPlugin
+            | Self::CursorTheme
+            | Self::Other(_) => RestartAction::None,
+        }
+    }
+
     pub(crate) const fn category_id(self) -> u16 {
         match self {
             Self::PlasmaWidget => CATEGORY_PLASMA_WIDGET,
@@ -57,6 +166,11 @@ impl ComponentType {
             Self::SddmTheme => CATEGORY_SDDM_THEME,
             Self::IconTheme => CATEGORY_ICON_THEME,
             Self::Wallpaper => CATEGORY_WALLPAPER,
+            Self::KRunnerPlugin => CATEGORY_KRUNNER_PLUGIN,
+            Self::CalendarPlugin => CATEGORY_CALENDAR_PLUGIN,
+            Self::CursorTheme => CATEGORY_CURSOR_THEME,
+            Self::Font => CATEGORY_FONT,
+            Self::Other(_) => CATEGORY_UNKNOWN,
         }
     }
 
@@ -67,6 +181,9 @@ impl ComponentType {
     /// specific subcategory IDs (706 "Applets", 708 "Clocks", 710 "Monitoring",
     /// etc.). This method accounts for those parent-child relationships.
     pub(crate) const fn matches_type_id(self, type_id: u16) -> bool {
+        if matches!(self, Self::Other(_)) {
+            return false;
+        }
         if self.category_id() == type_id {
             return true;
         }
@@ -108,7 +225,17 @@ impl ComponentType {
     /// Returns true if this type uses registry-based discovery only
     /// (no metadata files on disk).
     pub(crate) const fn registry_only(self) -> bool {
-        matches!(self, Self::IconTheme | Self::Wallpaper | Self::ColorScheme)
+        matches!(
+            self,
+            Self::IconTheme
+                | Self::Wallpaper
+                | Self::ColorScheme
+                | Self::KRunnerPlugin
+                | Self::CalendarPlugin
+                | Self::CursorTheme
+                | Self::Font
+                | Self::Other(_)
+        )
     }
 
     /// Returns all component types that share the same filesystem path.
@@ -116,6 +243,7 @@ impl ComponentType {
     /// `GlobalTheme` and `SplashScreen` both use `plasma/look-and-feel`.
     /// During discovery, components in shared directories need to be
     /// checked against all possible types to assign the correct `ComponentType`.
+    /// Empty for [`Other`](Self::Other), which has no directory of its own.
     pub(crate) fn shared_path_types(self) -> &'static [ComponentType] {
         match self {
             Self::GlobalTheme | Self::SplashScreen => &[Self::GlobalTheme, Self::SplashScreen],
@@ -130,26 +258,45 @@ impl ComponentType {
             Self::SddmTheme => &[Self::SddmTheme],
             Self::IconTheme => &[Self::IconTheme],
             Self::Wallpaper => &[Self::Wallpaper],
+            Self::KRunnerPlugin => &[Self::KRunnerPlugin],
+            Self::CalendarPlugin => &[Self::CalendarPlugin],
+            Self::CursorTheme => &[Self::CursorTheme],
+            Self::Font => &[Self::Font],
+            Self::Other(_) => &[],
         }
     }
 
     // -- Filesystem paths --
 
-    /// Returns the user-local data directory suffix, or `None` for system-only types (e.g., SDDM).
+    /// Returns the user-local data directory suffix, or `None` for system-only
+    /// types (e.g., SDDM) and types without a directory of their own.
     pub(crate) const fn user_suffix(self) -> Option<&'static str> {
         match self {
-            Self::PlasmaWidget => Some("plasma/plasmoids"),
-            Self::WallpaperPlugin => Some("plasma/wallpapers"),
-            Self::KWinEffect => Some("kwin/effects"),
-            Self::KWinScript => Some("kwin/scripts"),
-            Self::KWinSwitcher => Some("kwin/tabbox"),
-            Self::GlobalTheme | Self::SplashScreen => Some("plasma/look-and-feel"),
-            Self::PlasmaStyle => Some("plasma/desktoptheme"),
-            Self::AuroraeDecoration => Some("aurorae/themes"),
-            Self::ColorScheme => Some("color-schemes"),
-            Self::SddmTheme => None,
-            Self::IconTheme => Some("icons"),
-            Self::Wallpaper => Some("wallpapers"),
+            Self::SddmTheme | Self::Other(_) => None,
+            _ => Some(self.data_suffix()),
+        }
+    }
+
+    /// Returns the path of this component type below a data directory.
+    const fn data_suffix(self) -> &'static str {
+        match self {
+            Self::PlasmaWidget => "plasma/plasmoids",
+            Self::WallpaperPlugin => "plasma/wallpapers",
+            Self::KWinEffect => "kwin/effects",
+            Self::KWinScript => "kwin/scripts",
+            Self::KWinSwitcher => "kwin/tabbox",
+            Self::GlobalTheme | Self::SplashScreen => "plasma/look-and-feel",
+            Self::PlasmaStyle => "plasma/desktoptheme",
+            Self::AuroraeDecoration => "aurorae/themes",
+            Self::ColorScheme => "color-schemes",
+            Self::SddmTheme => "sddm/themes",
+            Self::IconTheme | Self::CursorTheme => "icons",
+            Self::Wallpaper => "wallpapers",
+            Self::KRunnerPlugin => "krunner/dbusplugins",
+            Self::CalendarPlugin => "plasma/calendarplugins",
+            Self::Font => "fonts",
+            // Entries record their own paths, anywhere below the data dirs.
+            Self::Other(_) => "",
         }
     }
 
@@ -161,22 +308,20 @@ impl ComponentType {
         }
     }
 
-    /// Returns the system-wide installation path for this component type.
+    /// Returns the system-wide installation path for this component type,
+    /// under `/usr/share`.
     pub fn system_path(self) -> PathBuf {
-        PathBuf::from(match self {
-            Self::PlasmaWidget => "/usr/share/plasma/plasmoids",
-            Self::WallpaperPlugin => "/usr/share/plasma/wallpapers",
-            Self::KWinEffect => "/usr/share/kwin/effects",
-            Self::KWinScript => "/usr/share/kwin/scripts",
-            Self::KWinSwitcher => "/usr/share/kwin/tabbox",
-            Self::GlobalTheme | Self::SplashScreen => "/usr/share/plasma/look-and-feel",
-            Self::PlasmaStyle => "/usr/share/plasma/desktoptheme",
-            Self::AuroraeDecoration => "/usr/share/aurorae/themes",
-            Self::ColorScheme => "/usr/share/color-schemes",
-            Self::SddmTheme => "/usr/share/sddm/themes",
-            Self::IconTheme => "/usr/share/icons",
-            Self::Wallpaper => "/usr/share/wallpapers",
-        })
+        Path::new(crate::paths::SYSTEM_DATA_DIR).join(self.data_suffix())
+    }
+
+    /// Returns every system-wide location of this component type, one per
+    /// `XDG_DATA_DIRS` entry in precedence order, always including
+    /// [`system_path()`](Self::system_path).
+    pub fn system_paths(self) -> Vec<PathBuf> {
+        crate::paths::system_data_dirs()
+            .into_iter()
+            .map(|dir| dir.join(self.data_suffix()))
+            .collect()
     }
 
     /// Returns the backup subdirectory name for this component type.
@@ -195,6 +340,12 @@ impl ComponentType {
             Self::SddmTheme => "sddm-themes",
             Self::IconTheme => "icons",
             Self::Wallpaper => "wallpapers",
+            Self::KRunnerPlugin => "krunner-dbusplugins",
+            Self::CalendarPlugin => "plasma-calendarplugins",
+            Self::CursorTheme => "cursors",
+            Self::Font => "fonts",
+            // The registry name holds a dot, so it can't clash with the above.
+            Self::Other(registry) => registry,
         }
     }
 
@@ -215,11 +366,17 @@ impl ComponentType {
             Self::SddmTheme => Some("sddmtheme.knsregistry"),
             Self::IconTheme => Some("icons.knsregistry"),
             Self::Wallpaper => Some("wallpaper.knsregistry"),
+            Self::KRunnerPlugin => Some("krunner.knsregistry"),
+            Self::CalendarPlugin => Some("plasma_calendar_plugins.knsregistry"),
+            Self::CursorTheme => Some("xcursor.knsregistry"),
+            Self::Font => Some("fonts.knsregistry"),
+            Self::Other(registry) => Some(registry),
         }
     }
 
     // -- Enumeration --
 
+    /// Returns every modelled type, i.e. all but [`Other`](Self::Other).
     pub const fn all() -> &'static [ComponentType] {
         &[
             Self::PlasmaWidget,
@@ -235,9 +392,14 @@ impl ComponentType {
             Self::SddmTheme,
             Self::IconTheme,
             Self::Wallpaper,
+            Self::KRunnerPlugin,
+            Self::CalendarPlugin,
+            Self::CursorTheme,
+            Self::Font,
         ]
     }
 
+    /// Like [`all()`](Self::all), without the system-only types.
     pub const fn all_user() -> &'static [ComponentType] {
         &[
             Self::PlasmaWidget,
@@ -252,6 +414,10 @@ impl ComponentType {
             Self::SplashScreen,
             Self::IconTheme,
             Self::Wallpaper,
+            Self::KRunnerPlugin,
+            Self::CalendarPlugin,
+            Self::CursorTheme,
+            Self::Font,
         ]
     }
 }
@@ -272,10 +438,32 @@ impl std::fmt::Display for ComponentType {
             Self::SddmTheme => write!(f, "SDDM Theme"),
             Self::IconTheme => write!(f, "Icon Theme"),
             Self::Wallpaper => write!(f, "Wallpaper"),
+            Self::KRunnerPlugin => write!(f, "KRunner Plugin"),
+            Self::CalendarPlugin => write!(f, "Calendar Plugin"),
+            Self::CursorTheme => write!(f, "Cursor Theme"),
+            Self::Font => write!(f, "Font"),
+            Self::Other(registry) => {
+                let name = registry.strip_suffix(".knsregistry").unwrap_or(registry);
+                write!(f, "Other ({name})")
+            }
         }
     }
 }
 
+impl std::str::FromStr for ComponentType {
+    type Err = crate::Error;
+
+    /// Parses the snake_case name used in config files, e.g. `plasma_widget`.
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        use serde::de::IntoDeserializer;
+
+        let deserializer: serde::de::value::StrDeserializer<'_, serde::de::value::Error> =
+            s.into_deserializer();
+        Self::deserialize(deserializer)
+            .map_err(|_| crate::Error::other(format!("unknown component type '{s}'")))
+    }
+}
+
 // -- Internal types --
 
 /// A KDE component installed on the local system.
@@ -289,6 +477,174 @@ pub struct InstalledComponent {
     pub path: PathBuf,
     pub is_system: bool,
     pub release_date: String,
+    /// `true` if a distro package (rpm, deb, pacman) owns the component's
+    /// files. Only detected for system components.
+    #[serde(default)]
+    pub managed_by_distro: bool,
+    /// Descriptive fields from the package metadata, serialized inline.
+    #[serde(default, flatten)]
+    pub metadata: ComponentMetadata,
+    /// For a user component, the system-wide copy of the same package it
+    /// hides from Plasma. See [`find_duplicates()`](crate::find_duplicates).
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub shadowed: Option<PathBuf>,
+    /// `true` if the widget or wallpaper plugin is placed on the desktop or a
+    /// panel, according to the user's Plasma layout. Updating it may glitch
+    /// the layout until plasmashell restarts.
+    #[serde(default)]
+    pub in_use: bool,
+}
+
+impl InstalledComponent {
+    /// Bytes the component occupies on disk, following no symlinks.
+    ///
+    /// Walks the package directory, so it is not free for large themes.
+    pub fn disk_size(&self) -> u64 {
+        crate::du::dir_size(&self.path)
+    }
+}
+
+#[cfg(any(test, feature = "test-support"))]
+impl InstalledComponent {
+    /// A user component at `path` for tests, version `1.0`, with `name` as
+    /// its directory name too. Other fields are set with struct update syntax.
+    pub(crate) fn fixture(
+        name: &str,
+        component_type: ComponentType,
+        path: impl Into<PathBuf>,
+    ) -> Self {
+        Self {
+            name: name.to_string(),
+            directory_name: name.to_string(),
+            version: "1.0".to_string(),
+            component_type,
+            path: path.into(),
+            is_system: false,
+            release_date: String::new(),
+            managed_by_distro: false,
+            metadata: ComponentMetadata::default(),
+            shadowed: None,
+            in_use: false,
+        }
+    }
+}
+
+/// A directory among the installed packages that looks like a package but
+/// whose `metadata.json` or `metadata.desktop` cannot be read.
+///
+/// Such packages are never checked or updated. Returned by
+/// [`find_unrecognized()`](crate::find_unrecognized) and reported by
+/// [`check()`](crate::check) as [`DiagnosticKind::InvalidMetadata`].
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct UnrecognizedComponent {
+    /// The package as far as it is known: named after its directory, with
+    /// the version `unknown` and a type guessed from where it is installed.
+    pub component: InstalledComponent,
+    /// Why the metadata could not be read.
+    pub reason: String,
+}
+
+impl UnrecognizedComponent {
+    /// The component as a check diagnostic.
+    pub fn diagnostic(&self) -> Diagnostic {
+        Diagnostic::new(self.component.name.clone(), self.reason.clone())
+            .with_kind(DiagnosticKind::InvalidMetadata)
+            .with_versions(Some(self.component.version.clone()), None)
+    }
+}
+
+/// Which installed components [`list_installed()`](crate::list_installed)
+/// returns, and in which order.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct InstalledQuery {
+    pub status: InstalledStatus,
+    pub sort: InstalledSort,
+}
+
+/// Filters installed components by their state on the KDE Store.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum InstalledStatus {
+    /// Every installed component, without contacting the store.
+    #[default]
+    All,
+    /// Components with an update available.
+    Outdated,
+    /// Components that match no store entry, including deleted ones.
+    Orphaned,
+}
+
+/// Order of [`list_installed()`](crate::list_installed) results.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum InstalledSort {
+    /// By name, case-insensitively.
+    #[default]
+    Name,
+    /// By component type, then name.
+    Type,
+    /// By installed version, oldest first.
+    Version,
+    /// By [`InstalledComponent::disk_size`], largest first.
+    Size,
+}
+
+impl std::str::FromStr for InstalledSort {
+    type Err = crate::Error;
+
+    /// Parses `name`, `type`, `version` or `size`.
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        use serde::de::IntoDeserializer;
+
+        let deserializer: serde::de::value::StrDeserializer<'_, serde::de::value::Error> =
+            s.into_deserializer();
+        Self::deserialize(deserializer)
+            .map_err(|_| crate::Error::other(format!("unknown sort order '{s}'")))
+    }
+}
+
+impl InstalledSort {
+    /// Sorts `components` in this order.
+    pub fn sort(self, components: &mut [InstalledComponent]) {
+        let by_name = |a: &InstalledComponent, b: &InstalledComponent| {
+            a.name.to_lowercase().cmp(&b.name.to_lowercase())
+        };
+        match self {
+            Self::Name => components.sort_by(by_name),
+            Self::Type => components.sort_by(|a, b| {
+                a.component_type
+                    .to_string()
+                    .cmp(&b.component_type.to_string())
+                    .then_with(|| by_name(a, b))
+            }),
+            Self::Version => components.sort_by(|a, b| {
+                crate::version::compare(&a.version, &b.version).then_with(|| by_name(a, b))
+            }),
+            Self::Size => components.sort_by_cached_key(|c| std::cmp::Reverse(c.disk_size())),
+        }
+    }
+}
+
+/// Descriptive metadata of an installed package, read from its
+/// `metadata.json` or `metadata.desktop` during discovery.
+///
+/// Fields missing from the package are `None`; components without package
+/// metadata (e.g. color schemes) have none at all.
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct ComponentMetadata {
+    /// Author names, comma-separated.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub author: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub description: Option<String>,
+    /// An icon theme name, or the absolute path of an icon file shipped in
+    /// the package.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub icon: Option<String>,
+    /// SPDX-style license identifier, e.g. `GPL-2.0+`.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub license: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub website: Option<String>,
 }
 
 /// An available update for an installed component, with download metadata.
@@ -304,6 +660,117 @@ pub struct AvailableUpdate {
     pub checksum: Option<String>,
     #[serde(skip_serializing_if = "Option::is_none")]
     pub download_size: Option<u64>,
+    /// Every download the store entry offers, including the selected one.
+    /// Empty when there is only one.
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub alternatives: Vec<DownloadVariant>,
+    /// Changelog of the store entry as published; may contain HTML and is
+    /// often empty.
+    #[serde(default, skip_serializing_if = "String::is_empty")]
+    pub changelog: String,
+    /// Entries of [`changelog`](Self::changelog) for the releases after the
+    /// installed version, as plain text. Empty if the changelog is empty or
+    /// only covers older releases.
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub changes_since_installed: Vec<String>,
+    /// What has to happen after installing the update: the
+    /// [type default](ComponentType::restart_action), overridden by
+    /// [`Config::restart_required`](crate::Config::restart_required) and
+    /// [`Config::no_restart`](crate::Config::no_restart).
+    #[serde(default)]
+    pub restart_action: RestartAction,
+    /// How large a step the update is, from the installed and the store
+    /// version; see [`Config::auto_apply`](crate::Config::auto_apply).
+    #[serde(default)]
+    pub severity: UpdateSeverity,
+    /// Store account that publishes the entry; may be empty.
+    #[serde(default, skip_serializing_if = "String::is_empty")]
+    pub author: String,
+    /// The author recorded for the store entry when it was first seen, if
+    /// [`author`](Self::author) is someone else: the entry changed hands,
+    /// see [`Config::confirm_author_changes`](crate::Config::confirm_author_changes).
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub previous_author: Option<String>,
+    /// For a global theme, the components its defaults apply along with it,
+    /// e.g. its Plasma style, color scheme and icon theme, which the store
+    /// publishes as separate entries. See [`Self::companion_updates`].
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub suggested_companions: Vec<Companion>,
+}
+
+/// A component a global theme refers to in its defaults, see
+/// [`AvailableUpdate::suggested_companions`].
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Companion {
+    pub component_type: ComponentType,
+    /// Name the theme uses for it: the package directory, or the file stem
+    /// for color schemes.
+    pub name: String,
+    /// `true` if it is installed for the user or system-wide.
+    pub installed: bool,
+}
+
+impl Companion {
+    /// Returns `true` if `component` is this companion.
+    pub fn matches(&self, component: &InstalledComponent) -> bool {
+        component.component_type == self.component_type
+            && component.directory_name == self.directory_name()
+    }
+
+    /// Directory or file name of the companion below its type's
+    /// installation path.
+    pub(crate) fn directory_name(&self) -> String {
+        match self.component_type {
+            ComponentType::ColorScheme => format!("{}.colors", self.name),
+            _ => self.name.clone(),
+        }
+    }
+}
+
+/// One of several downloads published for a store entry, e.g. separate
+/// Plasma 5 and Plasma 6 builds.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct DownloadVariant {
+    /// File name shown on the store; may be empty.
+    pub name: String,
+    pub version: String,
+    pub url: String,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub checksum: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub download_size: Option<u64>,
+}
+
+impl DownloadVariant {
+    /// Name to show for the variant: the store file name, or the last URL
+    /// path segment if the store gave none.
+    pub fn label(&self) -> &str {
+        if !self.name.is_empty() {
+            return &self.name;
+        }
+        self.url.rsplit('/').next().unwrap_or(&self.url)
+    }
+
+    /// Returns `true` if `selector` is a case-insensitive substring of the
+    /// label or version, or the exact URL.
+    pub fn matches(&self, selector: &str) -> bool {
+        let needle = selector.to_lowercase();
+        self.url == selector
+            || self.label().to_lowercase().contains(&needle)
+            || self.version.to_lowercase().contains(&needle)
+    }
+}
+
+impl From<&DownloadLink> for DownloadVariant {
+    fn from(link: &DownloadLink) -> Self {
+        Self {
+            name: link.name.clone(),
+            version: link.version.clone(),
+            url: link.url.clone(),
+            checksum: link.checksum.clone(),
+            download_size: link.size_kb.map(|kb| kb * 1024),
+        }
+    }
 }
 
 /// Builder for constructing [`AvailableUpdate`] instances with optional fields.
@@ -315,6 +782,9 @@ pub(crate) struct AvailableUpdateBuilder {
     release_date: String,
     checksum: Option<String>,
     download_size: Option<u64>,
+    alternatives: Vec<DownloadVariant>,
+    changelog: String,
+    author: String,
 }
 
 impl AvailableUpdateBuilder {
@@ -328,8 +798,30 @@ impl AvailableUpdateBuilder {
         self
     }
 
+    pub(crate) fn alternatives(mut self, alternatives: Vec<DownloadVariant>) -> Self {
+        self.alternatives = alternatives;
+        self
+    }
+
+    pub(crate) fn changelog(mut self, changelog: String) -> Self {
+        self.changelog = changelog;
+        self
+    }
+
+    pub(crate) fn author(mut self, author: String) -> Self {
+        self.author = author;
+        self
+    }
+
     pub(crate) fn build(self) -> AvailableUpdate {
         let store_url = format!("https://store.kde.org/p/{}", self.content_id);
+        let restart_action = self.installed.component_type.restart_action();
+        let changes_since_installed = crate::changelog::changes_since(
+            &self.changelog,
+            &self.installed.version,
+            &self.latest_version,
+        );
+        let severity = crate::version::severity(&self.installed.version, &self.latest_version);
         AvailableUpdate {
             installed: self.installed,
             content_id: self.content_id,
@@ -339,6 +831,14 @@ impl AvailableUpdateBuilder {
             release_date: self.release_date,
             checksum: self.checksum,
             download_size: self.download_size,
+            alternatives: self.alternatives,
+            changelog: self.changelog,
+            changes_since_installed,
+            restart_action,
+            severity,
+            author: self.author,
+            previous_author: None,
+            suggested_companions: Vec::new(),
         }
     }
 }
@@ -359,12 +859,52 @@ impl AvailableUpdate {
             release_date,
             checksum: None,
             download_size: None,
+            alternatives: Vec::new(),
+            changelog: String::new(),
+            author: String::new(),
         }
     }
+
+    /// Returns the updates in `updates` to this update's
+    /// [`suggested_companions`](Self::suggested_companions), e.g. to offer
+    /// them along with a global theme.
+    pub fn companion_updates<'a>(
+        &self,
+        updates: &'a [AvailableUpdate],
+    ) -> Vec<&'a AvailableUpdate> {
+        updates
+            .iter()
+            .filter(|u| {
+                self.suggested_companions
+                    .iter()
+                    .any(|c| c.matches(&u.installed))
+            })
+            .collect()
+    }
+
+    /// Returns the alternative currently selected for download, if any.
+    pub fn selected_variant(&self) -> Option<&DownloadVariant> {
+        self.alternatives
+            .iter()
+            .find(|v| v.url == self.download_url)
+    }
+
+    /// Switches the download to the first alternative matching `selector`
+    /// (see [`DownloadVariant::matches`]). Returns `false` and leaves the
+    /// update unchanged if none matches.
+    pub fn choose_variant(&mut self, selector: &str) -> bool {
+        let Some(variant) = self.alternatives.iter().find(|v| v.matches(selector)) else {
+            return false;
+        };
+        self.download_url = variant.url.clone();
+        self.checksum = variant.checksum.clone();
+        self.download_size = variant.download_size;
+        true
+    }
 }
 
 /// An entry from the KDE Store API representing a published component.
-#[derive(Debug, Clone)]
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
 pub(crate) struct StoreEntry {
     pub id: u64,
     pub name: String,
@@ -372,12 +912,26 @@ pub(crate) struct StoreEntry {
     pub type_id: u16,
     pub download_links: Vec<DownloadLink>,
     pub changed_date: String,
+    /// Release notes published with the entry; may be empty.
+    #[serde(default)]
+    pub changelog: String,
+    /// Store account that publishes the entry; may be empty.
+    #[serde(default)]
+    pub author: String,
+    /// First screenshot of the entry; may be empty.
+    #[serde(default)]
+    pub preview_url: String,
+    /// Thumbnail of [`Self::preview_url`]; may be empty.
+    #[serde(default)]
+    pub thumbnail_url: String,
 }
 
 /// A download link for a store entry, with optional checksum and size.
-#[derive(Debug, Clone)]
+#[derive(Debug, Clone, Serialize, Deserialize)]
 pub(crate) struct DownloadLink {
     pub url: String,
+    /// File name shown on the store, e.g. `clock-plasma6.tar.gz`.
+    pub name: String,
     pub version: String,
     pub checksum: Option<String>,
     pub size_kb: Option<u64>,
@@ -393,6 +947,8 @@ pub(crate) struct PackageMetadata {
 /// Plugin metadata from the `KPlugin` section of `metadata.json`.
 #[derive(Debug, Clone, Default, Deserialize, Serialize)]
 pub(crate) struct KPluginInfo {
+    #[serde(rename = "Id")]
+    pub id: Option<String>,
     #[serde(rename = "Name")]
     pub name: Option<String>,
     #[serde(rename = "Version")]
@@ -401,6 +957,19 @@ pub(crate) struct KPluginInfo {
     pub description: Option<String>,
     #[serde(rename = "Icon")]
     pub icon: Option<String>,
+    #[serde(rename = "Website")]
+    pub website: Option<String>,
+    #[serde(rename = "Authors", default)]
+    pub authors: Vec<KPluginAuthor>,
+    #[serde(rename = "License")]
+    pub license: Option<String>,
+}
+
+/// An entry of `KPlugin.Authors` in `metadata.json`.
+#[derive(Debug, Clone, Default, Deserialize, Serialize)]
+pub(crate) struct KPluginAuthor {
+    #[serde(rename = "Name")]
+    pub name: Option<String>,
 }
 
 impl PackageMetadata {
@@ -408,8 +977,84 @@ impl PackageMetadata {
         self.kplugin.as_ref()?.name.as_deref()
     }
 
+    /// Plugin ID (`KPlugin.Id`, or `X-KDE-PluginInfo-Name` in
+    /// `metadata.desktop`).
+    pub(crate) fn id(&self) -> Option<&str> {
+        self.kplugin.as_ref()?.id.as_deref()
+    }
+
     pub(crate) fn version(&self) -> Option<&str> {
-        self.kplugin.as_ref()?.version.as_deref()
+        self.kplugin
+            .as_ref()?
+            .version
+            .as_deref()
+            .filter(|v| !v.trim().is_empty())
+    }
+
+    pub(crate) fn website(&self) -> Option<&str> {
+        self.kplugin.as_ref()?.website.as_deref()
+    }
+
+    /// Descriptive fields for [`InstalledComponent::metadata`]. An icon naming
+    /// a file in `package_dir` is resolved to its absolute path.
+    pub(crate) fn details(&self, package_dir: &Path) -> ComponentMetadata {
+        let Some(info) = &self.kplugin else {
+            return ComponentMetadata::default();
+        };
+        let non_empty = |value: &Option<String>| {
+            value
+                .as_deref()
+                .map(str::trim)
+                .filter(|v| !v.is_empty())
+                .map(str::to_string)
+        };
+
+        let authors: Vec<&str> = info
+            .authors
+            .iter()
+            .filter_map(|a| a.name.as_deref().map(str::trim))
+            .filter(|name| !name.is_empty())
+            .collect();
+        let icon = non_empty(&info.icon).map(|icon| {
+            let file = package_dir.join(&icon);
+            if file.is_file() {
+                file.display().to_string()
+            } else {
+                icon
+            }
+        });
+
+        ComponentMetadata {
+            author: (!authors.is_empty()).then(|| authors.join(", ")),
+            description: non_empty(&info.description),
+            icon,
+            license: non_empty(&info.license),
+            website: non_empty(&info.website),
+        }
+    }
+}
+
+/// A local account whose per-user components are in scope for an admin run.
+///
+/// Returned by [`find_users()`](crate::find_users). Each user's components live
+/// under their own home, so work on them is done by running a command as that
+/// user via [`UserScope::command`].
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct UserScope {
+    /// Login name.
+    pub name: String,
+    /// Numeric user ID.
+    pub uid: u32,
+    /// Home directory from the passwd database.
+    #[serde(with = "pathbuf_serde")]
+    pub home: PathBuf,
+}
+
+impl UserScope {
+    /// Builds a command that runs `program` as this user with their own home
+    /// and environment (`sudo -u <name> -H`).
+    pub fn command(&self, program: impl AsRef<std::ffi::OsStr>) -> std::process::Command {
+        crate::installer::privilege::user_command(self, program.as_ref())
     }
 }
 
@@ -421,6 +1066,8 @@ impl PackageMetadata {
 pub struct Diagnostic {
     pub name: String,
     pub reason: String,
+    #[serde(default)]
+    pub kind: DiagnosticKind,
     #[serde(skip_serializing_if = "Option::is_none")]
     pub installed_version: Option<String>,
     #[serde(skip_serializing_if = "Option::is_none")]
@@ -434,6 +1081,7 @@ impl Diagnostic {
         Self {
             name,
             reason,
+            kind: DiagnosticKind::Unresolved,
             installed_version: None,
             available_version: None,
             content_id: None,
@@ -454,6 +1102,58 @@ impl Diagnostic {
         self.content_id = Some(id);
         self
     }
+
+    pub(crate) fn with_kind(mut self, kind: DiagnosticKind) -> Self {
+        self.kind = kind;
+        self
+    }
+}
+
+/// Why a component could not be checked.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum DiagnosticKind {
+    /// No KDE Store entry could be matched to the component.
+    #[default]
+    Unresolved,
+    /// The component's store entry was deleted; it can never update again.
+    StoreEntryGone,
+    /// A store entry matched, but the update could not be evaluated, e.g.
+    /// it offers no download.
+    CheckFailed,
+    /// The package's metadata is missing or unreadable, so the component
+    /// cannot be identified. See [`UnrecognizedComponent`].
+    InvalidMetadata,
+}
+
+/// An update held back because an advisory lists its version as broken.
+///
+/// Held updates are reported by [`check()`](crate::check) but never installed.
+/// See [`Config::advisories_url`](crate::Config::advisories_url).
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct HeldUpdate {
+    pub update: AvailableUpdate,
+    /// Why the advisory marks the version as broken.
+    pub reason: String,
+}
+
+/// Catalog pages fetched during a check, and how many of them failed.
+///
+/// Components listed on a failed page look unresolved, so an incomplete
+/// catalog explains otherwise puzzling [`Diagnostic`]s.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct CatalogFetchReport {
+    /// Catalog pages requested, across all component types.
+    pub pages: u32,
+    /// Pages that failed after retries; their entries are missing.
+    pub failed_pages: u32,
+}
+
+impl CatalogFetchReport {
+    /// Returns `true` if every requested page arrived.
+    pub fn is_complete(&self) -> bool {
+        self.failed_pages == 0
+    }
 }
 
 /// Internal result of checking for available updates, including diagnostics.
@@ -462,6 +1162,9 @@ pub(crate) struct UpdateCheckResult {
     pub updates: Vec<AvailableUpdate>,
     pub unresolved: Vec<Diagnostic>,
     pub check_failures: Vec<Diagnostic>,
+    pub held: Vec<HeldUpdate>,
+    pub shadowed: Vec<InstalledComponent>,
+    pub catalog: CatalogFetchReport,
 }
 
 impl UpdateCheckResult {
@@ -482,6 +1185,80 @@ impl UpdateCheckResult {
 mod tests {
     use super::*;
 
+    #[test]
+    fn component_type_parses_config_names() {
+        assert_eq!(
+            "plasma_widget".parse::<ComponentType>().unwrap(),
+            ComponentType::PlasmaWidget
+        );
+        assert_eq!(
+            "kwin_script".parse::<ComponentType>().unwrap(),
+            ComponentType::KWinScript
+        );
+        assert!("Plasma Widget".parse::<ComponentType>().is_err());
+    }
+
+    #[test]
+    fn restart_actions_are_minimal_per_type() {
+        assert_eq!(
+            ComponentType::KWinScript.restart_action(),
+            RestartAction::KWinReconfigure
+        );
+        assert_eq!(
+            ComponentType::GlobalTheme.restart_action(),
+            RestartAction::LookAndFeelReapply
+        );
+        assert_eq!(
+            ComponentType::SddmTheme.restart_action(),
+            RestartAction::SddmNone
+        );
+        assert_eq!(
+            ComponentType::IconTheme.restart_action(),
+            RestartAction::None
+        );
+        assert!(RestartAction::PlasmashellRestart > RestartAction::KWinReconfigure);
+    }
+
+    #[test]
+    fn choose_variant_switches_download() {
+        let variant = |name: &str, size| DownloadVariant {
+            name: name.to_string(),
+            version: "2.0".to_string(),
+            url: format!("https://example.com/{name}"),
+            checksum: None,
+            download_size: Some(size),
+        };
+        let installed = InstalledComponent {
+            directory_name: "org.example.clock".to_string(),
+            ..InstalledComponent::fixture("Clock", ComponentType::PlasmaWidget, "")
+        };
+        let mut update = AvailableUpdate::builder(
+            installed,
+            1,
+            "2.0".to_string(),
+            "https://example.com/clock-plasma6.tar.gz".to_string(),
+            String::new(),
+        )
+        .alternatives(vec![
+            variant("clock-plasma6.tar.gz", 10),
+            variant("clock-plasma5.tar.gz", 20),
+        ])
+        .build();
+
+        assert!(!update.choose_variant("qt7"));
+        assert_eq!(
+            update.selected_variant().unwrap().label(),
+            "clock-plasma6.tar.gz"
+        );
+
+        assert!(update.choose_variant("Plasma5"));
+        assert_eq!(
+            update.download_url,
+            "https://example.com/clock-plasma5.tar.gz"
+        );
+        assert_eq!(update.download_size, Some(20));
+    }
+
     #[test]
     fn shared_path_types_returns_both_for_global_theme() {
         let types = ComponentType::GlobalTheme.shared_path_types();
@@ -524,6 +1301,65 @@ mod tests {
             &[ComponentType::IconTheme]
         );
     }
+
+    #[test]
+    fn installed_sort_orders_versions_semantically_and_sizes_descending() {
+        let dir = tempfile::tempdir().unwrap();
+        let component = |name: &str, version: &str, bytes: usize| {
+            let path = dir.path().join(name);
+            std::fs::create_dir_all(&path).unwrap();
+            std::fs::write(path.join("data"), vec![0u8; bytes]).unwrap();
+            InstalledComponent {
+                version: version.to_string(),
+                ..InstalledComponent::fixture(name, ComponentType::PlasmaWidget, path)
+            }
+        };
+        let mut components = vec![
+            component("b", "1.10", 10),
+            component("a", "1.9", 300),
+            component("C", "v2.0", 20),
+        ];
+
+        InstalledSort::Version.sort(&mut components);
+        let names: Vec<&str> = components.iter().map(|c| c.name.as_str()).collect();
+        assert_eq!(names, ["a", "b", "C"]);
+
+        InstalledSort::Size.sort(&mut components);
+        assert_eq!(components[0].name, "a");
+        assert_eq!(components[0].disk_size(), 300);
+
+        assert_eq!(
+            "size".parse::<InstalledSort>().unwrap(),
+            InstalledSort::Size
+        );
+        assert!("date".parse::<InstalledSort>().is_err());
+    }
+
+    #[test]
+    fn package_metadata_details_joins_authors_and_resolves_icon_files() {
+        let dir = tempfile::tempdir().unwrap();
+        std::fs::write(dir.path().join("icon.svg"), "<svg/>").unwrap();
+        let metadata: PackageMetadata = serde_json::from_str(
+            r#"{"KPlugin": {
+                "Name": "Clock",
+                "Authors": [{"Name": "Ada"}, {"Name": "Grace", "Email": "g@example.org"}],
+                "Description": "Shows the time",
+                "Icon": "icon.svg",
+                "License": "GPL-2.0+",
+                "Website": ""
+            }}"#,
+        )
+        .unwrap();
+
+        let details = metadata.details(dir.path());
+
+        assert_eq!(details.author.as_deref(), Some("Ada, Grace"));
+        assert_eq!(details.description.as_deref(), Some("Shows the time"));
+        let icon = dir.path().join("icon.svg").display().to_string();
+        assert_eq!(details.icon, Some(icon));
+        assert_eq!(details.license.as_deref(), Some("GPL-2.0+"));
+        assert_eq!(details.website, None);
+    }
 }
 
 mod pathbuf_serde {
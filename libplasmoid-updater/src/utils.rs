@@ -1,34 +1,56 @@
 // SPDX-License-Identifier: GPL-3.0-or-later
 
 #[cfg(feature = "cli")]
-use crate::cli::{self, progress::create_fetch_spinner};
+use crate::{
+    cli::{self, progress::create_fetch_spinner},
+    tr,
+};
 #[cfg(feature = "cli")]
 use inquire::InquireError;
 
-use crate::{FailedUpdate, UnverifiedUpdate};
-use rayon::iter::{IndexedParallelIterator, IntoParallelRefIterator, ParallelIterator};
-use std::sync::Arc;
+use crate::{ComponentOutcome, FailedUpdate, OutcomeStatus, UnhealthyUpdate, UnverifiedUpdate};
+use rayon::iter::{IntoParallelRefIterator, ParallelIterator};
+use std::{
+    path::PathBuf,
+    time::{Duration, Instant},
+};
 
 use crate::{
-    Config, Error, RestartBehavior, UpdateResult,
+    Config, ConflictPolicy, Error, Event, RestartAction, RestartBehavior, UpdateResult,
     api::ApiClient,
-    checker::{check_with_components, find_installed},
+    checker::{check_streaming, check_with_components, find_installed, find_unrecognized},
+    events::{CheckEvent, InstallStage},
     installer,
-    types::{AvailableUpdate, UpdateCheckResult},
+    matcher::PackageMatcher,
+    types::{AvailableUpdate, Companion, HeldUpdate, InstalledComponent, UpdateCheckResult},
 };
 
 pub(crate) fn validate_environment(skip_plasma_detection: bool) -> crate::Result<()> {
     if cfg!(not(target_os = "linux")) {
         return Err(Error::UnsupportedOS(std::env::consts::OS.to_string()));
     }
-    let plasma_found = skip_plasma_detection || crate::paths::is_kde();
-    if !plasma_found {
-        return Err(Error::NotKDE);
+    if !skip_plasma_detection
+        && let Some(problem) = crate::environment::Environment::detect().problem()
+    {
+        return Err(Error::NotKDE(problem));
     }
     check_dependency("bsdtar")?;
     Ok(())
 }
 
+/// Fails early with [`Error::ImmutableSystem`] when [`Config::system`] is
+/// set on a system whose data directories are read-only, rather than deep
+/// inside a privileged copy.
+pub(crate) fn validate_system_scope(config: &Config) -> crate::Result<()> {
+    if !config.system {
+        return Ok(());
+    }
+    match installer::privilege::immutable_system() {
+        Some(system) => Err(Error::ImmutableSystem(system)),
+        None => Ok(()),
+    }
+}
+
 fn check_dependency(name: &str) -> crate::Result<()> {
     use std::process::Command;
     match Command::new("which").arg(name).output() {
@@ -41,73 +63,376 @@ pub(crate) fn fetch_updates(
     api_client: &ApiClient,
     config: &Config,
 ) -> crate::Result<UpdateCheckResult> {
+    fetch_updates_with(api_client, config, None)
+}
+
+/// Like [`fetch_updates`], and passes each [`CheckEvent`] to `report`. The
+/// spinner is only shown without one, since the caller then draws its own
+/// progress.
+pub(crate) fn fetch_updates_with(
+    api_client: &ApiClient,
+    config: &Config,
+    report: Option<&mut dyn FnMut(CheckEvent)>,
+) -> crate::Result<UpdateCheckResult> {
+    let spinner = report.is_none();
+    run_check(api_client, config, report, spinner)
+}
+
+/// Like [`fetch_updates`], and queues each update that will be installed
+/// without asking on `prefetcher` as soon as it is found, see
+/// [`Config::pipeline_downloads`]. `excluded` and `group` select the
+/// updates like [`update()`](crate::update) does.
+pub(crate) fn fetch_updates_prefetching(
+    api_client: &ApiClient,
+    config: &Config,
+    prefetcher: &installer::Prefetcher,
+    excluded: &PackageMatcher,
+    group: Option<&PackageMatcher>,
+) -> crate::Result<UpdateCheckResult> {
+    let mut queue = |event: CheckEvent| {
+        if let CheckEvent::UpdateAvailable(update) = event
+            && !excluded.matches(&update.installed)
+            && group.is_none_or(|g| g.matches(&update.installed))
+            && (config.force_distro_owned || !update.installed.managed_by_distro)
+            && config.auto_applies(&update)
+        {
+            prefetcher.queue(&update);
+        }
+    };
+    run_check(api_client, config, Some(&mut queue), true)
+}
+
+/// Returns `true` if the user picks the updates to install, so none can be
+/// downloaded before the check is done.
+pub(crate) fn selects_interactively(config: &Config) -> bool {
+    #[cfg(feature = "cli")]
+    if is_interactive(config) {
+        return true;
+    }
+    #[cfg(not(feature = "cli"))]
+    let _ = config;
+    false
+}
+
+fn run_check(
+    api_client: &ApiClient,
+    config: &Config,
+    mut report: Option<&mut dyn FnMut(CheckEvent)>,
+    spinner: bool,
+) -> crate::Result<UpdateCheckResult> {
+    config.emit(|| Event::CheckStarted {
+        system: config.system,
+    });
+
     #[cfg(feature = "cli")]
-    let spinner = create_fetch_spinner();
+    let spinner = (spinner && config.terminal_output()).then(create_fetch_spinner);
+    #[cfg(not(feature = "cli"))]
+    let _ = spinner;
 
-    let components = find_installed(config.system)?;
-    let result = check_with_components(config, api_client, components)?;
+    let mut components = find_installed(config.system, |ct| config.includes_type(ct))?;
+    let mut unrecognized = find_unrecognized(config.system, |ct| config.includes_type(ct));
+    if !config.only_packages.is_empty() {
+        let only = PackageMatcher::new(&config.only_packages)?;
+        components.retain(|c| only.matches(c));
+        unrecognized.retain(|u| only.matches(&u.component));
+    }
+    let invalid: Vec<_> = unrecognized.iter().map(|u| u.diagnostic()).collect();
+    let shadowed = components
+        .iter()
+        .filter(|c| c.shadowed.is_some())
+        .cloned()
+        .collect();
+    if let Some(report) = report.as_mut() {
+        report(CheckEvent::Discovered(components.clone()));
+        for diagnostic in &invalid {
+            report(CheckEvent::Unresolved(diagnostic.clone()));
+        }
+    }
+    let mut result = match report {
+        Some(report) => check_streaming(config, api_client, components, report)?,
+        None => check_with_components(config, api_client, components)?,
+    };
+    result.shadowed = shadowed;
+    result.unresolved.extend(invalid);
 
     #[cfg(feature = "cli")]
-    spinner.finish_and_clear();
+    if let Some(spinner) = spinner {
+        spinner.finish_and_clear();
+        cli::output::print_catalog_warning(&result.catalog);
+    }
+
+    for update in &result.updates {
+        config.emit(|| Event::update_found(update));
+    }
 
     Ok(result)
 }
 
+/// Switches updates to the download configured in
+/// [`Config::download_variants`], keeping the default when nothing matches.
+pub(crate) fn apply_download_variants(updates: &mut [AvailableUpdate], config: &Config) {
+    for update in updates {
+        apply_download_variant(update, config);
+    }
+}
+
+/// Switches one update to its configured download, see
+/// [`apply_download_variants`].
+pub(crate) fn apply_download_variant(update: &mut AvailableUpdate, config: &Config) {
+    let Some(selector) = config.download_variant_for(&update.installed) else {
+        return;
+    };
+    if !update.choose_variant(selector) {
+        log::warn!(
+            target: "update",
+            "no download of {} matches '{selector}', using {}",
+            update.installed.name,
+            update.selected_variant().map_or(update.download_url.as_str(), |v| v.label())
+        );
+    }
+}
+
+/// Builds a matcher for the selected update group, if any.
+pub(crate) fn group_matcher(config: &Config) -> crate::Result<Option<PackageMatcher>> {
+    let Some(name) = &config.group else {
+        return Ok(None);
+    };
+
+    let packages = config
+        .groups
+        .get(name)
+        .ok_or_else(|| Error::other(format!("unknown update group '{name}'")))?;
+
+    PackageMatcher::new(packages).map(Some)
+}
+
+/// Removes updates for components owned by a distro package and returns
+/// their names, see [`Config::force_distro_owned`].
+pub(crate) fn take_distro_owned(
+    updates: &mut Vec<AvailableUpdate>,
+    config: &Config,
+) -> Vec<String> {
+    let (owned, rest): (Vec<_>, Vec<_>) = std::mem::take(updates)
+        .into_iter()
+        .partition(|u| u.installed.managed_by_distro);
+    *updates = rest;
+
+    let names: Vec<String> = owned.into_iter().map(|u| u.installed.name).collect();
+    for name in &names {
+        #[cfg(feature = "cli")]
+        if config.terminal_output() {
+            println!(
+                "{}",
+                tr!(
+                    "skip-distro-owned",
+                    "skipping {name}: installed by a distro package",
+                    name = name,
+                )
+            );
+            continue;
+        }
+        log::info!(target: "update", "skipping {name}: installed by a distro package");
+    }
+    #[cfg(not(feature = "cli"))]
+    let _ = config;
+
+    names
+}
+
+/// Reports updates held back by advisories and returns their names.
+pub(crate) fn report_held(held: &[HeldUpdate], config: &Config) -> Vec<String> {
+    for held in held {
+        #[cfg(feature = "cli")]
+        if config.terminal_output() {
+            println!("{}", held_message(held));
+            continue;
+        }
+        log::info!(
+            target: "update",
+            "skipping {} {}: known broken ({})",
+            held.update.installed.name,
+            held.update.latest_version,
+            held.reason
+        );
+    }
+    #[cfg(not(feature = "cli"))]
+    let _ = config;
+
+    held.iter()
+        .map(|h| h.update.installed.name.clone())
+        .collect()
+}
+
 pub(crate) fn select_updates<'a>(
     updates: &'a [AvailableUpdate],
     config: &Config,
+    excluded: &PackageMatcher,
 ) -> crate::Result<Vec<&'a AvailableUpdate>> {
     #[cfg(feature = "cli")]
-    if !config.auto_confirm && stdin_is_terminal() {
-        return prompt_update_selection(updates, &config.excluded_packages);
+    if is_interactive(config) {
+        return prompt_update_selection(updates, excluded);
+    }
+    #[cfg(not(feature = "cli"))]
+    let _ = config;
+
+    Ok(filter_excluded(updates, excluded))
+}
+
+/// Leaves updates above [`Config::auto_apply`] for manual review when no
+/// one picked them, adding their names to `skipped`.
+pub(crate) fn apply_severity_policy<'a>(
+    selected: Vec<&'a AvailableUpdate>,
+    config: &Config,
+    skipped: &mut Vec<String>,
+) -> Vec<&'a AvailableUpdate> {
+    if selects_interactively(config) {
+        return selected;
+    }
+    let (selected, review): (Vec<_>, Vec<_>) =
+        selected.into_iter().partition(|u| config.auto_applies(u));
+    for update in review {
+        let name = &update.installed.name;
+        let version = &update.latest_version;
+        let severity = update.severity.to_string();
+        skipped.push(name.clone());
+        #[cfg(feature = "cli")]
+        if config.terminal_output() {
+            println!(
+                "{}",
+                tr!(
+                    "skip-severity",
+                    "left for review: {name} {version} ({severity} update)",
+                    name = name,
+                    version = version,
+                    severity = severity,
+                )
+            );
+            continue;
+        }
+        log::info!(target: "update", "skipping {name} {version}: {severity} update above auto_apply");
+    }
+    selected
+}
+
+/// Lets the user pick a download for selected updates that offer several
+/// and have no entry in [`Config::download_variants`]. Without a terminal
+/// the default choice is kept.
+pub(crate) fn choose_variants(
+    selected: Vec<&AvailableUpdate>,
+    config: &Config,
+) -> crate::Result<Vec<AvailableUpdate>> {
+    let chosen = selected.into_iter().cloned().collect();
+
+    #[cfg(feature = "cli")]
+    if is_interactive(config) {
+        return prompt_download_variants(chosen, config);
     }
+    #[cfg(not(feature = "cli"))]
+    let _ = config;
 
-    Ok(filter_excluded(updates, &config.excluded_packages))
+    Ok(chosen)
+}
+
+#[cfg(feature = "cli")]
+fn prompt_download_variants(
+    mut updates: Vec<AvailableUpdate>,
+    config: &Config,
+) -> crate::Result<Vec<AvailableUpdate>> {
+    for update in &mut updates {
+        if update.alternatives.len() > 1 && config.download_variant_for(&update.installed).is_none()
+        {
+            prompt_download_variant(update)?;
+        }
+    }
+    Ok(updates)
+}
+
+#[cfg(feature = "cli")]
+fn is_interactive(config: &Config) -> bool {
+    !config.auto_confirm && config.terminal_output() && stdin_is_terminal()
+}
+
+#[cfg(feature = "cli")]
+fn prompt_download_variant(update: &mut AvailableUpdate) -> crate::Result<()> {
+    let options: Vec<String> = update
+        .alternatives
+        .iter()
+        .map(|v| match v.version.as_str() {
+            "" => v.label().to_string(),
+            version => format!("{} ({version})", v.label()),
+        })
+        .collect();
+    let current = update
+        .alternatives
+        .iter()
+        .position(|v| v.url == update.download_url)
+        .unwrap_or(0);
+    let prompt = tr!(
+        "prompt-variant",
+        "{name} offers several downloads, pick one:",
+        name = update.installed.name,
+    );
+
+    match inquire::Select::new(&prompt, options)
+        .with_render_config(cli::theme::prompt_config())
+        .with_starting_cursor(current)
+        .raw_prompt()
+    {
+        Ok(choice) => {
+            let url = update.alternatives[choice.index].url.clone();
+            update.choose_variant(&url);
+            Ok(())
+        }
+        // Keep the default download rather than dropping the update.
+        Err(InquireError::OperationCanceled | InquireError::OperationInterrupted) => Ok(()),
+        Err(e) => Err(crate::Error::other(format!("prompt failed: {e}"))),
+    }
 }
 
 pub(crate) fn filter_excluded<'a>(
     updates: &'a [AvailableUpdate],
-    excluded: &[String],
+    excluded: &PackageMatcher,
 ) -> Vec<&'a AvailableUpdate> {
     updates
         .iter()
-        .filter(|u| !is_excluded(u, excluded))
+        .filter(|u| !excluded.matches(&u.installed))
         .collect()
 }
 
-pub(crate) fn is_excluded(update: &AvailableUpdate, excluded: &[String]) -> bool {
-    excluded
-        .iter()
-        .any(|e| e == &update.installed.directory_name || e == &update.installed.name)
-}
-
 #[cfg(feature = "cli")]
 pub(crate) fn stdin_is_terminal() -> bool {
     use is_terminal::IsTerminal;
     std::io::stdin().is_terminal()
 }
 
+/// Changelog entries shown per update above the selection menu.
+#[cfg(feature = "cli")]
+const MENU_CHANGES_LIMIT: usize = 5;
+
 #[cfg(feature = "cli")]
 pub(crate) fn prompt_update_selection<'a>(
     updates: &'a [AvailableUpdate],
-    excluded: &[String],
+    excluded: &PackageMatcher,
 ) -> crate::Result<Vec<&'a AvailableUpdate>> {
+    cli::output::print_changes(updates, Some(MENU_CHANGES_LIMIT));
     let options = format_menu_options(updates);
 
     let defaults: Vec<usize> = updates
         .iter()
         .enumerate()
-        .filter(|(_, u)| !is_excluded(u, excluded))
+        .filter(|(_, u)| !excluded.matches(&u.installed))
         .map(|(i, _)| i)
         .collect();
 
     let plural = if updates.len() == 1 { "" } else { "s" };
-    let prompt = format!(
-        "{} update{plural} available, select to apply:",
-        updates.len()
+    let prompt = tr!(
+        "prompt-select",
+        "{count} update{plural} available, select to apply:",
+        count = updates.len(),
     );
 
     match inquire::MultiSelect::new(&prompt, options)
+        .with_render_config(cli::theme::prompt_config())
         .with_default(&defaults)
         .with_page_size(15)
         .raw_prompt()
@@ -151,21 +476,45 @@ pub(crate) fn format_menu_options(updates: &[AvailableUpdate]) -> Vec<String> {
         .collect()
 }
 
+/// Delay before the first retry pass of [`Config::retry_failed`], doubled
+/// for each further pass.
+const RETRY_FAILED_BACKOFF: Duration = Duration::from_secs(2);
+
+/// One attempt at updating a component in [`install_selected_updates`].
+struct ComponentRun {
+    outcome: ComponentOutcome,
+    installed: Option<installer::InstallOutcome>,
+    failed: Option<FailedUpdate>,
+    /// The failure may go away on retry, see [`Error::is_transient`].
+    transient: bool,
+}
+
+/// Indices of the runs that failed transiently and are worth retrying.
+fn transient_failures(runs: &[ComponentRun]) -> Vec<usize> {
+    runs.iter()
+        .enumerate()
+        .filter(|(_, run)| run.outcome.status == OutcomeStatus::Failed && run.transient)
+        .map(|(index, _)| index)
+        .collect()
+}
+
 pub(crate) fn install_selected_updates(
     updates: &[&AvailableUpdate],
     api_client: &ApiClient,
     config: &Config,
 ) -> crate::Result<UpdateResult> {
-    let result = Arc::new(parking_lot::Mutex::new(UpdateResult::default()));
-
     let _inhibit = if config.inhibit_idle {
         installer::InhibitGuard::acquire()
     } else {
         installer::InhibitGuard::None
     };
 
+    installer::cleanup_stale_temp_dirs(config);
+
     #[cfg(feature = "cli")]
-    let ui = cli::update_ui::UpdateUi::new(updates);
+    let ui = config
+        .terminal_output()
+        .then(|| cli::progress::update_reporter(updates));
 
     // 0 = rayon default = number of logical CPUs
     let thread_count = config.threads.unwrap_or(0);
@@ -178,55 +527,176 @@ pub(crate) fn install_selected_updates(
                 .expect("failed to build default thread pool")
         });
 
-    let counter = api_client.request_counter();
+    let counter = api_client.counters();
 
-    pool.install(|| {
-        updates.par_iter().enumerate().for_each(|(index, update)| {
-            #[cfg(not(feature = "cli"))]
-            let _ = index;
-            let name = update.installed.name.clone();
+    let attempt = |indices: &[usize]| -> Vec<ComponentRun> {
+        // Collecting an indexed parallel iterator keeps the input order.
+        pool.install(|| {
+            indices
+                .par_iter()
+                .map(|&index| {
+                    let update = updates[index];
+                    let name = update.installed.name.clone();
+                    let started = Instant::now();
+                    let outcome = |status, bytes, error| ComponentOutcome {
+                        name: name.clone(),
+                        status,
+                        duration: started.elapsed(),
+                        bytes,
+                        sha256: None,
+                        error,
+                        retries: 0,
+                    };
 
-            #[cfg(feature = "cli")]
-            let reporter = ui.reporter(index);
-            #[cfg(not(feature = "cli"))]
-            let reporter = |_: u8| {};
+                    if config.is_cancelled() {
+                        #[cfg(feature = "cli")]
+                        if let Some(ui) = &ui {
+                            ui.complete(index, false);
+                        }
+                        return ComponentRun {
+                            outcome: outcome(OutcomeStatus::Skipped, None, None),
+                            installed: None,
+                            failed: None,
+                            transient: false,
+                        };
+                    }
 
-            match installer::update_component(update, api_client.http_client(), reporter, &counter)
-            {
-                Ok(outcome) => {
-                    #[cfg(feature = "cli")]
-                    ui.complete_task(index, true);
-                    let mut r = result.lock();
-                    if !outcome.verified {
-                        r.unverified.push(UnverifiedUpdate {
-                            name: name.clone(),
-                            expected_version: outcome.expected_version,
-                            actual_version: outcome.actual_version,
-                        });
+                    let reporter = |stage: u8| {
+                        #[cfg(feature = "cli")]
+                        if let Some(ui) = &ui {
+                            ui.stage(index, stage);
+                        }
+                        if let Some(stage) = InstallStage::from_reporter(stage) {
+                            config.emit(|| Event::DownloadProgress {
+                                name: name.clone(),
+                                stage,
+                            });
+                        }
+                    };
+
+                    match installer::update_component(
+                        update,
+                        api_client.http_client(),
+                        config,
+                        reporter,
+                        &counter,
+                    ) {
+                        Ok(installed) => {
+                            #[cfg(feature = "cli")]
+                            if let Some(ui) = &ui {
+                                ui.complete(index, true);
+                            }
+                            config.emit(|| Event::ComponentSucceeded { name: name.clone() });
+                            ComponentRun {
+                                outcome: ComponentOutcome {
+                                    sha256: Some(installed.sha256.clone()),
+                                    ..outcome(OutcomeStatus::Succeeded, Some(installed.bytes), None)
+                                },
+                                installed: Some(installed),
+                                failed: None,
+                                transient: false,
+                            }
+                        }
+                        Err(e) => {
+                            #[cfg(feature = "cli")]
+                            if let Some(ui) = &ui {
+                                ui.complete(index, false);
+                            }
+                            let failed = FailedUpdate::new(&name, &e);
+                            config.emit(|| Event::ComponentFailed {
+                                name: name.clone(),
+                                error: failed.error.clone(),
+                                operation: failed.operation,
+                            });
+                            ComponentRun {
+                                outcome: outcome(OutcomeStatus::Failed, None, Some(e.to_string())),
+                                installed: None,
+                                failed: Some(failed),
+                                transient: e.is_transient(),
+                            }
+                        }
                     }
-                    r.succeeded.push(name);
-                }
-                Err(e) => {
-                    #[cfg(feature = "cli")]
-                    ui.complete_task(index, false);
-                    result.lock().failed.push(FailedUpdate {
-                        name,
-                        error: e.to_string(),
-                    });
-                }
+                })
+                .collect()
+        })
+    };
+
+    let all: Vec<usize> = (0..updates.len()).collect();
+    let mut runs = attempt(&all);
+
+    let mut backoff = RETRY_FAILED_BACKOFF;
+    for pass in 1..=config.retry_failed {
+        let retry = transient_failures(&runs);
+        if retry.is_empty() || config.is_cancelled() {
+            break;
+        }
+        log::info!(
+            target: "update",
+            "retrying {} component(s) after a transient failure in {}s",
+            retry.len(),
+            backoff.as_secs(),
+        );
+        std::thread::sleep(backoff);
+        backoff = backoff.saturating_mul(2);
+
+        #[cfg(feature = "cli")]
+        if let Some(ui) = &ui {
+            for &index in &retry {
+                ui.restart(index);
             }
-        });
-    });
+        }
+        for (index, mut run) in retry.iter().copied().zip(attempt(&retry)) {
+            run.outcome.retries = pass;
+            runs[index] = run;
+        }
+    }
 
     #[cfg(feature = "cli")]
-    ui.finish();
+    if let Some(ui) = ui {
+        ui.finish();
+    }
 
-    Ok(Arc::try_unwrap(result)
-        .expect("thread pool completed; Arc should have single owner")
-        .into_inner())
+    let mut result = UpdateResult::default();
+    for ComponentRun {
+        outcome,
+        installed,
+        failed,
+        ..
+    } in runs
+    {
+        let name = outcome.name.clone();
+        match outcome.status {
+            OutcomeStatus::Succeeded => {
+                if let Some(installed) = installed {
+                    if !installed.verified {
+                        result.unverified.push(UnverifiedUpdate {
+                            name: name.clone(),
+                            expected_version: installed.expected_version,
+                            actual_version: installed.actual_version,
+                        });
+                    }
+                    if !installed.problems.is_empty() {
+                        result.unhealthy.push(UnhealthyUpdate {
+                            name: name.clone(),
+                            problems: installed.problems,
+                        });
+                    }
+                }
+                result.succeeded.push(name);
+            }
+            OutcomeStatus::Failed => result.failed.extend(failed),
+            OutcomeStatus::Skipped => result.skipped.push(name),
+        }
+        result.outcomes.push(outcome);
+    }
+    Ok(result)
 }
 
-pub(crate) fn handle_restart(config: &Config, updates: &[AvailableUpdate], result: &UpdateResult) {
+pub(crate) fn handle_restart(
+    config: &Config,
+    updates: &[AvailableUpdate],
+    result: &mut UpdateResult,
+) {
     if result.succeeded.is_empty() {
         return;
     }
@@ -236,54 +706,709 @@ pub(crate) fn handle_restart(config: &Config, updates: &[AvailableUpdate], resul
         .filter(|u| result.succeeded.contains(&u.installed.name))
         .collect();
 
-    if !installer::any_requires_restart(&succeeded_updates) {
+    let mut actions = installer::required_actions(&succeeded_updates);
+    if !config.reapply_active_themes {
+        actions.remove(&RestartAction::LookAndFeelReapply);
+    }
+
+    if actions.contains(&RestartAction::SddmNone) {
+        log::info!(target: "restart", "SDDM theme updates take effect at the next login");
+    }
+
+    let taken = &mut result.restart_actions;
+    if actions.contains(&RestartAction::FontCacheRefresh) {
+        run_restart_action(RestartAction::FontCacheRefresh, &succeeded_updates, taken);
+    }
+
+    if config.reload_kwin && actions.contains(&RestartAction::KWinReconfigure) {
+        run_restart_action(RestartAction::KWinReconfigure, &succeeded_updates, taken);
+    }
+
+    // Re-applying themes and restarting plasmashell both disturb the
+    // running session, so they follow `config.restart`.
+    let session_actions: Vec<RestartAction> = actions
+        .into_iter()
+        .filter(|action| {
+            matches!(
+                action,
+                RestartAction::LookAndFeelReapply | RestartAction::PlasmashellRestart
+            )
+        })
+        .collect();
+
+    if session_actions.is_empty() {
         return;
     }
 
-    match config.restart {
-        RestartBehavior::Never => {}
-        RestartBehavior::Always => {
-            if let Err(e) = installer::restart_plasmashell() {
-                log::warn!(target: "restart", "failed to restart plasmashell: {e}");
-            }
-        }
+    let restarted = match config.restart {
+        RestartBehavior::Never => false,
+        RestartBehavior::Always => run_session_actions(&session_actions, &succeeded_updates, taken),
         #[cfg(feature = "cli")]
         RestartBehavior::Prompt => {
-            if stdin_is_terminal() {
-                prompt_restart();
-            }
+            config.terminal_output()
+                && stdin_is_terminal()
+                && prompt_restart(&session_actions, &succeeded_updates, taken)
         }
         #[cfg(not(feature = "cli"))]
         RestartBehavior::Prompt => {
             // Without CLI, cannot prompt — fall back to not restarting
             log::info!(target: "restart", "prompt restart requested but no CLI available, skipping");
+            false
         }
+    };
+
+    if restarted {
+        monitor_plasmashell(config, &succeeded_updates, result);
+    }
+}
+
+/// Runs `actions` in order, adding those that succeed to `taken`. Returns
+/// `true` if plasmashell was restarted.
+fn run_session_actions(
+    actions: &[RestartAction],
+    updates: &[&AvailableUpdate],
+    taken: &mut Vec<RestartAction>,
+) -> bool {
+    let mut restarted = false;
+    for &action in actions {
+        let ok = run_restart_action(action, updates, taken);
+        restarted |= ok && action == RestartAction::PlasmashellRestart;
     }
+    restarted
 }
 
+fn run_restart_action(
+    action: RestartAction,
+    updates: &[&AvailableUpdate],
+    taken: &mut Vec<RestartAction>,
+) -> bool {
+    match installer::run_action(action, updates) {
+        Ok(()) => {
+            taken.push(action);
+            true
+        }
+        Err(e) => {
+            log::warn!(
+                target: "restart",
+                "failed to {}: {e}",
+                installer::describe_restart_action(action)
+            );
+            false
+        }
+    }
+}
+
+/// Prompts for the session actions and runs them if confirmed. Returns
+/// `true` if plasmashell was restarted.
 #[cfg(feature = "cli")]
-pub(crate) fn prompt_restart() {
-    match inquire::Confirm::new("Restart plasmashell now?")
+pub(crate) fn prompt_restart(
+    actions: &[RestartAction],
+    updates: &[&AvailableUpdate],
+    taken: &mut Vec<RestartAction>,
+) -> bool {
+    let and = tr!("prompt-restart-and", "and");
+    let description = actions
+        .iter()
+        .map(|&action| cli::output::describe_restart_action(action))
+        .collect::<Vec<_>>()
+        .join(&format!(" {and} "));
+    let mut chars = description.chars();
+    let question = match chars.next() {
+        Some(first) => {
+            let actions = format!("{}{}", first.to_uppercase(), chars.as_str());
+            tr!("prompt-restart", "{actions} now?", actions = actions)
+        }
+        None => return false,
+    };
+
+    match inquire::Confirm::new(&question)
+        .with_render_config(cli::theme::prompt_config())
         .with_default(false)
         .prompt()
     {
-        Ok(true) => {
-            if let Err(e) = installer::restart_plasmashell() {
-                log::warn!(target: "restart", "failed to restart plasmashell: {e}");
+        Ok(true) => run_session_actions(actions, updates, taken),
+        Ok(false) | Err(InquireError::OperationCanceled | InquireError::OperationInterrupted) => {
+            false
+        }
+        Err(e) => {
+            log::warn!(target: "restart", "prompt failed: {e}");
+            false
+        }
+    }
+}
+
+/// Watches plasmashell after a restart and rolls back the components that
+/// required it if it crashes, see [`Config::crash_monitor_window`].
+fn monitor_plasmashell(config: &Config, updates: &[&AvailableUpdate], result: &mut UpdateResult) {
+    let Some(window) = config.crash_monitor_window else {
+        return;
+    };
+
+    #[cfg(feature = "cli")]
+    if config.terminal_output() {
+        println!(
+            "{}",
+            tr!(
+                "watching-plasmashell",
+                "watching plasmashell for {seconds}s...",
+                seconds = window.as_secs(),
+            )
+        );
+    }
+
+    let installer::RestartHealth::Crashed(reason) = installer::monitor_restart(window) else {
+        return;
+    };
+    log::warn!(target: "restart", "plasmashell crashed after the restart: {reason}");
+
+    let suspects: Vec<&AvailableUpdate> = updates
+        .iter()
+        .copied()
+        .filter(|u| u.restart_action == RestartAction::PlasmashellRestart)
+        .collect();
+    if suspects.is_empty() || !confirm_crash_rollback(config, &reason, &suspects) {
+        return;
+    }
+
+    for update in suspects {
+        match installer::rollback_update(update) {
+            Ok(()) => result.rolled_back.push(update.installed.name.clone()),
+            Err(e) => log::warn!(
+                target: "restore",
+                "failed to roll back {}: {e}",
+                update.installed.name
+            ),
+        }
+    }
+
+    if result.rolled_back.is_empty() {
+        return;
+    }
+    let taken = &mut result.restart_actions;
+    match &result.layout_backup {
+        Some(backup) => match installer::restart_with_layout(backup) {
+            Ok(()) => taken.push(RestartAction::PlasmashellRestart),
+            Err(e) => {
+                log::warn!(target: "restore", "failed to restore the Plasma layout: {e}");
+                run_restart_action(RestartAction::PlasmashellRestart, &[], taken);
+            }
+        },
+        None => {
+            run_restart_action(RestartAction::PlasmashellRestart, &[], taken);
+        }
+    }
+}
+
+/// Backs up the Plasma layout if any of `updates` needs a plasmashell
+/// restart, see [`Config::backup_layout`]. Returns the backup directory.
+pub(crate) fn backup_layout(updates: &[&AvailableUpdate], config: &Config) -> Option<PathBuf> {
+    if !config.backup_layout
+        || config.system
+        || !updates
+            .iter()
+            .any(|u| u.restart_action == RestartAction::PlasmashellRestart)
+    {
+        return None;
+    }
+    match installer::backup_layout() {
+        Ok(backup) => backup,
+        Err(e) => {
+            log::warn!(target: "backup", "failed to back up the Plasma layout: {e}");
+            None
+        }
+    }
+}
+
+/// Offers to undo installs interrupted by an earlier crash before starting
+/// new ones. Recovers without asking with [`Config::auto_confirm`];
+/// otherwise asks when interactive, or leaves them for
+/// [`recover()`](crate::recover).
+pub(crate) fn offer_recovery(config: &Config) {
+    let pending = installer::incomplete_transactions(config.system);
+    if pending.is_empty() {
+        return;
+    }
+    let names = pending
+        .iter()
+        .map(|t| t.component.name.as_str())
+        .collect::<Vec<_>>()
+        .join(", ");
+    log::warn!(target: "journal", "interrupted installs found: {names}");
+
+    if !confirm_recovery(config, &names) {
+        return;
+    }
+    for transaction in &pending {
+        match installer::recover_transaction(transaction) {
+            #[cfg(feature = "cli")]
+            Ok(()) if config.terminal_output() => {
+                println!(
+                    "{}",
+                    tr!(
+                        "restored",
+                        "restored {name}",
+                        name = transaction.component.name,
+                    )
+                );
+            }
+            Ok(()) => {}
+            Err(e) => log::warn!(
+                target: "journal",
+                "failed to recover {}: {e}",
+                transaction.component.name
+            ),
+        }
+    }
+}
+
+/// Asks before updating widgets that are on the desktop or a panel, whose
+/// layout may glitch until plasmashell restarts. Declined ones are added to
+/// `skipped`; without a prompt everything is kept.
+pub(crate) fn confirm_in_use(
+    updates: Vec<AvailableUpdate>,
+    config: &Config,
+    skipped: &mut Vec<String>,
+) -> Vec<AvailableUpdate> {
+    let in_use: Vec<&str> = updates
+        .iter()
+        .filter(|u| u.installed.in_use)
+        .map(|u| u.installed.name.as_str())
+        .collect();
+    if in_use.is_empty() {
+        return updates;
+    }
+    let names = in_use.join(", ");
+    log::warn!(target: "update", "updating widgets in use: {names}");
+
+    #[cfg(feature = "cli")]
+    if is_interactive(config) {
+        let is = if in_use.len() == 1 { "is" } else { "are" };
+        let question = tr!(
+            "prompt-in-use",
+            "{names} {is} on your desktop or panels and may glitch until plasmashell restarts. \
+             Update anyway?",
+            names = names,
+            count = in_use.len(),
+        );
+        if matches!(
+            inquire::Confirm::new(&question)
+                .with_render_config(cli::theme::prompt_config())
+                .with_default(true)
+                .prompt(),
+            Ok(false) | Err(InquireError::OperationCanceled)
+        ) {
+            let (declined, kept): (Vec<_>, Vec<_>) =
+                updates.into_iter().partition(|u| u.installed.in_use);
+            skipped.extend(declined.into_iter().map(|u| u.installed.name));
+            return kept;
+        }
+    }
+    #[cfg(not(feature = "cli"))]
+    let _ = (config, skipped);
+
+    updates
+}
+
+/// Asks before installing updates whose store entry changed author, if
+/// [`Config::confirm_author_changes`] is set. Declined ones, and all of them
+/// without a prompt, are added to `skipped`; components named in
+/// [`Config::only_packages`] count as confirmed.
+pub(crate) fn confirm_author_changes(
+    updates: Vec<AvailableUpdate>,
+    config: &Config,
+    skipped: &mut Vec<String>,
+) -> Vec<AvailableUpdate> {
+    if !config.confirm_author_changes || !config.only_packages.is_empty() {
+        return updates;
+    }
+    let mut kept = Vec::with_capacity(updates.len());
+    for update in updates {
+        let Some(previous) = &update.previous_author else {
+            kept.push(update);
+            continue;
+        };
+        if confirm_author_change(config, &update, previous) {
+            kept.push(update);
+            continue;
+        }
+        let name = update.installed.name;
+        #[cfg(feature = "cli")]
+        if config.terminal_output() && !is_interactive(config) {
+            println!(
+                "{}",
+                tr!(
+                    "skip-author",
+                    "left for review: {name} (store author changed from {previous} to {author})",
+                    name = name,
+                    previous = previous,
+                    author = update.author,
+                )
+            );
+        }
+        log::warn!(
+            target: "update",
+            "skipping {name}: store author changed from {previous} to {}",
+            update.author
+        );
+        skipped.push(name);
+    }
+    kept
+}
+
+fn confirm_author_change(config: &Config, update: &AvailableUpdate, previous: &str) -> bool {
+    #[cfg(feature = "cli")]
+    if is_interactive(config) {
+        let question = tr!(
+            "prompt-author",
+            "The store author of {name} changed from {previous} to {author}. Install {version} anyway?",
+            name = update.installed.name,
+            previous = previous,
+            author = update.author,
+            version = update.latest_version,
+        );
+        return matches!(
+            inquire::Confirm::new(&question)
+                .with_render_config(cli::theme::prompt_config())
+                .with_default(false)
+                .prompt(),
+            Ok(true)
+        );
+    }
+
+    let _ = (config, update, previous);
+    false
+}
+
+/// Scans `selected` for updates that would write to the same files before
+/// anything is installed. Returns [`Error::FileConflicts`] if there are
+/// any, unless [`Config::conflict_policy`] is [`ConflictPolicy::Warn`],
+/// which reports them and goes on.
+pub(crate) fn check_conflicts(selected: &[&AvailableUpdate], config: &Config) -> crate::Result<()> {
+    let conflicts = installer::find_conflicts(selected);
+    if conflicts.is_empty() {
+        return Ok(());
+    }
+    if config.conflict_policy == ConflictPolicy::Fail {
+        return Err(Error::FileConflicts(conflicts));
+    }
+    for conflict in &conflicts {
+        log::warn!(target: "update", "conflicting updates: {conflict}");
+        #[cfg(feature = "cli")]
+        if config.terminal_output() {
+            println!(
+                "{}",
+                tr!(
+                    "file-conflict",
+                    "warning: {components} write to {path}; the last one installed wins",
+                    components = conflict.components.join(", "),
+                    path = conflict.path.display().to_string(),
+                )
+            );
+        }
+    }
+    Ok(())
+}
+
+/// Offers the pending updates to the companions of selected global themes
+/// that were left out of the interactive selection, see
+/// [`AvailableUpdate::suggested_companions`]. Other runs keep the selection:
+/// they select every update that is not excluded anyway.
+pub(crate) fn add_companion_updates<'a>(
+    mut selected: Vec<&'a AvailableUpdate>,
+    updates: &'a [AvailableUpdate],
+    config: &Config,
+    excluded: &PackageMatcher,
+) -> Vec<&'a AvailableUpdate> {
+    if !selects_interactively(config) {
+        return selected;
+    }
+    for theme in selected.clone() {
+        let pending: Vec<&AvailableUpdate> = theme
+            .companion_updates(updates)
+            .into_iter()
+            .filter(|u| !excluded.matches(&u.installed))
+            .filter(|u| !selected.iter().any(|s| std::ptr::eq(*s, *u)))
+            .collect();
+        if pending.is_empty() {
+            continue;
+        }
+        let names = pending
+            .iter()
+            .map(|u| u.installed.name.as_str())
+            .collect::<Vec<_>>()
+            .join(", ");
+        if confirm_companion_updates(config, &theme.installed.name, &names) {
+            selected.extend(pending);
+        }
+    }
+    selected
+}
+
+fn confirm_companion_updates(config: &Config, theme: &str, names: &str) -> bool {
+    #[cfg(feature = "cli")]
+    if is_interactive(config) {
+        let question = tr!(
+            "prompt-companion-updates",
+            "{theme} uses {names}, which have updates too. Update them as well?",
+            theme = theme,
+            names = names,
+        );
+        return matches!(
+            inquire::Confirm::new(&question)
+                .with_render_config(cli::theme::prompt_config())
+                .with_default(true)
+                .prompt(),
+            Ok(true)
+        );
+    }
+
+    let _ = (config, theme, names);
+    false
+}
+
+/// Installs the companions of the updated global themes in `updated` that
+/// are missing, from the KDE Store. Asks first unless
+/// [`Config::install_companions`] is set; runs without a prompt install none
+/// then. Installed companions are added to `result.succeeded`, failed ones
+/// to `result.failed`.
+pub(crate) fn install_missing_companions(
+    updated: &[&AvailableUpdate],
+    api_client: &ApiClient,
+    config: &Config,
+    result: &mut UpdateResult,
+) {
+    let mut offered: Vec<&Companion> = Vec::new();
+    for theme in updated {
+        let missing: Vec<&Companion> = theme
+            .suggested_companions
+            .iter()
+            .filter(|c| !c.installed && config.includes_type(c.component_type))
+            .filter(|c| !offered.contains(c))
+            .collect();
+        if missing.is_empty() {
+            continue;
+        }
+        offered.extend(&missing);
+        let names = missing
+            .iter()
+            .map(|c| c.name.as_str())
+            .collect::<Vec<_>>()
+            .join(", ");
+        if !config.install_companions
+            && !confirm_companion_install(config, &theme.installed.name, &names)
+        {
+            continue;
+        }
+
+        for companion in missing {
+            match install_companion(companion, api_client, config) {
+                Ok(component) => {
+                    #[cfg(feature = "cli")]
+                    if config.terminal_output() {
+                        println!(
+                            "{}",
+                            tr!(
+                                "companion-installed",
+                                "installed {name} {version} for {theme}",
+                                name = component.name,
+                                version = component.version,
+                                theme = theme.installed.name,
+                            )
+                        );
+                    }
+                    result.succeeded.push(component.name);
+                }
+                Err(e) => {
+                    log::warn!(
+                        target: "update",
+                        "failed to install {} for {}: {e}",
+                        companion.name,
+                        theme.installed.name
+                    );
+                    result.failed.push(FailedUpdate::new(&companion.name, &e));
+                }
             }
         }
-        Ok(false) | Err(InquireError::OperationCanceled | InquireError::OperationInterrupted) => {}
-        Err(e) => log::warn!(target: "restart", "prompt failed: {e}"),
     }
 }
 
+fn confirm_companion_install(config: &Config, theme: &str, names: &str) -> bool {
+    #[cfg(feature = "cli")]
+    if is_interactive(config) {
+        let question = tr!(
+            "prompt-companion-install",
+            "{theme} uses {names}, which are not installed. Install them from the KDE Store?",
+            theme = theme,
+            names = names,
+        );
+        return matches!(
+            inquire::Confirm::new(&question)
+                .with_render_config(cli::theme::prompt_config())
+                .with_default(true)
+                .prompt(),
+            Ok(true)
+        );
+    }
+
+    let _ = (config, theme, names);
+    false
+}
+
+/// Finds the store entry of `companion` by name among the entries of its
+/// type and installs it. Only close matches count, as the theme names the
+/// package rather than the store entry.
+fn install_companion(
+    companion: &Companion,
+    api_client: &ApiClient,
+    config: &Config,
+) -> crate::Result<InstalledComponent> {
+    let component_type = companion.component_type;
+    let entries = api_client.fetch_all(&[component_type])?;
+    let entry = crate::checker::rank_by_name(&companion.name, component_type, &entries)
+        .into_iter()
+        .find(|&(entry, score)| {
+            score >= crate::checker::LOOSE_NAME_SCORE
+                && component_type.matches_type_id(entry.type_id)
+        })
+        .map(|(entry, _)| entry)
+        .ok_or_else(|| Error::IdResolutionFailed(companion.name.clone()))?;
+    let mut update = crate::checker::new_install(entry, component_type)
+        .ok_or_else(|| Error::download("no download url available"))?;
+    apply_download_variants(std::slice::from_mut(&mut update), config);
+    installer::install_new(
+        &update,
+        api_client.http_client(),
+        config,
+        &api_client.counters(),
+    )
+}
+
+fn confirm_recovery(config: &Config, names: &str) -> bool {
+    if config.auto_confirm {
+        return true;
+    }
+
+    #[cfg(feature = "cli")]
+    if is_interactive(config) {
+        let question = tr!(
+            "prompt-recover",
+            "A previous run was interrupted while installing {names}. Restore?",
+            names = names,
+        );
+        return matches!(
+            inquire::Confirm::new(&question)
+                .with_render_config(cli::theme::prompt_config())
+                .with_default(true)
+                .prompt(),
+            Ok(true)
+        );
+    }
+
+    let _ = names;
+    false
+}
+
+fn confirm_crash_rollback(config: &Config, reason: &str, suspects: &[&AvailableUpdate]) -> bool {
+    if config.rollback_on_crash {
+        return true;
+    }
+
+    #[cfg(feature = "cli")]
+    if is_interactive(config) {
+        let names = suspects
+            .iter()
+            .map(|u| u.installed.name.as_str())
+            .collect::<Vec<_>>()
+            .join(", ");
+        let question = tr!(
+            "prompt-crash-rollback",
+            "plasmashell crashed after the restart ({reason}). Roll back {names}?",
+            reason = reason,
+            names = names,
+        );
+        return matches!(
+            inquire::Confirm::new(&question)
+                .with_render_config(cli::theme::prompt_config())
+                .with_default(true)
+                .prompt(),
+            Ok(true)
+        );
+    }
+
+    let _ = (reason, suspects);
+    false
+}
+
 #[cfg(feature = "cli")]
 pub(crate) fn display_check_results(result: &crate::types::UpdateCheckResult) {
     if result.updates.is_empty() {
-        println!("no updates available");
-        return;
+        println!("{}", tr!("no-updates", "no updates available"));
+    } else {
+        cli::output::print_update_count(result.updates.len());
+        cli::output::print_updates_table(&result.updates);
+    }
+
+    for held in &result.held {
+        println!("{}", held_message(held));
+    }
+    for update in &result.updates {
+        if let Some(previous) = &update.previous_author {
+            println!("{}", author_change_message(update, previous));
+        }
+    }
+    for update in &result.updates {
+        let missing: Vec<&str> = update
+            .suggested_companions
+            .iter()
+            .filter(|c| !c.installed)
+            .map(|c| c.name.as_str())
+            .collect();
+        if !missing.is_empty() {
+            println!(
+                "{}",
+                tr!(
+                    "companions-missing",
+                    "note: {name} also uses {names}, which are not installed",
+                    name = update.installed.name,
+                    names = missing.join(", "),
+                )
+            );
+        }
+    }
+    for component in &result.shadowed {
+        if let Some(system_copy) = &component.shadowed {
+            println!(
+                "{}",
+                tr!(
+                    "shadowed",
+                    "warning: {name} in {path} hides the system copy in {system}",
+                    name = component.name,
+                    path = component.path.display().to_string(),
+                    system = system_copy.display().to_string(),
+                )
+            );
+        }
     }
+}
 
-    cli::output::print_count_message(result.updates.len(), "update");
-    cli::output::print_updates_table(&result.updates);
+#[cfg(feature = "cli")]
+fn author_change_message(update: &AvailableUpdate, previous: &str) -> String {
+    tr!(
+        "author-changed",
+        "warning: the store author of {name} changed from {previous} to {author}",
+        name = update.installed.name,
+        previous = previous,
+        author = update.author,
+    )
+}
+
+#[cfg(feature = "cli")]
+fn held_message(held: &HeldUpdate) -> String {
+    tr!(
+        "held",
+        "held (known broken): {name} {version} — {reason}",
+        name = held.update.installed.name,
+        version = held.update.latest_version,
+        reason = held.reason,
+    )
 }
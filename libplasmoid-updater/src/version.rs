@@ -1,5 +1,8 @@
 // SPDX-License-Identifier: GPL-3.0-or-later
 
+use std::{cmp::Ordering, fmt, str::FromStr};
+
+use serde::{Deserialize, Serialize};
 use versions::Versioning;
 
 /// Normalizes a version string for more robust parsing.
@@ -48,6 +51,18 @@ pub(crate) fn normalize_version(version: &str) -> String {
     result
 }
 
+/// Orders two version strings semantically where possible. Unparseable
+/// versions sort before parseable ones, and by their text among themselves.
+pub(crate) fn compare(a: &str, b: &str) -> Ordering {
+    let parse = |v: &str| Versioning::new(normalize_version(v));
+    match (parse(a), parse(b)) {
+        (Some(a), Some(b)) => a.cmp(&b),
+        (Some(_), None) => Ordering::Greater,
+        (None, Some(_)) => Ordering::Less,
+        (None, None) => a.cmp(b),
+    }
+}
+
 /// Returns true if there's an update based on version or date.
 ///
 /// Mirrors KNewStuff's update detection: an update is available when the
@@ -61,12 +76,67 @@ pub(crate) fn is_update_available_with_date(
     installed_date: &str,
     available_date: &str,
 ) -> bool {
+    compare_release(
+        installed_version,
+        available_version,
+        installed_date,
+        available_date,
+    )
+    .is_update()
+}
+
+/// How a store release compares to the installed one, see
+/// [`Explanation::comparison`](crate::Explanation::comparison).
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum VersionComparison {
+    /// The store version is newer, or the installed one is not a version.
+    NewerVersion,
+    /// The versions are equal but the store release is dated later, e.g.
+    /// a re-upload of the same version.
+    NewerRelease,
+    /// The installed release is the latest.
+    Same,
+    /// The installed version is newer than the store's.
+    InstalledNewer,
+    /// The versions cannot be ordered and the dates show no newer release.
+    Incomparable,
+}
+
+impl VersionComparison {
+    /// Whether the store release counts as an update.
+    pub fn is_update(self) -> bool {
+        matches!(self, Self::NewerVersion | Self::NewerRelease)
+    }
+}
+
+/// Compares an installed release to a store release the way
+/// [`is_update_available_with_date`] decides on updates.
+pub(crate) fn compare_release(
+    installed_version: &str,
+    available_version: &str,
+    installed_date: &str,
+    available_date: &str,
+) -> VersionComparison {
+    let by_date = |otherwise| {
+        if is_date_newer(installed_date, available_date) {
+            VersionComparison::NewerRelease
+        } else {
+            otherwise
+        }
+    };
+    let ordered = |inst: &Versioning, avail: &Versioning| match inst.cmp(avail) {
+        Ordering::Less => VersionComparison::NewerVersion,
+        Ordering::Equal => by_date(VersionComparison::Same),
+        Ordering::Greater => VersionComparison::InstalledNewer,
+    };
+
     // Fast path: identical raw strings
     if !installed_version.is_empty()
         && !available_version.is_empty()
         && installed_version == available_version
     {
-        return is_date_newer(installed_date, available_date);
+        return by_date(VersionComparison::Same);
     }
 
     // Try parsing originals first (preserves pre-release semantics)
@@ -74,13 +144,7 @@ pub(crate) fn is_update_available_with_date(
     let avail_orig = Versioning::new(available_version);
 
     if let (Some(inst), Some(avail)) = (&inst_orig, &avail_orig) {
-        if inst < avail {
-            return true;
-        }
-        if inst == avail {
-            return is_date_newer(installed_date, available_date);
-        }
-        return false;
+        return ordered(inst, avail);
     }
 
     // Fall back to normalized comparison
@@ -88,29 +152,90 @@ pub(crate) fn is_update_available_with_date(
     let avail_norm = normalize_version(available_version);
 
     if !inst_norm.is_empty() && !avail_norm.is_empty() && inst_norm == avail_norm {
-        return is_date_newer(installed_date, available_date);
+        return by_date(VersionComparison::Same);
     }
 
     let inst_parsed = Versioning::new(&inst_norm);
     let avail_parsed = Versioning::new(&avail_norm);
 
     if let (Some(inst), Some(avail)) = (&inst_parsed, &avail_parsed) {
-        if inst < avail {
-            return true;
-        }
-        if inst == avail {
-            return is_date_newer(installed_date, available_date);
-        }
-        return false;
+        return ordered(inst, avail);
     }
 
     if avail_parsed.is_some() && inst_parsed.is_none() {
-        return true;
+        return VersionComparison::NewerVersion;
     }
 
     // Both unparseable and differ: we can't determine ordering,
     // so fall through to date comparison instead of assuming update.
-    is_date_newer(installed_date, available_date)
+    by_date(VersionComparison::Incomparable)
+}
+
+/// How large a step an update is, see
+/// [`AvailableUpdate::severity`](crate::AvailableUpdate::severity).
+///
+/// Ordered from the smallest step, so `severity <= UpdateSeverity::Minor`
+/// selects minor and patch updates.
+#[derive(
+    Debug, Clone, Copy, Default, PartialEq, Eq, PartialOrd, Ord, Hash, Serialize, Deserialize,
+)]
+#[serde(rename_all = "snake_case")]
+pub enum UpdateSeverity {
+    /// A later version part changed (`1.2.3` → `1.2.4`), or the same
+    /// version was uploaded again.
+    Patch,
+    /// The second version part changed (`1.2.3` → `1.3.0`).
+    Minor,
+    /// The first version part changed (`1.2.3` → `2.0`), or either version
+    /// is not numeric (default).
+    #[default]
+    Major,
+}
+
+impl fmt::Display for UpdateSeverity {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(match self {
+            Self::Patch => "patch",
+            Self::Minor => "minor",
+            Self::Major => "major",
+        })
+    }
+}
+
+impl FromStr for UpdateSeverity {
+    type Err = crate::Error;
+
+    fn from_str(s: &str) -> crate::Result<Self> {
+        match s {
+            "patch" => Ok(Self::Patch),
+            "minor" => Ok(Self::Minor),
+            "major" => Ok(Self::Major),
+            _ => Err(crate::Error::other(format!(
+                "invalid update severity '{s}', expected patch, minor or major"
+            ))),
+        }
+    }
+}
+
+/// Classifies the update from `installed` to `available` by the first
+/// numeric version part that differs, missing parts counting as `0`.
+pub(crate) fn severity(installed: &str, available: &str) -> UpdateSeverity {
+    let parts = |version: &str| -> Option<Vec<u64>> {
+        let parts: Vec<u64> = normalize_version(version)
+            .split('.')
+            .map_while(|part| part.parse().ok())
+            .collect();
+        (!parts.is_empty()).then_some(parts)
+    };
+    let (Some(from), Some(to)) = (parts(installed), parts(available)) else {
+        return UpdateSeverity::Major;
+    };
+    let part = |parts: &[u64], i: usize| parts.get(i).copied().unwrap_or(0);
+    match (0..from.len().max(to.len())).find(|&i| part(&from, i) != part(&to, i)) {
+        Some(0) => UpdateSeverity::Major,
+        Some(1) => UpdateSeverity::Minor,
+        _ => UpdateSeverity::Patch,
+    }
 }
 
 /// Returns true if `available_date` is strictly newer than `installed_date`.
@@ -235,4 +360,27 @@ mod tests {
         assert!(is_update_available_with_date("v1.0", "v2.0", "", ""));
         assert!(!is_update_available_with_date("v2.0", "v1.0", "", ""));
     }
+
+    #[test]
+    fn severity_follows_the_first_changed_part() {
+        assert_eq!(severity("1.2.3", "1.2.4"), UpdateSeverity::Patch);
+        assert_eq!(severity("v1.2", "1.2.0.1"), UpdateSeverity::Patch);
+        assert_eq!(severity("1.2.3", "1.3"), UpdateSeverity::Minor);
+        assert_eq!(severity("1.9", "2.0"), UpdateSeverity::Major);
+        assert_eq!(severity("1.0", "1.0"), UpdateSeverity::Patch);
+        assert_eq!(severity("1.2.0-rc1", "1.2.0"), UpdateSeverity::Patch);
+        assert_eq!(severity("", "1.0"), UpdateSeverity::Major);
+        assert_eq!(severity("nightly", "beta"), UpdateSeverity::Major);
+    }
+
+    #[test]
+    fn severities_order_from_patch_to_major() {
+        assert!(UpdateSeverity::Patch < UpdateSeverity::Minor);
+        assert!(UpdateSeverity::Minor < UpdateSeverity::Major);
+        assert_eq!(
+            "minor".parse::<UpdateSeverity>().unwrap(),
+            UpdateSeverity::Minor
+        );
+        assert!("huge".parse::<UpdateSeverity>().is_err());
+    }
 }
@@ -0,0 +1,1224 @@
+// SPDX-License-Identifier: GPL-3.0-or-later
+
+use libplasmoid_updater::{
+    Blocker, CancelToken, CheckEvent, Companion, ComponentType, ConflictPolicy, DiagnosticKind,
+    DoctorStatus, Error, Hooks, InstalledQuery, InstalledStatus, Manifest, ManifestEntry,
+    MatchSource, Metrics, Notifier, Operation, OutcomeStatus, PreviewSize, RemovalMode,
+    ResolutionSource, RestartAction, StoreSnapshot, UpdateSeverity, VersionComparison,
+    test_support::{FakeComponent, Sandbox},
+};
+
+#[test]
+fn check_finds_update_from_mock_store() {
+    let sandbox = Sandbox::new().unwrap();
+    let clock = FakeComponent::plasmoid("org.example.clock", "Clock", "1.0");
+    sandbox.install(&clock, Some(1000)).unwrap();
+    sandbox.publish(1000, &clock.with_version("2.0")).unwrap();
+
+    let result = libplasmoid_updater::check(&sandbox.config()).unwrap();
+
+    assert_eq!(result.available_updates.len(), 1);
+    let update = &result.available_updates[0];
+    assert_eq!(update.installed.directory_name, "org.example.clock");
+    assert_eq!(update.latest_version, "2.0");
+    assert_eq!(update.content_id, 1000);
+
+    let report = result.to_markdown();
+    assert!(report.contains("| [Clock](https://store.kde.org/p/1000) | 1.0 | 2.0 |"));
+}
+
+#[test]
+fn update_installs_new_version() {
+    let sandbox = Sandbox::new().unwrap();
+    let clock = FakeComponent::plasmoid("org.example.clock", "Clock", "1.0");
+    sandbox.install(&clock, Some(1000)).unwrap();
+    sandbox.publish(1000, &clock.with_version("2.0")).unwrap();
+
+    let result = libplasmoid_updater::update(&sandbox.config()).unwrap();
+
+    assert_eq!(result.succeeded, ["Clock"]);
+    assert!(result.failed.is_empty());
+    assert!(result.unhealthy.is_empty());
+    assert_eq!(sandbox.installed_version(&clock).as_deref(), Some("2.0"));
+
+    let recheck = libplasmoid_updater::check(&sandbox.config()).unwrap();
+    assert!(recheck.available_updates.is_empty());
+}
+
+#[test]
+fn update_runs_are_kept_in_history() {
+    let sandbox = Sandbox::new().unwrap();
+    assert!(libplasmoid_updater::last_run(&sandbox.config()).is_none());
+
+    let clock = FakeComponent::plasmoid("org.example.clock", "Clock", "1.0");
+    sandbox.install(&clock, Some(1000)).unwrap();
+    sandbox.publish(1000, &clock.with_version("2.0")).unwrap();
+    libplasmoid_updater::update(&sandbox.config()).unwrap();
+
+    let run = libplasmoid_updater::last_run(&sandbox.config()).unwrap();
+    assert_eq!(run.components.len(), 1);
+    let component = &run.components[0];
+    assert_eq!(component.name, "Clock");
+    assert_eq!(
+        (
+            component.from_version.as_str(),
+            component.to_version.as_str()
+        ),
+        ("1.0", "2.0")
+    );
+    assert_eq!(component.status, OutcomeStatus::Succeeded);
+    assert!(run.total_bytes() > 0);
+    assert!(run.api_stats.requests > 0);
+    assert!(run.to_html().contains("<td>Clock</td>"));
+    assert!(
+        sandbox
+            .state_home()
+            .join("plasmoid-updater/history")
+            .is_dir()
+    );
+}
+
+#[test]
+fn failed_update_rolls_back() {
+    let sandbox = Sandbox::new().unwrap();
+    let clock = FakeComponent::plasmoid("org.example.clock", "Clock", "1.0");
+    sandbox.install(&clock, Some(1000)).unwrap();
+    sandbox.publish_broken(1000, &clock.with_version("2.0"));
+
+    let result = libplasmoid_updater::update(&sandbox.config()).unwrap();
+
+    assert!(result.succeeded.is_empty());
+    assert_eq!(result.failed.len(), 1);
+    assert_eq!(result.failed[0].operation, Some(Operation::Extract));
+    assert!(result.failed[0].target.is_some());
+    assert_eq!(sandbox.installed_version(&clock).as_deref(), Some("1.0"));
+}
+
+#[test]
+fn unmodelled_registry_entries_update_with_generic_kns() {
+    let sandbox = Sandbox::new().unwrap();
+    let title = FakeComponent {
+        id: "Intro.kdenlivetitle".to_string(),
+        name: "Intro".to_string(),
+        version: "1.0".to_string(),
+        component_type: ComponentType::Other("kdenlive_titles.knsregistry"),
+        main_qml: String::new(),
+        author: "alice".to_string(),
+    };
+    let path = sandbox
+        .data_home()
+        .join("kdenlive/titles/Intro.kdenlivetitle");
+    sandbox.install_raw(&title, &path, b"old", 3000).unwrap();
+    sandbox.publish_raw(3000, &title.with_version("2.0"), b"new");
+
+    let result = libplasmoid_updater::check(&sandbox.config()).unwrap();
+    assert!(result.available_updates.is_empty());
+
+    let config = sandbox.config().with_generic_kns(true);
+    let result = libplasmoid_updater::check(&config).unwrap();
+    assert_eq!(result.available_updates.len(), 1);
+    assert_eq!(
+        result.available_updates[0].installed.component_type,
+        ComponentType::Other("kdenlive_titles.knsregistry")
+    );
+
+    let result = libplasmoid_updater::update(&config).unwrap();
+    assert_eq!(result.succeeded, ["Intro"]);
+    assert_eq!(std::fs::read(&path).unwrap(), b"new");
+    assert!(
+        libplasmoid_updater::check(&config)
+            .unwrap()
+            .available_updates
+            .is_empty()
+    );
+}
+
+#[test]
+fn transient_failures_are_retried_at_end_of_run() {
+    let sandbox = Sandbox::new().unwrap();
+    let clock = FakeComponent::plasmoid("org.example.clock", "Clock", "1.0");
+    sandbox.install(&clock, Some(1000)).unwrap();
+    sandbox.publish(1000, &clock.with_version("2.0")).unwrap();
+
+    sandbox.throttle_downloads(1000, 1);
+    let result = libplasmoid_updater::update(&sandbox.config()).unwrap();
+    assert_eq!(result.failed.len(), 1);
+    assert_eq!(result.failed[0].operation, Some(Operation::Download));
+
+    sandbox.throttle_downloads(1000, 1);
+    let result = libplasmoid_updater::update(&sandbox.config().with_retry_failed(1)).unwrap();
+    assert!(result.failed.is_empty());
+    let outcome = result.outcome("Clock").unwrap();
+    assert_eq!(outcome.status, OutcomeStatus::Succeeded);
+    assert_eq!(outcome.retries, 1);
+    assert_eq!(sandbox.installed_version(&clock).as_deref(), Some("2.0"));
+}
+
+#[test]
+fn update_outcomes_follow_selection_order() {
+    let sandbox = Sandbox::new().unwrap();
+    let clock = FakeComponent::plasmoid("org.example.clock", "Clock", "1.0");
+    let weather = FakeComponent::plasmoid("org.example.weather", "Weather", "1.0");
+    sandbox.install(&clock, Some(1000)).unwrap();
+    sandbox.install(&weather, Some(1001)).unwrap();
+    sandbox.publish(1000, &clock.with_version("2.0")).unwrap();
+    sandbox.publish_broken(1001, &weather.with_version("2.0"));
+    let config = sandbox.config().with_threads(2);
+
+    let order: Vec<String> = libplasmoid_updater::check(&config)
+        .unwrap()
+        .available_updates
+        .into_iter()
+        .map(|u| u.installed.name)
+        .collect();
+    let result = libplasmoid_updater::update(&config).unwrap();
+
+    let names: Vec<&str> = result.outcomes.iter().map(|o| o.name.as_str()).collect();
+    assert_eq!(names, order);
+    let clock = result.outcome("Clock").unwrap();
+    assert_eq!(clock.status, OutcomeStatus::Succeeded);
+    assert!(clock.bytes.is_some_and(|b| b > 0));
+    let weather = result.outcome("Weather").unwrap();
+    assert_eq!(weather.status, OutcomeStatus::Failed);
+    assert!(weather.error.is_some());
+    assert_eq!(result.total_bytes(), clock.bytes.unwrap());
+
+    let json = serde_json::to_value(&result).unwrap();
+    assert!(json["outcomes"][0]["duration_ms"].is_u64());
+}
+
+#[test]
+fn trash_removal_mode_keeps_replaced_version_in_trash() {
+    let sandbox = Sandbox::new().unwrap();
+    let clock = FakeComponent::plasmoid("org.example.clock", "Clock", "1.0");
+    sandbox.install(&clock, Some(1000)).unwrap();
+    sandbox.publish(1000, &clock.with_version("2.0")).unwrap();
+    let config = sandbox.config().with_removal_mode(RemovalMode::Trash);
+
+    libplasmoid_updater::update(&config).unwrap();
+
+    assert_eq!(sandbox.installed_version(&clock).as_deref(), Some("2.0"));
+    let trash = sandbox.data_home().join("Trash");
+    let trashed = trash.join("files/org.example.clock/metadata.json");
+    assert!(
+        std::fs::read_to_string(trashed)
+            .unwrap()
+            .contains("\"1.0\"")
+    );
+    let info = std::fs::read_to_string(trash.join("info/org.example.clock.trashinfo")).unwrap();
+    assert!(info.contains("/plasma/plasmoids/org.example.clock\n"));
+}
+
+#[test]
+fn restart_overrides_replace_type_defaults() {
+    let sandbox = Sandbox::new().unwrap();
+    let clock = FakeComponent::plasmoid("org.example.clock", "Clock", "1.0");
+    let weather = FakeComponent::plasmoid("org.example.weather", "Weather", "1.0");
+    sandbox.install(&clock, Some(1000)).unwrap();
+    sandbox.install(&weather, Some(1001)).unwrap();
+    sandbox.publish(1000, &clock.with_version("2.0")).unwrap();
+    sandbox.publish(1001, &weather.with_version("2.0")).unwrap();
+    let config = sandbox
+        .config()
+        .with_no_restart(vec!["org.example.*".to_string()])
+        .with_restart_required(vec!["Weather".to_string()]);
+
+    let result = libplasmoid_updater::check(&config).unwrap();
+
+    let action = |name: &str| {
+        result
+            .available_updates
+            .iter()
+            .find(|u| u.installed.name == name)
+            .map(|u| u.restart_action)
+    };
+    assert_eq!(action("Clock"), Some(RestartAction::None));
+    assert_eq!(action("Weather"), Some(RestartAction::PlasmashellRestart));
+}
+
+#[test]
+fn widgets_in_plasma_layout_are_in_use() {
+    let sandbox = Sandbox::new().unwrap();
+    let clock = FakeComponent::plasmoid("org.example.clock", "Clock", "1.0");
+    let weather = FakeComponent::plasmoid("org.example.weather", "Weather", "1.0");
+    sandbox.install(&clock, None).unwrap();
+    sandbox.install(&weather, None).unwrap();
+    std::fs::write(
+        sandbox
+            .config_home()
+            .join("plasma-org.kde.plasma.desktop-appletsrc"),
+        "[Containments][2][Applets][5]\nplugin=org.example.clock\n",
+    )
+    .unwrap();
+
+    let installed = libplasmoid_updater::get_installed(&sandbox.config()).unwrap();
+
+    let in_use = |name: &str| installed.iter().find(|c| c.name == name).unwrap().in_use;
+    assert!(in_use("Clock"));
+    assert!(!in_use("Weather"));
+}
+
+#[test]
+fn update_backs_up_plasma_layout_for_widgets() {
+    let sandbox = Sandbox::new().unwrap();
+    let clock = FakeComponent::plasmoid("org.example.clock", "Clock", "1.0");
+    sandbox.install(&clock, Some(1000)).unwrap();
+    sandbox.publish(1000, &clock.with_version("2.0")).unwrap();
+    std::fs::write(
+        sandbox.config_home().join("plasmashellrc"),
+        "[PlasmaViews]\n",
+    )
+    .unwrap();
+
+    let result = libplasmoid_updater::update(&sandbox.config()).unwrap();
+
+    assert_eq!(result.succeeded, ["Clock"]);
+    let backup = result.layout_backup.expect("layout backup");
+    assert!(backup.starts_with(sandbox.state_home()));
+    assert_eq!(
+        std::fs::read_to_string(backup.join("plasmashellrc")).unwrap(),
+        "[PlasmaViews]\n"
+    );
+}
+
+#[test]
+fn check_updates_for_only_queries_named_components() {
+    let sandbox = Sandbox::new().unwrap();
+    let clock = FakeComponent::plasmoid("org.example.clock", "Clock", "1.0");
+    let weather = FakeComponent::plasmoid("org.example.weather", "Weather", "1.0");
+    sandbox.install(&clock, Some(1000)).unwrap();
+    sandbox.install(&weather, Some(1001)).unwrap();
+    sandbox.publish(1000, &clock.with_version("2.0")).unwrap();
+    sandbox.publish(1001, &weather.with_version("2.0")).unwrap();
+
+    let result =
+        libplasmoid_updater::check_updates_for(&["org.example.clock"], &sandbox.config()).unwrap();
+
+    assert_eq!(result.available_updates.len(), 1);
+    assert_eq!(result.available_updates[0].installed.name, "Clock");
+    let requests = sandbox.requests();
+    assert!(requests.iter().any(|r| r.contains("1000")));
+    assert!(!requests.iter().any(|r| r.contains("1001")));
+}
+
+#[test]
+fn streaming_check_reports_every_component_once() {
+    let sandbox = Sandbox::new().unwrap();
+    let clock = FakeComponent::plasmoid("org.example.clock", "Clock", "1.0");
+    let weather = FakeComponent::plasmoid("org.example.weather", "Weather", "2.0");
+    let local = FakeComponent::plasmoid("org.example.local", "Local Only", "1.0");
+    sandbox.install(&clock, Some(1000)).unwrap();
+    sandbox.install(&weather, Some(1001)).unwrap();
+    sandbox.install(&local, None).unwrap();
+    sandbox.publish(1000, &clock.with_version("2.0")).unwrap();
+    sandbox.publish(1001, &weather).unwrap();
+
+    let mut events = Vec::new();
+    let result =
+        libplasmoid_updater::check_updates_streaming(&sandbox.config(), |event| events.push(event))
+            .unwrap();
+
+    let CheckEvent::Discovered(discovered) = &events[0] else {
+        panic!("first event is {:?}", events[0]);
+    };
+    assert_eq!(discovered.len(), 3);
+    let mut outcomes: Vec<String> = events[1..]
+        .iter()
+        .map(|event| match event {
+            CheckEvent::UpdateAvailable(update) => format!("update {}", update.installed.name),
+            CheckEvent::UpToDate(component) => format!("current {}", component.name),
+            CheckEvent::Unresolved(diagnostic) => format!("unresolved {}", diagnostic.name),
+            other => panic!("unexpected {other:?}"),
+        })
+        .collect();
+    outcomes.sort();
+    assert_eq!(
+        outcomes,
+        ["current Weather", "unresolved Local Only", "update Clock"]
+    );
+    assert_eq!(result.available_updates.len(), 1);
+    assert_eq!(result.diagnostics.len(), 1);
+}
+
+#[test]
+fn results_count_store_requests_and_traffic() {
+    let sandbox = Sandbox::new().unwrap();
+    let clock = FakeComponent::plasmoid("org.example.clock", "Clock", "1.0");
+    sandbox.install(&clock, Some(1000)).unwrap();
+    sandbox.publish(1000, &clock.with_version("2.0")).unwrap();
+
+    let check = libplasmoid_updater::check(&sandbox.config()).unwrap();
+    assert_eq!(check.api_stats.requests, sandbox.requests().len());
+    assert!(check.api_stats.bytes_downloaded > 0);
+    assert_eq!(check.api_stats.retries, 0);
+
+    let result = libplasmoid_updater::update(&sandbox.config()).unwrap();
+    let package = result.outcome("Clock").unwrap().bytes.unwrap();
+    assert!(result.api_stats.requests > check.api_stats.requests);
+    assert!(result.api_stats.bytes_downloaded >= check.api_stats.bytes_downloaded + package);
+}
+
+#[test]
+fn pipelined_downloads_fetch_each_package_once() {
+    let sandbox = Sandbox::new().unwrap();
+    let clock = FakeComponent::plasmoid("org.example.clock", "Clock", "1.0");
+    let weather = FakeComponent::plasmoid("org.example.weather", "Weather", "1.0");
+    sandbox.install(&clock, Some(1000)).unwrap();
+    sandbox.install(&weather, Some(1001)).unwrap();
+    sandbox.publish(1000, &clock.with_version("2.0")).unwrap();
+    sandbox.publish(1001, &weather.with_version("3.0")).unwrap();
+
+    let config = sandbox.config().with_pipeline_downloads(true);
+    let result = libplasmoid_updater::update(&config).unwrap();
+
+    assert_eq!(result.succeeded, ["Clock", "Weather"]);
+    assert_eq!(sandbox.installed_version(&weather).as_deref(), Some("3.0"));
+    let downloads: Vec<String> = sandbox
+        .requests()
+        .into_iter()
+        .filter(|r| r.starts_with("/files/"))
+        .collect();
+    assert_eq!(downloads.len(), 2, "{downloads:?}");
+    assert_eq!(result.api_stats.requests, sandbox.requests().len());
+}
+
+#[test]
+fn unchanged_components_are_not_checked_again() {
+    let sandbox = Sandbox::new().unwrap();
+    let clock = FakeComponent::plasmoid("org.example.clock", "Clock", "1.0");
+    sandbox.install(&clock, Some(1000)).unwrap();
+    sandbox.publish(1000, &clock).unwrap();
+
+    let first = libplasmoid_updater::check(&sandbox.config()).unwrap();
+    assert!(first.available_updates.is_empty());
+    let requests = sandbox.requests().len();
+
+    sandbox.publish(1000, &clock.with_version("2.0")).unwrap();
+    let second = libplasmoid_updater::check(&sandbox.config()).unwrap();
+    assert!(second.available_updates.is_empty());
+    assert_eq!(sandbox.requests().len(), requests);
+
+    let full = libplasmoid_updater::check(&sandbox.config().with_full_scan(true)).unwrap();
+    assert_eq!(full.available_updates.len(), 1);
+    libplasmoid_updater::update(&sandbox.config().with_full_scan(true)).unwrap();
+
+    // The update changed the component, so it is checked again.
+    sandbox.publish(1000, &clock.with_version("3.0")).unwrap();
+    let after = libplasmoid_updater::check(&sandbox.config()).unwrap();
+    assert_eq!(after.available_updates[0].latest_version, "3.0");
+}
+
+#[test]
+fn update_caches_downloads_until_cleaned() {
+    let sandbox = Sandbox::new().unwrap();
+    let clock = FakeComponent::plasmoid("org.example.clock", "Clock", "1.0");
+    sandbox.install(&clock, Some(1000)).unwrap();
+    sandbox.publish(1000, &clock.with_version("2.0")).unwrap();
+
+    let result = libplasmoid_updater::update(&sandbox.config()).unwrap();
+
+    assert_eq!(result.succeeded, ["Clock"]);
+    let downloads = sandbox.cache_home().join("plasmoid-updater/downloads");
+    assert_eq!(std::fs::read_dir(&downloads).unwrap().count(), 1);
+    assert!(libplasmoid_updater::clean_cache().unwrap() > 0);
+    assert!(!downloads.exists());
+}
+
+#[test]
+fn previews_are_downloaded_once_and_cleaned_with_the_cache() {
+    let sandbox = Sandbox::new().unwrap();
+    let clock = FakeComponent::plasmoid("org.example.clock", "Clock", "1.0");
+    sandbox.publish(1000, &clock).unwrap();
+    let config = sandbox.config();
+
+    let small = libplasmoid_updater::download_preview(1000, PreviewSize::Small, &config).unwrap();
+    let large = libplasmoid_updater::download_preview(1000, PreviewSize::Large, &config).unwrap();
+
+    let previews = sandbox.cache_home().join("plasmoid-updater/previews");
+    assert!(small.starts_with(&previews) && large.starts_with(&previews));
+    assert_eq!(std::fs::read(&small).unwrap(), b"preview 1000-small.png");
+    assert_ne!(small, large);
+
+    let requests = sandbox.requests().len();
+    let again = libplasmoid_updater::download_preview(1000, PreviewSize::Small, &config).unwrap();
+    assert_eq!(again, small);
+    assert!(
+        !sandbox.requests()[requests..]
+            .iter()
+            .any(|path| path.starts_with("/previews/"))
+    );
+
+    assert!(libplasmoid_updater::clean_cache().unwrap() > 0);
+    assert!(!previews.exists());
+}
+
+#[test]
+fn global_theme_updates_bring_missing_companions() {
+    let sandbox = Sandbox::new().unwrap();
+    let theme = FakeComponent {
+        component_type: ComponentType::GlobalTheme,
+        ..FakeComponent::plasmoid("org.example.desktop", "Example Desktop", "1.0")
+    };
+    let style = FakeComponent {
+        component_type: ComponentType::PlasmaStyle,
+        ..FakeComponent::plasmoid("example-style", "Example Style", "1.0")
+    };
+    let path = sandbox.install(&theme, Some(1000)).unwrap();
+    std::fs::write(
+        path.join("contents/defaults"),
+        "[plasmarc][Theme]\nname=example-style\n\n[ksplashrc][KSplash]\nTheme=org.example.desktop\n",
+    )
+    .unwrap();
+    sandbox.publish(1000, &theme.with_version("2.0")).unwrap();
+    sandbox.publish(1001, &style).unwrap();
+
+    let check = libplasmoid_updater::check(&sandbox.config()).unwrap();
+    assert_eq!(
+        check.available_updates[0].suggested_companions,
+        [Companion {
+            component_type: ComponentType::PlasmaStyle,
+            name: "example-style".to_string(),
+            installed: false,
+        }]
+    );
+
+    let result = libplasmoid_updater::update(
+        &sandbox
+            .config()
+            .with_full_scan(true)
+            .with_install_companions(true),
+    )
+    .unwrap();
+
+    assert_eq!(result.succeeded, ["Example Desktop", "Example Style"]);
+    assert!(
+        sandbox
+            .component_path(&style)
+            .join("metadata.json")
+            .is_file()
+    );
+}
+
+#[test]
+fn updates_writing_to_the_same_files_are_refused() {
+    let sandbox = Sandbox::new().unwrap();
+    let clock = FakeComponent::plasmoid("org.example.clock", "Clock", "1.0");
+    let skin = FakeComponent {
+        id: "skin.svg".to_string(),
+        component_type: ComponentType::Other("clock_skins.knsregistry"),
+        ..FakeComponent::plasmoid("skin.svg", "Clock Skin", "1.0")
+    };
+    let clock_path = sandbox.install(&clock, Some(1000)).unwrap();
+    let skin_path = clock_path.join("contents/skin.svg");
+    sandbox
+        .install_raw(&skin, &skin_path, b"old", 3000)
+        .unwrap();
+    sandbox.publish(1000, &clock.with_version("2.0")).unwrap();
+    sandbox.publish_raw(3000, &skin.with_version("2.0"), b"new");
+    let config = sandbox.config().with_generic_kns(true);
+
+    let err = libplasmoid_updater::update(&config).unwrap_err();
+
+    let Error::FileConflicts(conflicts) = &err else {
+        panic!("expected a file conflict, got {err}");
+    };
+    assert_eq!(conflicts[0].path, clock_path);
+    assert_eq!(conflicts[0].components.len(), 2);
+    assert_eq!(sandbox.installed_version(&clock).as_deref(), Some("1.0"));
+    assert_eq!(std::fs::read(&skin_path).unwrap(), b"old");
+
+    let warned = config
+        .with_full_scan(true)
+        .with_conflict_policy(ConflictPolicy::Warn);
+    assert!(libplasmoid_updater::update(&warned).is_ok());
+    assert_eq!(sandbox.installed_version(&clock).as_deref(), Some("2.0"));
+}
+
+#[test]
+fn doctor_reports_tools_and_store_health() {
+    let sandbox = Sandbox::new().unwrap();
+
+    let report = libplasmoid_updater::doctor(&sandbox.config());
+
+    let status = |name: &str| {
+        report
+            .checks
+            .iter()
+            .find(|c| c.name == name)
+            .unwrap()
+            .status
+    };
+    assert_eq!(report.library_version, libplasmoid_updater::VERSION);
+    assert_ne!(status("plasma"), DoctorStatus::Failed);
+    assert_eq!(status("kpackagetool6"), DoctorStatus::Ok);
+    assert_eq!(status("knewstuff registry"), DoctorStatus::Ok);
+    assert_eq!(status("store api"), DoctorStatus::Ok);
+    assert!(report.api_latency_ms.is_some());
+}
+
+#[test]
+fn cancelled_update_skips_pending_components() {
+    let sandbox = Sandbox::new().unwrap();
+    let clock = FakeComponent::plasmoid("org.example.clock", "Clock", "1.0");
+    sandbox.install(&clock, Some(1000)).unwrap();
+    sandbox.publish(1000, &clock.with_version("2.0")).unwrap();
+
+    let token = CancelToken::new();
+    token.cancel();
+    let config = sandbox.config().with_cancel_token(token);
+    let result = libplasmoid_updater::update(&config).unwrap();
+
+    assert!(result.succeeded.is_empty());
+    assert_eq!(result.skipped, ["Clock"]);
+    assert_eq!(sandbox.installed_version(&clock).as_deref(), Some("1.0"));
+}
+
+#[test]
+fn failing_pre_update_hook_skips_install() {
+    let sandbox = Sandbox::new().unwrap();
+    let clock = FakeComponent::plasmoid("org.example.clock", "Clock", "1.0");
+    sandbox.install(&clock, Some(1000)).unwrap();
+    sandbox.publish(1000, &clock.with_version("2.0")).unwrap();
+
+    let log = sandbox.root().join("hook.log");
+    let hooks = Hooks::new()
+        .with_pre_update("exit 1")
+        .with_on_failure(format!("echo \"$NEW_VERSION\" > {}", log.display()));
+    let result = libplasmoid_updater::update(&sandbox.config().with_hooks(hooks)).unwrap();
+
+    assert_eq!(result.failed.len(), 1);
+    assert!(result.failed[0].error.contains("pre_update"));
+    assert_eq!(sandbox.installed_version(&clock).as_deref(), Some("1.0"));
+    assert_eq!(std::fs::read_to_string(log).unwrap(), "2.0\n");
+}
+
+#[test]
+fn update_summary_is_pushed_to_notifiers() {
+    let sandbox = Sandbox::new().unwrap();
+    let clock = FakeComponent::plasmoid("org.example.clock", "Clock", "1.0");
+    sandbox.install(&clock, Some(1000)).unwrap();
+    sandbox.publish(1000, &clock.with_version("2.0")).unwrap();
+
+    let notifiers = vec![
+        Notifier::Ntfy {
+            url: format!("{}/ntfy/updates", sandbox.server_url()),
+            token: None,
+        },
+        Notifier::Gotify {
+            url: format!("{}/gotify/", sandbox.server_url()),
+            token: "secret".to_string(),
+        },
+    ];
+    let result = libplasmoid_updater::update(&sandbox.config().with_notifiers(notifiers)).unwrap();
+
+    // The mock store answers 404, which is only logged.
+    assert_eq!(result.succeeded, ["Clock"]);
+    let requests = sandbox.requests();
+    assert!(requests.iter().any(|r| r == "/ntfy/updates"));
+    assert!(requests.iter().any(|r| r == "/gotify/message"));
+}
+
+#[test]
+fn oversized_download_is_refused() {
+    let sandbox = Sandbox::new().unwrap();
+    let clock = FakeComponent::plasmoid("org.example.clock", "Clock", "1.0");
+    sandbox.install(&clock, Some(1000)).unwrap();
+    sandbox.publish(1000, &clock.with_version("2.0")).unwrap();
+
+    let config = sandbox.config().with_max_download_size(Some(16));
+    let result = libplasmoid_updater::update(&config).unwrap();
+
+    assert_eq!(result.failed.len(), 1);
+    assert!(result.failed[0].error.contains("size limit"));
+    assert_eq!(sandbox.installed_version(&clock).as_deref(), Some("1.0"));
+}
+
+#[test]
+fn downgrade_restores_retained_version() {
+    let sandbox = Sandbox::new().unwrap();
+    let clock = FakeComponent::plasmoid("org.example.clock", "Clock", "1.0");
+    sandbox.install(&clock, Some(1000)).unwrap();
+    sandbox.publish(1000, &clock.with_version("2.0")).unwrap();
+
+    let config = sandbox.config().with_keep_versions(2);
+    libplasmoid_updater::update(&config).unwrap();
+    assert_eq!(sandbox.installed_version(&clock).as_deref(), Some("2.0"));
+
+    let retained = libplasmoid_updater::retained_versions("Clock", &config).unwrap();
+    let versions: Vec<&str> = retained.iter().map(|r| r.version.as_str()).collect();
+    assert_eq!(versions.len(), 2);
+    assert!(versions.contains(&"1.0") && versions.contains(&"2.0"));
+
+    libplasmoid_updater::downgrade("org.example.clock", "1.0", &config).unwrap();
+    assert_eq!(sandbox.installed_version(&clock).as_deref(), Some("1.0"));
+
+    assert!(libplasmoid_updater::downgrade("Clock", "0.5", &config).is_err());
+}
+
+#[test]
+fn check_against_snapshot_ignores_newer_store_releases() {
+    let sandbox = Sandbox::new().unwrap();
+    let clock = FakeComponent::plasmoid("org.example.clock", "Clock", "1.0");
+    sandbox.install(&clock, Some(1000)).unwrap();
+    sandbox.publish(1000, &clock.with_version("2.0")).unwrap();
+
+    let snapshot = libplasmoid_updater::create_snapshot(&sandbox.config()).unwrap();
+    let path = sandbox.root().join("snapshot.json");
+    snapshot.save(&path).unwrap();
+
+    sandbox.publish(1000, &clock.with_version("3.0")).unwrap();
+    let requests = sandbox.requests().len();
+
+    let snapshot = StoreSnapshot::load(&path).unwrap();
+    let config = sandbox.config().with_store_snapshot(snapshot);
+    let result = libplasmoid_updater::check(&config).unwrap();
+
+    assert_eq!(result.available_updates.len(), 1);
+    assert_eq!(result.available_updates[0].latest_version, "2.0");
+    assert_eq!(sandbox.requests().len(), requests);
+}
+
+#[test]
+fn unhealthy_update_is_reported_or_rolled_back() {
+    let sandbox = Sandbox::new().unwrap();
+    let clock = FakeComponent::plasmoid("org.example.clock", "Clock", "1.0");
+    sandbox.install(&clock, Some(1000)).unwrap();
+    let broken = clock.with_version("2.0").with_main_qml("Item {\n");
+    sandbox.publish(1000, &broken).unwrap();
+
+    let config = sandbox.config().with_rollback_unhealthy(true);
+    let result = libplasmoid_updater::update(&config).unwrap();
+
+    assert_eq!(result.failed.len(), 1);
+    assert!(result.failed[0].error.contains("contents/ui/main.qml"));
+    assert_eq!(sandbox.installed_version(&clock).as_deref(), Some("1.0"));
+
+    let result = libplasmoid_updater::update(&sandbox.config()).unwrap();
+
+    assert_eq!(result.succeeded, ["Clock"]);
+    assert_eq!(result.unhealthy.len(), 1);
+    assert_eq!(sandbox.installed_version(&clock).as_deref(), Some("2.0"));
+}
+
+#[test]
+fn known_broken_update_is_held() {
+    let sandbox = Sandbox::new().unwrap();
+    let clock = FakeComponent::plasmoid("org.example.clock", "Clock", "1.0");
+    sandbox.install(&clock, Some(1000)).unwrap();
+    sandbox.publish(1000, &clock.with_version("2.0")).unwrap();
+    sandbox
+        .publish_advisories(r#"{"1000": [{"version": "2.0", "reason": "crashes plasmashell"}]}"#);
+    let config = sandbox
+        .config()
+        .with_advisories_url(Some(sandbox.advisories_url()));
+
+    let check = libplasmoid_updater::check(&config).unwrap();
+    assert!(check.available_updates.is_empty());
+    assert_eq!(check.held.len(), 1);
+    assert_eq!(check.held[0].reason, "crashes plasmashell");
+
+    let result = libplasmoid_updater::update(&config).unwrap();
+    assert!(result.succeeded.is_empty());
+    assert_eq!(result.skipped, ["Clock"]);
+    assert_eq!(sandbox.installed_version(&clock).as_deref(), Some("1.0"));
+
+    let ignoring = libplasmoid_updater::check(&config.with_advisories_url(None)).unwrap();
+    assert_eq!(ignoring.available_updates.len(), 1);
+}
+
+#[test]
+fn auto_apply_leaves_larger_updates_for_review() {
+    let sandbox = Sandbox::new().unwrap();
+    let clock = FakeComponent::plasmoid("org.example.clock", "Clock", "1.0.0");
+    let notes = FakeComponent::plasmoid("org.example.notes", "Notes", "1.0.0");
+    sandbox.install(&clock, Some(1000)).unwrap();
+    sandbox.install(&notes, Some(1001)).unwrap();
+    sandbox.publish(1000, &clock.with_version("1.0.1")).unwrap();
+    sandbox.publish(1001, &notes.with_version("2.0.0")).unwrap();
+    let config = sandbox
+        .config()
+        .with_auto_apply(Some(UpdateSeverity::Patch));
+
+    let check = libplasmoid_updater::check(&config).unwrap();
+    let severity = |name: &str| {
+        let updates = &check.available_updates;
+        updates
+            .iter()
+            .find(|u| u.installed.name == name)
+            .unwrap()
+            .severity
+    };
+    assert_eq!(severity("Clock"), UpdateSeverity::Patch);
+    assert_eq!(severity("Notes"), UpdateSeverity::Major);
+
+    let result = libplasmoid_updater::update(&config).unwrap();
+    assert_eq!(result.succeeded, ["Clock"]);
+    assert_eq!(result.skipped, ["Notes"]);
+    assert_eq!(sandbox.installed_version(&notes).as_deref(), Some("1.0.0"));
+}
+
+#[test]
+fn author_change_is_flagged_and_held_for_confirmation() {
+    let sandbox = Sandbox::new().unwrap();
+    let clock = FakeComponent::plasmoid("org.example.clock", "Clock", "1.0");
+    sandbox.install(&clock, Some(1000)).unwrap();
+    sandbox.publish(1000, &clock).unwrap();
+    let config = sandbox
+        .config()
+        .with_full_scan(true)
+        .with_confirm_author_changes(true);
+
+    let check = libplasmoid_updater::check(&config).unwrap();
+    assert!(check.available_updates.is_empty());
+
+    sandbox
+        .publish(1000, &clock.with_version("2.0").with_author("mallory"))
+        .unwrap();
+    let check = libplasmoid_updater::check(&config).unwrap();
+    let update = &check.available_updates[0];
+    assert_eq!(update.author, "mallory");
+    assert_eq!(update.previous_author.as_deref(), Some("alice"));
+
+    let result = libplasmoid_updater::update(&config).unwrap();
+    assert_eq!(result.skipped, ["Clock"]);
+    assert_eq!(sandbox.installed_version(&clock).as_deref(), Some("1.0"));
+
+    let trusting = config
+        .clone()
+        .with_trusted_authors(vec!["mallory".to_string()]);
+    let result = libplasmoid_updater::update(&trusting).unwrap();
+    assert_eq!(result.succeeded, ["Clock"]);
+    let check = libplasmoid_updater::check(&config).unwrap();
+    assert!(check.available_updates.is_empty());
+}
+
+#[test]
+fn explain_traces_resolution_comparison_and_holds() {
+    let sandbox = Sandbox::new().unwrap();
+    let clock = FakeComponent::plasmoid("org.example.clock", "Clock", "1.0");
+    let notes = FakeComponent::plasmoid("org.example.notes", "Notes", "1.0");
+    sandbox.install(&clock, Some(1000)).unwrap();
+    sandbox.install(&notes, None).unwrap();
+    sandbox.publish(1000, &clock.with_version("2.0")).unwrap();
+    sandbox
+        .publish_advisories(r#"{"1000": [{"version": "2.0", "reason": "crashes plasmashell"}]}"#);
+
+    let explanation = libplasmoid_updater::explain_component("Clock", &sandbox.config()).unwrap();
+    assert_eq!(explanation.resolution, Some(ResolutionSource::Registry));
+    assert_eq!(explanation.content_id, Some(1000));
+    assert_eq!(explanation.store_version.as_deref(), Some("2.0"));
+    assert_eq!(
+        explanation.comparison,
+        Some(VersionComparison::NewerVersion)
+    );
+    assert!(explanation.update_offered());
+    assert_eq!(
+        explanation.trace[0],
+        "KNewStuff registry records content ID 1000"
+    );
+
+    let config = sandbox
+        .config()
+        .with_advisories_url(Some(sandbox.advisories_url()))
+        .with_excluded_packages(vec!["org.example.clock".to_string()]);
+    let held = libplasmoid_updater::explain_component("org.example.clock", &config).unwrap();
+    assert_eq!(
+        held.blockers,
+        [
+            Blocker::Excluded,
+            Blocker::Advisory {
+                reason: "crashes plasmashell".to_string()
+            }
+        ]
+    );
+    assert!(!held.update_offered());
+
+    let unmatched = libplasmoid_updater::explain_component("Notes", &sandbox.config()).unwrap();
+    assert_eq!(unmatched.resolution, None);
+    assert_eq!(unmatched.summary(), "not matched to a KDE Store entry");
+    assert!(matches!(
+        libplasmoid_updater::explain_component("Weather", &sandbox.config()),
+        Err(Error::ComponentNotFound(_))
+    ));
+}
+
+#[test]
+fn deleted_store_entry_is_reported_as_orphan() {
+    let sandbox = Sandbox::new().unwrap();
+    let clock = FakeComponent::plasmoid("org.example.clock", "Clock", "1.0");
+    sandbox.install(&clock, Some(1000)).unwrap();
+
+    let check = libplasmoid_updater::check(&sandbox.config()).unwrap();
+    assert_eq!(check.diagnostics.len(), 1);
+    assert_eq!(check.diagnostics[0].kind, DiagnosticKind::StoreEntryGone);
+
+    let orphans = libplasmoid_updater::orphans(&sandbox.config()).unwrap();
+    assert_eq!(orphans.len(), 1);
+    assert_eq!(orphans[0].name, "Clock");
+    assert_eq!(orphans[0].content_id, Some(1000));
+}
+
+#[test]
+fn packages_with_broken_metadata_are_reported_not_skipped() {
+    let sandbox = Sandbox::new().unwrap();
+    let clock = FakeComponent::plasmoid("org.example.clock", "Clock", "1.0");
+    let broken = FakeComponent::plasmoid("org.example.broken", "Broken", "1.0");
+    sandbox.install(&clock, Some(1000)).unwrap();
+    sandbox.publish(1000, &clock).unwrap();
+    let path = sandbox.install(&broken, None).unwrap();
+    std::fs::write(path.join("metadata.json"), "{\"KPlugin\": ").unwrap();
+    // Not a package at all: no contents/ and no metadata.
+    std::fs::create_dir_all(path.with_file_name("notes")).unwrap();
+
+    let unrecognized = libplasmoid_updater::find_unrecognized(&sandbox.config());
+    assert_eq!(unrecognized.len(), 1);
+    let component = &unrecognized[0].component;
+    assert_eq!(component.name, "org.example.broken");
+    assert_eq!(component.version, "unknown");
+    assert_eq!(component.component_type, ComponentType::PlasmaWidget);
+    assert!(
+        unrecognized[0]
+            .reason
+            .starts_with("metadata.json is invalid")
+    );
+
+    let check = libplasmoid_updater::check(&sandbox.config()).unwrap();
+    assert_eq!(check.diagnostics.len(), 1);
+    assert_eq!(check.diagnostics[0].kind, DiagnosticKind::InvalidMetadata);
+    assert_eq!(check.diagnostics[0].name, "org.example.broken");
+}
+
+#[test]
+fn list_installed_filters_outdated_and_orphaned_components() {
+    let sandbox = Sandbox::new().unwrap();
+    let clock = FakeComponent::plasmoid("org.example.clock", "Clock", "1.0");
+    let gone = FakeComponent::plasmoid("org.example.gone", "Gone", "1.0");
+    sandbox.install(&clock, Some(1000)).unwrap();
+    sandbox.install(&gone, Some(2000)).unwrap();
+    sandbox.publish(1000, &clock.with_version("2.0")).unwrap();
+
+    let names = |status| {
+        let query = InstalledQuery {
+            status,
+            ..Default::default()
+        };
+        libplasmoid_updater::list_installed(&sandbox.config(), query)
+            .unwrap()
+            .into_iter()
+            .map(|c| c.name)
+            .collect::<Vec<_>>()
+    };
+
+    assert_eq!(names(InstalledStatus::All), ["Clock", "Gone"]);
+    assert_eq!(names(InstalledStatus::Outdated), ["Clock"]);
+    assert_eq!(names(InstalledStatus::Orphaned), ["Gone"]);
+}
+
+#[test]
+fn inspect_component_merges_registry_store_and_history() {
+    let sandbox = Sandbox::new().unwrap();
+    let clock = FakeComponent::plasmoid("org.example.clock", "Clock", "1.0");
+    sandbox.install(&clock, Some(1000)).unwrap();
+    sandbox.publish(1000, &clock.with_version("2.0")).unwrap();
+    let config = sandbox
+        .config()
+        .with_keep_versions(2)
+        .with_excluded_packages(vec!["org.example.*".to_string()]);
+
+    let report = libplasmoid_updater::inspect_component("Clock", &config).unwrap();
+    let registry = report.registry.unwrap();
+    assert_eq!(registry.content_id, Some(1000));
+    assert_eq!(registry.version, "1.0");
+    let store = report.store.unwrap();
+    assert_eq!(store.latest_version, "2.0");
+    assert!(store.update_available);
+    assert_eq!(store.downloads.len(), 1);
+    assert!(report.excluded);
+    assert!(report.backups.is_empty());
+
+    libplasmoid_updater::update(&sandbox.config().with_keep_versions(2)).unwrap();
+
+    let report = libplasmoid_updater::inspect_component("org.example.clock", &config).unwrap();
+    assert_eq!(report.installed.version, "2.0");
+    assert!(!report.store.unwrap().update_available);
+    assert_eq!(report.backups.len(), 1);
+    assert_eq!(report.retained.len(), 2);
+
+    assert!(libplasmoid_updater::inspect_component("Missing", &config).is_err());
+}
+
+#[test]
+fn apply_converges_on_manifest() {
+    let sandbox = Sandbox::new().unwrap();
+    let clock = FakeComponent::plasmoid("org.example.clock", "Clock", "1.0");
+    let weather = FakeComponent::plasmoid("org.example.weather", "Weather", "3.0");
+    let gone = FakeComponent::plasmoid("org.example.gone", "Gone", "1.0");
+    sandbox.install(&clock, Some(1000)).unwrap();
+    sandbox.install(&gone, Some(3000)).unwrap();
+    sandbox.publish(1000, &clock.with_version("2.0")).unwrap();
+    sandbox.publish(2000, &weather).unwrap();
+    sandbox.publish(3000, &gone).unwrap();
+
+    let entry = |content_id| ManifestEntry {
+        content_id,
+        name: None,
+        component_type: None,
+    };
+    let manifest = Manifest {
+        components: vec![entry(1000), entry(2000)],
+    };
+
+    let result = libplasmoid_updater::apply(&manifest, true, &sandbox.config()).unwrap();
+    assert!(result.changed());
+    assert!(result.failed.is_empty(), "{:?}", result.failed);
+    assert_eq!(result.updated, ["Clock"]);
+    assert_eq!(result.installed, ["Weather"]);
+    assert_eq!(result.removed, ["Gone"]);
+    assert_eq!(sandbox.installed_version(&clock).as_deref(), Some("2.0"));
+    assert_eq!(sandbox.installed_version(&weather).as_deref(), Some("3.0"));
+    assert!(!sandbox.component_path(&gone).exists());
+
+    let again = libplasmoid_updater::apply(&manifest, true, &sandbox.config()).unwrap();
+    assert!(!again.changed());
+    assert_eq!(again.unchanged.len(), 2);
+}
+
+#[test]
+fn install_from_file_links_package_to_store() {
+    let sandbox = Sandbox::new().unwrap();
+    let weather = FakeComponent::plasmoid("org.example.weather", "Weather", "1.0");
+    let notes = FakeComponent::plasmoid("org.example.notes", "Notes", "1.0");
+    sandbox.publish(2000, &weather.with_version("2.0")).unwrap();
+
+    let weather_file = sandbox.root().join("weather.plasmoid");
+    let notes_file = sandbox.root().join("notes.plasmoid");
+    sandbox.write_archive(&weather, &weather_file).unwrap();
+    sandbox.write_archive(&notes, &notes_file).unwrap();
+
+    let config = sandbox.config();
+    let result =
+        libplasmoid_updater::install_from_file(&weather_file, ComponentType::PlasmaWidget, &config)
+            .unwrap();
+    assert_eq!(result.component.name, "Weather");
+    assert_eq!(result.content_id, Some(2000));
+    assert_eq!(sandbox.installed_version(&weather).as_deref(), Some("1.0"));
+
+    let unlisted =
+        libplasmoid_updater::install_from_file(&notes_file, ComponentType::PlasmaWidget, &config)
+            .unwrap();
+    assert_eq!(unlisted.content_id, None);
+    assert!(sandbox.component_path(&notes).exists());
+
+    let check = libplasmoid_updater::check(&config).unwrap();
+    assert_eq!(check.available_updates.len(), 1);
+    assert_eq!(check.available_updates[0].latest_version, "2.0");
+
+    let again =
+        libplasmoid_updater::install_from_file(&notes_file, ComponentType::PlasmaWidget, &config);
+    assert!(matches!(again, Err(Error::InstallFailed(_))));
+}
+
+#[test]
+fn adopt_registers_manual_installs_once() {
+    let sandbox = Sandbox::new().unwrap();
+    let clock = FakeComponent::plasmoid("org.example.clock", "Clock", "1.0");
+    let weather = FakeComponent::plasmoid("org.example.weather", "Weather", "1.0");
+    sandbox.install(&clock, None).unwrap();
+    sandbox.install(&weather, None).unwrap();
+    sandbox.publish(1000, &clock).unwrap();
+    sandbox.publish(2000, &weather.with_version("2.0")).unwrap();
+    let config = sandbox.config();
+
+    let result = libplasmoid_updater::adopt(None, &config).unwrap();
+    let mut adopted: Vec<_> = result
+        .adopted
+        .iter()
+        .map(|c| (c.directory_name.as_str(), c.content_id))
+        .collect();
+    adopted.sort_unstable();
+    assert_eq!(
+        adopted,
+        [("org.example.clock", 1000), ("org.example.weather", 2000)]
+    );
+    assert!(result.unresolved.is_empty());
+
+    // Only the component matching the store version gets its release date.
+    let installed =
+        libplasmoid_updater::list_installed(&config, InstalledQuery::default()).unwrap();
+    let release_date = |id: &str| {
+        installed
+            .iter()
+            .find(|c| c.directory_name == id)
+            .map(|c| c.release_date.clone())
+            .unwrap()
+    };
+    assert!(!release_date("org.example.clock").is_empty());
+    assert_eq!(release_date("org.example.weather"), "");
+
+    let registry = sandbox.data_home().join("knewstuff3/plasmoids.knsregistry");
+    let registered = std::fs::read_to_string(&registry).unwrap();
+    assert!(
+        libplasmoid_updater::adopt(None, &config)
+            .unwrap()
+            .is_empty()
+    );
+    assert_eq!(std::fs::read_to_string(&registry).unwrap(), registered);
+
+    let check = libplasmoid_updater::check(&config).unwrap();
+    assert_eq!(check.available_updates.len(), 1);
+    assert_eq!(check.available_updates[0].content_id, 2000);
+}
+
+#[test]
+fn verify_reports_files_changed_since_update() {
+    let sandbox = Sandbox::new().unwrap();
+    let clock = FakeComponent::plasmoid("org.example.clock", "Clock", "1.0");
+    let path = sandbox.install(&clock, Some(1000)).unwrap();
+    sandbox.publish(1000, &clock.with_version("2.0")).unwrap();
+    let config = sandbox.config();
+
+    let before = libplasmoid_updater::verify(Some("Clock"), &config).unwrap();
+    assert_eq!(before[0].recorded_version, None);
+
+    libplasmoid_updater::update(&config).unwrap();
+    let clean = libplasmoid_updater::verify(Some("Clock"), &config).unwrap();
+    assert!(clean[0].is_intact(), "{:?}", clean[0]);
+    assert_eq!(clean[0].recorded_version.as_deref(), Some("2.0"));
+
+    std::fs::write(path.join("contents/ui/main.qml"), "Item {}").unwrap();
+    std::fs::write(path.join("notes.txt"), "").unwrap();
+    let changed = libplasmoid_updater::verify(None, &config).unwrap();
+    assert!(!changed[0].is_intact());
+    assert_eq!(changed[0].modified, ["contents/ui/main.qml"]);
+    assert_eq!(changed[0].extra, ["notes.txt"]);
+    assert!(changed[0].missing.is_empty());
+}
+
+#[test]
+fn reinstall_refuses_a_swapped_payload() {
+    let sandbox = Sandbox::new().unwrap();
+    let clock = FakeComponent::plasmoid("org.example.clock", "Clock", "1.0");
+    let path = sandbox.install(&clock, Some(1000)).unwrap();
+    let released = clock.with_version("2.0");
+    sandbox.publish(1000, &released).unwrap();
+    let config = sandbox.config();
+
+    libplasmoid_updater::update(&config).unwrap();
+    let run = libplasmoid_updater::last_run(&config).unwrap();
+    let sha256 = run.components[0].sha256.clone().unwrap();
+    assert_eq!(sha256.len(), 64);
+
+    std::fs::write(path.join("contents/ui/main.qml"), "Item {}").unwrap();
+    let pin = libplasmoid_updater::reinstall("Clock", &config).unwrap();
+    assert_eq!(pin.version, "2.0");
+    assert_eq!(pin.sha256, sha256);
+    let report = libplasmoid_updater::verify(Some("Clock"), &config).unwrap();
+    assert!(report[0].is_intact(), "{:?}", report[0]);
+
+    sandbox
+        .publish(
+            1000,
+            &released.with_main_qml("import QtQuick\n\nRectangle {}\n"),
+        )
+        .unwrap();
+    let swapped = libplasmoid_updater::reinstall("Clock", &config).unwrap_err();
+    assert!(
+        matches!(swapped.root_cause(), Error::PayloadChanged { version, .. } if version == "2.0"),
+        "{swapped}"
+    );
+    assert_eq!(
+        std::fs::read_to_string(path.join("contents/ui/main.qml")).unwrap(),
+        clock.main_qml
+    );
+}
+
+#[test]
+fn runs_write_prometheus_metrics() {
+    let sandbox = Sandbox::new().unwrap();
+    let clock = FakeComponent::plasmoid("org.example.clock", "Clock", "1.0");
+    let notes = FakeComponent::plasmoid("org.example.notes", "Notes", "1.0");
+    sandbox.install(&clock, Some(1000)).unwrap();
+    sandbox.install(&notes, Some(1001)).unwrap();
+    sandbox.publish(1000, &clock.with_version("2.0")).unwrap();
+    sandbox.publish_broken(1001, &notes.with_version("2.0"));
+    let path = sandbox.root().join("textfile/plasmoid_updater.prom");
+    let config = sandbox.config().with_metrics_file(Some(path.clone()));
+
+    libplasmoid_updater::check(&config).unwrap();
+    let checked = Metrics::read(&path).unwrap();
+    assert_eq!(checked.updates_available, 2);
+    assert_eq!(checked.failed_updates, 0);
+    assert!(checked.last_check_timestamp > 0);
+
+    libplasmoid_updater::update(&config).unwrap();
+    let updated = Metrics::read(&path).unwrap();
+    assert_eq!(updated.updates_available, 1);
+    assert_eq!(updated.failed_updates, 1);
+    let text = std::fs::read_to_string(&path).unwrap();
+    assert!(text.contains("# TYPE plasmoid_updater_updates_available gauge\n"));
+}
+
+#[test]
+fn rate_votes_for_matched_store_entry() {
+    let sandbox = Sandbox::new().unwrap();
+    let clock = FakeComponent::plasmoid("org.example.clock", "Clock", "1.0");
+    sandbox.install(&clock, Some(1000)).unwrap();
+    sandbox.publish(1000, &clock).unwrap();
+
+    let content_id = libplasmoid_updater::rate("Clock", 8, &sandbox.config()).unwrap();
+
+    assert_eq!(content_id, 1000);
+    assert_eq!(sandbox.vote(1000), Some(80));
+}
+
+#[test]
+fn recover_restores_component_from_interrupted_update() {
+    let sandbox = Sandbox::new().unwrap();
+    let clock = FakeComponent::plasmoid("org.example.clock", "Clock", "1.0");
+    let path = sandbox.install(&clock, Some(1000)).unwrap();
+    sandbox
+        .interrupt_update(&clock, &clock.with_version("2.0"), 1000)
+        .unwrap();
+    let config = sandbox.config();
+
+    let pending = libplasmoid_updater::pending_recovery(&config);
+    assert_eq!(pending.len(), 1);
+    assert_eq!(pending[0].component.name, "Clock");
+    assert_eq!(pending[0].target_version, "2.0");
+
+    let result = libplasmoid_updater::recover(&config).unwrap();
+
+    assert_eq!(result.recovered, ["Clock"]);
+    assert!(result.failed.is_empty());
+    assert_eq!(sandbox.installed_version(&clock).as_deref(), Some("1.0"));
+    assert!(path.join("contents/ui/main.qml").exists());
+    assert!(libplasmoid_updater::pending_recovery(&config).is_empty());
+}
+
+#[test]
+fn resolve_store_entry_ranks_candidates() {
+    let sandbox = Sandbox::new().unwrap();
+    let clock = FakeComponent::plasmoid("org.example.clock", "Clock", "1.0");
+    let clock_plus = FakeComponent::plasmoid("org.example.clockplus", "Clock Plus", "1.0");
+    sandbox.publish(1000, &clock).unwrap();
+    sandbox.publish(1001, &clock_plus).unwrap();
+    let config = sandbox.config();
+
+    let candidates =
+        libplasmoid_updater::resolve_store_entry("clock", ComponentType::PlasmaWidget, &config)
+            .unwrap();
+    let ranked: Vec<(u64, u8)> = candidates.iter().map(|c| (c.content_id, c.score)).collect();
+    assert_eq!(ranked, [(1000, 100), (1001, 56)]);
+    assert_eq!(candidates[0].matched_by, MatchSource::Name);
+
+    sandbox.install(&clock, Some(1000)).unwrap();
+    let candidates = libplasmoid_updater::resolve_store_entry(
+        "org.example.clock",
+        ComponentType::PlasmaWidget,
+        &config,
+    )
+    .unwrap();
+    assert_eq!(candidates[0].content_id, 1000);
+    assert_eq!(candidates[0].matched_by, MatchSource::Installed);
+    assert_eq!(candidates.len(), 2);
+}
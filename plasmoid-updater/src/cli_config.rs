@@ -3,22 +3,119 @@
 use std::collections::HashMap;
 use std::fs;
 use std::path::{Path, PathBuf};
+use std::time::Duration;
 
 use serde::Deserialize;
 
 const CONFIG_FILE_NAME: &str = "plasmoid-updater.toml";
 
+/// Layout version of config files written by this build, stored as
+/// `config_version`. Files without the key are version 0.
+const CONFIG_VERSION: i64 = 1;
+
+/// One step of the config file upgrade, from version `to - 1` to `to`.
+struct Migration {
+    to: i64,
+    apply: fn(&mut toml_edit::DocumentMut),
+}
+
+/// Upgrades applied in order to files older than [`CONFIG_VERSION`].
+const MIGRATIONS: &[Migration] = &[
+    // Version 1 introduced `config_version` itself; no keys changed.
+    Migration {
+        to: 1,
+        apply: |_| {},
+    },
+];
+
 fn config_path() -> Option<PathBuf> {
     dirs::config_dir().map(|d| d.join(CONFIG_FILE_NAME))
 }
 
 #[derive(Debug, Deserialize, Default)]
-#[serde(default)]
+#[serde(default, deny_unknown_fields)]
 struct TomlConfig {
+    config_version: i64,
+    color: libplasmoid_updater::ColorMode,
+    progress: libplasmoid_updater::ProgressMode,
     excluded_packages: Vec<String>,
+    excluded_types: Vec<libplasmoid_updater::ComponentType>,
+    generic_kns: bool,
     update_all_by_default: bool,
     assume_yes: bool,
+    auto_apply: Option<libplasmoid_updater::UpdateSeverity>,
+    trusted_authors: Vec<String>,
+    confirm_author_changes: bool,
+    install_companions: bool,
+    metrics_file: Option<PathBuf>,
     prompt_restart: bool,
+    restart_required: Vec<String>,
+    no_restart: Vec<String>,
+    reload_kwin: Option<bool>,
+    clear_theme_caches: Option<bool>,
+    reapply_active_themes: Option<bool>,
+    require_https: Option<bool>,
+    allowed_download_hosts: Vec<String>,
+    denied_download_hosts: Vec<String>,
+    max_download_size_mb: Option<u64>,
+    download_cache_mb: Option<u64>,
+    pipeline_downloads: bool,
+    stale_temp_days: Option<u64>,
+    keep_versions: usize,
+    removal_mode: libplasmoid_updater::RemovalMode,
+    conflict_policy: libplasmoid_updater::ConflictPolicy,
+    health_check: Option<bool>,
+    rollback_unhealthy: bool,
+    migrate_legacy_metadata: bool,
+    crash_monitor_secs: Option<u64>,
+    rollback_on_crash: bool,
+    backup_layout: Option<bool>,
+    retry_failed: u8,
+    pre_update_cmd: Option<String>,
+    post_update_cmd: Option<String>,
+    on_failure_cmd: Option<String>,
+    fetch_strategy: libplasmoid_updater::FetchStrategy,
+    conditional_requests: Option<bool>,
+    trusted_keys: Vec<String>,
+    advisories_url: Option<String>,
+    groups: HashMap<String, Vec<String>>,
+    download_variants: HashMap<String, String>,
+    api: TomlApi,
+    notifiers: Vec<libplasmoid_updater::Notifier>,
+    store_credentials: Option<libplasmoid_updater::StoreCredentials>,
+}
+
+/// The `[api]` table: timeouts and retries, library defaults when unset.
+#[derive(Debug, Deserialize, Default)]
+#[serde(default, deny_unknown_fields)]
+struct TomlApi {
+    connect_timeout_secs: Option<u64>,
+    request_timeout_secs: Option<u64>,
+    download_timeout_secs: Option<u64>,
+    max_attempts: Option<u8>,
+    backoff_ms: Option<u64>,
+}
+
+impl TomlApi {
+    fn into_api_config(self) -> libplasmoid_updater::ApiConfig {
+        let mut api = libplasmoid_updater::ApiConfig::new();
+        if let Some(secs) = self.connect_timeout_secs {
+            api = api.with_connect_timeout(Duration::from_secs(secs));
+        }
+        if let Some(secs) = self.request_timeout_secs {
+            api = api.with_request_timeout(Duration::from_secs(secs));
+        }
+        if let Some(secs) = self.download_timeout_secs {
+            api = api.with_download_timeout(Duration::from_secs(secs));
+        }
+        if let Some(attempts) = self.max_attempts {
+            api = api.with_max_attempts(attempts);
+        }
+        if let Some(ms) = self.backoff_ms {
+            api = api.with_backoff(Duration::from_millis(ms));
+        }
+        api
+    }
 }
 
 #[derive(Debug, Clone)]
@@ -26,6 +123,8 @@ pub struct CliConfig {
     pub inner: libplasmoid_updater::Config,
     pub update_all_by_default: bool,
     pub assume_yes: bool,
+    pub color: libplasmoid_updater::ColorMode,
+    pub progress: libplasmoid_updater::ProgressMode,
 }
 
 impl std::ops::Deref for CliConfig {
@@ -57,11 +156,75 @@ impl CliConfig {
 
         let mut inner = libplasmoid_updater::Config::new()
             .with_excluded_packages(toml_config.excluded_packages)
+            .with_excluded_types(toml_config.excluded_types)
+            .with_generic_kns(toml_config.generic_kns)
+            .with_groups(toml_config.groups)
+            .with_download_variants(toml_config.download_variants)
+            .with_reload_kwin(toml_config.reload_kwin.unwrap_or(true))
+            .with_clear_theme_caches(toml_config.clear_theme_caches.unwrap_or(true))
+            .with_reapply_active_themes(toml_config.reapply_active_themes.unwrap_or(true))
+            .with_require_https(toml_config.require_https.unwrap_or(true))
+            .with_allowed_download_hosts(toml_config.allowed_download_hosts)
+            .with_denied_download_hosts(toml_config.denied_download_hosts)
+            .with_keep_versions(toml_config.keep_versions)
+            .with_removal_mode(toml_config.removal_mode)
+            .with_conflict_policy(toml_config.conflict_policy)
+            .with_health_check(toml_config.health_check.unwrap_or(true))
+            .with_rollback_unhealthy(toml_config.rollback_unhealthy)
+            .with_migrate_legacy_metadata(toml_config.migrate_legacy_metadata)
+            .with_crash_monitor_window(match toml_config.crash_monitor_secs {
+                Some(0) => None,
+                Some(secs) => Some(Duration::from_secs(secs)),
+                None => Some(libplasmoid_updater::Config::DEFAULT_CRASH_MONITOR_WINDOW),
+            })
+            .with_rollback_on_crash(toml_config.rollback_on_crash)
+            .with_backup_layout(toml_config.backup_layout.unwrap_or(true))
+            .with_retry_failed(toml_config.retry_failed)
+            .with_auto_apply(toml_config.auto_apply)
+            .with_trusted_authors(toml_config.trusted_authors)
+            .with_confirm_author_changes(toml_config.confirm_author_changes)
+            .with_install_companions(toml_config.install_companions)
+            .with_metrics_file(toml_config.metrics_file)
+            .with_hooks(libplasmoid_updater::Hooks {
+                pre_update: toml_config.pre_update_cmd,
+                post_update: toml_config.post_update_cmd,
+                on_failure: toml_config.on_failure_cmd,
+            })
+            .with_fetch_strategy(toml_config.fetch_strategy)
+            .with_api(toml_config.api.into_api_config())
+            .with_notifiers(toml_config.notifiers)
+            .with_conditional_requests(toml_config.conditional_requests.unwrap_or(true))
+            .with_trusted_keys(toml_config.trusted_keys)
+            .with_advisories_url(Some(toml_config.advisories_url.unwrap_or_else(|| {
+                libplasmoid_updater::Config::DEFAULT_ADVISORIES_URL.to_string()
+            })))
+            .with_max_download_size(match toml_config.max_download_size_mb {
+                Some(0) => None,
+                Some(mb) => Some(mb * 1024 * 1024),
+                None => Some(libplasmoid_updater::Config::DEFAULT_MAX_DOWNLOAD_SIZE),
+            })
+            .with_download_cache_limit(match toml_config.download_cache_mb {
+                Some(0) => None,
+                Some(mb) => Some(mb * 1024 * 1024),
+                None => Some(libplasmoid_updater::Config::DEFAULT_DOWNLOAD_CACHE_LIMIT),
+            })
+            .with_pipeline_downloads(toml_config.pipeline_downloads)
+            .with_stale_temp_max_age(match toml_config.stale_temp_days {
+                Some(0) => None,
+                Some(days) => Some(Duration::from_secs(days.saturating_mul(24 * 60 * 60))),
+                None => Some(libplasmoid_updater::Config::DEFAULT_STALE_TEMP_MAX_AGE),
+            })
             .with_restart(if toml_config.prompt_restart {
                 libplasmoid_updater::RestartBehavior::Prompt
             } else {
                 libplasmoid_updater::RestartBehavior::Never
-            });
+            })
+            .with_restart_required(toml_config.restart_required)
+            .with_no_restart(toml_config.no_restart);
+
+        if let Some(credentials) = toml_config.store_credentials {
+            inner = inner.with_store_credentials(credentials);
+        }
 
         if let Some(path) = widgets_id_path {
             let widgets_id_table = Self::load_widgets_id_table_from(path)?;
@@ -72,6 +235,8 @@ impl CliConfig {
             inner,
             update_all_by_default: toml_config.update_all_by_default,
             assume_yes: toml_config.assume_yes,
+            color: toml_config.color,
+            progress: toml_config.progress,
         })
     }
 
@@ -84,19 +249,30 @@ impl CliConfig {
             return Ok(TomlConfig::default());
         }
 
-        let content = fs::read_to_string(&path).map_err(|e| {
+        let mut content = fs::read_to_string(&path).map_err(|e| {
             libplasmoid_updater::Error::other(format!(
                 "failed to read config file {}: {e}",
                 path.display()
             ))
         })?;
+        if let Some(migrated) = migrate(&path, &content) {
+            content = migrated;
+        }
 
-        toml::from_str(&content).map_err(|e| {
-            libplasmoid_updater::Error::other(format!(
-                "failed to parse config file {}: {e}",
-                path.display()
-            ))
-        })
+        // Read before deserializing: a newer file may have keys this
+        // version rejects, and the error should say why.
+        let version = file_version(&content);
+        let config: TomlConfig =
+            toml::from_str(&content).map_err(|e| config_error(&path, &content, &e, version))?;
+
+        if version > CONFIG_VERSION {
+            eprintln!(
+                "warning: config file {} is version {version}, newer than the {CONFIG_VERSION} this plasmoid-updater understands",
+                path.display(),
+            );
+        }
+
+        Ok(config)
     }
 
     fn load_widgets_id_table_from(
@@ -125,6 +301,104 @@ impl CliConfig {
     }
 }
 
+/// The `config_version` of `content`, 0 if it has none or does not parse.
+fn file_version(content: &str) -> i64 {
+    content
+        .parse::<toml_edit::DocumentMut>()
+        .ok()
+        .and_then(|document| document.get("config_version")?.as_integer())
+        .unwrap_or(0)
+}
+
+/// Describes a config file parse error. An unknown key is reported with its
+/// line and, when one is close enough to be a typo, the key probably meant.
+/// Errors in a file newer than [`CONFIG_VERSION`] mention its `version`.
+fn config_error(
+    path: &Path,
+    content: &str,
+    error: &toml::de::Error,
+    version: i64,
+) -> libplasmoid_updater::Error {
+    let mut message = match unknown_key(error.message()) {
+        Some((key, valid)) => {
+            let mut message = format!("unknown key `{key}` in {}", path.display());
+            if let Some(span) = error.span() {
+                let line = content[..span.start].matches('\n').count() + 1;
+                message.push_str(&format!(", line {line}"));
+            }
+            if let Some(suggestion) = closest_key(key, &valid) {
+                message.push_str(&format!(", did you mean `{suggestion}`?"));
+            }
+            message
+        }
+        None => format!("failed to parse config file {}: {error}", path.display()),
+    };
+    if version > CONFIG_VERSION {
+        message.push_str(&format!(
+            " (the file is version {version}, newer than the {CONFIG_VERSION} this plasmoid-updater understands)"
+        ));
+    }
+    libplasmoid_updater::Error::Config(message)
+}
+
+/// Splits serde's "unknown field `x`, expected one of `a`, `b`" into the
+/// field and the valid ones.
+fn unknown_key(message: &str) -> Option<(&str, Vec<&str>)> {
+    let (key, expected) = message.strip_prefix("unknown field `")?.split_once('`')?;
+    let valid = expected.split('`').skip(1).step_by(2).collect();
+    Some((key, valid))
+}
+
+/// The valid key nearest to `key`, if it is off by about a third of its
+/// length or less.
+fn closest_key<'a>(key: &str, valid: &[&'a str]) -> Option<&'a str> {
+    let max_distance = (key.len() / 3).max(1);
+    valid
+        .iter()
+        .map(|candidate| (strsim::levenshtein(key, candidate), *candidate))
+        .filter(|(distance, _)| *distance <= max_distance)
+        .min_by_key(|(distance, _)| *distance)
+        .map(|(_, candidate)| candidate)
+}
+
+/// Upgrades a config file older than [`CONFIG_VERSION`] in place, keeping
+/// its comments, and returns the new content.
+///
+/// The old file is kept as `plasmoid-updater.toml.v<version>.bak`. If it
+/// cannot be rewritten, the upgraded content is still used for this run.
+/// Returns `None` for current or unparsable files; the latter fail later with
+/// a proper parse error.
+fn migrate(path: &Path, content: &str) -> Option<String> {
+    let mut document: toml_edit::DocumentMut = content.parse().ok()?;
+    let version = document
+        .get("config_version")
+        .and_then(|v| v.as_integer())
+        .unwrap_or(0);
+    if version >= CONFIG_VERSION {
+        return None;
+    }
+
+    for migration in MIGRATIONS.iter().filter(|m| m.to > version) {
+        (migration.apply)(&mut document);
+    }
+    document["config_version"] = toml_edit::value(CONFIG_VERSION);
+    let migrated = document.to_string();
+
+    let backup = path.with_file_name(format!("{CONFIG_FILE_NAME}.v{version}.bak"));
+    match fs::copy(path, &backup).and_then(|_| fs::write(path, &migrated)) {
+        Ok(()) => eprintln!(
+            "upgraded config file {} to version {CONFIG_VERSION}, the old one is at {}",
+            path.display(),
+            backup.display()
+        ),
+        Err(e) => eprintln!(
+            "warning: could not upgrade config file {} to version {CONFIG_VERSION}: {e}",
+            path.display()
+        ),
+    }
+    Some(migrated)
+}
+
 fn ensure_config_exists(path: &Path) -> libplasmoid_updater::Result<()> {
     if path.exists() {
         return Ok(());
@@ -148,10 +422,87 @@ fn create_config_directory(path: &Path) -> libplasmoid_updater::Result<()> {
 
 fn create_default_config(path: &Path) -> libplasmoid_updater::Result<()> {
     let default_content = r#"# plasmoid-updater configuration
-# excluded_packages = ["widget-name", "another.widget"]
+config_version = 1  # layout of this file, upgraded automatically
+# color = "auto"  # or "always" or "never"; auto honors NO_COLOR and only colors terminals
+# progress = "fancy"  # or "plain" (one timestamped line per step, for screen readers and logs) or "none"
+# excluded_packages = ["widget-name", "org.kde.plasma.*", "re:^com\\.github\\..*"]
+# excluded_types = ["icon_theme", "global_theme"]
+# generic_kns = false  # also update entries of KNewStuff registries without a dedicated type (Kate themes, Kdenlive titles, ...)
 # update_all_by_default = false
 # assume_yes = false  # automatically confirm all updates without prompting
+# auto_apply = "patch"  # or "minor"; runs without the selection menu leave larger updates for review
+# trusted_authors = ["store-user"]  # store users whose takeover of an entry is not flagged
+# confirm_author_changes = false  # ask before installing an update from a new store author; unattended runs skip it
+# install_companions = false  # install the style, colors and icons a global theme uses without asking
+# metrics_file = "/var/lib/node_exporter/textfile/plasmoid_updater.prom"  # Prometheus gauges written after each check and update
 # prompt_restart = true
+# restart_required = ["com.example.wallpaper"]  # always restart plasmashell after updating these
+# no_restart = ["org.example.clock"]  # never restart for these, whatever their type
+# reapply_active_themes = true  # with prompt_restart, offer to re-apply updated themes in use
+# reload_kwin = true  # reconfigure KWin after KWin effect/script/switcher/decoration updates
+# clear_theme_caches = true  # drop Plasma's cached SVGs of updated Plasma styles, global themes and Aurorae decorations
+# require_https = true  # refuse plain HTTP package downloads
+# allowed_download_hosts = ["*.kde.org", "*.pling.com"]  # empty allows any host
+# denied_download_hosts = ["example.com"]
+# max_download_size_mb = 500  # 0 disables the limit
+# download_cache_mb = 1024  # keep downloaded packages for reuse up to this size, 0 disables the cache
+# pipeline_downloads = false  # in unattended runs, start downloading updates while the check is still running
+# stale_temp_days = 1  # remove temp dirs left behind by crashed runs after this many days, 0 keeps them
+# fetch_strategy = "catalog"  # or "targeted" (one request per known widget) or "auto"
+# conditional_requests = true  # cache store pages and revalidate them with ETag/Last-Modified
+# trusted_keys = ["RWQ..."]  # minisign public keys; when set, --snapshot files need a valid <file>.minisig
+# advisories_url = "https://example.org/advisories.json"  # feed of known-broken releases to hold back
+# keep_versions = 0  # installed versions to keep per component for `plasmoid-updater downgrade`
+# removal_mode = "backup"  # or "trash": also move replaced and removed user components to the trash
+# conflict_policy = "fail"  # or "warn": install updates that write to the same files anyway
+# health_check = true  # validate metadata, entry points and QML after installing
+# rollback_unhealthy = false  # roll back updates that fail the health check
+# migrate_legacy_metadata = false  # generate metadata.json for updated packages that only ship metadata.desktop
+# crash_monitor_secs = 15  # watch plasmashell for crashes after restarting it, 0 disables
+# rollback_on_crash = false  # roll back without asking if plasmashell crashes
+# backup_layout = true  # back up the desktop and panel layout before widget updates
+# retry_failed = 0  # retry passes at the end of a run for components that hit a network error or rate limit
+#
+# shell commands run around each component update, with PLASMOID_NAME,
+# PLASMOID_DIRECTORY, PLASMOID_TYPE, PLASMOID_PATH, PLASMOID_CONTENT_ID,
+# OLD_VERSION and NEW_VERSION set (and ERROR for on_failure_cmd)
+# pre_update_cmd = "snapper create -d \"before $PLASMOID_NAME $NEW_VERSION\""  # non-zero exit skips the update
+# post_update_cmd = "logger \"updated $PLASMOID_NAME to $NEW_VERSION\""
+# on_failure_cmd = "notify-send \"$PLASMOID_NAME update failed\" \"$ERROR\""
+#
+# named groups for `plasmoid-updater update --group <name>`
+# [groups]
+# work = ["widgetA", "widgetB"]
+#
+# preferred download for widgets that publish several (matched against the
+# file name or version); others get the one for the running Plasma version
+# [download_variants]
+# "org.example.clock" = "plasma6"
+#
+# where to send a summary after an update run; kind is "desktop", "ntfy" or "gotify"
+# [[notifiers]]
+# kind = "desktop"
+# [[notifiers]]
+# kind = "ntfy"
+# url = "https://ntfy.sh/my-topic"
+# token = "tk_..."  # optional, for protected topics
+# [[notifiers]]
+# kind = "gotify"
+# url = "https://gotify.example.org"
+# token = "A..."  # application token
+#
+# timeouts and retries for store requests and downloads
+# [api]
+# connect_timeout_secs = 10
+# request_timeout_secs = 60
+# download_timeout_secs = 60
+# max_attempts = 3  # attempts per store request
+# backoff_ms = 100  # delay before the second attempt, doubled for each further one
+#
+# authenticate store requests for higher rate limits; kind is "keyring", "basic" or "token"
+# [store_credentials]
+# kind = "keyring"  # password from `secret-tool store --label="KDE Store" service plasmoid-updater username me`
+# username = "me"
 "#;
     fs::write(path, default_content).map_err(|e| {
         libplasmoid_updater::Error::other(format!(
@@ -171,3 +522,95 @@ fn open_in_editor(path: &Path) -> libplasmoid_updater::Result<()> {
         })?;
     Ok(())
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn parse_error(content: &str) -> String {
+        let error = toml::from_str::<TomlConfig>(content).unwrap_err();
+        config_error(
+            Path::new("plasmoid-updater.toml"),
+            content,
+            &error,
+            file_version(content),
+        )
+        .to_string()
+    }
+
+    #[test]
+    fn migrate_upgrades_old_files_and_keeps_a_backup() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join(CONFIG_FILE_NAME);
+        let original = "# my settings\nassume_yes = true  # no prompts\n\n[api]\n# slow link\nrequest_timeout_secs = 120\n";
+        fs::write(&path, original).unwrap();
+
+        let migrated = migrate(&path, original).unwrap();
+
+        assert_eq!(fs::read_to_string(&path).unwrap(), migrated);
+        assert_eq!(file_version(&migrated), CONFIG_VERSION);
+        for comment in ["# my settings", "# no prompts", "# slow link"] {
+            assert!(migrated.contains(comment), "{migrated}");
+        }
+        let backup = dir.path().join(format!("{CONFIG_FILE_NAME}.v0.bak"));
+        assert_eq!(fs::read_to_string(backup).unwrap(), original);
+        assert!(toml::from_str::<TomlConfig>(&migrated).unwrap().assume_yes);
+    }
+
+    #[test]
+    fn migrate_leaves_current_files_alone() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join(CONFIG_FILE_NAME);
+        let current = format!("config_version = {CONFIG_VERSION}\nassume_yes = true\n");
+        fs::write(&path, &current).unwrap();
+
+        assert!(migrate(&path, &current).is_none());
+        assert_eq!(fs::read_to_string(&path).unwrap(), current);
+        assert_eq!(fs::read_dir(dir.path()).unwrap().count(), 1);
+    }
+
+    #[test]
+    fn unknown_keys_are_reported_with_line_and_suggestion() {
+        let message = parse_error("assume_yes = true\n\nexclude_packages = [\"clock\"]\n");
+        assert!(
+            message.contains("unknown key `exclude_packages`"),
+            "{message}"
+        );
+        assert!(message.contains(", line 3,"), "{message}");
+        assert!(
+            message.contains("did you mean `excluded_packages`?"),
+            "{message}"
+        );
+    }
+
+    #[test]
+    fn unknown_keys_in_tables_are_matched_against_the_table() {
+        let message = parse_error("[api]\nconnect_timeout_secs = 5\nmax_attempt = 5\n");
+        assert!(message.contains("unknown key `max_attempt`"), "{message}");
+        assert!(message.contains(", line 3,"), "{message}");
+        assert!(
+            message.contains("did you mean `max_attempts`?"),
+            "{message}"
+        );
+    }
+
+    #[test]
+    fn unrelated_keys_get_no_suggestion() {
+        let message = parse_error("frobnicate = true\n");
+        assert!(message.contains("unknown key `frobnicate`"), "{message}");
+        assert!(!message.contains("did you mean"), "{message}");
+    }
+
+    #[test]
+    fn errors_in_newer_files_mention_their_version() {
+        let message = parse_error("config_version = 7\nshiny_new_option = true\n");
+        assert!(
+            message.contains("unknown key `shiny_new_option`"),
+            "{message}"
+        );
+        assert!(message.contains("the file is version 7"), "{message}");
+
+        let message = parse_error("frobnicate = true\n");
+        assert!(!message.contains("the file is version"), "{message}");
+    }
+}
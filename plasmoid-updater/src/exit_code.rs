@@ -1,14 +1,104 @@
 // SPDX-License-Identifier: GPL-3.0-or-later
 
+use libplasmoid_updater::{CheckResult, UpdateResult};
+
+/// Process exit status.
+///
+/// Variants are declared from least to most severe; use
+/// [`combine`](Self::combine) to merge the outcomes of several runs, as the
+/// numeric codes are not ordered by severity.
 #[derive(Debug, Clone, Copy, PartialEq, Eq)]
 pub enum ExitCode {
+    /// No updates were available or selected.
+    NothingToDo = 3,
+    /// Updates were found (check) or applied (update).
     Success = 0,
+    /// Some components could not be checked. Only reported with `--strict`.
+    Unresolved = 4,
     PartialFailure = 1,
     FatalError = 2,
 }
 
+impl ExitCode {
+    /// Rank of the code by severity, in declaration order.
+    const fn severity(self) -> u8 {
+        match self {
+            Self::NothingToDo => 0,
+            Self::Success => 1,
+            Self::Unresolved => 2,
+            Self::PartialFailure => 3,
+            Self::FatalError => 4,
+        }
+    }
+
+    /// The more severe of two outcomes, e.g. of runs for several users.
+    pub fn combine(self, other: Self) -> Self {
+        if other.severity() > self.severity() {
+            other
+        } else {
+            self
+        }
+    }
+
+    /// Maps a child process exit status back to an `ExitCode`.
+    /// Unknown codes and signal terminations count as fatal.
+    pub fn from_status(code: Option<i32>) -> Self {
+        match code {
+            Some(0) => Self::Success,
+            Some(1) => Self::PartialFailure,
+            Some(3) => Self::NothingToDo,
+            Some(4) => Self::Unresolved,
+            _ => Self::FatalError,
+        }
+    }
+
+    pub fn from_check(result: &CheckResult, strict: bool) -> Self {
+        if strict && !result.diagnostics.is_empty() {
+            Self::Unresolved
+        } else if result.has_updates() {
+            Self::Success
+        } else {
+            Self::NothingToDo
+        }
+    }
+
+    pub fn from_update(result: &UpdateResult, strict: bool) -> Self {
+        if result.has_failures() {
+            Self::PartialFailure
+        } else if strict && !result.unresolved.is_empty() {
+            Self::Unresolved
+        } else if result.succeeded.is_empty() {
+            Self::NothingToDo
+        } else {
+            Self::Success
+        }
+    }
+}
+
 impl From<ExitCode> for i32 {
     fn from(code: ExitCode) -> i32 {
         code as i32
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn combine_keeps_the_more_severe_outcome() {
+        let by_severity = [
+            ExitCode::NothingToDo,
+            ExitCode::Success,
+            ExitCode::Unresolved,
+            ExitCode::PartialFailure,
+            ExitCode::FatalError,
+        ];
+        for (i, &a) in by_severity.iter().enumerate() {
+            for (j, &b) in by_severity.iter().enumerate() {
+                let expected = by_severity[i.max(j)];
+                assert_eq!(a.combine(b), expected, "{a:?} + {b:?}");
+            }
+        }
+    }
+}
@@ -3,12 +3,25 @@
 
 mod cli_config;
 mod exit_code;
+mod serve;
+mod timer;
 
-use clap::{Parser, Subcommand};
+use std::path::{Path, PathBuf};
+
+use clap::{CommandFactory, Parser, Subcommand, ValueEnum};
 
 use cli_config::CliConfig;
 use exit_code::ExitCode;
-use libplasmoid_updater::{check, show_installed, update};
+use libplasmoid_updater::{
+    ColorMode, ComponentType, DuplicateCopy, EventSink, InstalledQuery, InstalledSort,
+    InstalledStatus, Manifest, ProgressMode, RegistryReport, StoreSnapshot, adopt, apply,
+    audit_registry, check, clean_cache,
+    cli::theme::{Stream, Style, paint},
+    create_snapshot, dedupe, disk_usage, doctor, downgrade, explain_component, find_duplicates,
+    find_users, immutable_system, inspect_component, install_from_file, last_run, orphans,
+    pending_recovery, rate, recover, reinstall, repair_registry, retained_versions, set_color_mode,
+    set_progress_mode, show_changes, show_disk_usage, show_installed_with, tr, update, verify,
+};
 
 #[derive(Parser)]
 #[command(name = "plasmoid-updater")]
@@ -33,45 +46,392 @@ struct Cli {
     )]
     system: bool,
 
+    #[arg(
+        long,
+        global = true,
+        requires = "system",
+        help = "with --system, also run for every user under /home (admin mode)"
+    )]
+    all_users: bool,
+
     #[arg(long, help = "open configuration file in editor")]
     edit_config: bool,
 
-    #[arg(long, global = true, help = "skip KDE Plasma detection")]
+    #[arg(
+        long,
+        global = true,
+        visible_alias = "force-non-kde",
+        help = "skip KDE Plasma detection"
+    )]
     skip_plasma_detection: bool,
+
+    #[arg(
+        long,
+        global = true,
+        conflicts_with = "all_users",
+        help = "print check and update progress as JSON lines on stdout"
+    )]
+    json_stream: bool,
+
+    #[arg(
+        long,
+        global = true,
+        help = "exit with 4 when some components could not be checked"
+    )]
+    strict: bool,
+
+    #[arg(
+        long,
+        global = true,
+        value_name = "FILE",
+        help = "resolve against a store snapshot instead of the KDE Store"
+    )]
+    snapshot: Option<PathBuf>,
+
+    #[arg(
+        long,
+        global = true,
+        help = "offer updates even if the advisory feed lists them as known broken"
+    )]
+    ignore_advisories: bool,
+
+    #[arg(
+        long,
+        global = true,
+        help = "check every component, even those unchanged since a recent check"
+    )]
+    full: bool,
+
+    #[arg(
+        long,
+        global = true,
+        value_name = "WHEN",
+        help = "when to color output: auto, always or never"
+    )]
+    color: Option<ColorMode>,
+
+    #[arg(
+        long,
+        global = true,
+        value_name = "MODE",
+        help = "how to show progress: fancy, plain (timestamped lines) or none"
+    )]
+    progress: Option<ProgressMode>,
+
+    #[arg(
+        long,
+        global = true,
+        value_name = "FILE",
+        help = "write Prometheus metrics to FILE after check and update runs"
+    )]
+    metrics_file: Option<PathBuf>,
 }
 
 #[derive(Subcommand)]
 enum Commands {
     #[command(about = "check for available updates")]
-    Check,
+    Check {
+        #[arg(
+            long = "type",
+            value_name = "TYPE",
+            help = "only check components of this type (repeatable)"
+        )]
+        types: Vec<ComponentType>,
+        #[arg(
+            short,
+            long,
+            help = "also show what changed since the installed version and how many store requests the check made"
+        )]
+        verbose: bool,
+        #[arg(
+            long,
+            value_enum,
+            default_value_t = CheckFormat::Table,
+            help = "output format; markdown prints a report for mail or chat"
+        )]
+        format: CheckFormat,
+    },
     #[command(about = "list all installed components")]
-    ListInstalled,
+    ListInstalled {
+        #[arg(
+            short,
+            long,
+            help = "also show author, description, license, website, icon and size"
+        )]
+        verbose: bool,
+        #[arg(
+            long = "type",
+            value_name = "TYPE",
+            help = "only list components of this type (repeatable)"
+        )]
+        types: Vec<ComponentType>,
+        #[arg(
+            long,
+            conflicts_with = "orphaned",
+            help = "only list components with an update available"
+        )]
+        outdated: bool,
+        #[arg(long, help = "only list components that match no store entry")]
+        orphaned: bool,
+        #[arg(
+            long,
+            value_name = "ORDER",
+            default_value = "name",
+            help = "sort by name, type, version or size"
+        )]
+        sort: InstalledSort,
+    },
+    #[command(about = "list components whose store entry was deleted")]
+    Orphans,
+    #[command(about = "find components installed both for the user and system-wide")]
+    Dedupe {
+        #[arg(
+            long,
+            help = "remove redundant user copies instead of only listing them"
+        )]
+        apply: bool,
+    },
+    #[command(about = "show everything known about one component")]
+    Info {
+        #[arg(help = "component name or directory")]
+        component: String,
+    },
+    #[command(about = "explain why a component is or is not offered an update")]
+    Explain {
+        #[arg(help = "component name or directory")]
+        component: String,
+        #[arg(long, help = "print one JSON object instead of a list")]
+        json: bool,
+    },
+    #[command(about = "show disk space used by components, backups and cache")]
+    DiskUsage {
+        #[arg(long, help = "print one JSON object instead of tables")]
+        json: bool,
+    },
+    #[command(about = "delete cached package downloads and previews")]
+    CleanCache,
+    #[command(about = "check the environment and KDE Store access, for bug reports")]
+    Doctor {
+        #[arg(long, help = "print one JSON object instead of a list")]
+        json: bool,
+    },
     #[command(about = "update components")]
     Update {
-        #[arg(help = "component name or directory to update")]
-        component: Option<String>,
+        #[arg(help = "component names or directories to update; only these are checked")]
+        components: Vec<String>,
         #[arg(long, help = "automatically restart plasmashell")]
         restart_plasma: bool,
         #[arg(long, help = "do not restart plasmashell")]
         no_restart_plasma: bool,
+        #[arg(long, help = "do not reconfigure KWin after KWin-related updates")]
+        no_reload_kwin: bool,
         #[arg(short = 'y', long, help = "automatically confirm all updates")]
         yes: bool,
+        #[arg(
+            long,
+            requires = "system",
+            help = "also update system components installed by a distro package"
+        )]
+        force_distro_owned: bool,
+        #[arg(
+            long = "type",
+            value_name = "TYPE",
+            help = "only update components of this type (repeatable)"
+        )]
+        types: Vec<ComponentType>,
+        #[arg(
+            long,
+            value_name = "NAME",
+            conflicts_with = "components",
+            help = "only update components in this config group"
+        )]
+        group: Option<String>,
+    },
+    #[command(about = "install a component from a downloaded package file")]
+    Install {
+        #[arg(
+            long,
+            value_name = "FILE",
+            help = "package to install, e.g. foo.plasmoid"
+        )]
+        file: PathBuf,
+        #[arg(
+            long = "type",
+            value_name = "TYPE",
+            default_value = "plasma_widget",
+            help = "component type of the package"
+        )]
+        component_type: ComponentType,
+    },
+    #[command(about = "register manually installed components with KNewStuff")]
+    Adopt {
+        #[arg(
+            help = "component name or directory to adopt",
+            required_unless_present = "all"
+        )]
+        component: Option<String>,
+        #[arg(
+            long,
+            conflicts_with = "component",
+            help = "adopt every unregistered component"
+        )]
+        all: bool,
+    },
+    #[command(about = "swap in a version kept by keep_versions")]
+    Downgrade {
+        #[arg(help = "component name or directory")]
+        component: String,
+        #[arg(help = "version to restore; lists the kept versions if omitted")]
+        version: Option<String>,
+    },
+    #[command(about = "download the package a component was installed from again and reinstall it")]
+    Reinstall {
+        #[arg(help = "component name or directory")]
+        component: String,
+    },
+    #[command(about = "compare installed files to the state recorded at install time")]
+    Verify {
+        #[arg(help = "component name or directory; verifies every component if omitted")]
+        component: Option<String>,
+    },
+    #[command(about = "restore components left half-installed by an interrupted run")]
+    Recover,
+    #[command(about = "summarize what the last update run did")]
+    Report {
+        #[arg(long, required = true, help = "report the most recent run")]
+        last: bool,
+        #[arg(
+            long,
+            value_enum,
+            default_value_t = ReportFormat::Terminal,
+            help = "output format; html prints a page for saving or sharing"
+        )]
+        format: ReportFormat,
+    },
+    #[command(about = "rate a component on the KDE Store (needs store_credentials)")]
+    Rate {
+        #[arg(help = "component name or directory")]
+        component: String,
+        #[arg(
+            help = "score from 1 (bad) to 10 (excellent)",
+            value_parser = clap::value_parser!(u8).range(1..=10)
+        )]
+        score: u8,
+    },
+    #[command(about = "install and update the components listed in a manifest")]
+    Apply {
+        #[arg(
+            long,
+            value_name = "FILE",
+            help = "JSON manifest of KDE Store content IDs"
+        )]
+        manifest: PathBuf,
+        #[arg(
+            long,
+            help = "remove store-installed components the manifest does not list"
+        )]
+        remove_extras: bool,
+        #[arg(
+            long,
+            help = "print one JSON object with \"changed\" and exit 0 unless something failed"
+        )]
+        idempotent: bool,
+    },
+    #[command(about = "capture store metadata for reproducible rollouts")]
+    Snapshot {
+        #[command(subcommand)]
+        command: SnapshotCommands,
+    },
+    #[command(about = "inspect the KNewStuff registry")]
+    Registry {
+        #[command(subcommand)]
+        command: RegistryCommands,
+    },
+    #[command(about = "schedule regular checks with a systemd user timer")]
+    InstallTimer {
+        #[arg(
+            long,
+            default_value = "1d",
+            help = "time between runs (e.g. 12h, 1d, 1w) or hourly, daily, weekly, monthly"
+        )]
+        interval: String,
+        #[arg(long, help = "install updates instead of only checking for them")]
+        auto_apply: bool,
+    },
+    #[command(about = "remove the timer set up by install-timer")]
+    RemoveTimer,
+    #[command(about = "answer JSON-RPC requests from a front end")]
+    Serve {
+        #[arg(
+            long,
+            required = true,
+            help = "read requests from stdin and write responses to stdout"
+        )]
+        stdio: bool,
+    },
+    #[command(about = "generate man pages", hide = true)]
+    GenerateMan {
+        #[arg(
+            help = "directory to write one page per command to; prints the main page if omitted"
+        )]
+        dir: Option<PathBuf>,
+    },
+}
+
+#[derive(Clone, Copy, PartialEq, Eq, ValueEnum)]
+enum CheckFormat {
+    Table,
+    Markdown,
+}
+
+#[derive(Clone, Copy, PartialEq, Eq, ValueEnum)]
+enum ReportFormat {
+    Terminal,
+    Html,
+}
+
+#[derive(Subcommand)]
+enum SnapshotCommands {
+    #[command(about = "save the store entries of installed components to a file")]
+    Create {
+        #[arg(help = "snapshot file to write")]
+        file: PathBuf,
+    },
+}
+
+#[derive(Subcommand)]
+enum RegistryCommands {
+    #[command(about = "check registry entries against installed files")]
+    Fsck {
+        #[arg(long, help = "remove orphans and duplicates, fix mismatched entries")]
+        fix: bool,
     },
 }
 
 #[derive(Default)]
 struct UpdateArgs {
-    component: Option<String>,
+    components: Vec<String>,
     restart_plasma: bool,
     no_restart_plasma: bool,
+    no_reload_kwin: bool,
     yes: bool,
+    force_distro_owned: bool,
+    types: Vec<ComponentType>,
+    group: Option<String>,
+    strict: bool,
 }
 
 fn main() {
     let cli = Cli::parse();
+    if let Some(mode) = cli.color {
+        set_color_mode(mode);
+    }
+    if let Some(mode) = cli.progress {
+        set_progress_mode(mode);
+    }
 
     let exit_code = run(cli).unwrap_or_else(|e| {
-        eprintln!("error: {e}");
+        eprintln!("{} {e}", paint(Stream::Stderr, Style::Red, "error:"));
         ExitCode::FatalError
     });
 
@@ -84,116 +444,1171 @@ fn run(cli: Cli) -> Result<ExitCode, libplasmoid_updater::Error> {
         return Ok(ExitCode::Success);
     }
 
+    if let Some(Commands::GenerateMan { dir }) = &cli.command {
+        generate_man(dir.as_deref())?;
+        return Ok(ExitCode::Success);
+    }
+
     let mut config = CliConfig::load()?;
+    set_color_mode(cli.color.unwrap_or(config.color));
+    set_progress_mode(cli.progress.unwrap_or(config.progress));
     config.inner.system = cli.system;
     config.inner.skip_plasma_detection = cli.skip_plasma_detection;
+    if cli.json_stream {
+        config.inner.event_sink = Some(EventSink::json_lines(std::io::stdout()));
+    }
+    if let Some(path) = &cli.snapshot {
+        let snapshot = if config.trusted_keys.is_empty() {
+            StoreSnapshot::load(path)?
+        } else {
+            StoreSnapshot::load_signed(path, &config.trusted_keys)?
+        };
+        config.inner = config.inner.with_store_snapshot(snapshot);
+    }
+    if cli.ignore_advisories {
+        config.inner.advisories_url = None;
+    }
+    config.inner.full_scan = cli.full;
+    if let Some(path) = &cli.metrics_file {
+        config.inner.metrics_file = Some(path.clone());
+    }
+
+    execute_command(&cli, &config).or_else(skip_store_outage)
+}
 
-    execute_command(&cli, &config)
+/// Turns a store outage that outlasted the retries into a skipped run, so
+/// timers and cron jobs don't report maintenance windows as failures.
+fn skip_store_outage(
+    error: libplasmoid_updater::Error,
+) -> Result<ExitCode, libplasmoid_updater::Error> {
+    match error.root_cause() {
+        libplasmoid_updater::Error::StoreUnavailable { .. } => {
+            eprintln!(
+                "{}",
+                tr!(
+                    "skipped-outage",
+                    "skipped: {error}",
+                    error = error.to_string()
+                )
+            );
+            Ok(ExitCode::NothingToDo)
+        }
+        _ => Err(error),
+    }
 }
 
 fn execute_command(cli: &Cli, config: &CliConfig) -> Result<ExitCode, libplasmoid_updater::Error> {
-    if cli.system && !is_root_user() {
+    // No sudo prompt where system components cannot be written anyway.
+    let immutable = if cli.system { immutable_system() } else { None };
+    if cli.system && immutable.is_none() && !is_root_user() {
         validate_sudo()?;
     }
 
+    let per_user = if cli.all_users {
+        Some(per_user_args(cli).ok_or_else(|| {
+            libplasmoid_updater::Error::other(
+                "--all-users only applies to check, list-installed and update",
+            )
+        })?)
+    } else {
+        None
+    };
+
+    warn_interrupted(cli, config);
+    let code = match &immutable {
+        // A plain run skips what it cannot update instead of failing.
+        Some(system) if cli.command.is_none() => {
+            println!(
+                "{}",
+                tr!(
+                    "skip-immutable",
+                    "skipping system components: they are read-only on {system}",
+                    system = system,
+                )
+            );
+            ExitCode::NothingToDo
+        }
+        _ => run_command(cli, config)?,
+    };
+
+    match per_user {
+        Some(args) => Ok(code.combine(do_all_users(&args)?)),
+        None => Ok(code),
+    }
+}
+
+fn run_command(cli: &Cli, config: &CliConfig) -> Result<ExitCode, libplasmoid_updater::Error> {
     match &cli.command {
-        None => do_update(config, UpdateArgs::default()),
-        Some(Commands::Check) => do_check(config),
-        Some(Commands::ListInstalled) => do_list_installed(config),
+        None => do_update(
+            config,
+            UpdateArgs {
+                strict: cli.strict,
+                ..Default::default()
+            },
+        ),
+        Some(Commands::Check {
+            types,
+            verbose,
+            format,
+        }) => do_check(config, types, *verbose, *format, cli.strict),
+        Some(Commands::ListInstalled {
+            verbose,
+            types,
+            outdated,
+            orphaned,
+            sort,
+        }) => {
+            let status = if *outdated {
+                InstalledStatus::Outdated
+            } else if *orphaned {
+                InstalledStatus::Orphaned
+            } else {
+                InstalledStatus::All
+            };
+            let query = InstalledQuery {
+                status,
+                sort: *sort,
+            };
+            do_list_installed(config, types, query, *verbose)
+        }
+        Some(Commands::Orphans) => do_orphans(config),
+        Some(Commands::Dedupe { apply }) => do_dedupe(config, *apply),
+        Some(Commands::Info { component }) => do_info(config, component),
+        Some(Commands::Explain { component, json }) => do_explain(config, component, *json),
+        Some(Commands::DiskUsage { json }) => do_disk_usage(config, *json),
+        Some(Commands::Report { format, .. }) => do_report(config, *format),
+        Some(Commands::CleanCache) => do_clean_cache(),
+        Some(Commands::Doctor { json }) => do_doctor(config, *json),
         Some(Commands::Update {
-            component,
+            components,
             restart_plasma,
             no_restart_plasma,
+            no_reload_kwin,
             yes,
+            force_distro_owned,
+            types,
+            group,
         }) => do_update(
             config,
             UpdateArgs {
-                component: component.clone(),
+                components: components.clone(),
                 restart_plasma: *restart_plasma,
                 no_restart_plasma: *no_restart_plasma,
+                no_reload_kwin: *no_reload_kwin,
                 yes: *yes,
+                force_distro_owned: *force_distro_owned,
+                types: types.clone(),
+                group: group.clone(),
+                strict: cli.strict,
             },
         ),
+        Some(Commands::Install {
+            file,
+            component_type,
+        }) => do_install(config, file, *component_type),
+        Some(Commands::Adopt { component, .. }) => do_adopt(config, component.as_deref()),
+        Some(Commands::Downgrade { component, version }) => {
+            do_downgrade(config, component, version.as_deref())
+        }
+        Some(Commands::Reinstall { component }) => do_reinstall(config, component),
+        Some(Commands::Verify { component }) => do_verify(config, component.as_deref()),
+        Some(Commands::Recover) => do_recover(config),
+        Some(Commands::Rate { component, score }) => do_rate(config, component, *score),
+        Some(Commands::Apply {
+            manifest,
+            remove_extras,
+            idempotent,
+        }) => do_apply(config, manifest, *remove_extras, *idempotent),
+        Some(Commands::Snapshot {
+            command: SnapshotCommands::Create { file },
+        }) => do_snapshot_create(config, file),
+        Some(Commands::Registry {
+            command: RegistryCommands::Fsck { fix },
+        }) => do_registry_fsck(*fix),
+        Some(Commands::InstallTimer {
+            interval,
+            auto_apply,
+        }) => do_install_timer(cli, interval, *auto_apply),
+        Some(Commands::RemoveTimer) => do_remove_timer(cli),
+        Some(Commands::Serve { .. }) => {
+            serve::serve_stdio(config.inner.clone())?;
+            Ok(ExitCode::Success)
+        }
+        Some(Commands::GenerateMan { dir }) => {
+            generate_man(dir.as_deref())?;
+            Ok(ExitCode::Success)
+        }
+    }
+}
+
+/// Re-runs the command as each Plasma user so their files stay owned by them
+/// and their own registry and config are used.
+fn do_all_users(args: &[String]) -> Result<ExitCode, libplasmoid_updater::Error> {
+    let exe = std::env::current_exe()
+        .map_err(|e| libplasmoid_updater::Error::other(format!("failed to locate binary: {e}")))?;
+
+    let mut code = ExitCode::NothingToDo;
+    for user in find_users()? {
+        println!("\n== {} ==", user.name);
+        let status = user.command(&exe).args(args).status().map_err(|e| {
+            libplasmoid_updater::Error::other(format!("failed to run as {}: {e}", user.name))
+        })?;
+        code = code.combine(ExitCode::from_status(status.code()));
+    }
+
+    Ok(code)
+}
+
+/// Arguments for the per-user run. Other users' sessions can't be reached,
+/// so updates are non-interactive and never restart plasmashell or KWin.
+fn per_user_args(cli: &Cli) -> Option<Vec<String>> {
+    let mut args = match &cli.command {
+        Some(Commands::Check {
+            types,
+            verbose,
+            format,
+        }) => {
+            let mut args = vec!["check".to_string()];
+            args.extend(type_args(types));
+            if *verbose {
+                args.push("--verbose".to_string());
+            }
+            if *format == CheckFormat::Markdown {
+                args.extend(["--format".to_string(), "markdown".to_string()]);
+            }
+            args
+        }
+        Some(Commands::ListInstalled {
+            verbose,
+            types,
+            outdated,
+            orphaned,
+            sort,
+        }) => {
+            let mut args = vec!["list-installed".to_string()];
+            args.extend(type_args(types));
+            for (set, flag) in [
+                (*verbose, "--verbose"),
+                (*outdated, "--outdated"),
+                (*orphaned, "--orphaned"),
+            ] {
+                if set {
+                    args.push(flag.to_string());
+                }
+            }
+            args.extend(["--sort".to_string(), sort_name(*sort).to_string()]);
+            args
+        }
+        Some(Commands::Orphans) => vec!["orphans".to_string()],
+        Some(Commands::Dedupe { apply }) => {
+            let mut args = vec!["dedupe".to_string()];
+            if *apply {
+                args.push("--apply".to_string());
+            }
+            args
+        }
+        Some(Commands::Info { component }) => vec!["info".to_string(), component.clone()],
+        Some(Commands::Explain { component, json }) => {
+            let mut args = vec!["explain".to_string(), component.clone()];
+            if *json {
+                args.push("--json".to_string());
+            }
+            args
+        }
+        Some(Commands::DiskUsage { json }) => {
+            let mut args = vec!["disk-usage".to_string()];
+            if *json {
+                args.push("--json".to_string());
+            }
+            args
+        }
+        Some(Commands::CleanCache) => vec!["clean-cache".to_string()],
+        Some(Commands::Verify { component }) => {
+            let mut args = vec!["verify".to_string()];
+            args.extend(component.clone());
+            args
+        }
+        Some(Commands::Recover) => vec!["recover".to_string()],
+        None => vec!["update".to_string()],
+        Some(Commands::Update {
+            components,
+            types,
+            group,
+            ..
+        }) => {
+            let mut args = vec!["update".to_string()];
+            args.extend(components.iter().cloned());
+            args.extend(type_args(types));
+            if let Some(group) = group {
+                args.extend(["--group".to_string(), group.clone()]);
+            }
+            args
+        }
+        Some(_) => return None,
+    };
+    if args[0] == "update" {
+        args.extend([
+            "--yes".to_string(),
+            "--no-restart-plasma".to_string(),
+            "--no-reload-kwin".to_string(),
+        ]);
+    }
+    if cli.skip_plasma_detection {
+        args.push("--skip-plasma-detection".to_string());
+    }
+    if cli.strict {
+        args.push("--strict".to_string());
+    }
+    if let Some(path) = &cli.snapshot {
+        args.extend(["--snapshot".to_string(), path.display().to_string()]);
+    }
+    if cli.ignore_advisories {
+        args.push("--ignore-advisories".to_string());
+    }
+    if cli.full {
+        args.push("--full".to_string());
+    }
+    if let Some(mode) = cli.color {
+        args.extend(["--color".to_string(), color_name(mode).to_string()]);
     }
+    if let Some(mode) = cli.progress {
+        args.extend(["--progress".to_string(), progress_name(mode).to_string()]);
+    }
+    Some(args)
+}
+
+fn type_args(types: &[ComponentType]) -> Vec<String> {
+    types
+        .iter()
+        .flat_map(|ct| ["--type".to_string(), type_name(*ct)])
+        .collect()
+}
+
+/// The snake_case name accepted by `--type` and the config file.
+fn type_name(component_type: ComponentType) -> String {
+    serde_json::to_value(component_type)
+        .ok()
+        .and_then(|v| v.as_str().map(str::to_string))
+        .unwrap_or_default()
 }
 
-fn do_check(config: &CliConfig) -> Result<ExitCode, libplasmoid_updater::Error> {
-    check(&config.inner)?;
+/// The name accepted by `--color` and the config file.
+fn color_name(mode: ColorMode) -> &'static str {
+    match mode {
+        ColorMode::Auto => "auto",
+        ColorMode::Always => "always",
+        ColorMode::Never => "never",
+    }
+}
+
+/// The name accepted by `--progress` and the config file.
+fn progress_name(mode: ProgressMode) -> &'static str {
+    match mode {
+        ProgressMode::Fancy => "fancy",
+        ProgressMode::Plain => "plain",
+        ProgressMode::None => "none",
+    }
+}
+
+/// The name accepted by `list-installed --sort`.
+fn sort_name(sort: InstalledSort) -> &'static str {
+    match sort {
+        InstalledSort::Name => "name",
+        InstalledSort::Type => "type",
+        InstalledSort::Version => "version",
+        InstalledSort::Size => "size",
+    }
+}
+
+fn do_check(
+    config: &CliConfig,
+    types: &[ComponentType],
+    verbose: bool,
+    format: CheckFormat,
+    strict: bool,
+) -> Result<ExitCode, libplasmoid_updater::Error> {
+    let mut check_config = config.inner.clone().with_included_types(types.to_vec());
+    if format == CheckFormat::Markdown && check_config.event_sink.is_none() {
+        // Any sink replaces the built-in terminal output.
+        check_config.event_sink = Some(EventSink::new(|_| {}));
+    }
+    let result = check(&check_config)?;
+    if format == CheckFormat::Markdown {
+        print!("{}", result.to_markdown());
+    } else if verbose && check_config.event_sink.is_none() {
+        show_changes(&result.available_updates);
+        println!(
+            "{}",
+            tr!(
+                "store-usage",
+                "store usage: {usage}",
+                usage = result.api_stats.summary(),
+            )
+        );
+    }
+    Ok(ExitCode::from_check(&result, strict))
+}
+
+fn do_list_installed(
+    config: &CliConfig,
+    types: &[ComponentType],
+    query: InstalledQuery,
+    verbose: bool,
+) -> Result<ExitCode, libplasmoid_updater::Error> {
+    let list_config = config.inner.clone().with_included_types(types.to_vec());
+    show_installed_with(&list_config, query, verbose)?;
     Ok(ExitCode::Success)
 }
 
-fn do_list_installed(config: &CliConfig) -> Result<ExitCode, libplasmoid_updater::Error> {
-    show_installed(&config.inner)?;
+fn do_orphans(config: &CliConfig) -> Result<ExitCode, libplasmoid_updater::Error> {
+    let orphans = orphans(&config.inner)?;
+    if orphans.is_empty() {
+        println!("{}", tr!("no-orphans", "no orphaned components found"));
+        return Ok(ExitCode::NothingToDo);
+    }
+
+    for diagnostic in &orphans {
+        println!("{}: {}", diagnostic.name, diagnostic.reason);
+    }
     Ok(ExitCode::Success)
 }
 
-fn do_update(config: &CliConfig, args: UpdateArgs) -> Result<ExitCode, libplasmoid_updater::Error> {
-    let mut update_config = config.inner.clone();
+fn do_dedupe(config: &CliConfig, apply: bool) -> Result<ExitCode, libplasmoid_updater::Error> {
+    let duplicates = if apply {
+        dedupe(&config.inner)?
+    } else {
+        find_duplicates(&config.inner)?
+    };
+    if duplicates.is_empty() {
+        println!(
+            "{}",
+            tr!("no-duplicates", "no duplicate installations found")
+        );
+        return Ok(ExitCode::NothingToDo);
+    }
 
-    if args.yes || config.assume_yes || config.update_all_by_default {
-        update_config.auto_confirm = true;
+    for duplicate in &duplicates {
+        let user = &duplicate.user;
+        println!(
+            "{}",
+            tr!(
+                "duplicate",
+                "{name}: {version} in {path} hides {system_version} in {system_path}",
+                name = user.name,
+                version = user.version,
+                path = user.path.display().to_string(),
+                system_version = duplicate.system_version,
+                system_path = duplicate.system_path.display().to_string(),
+            )
+        );
+        let note = match duplicate.redundant {
+            DuplicateCopy::User if duplicate.removed => {
+                tr!("duplicate-removed", "removed the user copy")
+            }
+            DuplicateCopy::User => tr!(
+                "duplicate-redundant",
+                "the user copy is redundant; remove it with --apply"
+            ),
+            DuplicateCopy::System => tr!(
+                "duplicate-system-older",
+                "the system copy is older; uninstall it with your package manager or remove {path}",
+                path = duplicate.system_path.display().to_string(),
+            ),
+        };
+        println!("  {note}");
     }
+    Ok(ExitCode::Success)
+}
 
-    if args.restart_plasma {
-        update_config.restart = libplasmoid_updater::RestartBehavior::Always;
-    } else if args.no_restart_plasma {
-        update_config.restart = libplasmoid_updater::RestartBehavior::Never;
+fn do_disk_usage(config: &CliConfig, json: bool) -> Result<ExitCode, libplasmoid_updater::Error> {
+    let usage = disk_usage(&config.inner)?;
+    if json {
+        println!("{}", serde_json::to_string_pretty(&usage)?);
+    } else {
+        show_disk_usage(&usage);
     }
+    Ok(ExitCode::Success)
+}
 
-    if let Some(ref name) = args.component {
-        return do_update_single(name, update_config);
+fn do_report(
+    config: &CliConfig,
+    format: ReportFormat,
+) -> Result<ExitCode, libplasmoid_updater::Error> {
+    let Some(run) = last_run(&config.inner) else {
+        println!("{}", tr!("no-runs", "no update runs recorded yet"));
+        return Ok(ExitCode::NothingToDo);
+    };
+    match format {
+        ReportFormat::Terminal => run.print(),
+        ReportFormat::Html => print!("{}", run.to_html()),
     }
+    Ok(ExitCode::Success)
+}
 
-    do_full_update(update_config)
+fn do_doctor(config: &CliConfig, json: bool) -> Result<ExitCode, libplasmoid_updater::Error> {
+    let report = doctor(&config.inner);
+    if json {
+        println!("{}", serde_json::to_string_pretty(&report)?);
+    } else {
+        println!("plasmoid-updater {}", env!("CARGO_PKG_VERSION"));
+        report.print();
+    }
+    Ok(if report.has_failures() {
+        ExitCode::PartialFailure
+    } else {
+        ExitCode::Success
+    })
 }
 
-fn do_update_single(
-    name: &str,
-    mut config: libplasmoid_updater::Config,
+fn do_clean_cache() -> Result<ExitCode, libplasmoid_updater::Error> {
+    let freed = clean_cache()?;
+    if freed == 0 {
+        println!("{}", tr!("cache-empty", "download cache is empty"));
+        return Ok(ExitCode::NothingToDo);
+    }
+    let mib = format!("{:.1}", freed as f64 / (1024.0 * 1024.0));
+    println!("{}", tr!("cache-freed", "freed {mib} MiB", mib = mib));
+    Ok(ExitCode::Success)
+}
+
+fn do_install_timer(
+    cli: &Cli,
+    interval: &str,
+    auto_apply: bool,
 ) -> Result<ExitCode, libplasmoid_updater::Error> {
-    let check_result = check(&config)?;
+    reject_system_timer(cli)?;
+    let dir = timer::install(interval, auto_apply)?;
+    let action = if auto_apply {
+        tr!("timer-installs", "installs updates")
+    } else {
+        tr!("timer-checks", "checks for updates")
+    };
+    let when = if interval.chars().all(|c| c.is_ascii_alphabetic()) {
+        interval.to_string()
+    } else {
+        tr!("timer-every", "every {interval}", interval = interval)
+    };
+    println!(
+        "{}",
+        tr!(
+            "timer-installed",
+            "installed plasmoid-updater-check.timer in {dir}; it {action} {when}",
+            dir = dir.display().to_string(),
+            action = action,
+            when = when,
+        )
+    );
+    println!(
+        "{}",
+        tr!(
+            "timer-hint",
+            "see `systemctl --user list-timers` and `journalctl --user -u plasmoid-updater-check`"
+        )
+    );
+    Ok(ExitCode::Success)
+}
 
-    let matched = check_result
-        .available_updates
-        .iter()
-        .any(|u| u.installed.name == name || u.installed.directory_name == name);
+fn do_remove_timer(cli: &Cli) -> Result<ExitCode, libplasmoid_updater::Error> {
+    reject_system_timer(cli)?;
+    if !timer::remove()? {
+        println!("{}", tr!("timer-none", "no timer installed"));
+        return Ok(ExitCode::NothingToDo);
+    }
+    println!(
+        "{}",
+        tr!("timer-removed", "removed plasmoid-updater-check.timer")
+    );
+    Ok(ExitCode::Success)
+}
 
-    if !matched {
-        println!("no update available for '{name}'");
-        return Ok(ExitCode::Success);
+/// The timer is a user unit; system components need a hand-written system unit.
+fn reject_system_timer(cli: &Cli) -> Result<(), libplasmoid_updater::Error> {
+    if cli.system {
+        return Err(libplasmoid_updater::Error::other(
+            "the timer only covers user components; run without --system",
+        ));
     }
+    Ok(())
+}
 
-    let excluded: Vec<String> = check_result
-        .available_updates
+fn do_verify(
+    config: &CliConfig,
+    component: Option<&str>,
+) -> Result<ExitCode, libplasmoid_updater::Error> {
+    let reports = verify(component, &config.inner)?;
+
+    let mut changed = false;
+    for report in &reports {
+        let Some(version) = &report.recorded_version else {
+            if component.is_some() {
+                println!(
+                    "{}",
+                    tr!(
+                        "verify-no-record",
+                        "{name}: no install record to compare against",
+                        name = report.name,
+                    )
+                );
+            }
+            continue;
+        };
+        if report.is_intact() {
+            println!(
+                "{}",
+                tr!(
+                    "verify-ok",
+                    "{name} {version}: ok",
+                    name = report.name,
+                    version = version,
+                )
+            );
+            continue;
+        }
+        changed = true;
+        println!(
+            "{}",
+            tr!(
+                "verify-changed",
+                "{name} {version}: {modified} modified, {missing} missing, {extra} extra",
+                name = report.name,
+                version = version,
+                modified = report.modified.len(),
+                missing = report.missing.len(),
+                extra = report.extra.len(),
+            )
+        );
+        for (files, label) in [
+            (&report.modified, tr!("verify-modified", "modified")),
+            (&report.missing, tr!("verify-missing", "missing")),
+            (&report.extra, tr!("verify-extra", "extra")),
+        ] {
+            for file in files {
+                println!("  {label:<8} {file}");
+            }
+        }
+    }
+
+    let unrecorded = reports
         .iter()
-        .filter(|u| u.installed.name != name && u.installed.directory_name != name)
-        .map(|u| u.installed.directory_name.clone())
-        .collect();
+        .filter(|r| r.recorded_version.is_none())
+        .count();
+    if component.is_none() && unrecorded > 0 {
+        println!(
+            "{}",
+            tr!(
+                "verify-unrecorded",
+                "{count} components have no install record to compare against",
+                count = unrecorded,
+            )
+        );
+    }
+
+    if changed {
+        Ok(ExitCode::PartialFailure)
+    } else {
+        Ok(ExitCode::Success)
+    }
+}
 
-    config.excluded_packages.extend(excluded);
-    config.auto_confirm = true;
+/// Points at `recover` when an earlier run was interrupted mid-install;
+/// `update` offers the recovery itself.
+fn warn_interrupted(cli: &Cli, config: &CliConfig) {
+    if config.inner.event_sink.is_some()
+        || matches!(
+            cli.command,
+            None | Some(Commands::Update { .. } | Commands::Recover)
+        )
+    {
+        return;
+    }
+    let pending = pending_recovery(&config.inner);
+    if !pending.is_empty() {
+        eprintln!(
+            "{}",
+            tr!(
+                "interrupted",
+                "warning: {count} install(s) were interrupted, run `plasmoid-updater recover` to restore them",
+                count = pending.len(),
+            )
+        );
+    }
+}
 
-    do_full_update(config)
+fn do_recover(config: &CliConfig) -> Result<ExitCode, libplasmoid_updater::Error> {
+    let result = recover(&config.inner)?;
+    if result.recovered.is_empty() && result.failed.is_empty() {
+        println!("{}", tr!("nothing-to-recover", "nothing to recover"));
+        return Ok(ExitCode::NothingToDo);
+    }
+    for name in &result.recovered {
+        println!("{}", tr!("restored", "restored {name}", name = name));
+    }
+    for failed in &result.failed {
+        eprintln!("{}: {}", failed.name, failed.error);
+    }
+    Ok(if result.failed.is_empty() {
+        ExitCode::Success
+    } else {
+        ExitCode::PartialFailure
+    })
 }
 
-fn do_full_update(
-    config: libplasmoid_updater::Config,
+fn do_rate(
+    config: &CliConfig,
+    component: &str,
+    score: u8,
 ) -> Result<ExitCode, libplasmoid_updater::Error> {
-    let result = update(&config)?;
+    let content_id = rate(component, score, &config.inner)?;
+    println!(
+        "{}",
+        tr!(
+            "rated",
+            "rated {component} {score}/10 (https://store.kde.org/p/{content_id})",
+            component = component,
+            score = score,
+            content_id = content_id,
+        )
+    );
+    Ok(ExitCode::Success)
+}
 
-    if result.is_empty() {
-        return Ok(ExitCode::Success);
+fn do_apply(
+    config: &CliConfig,
+    manifest: &Path,
+    remove_extras: bool,
+    idempotent: bool,
+) -> Result<ExitCode, libplasmoid_updater::Error> {
+    let manifest = if config.trusted_keys.is_empty() {
+        Manifest::load(manifest)?
+    } else {
+        Manifest::load_signed(manifest, &config.trusted_keys)?
+    };
+
+    let mut apply_config = config.inner.clone();
+    if idempotent && apply_config.event_sink.is_none() {
+        // Any sink replaces the built-in terminal output.
+        apply_config.event_sink = Some(EventSink::new(|_| {}));
+    }
+    let result = apply(&manifest, remove_extras, &apply_config)?;
+
+    if idempotent {
+        let mut output = serde_json::to_value(&result)?;
+        output["changed"] = result.changed().into();
+        println!("{output}");
+    } else {
+        for (names, action) in [
+            (&result.installed, tr!("apply-installed", "installed")),
+            (&result.updated, tr!("apply-updated", "updated")),
+            (&result.removed, tr!("apply-removed", "removed")),
+            (&result.unchanged, tr!("apply-unchanged", "up to date")),
+        ] {
+            for name in names {
+                println!("{name}: {action}");
+            }
+        }
+        for failed in &result.failed {
+            eprintln!("{}: {}", failed.name, failed.error);
+        }
     }
 
-    result.print_summary();
     if result.has_failures() {
-        result.print_error_table();
         Ok(ExitCode::PartialFailure)
     } else {
         Ok(ExitCode::Success)
     }
 }
 
+fn do_info(config: &CliConfig, component: &str) -> Result<ExitCode, libplasmoid_updater::Error> {
+    let report = inspect_component(component, &config.inner)?;
+    let installed = &report.installed;
+    let field = |label: &str, value: &dyn std::fmt::Display| {
+        println!("  {:<12}{value}", format!("{label}:"));
+    };
+
+    println!("{} ({})", installed.name, installed.directory_name);
+    field(&tr!("info-type", "type"), &installed.component_type);
+    field(&tr!("info-version", "version"), &installed.version);
+    field(&tr!("info-path", "path"), &installed.path.display());
+    let metadata = &installed.metadata;
+    for (label, value) in [
+        (tr!("info-author", "author"), &metadata.author),
+        (tr!("info-license", "license"), &metadata.license),
+        (tr!("info-website", "website"), &metadata.website),
+        (tr!("info-summary", "summary"), &metadata.description),
+    ] {
+        if let Some(value) = value {
+            field(&label, value);
+        }
+    }
+
+    println!("\n{}", tr!("info-registry", "registry"));
+    match &report.registry {
+        Some(entry) => {
+            if let Some(id) = entry.content_id {
+                field(&tr!("info-content-id", "content id"), &id);
+            }
+            field(&tr!("info-version", "version"), &entry.version);
+            field(&tr!("info-released", "released"), &entry.release_date);
+        }
+        None => println!("  {}", tr!("info-not-registered", "not registered")),
+    }
+
+    println!("\n{}", tr!("info-store", "store"));
+    match &report.store {
+        Some(store) => {
+            println!("  {} ({})", store.name, store.store_url);
+            let status = if store.update_available {
+                tr!("info-update-available", "update available")
+            } else {
+                tr!("info-up-to-date", "up to date")
+            };
+            field(
+                &tr!("info-latest", "latest"),
+                &format!("{} ({status})", store.latest_version),
+            );
+            field(&tr!("info-released", "released"), &store.release_date);
+            for download in &store.downloads {
+                field(
+                    &tr!("info-download", "download"),
+                    &format!("{} {}", download.label(), download.url),
+                );
+            }
+            if !store.changelog.is_empty() {
+                println!("  {}:", tr!("info-changelog", "changelog"));
+                for line in store.changelog.lines() {
+                    println!("    {line}");
+                }
+            }
+        }
+        None => println!(
+            "  {}",
+            tr!("info-no-store-entry", "no matching store entry")
+        ),
+    }
+
+    if !report.backups.is_empty() {
+        println!("\n{}", tr!("info-backups", "backups"));
+        for backup in &report.backups {
+            println!("  {}", backup.display());
+        }
+    }
+    if !report.retained.is_empty() {
+        println!("\n{}", tr!("info-kept-versions", "kept versions"));
+        for entry in &report.retained {
+            println!(
+                "  {}",
+                tr!(
+                    "info-kept-version",
+                    "{version}  (kept {date})",
+                    version = entry.version,
+                    date = entry.retained_at,
+                )
+            );
+        }
+    }
+    if let Some(payload) = &report.payload {
+        println!("\n{}", tr!("info-package", "installed package"));
+        field(&tr!("info-version", "version"), &payload.version);
+        field("sha256", &payload.sha256);
+        field(&tr!("info-installed", "installed"), &payload.pinned_at);
+    }
+
+    println!("\n{}", tr!("info-config", "config"));
+    let excluded = if report.excluded {
+        tr!("info-yes", "yes")
+    } else {
+        tr!("info-no", "no")
+    };
+    field(&tr!("info-excluded", "excluded"), &excluded);
+    if !report.groups.is_empty() {
+        field(&tr!("info-groups", "groups"), &report.groups.join(", "));
+    }
+    if let Some(variant) = &report.download_variant {
+        field(&tr!("info-download", "download"), variant);
+    }
+
+    Ok(ExitCode::Success)
+}
+
+fn do_explain(
+    config: &CliConfig,
+    component: &str,
+    json: bool,
+) -> Result<ExitCode, libplasmoid_updater::Error> {
+    let explanation = explain_component(component, &config.inner)?;
+    if json {
+        println!("{}", serde_json::to_string_pretty(&explanation)?);
+        return Ok(ExitCode::Success);
+    }
+
+    let installed = &explanation.component;
+    println!("{} ({})", installed.name, installed.directory_name);
+    println!("  {}", explanation.summary());
+    println!("\n{}", tr!("explain-resolution", "resolution"));
+    for step in &explanation.trace {
+        println!("  {step}");
+    }
+    if !explanation.blockers.is_empty() {
+        println!("\n{}", tr!("explain-held-back", "held back"));
+        for blocker in &explanation.blockers {
+            println!("  {blocker}");
+        }
+    }
+    Ok(ExitCode::Success)
+}
+
+fn do_install(
+    config: &CliConfig,
+    file: &Path,
+    component_type: ComponentType,
+) -> Result<ExitCode, libplasmoid_updater::Error> {
+    let result = install_from_file(file, component_type, &config.inner)?;
+    let component = &result.component;
+    let version = if component.version.is_empty() {
+        String::new()
+    } else {
+        format!(" {}", component.version)
+    };
+    println!(
+        "{}",
+        tr!(
+            "installed-file",
+            "installed {name}{version} to {path}",
+            name = component.name,
+            version = version,
+            path = component.path.display().to_string(),
+        )
+    );
+    let store = match result.content_id {
+        Some(id) => tr!(
+            "installed-matched",
+            "matched to KDE Store entry {id}; it will be kept up to date",
+            id = id,
+        ),
+        None => tr!(
+            "installed-unmatched",
+            "no KDE Store entry found; it will not receive updates"
+        ),
+    };
+    println!("{store}");
+    Ok(ExitCode::Success)
+}
+
+fn do_adopt(
+    config: &CliConfig,
+    component: Option<&str>,
+) -> Result<ExitCode, libplasmoid_updater::Error> {
+    let result = adopt(component, &config.inner)?;
+
+    if result.is_empty() {
+        println!(
+            "{}",
+            tr!("no-unregistered", "no unregistered components found")
+        );
+        return Ok(ExitCode::NothingToDo);
+    }
+
+    if !result.adopted.is_empty() {
+        result.print_table();
+    }
+    for diagnostic in &result.unresolved {
+        println!(
+            "{}",
+            tr!(
+                "adopt-failed",
+                "could not adopt {name}: {reason}",
+                name = diagnostic.name,
+                reason = diagnostic.reason,
+            )
+        );
+    }
+
+    if result.unresolved.is_empty() {
+        Ok(ExitCode::Success)
+    } else {
+        Ok(ExitCode::PartialFailure)
+    }
+}
+
+fn do_downgrade(
+    config: &CliConfig,
+    component: &str,
+    version: Option<&str>,
+) -> Result<ExitCode, libplasmoid_updater::Error> {
+    let Some(version) = version else {
+        let retained = retained_versions(component, &config.inner)?;
+        if retained.is_empty() {
+            println!(
+                "{}",
+                tr!(
+                    "no-kept-versions",
+                    "no versions of {component} are kept (see keep_versions)",
+                    component = component,
+                )
+            );
+        }
+        for entry in &retained {
+            println!("{}  (kept {})", entry.version, entry.retained_at);
+        }
+        return Ok(ExitCode::Success);
+    };
+
+    downgrade(component, version, &config.inner)?;
+    println!(
+        "{}",
+        tr!(
+            "downgraded",
+            "{component} is now at {version}; restart plasmashell to load it",
+            component = component,
+            version = version,
+        )
+    );
+    Ok(ExitCode::Success)
+}
+
+fn do_reinstall(
+    config: &CliConfig,
+    component: &str,
+) -> Result<ExitCode, libplasmoid_updater::Error> {
+    let pin = reinstall(component, &config.inner)?;
+    println!(
+        "{}",
+        tr!(
+            "reinstalled",
+            "reinstalled {component} {version} (sha256 {sha256}); restart plasmashell to load it",
+            component = component,
+            version = pin.version,
+            sha256 = pin.sha256,
+        )
+    );
+    Ok(ExitCode::Success)
+}
+
+fn do_snapshot_create(
+    config: &CliConfig,
+    file: &Path,
+) -> Result<ExitCode, libplasmoid_updater::Error> {
+    let snapshot = create_snapshot(&config.inner)?;
+    snapshot.save(file)?;
+    println!(
+        "{}",
+        tr!(
+            "snapshot-saved",
+            "saved {count} store entries to {file}",
+            count = snapshot.len(),
+            file = file.display().to_string(),
+        )
+    );
+    Ok(ExitCode::Success)
+}
+
+fn do_registry_fsck(fix: bool) -> Result<ExitCode, libplasmoid_updater::Error> {
+    let report = if fix {
+        repair_registry()?
+    } else {
+        audit_registry()?
+    };
+
+    print_registry_report(&report);
+
+    if report.is_clean() || report.repaired {
+        Ok(ExitCode::Success)
+    } else {
+        Ok(ExitCode::PartialFailure)
+    }
+}
+
+fn print_registry_report(report: &RegistryReport) {
+    if report.is_clean() {
+        println!("{}", tr!("registry-clean", "registry is consistent"));
+        return;
+    }
+
+    report.print_table();
+    let count = report.issues.len();
+    let plural = if count == 1 { "" } else { "s" };
+    let message = if report.repaired {
+        tr!(
+            "registry-fixed",
+            "fixed {count} registry issue{plural}",
+            count = count
+        )
+    } else {
+        tr!(
+            "registry-issues",
+            "{count} registry issue{plural} found, run with --fix to repair",
+            count = count,
+        )
+    };
+    println!("{message}");
+}
+
+fn do_update(config: &CliConfig, args: UpdateArgs) -> Result<ExitCode, libplasmoid_updater::Error> {
+    let mut update_config = config.inner.clone().with_included_types(args.types);
+    if let Some(group) = args.group {
+        update_config = update_config.with_group(group);
+    }
+
+    if args.yes || config.assume_yes || config.update_all_by_default {
+        update_config.auto_confirm = true;
+    }
+
+    if args.restart_plasma {
+        update_config.restart = libplasmoid_updater::RestartBehavior::Always;
+    } else if args.no_restart_plasma {
+        update_config.restart = libplasmoid_updater::RestartBehavior::Never;
+    }
+
+    if args.no_reload_kwin {
+        update_config.reload_kwin = false;
+    }
+
+    if args.force_distro_owned {
+        update_config.force_distro_owned = true;
+    }
+
+    if !args.components.is_empty() {
+        update_config.auto_confirm = true;
+        update_config = update_config.with_only_packages(args.components);
+    }
+
+    do_full_update(update_config, args.strict)
+}
+
+fn do_full_update(
+    config: libplasmoid_updater::Config,
+    strict: bool,
+) -> Result<ExitCode, libplasmoid_updater::Error> {
+    let result = update(&config)?;
+
+    if !result.is_empty() && config.event_sink.is_none() {
+        result.print_summary();
+        if result.has_failures() {
+            result.print_error_table();
+        }
+    }
+
+    Ok(ExitCode::from_update(&result, strict))
+}
+
+/// Renders man pages from the clap definitions, for packagers.
+fn generate_man(dir: Option<&Path>) -> Result<(), libplasmoid_updater::Error> {
+    let io_error = |e: std::io::Error| {
+        libplasmoid_updater::Error::other(format!("failed to write man page: {e}"))
+    };
+
+    let cmd = Cli::command();
+    match dir {
+        Some(dir) => {
+            std::fs::create_dir_all(dir).map_err(io_error)?;
+            clap_mangen::generate_to(cmd, dir).map_err(io_error)
+        }
+        None => clap_mangen::Man::new(cmd)
+            .render(&mut std::io::stdout())
+            .map_err(io_error),
+    }
+}
+
 fn is_root_user() -> bool {
     nix::unistd::Uid::effective().is_root()
 }
@@ -0,0 +1,237 @@
+// SPDX-License-Identifier: GPL-3.0-or-later
+
+//! `serve --stdio`: a small JSON-RPC 2.0 protocol over stdin/stdout, so a
+//! front end (e.g. a QML plasmoid) can keep one process running instead of
+//! spawning the CLI per action and parsing its human output.
+//!
+//! Requests and responses are one JSON object per line. Methods:
+//!
+//! - `check` `{types?}` → check result
+//! - `update` `{component?, types?, group?}` → update result
+//! - `cancel` → `true` if a running update was asked to stop
+//! - `subscribe-progress` → `true`; progress events then arrive as
+//!   `{"jsonrpc":"2.0","method":"progress","params":<event>}` notifications
+//!
+//! One check or update runs at a time, on a worker thread, so `cancel` is
+//! read while it is in progress. The server exits at end of input.
+
+use std::{
+    io::{BufRead, Write},
+    sync::{
+        Arc, Mutex,
+        atomic::{AtomicBool, Ordering},
+    },
+    thread::JoinHandle,
+};
+
+use libplasmoid_updater::{CancelToken, ComponentType, Config, EventSink, check, update};
+use serde::Deserialize;
+use serde_json::{Value, json};
+
+const PARSE_ERROR: i64 = -32700;
+const INVALID_REQUEST: i64 = -32600;
+const METHOD_NOT_FOUND: i64 = -32601;
+const INVALID_PARAMS: i64 = -32602;
+/// A check or update failed, or another one is still running.
+const SERVER_ERROR: i64 = -32000;
+
+#[derive(Deserialize)]
+struct Request {
+    #[serde(default)]
+    id: Value,
+    method: String,
+    #[serde(default)]
+    params: Value,
+}
+
+#[derive(Default, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+struct CheckParams {
+    types: Vec<ComponentType>,
+}
+
+#[derive(Default, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+struct UpdateParams {
+    component: Option<String>,
+    types: Vec<ComponentType>,
+    group: Option<String>,
+}
+
+/// Serialises writes so responses and progress notifications never interleave.
+#[derive(Clone)]
+struct Output(Arc<Mutex<std::io::Stdout>>);
+
+impl Output {
+    fn send(&self, message: &Value) {
+        let mut stdout = self.0.lock().unwrap_or_else(|e| e.into_inner());
+        let _ = writeln!(stdout, "{message}").and_then(|()| stdout.flush());
+    }
+
+    fn result(&self, id: Value, result: Value) {
+        self.send(&json!({ "jsonrpc": "2.0", "id": id, "result": result }));
+    }
+
+    fn error(&self, id: Value, code: i64, message: impl Into<String>) {
+        self.send(&json!({
+            "jsonrpc": "2.0",
+            "id": id,
+            "error": { "code": code, "message": message.into() },
+        }));
+    }
+}
+
+struct Job {
+    handle: JoinHandle<()>,
+    cancel: CancelToken,
+}
+
+struct Server {
+    config: Config,
+    output: Output,
+    subscribed: Arc<AtomicBool>,
+    job: Option<Job>,
+}
+
+/// Runs the stdio server until stdin is closed.
+pub fn serve_stdio(config: Config) -> Result<(), libplasmoid_updater::Error> {
+    let mut server = Server {
+        config,
+        output: Output(Arc::new(Mutex::new(std::io::stdout()))),
+        subscribed: Arc::new(AtomicBool::new(false)),
+        job: None,
+    };
+
+    for line in std::io::stdin().lock().lines() {
+        let line = line
+            .map_err(|e| libplasmoid_updater::Error::other(format!("failed to read stdin: {e}")))?;
+        if !line.trim().is_empty() {
+            server.handle(&line);
+        }
+    }
+
+    if let Some(job) = server.job.take() {
+        job.cancel.cancel();
+        let _ = job.handle.join();
+    }
+
+    Ok(())
+}
+
+impl Server {
+    fn handle(&mut self, line: &str) {
+        let value: Value = match serde_json::from_str(line) {
+            Ok(value) => value,
+            Err(e) => return self.output.error(Value::Null, PARSE_ERROR, e.to_string()),
+        };
+        let request: Request = match serde_json::from_value(value) {
+            Ok(request) => request,
+            Err(e) => {
+                return self
+                    .output
+                    .error(Value::Null, INVALID_REQUEST, e.to_string());
+            }
+        };
+
+        match request.method.as_str() {
+            "check" => match parse_params::<CheckParams>(request.params) {
+                Ok(params) => self.spawn(request.id, move |config| {
+                    let result = check(&config.with_included_types(params.types))?;
+                    Ok(json!(result))
+                }),
+                Err(e) => self.output.error(request.id, INVALID_PARAMS, e),
+            },
+            "update" => match parse_params::<UpdateParams>(request.params) {
+                Ok(params) => self.spawn(request.id, move |config| run_update(config, params)),
+                Err(e) => self.output.error(request.id, INVALID_PARAMS, e),
+            },
+            "cancel" => {
+                let running = self.running_job();
+                if let Some(job) = running {
+                    job.cancel.cancel();
+                }
+                self.output.result(request.id, json!(running.is_some()));
+            }
+            "subscribe-progress" => {
+                self.subscribed.store(true, Ordering::Release);
+                self.output.result(request.id, json!(true));
+            }
+            method => self.output.error(
+                request.id,
+                METHOD_NOT_FOUND,
+                format!("unknown method '{method}'"),
+            ),
+        }
+    }
+
+    fn running_job(&self) -> Option<&Job> {
+        self.job.as_ref().filter(|job| !job.handle.is_finished())
+    }
+
+    /// Runs `task` on a worker thread and answers `id` when it finishes.
+    fn spawn(
+        &mut self,
+        id: Value,
+        task: impl FnOnce(Config) -> Result<Value, libplasmoid_updater::Error> + Send + 'static,
+    ) {
+        if self.running_job().is_some() {
+            return self
+                .output
+                .error(id, SERVER_ERROR, "another check or update is running");
+        }
+
+        let cancel = CancelToken::new();
+        let config = self
+            .config
+            .clone()
+            .with_event_sink(self.progress_sink())
+            .with_cancel_token(cancel.clone());
+        let output = self.output.clone();
+
+        let handle = std::thread::spawn(move || match task(config) {
+            Ok(result) => output.result(id, result),
+            Err(e) => output.error(id, SERVER_ERROR, e.to_string()),
+        });
+        self.job = Some(Job { handle, cancel });
+    }
+
+    /// Forwards events as `progress` notifications once subscribed.
+    fn progress_sink(&self) -> EventSink {
+        let output = self.output.clone();
+        let subscribed = Arc::clone(&self.subscribed);
+        EventSink::new(move |event| {
+            if subscribed.load(Ordering::Acquire) {
+                output.send(&json!({ "jsonrpc": "2.0", "method": "progress", "params": event }));
+            }
+        })
+    }
+}
+
+fn parse_params<T: Default + for<'de> Deserialize<'de>>(params: Value) -> Result<T, String> {
+    if params.is_null() {
+        return Ok(T::default());
+    }
+    serde_json::from_value(params).map_err(|e| e.to_string())
+}
+
+fn run_update(config: Config, params: UpdateParams) -> Result<Value, libplasmoid_updater::Error> {
+    let mut config = config.with_included_types(params.types);
+    if let Some(group) = params.group {
+        config = config.with_group(group);
+    }
+    config.auto_confirm = true;
+
+    if let Some(name) = &params.component {
+        config = config.with_only_packages(vec![name.clone()]);
+    }
+
+    let result = update(&config)?;
+    if let Some(name) = &params.component
+        && result.outcomes.is_empty()
+    {
+        return Err(libplasmoid_updater::Error::other(format!(
+            "no update available for '{name}'"
+        )));
+    }
+    Ok(json!(result))
+}
@@ -0,0 +1,209 @@
+// SPDX-License-Identifier: GPL-3.0-or-later
+
+//! `install-timer` / `remove-timer`: a systemd user timer that runs
+//! `plasmoid-updater check` (or `update --yes`) on a schedule.
+//!
+//! Units are written to `$XDG_CONFIG_HOME/systemd/user` and managed with
+//! `systemctl --user`, so no root is needed and they only run while the
+//! user's systemd instance does.
+
+use std::{
+    fs,
+    path::{Path, PathBuf},
+    process::Command,
+};
+
+use libplasmoid_updater::Error;
+
+const SERVICE: &str = "plasmoid-updater-check.service";
+const TIMER: &str = "plasmoid-updater-check.timer";
+
+/// Intervals systemd understands as calendar events, run at a fixed time
+/// and caught up after the machine was off.
+const CALENDAR_KEYWORDS: &[&str] = &["hourly", "daily", "weekly", "monthly"];
+
+/// Time span units accepted in `--interval`, as systemd spells them.
+const SPAN_UNITS: &[&str] = &["s", "min", "m", "h", "d", "w"];
+
+fn unit_dir() -> Result<PathBuf, Error> {
+    dirs::config_dir()
+        .map(|dir| dir.join("systemd/user"))
+        .ok_or_else(|| Error::other("cannot determine the config directory"))
+}
+
+/// Writes the service and timer units and enables the timer. Returns the
+/// directory the units were written to.
+pub(crate) fn install(interval: &str, auto_apply: bool) -> Result<PathBuf, Error> {
+    let schedule = schedule(interval)?;
+    let exe = std::env::current_exe()
+        .map_err(|e| Error::other(format!("failed to locate binary: {e}")))?;
+
+    let dir = unit_dir()?;
+    fs::create_dir_all(&dir)?;
+    fs::write(dir.join(SERVICE), service_unit(&exe, auto_apply))?;
+    fs::write(dir.join(TIMER), timer_unit(&schedule))?;
+
+    systemctl(&["daemon-reload"])?;
+    systemctl(&["enable", "--now", TIMER])?;
+    Ok(dir)
+}
+
+/// Disables the timer and deletes both units. Returns `false` if they were
+/// not installed.
+pub(crate) fn remove() -> Result<bool, Error> {
+    let dir = unit_dir()?;
+    let units = [dir.join(TIMER), dir.join(SERVICE)];
+    if !units.iter().any(|unit| unit.exists()) {
+        return Ok(false);
+    }
+
+    // Fails if the timer was never loaded; the files go either way.
+    if let Err(e) = systemctl(&["disable", "--now", TIMER]) {
+        eprintln!("warning: {e}");
+    }
+    for unit in &units {
+        if let Err(e) = fs::remove_file(unit)
+            && e.kind() != std::io::ErrorKind::NotFound
+        {
+            return Err(e.into());
+        }
+    }
+    systemctl(&["daemon-reload"])?;
+    Ok(true)
+}
+
+fn systemctl(args: &[&str]) -> Result<(), Error> {
+    let status = Command::new("systemctl")
+        .arg("--user")
+        .args(args)
+        .status()
+        .map_err(|e| Error::other(format!("failed to run systemctl: {e}")))?;
+    if !status.success() {
+        return Err(Error::other(format!(
+            "systemctl --user {} exited with {status}",
+            args.join(" ")
+        )));
+    }
+    Ok(())
+}
+
+/// The `[Timer]` lines for `interval`: a calendar keyword, or a time span
+/// like `1d`, `12h` or `30min` between runs.
+fn schedule(interval: &str) -> Result<String, Error> {
+    let interval = interval.trim();
+    if CALENDAR_KEYWORDS.contains(&interval) {
+        return Ok(format!("OnCalendar={interval}\nPersistent=true\n"));
+    }
+
+    let split = interval
+        .find(|c: char| !c.is_ascii_digit())
+        .unwrap_or(interval.len());
+    let (count, unit) = interval.split_at(split);
+    if count.parse::<u32>().is_ok_and(|n| n > 0) && SPAN_UNITS.contains(&unit) {
+        return Ok(format!("OnBootSec=5min\nOnUnitActiveSec={interval}\n"));
+    }
+
+    Err(Error::other(format!(
+        "invalid interval {interval:?}: use a span like 1d, 12h or 30min, \
+         or one of {}",
+        CALENDAR_KEYWORDS.join(", ")
+    )))
+}
+
+fn service_unit(exe: &Path, auto_apply: bool) -> String {
+    let (description, command) = if auto_apply {
+        ("Update KDE Plasma components", "update --yes")
+    } else {
+        ("Check KDE Plasma components for updates", "check")
+    };
+    // The service environment may lack the session variables Plasma
+    // detection looks at; exit code 3 means there was nothing to do.
+    format!(
+        "[Unit]\n\
+         Description={description}\n\
+         Documentation=https://github.com/uwuclxdy/plasmoid-updater\n\
+         \n\
+         [Service]\n\
+         Type=oneshot\n\
+         ExecStart=\"{}\" --skip-plasma-detection {command}\n\
+         SuccessExitStatus=3\n",
+        quote_exec_arg(&exe.to_string_lossy())
+    )
+}
+
+/// Escapes `arg` for use inside a double-quoted `ExecStart=` word: `%`
+/// specifiers and `$` variables are doubled, quotes and backslashes are
+/// backslash-escaped.
+fn quote_exec_arg(arg: &str) -> String {
+    let mut quoted = String::with_capacity(arg.len());
+    for c in arg.chars() {
+        match c {
+            '%' => quoted.push_str("%%"),
+            '$' => quoted.push_str("$$"),
+            '"' | '\\' => {
+                quoted.push('\\');
+                quoted.push(c);
+            }
+            _ => quoted.push(c),
+        }
+    }
+    quoted
+}
+
+fn timer_unit(schedule: &str) -> String {
+    format!(
+        "[Unit]\n\
+         Description=Run {SERVICE} regularly\n\
+         \n\
+         [Timer]\n\
+         {schedule}\
+         RandomizedDelaySec=10min\n\
+         \n\
+         [Install]\n\
+         WantedBy=timers.target\n"
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn schedule_accepts_keywords_and_spans() {
+        assert_eq!(
+            schedule("daily").unwrap(),
+            "OnCalendar=daily\nPersistent=true\n"
+        );
+        assert_eq!(
+            schedule(" 12h ").unwrap(),
+            "OnBootSec=5min\nOnUnitActiveSec=12h\n"
+        );
+        assert_eq!(
+            schedule("30min").unwrap(),
+            "OnBootSec=5min\nOnUnitActiveSec=30min\n"
+        );
+    }
+
+    #[test]
+    fn schedule_rejects_invalid_intervals() {
+        for interval in ["", "0d", "d", "12", "1y", "-1h", "1.5h", "fortnightly"] {
+            assert!(schedule(interval).is_err(), "{interval:?} was accepted");
+        }
+    }
+
+    #[test]
+    fn service_unit_escapes_the_executable_path() {
+        let unit = service_unit(Path::new("/opt/my apps/100%\"new\"\\bin"), false);
+        assert!(unit.contains(
+            "ExecStart=\"/opt/my apps/100%%\\\"new\\\"\\\\bin\" --skip-plasma-detection check\n"
+        ));
+    }
+
+    #[test]
+    fn service_unit_runs_update_when_auto_applying() {
+        let unit = service_unit(Path::new("/usr/bin/plasmoid-updater"), true);
+        assert!(unit.contains(
+            "ExecStart=\"/usr/bin/plasmoid-updater\" --skip-plasma-detection update --yes\n"
+        ));
+    }
+}